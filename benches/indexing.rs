@@ -0,0 +1,157 @@
+use contextmesh::config::IndexConfig;
+use contextmesh::fixtures::{fixture_module, generate_rust_fixture};
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+const FIXTURE_SIZES: &[usize] = &[10, 100, 500];
+const FNS_PER_FILE: usize = 20;
+
+fn fixture(files: usize) -> (TempDir, Vec<PathBuf>) {
+    let dir = TempDir::new().expect("create temp dir");
+    let paths = generate_rust_fixture(dir.path(), files, FNS_PER_FILE).expect("write fixture");
+    (dir, paths)
+}
+
+fn build_index(paths: &[PathBuf], code_parser: &mut CodeParser) -> Index {
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let mut index = Index::new();
+    index.index_files(&paths, code_parser).expect("index files");
+    index.recheck_unresolved();
+    index
+}
+
+fn parse_throughput(c: &mut Criterion) {
+    let (_dir, paths) = fixture(1);
+    let path = paths[0].to_string_lossy().to_string();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(fs::metadata(&path).unwrap().len()));
+    group.bench_function("single_file", |b| {
+        b.iter(|| code_parser.parse_file(&path).unwrap())
+    });
+
+    // Alternate between two versions of the file so every parse sees a small edit
+    let versions = [
+        fixture_module(0, FNS_PER_FILE),
+        fixture_module(0, FNS_PER_FILE + 1),
+    ];
+    let mut cached_parser = CodeParser::new_rust().unwrap();
+    cached_parser.enable_tree_cache();
+    let mut turn = 0;
+    group.bench_function("single_file_incremental", |b| {
+        b.iter_batched(
+            || {
+                turn += 1;
+                fs::write(&path, &versions[turn % 2]).unwrap();
+            },
+            |_| cached_parser.parse_file(&path).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn full_index_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full_index");
+    group.sample_size(10);
+    for &files in FIXTURE_SIZES {
+        let (_dir, paths) = fixture(files);
+        let mut code_parser = CodeParser::new_rust().unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(files), &paths, |b, paths| {
+            b.iter(|| build_index(paths, &mut code_parser))
+        });
+    }
+    group.finish();
+}
+
+/// The full build on one thread against all available ones; parsing is
+/// sequential either way, so the difference is the resolution stage.
+fn resolution_threads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolution_threads");
+    group.sample_size(10);
+    let files = *FIXTURE_SIZES.last().unwrap();
+    let (_dir, paths) = fixture(files);
+    let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for threads in [1, available] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &paths, |b, paths| {
+            // Parsers can't move between threads, so each build makes its own
+            b.iter(|| pool.install(|| build_index(paths, &mut CodeParser::new_rust().unwrap())))
+        });
+    }
+    group.finish();
+}
+
+fn incremental_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("incremental_update");
+    group.sample_size(10);
+    for &files in FIXTURE_SIZES {
+        let (_dir, paths) = fixture(files);
+        let mut code_parser = CodeParser::new_rust().unwrap();
+        let changed = paths.len() / 2;
+
+        group.bench_with_input(BenchmarkId::from_parameter(files), &paths, |b, paths| {
+            b.iter_batched(
+                || {
+                    fs::write(&paths[changed], fixture_module(changed, FNS_PER_FILE)).unwrap();
+                    let mut code_parser = CodeParser::new_rust().unwrap();
+                    let index = build_index(paths, &mut code_parser);
+                    fs::write(&paths[changed], fixture_module(changed, FNS_PER_FILE + 1)).unwrap();
+                    index
+                },
+                |mut index| {
+                    index
+                        .index_file(
+                            paths[changed].to_string_lossy().to_string(),
+                            &mut code_parser,
+                        )
+                        .unwrap();
+                    index
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn save_and_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("save_load");
+    group.sample_size(10);
+    for &files in FIXTURE_SIZES {
+        let (dir, paths) = fixture(files);
+        let mut code_parser = CodeParser::new_rust().unwrap();
+        let index = build_index(&paths, &mut code_parser);
+        let index_path = dir.path().join("index.bin");
+        let config = IndexConfig::default();
+
+        group.bench_with_input(BenchmarkId::new("save", files), &index, |b, index| {
+            b.iter(|| index.save_index_to(&index_path, &config).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("load", files), &index_path, |b, path| {
+            b.iter(|| Index::load_index_from(path).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    parse_throughput,
+    full_index_build,
+    resolution_threads,
+    incremental_update,
+    save_and_load
+);
+criterion_main!(benches);
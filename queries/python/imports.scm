@@ -0,0 +1,13 @@
+; Import statements of Python source files.
+
+(import_statement
+  name: (dotted_name) @import.path)
+
+(import_statement
+  name: (aliased_import
+    name: (dotted_name) @import.path
+    alias: (identifier) @import.alias))
+
+(import_from_statement
+  module_name: (_) @import.glob
+  (wildcard_import))
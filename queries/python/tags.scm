@@ -0,0 +1,22 @@
+; Symbols and references of Python source files.
+
+(class_definition
+  name: (identifier) @name
+  body: (block . (expression_statement (string) @doc)?)) @definition.class
+
+(function_definition
+  name: (identifier) @name
+  body: (block . (expression_statement (string) @doc)?)) @definition.function
+
+(module
+  (expression_statement
+    (assignment left: (identifier) @name) @definition.constant))
+
+(call
+  function: [
+    (identifier) @name
+    (attribute attribute: (identifier) @name)
+  ]) @reference.call
+
+(class_definition
+  superclasses: (argument_list (identifier) @name) @reference.class)
@@ -0,0 +1,23 @@
+; Import clauses of Scala source files, for the tree-sitter-scala grammar. The
+; grammar has no node for a whole path, so its segments are captured one by one.
+
+; import a.b.C (the package path of the clauses below, which this also matches up
+; to its trailing separator, is skipped by the indexer)
+(import_declaration
+  [(identifier) "."]+ @import.path)
+
+; import a.b.{C, D => E}
+(import_declaration
+  [(identifier) "."]+ @import.path
+  (namespace_selectors
+    [
+      (identifier) @import.name
+      (arrow_renamed_identifier
+        name: (identifier) @import.name
+        alias: (identifier) @import.alias)
+    ]))
+
+; import a.b._ and import a.b.*
+(import_declaration
+  [(identifier) "."]+ @import.glob
+  (namespace_wildcard))
@@ -0,0 +1,71 @@
+; Symbols and references of Scala source files, for the tree-sitter-scala grammar.
+
+((block_comment)? @doc
+  .
+  (object_definition
+    name: (identifier) @name) @definition.object)
+
+((block_comment)? @doc
+  .
+  (class_definition
+    name: (identifier) @name) @definition.class)
+
+((block_comment)? @doc
+  .
+  (trait_definition
+    name: (identifier) @name) @definition.trait)
+
+((block_comment)? @doc
+  .
+  (enum_definition
+    name: (identifier) @name) @definition.enum)
+
+((block_comment)? @doc
+  .
+  (function_definition
+    name: (identifier) @name) @definition.function)
+
+((block_comment)? @doc
+  .
+  (function_declaration
+    name: (identifier) @name) @definition.function)
+
+((block_comment)? @doc
+  .
+  (type_definition
+    name: (type_identifier) @name) @definition.type)
+
+; Only members and top-level values; locals of a function body aren't symbols
+(template_body
+  (block_comment)? @doc
+  .
+  (val_definition
+    pattern: (identifier) @name) @definition.val)
+
+(template_body
+  (block_comment)? @doc
+  .
+  (var_definition
+    pattern: (identifier) @name) @definition.var)
+
+(compilation_unit
+  (val_definition
+    pattern: (identifier) @name) @definition.val)
+
+(call_expression
+  function: [
+    (identifier) @name
+    (field_expression field: (identifier) @name)
+  ]) @reference.call
+
+(instance_expression
+  [
+    (type_identifier) @name
+    (generic_type type: (type_identifier) @name)
+  ]) @reference.class
+
+(extends_clause
+  type: [
+    (type_identifier) @name
+    (generic_type type: (type_identifier) @name)
+  ] @reference.class)
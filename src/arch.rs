@@ -0,0 +1,222 @@
+//! Architecture rules evaluated against the symbol graph.
+//!
+//! The `[architecture]` config section groups modules into layers and restricts
+//! the dependency edges between them:
+//!
+//! ```toml
+//! [[architecture.layers]]
+//! name = "commands"
+//! modules = ["crate::commands::**"]
+//! may_use = ["index", "config"]
+//!
+//! [[architecture.deny]]
+//! from = "crate::parser::**"
+//! to = "crate::commands::**"
+//! message = "parsers must not know about the CLI"
+//!
+//! [[architecture.allow]]
+//! from = "commands"
+//! to = "crate::parser::CodeParser::**"
+//! ```
+//!
+//! Globs match the qualified name of a symbol (`crate::index::Index::load_index`)
+//! if they start with `crate`, and its file path (`src/index/**`) otherwise. `*`
+//! matches within one segment and `**` any number of segments. A rule's glob
+//! also covers everything inside what it matches, so `crate::parser` is
+//! `crate::parser::**` and `src/parser/` is `src/parser/**`; the `[[rules]]` of
+//! `check` are matched the same way (see [`rule_matches`]).
+
+use serde::Serialize;
+
+use crate::config::{ArchitectureConfig, EdgeRule, Layer};
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::symbol::Symbol;
+use crate::utils::module_path;
+
+/// A dependency edge breaking an architecture rule.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Violation {
+    pub file_path: String,
+    pub line_number: usize,
+    /// Qualified name of the depending symbol.
+    pub from: String,
+    /// Qualified name of the symbol depended on.
+    pub to: String,
+    pub to_file_path: String,
+    pub to_line_number: usize,
+    /// The rule broken.
+    pub reason: String,
+}
+
+/// Returns the edges of `index` violating `config`, sorted by location.
+pub fn violations(
+    index: &Index,
+    config: &ArchitectureConfig,
+) -> Result<Vec<Violation>, ContextMeshError> {
+    validate(config)?;
+
+    let mut violations = Vec::new();
+    for symbol in index.symbols.values().filter(|sym| sym.is_code()) {
+        let from_name = qualified_name(index, symbol);
+        let from_layer = layer_of(config, &from_name, symbol);
+
+        for dependency in symbol
+            .dependencies
+            .iter()
+            .filter_map(|id| index.symbol(*id))
+        {
+            let to_name = qualified_name(index, dependency);
+            let to_layer = layer_of(config, &to_name, dependency);
+            let edge_matches = |rule: &EdgeRule| {
+                endpoint_matches(config, &rule.from, &from_name, symbol)
+                    && endpoint_matches(config, &rule.to, &to_name, dependency)
+            };
+            if config.allow.iter().any(edge_matches) {
+                continue;
+            }
+
+            let reason = if let Some(rule) = config.deny.iter().find(|rule| edge_matches(rule)) {
+                rule.message
+                    .clone()
+                    .unwrap_or_else(|| format!("`{}` must not use `{}`", rule.from, rule.to))
+            } else {
+                match (from_layer, to_layer) {
+                    (Some(from), Some(to)) if from.name != to.name => match &from.may_use {
+                        Some(may_use) if !may_use.contains(&to.name) => {
+                            format!("layer `{}` may not use layer `{}`", from.name, to.name)
+                        }
+                        _ => continue,
+                    },
+                    _ => continue,
+                }
+            };
+
+            violations.push(Violation {
+                file_path: symbol.file_path.to_string(),
+                line_number: symbol.line_number,
+                from: from_name.clone(),
+                to: to_name,
+                to_file_path: dependency.file_path.to_string(),
+                to_line_number: dependency.line_number,
+                reason,
+            });
+        }
+    }
+    violations.sort();
+    violations.dedup();
+    Ok(violations)
+}
+
+/// Module path and enclosing symbols of `symbol`, e.g. `crate::index::Index::load_index`.
+pub fn qualified_name(index: &Index, symbol: &Symbol) -> String {
+    let mut names = vec![symbol.name.as_str()];
+    let mut current = symbol;
+    while let Some(parent) = current.parent.and_then(|id| index.symbol(id)) {
+        names.push(&parent.name);
+        current = parent;
+    }
+    names.reverse();
+    format!("{}::{}", module_path(&symbol.file_path), names.join("::"))
+}
+
+/// Whether the symbol is matched by `pattern` (see the module documentation).
+pub fn glob_matches(pattern: &str, qualified_name: &str, symbol: &Symbol) -> bool {
+    if pattern == "crate" || pattern.starts_with("crate::") {
+        let pattern: Vec<&str> = pattern.split("::").collect();
+        let name: Vec<&str> = qualified_name.split("::").collect();
+        segments_match(&pattern, &name)
+    } else {
+        path_matches(pattern, &symbol.file_path)
+    }
+}
+
+/// Whether the symbol is matched by the rule pattern `pattern` or inside what
+/// it matches, a module, item, or directory (see the module documentation).
+pub fn rule_matches(pattern: &str, qualified_name: &str, symbol: &Symbol) -> bool {
+    let pattern = pattern.trim_end_matches("::").trim_end_matches('/');
+    let inside = if pattern == "crate" || pattern.starts_with("crate::") {
+        format!("{}::**", pattern)
+    } else {
+        format!("{}/**", pattern)
+    };
+    glob_matches(pattern, qualified_name, symbol) || glob_matches(&inside, qualified_name, symbol)
+}
+
+/// Whether the file glob `pattern` (e.g. `src/**/*_pb.rs`) matches `path`.
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_start_matches("./").split('/').collect();
+    let path: Vec<&str> = path.trim_start_matches("./").split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn validate(config: &ArchitectureConfig) -> Result<(), ContextMeshError> {
+    for layer in &config.layers {
+        for name in layer.may_use.iter().flatten() {
+            if !config.layers.iter().any(|other| other.name == *name) {
+                return Err(ContextMeshError::ConfigError(format!(
+                    "Layer '{}' may use unknown layer '{}'",
+                    layer.name, name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn layer_of<'a>(
+    config: &'a ArchitectureConfig,
+    qualified_name: &str,
+    symbol: &Symbol,
+) -> Option<&'a Layer> {
+    config.layers.iter().find(|layer| {
+        layer
+            .modules
+            .iter()
+            .any(|pattern| rule_matches(pattern, qualified_name, symbol))
+    })
+}
+
+/// Whether a rule endpoint, a layer name or a glob, matches the symbol.
+fn endpoint_matches(
+    config: &ArchitectureConfig,
+    endpoint: &str,
+    qualified_name: &str,
+    symbol: &Symbol,
+) -> bool {
+    match config.layers.iter().find(|layer| layer.name == endpoint) {
+        Some(layer) => layer
+            .modules
+            .iter()
+            .any(|pattern| rule_matches(pattern, qualified_name, symbol)),
+        None => rule_matches(endpoint, qualified_name, symbol),
+    }
+}
+
+fn segments_match(pattern: &[&str], name: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((&"**", rest)) => (0..=name.len()).any(|skip| segments_match(rest, &name[skip..])),
+        Some((segment, rest)) => match name.split_first() {
+            Some((first, name_rest)) => {
+                wildcard_matches(segment, first) && segments_match(rest, name_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches one segment against a pattern in which `*` stands for any characters.
+fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| wildcard_matches(rest, &text[i..]))
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Bazel and Buck build targets, read from `BUILD`, `BUILD.bazel`, and `BUCK`
+//! files.
+//!
+//! Each rule call with a `name` becomes a target labelled `//<package>:<name>`,
+//! where the package is the directory of the build file relative to the project
+//! root. Its `srcs` and `hdrs` are the files it owns (plain paths or `glob()`
+//! patterns, minus `exclude`), and its `deps` the targets it directly depends on.
+//! Build files are read textually rather than evaluated, so macros hiding those
+//! attributes, and sources produced by other rules, are not seen.
+
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::arch::path_matches;
+use crate::errors::ContextMeshError;
+
+/// File names of build files, in order of precedence within a directory.
+pub const BUILD_FILES: &[&str] = &["BUILD.bazel", "BUILD", "BUCK"];
+
+/// Attributes listing the files a target owns.
+const SOURCE_ATTRIBUTES: &[&str] = &["srcs", "hdrs"];
+
+/// The start of a rule call at the beginning of a line, e.g. `rust_library(`.
+static RULE_CALL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^[A-Za-z_][\w.]*\s*\(").expect("valid regex"));
+
+/// A string literal, single- or double-quoted.
+static STRING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""([^"\\]*)"|'([^'\\]*)'"#).expect("valid regex"));
+
+/// A target declared in a build file.
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// `//<package>:<name>`
+    pub label: String,
+    /// Globs of the files the target owns, relative to the project root
+    pub sources: Vec<String>,
+    /// Globs of files left out of `sources` (`glob(..., exclude = [...])`)
+    pub excluded: Vec<String>,
+    /// Labels of the targets it directly depends on
+    pub deps: Vec<String>,
+}
+
+impl Target {
+    /// Whether the file at `path`, relative to the project root, is one of the
+    /// target's sources.
+    pub fn owns(&self, path: &str) -> bool {
+        self.sources
+            .iter()
+            .any(|pattern| path_matches(pattern, path))
+            && !self
+                .excluded
+                .iter()
+                .any(|pattern| path_matches(pattern, path))
+    }
+}
+
+/// Reads the targets of every build file under `root`, skipping hidden
+/// directories, `target`, `node_modules`, and Bazel's output links.
+pub fn load(root: &Path) -> Result<Vec<Target>, ContextMeshError> {
+    let mut targets = Vec::new();
+    load_package(root, "", &mut targets)?;
+    Ok(targets)
+}
+
+fn load_package(
+    dir: &Path,
+    package: &str,
+    targets: &mut Vec<Target>,
+) -> Result<(), ContextMeshError> {
+    if let Some(build_file) = BUILD_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+    {
+        targets.extend(parse(package, &fs::read_to_string(build_file)?));
+    }
+
+    let mut subdirs: Vec<_> = fs::read_dir(dir)?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            !name.starts_with('.')
+                && !name.starts_with("bazel-")
+                && !name.starts_with("buck-")
+                && name != "target"
+                && name != "node_modules"
+        })
+        .collect();
+    subdirs.sort();
+    for name in subdirs {
+        let subpackage = if package.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", package, name)
+        };
+        load_package(&dir.join(&name), &subpackage, targets)?;
+    }
+    Ok(())
+}
+
+/// The targets declared in the build file of `package`.
+pub fn parse(package: &str, source: &str) -> Vec<Target> {
+    let source = strip_comments(source);
+    let mut targets = Vec::new();
+    for call in RULE_CALL.find_iter(&source) {
+        let Some(arguments) = balanced(&source[call.end()..]) else {
+            continue;
+        };
+        let attributes = top_level_attributes(arguments);
+        let Some(name) = attributes
+            .iter()
+            .find(|(key, _)| *key == "name")
+            .and_then(|(_, value)| strings(value).into_iter().next())
+        else {
+            continue;
+        };
+
+        let mut sources = Vec::new();
+        let mut excluded = Vec::new();
+        let mut deps = Vec::new();
+        for (key, value) in &attributes {
+            if SOURCE_ATTRIBUTES.contains(key) {
+                let (included, exclude) = value.split_once("exclude").unwrap_or((value, ""));
+                sources.extend(
+                    strings(included)
+                        .into_iter()
+                        // Labels name the outputs of other rules
+                        .filter(|src| !src.starts_with(':') && !src.contains("//"))
+                        .map(|src| join(package, &src)),
+                );
+                excluded.extend(strings(exclude).iter().map(|src| join(package, src)));
+            } else if *key == "deps" {
+                deps.extend(strings(value).iter().map(|dep| resolve_label(package, dep)));
+            }
+        }
+        targets.push(Target {
+            label: format!("//{}:{}", package, name),
+            sources,
+            excluded,
+            deps,
+        });
+    }
+    targets
+}
+
+/// The absolute form of a label given on the command line: `services/foo` and
+/// `//services/foo` both mean `//services/foo:foo`.
+pub fn absolute_label(label: &str) -> String {
+    if label.starts_with("//") || label.starts_with('@') {
+        resolve_label("", label)
+    } else {
+        resolve_label("", &format!("//{}", label))
+    }
+}
+
+/// The absolute form of `label` as written in the build file of `package`.
+fn resolve_label(package: &str, label: &str) -> String {
+    if let Some(name) = label.strip_prefix(':') {
+        return format!("//{}:{}", package, name);
+    }
+    let Some((repository, path)) = label.split_once("//") else {
+        return format!("//{}:{}", package, label);
+    };
+    if path.contains(':') {
+        return label.to_string();
+    }
+    let name = path.rsplit('/').next().unwrap_or(path);
+    format!("{}//{}:{}", repository, path, name)
+}
+
+fn join(package: &str, path: &str) -> String {
+    if package.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", package, path)
+    }
+}
+
+/// `source` without `#` comments outside string literals.
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    for line in source.lines() {
+        let mut quote = None;
+        for c in line.chars() {
+            match (quote, c) {
+                (None, '#') => break,
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(open), c) if c == open => quote = None,
+                _ => {}
+            }
+            out.push(c);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The text up to the `)` closing an already opened parenthesis, or `None` if
+/// it is never closed.
+fn balanced(text: &str) -> Option<&str> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (pos, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')') if depth == 0 => return Some(&text[..pos]),
+            (None, ')' | ']' | '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The `key = value` pairs among the comma-separated `arguments` of a call.
+fn top_level_attributes(arguments: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (pos, c) in arguments.char_indices().chain([(arguments.len(), ',')]) {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                if let Some((key, value)) = arguments[start..pos].split_once('=') {
+                    attributes.push((key.trim(), value.trim()));
+                }
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+    attributes
+}
+
+/// The contents of the string literals in `expression`.
+fn strings(expression: &str) -> Vec<String> {
+    STRING
+        .captures_iter(expression)
+        .filter_map(|captures| captures.get(1).or_else(|| captures.get(2)))
+        .map(|text| text.as_str().to_string())
+        .collect()
+}
@@ -0,0 +1,388 @@
+//! Assembly of context bundles from whole files and slices of files.
+//!
+//! Slices of the same file are deduplicated by byte range and merged when they
+//! overlap or only whitespace separates them, so a file and the symbols in it,
+//! or a type and its methods, cost their tokens once. The code left out between
+//! two slices is marked as elided. Notes about the included code, e.g. that it
+//! is feature-gated, go in a section of their own before the code. When not all
+//! code fits, symbols can also be included by their signature or by name only,
+//! in sections after it.
+//!
+//! A [`Manifest`] records what went into a bundle by symbol rather than by byte
+//! range, so `contextmesh replay` can assemble the same bundle from a later
+//! version of the code.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+
+use crate::errors::ContextMeshError;
+use crate::git::current_git_commit;
+use crate::utils::{read_source, unix_timestamp};
+
+/// The files and byte ranges making up a bundle, in the order files were first
+/// added.
+#[derive(Default)]
+pub struct Bundle {
+    files: Vec<(String, Vec<Range<usize>>)>,
+    positions: HashMap<String, usize>,
+    /// Byte ranges left out of the files, by normalized path
+    excluded: HashMap<String, Vec<Range<usize>>>,
+    /// Text put between the path and the code of a file, by normalized path
+    headers: HashMap<String, String>,
+    notes: Vec<String>,
+    /// Signatures by file, in the order files were first added
+    signatures: Vec<(String, Vec<String>)>,
+    names: Vec<String>,
+}
+
+impl Bundle {
+    /// Includes all of the file at `path`.
+    pub fn add_file(&mut self, path: &str) {
+        self.add_range(path, 0..usize::MAX);
+    }
+
+    /// Includes bytes `range` of the file at `path`.
+    pub fn add_range(&mut self, path: &str, range: Range<usize>) {
+        let key = normalize(path);
+        let position = *self.positions.entry(key.to_string()).or_insert_with(|| {
+            self.files.push((path.to_string(), Vec::new()));
+            self.files.len() - 1
+        });
+        self.files[position].1.push(range);
+    }
+
+    /// Leaves bytes `range` of the file at `path` out, even if included; what
+    /// is left out is marked as elided.
+    pub fn exclude_range(&mut self, path: &str, range: Range<usize>) {
+        self.excluded
+            .entry(normalize(path).to_string())
+            .or_default()
+            .push(range);
+    }
+
+    /// Puts `header` between the path and the code of the file at `path`.
+    pub fn set_header(&mut self, path: &str, header: String) {
+        self.headers.insert(normalize(path).to_string(), header);
+    }
+
+    /// The paths of the files code is included of, in the order they were added.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(path, _)| path.as_str())
+    }
+
+    /// Adds a note about the included code, rendered as a list item.
+    pub fn add_note(&mut self, note: String) {
+        self.notes.push(note);
+    }
+
+    /// Includes a symbol of the file at `path` by its `signature`, after a
+    /// `summary` of what it does if there is one.
+    pub fn add_signature(&mut self, path: &str, signature: &str, summary: Option<&str>) {
+        let mut entry = String::new();
+        if let Some(summary) = summary {
+            entry.push_str(&format!("{} {}\n", comment_prefix(path), summary));
+        }
+        entry.push_str(signature.trim_end());
+        match self
+            .signatures
+            .iter_mut()
+            .find(|(file, _)| normalize(file) == normalize(path))
+        {
+            Some((_, entries)) => entries.push(entry),
+            None => self.signatures.push((path.to_string(), vec![entry])),
+        }
+    }
+
+    /// Mentions a symbol by name only, e.g. `` `crate::index::Index` (src/index/mod.rs:42) ``.
+    pub fn add_name(&mut self, name: String) {
+        self.names.push(name);
+    }
+
+    /// Whether bytes `range` of the file at `path` are already included.
+    pub fn covers(&self, path: &str, range: &Range<usize>) -> bool {
+        self.positions
+            .get(normalize(path))
+            .is_some_and(|&position| {
+                self.files[position]
+                    .1
+                    .iter()
+                    .any(|included| included.start <= range.start && range.end <= included.end)
+            })
+    }
+
+    /// The notes under a `# Notes` header, then the included code under a
+    /// `# <path>` header (and the header set for the file) per file, then the
+    /// signatures and names of symbols included without their code. Files that
+    /// can't be read are skipped with a message.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if !self.notes.is_empty() {
+            out.push_str("# Notes\n\n");
+            for note in &self.notes {
+                out.push_str(&format!("- {}\n", note));
+            }
+            out.push('\n');
+        }
+        for (path, ranges) in &self.files {
+            let content = match read_source(path) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Failed to read file '{}': {}. Skipping.", path, e);
+                    continue;
+                }
+            };
+            let key = normalize(path);
+            match self.headers.get(key) {
+                Some(header) => out.push_str(&format!("# {}\n{}\n", path, header)),
+                None => out.push_str(&format!("# {}\n\n", path)),
+            }
+
+            let included = merge(&content, ranges);
+            let (included, cut) = match self.excluded.get(key) {
+                Some(excluded) => subtract(&content, included, &merge(&content, excluded)),
+                None => (included, None),
+            };
+            // Code left out before the first range or after the last one
+            let (leading, trailing) = match (&cut, included.first(), included.last()) {
+                (Some(cut), Some(first), Some(last)) => (
+                    (cut.start < first.start).then(|| {
+                        (
+                            line_of(&content, cut.start),
+                            line_of(&content, first.start) - 1,
+                        )
+                    }),
+                    (cut.end > last.end).then(|| {
+                        (
+                            line_of(&content, last.end) + 1,
+                            line_of(&content, cut.end - 1),
+                        )
+                    }),
+                ),
+                (Some(cut), _, _) => (
+                    Some((line_of(&content, cut.start), line_of(&content, cut.end - 1))),
+                    None,
+                ),
+                (None, _, _) => (None, None),
+            };
+            if let Some((first, last)) = leading {
+                out.push_str(&elision(path, first, last));
+            }
+            let mut previous_end = None;
+            for range in &included {
+                if let Some(end) = previous_end {
+                    out.push_str(&elision(
+                        path,
+                        line_of(&content, end) + 1,
+                        line_of(&content, range.start) - 1,
+                    ));
+                }
+                let slice = String::from_utf8_lossy(&content[range.clone()]);
+                out.push_str(&format!("{}\n\n", slice.trim_end()));
+                previous_end = Some(range.end);
+            }
+            if let Some((first, last)) = trailing {
+                out.push_str(&elision(path, first, last));
+            }
+        }
+        if !self.signatures.is_empty() {
+            out.push_str("# Signatures\n\nThe code of these was left out to fit the budget.\n\n");
+            for (path, entries) in &self.signatures {
+                out.push_str(&format!("## {}\n\n", path));
+                for entry in entries {
+                    out.push_str(&format!("{}\n\n", entry));
+                }
+            }
+        }
+        if !self.names.is_empty() {
+            out.push_str("# Also relevant\n\nOnly the names of these fit the budget.\n\n");
+            for name in &self.names {
+                out.push_str(&format!("- {}\n", name));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// The files and symbols a bundle was made of, written by `context --manifest`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub tool_version: String,
+    pub created_at: u64,
+    /// Commit checked out when the bundle was made, if in a git repository
+    pub git_commit: Option<String>,
+    /// Files included whole
+    pub files: Vec<String>,
+    pub symbols: Vec<ManifestSymbol>,
+}
+
+/// A symbol included in a bundle, as it was when the bundle was made.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestSymbol {
+    pub qualified_name: String,
+    pub kind: String,
+    pub file: String,
+    pub line: usize,
+    /// Tells whether the symbol's source changed since
+    pub body_hash: String,
+    /// How much of the symbol went in; manifests of older versions only have
+    /// full symbols
+    #[serde(default)]
+    pub detail: Detail,
+}
+
+/// How much of a symbol a bundle includes, from its whole source down to its
+/// name, as less relevant symbols are cut to fit a budget.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Detail {
+    #[default]
+    Full,
+    Signature,
+    Name,
+}
+
+impl Manifest {
+    /// An empty manifest of a bundle made now.
+    pub fn new() -> Manifest {
+        Manifest {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: unix_timestamp(),
+            git_commit: current_git_commit(),
+            ..Manifest::default()
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Manifest, ContextMeshError> {
+        let data = fs::read(path)?;
+        serde_json::from_slice(&data).map_err(|e| {
+            ContextMeshError::DeserializationError(format!(
+                "'{}' is not a bundle manifest: {}",
+                path, e
+            ))
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), ContextMeshError> {
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+        fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+/// `ranges` clamped to `content`, widened to whole lines where only indentation
+/// precedes them, sorted, and merged where they overlap or are separated by
+/// whitespace only.
+fn merge(content: &[u8], ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = ranges
+        .iter()
+        .map(|range| {
+            let end = range.end.min(content.len());
+            let start = range.start.min(end);
+            let line_start = content[..start]
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |newline| newline + 1);
+            let indented = content[line_start..start]
+                .iter()
+                .all(u8::is_ascii_whitespace);
+            (if indented { line_start } else { start })..end
+        })
+        .filter(|range| !range.is_empty())
+        .collect();
+    ranges.sort_by_key(|range| (range.start, range.end));
+
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last)
+                if range.start <= last.end
+                    || content[last.end..range.start]
+                        .iter()
+                        .all(u8::is_ascii_whitespace) =>
+            {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// `ranges` without the bytes of `excluded`, both sorted and merged, and the
+/// span of what was cut out of them, if anything. What is left of a range is
+/// trimmed to the lines with code, and dropped if there are none.
+fn subtract(
+    content: &[u8],
+    ranges: Vec<Range<usize>>,
+    excluded: &[Range<usize>],
+) -> (Vec<Range<usize>>, Option<Range<usize>>) {
+    let mut pieces = Vec::new();
+    let mut span: Option<Range<usize>> = None;
+    for range in ranges {
+        let mut start = range.start;
+        for cut in excluded
+            .iter()
+            .filter(|cut| cut.start < range.end && range.start < cut.end)
+        {
+            pieces.push(start..cut.start.max(start));
+            start = start.max(cut.end);
+            let removed = cut.start.max(range.start)..cut.end.min(range.end);
+            span = Some(match span {
+                Some(span) => span.start.min(removed.start)..span.end.max(removed.end),
+                None => removed,
+            });
+        }
+        pieces.push(start..range.end.max(start));
+    }
+    let pieces = pieces
+        .into_iter()
+        .filter_map(|piece| {
+            let slice = &content[piece.clone()];
+            let first = slice.iter().position(|b| !b.is_ascii_whitespace())?;
+            let last = slice.iter().rposition(|b| !b.is_ascii_whitespace())?;
+            let line_start = slice[..first]
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |newline| newline + 1);
+            Some(piece.start + line_start..piece.start + last + 1)
+        })
+        .collect();
+    (pieces, span)
+}
+
+/// The mark of lines `first` to `last` of the file at `path` left out, or
+/// nothing if there are none.
+fn elision(path: &str, first: usize, last: usize) -> String {
+    match last >= first {
+        true => format!(
+            "{} ... lines {}-{} elided ...\n\n",
+            comment_prefix(path),
+            first,
+            last
+        ),
+        false => String::new(),
+    }
+}
+
+/// 1-based number of the line containing byte `offset`.
+fn line_of(content: &[u8], offset: usize) -> usize {
+    1 + content[..offset.min(content.len())]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+}
+
+fn comment_prefix(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("py" | "sh" | "toml" | "yaml" | "yml" | "rb") => "#",
+        _ => "//",
+    }
+}
+
+fn normalize(path: &str) -> &str {
+    path.trim_start_matches("./")
+}
@@ -0,0 +1,133 @@
+//! Cargo workspace awareness: the crate owning each file, from `cargo metadata`,
+//! and the feature flags guarding code through `#[cfg(feature = "...")]`.
+//!
+//! The feature gates of an item are the features named in the `cfg` predicates
+//! guarding it (see [`crate::cfg`]), so a `#[cfg(feature = "otel")] mod
+//! telemetry;` gates everything in `telemetry.rs`. Gates are feature names,
+//! prefixed with `!` for code compiled only without the feature
+//! (`cfg(not(feature = "..."))`), worked out from the parsed predicates so that
+//! `any(...)` and `not(any(...))` gate what they really require.
+
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cfg::Cfg;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::symbol::Symbol;
+use crate::utils::project_root;
+
+/// A package of the Cargo workspace.
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub name: String,
+    /// Directory of its manifest, relative to the current directory (empty for
+    /// the package there)
+    pub dir: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<MetadataPackage>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct MetadataPackage {
+    name: String,
+    manifest_path: PathBuf,
+}
+
+/// The packages of the workspace in the current directory, from
+/// `cargo metadata`.
+pub fn workspace_packages() -> Result<Vec<Package>, ContextMeshError> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .map_err(|e| ContextMeshError::ToolError(format!("Failed to run cargo: {}", e)))?;
+    if !output.status.success() {
+        return Err(ContextMeshError::ToolError(format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let metadata: Metadata = serde_json::from_slice(&output.stdout).map_err(|e| {
+        ContextMeshError::ToolError(format!("cargo metadata printed invalid output: {}", e))
+    })?;
+
+    let root = project_root()?;
+    let root = if root.starts_with(&metadata.workspace_root) {
+        root
+    } else {
+        metadata.workspace_root
+    };
+    Ok(metadata
+        .packages
+        .into_iter()
+        .map(|package| {
+            let dir = package.manifest_path.parent().unwrap_or(Path::new(""));
+            Package {
+                dir: dir.strip_prefix(&root).unwrap_or(dir).to_path_buf(),
+                name: package.name,
+            }
+        })
+        .collect())
+}
+
+/// The innermost of `packages` containing the file at `path`.
+pub fn package_of<'a>(packages: &'a [Package], path: &str) -> Option<&'a Package> {
+    let path = Path::new(path.trim_start_matches("./"));
+    packages
+        .iter()
+        .filter(|package| path.starts_with(&package.dir))
+        .max_by_key(|package| package.dir.components().count())
+}
+
+/// The feature gates of the `cfg` predicates among `attributes`; see
+/// [`Cfg::feature_gates`].
+pub fn feature_gates(attributes: &[String]) -> BTreeSet<String> {
+    Cfg::of_attributes(attributes)
+        .iter()
+        .flat_map(Cfg::feature_gates)
+        .collect()
+}
+
+/// The feature gates guarding `sym`: those on it, its parents, and its file.
+pub fn symbol_gates(index: &Index, sym: &Symbol) -> BTreeSet<String> {
+    let mut gates = feature_gates(&sym.attributes);
+    let mut current = sym;
+    while let Some(parent) = current.parent.and_then(|id| index.symbol(id)) {
+        gates.extend(feature_gates(&parent.attributes));
+        current = parent;
+    }
+    if let Some(attributes) = index.file_cfgs.get(&*sym.file_path) {
+        gates.extend(feature_gates(attributes));
+    }
+    gates
+}
+
+/// `gates` in words, e.g. "with feature `otel`" or "with features `a`, `b` and
+/// without feature `c`".
+pub fn describe(gates: &BTreeSet<String>) -> String {
+    let (without, with): (Vec<&str>, Vec<&str>) = gates
+        .iter()
+        .map(String::as_str)
+        .partition(|gate| gate.starts_with('!'));
+    let without: Vec<&str> = without.iter().map(|gate| &gate[1..]).collect();
+
+    let list = |preposition: &str, names: &[&str]| {
+        let quoted: Vec<String> = names.iter().map(|name| format!("`{}`", name)).collect();
+        let plural = if names.len() > 1 { "s" } else { "" };
+        format!("{} feature{} {}", preposition, plural, quoted.join(", "))
+    };
+    let mut parts = Vec::new();
+    if !with.is_empty() {
+        parts.push(list("with", &with));
+    }
+    if !without.is_empty() {
+        parts.push(list("without", &without));
+    }
+    parts.join(" and ")
+}
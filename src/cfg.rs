@@ -0,0 +1,268 @@
+//! Conditional compilation: the `#[cfg(...)]` predicates guarding Rust items,
+//! and filtering by the cfg set of a build target.
+//!
+//! An item is guarded by the `cfg` attributes on it, on its parents, and on the
+//! `mod` declarations of the modules it is in, so `#[cfg(unix)] mod unix;` guards
+//! everything in `unix.rs`. All variants of an item behind mutually exclusive
+//! cfgs (`#[cfg(unix)]` and `#[cfg(windows)]`, or a feature and its negation)
+//! are indexed side by side; a [`CfgSet`] picks the ones one build compiles.
+
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::symbol::Symbol;
+
+/// An out-of-line module declaration with `cfg` attributes, e.g.
+/// `#[cfg(feature = "otel")] pub mod telemetry;`.
+static GATED_MOD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"((?:#\[cfg\([^\]]*\)\]\s*)+)(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)\s*;")
+        .expect("valid regex")
+});
+
+/// A `cfg` predicate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cfg {
+    /// `unix`, `test`, ...
+    Name(String),
+    /// `feature = "otel"`, `target_os = "linux"`, ...
+    KeyValue(String, String),
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+    Not(Box<Cfg>),
+}
+
+impl Cfg {
+    /// Parses a predicate as written inside `cfg(...)`, or `None` if it is
+    /// malformed.
+    pub fn parse(text: &str) -> Option<Cfg> {
+        let (cfg, rest) = parse_predicate(text.trim())?;
+        rest.trim().is_empty().then_some(cfg)
+    }
+
+    /// The predicates of the `cfg(...)` attributes among `attributes`, as stored
+    /// on symbols (without `#[...]`). Malformed ones are skipped.
+    pub fn of_attributes(attributes: &[String]) -> Vec<Cfg> {
+        attributes
+            .iter()
+            .filter_map(|attribute| attribute.strip_prefix("cfg(")?.strip_suffix(')'))
+            .filter_map(Cfg::parse)
+            .collect()
+    }
+
+    /// Whether the predicate holds for the options in `set`.
+    pub fn holds(&self, set: &CfgSet) -> bool {
+        match self {
+            Cfg::Name(name) => set.options.contains(&(name.clone(), None)),
+            Cfg::KeyValue(key, value) => set.options.contains(&(key.clone(), Some(value.clone()))),
+            Cfg::All(cfgs) => cfgs.iter().all(|cfg| cfg.holds(set)),
+            Cfg::Any(cfgs) => cfgs.iter().any(|cfg| cfg.holds(set)),
+            Cfg::Not(cfg) => !cfg.holds(set),
+        }
+    }
+
+    /// The features the predicate requires, and as `!name` those it requires to
+    /// be off, e.g. `otel` and `!tui` for `all(feature = "otel", not(feature =
+    /// "tui"))`. A feature counts only if the predicate can't hold otherwise, so
+    /// `any(feature = "a", feature = "b")` requires neither.
+    pub fn feature_gates(&self) -> BTreeSet<String> {
+        self.gates(true)
+    }
+
+    /// [`Cfg::feature_gates`] of the predicate, or of its negation if `holds`
+    /// is false.
+    fn gates(&self, holds: bool) -> BTreeSet<String> {
+        let common = |cfgs: &[Cfg]| {
+            let mut gates = cfgs.iter().map(|cfg| cfg.gates(holds));
+            let first = gates.next().unwrap_or_default();
+            gates.fold(first, |common, gates| &common & &gates)
+        };
+        let every = |cfgs: &[Cfg]| cfgs.iter().flat_map(|cfg| cfg.gates(holds)).collect();
+        match self {
+            Cfg::KeyValue(key, value) if key == "feature" => {
+                let negated = if holds { "" } else { "!" };
+                BTreeSet::from([format!("{}{}", negated, value)])
+            }
+            Cfg::Name(_) | Cfg::KeyValue(..) => BTreeSet::new(),
+            // `not(all(a, b))` holds if either is off, `not(any(a, b))` if both are
+            Cfg::All(cfgs) if holds => every(cfgs),
+            Cfg::All(cfgs) => common(cfgs),
+            Cfg::Any(cfgs) if holds => common(cfgs),
+            Cfg::Any(cfgs) => every(cfgs),
+            Cfg::Not(cfg) => cfg.gates(!holds),
+        }
+    }
+
+    /// All of `cfgs` as one predicate, or `None` if there are none.
+    pub fn all(mut cfgs: Vec<Cfg>) -> Option<Cfg> {
+        match cfgs.len() {
+            0 => None,
+            1 => cfgs.pop(),
+            _ => Some(Cfg::All(cfgs)),
+        }
+    }
+}
+
+impl fmt::Display for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, name: &str, cfgs: &[Cfg]| {
+            let cfgs: Vec<String> = cfgs.iter().map(Cfg::to_string).collect();
+            write!(f, "{}({})", name, cfgs.join(", "))
+        };
+        match self {
+            Cfg::Name(name) => write!(f, "{}", name),
+            Cfg::KeyValue(key, value) => write!(f, "{} = \"{}\"", key, value),
+            Cfg::All(cfgs) => list(f, "all", cfgs),
+            Cfg::Any(cfgs) => list(f, "any", cfgs),
+            Cfg::Not(cfg) => write!(f, "not({})", cfg),
+        }
+    }
+}
+
+/// Parses one predicate at the start of `text`, returning it and the rest.
+fn parse_predicate(text: &str) -> Option<(Cfg, &str)> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let (name, rest) = text.split_at(end);
+    if name.is_empty() {
+        return None;
+    }
+    let rest = rest.trim_start();
+
+    if let Some(rest) = rest.strip_prefix('=') {
+        let rest = rest.trim_start().strip_prefix('"')?;
+        let (value, rest) = rest.split_once('"')?;
+        return Some((Cfg::KeyValue(name.to_string(), value.to_string()), rest));
+    }
+    let Some(mut rest) = rest.strip_prefix('(') else {
+        return Some((Cfg::Name(name.to_string()), rest));
+    };
+
+    let mut cfgs = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix(')') {
+            rest = after;
+            break;
+        }
+        let (cfg, after) = parse_predicate(rest)?;
+        cfgs.push(cfg);
+        rest = after.trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest);
+    }
+    let cfg = match name {
+        "all" => Cfg::All(cfgs),
+        "any" => Cfg::Any(cfgs),
+        "not" if cfgs.len() == 1 => Cfg::Not(Box::new(cfgs.pop()?)),
+        _ => return None,
+    };
+    Some((cfg, rest))
+}
+
+/// The cfg options set for one build, as passed to `rustc --cfg`: names such as
+/// `unix` and key-value pairs such as `feature="otel"`. Options not in the set
+/// are unset, so predicates on them don't hold.
+#[derive(Debug, Clone, Default)]
+pub struct CfgSet {
+    options: HashSet<(String, Option<String>)>,
+}
+
+impl CfgSet {
+    /// The set of the `--cfg` values `specs`.
+    pub fn parse(specs: &[String]) -> Result<CfgSet, ContextMeshError> {
+        let mut options = HashSet::new();
+        for spec in specs {
+            let option = match Cfg::parse(spec) {
+                Some(Cfg::Name(name)) => (name, None),
+                Some(Cfg::KeyValue(key, value)) => (key, Some(value)),
+                _ => {
+                    return Err(ContextMeshError::QueryError(format!(
+                        "Invalid --cfg '{}': expected a name like `unix` or a pair like `feature=\"otel\"`",
+                        spec
+                    )))
+                }
+            };
+            options.insert(option);
+        }
+        Ok(CfgSet { options })
+    }
+
+    /// Whether `sym` is compiled with these options.
+    pub fn enables(&self, index: &Index, sym: &Symbol) -> bool {
+        symbol_cfgs(index, sym).iter().all(|cfg| cfg.holds(self))
+    }
+}
+
+/// The predicates guarding `sym`: those on it, its parents, and its file.
+pub fn symbol_cfgs(index: &Index, sym: &Symbol) -> Vec<Cfg> {
+    let mut cfgs = Cfg::of_attributes(&sym.attributes);
+    let mut current = sym;
+    while let Some(parent) = current.parent.and_then(|id| index.symbol(id)) {
+        cfgs.extend(Cfg::of_attributes(&parent.attributes));
+        current = parent;
+    }
+    if let Some(attributes) = index.file_cfgs.get(&*sym.file_path) {
+        cfgs.extend(Cfg::of_attributes(attributes));
+    }
+    cfgs
+}
+
+/// The `cfg` attributes guarding whole files through the `mod` declarations of
+/// their modules, for the Rust files among `files`.
+pub fn file_cfgs(files: &[&str]) -> HashMap<String, Vec<String>> {
+    // Each module file's declaring file and the cfgs on its declaration
+    let mut declared: HashMap<PathBuf, (PathBuf, Vec<String>)> = HashMap::new();
+    for file in files.iter().filter(|file| file.ends_with(".rs")) {
+        let Ok(source) = fs::read_to_string(file) else {
+            continue;
+        };
+        let file = Path::new(file.trim_start_matches("./"));
+        for captures in GATED_MOD.captures_iter(&source) {
+            let attributes: Vec<String> = captures[1]
+                .split("#[")
+                .filter_map(|attribute| attribute.trim().strip_suffix(']'))
+                .map(str::to_string)
+                .collect();
+            let dir = module_dir(file);
+            for child in [
+                dir.join(format!("{}.rs", &captures[2])),
+                dir.join(&captures[2]).join("mod.rs"),
+            ] {
+                declared.insert(child, (file.to_path_buf(), attributes.clone()));
+            }
+        }
+    }
+
+    let mut cfgs = HashMap::new();
+    for file in files {
+        let mut file_cfgs = Vec::new();
+        let mut current = PathBuf::from(file.trim_start_matches("./"));
+        // Bounded in case of a declaration cycle
+        for _ in 0..32 {
+            let Some((parent, own)) = declared.get(&current) else {
+                break;
+            };
+            file_cfgs.extend(own.iter().cloned());
+            current = parent.clone();
+        }
+        if !file_cfgs.is_empty() {
+            cfgs.insert(file.to_string(), file_cfgs);
+        }
+    }
+    cfgs
+}
+
+/// The directory holding the files of the modules declared in `file`.
+fn module_dir(file: &Path) -> PathBuf {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    match file.file_stem().and_then(|stem| stem.to_str()) {
+        Some("lib" | "main" | "mod") | None => dir.to_path_buf(),
+        Some(stem) => dir.join(stem),
+    }
+}
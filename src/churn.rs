@@ -0,0 +1,69 @@
+//! Change frequency and recency of files from git history.
+//!
+//! Each commit touching a file adds to its score, weighted down exponentially with
+//! the commit's age, so a file edited often and recently scores highest and one
+//! untouched for months scores close to zero regardless of its past activity.
+
+use std::collections::HashMap;
+
+use crate::errors::ContextMeshError;
+use crate::git;
+use crate::symbol::Symbol;
+use crate::utils::unix_timestamp;
+
+/// How many commits back the history is analyzed.
+const MAX_COMMITS: usize = 2000;
+
+/// Age at which a commit counts half as much as one made now.
+const HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Churn scores of files, normalized so the most volatile file scores 1.
+pub struct Churn {
+    scores: HashMap<String, f64>,
+    now: u64,
+}
+
+impl Churn {
+    /// Analyzes the history of the git repository in the current directory.
+    pub fn from_git() -> Result<Self, ContextMeshError> {
+        let now = unix_timestamp();
+        let mut scores: HashMap<String, f64> = HashMap::new();
+        for (time, files) in git::file_history(MAX_COMMITS)? {
+            let weight = decay(now, time);
+            for file in files {
+                *scores.entry(file).or_default() += weight;
+            }
+        }
+
+        let max = scores.values().copied().fold(0.0, f64::max);
+        if max > 0.0 {
+            scores.values_mut().for_each(|score| *score /= max);
+        }
+        Ok(Churn { scores, now })
+    }
+
+    /// The score of a file, between 0 (never or long ago changed) and 1.
+    pub fn file_score(&self, path: &str) -> f64 {
+        let path = path.trim_start_matches("./");
+        self.scores.get(path).copied().unwrap_or_default()
+    }
+
+    /// The score of the file defining `sym`, adjusted by how recently the symbol
+    /// itself changed if its blame was recorded (`index --blame`): a stable
+    /// function in a busy file scores lower than one just edited.
+    pub fn symbol_score(&self, sym: &Symbol) -> f64 {
+        let file = self.file_score(&sym.file_path);
+        match &sym.blame {
+            Some(blame) if !blame.is_uncommitted() => file * decay(self.now, blame.time),
+            // Uncommitted changes are as recent as it gets
+            Some(_) => 1.0,
+            None => file,
+        }
+    }
+}
+
+/// Weight of a change made at `time`: 1 for now, halving every `HALF_LIFE_DAYS`.
+fn decay(now: u64, time: u64) -> f64 {
+    let age_days = now.saturating_sub(time) as f64 / 86_400.0;
+    0.5f64.powf(age_days / HALF_LIFE_DAYS)
+}
@@ -0,0 +1,82 @@
+//! Co-change ("evolves with") edges between files, mined from git history.
+//!
+//! Two files evolve together when commits keep touching both, whether or not
+//! either refers to the other. The weight of the edge from one file to another
+//! is the share of the first file's commits that also touched the second. Pairs
+//! changed together fewer than [`MIN_SUPPORT`] times are dropped as noise, and
+//! commits touching more than [`MAX_FILES_PER_COMMIT`] files (mass renames,
+//! reformatting) are ignored since they say nothing about coupling.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::errors::ContextMeshError;
+use crate::git;
+
+/// How many commits back the history is analyzed.
+pub const MAX_COMMITS: usize = 2000;
+
+/// Fewest commits two files have to share to be linked.
+pub const MIN_SUPPORT: usize = 2;
+
+/// Larger commits are left out.
+pub const MAX_FILES_PER_COMMIT: usize = 50;
+
+/// For each file (relative to the repository, without `./`), the files evolving
+/// with it and the edge weights, heaviest first.
+pub type CoChanges = HashMap<String, Vec<(String, f32)>>;
+
+/// Analyzes the history of the git repository in the current directory.
+pub fn from_git() -> Result<CoChanges, ContextMeshError> {
+    Ok(from_history(&git::file_history(MAX_COMMITS)?))
+}
+
+/// The co-change edges of `history`, as from [`git::file_history`].
+pub fn from_history(history: &[(u64, Vec<String>)]) -> CoChanges {
+    let mut commits: HashMap<&str, usize> = HashMap::new();
+    let mut pairs: HashMap<(&str, &str), usize> = HashMap::new();
+    for (_, files) in history {
+        if files.len() > MAX_FILES_PER_COMMIT {
+            continue;
+        }
+        let files: HashSet<&str> = files.iter().map(String::as_str).collect();
+        for a in &files {
+            *commits.entry(a).or_default() += 1;
+            for b in &files {
+                if a != b {
+                    *pairs.entry((a, b)).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let mut co_changes = CoChanges::new();
+    for ((a, b), count) in pairs {
+        if count < MIN_SUPPORT {
+            continue;
+        }
+        let weight = count as f32 / commits[a] as f32;
+        co_changes
+            .entry(a.to_string())
+            .or_default()
+            .push((b.to_string(), weight));
+    }
+    for edges in co_changes.values_mut() {
+        edges.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    }
+    co_changes
+}
+
+/// How strongly each file evolves with any of `files`: the heaviest edge from
+/// one of them, and 1 for the files themselves.
+pub fn evolving_with(co_changes: &CoChanges, files: &HashSet<&str>) -> HashMap<String, f64> {
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    for file in files {
+        let file = file.trim_start_matches("./");
+        scores.insert(file.to_string(), 1.0);
+        for (other, weight) in co_changes.get(file).into_iter().flatten() {
+            let score = scores.entry(other.clone()).or_default();
+            *score = score.max(*weight as f64);
+        }
+    }
+    scores
+}
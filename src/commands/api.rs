@@ -0,0 +1,128 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::sorted_symbols;
+use crate::symbol::Symbol;
+
+/// Output formats of the `api` command.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ApiFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+/// One public item of the API report.
+#[derive(Serialize)]
+struct ApiItem<'a> {
+    name: &'a str,
+    kind: &'a str,
+    file_path: &'a str,
+    line_number: usize,
+    package: Option<String>,
+    signature: &'a str,
+    doc: Option<&'a str>,
+}
+
+pub fn handle_api(package: Option<&str>, format: ApiFormat) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+
+    let mut packages = PackageLookup::default();
+    let items: Vec<ApiItem> = sorted_symbols(&index)
+        .into_iter()
+        .map(|(_, sym)| sym)
+        .filter(|sym| sym.visibility.is_public() && !sym.is_test())
+        .map(|sym| api_item(sym, packages.package_of(&sym.file_path)))
+        .filter(|item| package.is_none() || item.package.as_deref() == package)
+        .collect();
+
+    match format {
+        ApiFormat::Json => {
+            let json = serde_json::to_string_pretty(&items)
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        ApiFormat::Markdown => print!("{}", render_markdown(&items, package)),
+    }
+
+    Ok(())
+}
+
+fn api_item(sym: &Symbol, package: Option<String>) -> ApiItem<'_> {
+    ApiItem {
+        name: &sym.name,
+        kind: &sym.node_kind,
+        file_path: &sym.file_path,
+        line_number: sym.line_number,
+        package,
+        signature: &sym.signature,
+        doc: sym.doc.as_deref(),
+    }
+}
+
+fn render_markdown(items: &[ApiItem], package: Option<&str>) -> String {
+    let mut out = match package {
+        Some(name) => format!("# Public API of `{}`\n", name),
+        None => "# Public API\n".to_string(),
+    };
+    if items.is_empty() {
+        out.push_str("\nNo public items found.\n");
+        return out;
+    }
+
+    let mut by_file: BTreeMap<&str, Vec<&ApiItem>> = BTreeMap::new();
+    for item in items {
+        by_file.entry(item.file_path).or_default().push(item);
+    }
+
+    for (file_path, file_items) in by_file {
+        out.push_str(&format!("\n## {}\n", file_path));
+        for item in file_items {
+            out.push_str(&format!("\n```rust\n{}\n```\n", item.signature));
+            if let Some(doc) = item.doc {
+                out.push_str(&format!("\n{}\n", doc));
+            }
+        }
+    }
+    out
+}
+
+/// Maps source files to the name of the Cargo package that owns them, found by
+/// walking up to the nearest `Cargo.toml` with a `[package]` section.
+#[derive(Default)]
+pub(super) struct PackageLookup {
+    by_dir: HashMap<PathBuf, Option<String>>,
+}
+
+impl PackageLookup {
+    pub(super) fn package_of(&mut self, file_path: &str) -> Option<String> {
+        let dir = Path::new(file_path).parent()?.to_path_buf();
+        if let Some(cached) = self.by_dir.get(&dir) {
+            return cached.clone();
+        }
+
+        let package = match read_package_name(&dir.join("Cargo.toml")) {
+            Some(name) => Some(name),
+            None => dir
+                .parent()
+                .and_then(|parent| self.package_of(&parent.join("_").to_string_lossy())),
+        };
+        self.by_dir.insert(dir, package.clone());
+        package
+    }
+}
+
+fn read_package_name(manifest: &Path) -> Option<String> {
+    let text = fs::read_to_string(manifest).ok()?;
+    let value: toml::Value = toml::from_str(&text).ok()?;
+    value
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(str::to_string)
+}
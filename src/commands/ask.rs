@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::combine::scrub;
+use crate::arch::qualified_name;
+use crate::bundle::Bundle;
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::llm::provider;
+use crate::output::symbol_order;
+use crate::related::{name_words, rank};
+use crate::symbol::Symbol;
+use crate::utils::estimate_tokens;
+
+/// Weights of a question word found in a symbol's name, file path, and doc
+/// comment.
+const NAME_WEIGHT: f64 = 1.0;
+const PATH_WEIGHT: f64 = 0.3;
+const DOC_WEIGHT: f64 = 0.2;
+
+/// How many of the best matches of a question their neighbours are ranked
+/// against, and how much a neighbour's [`rank`] score counts.
+const SEEDS: usize = 5;
+const RELATED_WEIGHT: f64 = 0.5;
+
+/// Words too common in questions to say anything about the code.
+const STOP_WORDS: &[&str] = &[
+    "the",
+    "and",
+    "for",
+    "how",
+    "what",
+    "where",
+    "when",
+    "why",
+    "which",
+    "who",
+    "does",
+    "are",
+    "is",
+    "can",
+    "this",
+    "that",
+    "with",
+    "from",
+    "into",
+    "there",
+    "their",
+    "its",
+    "get",
+    "use",
+    "used",
+    "code",
+    "function",
+    "functions",
+    "method",
+    "file",
+    "work",
+    "works",
+    "happen",
+];
+
+const SYSTEM_PROMPT: &str = "You answer questions about a codebase from the excerpts of it \
+    you are given. Cite every symbol your answer relies on by its qualified name in \
+    backticks, exactly as listed, e.g. `crate::index::Index`. If the excerpts don't \
+    answer the question, say so rather than guessing.";
+
+/// A span in backticks, which an answer cites symbols with.
+static CITATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"`([^`\n]+)`").expect("valid regex"));
+
+/// Sends `question` with the code most relevant to it, within `budget` tokens
+/// (the `[llm]` budget if unset), to the configured LLM and prints the answer
+/// and the symbols it cites. Secrets are masked before anything is sent, and
+/// generated and third-party code is left out. With `dry_run`, the prompt is
+/// printed instead of sent.
+pub fn handle_ask(
+    question: &str,
+    budget: Option<usize>,
+    dry_run: bool,
+) -> Result<(), ContextMeshError> {
+    let config = Config::load()?;
+    let index = Index::load_index()?;
+    let budget = budget.unwrap_or(config.llm.budget);
+
+    let selected = select(&index, question, budget);
+    if selected.is_empty() {
+        eprintln!("No indexed code matches the question; asking without context.");
+    }
+    let mut bundle = Bundle::default();
+    let mut listing = String::new();
+    for sym in &selected {
+        bundle.add_range(&sym.file_path, sym.start_byte..sym.end_byte);
+        listing.push_str(&format!(
+            "- `{}` ({}:{})\n",
+            qualified_name(&index, sym),
+            sym.file_path.trim_start_matches("./"),
+            sym.line_number
+        ));
+    }
+    let prompt = scrub(&format!(
+        "# Symbols\n\n{}\n{}\n# Question\n\n{}\n",
+        listing,
+        bundle.render(),
+        question
+    ))?;
+    eprintln!(
+        "Asking with {} symbol(s), ~{} tokens.",
+        selected.len(),
+        estimate_tokens(prompt.len())
+    );
+    if dry_run {
+        println!("{}", prompt);
+        return Ok(());
+    }
+
+    let answer = provider(&config.llm)?.complete(SYSTEM_PROMPT, &prompt)?;
+    println!("{}", answer.trim_end());
+    let cited = citations(&index, &selected, &answer);
+    if !cited.is_empty() {
+        println!("\nReferences:");
+        for sym in cited {
+            println!(
+                "  {} ({}:{})",
+                qualified_name(&index, sym),
+                sym.file_path.trim_start_matches("./"),
+                sym.line_number
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The outermost code symbols most relevant to `question` that together fit in
+/// `budget` tokens, in source order. A symbol is relevant if words of the
+/// question occur in its name, path, or doc comment, or if it is closely
+/// [related](rank) to the symbols matching best.
+fn select<'a>(index: &'a Index, question: &str, budget: usize) -> Vec<&'a Symbol> {
+    let words: Vec<String> = name_words(question)
+        .into_iter()
+        .filter(|word| word.len() > 2 && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stem(&word).to_string())
+        .collect();
+    let hits = |text: &str| {
+        let found = name_words(text);
+        words
+            .iter()
+            .filter(|word| found.iter().any(|other| same_word(word, stem(other))))
+            .count() as f64
+    };
+
+    let mut scores: HashMap<&str, f64> = index
+        .symbols
+        .iter()
+        .filter(|(_, sym)| sym.is_code())
+        .filter(|(_, sym)| !index.is_generated(sym) && !index.is_third_party(sym))
+        .map(|(hash, sym)| {
+            let score = NAME_WEIGHT * hits(&sym.name)
+                + PATH_WEIGHT * hits(&sym.file_path)
+                + DOC_WEIGHT * sym.doc.as_deref().map_or(0.0, hits);
+            (hash.as_str(), score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+
+    let mut best: Vec<(&str, f64)> = scores.iter().map(|(hash, score)| (*hash, *score)).collect();
+    best.sort_by(|(a, a_score), (b, b_score)| {
+        b_score
+            .total_cmp(a_score)
+            .then_with(|| symbol_order(&index.symbols[*a], &index.symbols[*b]))
+    });
+    let seeds: HashSet<&str> = best.iter().take(SEEDS).map(|(hash, _)| *hash).collect();
+    let top = best.first().map_or(0.0, |(_, score)| *score);
+    for related in rank(index, &seeds, &HashMap::new()) {
+        if index.is_generated(related.symbol) || index.is_third_party(related.symbol) {
+            continue;
+        }
+        let hash = related.symbol.hash();
+        let Some((hash, _)) = index.symbols.get_key_value(&hash) else {
+            continue;
+        };
+        let score = scores.entry(hash.as_str()).or_default();
+        *score = score.max(RELATED_WEIGHT * top * related.score);
+    }
+
+    let mut ranked: Vec<(&Symbol, f64)> = scores
+        .into_iter()
+        .map(|(hash, score)| (&index.symbols[hash], score))
+        .collect();
+    ranked.sort_by(|(a, a_score), (b, b_score)| {
+        b_score.total_cmp(a_score).then_with(|| symbol_order(a, b))
+    });
+
+    let mut bundle = Bundle::default();
+    let mut remaining = budget;
+    let mut selected: Vec<&Symbol> = Vec::new();
+    for (sym, _) in ranked {
+        let range = sym.start_byte..sym.end_byte;
+        let tokens = estimate_tokens(range.len());
+        if tokens > remaining || bundle.covers(&sym.file_path, &range) {
+            continue;
+        }
+        // A symbol replaces the ones nested in it that were selected before
+        selected.retain(|other| {
+            let nested = *other.file_path == *sym.file_path
+                && range.start <= other.start_byte
+                && other.end_byte <= range.end;
+            if nested {
+                remaining += estimate_tokens(other.end_byte - other.start_byte);
+            }
+            !nested
+        });
+        remaining -= tokens;
+        bundle.add_range(&sym.file_path, range);
+        selected.push(sym);
+    }
+    selected.sort_by(|a, b| symbol_order(a, b));
+    selected
+}
+
+/// `word` without a common inflection, e.g. `redact` for `redacted`.
+fn stem(word: &str) -> &str {
+    ["ing", "ion", "ed", "s"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .filter(|stem| stem.len() >= 4)
+        .unwrap_or(word)
+}
+
+/// Whether stems `a` and `b` are the same word, allowing for a derived one
+/// (`redactor`, `parser`).
+fn same_word(a: &str, b: &str) -> bool {
+    a == b || (a.len().min(b.len()) >= 4 && (a.starts_with(b) || b.starts_with(a)))
+}
+
+/// The `selected` symbols `answer` cites in backticks, by qualified name or a
+/// suffix of it, in order of first citation.
+fn citations<'a>(index: &Index, selected: &[&'a Symbol], answer: &str) -> Vec<&'a Symbol> {
+    let names: Vec<(String, &Symbol)> = selected
+        .iter()
+        .map(|sym| (qualified_name(index, sym), *sym))
+        .collect();
+    let mut cited: Vec<&Symbol> = Vec::new();
+    for citation in CITATION.captures_iter(answer) {
+        let citation = citation[1].trim().trim_end_matches("()");
+        let matching = names
+            .iter()
+            .filter(|(name, _)| name == citation || name.ends_with(&format!("::{}", citation)));
+        for (_, sym) in matching {
+            if !cited.iter().any(|other| std::ptr::eq(*other, *sym)) {
+                cited.push(sym);
+            }
+        }
+    }
+    cited
+}
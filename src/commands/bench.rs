@@ -0,0 +1,99 @@
+use log::info;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::config::IndexConfig;
+use crate::errors::ContextMeshError;
+use crate::fixtures::{fixture_module, generate_rust_fixture};
+use crate::index::Index;
+use crate::parser::CodeParser;
+
+/// Runs a quick, self-contained timing of the indexing pipeline on a generated
+/// fixture project. For statistically sound numbers use `cargo bench`.
+pub fn handle_bench(files: usize, fns_per_file: usize) -> Result<(), ContextMeshError> {
+    let root = std::env::temp_dir().join(format!("contextmesh-bench-{}", std::process::id()));
+    let result = run_bench(&root, files, fns_per_file);
+    if let Err(e) = fs::remove_dir_all(&root) {
+        eprintln!("Failed to clean up '{}': {}", root.display(), e);
+    }
+    result
+}
+
+fn run_bench(root: &Path, files: usize, fns_per_file: usize) -> Result<(), ContextMeshError> {
+    info!(
+        "Generating fixture: {} file(s) x {} function(s) in '{}'.",
+        files,
+        fns_per_file,
+        root.display()
+    );
+    let paths = generate_rust_fixture(root, files, fns_per_file)?;
+    let mut code_parser = CodeParser::new_rust()?;
+    code_parser.enable_tree_cache();
+
+    // Parse throughput (no index bookkeeping)
+    let start = Instant::now();
+    let mut bytes = 0;
+    for path in &paths {
+        bytes += fs::metadata(path)?.len();
+        code_parser.parse_file(&path.to_string_lossy())?;
+    }
+    let parse_time = start.elapsed();
+
+    // Full index build
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let mut index = Index::new();
+    let start = Instant::now();
+    index.index_files(&paths, &mut code_parser)?;
+    index.recheck_unresolved();
+    let build_time = start.elapsed();
+
+    // Incremental update of a single file
+    let changed = &paths[paths.len() / 2];
+    fs::write(changed, fixture_module(paths.len() / 2, fns_per_file + 1))?;
+    let start = Instant::now();
+    index.index_files(&paths, &mut code_parser)?;
+    index.recheck_unresolved();
+    let incremental_time = start.elapsed();
+
+    // Save and load round trip
+    let index_path = root.join("index.bin");
+    let start = Instant::now();
+    index.save_index_to(&index_path, &IndexConfig::default())?;
+    let save_time = start.elapsed();
+    let index_size = fs::metadata(&index_path)?.len();
+    let start = Instant::now();
+    Index::load_index_from(&index_path)?;
+    let load_time = start.elapsed();
+
+    println!("Benchmark results:");
+    println!(
+        "  Parse:              {:>10}  ({:.1} MB/s)",
+        format_duration(parse_time),
+        bytes as f64 / 1_000_000.0 / parse_time.as_secs_f64().max(f64::EPSILON)
+    );
+    println!(
+        "  Full index build:   {:>10}  ({} symbols)",
+        format_duration(build_time),
+        index.symbols.len()
+    );
+    println!(
+        "  Incremental update: {:>10}",
+        format_duration(incremental_time)
+    );
+    println!(
+        "  Save:               {:>10}  ({} bytes)",
+        format_duration(save_time),
+        index_size
+    );
+    println!("  Load:               {:>10}", format_duration(load_time));
+
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,126 @@
+use log::{info, warn};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::index::configured_parser;
+use super::OutputFormat;
+use crate::config::Config;
+use crate::detect::LanguageDetector;
+use crate::errors::ContextMeshError;
+use crate::git::{changed_files_since, file_at_revision};
+use crate::index::{diff_symbols, Index, SymbolChange};
+use crate::parser::CodeParser;
+use crate::symbol::Symbol;
+
+pub fn handle_changed(since: Option<&str>, format: OutputFormat) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+
+    let mut changes = match since {
+        Some(rev) => changes_since_revision(&index, rev)?,
+        None => index.last_changes.clone(),
+    };
+    changes.sort_by(|a, b| {
+        (&a.file_path, a.line_number, a.kind, &a.name).cmp(&(
+            &b.file_path,
+            b.line_number,
+            b.kind,
+            &b.name,
+        ))
+    });
+
+    match format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&changes)
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        OutputFormat::Text => {
+            if changes.is_empty() {
+                println!("No changed symbols.");
+            }
+            for change in &changes {
+                println!(
+                    "{:<9} {} ({}) {}:{}",
+                    change.kind.as_str(),
+                    change.name,
+                    change.node_kind,
+                    change.file_path,
+                    change.line_number
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Diffs the symbols of every file touched since `rev` against the index.
+///
+/// Files that were modified or added are compared against the indexed symbols,
+/// which are assumed to be current (run `contextmesh index` first). Deleted files
+/// report all of their old symbols as removed. The old revision of each file is
+/// parsed as its configured or detected language, as `index` would.
+fn changes_since_revision(index: &Index, rev: &str) -> Result<Vec<SymbolChange>, ContextMeshError> {
+    let config = Config::load()?;
+    let detector = LanguageDetector::new(&config);
+    // By language, or `None` for a language that has no parser
+    let mut parsers: HashMap<String, Option<CodeParser>> = HashMap::new();
+    let indexed_paths: HashSet<&str> = index.file_hashes.keys().map(String::as_str).collect();
+    let mut changes = Vec::new();
+
+    for file in changed_files_since(rev)? {
+        let Some(language) = detector.detect(&file.path) else {
+            continue;
+        };
+        let code_parser = match parsers.entry(language) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let code_parser = match configured_parser(&config, entry.key()) {
+                    Ok((_, code_parser)) => Some(code_parser),
+                    Err(e) => {
+                        warn!("Skipping changed {} files: {}", entry.key(), e);
+                        None
+                    }
+                };
+                entry.insert(code_parser)
+            }
+        };
+        let Some(code_parser) = code_parser else {
+            continue;
+        };
+
+        // Indexed paths may carry a leading "./" that git doesn't print
+        let dotted = format!("./{}", file.path);
+        let index_path = [file.path.as_str(), dotted.as_str()]
+            .into_iter()
+            .find(|p| indexed_paths.contains(p))
+            .unwrap_or(&file.path);
+
+        let old_symbols = match file_at_revision(rev, Path::new(&file.path)) {
+            Some(code) => code_parser.parse_source(index_path, code)?.symbols,
+            None => Vec::new(),
+        };
+        let new_symbols: Vec<Symbol> = if file.deleted {
+            Vec::new()
+        } else {
+            if !indexed_paths.contains(index_path) {
+                warn!(
+                    "'{}' changed since {} but is not indexed; skipping.",
+                    file.path, rev
+                );
+                continue;
+            }
+            index
+                .symbols_in_file(index_path)
+                .map(|(_, sym)| sym.clone())
+                .collect()
+        };
+
+        let old_refs: Vec<&Symbol> = old_symbols.iter().collect();
+        changes.extend(diff_symbols(&old_refs, &new_symbols));
+    }
+
+    info!("{} symbol(s) changed since {}.", changes.len(), rev);
+    Ok(changes)
+}
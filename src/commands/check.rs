@@ -0,0 +1,191 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+use super::ReportFormat;
+use crate::arch::{self, qualified_name, rule_matches};
+use crate::config::{Config, DependencyRule};
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::profile;
+use crate::sarif::{self, Finding, Rule};
+use crate::symbol::Symbol;
+
+/// A reference or dependency that `check` reports.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Problem {
+    pub file_path: String,
+    pub line_number: usize,
+    /// `unresolved`, `dependency-rule`, or `architecture`.
+    pub kind: &'static str,
+    pub message: String,
+    /// The symbol depended on, for dependency problems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related: Option<(String, usize)>,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        id: "unresolved",
+        description: "Reference that can't be resolved and isn't in the baseline",
+        level: "warning",
+    },
+    Rule {
+        id: "dependency-rule",
+        description: "Dependency forbidden by a [[rules]] entry of the config",
+        level: "error",
+    },
+    Rule {
+        id: "architecture",
+        description: "Dependency breaking the [architecture] rules of the config",
+        level: "error",
+    },
+];
+
+pub fn handle_check(
+    baseline: Option<&str>,
+    ci: bool,
+    format: ReportFormat,
+) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+    let config = Config::load()?;
+
+    let mut problems = rule_violations(&index, &config.rules);
+    problems.extend(
+        arch::violations(&index, &config.architecture)?
+            .into_iter()
+            .map(|violation| Problem {
+                file_path: violation.file_path,
+                line_number: violation.line_number,
+                kind: "architecture",
+                message: format!(
+                    "{} depends on {} ({})",
+                    violation.from, violation.to, violation.reason
+                ),
+                related: Some((violation.to_file_path, violation.to_line_number)),
+            }),
+    );
+    if let Some(name) = baseline {
+        let baseline = Index::load_index_from(&profile::snapshot_path(name)?)?;
+        problems.extend(new_unresolved_references(&index, &baseline));
+    }
+    problems.sort();
+    problems.dedup();
+
+    match format {
+        ReportFormat::Json => {
+            let json = serde_json::to_string_pretty(&problems)
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        ReportFormat::Sarif => {
+            let findings: Vec<Finding> = problems
+                .iter()
+                .map(|problem| Finding {
+                    rule_id: problem.kind,
+                    message: problem.message.clone(),
+                    file_path: problem.file_path.clone(),
+                    line_number: problem.line_number,
+                    related: problem
+                        .related
+                        .clone()
+                        .map(|(path, line)| (path, line, "Dependency".to_string())),
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&sarif::report(RULES, &findings))
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        ReportFormat::Text => {
+            if problems.is_empty() {
+                println!("No problems found.");
+            }
+            for problem in &problems {
+                println!(
+                    "{}:{}: {}: {}",
+                    problem.file_path, problem.line_number, problem.kind, problem.message
+                );
+            }
+        }
+    }
+
+    if ci && !problems.is_empty() {
+        return Err(ContextMeshError::CheckFailed(problems.len()));
+    }
+    Ok(())
+}
+
+/// Dependencies of symbols matching a rule's `from` on symbols it denies.
+fn rule_violations(index: &Index, rules: &[DependencyRule]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for symbol in index.symbols.values() {
+        for rule in rules
+            .iter()
+            .filter(|rule| matches(index, &rule.from, symbol))
+        {
+            for dependency in symbol
+                .dependencies
+                .iter()
+                .filter_map(|id| index.symbol(*id))
+            {
+                let denied = rule
+                    .deny
+                    .iter()
+                    .any(|pattern| matches(index, pattern, dependency))
+                    && !rule
+                        .allow
+                        .iter()
+                        .any(|pattern| matches(index, pattern, dependency));
+                if !denied {
+                    continue;
+                }
+                let mut message = format!(
+                    "{} depends on {}",
+                    qualified_name(index, symbol),
+                    qualified_name(index, dependency)
+                );
+                if let Some(explanation) = &rule.message {
+                    message.push_str(&format!(" ({})", explanation));
+                }
+                problems.push(Problem {
+                    file_path: symbol.file_path.to_string(),
+                    line_number: symbol.line_number,
+                    kind: "dependency-rule",
+                    message,
+                    related: Some((dependency.file_path.to_string(), dependency.line_number)),
+                });
+            }
+        }
+    }
+    problems
+}
+
+/// Unresolved references of the index that the baseline doesn't have. References
+/// are compared by file, caller name, and referenced name, so they survive edits
+/// that only move code around.
+fn new_unresolved_references(index: &Index, baseline: &Index) -> Vec<Problem> {
+    let known: HashSet<(&str, &str, &str)> = baseline
+        .unresolved_references()
+        .map(|(caller, name)| (&*caller.file_path, caller.name.as_str(), name))
+        .collect();
+
+    index
+        .unresolved_references()
+        .filter(|(caller, name)| !known.contains(&(&*caller.file_path, caller.name.as_str(), name)))
+        .map(|(caller, name)| Problem {
+            file_path: caller.file_path.to_string(),
+            line_number: caller.line_number,
+            kind: "unresolved",
+            message: format!(
+                "{} refers to unknown `{}`",
+                qualified_name(index, caller),
+                name
+            ),
+            related: None,
+        })
+        .collect()
+}
+
+/// Whether `symbol` is in the module or file `pattern` (see [`DependencyRule`]).
+fn matches(index: &Index, pattern: &str, symbol: &Symbol) -> bool {
+    rule_matches(pattern, &qualified_name(index, symbol), symbol)
+}
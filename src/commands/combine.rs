@@ -1,19 +1,184 @@
+use super::context::add_symbol;
+use super::tui::pick;
+use crate::arch::{glob_matches, path_matches, qualified_name};
+use crate::bundle::{Bundle, Detail};
+use crate::churn::Churn;
+use crate::config::Config;
 use crate::errors::ContextMeshError;
 use crate::index::Index;
-use crate::utils::collect_files;
-use arboard::Clipboard;
+use crate::models::ModelProfile;
+use crate::parser::document::is_document_file;
+use crate::redact::Redactor;
+use crate::sink::{self, Sink};
+use crate::symbol::Symbol;
+use crate::utils::{collect_files, estimate_tokens, module_path, read_source};
+use log::debug;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::ops::Range;
 
-pub fn handle_combine() -> Result<(), ContextMeshError> {
+/// Share of a budget the code of symbols may take when not all of it fits; the
+/// rest goes to the signatures and names of the symbols ranked next.
+const FULL_SHARE: f64 = 0.75;
+
+/// Which of the source files go into a combined bundle.
+#[derive(Debug, Default)]
+pub struct FileFilter {
+    /// Also include generated files
+    pub include_generated: bool,
+    /// Also include vendored and other third-party files
+    pub include_third_party: bool,
+    /// Globs of the files to include, all of them if empty. A glob matching a
+    /// directory includes the files under it.
+    pub include: Vec<String>,
+    /// Globs of the files to leave out, even if included, as for `include`
+    pub exclude: Vec<String>,
+}
+
+/// The symbols left out of every bundle, whatever selects them: those marked
+/// with a `contextmesh:ignore` comment (see [`crate::parser::ignore`]) and
+/// those matching a glob of the `[exclude]` config section.
+pub(super) struct Exclusions {
+    globs: Vec<String>,
+}
+
+impl Exclusions {
+    pub(super) fn load() -> Result<Self, ContextMeshError> {
+        Ok(Exclusions {
+            globs: Config::load()?.exclude.symbols,
+        })
+    }
+
+    pub(super) fn excludes(&self, index: &Index, sym: &Symbol) -> bool {
+        if sym.is_excluded() {
+            return true;
+        }
+        if self.globs.is_empty() {
+            return false;
+        }
+        let name = qualified_name(index, sym);
+        self.globs.iter().any(|glob| glob_matches(glob, &name, sym))
+    }
+
+    /// Cuts the excluded symbols out of the code `bundle` includes.
+    pub(super) fn apply(&self, index: &Index, bundle: &mut Bundle) {
+        let excluded: Vec<(String, Range<usize>)> = bundle
+            .paths()
+            .flat_map(|path| {
+                // Files given on the command line may lack the `./` of indexed paths
+                let indexed = match index.file_hashes.contains_key(path) {
+                    true => path.to_string(),
+                    false => format!("./{}", path.trim_start_matches("./")),
+                };
+                index
+                    .symbols_in_file(&indexed)
+                    .map(|(_, sym)| sym)
+                    .filter(|sym| self.excludes(index, sym))
+                    .map(move |sym| (path.to_string(), sym.start_byte..sym.end_byte))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for (path, range) in excluded {
+            bundle.exclude_range(&path, range);
+        }
+    }
+}
+
+impl FileFilter {
+    /// Whether the indexed file at `path` goes into the bundle.
+    pub(super) fn allows(&self, index: &Index, path: &str) -> bool {
+        (self.include_generated || !index.generated_files.contains(path))
+            && (self.include_third_party || !index.third_party_files.contains(path))
+            && self.allows_path(path)
+    }
+
+    /// Whether `path` passes the `include` and `exclude` globs.
+    fn allows_path(&self, path: &str) -> bool {
+        let covers = |glob: &String| {
+            let glob = glob.trim_end_matches('/');
+            path_matches(glob, path) || path_matches(&format!("{}/**", glob), path)
+        };
+        (self.include.is_empty() || self.include.iter().any(covers))
+            && !self.exclude.iter().any(covers)
+    }
+}
+
+/// Combines the indexed source files (and, with `docs`, the document sections
+/// that refer to their code) and copies the result to the clipboard. With a
+/// `budget`, only the highest-ranked symbols that fit in it are included,
+/// ranked by how much other code uses them, with `churn` by how actively they
+/// are changing, and with `complexity` by how much they branch. With `model`,
+/// the budget is what the model's context window leaves (see
+/// [`ModelProfile::budget`]). With `interactive`, the symbols and files to
+/// include are picked in the browser of `contextmesh tui`. Only the files
+/// `filter` allows are included, with or without an index. Files go after the
+/// files they depend on (see [`Index::dependency_order`]), whole files each
+/// preceded by a header summarizing them (see [`file_header`]). Notebooks are
+/// combined as the script of their code cells, in execution order.
+pub fn handle_combine(
+    docs: bool,
+    budget: Option<usize>,
+    model: Option<&str>,
+    churn: bool,
+    complexity: bool,
+    filter: &FileFilter,
+    interactive: bool,
+) -> Result<(), ContextMeshError> {
+    if interactive {
+        let index = Index::load_index()?;
+        let Some((mut bundle, _)) = pick(&index, filter)? else {
+            return Ok(());
+        };
+        if docs {
+            bundle.push_str(&related_doc_sections(&index));
+        }
+        return deliver(&bundle);
+    }
+    let budget = match model {
+        Some(name) => Some(ModelProfile::named(&Config::load()?, name)?.budget()),
+        None => budget,
+    };
     let index_result = Index::load_index();
     let mut combined_content = String::new();
 
-    if let Ok(index) = index_result {
-        println!("Index");
-        for file_path in index.file_hashes.keys() {
-            match fs::read_to_string(file_path) {
+    if let (Ok(index), Some(budget)) = (&index_result, budget) {
+        let churn = match churn {
+            true => Some(Churn::from_git()?),
+            false => None,
+        };
+        // Document sections asked for go in whole; symbols fill the rest
+        let doc_sections = match docs {
+            true => related_doc_sections(index),
+            false => String::new(),
+        };
+        let doc_tokens = estimate_tokens(doc_sections.len());
+        warn_over_budget("The document sections", doc_tokens, budget);
+        combined_content.push_str(&select_within_budget(
+            index,
+            budget.saturating_sub(doc_tokens),
+            churn.as_ref(),
+            complexity,
+            filter,
+            &Exclusions::load()?,
+        ));
+        combined_content.push_str(&doc_sections);
+    } else if let Ok(index) = index_result {
+        eprintln!("Index");
+        let file_paths: Vec<&str> = index
+            .file_hashes
+            .keys()
+            .map(String::as_str)
+            .filter(|path| !is_document_file(path) && filter.allows(&index, path))
+            .collect();
+        let exclusions = Exclusions::load()?;
+        let mut bundle = Bundle::default();
+        for file_path in index.dependency_order(&file_paths) {
+            match read_source(file_path) {
                 Ok(content) => {
-                    combined_content.push_str(&format!("# {}\n\n{}\n\n", file_path, content));
+                    let content = String::from_utf8_lossy(&content);
+                    bundle.add_file(file_path);
+                    let header = file_header(&index, file_path, &content, &exclusions);
+                    bundle.set_header(file_path, header);
                 }
                 Err(e) => {
                     eprintln!("Failed to read file '{}': {}. Skipping.", file_path, e);
@@ -21,16 +186,27 @@ pub fn handle_combine() -> Result<(), ContextMeshError> {
                 }
             }
         }
+        exclusions.apply(&index, &mut bundle);
+        combined_content.push_str(&bundle.render());
+
+        if docs {
+            combined_content.push_str(&related_doc_sections(&index));
+        }
     } else {
-        println!("Index not found. Collecting files directly from the directory.");
+        eprintln!("Index not found. Collecting files directly from the directory.");
 
         let default_directory = "./src";
         let extensions = &["rs"];
 
-        let files_to_combine = collect_files(default_directory, extensions);
+        let mut files_to_combine = collect_files(
+            default_directory,
+            extensions,
+            Config::load()?.files.follow_symlinks,
+        );
+        files_to_combine.retain(|path| filter.allows_path(path));
 
         if files_to_combine.is_empty() {
-            println!(
+            eprintln!(
                 "No files found to combine in the category '{}'.",
                 default_directory
             );
@@ -50,23 +226,347 @@ pub fn handle_combine() -> Result<(), ContextMeshError> {
         }
     }
 
+    if combined_content.is_empty() {
+        eprintln!("No files found to combine.");
+    }
+    deliver(&combined_content)
+}
+
+/// The summary put before the content of a whole file: the public symbols it
+/// defines, the other indexed files it depends on, and its size in tokens, so
+/// the map of a bundle comes before its code. Symbols `exclusions` cut out of
+/// the file count for neither.
+fn file_header(index: &Index, file_path: &str, content: &str, exclusions: &Exclusions) -> String {
+    let mut symbols: Vec<&Symbol> = index
+        .symbols_in_file(file_path)
+        .map(|(_, sym)| sym)
+        .filter(|sym| !exclusions.excludes(index, sym))
+        .collect();
+    symbols.sort_by_key(|sym| sym.start_byte);
+
+    // Fields show in the code of their type
+    let exports: Vec<String> = symbols
+        .iter()
+        .filter(|sym| sym.visibility.is_public() && !sym.node_kind.contains("field"))
+        .map(|sym| {
+            let module_prefix = format!("{}::", module_path(&sym.file_path));
+            let qualified = qualified_name(index, sym);
+            qualified
+                .strip_prefix(&module_prefix)
+                .unwrap_or(&qualified)
+                .to_string()
+        })
+        .collect();
+    let mut dependencies: Vec<&str> = symbols
+        .iter()
+        .flat_map(|sym| &sym.dependencies)
+        .filter_map(|id| index.symbol(*id))
+        .map(|dep| &*dep.file_path)
+        .filter(|path| *path != file_path)
+        .collect();
+    dependencies.sort();
+    dependencies.dedup();
+
+    let mut header = String::new();
+    if !exports.is_empty() {
+        header.push_str(&format!("Exports: {}\n", exports.join(", ")));
+    }
+    if !dependencies.is_empty() {
+        header.push_str(&format!("Depends on: {}\n", dependencies.join(", ")));
+    }
+    header.push_str(&format!("Tokens: {}\n", estimate_tokens(content.len())));
+    header
+}
+
+/// The complexity of `sym` and the definitions nested in it, 1 if unmeasured.
+fn total_complexity(index: &Index, sym: &Symbol) -> u32 {
+    index
+        .symbols_in_file(&sym.file_path)
+        .filter(|(_, other)| sym.start_byte <= other.start_byte && other.end_byte <= sym.end_byte)
+        .filter_map(|(_, other)| other.metrics)
+        .map(|metrics| metrics.complexity)
+        .sum::<u32>()
+        .max(1)
+}
+
+/// Warns that `what`, which goes into a bundle regardless of its budget, takes
+/// `tokens` of them when that is more than `budget`.
+pub(super) fn warn_over_budget(what: &str, tokens: usize, budget: usize) {
+    if tokens > budget {
+        eprintln!(
+            "{} take ~{} tokens, more than the budget of {}; the bundle won't fit.",
+            what, tokens, budget
+        );
+    }
+}
+
+/// Sends combined content to the first output sink that takes it (see
+/// [`crate::sink`]), with secrets masked (see [`scrub`]), and unless that is
+/// stdout, prints it.
+pub(super) fn deliver(combined_content: &str) -> Result<(), ContextMeshError> {
+    let combined_content = scrub(combined_content)?;
     if !combined_content.is_empty() {
-        match Clipboard::new() {
-            Ok(mut clipboard) => {
-                clipboard
-                    .set_text(combined_content.clone())
-                    .map_err(|e| ContextMeshError::ClipboardError(e.to_string()))?;
-                println!("Combined content copied to clipboard.");
-            }
-            Err(e) => {
-                eprintln!("Failed to initialize clipboard: {}.", e);
-                return Err(ContextMeshError::ClipboardError(e.to_string()));
-            }
+        let (sinks, file) = sink::chosen(&Config::load()?.output);
+        match sink::send(&combined_content, &sinks, file.as_ref())? {
+            Sink::Stdout => return Ok(()),
+            Sink::File => eprintln!(
+                "Combined content written to {}.",
+                file.unwrap_or_default().display()
+            ),
+            used => eprintln!("Combined content copied to the {}.", used.name()),
         }
-    } else {
-        println!("No files found to combine.");
     }
 
     println!("\nCombined Content:\n{}", combined_content);
     Ok(())
 }
+
+/// `content` with the secrets the `[redaction]` config matches masked. What was
+/// masked is reported on stderr.
+pub(super) fn scrub(content: &str) -> Result<String, ContextMeshError> {
+    let config = Config::load()?;
+    let (scrubbed, redactions) = Redactor::from_config(&config.redaction)?.redact(content);
+    if !redactions.is_empty() {
+        let found: Vec<String> = redactions
+            .iter()
+            .map(|redaction| format!("{} (line {})", redaction.pattern, redaction.line))
+            .collect();
+        eprintln!(
+            "Redacted {} secret(s): {}.",
+            redactions.len(),
+            found.join(", ")
+        );
+    }
+    Ok(scrubbed)
+}
+
+/// The outermost code symbols (those not nested in another symbol's source) with
+/// the highest weight that together fit in `budget` tokens, grouped by file in
+/// source order, and the signatures and names of the next ones (see
+/// [`pack_symbols`]).
+///
+/// A symbol's weight grows with the number of its users; with `churn`, it is
+/// scaled by the churn score so that volatile code wins over stable code, and
+/// with `complexity` it grows with the symbol's complexity.
+fn select_within_budget(
+    index: &Index,
+    budget: usize,
+    churn: Option<&Churn>,
+    complexity: bool,
+    filter: &FileFilter,
+    exclusions: &Exclusions,
+) -> String {
+    let candidates = index
+        .symbols
+        .values()
+        .filter(|sym| sym.is_code() && !is_document_file(&sym.file_path))
+        .filter(|sym| filter.allows(index, &sym.file_path))
+        .filter(|sym| !exclusions.excludes(index, sym))
+        .collect();
+    let mut bundle = Bundle::default();
+    for (sym, detail) in pack_symbols(index, candidates, Some(budget), churn, None, complexity) {
+        add_symbol(&mut bundle, index, sym, detail);
+    }
+    exclusions.apply(index, &mut bundle);
+    bundle.render()
+}
+
+/// The outermost of `candidates` with the highest weight that together fit in
+/// `budget` tokens (all of them without one), with how much of each to include,
+/// sorted by file (in [dependency order](Index::dependency_order)) and position.
+/// Symbols of files scoring high in `evolving` (see
+/// [`crate::cochange::evolving_with`]) weigh up to twice as much, and with
+/// `complexity` a symbol's weight grows logarithmically with the complexity of
+/// its code, including that of the definitions nested in it.
+///
+/// If not all of them fit, the best-ranked get up to [`FULL_SHARE`] of the
+/// budget for their code, the next ones as much as is left for their signature
+/// and doc summary, and the ones after that for their name.
+pub(super) fn pack_symbols<'a>(
+    index: &Index,
+    candidates: Vec<&'a Symbol>,
+    budget: Option<usize>,
+    churn: Option<&Churn>,
+    evolving: Option<&HashMap<String, f64>>,
+    complexity: bool,
+) -> Vec<(&'a Symbol, Detail)> {
+    let mut candidates: Vec<(&'a Symbol, f64)> = candidates
+        .into_iter()
+        .filter(|sym| {
+            !index.symbols_in_file(&sym.file_path).any(|(_, other)| {
+                other.start_byte <= sym.start_byte
+                    && sym.end_byte <= other.end_byte
+                    && (other.end_byte - other.start_byte) > (sym.end_byte - sym.start_byte)
+            })
+        })
+        .map(|sym| {
+            let usage = 1.0 + (sym.used_by.len() as f64).ln_1p();
+            let weight = match churn {
+                // Unchanged code keeps a little weight so it can still fill the budget
+                Some(churn) => usage * (0.1 + churn.symbol_score(sym)),
+                None => usage,
+            };
+            let coupling = evolving
+                .and_then(|evolving| evolving.get(sym.file_path.trim_start_matches("./")))
+                .copied()
+                .unwrap_or_default();
+            let branching = match complexity {
+                true => 1.0 + (total_complexity(index, sym) as f64).ln(),
+                false => 1.0,
+            };
+            (sym, weight * (1.0 + coupling) * branching)
+        })
+        .collect();
+    candidates.sort_by(|(a, a_weight), (b, b_weight)| {
+        b_weight
+            .total_cmp(a_weight)
+            .then_with(|| (&a.file_path, a.start_byte).cmp(&(&b.file_path, b.start_byte)))
+    });
+
+    let total = candidates.len();
+    let budget_or_max = budget.unwrap_or(usize::MAX);
+    let needed: usize = candidates
+        .iter()
+        .map(|(sym, _)| detail_tokens(index, sym, Detail::Full))
+        .sum();
+    let mut remaining = match needed > budget_or_max {
+        true => (budget_or_max as f64 * FULL_SHARE) as usize,
+        false => budget_or_max,
+    };
+    let mut used = 0;
+    let mut selected: Vec<(&'a Symbol, Detail)> = Vec::new();
+    let mut left_out: Vec<&'a Symbol> = candidates.into_iter().map(|(sym, _)| sym).collect();
+    for detail in [Detail::Full, Detail::Signature, Detail::Name] {
+        left_out.retain(|sym| {
+            let tokens = detail_tokens(index, sym, detail);
+            if tokens > remaining {
+                return true;
+            }
+            debug!("Selected '{}' ({:?}, {} tokens).", sym.name, detail, tokens);
+            remaining -= tokens;
+            used += tokens;
+            selected.push((sym, detail));
+            false
+        });
+        // What the code didn't take is left for the signatures and names
+        remaining = budget_or_max - used;
+    }
+    // Files go after the files they depend on, symbols in the order of their file
+    let mut files: Vec<&str> = selected.iter().map(|(sym, _)| &*sym.file_path).collect();
+    files.sort();
+    files.dedup();
+    let rank: HashMap<&str, usize> = index
+        .dependency_order(&files)
+        .into_iter()
+        .enumerate()
+        .map(|(rank, file)| (file, rank))
+        .collect();
+    selected.sort_by_key(|(sym, _)| (rank[&*sym.file_path], sym.start_byte));
+
+    let full = selected
+        .iter()
+        .filter(|(_, detail)| *detail == Detail::Full)
+        .count();
+    let outlined = match selected.len() - full {
+        0 => String::new(),
+        count => format!(" (and {} more by signature or name)", count),
+    };
+    match budget {
+        Some(budget) => eprintln!(
+            "Selected {} of {} symbol(s){}, ~{} of {} tokens.",
+            full, total, outlined, used, budget
+        ),
+        None => eprintln!("Selected {} symbol(s), ~{} tokens.", full, used),
+    }
+
+    selected
+}
+
+/// Estimated tokens of `sym` in a bundle at `detail`; more than any budget for a
+/// signature if it has none, so it is left for its name.
+fn detail_tokens(index: &Index, sym: &Symbol, detail: Detail) -> usize {
+    match detail {
+        Detail::Full => estimate_tokens(sym.end_byte - sym.start_byte),
+        Detail::Signature if sym.signature.is_empty() => usize::MAX,
+        Detail::Signature => {
+            let summary = doc_summary(sym).map_or(0, |summary| summary.len() + 4);
+            estimate_tokens(sym.signature.len() + summary + 2)
+        }
+        Detail::Name => estimate_tokens(name_entry(index, sym).len() + 3),
+    }
+}
+
+/// The first sentence of `sym`'s doc comment, on one line.
+pub(super) fn doc_summary(sym: &Symbol) -> Option<String> {
+    let paragraph = sym.doc.as_deref()?.trim().split("\n\n").next()?;
+    let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+    // A sentence ends at a period before a capital, unlike `e.g. this`
+    let end = paragraph.match_indices(". ").find(|(end, _)| {
+        paragraph[end + 2..]
+            .chars()
+            .next()
+            .is_some_and(char::is_uppercase)
+    });
+    let summary = match end {
+        Some((end, _)) => paragraph[..=end].to_string(),
+        None => paragraph,
+    };
+    (!summary.is_empty()).then_some(summary)
+}
+
+/// How `sym` is mentioned when only its name is included.
+pub(super) fn name_entry(index: &Index, sym: &Symbol) -> String {
+    format!(
+        "`{}` ({}:{})",
+        qualified_name(index, sym),
+        sym.file_path.trim_start_matches("./"),
+        sym.line_number
+    )
+}
+
+/// The document sections that reference indexed code, each once: a section is left
+/// out if an enclosing section is already included, since it contains it.
+fn related_doc_sections(index: &Index) -> String {
+    let mut related: Vec<&Symbol> = index
+        .symbols
+        .values()
+        .filter(|sym| sym.is_document() && !sym.dependencies.is_empty())
+        .collect();
+    related.sort_by(|a, b| (&a.file_path, a.start_byte).cmp(&(&b.file_path, b.start_byte)));
+
+    let included: HashSet<(&str, usize)> = related
+        .iter()
+        .map(|sym| (&*sym.file_path, sym.start_byte))
+        .collect();
+    let mut out = String::new();
+    for sym in related {
+        let mut ancestor = sym.parent.and_then(|id| index.symbol(id));
+        let mut nested = false;
+        while let Some(parent) = ancestor {
+            if included.contains(&(&*parent.file_path, parent.start_byte)) {
+                nested = true;
+                break;
+            }
+            ancestor = parent.parent.and_then(|id| index.symbol(id));
+        }
+        if nested {
+            continue;
+        }
+
+        match read_source(&sym.file_path) {
+            Ok(content) => {
+                let section = content
+                    .get(sym.start_byte..sym.end_byte)
+                    .unwrap_or_default();
+                out.push_str(&format!(
+                    "# {} ({})\n\n{}\n\n",
+                    sym.file_path,
+                    sym.name,
+                    String::from_utf8_lossy(section).trim_end()
+                ));
+            }
+            Err(e) => eprintln!("Failed to read file '{}': {}. Skipping.", sym.file_path, e),
+        }
+    }
+    out
+}
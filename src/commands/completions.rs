@@ -0,0 +1,93 @@
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+
+use clap::ValueEnum;
+use clap_complete::env::Shells;
+use clap_complete::CompletionCandidate;
+
+use crate::arch::qualified_name;
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::models::ModelProfile;
+
+/// Environment variable through which the shell asks for completions, the
+/// default of `CompleteEnv` in `main`.
+const COMPLETE_VAR: &str = "COMPLETE";
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+/// Prints the script registering completions for `shell`. The script calls back
+/// into contextmesh, so symbol names are completed from the current index.
+pub fn handle_completions(shell: Shell) -> Result<(), ContextMeshError> {
+    let name = shell
+        .to_possible_value()
+        .expect("no shell is skipped")
+        .get_name()
+        .to_string();
+    let shells = Shells::builtins();
+    let completer = shells.completer(&name).expect("every shell is built in");
+    let program = std::env::current_exe()?;
+    completer.write_registration(
+        COMPLETE_VAR,
+        "contextmesh",
+        "contextmesh",
+        &program.to_string_lossy(),
+        &mut std::io::stdout(),
+    )?;
+    Ok(())
+}
+
+/// Names of the indexed symbols starting with `current`: qualified names once it
+/// contains `::`, plain names otherwise.
+pub(super) fn complete_symbol(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(index) = Index::load_index() else {
+        return Vec::new();
+    };
+    let current = current.to_string_lossy();
+    let qualified = current.contains("::");
+    let names: BTreeSet<String> = index
+        .symbols
+        .values()
+        .filter(|sym| sym.is_code() && !sym.name.is_empty())
+        .map(|sym| {
+            if qualified {
+                qualified_name(&index, sym)
+            } else {
+                sym.name.clone()
+            }
+        })
+        .filter(|name| name.starts_with(&*current))
+        .collect();
+    names.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Names of the model profiles, built-in and configured.
+pub(super) fn model_candidates() -> Vec<CompletionCandidate> {
+    let config = Config::load().unwrap_or_default();
+    ModelProfile::names(&config)
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Names of the recipes in the config, with their descriptions.
+pub(super) fn recipe_candidates() -> Vec<CompletionCandidate> {
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    config
+        .recipes
+        .into_iter()
+        .map(|(name, recipe)| {
+            CompletionCandidate::new(name).help(recipe.description.map(Into::into))
+        })
+        .collect()
+}
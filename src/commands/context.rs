@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+use std::fs;
+
+use super::combine::{
+    deliver, doc_summary, name_entry, pack_symbols, warn_over_budget, Exclusions,
+};
+use crate::arch::{glob_matches, qualified_name};
+use crate::build_targets::absolute_label;
+use crate::bundle::{Bundle, Detail, Manifest, ManifestSymbol};
+use crate::cargo::{describe, symbol_gates};
+use crate::cochange::evolving_with;
+use crate::config::{Config, Recipe};
+use crate::errors::ContextMeshError;
+use crate::index::{EdgeConfidence, Index};
+use crate::models::ModelProfile;
+use crate::symbol::Symbol;
+use crate::utils::estimate_tokens;
+
+/// Gathers the symbols and files of a recipe, or of the given `options`, and
+/// copies them to the clipboard. With `from`, only symbols reachable from those
+/// entry symbols (through edges of at least the recipe's `min_confidence`) are
+/// candidates, and with `targets` only those in the files of
+/// those build targets and their direct deps. Options given on the command line
+/// extend the recipe, and their budget or model (whose context window sets the
+/// budget) overrides its own. Symbols of generated and third-party files are
+/// left out unless `include_generated` or `include_third_party` is set. With
+/// `manifest`, what went into the bundle is written there for `replay`.
+pub fn handle_context(
+    recipe: Option<&str>,
+    options: Recipe,
+    include_generated: bool,
+    include_third_party: bool,
+    manifest: Option<&str>,
+) -> Result<(), ContextMeshError> {
+    let config = Config::load()?;
+    let mut query = match recipe {
+        Some(name) => config
+            .recipes
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ContextMeshError::RecipeNotFound(name.to_string()))?,
+        None => Recipe::default(),
+    };
+    query.symbols.extend(options.symbols);
+    query.files.extend(options.files);
+    query.from.extend(options.from);
+    query.min_confidence = options.min_confidence.or(query.min_confidence);
+    query.targets.extend(options.targets);
+    // A budget or model given on the command line beats one of the recipe
+    let (budget, model) = match options.budget.is_some() || options.model.is_some() {
+        true => (options.budget, options.model),
+        false => (query.budget, query.model.take()),
+    };
+    query.budget = match model {
+        Some(name) => Some(ModelProfile::named(&config, &name)?.budget()),
+        None => budget,
+    };
+    query.prefer_complex |= options.prefer_complex;
+
+    let index = Index::load_index()?;
+    let (bundle, included) = assemble(&index, &query, include_generated, include_third_party)?;
+    if let Some(path) = manifest {
+        included.save(path)?;
+    }
+
+    let combined_content = bundle.render();
+    if combined_content.is_empty() {
+        eprintln!("Nothing matched the recipe.");
+        return Ok(());
+    }
+    deliver(&combined_content)
+}
+
+/// The bundle of the symbols and files `query` selects from `index`, and its
+/// manifest; see [`handle_context`].
+pub(super) fn assemble(
+    index: &Index,
+    query: &Recipe,
+    include_generated: bool,
+    include_third_party: bool,
+) -> Result<(Bundle, Manifest), ContextMeshError> {
+    let exclusions = Exclusions::load()?;
+    let mut bundle = Bundle::default();
+    let mut included = Manifest::new();
+    let mut used = 0;
+
+    // Files are asked for explicitly, so they always go in first
+    for path in &query.files {
+        match fs::metadata(path) {
+            Ok(metadata) => {
+                used += estimate_tokens(metadata.len() as usize);
+                bundle.add_file(path);
+                included.files.push(path.clone());
+            }
+            Err(e) => eprintln!("Failed to read file '{}': {}. Skipping.", path, e),
+        }
+    }
+    if let Some(budget) = query.budget {
+        warn_over_budget("The files asked for", used, budget);
+    }
+
+    let from: Vec<String> = query.from.iter().map(|p| normalize_pattern(p)).collect();
+    let roots: Vec<(&String, &Symbol)> = index
+        .symbols
+        .iter()
+        .filter(|(_, sym)| sym.is_code() && matches_any(index, &from, sym))
+        .collect();
+    let reachable = (!query.from.is_empty()).then(|| {
+        let min_confidence = query.min_confidence.unwrap_or(EdgeConfidence::NameOnly);
+        index.reachable_from(roots.iter().map(|(hash, _)| hash.as_str()), min_confidence)
+    });
+    if reachable
+        .as_ref()
+        .is_some_and(|reachable| reachable.is_empty())
+    {
+        eprintln!("No symbol matches --from {}.", query.from.join(", "));
+    }
+    let in_targets = if query.targets.is_empty() {
+        None
+    } else {
+        let mut files = HashSet::new();
+        for label in query.targets.iter().map(|label| absolute_label(label)) {
+            files.extend(
+                index
+                    .target_files(&label)
+                    .ok_or(ContextMeshError::TargetNotFound(label))?,
+            );
+        }
+        Some(files)
+    };
+    let patterns: Vec<String> = query.symbols.iter().map(|p| normalize_pattern(p)).collect();
+    let matched: Vec<&Symbol> = index
+        .symbols
+        .iter()
+        .filter(|(hash, _)| {
+            reachable
+                .as_ref()
+                .is_none_or(|reachable| reachable.contains(*hash))
+        })
+        .map(|(_, sym)| sym)
+        .filter(|sym| {
+            in_targets
+                .as_ref()
+                .is_none_or(|files| files.contains(&*sym.file_path))
+        })
+        .filter(|sym| sym.is_code())
+        .filter(|sym| include_generated || !index.is_generated(sym))
+        .filter(|sym| include_third_party || !index.is_third_party(sym))
+        .filter(|sym| !exclusions.excludes(index, sym))
+        .filter(|sym| !bundle.covers(&sym.file_path, &(sym.start_byte..sym.end_byte)))
+        // Reachable or in-target code is all wanted unless narrowed down
+        .filter(|sym| {
+            ((reachable.is_some() || in_targets.is_some()) && patterns.is_empty())
+                || matches_any(index, &patterns, sym)
+        })
+        .collect();
+    if !matched.is_empty() {
+        let budget = query.budget.map(|budget| budget.saturating_sub(used));
+        // Code evolving with the files and entry symbols asked for ranks higher
+        let anchors: HashSet<&str> = query
+            .files
+            .iter()
+            .map(String::as_str)
+            .chain(roots.iter().map(|(_, sym)| &*sym.file_path))
+            .collect();
+        let evolving = (!index.co_changes.is_empty() && !anchors.is_empty())
+            .then(|| evolving_with(&index.co_changes, &anchors));
+        for (sym, detail) in pack_symbols(
+            index,
+            matched,
+            budget,
+            None,
+            evolving.as_ref(),
+            query.prefer_complex,
+        ) {
+            add_symbol(&mut bundle, index, sym, detail);
+            included.symbols.push(ManifestSymbol {
+                qualified_name: qualified_name(index, sym),
+                kind: sym.node_kind.to_string(),
+                file: sym.file_path.to_string(),
+                line: sym.line_number,
+                body_hash: sym.body_hash.clone(),
+                detail,
+            });
+        }
+    }
+    exclusions.apply(index, &mut bundle);
+    Ok((bundle, included))
+}
+
+/// Includes `detail` of `sym` in `bundle`; its code with a note if only some
+/// builds compile it.
+pub(super) fn add_symbol(bundle: &mut Bundle, index: &Index, sym: &Symbol, detail: Detail) {
+    match detail {
+        Detail::Full => {
+            bundle.add_range(&sym.file_path, sym.start_byte..sym.end_byte);
+            let gates = symbol_gates(index, sym);
+            if !gates.is_empty() {
+                bundle.add_note(format!(
+                    "`{}` ({}:{}) is only compiled {}.",
+                    qualified_name(index, sym),
+                    sym.file_path.trim_start_matches("./"),
+                    sym.line_number,
+                    describe(&gates)
+                ));
+            }
+        }
+        Detail::Signature => {
+            bundle.add_signature(&sym.file_path, &sym.signature, doc_summary(sym).as_deref())
+        }
+        Detail::Name => bundle.add_name(name_entry(index, sym)),
+    }
+}
+
+pub fn handle_list_recipes() -> Result<(), ContextMeshError> {
+    let config = Config::load()?;
+    let mut names: Vec<&String> = config.recipes.keys().collect();
+    names.sort();
+    if names.is_empty() {
+        println!("No recipes configured (see [recipes.<name>] in the config).");
+    }
+    for name in names {
+        match &config.recipes[name].description {
+            Some(description) => println!("{:<24} {}", name, description),
+            None => println!("{}", name),
+        }
+    }
+    Ok(())
+}
+
+/// Whether `sym` matches any of the [`glob_matches`] `patterns`.
+fn matches_any(index: &Index, patterns: &[String], sym: &Symbol) -> bool {
+    let name = qualified_name(index, sym);
+    patterns
+        .iter()
+        .any(|pattern| glob_matches(pattern, &name, sym))
+}
+
+/// Turns the shorthands of [`Recipe::symbols`] into [`glob_matches`] patterns:
+/// `indexer::*` and plain names match at any module depth.
+fn normalize_pattern(pattern: &str) -> String {
+    if pattern == "crate" || pattern.starts_with("crate::") || pattern.contains('/') {
+        pattern.to_string()
+    } else {
+        format!("crate::**::{}", pattern)
+    }
+}
@@ -0,0 +1,405 @@
+//! `contextmesh daemon` keeps the index in memory and runs the commands of
+//! clients connecting to a Unix socket next to the index file.
+//!
+//! A client sends its arguments and working directory as a JSON line, along with
+//! its stdin, stdout, and stderr file descriptors. The daemon forks, so each
+//! command runs in a child with its own copy of the loaded index, writing
+//! straight to the client's terminal. The child answers with its exit code.
+//!
+//! Only the user running the daemon can connect: the socket is private to
+//! them, and connections from other users are refused. Only commands that read
+//! the index are run.
+
+use std::path::{Path, PathBuf};
+
+use crate::errors::ContextMeshError;
+use crate::profile;
+
+/// Where the daemon serving the selected index listens.
+fn socket_path() -> PathBuf {
+    profile::index_path().with_extension("sock")
+}
+
+#[cfg(not(unix))]
+pub fn handle_daemon(_otlp_endpoint: Option<&str>) -> Result<(), ContextMeshError> {
+    Err(ContextMeshError::DaemonError(
+        "The daemon is only supported on Unix".to_string(),
+    ))
+}
+
+#[cfg(not(unix))]
+pub(super) fn forward(_start_dir: &Path) -> Result<bool, ContextMeshError> {
+    Ok(false)
+}
+
+#[cfg(unix)]
+pub use unix::{forward, handle_daemon};
+
+#[cfg(unix)]
+mod unix {
+    use std::ffi::OsString;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::fd::{AsRawFd, RawFd};
+    use std::os::unix::ffi::OsStringExt;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::SystemTime;
+
+    use clap::{CommandFactory, FromArgMatches};
+    use log::{info, warn};
+    use serde::{Deserialize, Serialize};
+    use tracing::{field, info_span};
+
+    use super::*;
+    use crate::commands::{run_command, Cli};
+    use crate::index::Index;
+    use crate::telemetry;
+
+    /// Set in the children of the daemon, which run commands themselves.
+    static SERVING: AtomicBool = AtomicBool::new(false);
+
+    #[derive(Serialize, Deserialize)]
+    struct Request {
+        /// Arguments without the program name, as raw bytes.
+        args: Vec<Vec<u8>>,
+        /// The directory the client was started in.
+        cwd: PathBuf,
+    }
+
+    pub fn handle_daemon(otlp_endpoint: Option<&str>) -> Result<(), ContextMeshError> {
+        let env = std::env::var(telemetry::ENDPOINT_VAR).ok();
+        let otlp_endpoint = telemetry::endpoint(otlp_endpoint, env.as_deref())?;
+        let otlp_endpoint = otlp_endpoint.as_deref();
+        let index_path = profile::index_path();
+        let mut index = Index::load_index()?;
+        let mut loaded_at = modified(&index_path);
+
+        let socket_path = socket_path();
+        if UnixStream::connect(&socket_path).is_ok() {
+            return Err(ContextMeshError::DaemonError(format!(
+                "A daemon is already listening on {}",
+                socket_path.display()
+            )));
+        }
+        // Left behind by a daemon that didn't exit cleanly
+        let _ = std::fs::remove_file(&socket_path);
+        // Only the owner may connect; the mode is set from the start so there is
+        // no window in which others can
+        let umask = unsafe { libc::umask(0o177) };
+        let bound = UnixListener::bind(&socket_path);
+        unsafe { libc::umask(umask) };
+        let listener = bound?;
+        std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))?;
+        let uid = unsafe { libc::getuid() };
+        // Children are never waited for; they report to their client directly
+        unsafe { libc::signal(libc::SIGCHLD, libc::SIG_IGN) };
+        info!(
+            "Serving {} on {}",
+            index_path.display(),
+            socket_path.display()
+        );
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Failed to accept a connection: {}", e);
+                    continue;
+                }
+            };
+            match peer_uid(&stream) {
+                Ok(peer) if peer == uid => {}
+                Ok(peer) => {
+                    warn!("Refusing a connection from user {}", peer);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Refusing a connection of unknown user: {}", e);
+                    continue;
+                }
+            }
+            let (request, fds) = match receive_request(&stream) {
+                Ok(Some(received)) => received,
+                // e.g. another daemon checking whether this one is running
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Ignoring a malformed request: {}", e);
+                    continue;
+                }
+            };
+
+            if modified(&index_path) != loaded_at {
+                match Index::load_index_from(&index_path) {
+                    Ok(reloaded) => {
+                        index = reloaded;
+                        loaded_at = modified(&index_path);
+                    }
+                    Err(e) => warn!("Failed to reload the index, serving the old one: {}", e),
+                }
+            }
+
+            match unsafe { libc::fork() } {
+                -1 => warn!("Failed to fork: {}", std::io::Error::last_os_error()),
+                0 => {
+                    drop(listener);
+                    // Set up before the client's streams replace ours, so problems
+                    // are logged by the daemon
+                    let exporter = otlp_endpoint.and_then(|endpoint| {
+                        telemetry::export_to(endpoint)
+                            .map_err(|e| warn!("Not exporting spans: {}", e))
+                            .ok()
+                    });
+                    let index = std::mem::take(&mut index);
+                    let code = serve(request, &fds, index);
+                    let _ = (&stream).write_all(&code.to_le_bytes());
+                    // Flushed once the client has its answer
+                    drop(exporter);
+                    unsafe { libc::_exit(code) };
+                }
+                _ => {}
+            }
+            for fd in fds {
+                unsafe { libc::close(fd) };
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `request` in a child of the daemon with the client's standard streams.
+    fn serve(request: Request, fds: &[RawFd], index: Index) -> i32 {
+        for (target, &fd) in fds.iter().enumerate() {
+            unsafe {
+                libc::dup2(fd, target as RawFd);
+                libc::close(fd);
+            }
+        }
+        // Commands wait for the processes they run, e.g. git
+        unsafe { libc::signal(libc::SIGCHLD, libc::SIG_DFL) };
+        SERVING.store(true, Ordering::Relaxed);
+        let symbols = index.symbols.len();
+        Index::preload(index);
+
+        let args = std::iter::once(OsString::from("contextmesh"))
+            .chain(request.args.into_iter().map(OsString::from_vec));
+        let parsed = Cli::command()
+            .try_get_matches_from(args)
+            .and_then(|matches| Ok((Cli::from_arg_matches(&matches)?, matches)));
+        let code = match parsed {
+            Err(e) => {
+                let _ = e.print();
+                e.exit_code()
+            }
+            // Clients only forward commands reading the index; anything else
+            // would run with the daemon's rights instead of the client's
+            Ok((cli, _)) if !cli.command.reads_index() => {
+                eprintln!("The daemon only runs commands that read the index");
+                2
+            }
+            Ok((cli, matches)) => {
+                let span = info_span!(
+                    "request",
+                    command = matches.subcommand_name().unwrap_or_default(),
+                    symbols,
+                    exit_code = field::Empty,
+                );
+                let _entered = span.enter();
+                let porcelain = cli.porcelain;
+                let result = std::env::set_current_dir(&request.cwd)
+                    .map_err(ContextMeshError::from)
+                    .and_then(|_| {
+                        catch_unwind(AssertUnwindSafe(|| run_command(cli))).unwrap_or_else(|_| {
+                            Err(ContextMeshError::DaemonError(
+                                "Command panicked".to_string(),
+                            ))
+                        })
+                    });
+                let code = match result {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        e.report(porcelain);
+                        1
+                    }
+                };
+                span.record("exit_code", code);
+                code
+            }
+        };
+        let _ = std::io::stdout().flush();
+        code
+    }
+
+    /// Runs the command of this process in the daemon if one is serving the
+    /// selected index. Returns whether it did; exits with its code if it failed.
+    pub fn forward(start_dir: &Path) -> Result<bool, ContextMeshError> {
+        if SERVING.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        let Ok(mut stream) = UnixStream::connect(socket_path()) else {
+            return Ok(false);
+        };
+
+        let request = Request {
+            args: std::env::args_os()
+                .skip(1)
+                .map(OsString::into_vec)
+                .collect(),
+            cwd: start_dir.to_path_buf(),
+        };
+        let mut line = serde_json::to_vec(&request)
+            .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+        line.push(b'\n');
+        send_with_fds(&stream, &line, &[0, 1, 2])?;
+
+        let mut code = [0; 4];
+        stream.read_exact(&mut code).map_err(|e| {
+            ContextMeshError::DaemonError(format!("The daemon didn't finish the command: {}", e))
+        })?;
+        match i32::from_le_bytes(code) {
+            0 => Ok(true),
+            code => std::process::exit(code),
+        }
+    }
+
+    /// The user ID of the process on the other end of `stream`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn peer_uid(stream: &UnixStream) -> std::io::Result<libc::uid_t> {
+        let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        let result = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                (&mut cred as *mut libc::ucred).cast(),
+                &mut len,
+            )
+        };
+        match result {
+            0 => Ok(cred.uid),
+            _ => Err(std::io::Error::last_os_error()),
+        }
+    }
+
+    /// The user ID of the process on the other end of `stream`.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn peer_uid(stream: &UnixStream) -> std::io::Result<libc::uid_t> {
+        let mut uid = 0;
+        let mut gid = 0;
+        match unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } {
+            0 => Ok(uid),
+            _ => Err(std::io::Error::last_os_error()),
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    /// The request and standard streams sent over `stream`, or `None` if the
+    /// client hung up without sending anything.
+    fn receive_request(
+        stream: &UnixStream,
+    ) -> Result<Option<(Request, Vec<RawFd>)>, ContextMeshError> {
+        let mut buf = vec![0; 64 * 1024];
+        let (len, fds) = receive_with_fds(stream, &mut buf)?;
+        if len == 0 && fds.is_empty() {
+            return Ok(None);
+        }
+        buf.truncate(len);
+        if !buf.ends_with(b"\n") {
+            BufReader::new(stream).read_until(b'\n', &mut buf)?;
+        }
+        let request = serde_json::from_slice(&buf)
+            .map_err(|e| ContextMeshError::DeserializationError(e.to_string()));
+        match request {
+            Ok(request) if fds.len() == 3 => Ok(Some((request, fds))),
+            request => {
+                for fd in fds {
+                    unsafe { libc::close(fd) };
+                }
+                request?;
+                Err(ContextMeshError::DaemonError(
+                    "Expected the client's stdin, stdout, and stderr".to_string(),
+                ))
+            }
+        }
+    }
+
+    /// Sends `data` along with duplicates of `fds` (`SCM_RIGHTS`).
+    fn send_with_fds(
+        stream: &UnixStream,
+        data: &[u8],
+        fds: &[RawFd],
+    ) -> Result<(), ContextMeshError> {
+        let fds_len = std::mem::size_of_val(fds) as u32;
+        let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(fds_len) } as usize];
+        let mut iov = libc::iovec {
+            iov_base: data.as_ptr() as *mut _,
+            iov_len: data.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = control.len() as _;
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len) as _;
+            std::ptr::copy_nonoverlapping(fds.as_ptr(), libc::CMSG_DATA(cmsg).cast(), fds.len());
+        }
+
+        let sent = unsafe { libc::sendmsg(stream.as_raw_fd(), &msg, 0) };
+        if sent < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // Only the first chunk carries the descriptors
+        let mut stream = stream;
+        stream.write_all(&data[sent as usize..])?;
+        Ok(())
+    }
+
+    /// Receives up to `buf.len()` bytes and the file descriptors sent with them.
+    fn receive_with_fds(
+        stream: &UnixStream,
+        buf: &mut [u8],
+    ) -> Result<(usize, Vec<RawFd>), ContextMeshError> {
+        const MAX_FDS: u32 = 3;
+        let fds_len = MAX_FDS * std::mem::size_of::<RawFd>() as u32;
+        let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(fds_len) } as usize];
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = control.len() as _;
+
+        let received = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut msg, 0) };
+        if received < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut fds = Vec::new();
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                    let data_len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                    let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+                    for i in 0..data_len / std::mem::size_of::<RawFd>() {
+                        fds.push(data.add(i).read_unaligned());
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+        Ok((received as usize, fds))
+    }
+}
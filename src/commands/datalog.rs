@@ -0,0 +1,71 @@
+use serde_json::{Map, Value};
+use std::fs;
+
+use super::{to_json, TableFormat};
+use crate::datalog::{Database, Program};
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::Table;
+
+/// Evaluates the rule files and prints `relation`, or else the relations named
+/// by `.output`, or else every derived one; see [`crate::datalog`].
+pub fn handle_datalog(
+    files: &[String],
+    relation: Option<&str>,
+    format: TableFormat,
+) -> Result<(), ContextMeshError> {
+    let mut program = Program::default();
+    for file in files {
+        let source = fs::read_to_string(file)?;
+        program.parse(&source, file)?;
+    }
+    let index = Index::load_index()?;
+    let mut db = Database::from_index(&index);
+    db.evaluate(&program)?;
+
+    let relations: Vec<&str> = match relation {
+        Some(relation) => vec![relation],
+        None if !program.outputs.is_empty() => program.outputs.iter().map(String::as_str).collect(),
+        None => program.derived(),
+    };
+    let mut tables = Vec::new();
+    for relation in relations {
+        let arity = db.arity(relation).ok_or_else(|| {
+            ContextMeshError::RuleError(format!("no relation named '{}'", relation))
+        })?;
+        let columns = program.columns(relation, arity);
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+        let mut table = Table::new(&columns);
+        for tuple in db.tuples(relation) {
+            table.push(tuple.into_iter().map(Value::from).collect());
+        }
+        tables.push((relation, table));
+    }
+
+    match format {
+        TableFormat::Json => {
+            let relations: Map<String, Value> = tables
+                .iter()
+                .map(|(relation, table)| (relation.to_string(), table.to_json()))
+                .collect();
+            println!("{}", to_json(&relations)?);
+        }
+        // CSV sections are separated by an empty line
+        TableFormat::Csv => {
+            let sections: Vec<String> = tables.iter().map(|(_, table)| table.to_csv()).collect();
+            print!("{}", sections.join("\r\n"));
+        }
+        TableFormat::Table => {
+            for (i, (relation, table)) in tables.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("{} ({} tuples)", relation, table.len());
+                if !table.is_empty() {
+                    print!("{}", table.to_text());
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,420 @@
+use clap::ValueEnum;
+use log::info;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+
+use super::api::PackageLookup;
+use super::tree::kind_label;
+use crate::arch::qualified_name;
+use crate::cargo::symbol_gates;
+use crate::cfg::{symbol_cfgs, Cfg, CfgSet};
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::{sorted_edges, sorted_symbols, Table};
+use crate::owners::CodeOwners;
+use crate::scip;
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::{estimate_tokens, project_root, read_source};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Vim-style `tags` file (extended ctags format)
+    Ctags,
+    /// Emacs-style `TAGS` file
+    Etags,
+    /// SCIP index for Sourcegraph and other code intelligence tools
+    Scip,
+    /// `symbols.csv` and `edges.csv` in a directory, for spreadsheets and pandas
+    Csv,
+}
+
+/// Columns `export --format csv` can write to `symbols.csv`.
+pub const SYMBOL_COLUMNS: &[&str] = &[
+    "hash",
+    "name",
+    "qualified_name",
+    "kind",
+    "file",
+    "line",
+    "start_byte",
+    "end_byte",
+    "visibility",
+    "signature",
+    "deps",
+    "users",
+    "tokens",
+    "generated",
+    "third_party",
+    "owners",
+    "last_author",
+    "crate",
+    "features",
+    "cfg",
+    "lines",
+    "complexity",
+    "nesting",
+    "params",
+];
+
+/// The columns written when none are selected.
+pub const DEFAULT_SYMBOL_COLUMNS: &[&str] = &[
+    "hash",
+    "name",
+    "kind",
+    "file",
+    "line",
+    "visibility",
+    "deps",
+    "users",
+    "tokens",
+];
+
+impl ExportFormat {
+    fn default_output(self) -> &'static str {
+        match self {
+            ExportFormat::Ctags => "tags",
+            ExportFormat::Etags => "TAGS",
+            ExportFormat::Scip => "index.scip",
+            ExportFormat::Csv => ".",
+        }
+    }
+}
+
+/// Writes the index in `format` to `output`, or only the symbols of the Cargo
+/// package `krate`, compiled with the cfg set `cfgs`, and of the files in
+/// `scope` if given. `columns` selects the columns of `symbols.csv` for the CSV
+/// format.
+pub fn handle_export(
+    format: ExportFormat,
+    output: Option<&str>,
+    columns: &[String],
+    krate: Option<&str>,
+    cfgs: &[String],
+    scope: &[String],
+) -> Result<(), ContextMeshError> {
+    let cfg_set = (!cfgs.is_empty())
+        .then(|| CfgSet::parse(cfgs))
+        .transpose()?;
+    let mut index = Index::load_index()?;
+    if !scope.is_empty() {
+        index.restrict_to(scope);
+    }
+    let crate_files = krate
+        .map(|krate| files_of_crate(&index, krate))
+        .transpose()?;
+    let in_crate = |path: &str| {
+        crate_files
+            .as_ref()
+            .is_none_or(|files| files.contains(path.trim_start_matches("./")))
+    };
+    // Of the variants of an item behind exclusive cfgs, only the compiled one
+    let compiled = |sym: &Symbol| cfg_set.as_ref().is_none_or(|set| set.enables(&index, sym));
+
+    if format == ExportFormat::Csv {
+        let output = output.unwrap_or(format.default_output());
+        return export_csv(&index, output, columns, &in_crate, &compiled);
+    }
+
+    // Tag names can't contain whitespace, which excludes e.g. `GET /users` endpoints
+    let symbols: Vec<&Symbol> = index
+        .symbols
+        .values()
+        .filter(|sym| sym.is_code() && !sym.name.is_empty())
+        .filter(|sym| !sym.name.contains(char::is_whitespace))
+        .filter(|sym| in_crate(&sym.file_path) && compiled(sym))
+        .collect();
+
+    let contents = match format {
+        ExportFormat::Ctags => ctags(&index, &symbols).into_bytes(),
+        ExportFormat::Etags => etags(&symbols).into_bytes(),
+        ExportFormat::Csv => unreachable!("written above"),
+        ExportFormat::Scip => {
+            let root = project_root()?;
+            let mut packages = PackageLookup::default();
+            let mut scip = scip::from_index(
+                &index,
+                format!("file://{}", root.display()),
+                |path| packages.package_of(path),
+                compiled,
+            );
+            scip.documents
+                .retain(|document| in_crate(&document.relative_path));
+            scip.encode()
+        }
+    };
+
+    match output.unwrap_or(format.default_output()) {
+        "-" => std::io::stdout().write_all(&contents)?,
+        path => {
+            std::fs::write(path, contents)?;
+            info!("Wrote {} symbol(s) to {}", symbols.len(), path);
+        }
+    }
+    Ok(())
+}
+
+/// The paths, without `./`, of the files of the Cargo package `krate`.
+fn files_of_crate<'a>(index: &'a Index, krate: &str) -> Result<HashSet<&'a str>, ContextMeshError> {
+    let files: HashSet<&str> = index
+        .file_crates
+        .iter()
+        .filter(|(_, name)| *name == krate)
+        .map(|(path, _)| path.trim_start_matches("./"))
+        .collect();
+    if files.is_empty() {
+        return Err(ContextMeshError::CrateNotFound(krate.to_string()));
+    }
+    Ok(files)
+}
+
+/// Writes `symbols.csv` with the selected `columns` (the defaults if empty) and
+/// `edges.csv` with one row per dependency, with its confidence, of the symbols
+/// of the files passing `in_crate` into the directory `output`. Symbols not
+/// passing `compiled` are left out of both.
+fn export_csv(
+    index: &Index,
+    output: &str,
+    columns: &[String],
+    in_crate: &dyn Fn(&str) -> bool,
+    compiled: &dyn Fn(&Symbol) -> bool,
+) -> Result<(), ContextMeshError> {
+    if output == "-" {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "CSV export writes symbols.csv and edges.csv; pass a directory with --output",
+        )
+        .into());
+    }
+    let columns: Vec<&str> = if columns.is_empty() {
+        DEFAULT_SYMBOL_COLUMNS.to_vec()
+    } else {
+        columns.iter().map(String::as_str).collect()
+    };
+
+    let codeowners = match columns.contains(&"owners") {
+        true => CodeOwners::load()?,
+        false => CodeOwners::default(),
+    };
+
+    let symbols: Vec<_> = sorted_symbols(index)
+        .into_iter()
+        .filter(|(_, sym)| in_crate(&sym.file_path) && compiled(sym))
+        .collect();
+    let mut symbol_table = Table::new(&columns);
+    for (hash, sym) in &symbols {
+        symbol_table.push(
+            columns
+                .iter()
+                .map(|column| symbol_column(index, &codeowners, hash, sym, column))
+                .collect(),
+        );
+    }
+
+    let mut edge_table = Table::new(&[
+        "source",
+        "target",
+        "source_name",
+        "target_name",
+        "confidence",
+    ]);
+    for (hash, sym) in &symbols {
+        for target in sorted_edges(index, &sym.dependencies) {
+            let dep = index.symbols.get(target);
+            if dep.is_some_and(|dep| !compiled(dep)) {
+                continue;
+            }
+            let target_name = dep.map_or("", |dep| &dep.name);
+            edge_table.push(vec![
+                hash.as_str().into(),
+                target.into(),
+                sym.name.as_str().into(),
+                target_name.into(),
+                index.edge_confidence(hash, target).name().into(),
+            ]);
+        }
+    }
+
+    let dir = Path::new(output);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("symbols.csv"), symbol_table.to_csv())?;
+    std::fs::write(dir.join("edges.csv"), edge_table.to_csv())?;
+    info!(
+        "Wrote {} symbol(s) and {} edge(s) to {}",
+        symbols.len(),
+        edge_table.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// The value of one of [`SYMBOL_COLUMNS`] for `sym`.
+fn symbol_column(
+    index: &Index,
+    codeowners: &CodeOwners,
+    hash: &str,
+    sym: &Symbol,
+    column: &str,
+) -> Value {
+    match column {
+        "hash" => hash.into(),
+        "name" => sym.name.as_str().into(),
+        "qualified_name" => qualified_name(index, sym).into(),
+        "kind" => (&*sym.node_kind).into(),
+        "file" => sym.file_path.trim_start_matches("./").into(),
+        "line" => sym.line_number.into(),
+        "start_byte" => sym.start_byte.into(),
+        "end_byte" => sym.end_byte.into(),
+        "visibility" => match &sym.visibility {
+            Visibility::Private => "private".into(),
+            Visibility::Public => "pub".into(),
+            Visibility::Crate => "pub(crate)".into(),
+            Visibility::Restricted(restriction) => restriction.as_str().into(),
+        },
+        "signature" => sym.signature.as_str().into(),
+        "deps" => sym.dependencies.len().into(),
+        "users" => sym.used_by.len().into(),
+        "tokens" => estimate_tokens(sym.end_byte.saturating_sub(sym.start_byte)).into(),
+        "generated" => index.is_generated(sym).into(),
+        "third_party" => index.is_third_party(sym).into(),
+        "owners" => codeowners.owners_of(&sym.file_path).join(" ").into(),
+        // Recorded by `index --blame`
+        "last_author" => sym
+            .blame
+            .as_ref()
+            .map_or("", |blame| blame.author.as_str())
+            .into(),
+        // Recorded for Cargo workspaces
+        "crate" => index
+            .file_crates
+            .get(&*sym.file_path)
+            .map_or("", String::as_str)
+            .into(),
+        "features" => symbol_gates(index, sym)
+            .into_iter()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .into(),
+        "cfg" => Cfg::all(symbol_cfgs(index, sym))
+            .map_or_else(String::new, |cfg| cfg.to_string())
+            .into(),
+        // Measured for code parsed with tree-sitter
+        "lines" | "complexity" | "nesting" | "params" => match sym.metrics {
+            Some(metrics) => match column {
+                "lines" => metrics.lines,
+                "complexity" => metrics.complexity,
+                "nesting" => metrics.nesting,
+                _ => metrics.parameters,
+            }
+            .into(),
+            None => "".into(),
+        },
+        other => unreachable!("column '{}' is rejected by the CLI", other),
+    }
+}
+
+/// Extended ctags format with line number addresses, sorted by tag name.
+fn ctags(index: &Index, symbols: &[&Symbol]) -> String {
+    let mut lines: Vec<String> = symbols
+        .iter()
+        .map(|sym| {
+            let mut line = format!(
+                "{}\t{}\t{};\"\t{}\tline:{}",
+                sym.name,
+                sym.file_path.trim_start_matches("./"),
+                sym.line_number,
+                kind_letter(&sym.node_kind),
+                sym.line_number
+            );
+            if let Some(parent) = sym.parent.and_then(|id| index.symbol(id)) {
+                let _ = write!(line, "\t{}:{}", kind_label(&parent.node_kind), parent.name);
+            }
+            line
+        })
+        .collect();
+    lines.sort();
+
+    let mut contents = String::from(concat!(
+        "!_TAG_FILE_FORMAT\t2\t/extended format/\n",
+        "!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/\n",
+        "!_TAG_PROGRAM_NAME\tcontextmesh\t//\n",
+    ));
+    for line in lines {
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+    contents
+}
+
+/// Etags format: one section per file listing the definition line, name, line
+/// number, and byte offset of each tag.
+fn etags(symbols: &[&Symbol]) -> String {
+    let mut by_file: BTreeMap<&str, Vec<&Symbol>> = BTreeMap::new();
+    for sym in symbols {
+        by_file.entry(&sym.file_path).or_default().push(sym);
+    }
+
+    let mut contents = String::new();
+    for (path, mut file_symbols) in by_file {
+        file_symbols.sort_by_key(|sym| sym.start_byte);
+        let source = read_source(path).unwrap_or_default();
+
+        let mut section = String::new();
+        for sym in file_symbols {
+            let (line_start, text) = definition_line(&source, sym);
+            let _ = writeln!(
+                section,
+                "{}\x7f{}\x01{},{}",
+                text, sym.name, sym.line_number, line_start
+            );
+        }
+        let _ = write!(
+            contents,
+            "\x0c\n{},{}\n{}",
+            path.trim_start_matches("./"),
+            section.len(),
+            section
+        );
+    }
+    contents
+}
+
+/// Byte offset and text of the line a symbol starts on, falling back to its
+/// signature if the file can't be read.
+fn definition_line(source: &[u8], sym: &Symbol) -> (usize, String) {
+    if sym.start_byte < source.len() {
+        let start = source[..sym.start_byte]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |newline| newline + 1);
+        let end = source[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(source.len(), |newline| start + newline);
+        let text = String::from_utf8_lossy(&source[start..end]);
+        return (start, text.trim_end().to_string());
+    }
+    let signature = sym.signature.lines().next().unwrap_or(&sym.name);
+    (sym.start_byte, signature.to_string())
+}
+
+/// Single-letter ctags kind, following universal-ctags' Rust kinds where they exist.
+fn kind_letter(node_kind: &str) -> char {
+    match node_kind {
+        "function_item" | "function_signature_item" | "method_declaration" => 'f',
+        "struct_item" | "union_item" => 's',
+        "enum_item" => 'g',
+        "enum_variant" => 'e',
+        "field_declaration" => 'm',
+        "trait_item" => 'i',
+        "impl_item" => 'c',
+        "mod_item" => 'n',
+        "const_item" => 'C',
+        "static_item" | "let_declaration" => 'v',
+        "type_item" => 't',
+        "macro_definition" => 'M',
+        other => other.chars().next().unwrap_or('x'),
+    }
+}
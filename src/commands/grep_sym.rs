@@ -0,0 +1,79 @@
+use serde::Serialize;
+
+use super::OutputFormat;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::sorted_symbols;
+
+/// A symbol using string literals that match the pattern.
+#[derive(Serialize)]
+struct LiteralMatch<'a> {
+    name: &'a str,
+    node_kind: &'a str,
+    file_path: &'a str,
+    line_number: usize,
+    literals: Vec<&'a str>,
+}
+
+/// Prints the symbols containing a notable string literal that contains `pattern`
+/// (or equals it, with `exact`), e.g. every symbol reading an environment variable.
+pub fn handle_grep_sym(
+    pattern: &str,
+    exact: bool,
+    format: OutputFormat,
+) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+
+    let matches: Vec<LiteralMatch> = sorted_symbols(&index)
+        .into_iter()
+        .map(|(_, sym)| sym)
+        .filter_map(|sym| {
+            let literals: Vec<&str> = sym
+                .literals
+                .iter()
+                .map(String::as_str)
+                .filter(|literal| match exact {
+                    true => *literal == pattern,
+                    false => literal.contains(pattern),
+                })
+                .collect();
+            (!literals.is_empty()).then(|| LiteralMatch {
+                name: &sym.name,
+                node_kind: &sym.node_kind,
+                file_path: &sym.file_path,
+                line_number: sym.line_number,
+                literals,
+            })
+        })
+        .collect();
+
+    match format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&matches)
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        OutputFormat::Text => {
+            if matches.is_empty() {
+                println!("No symbols use a literal matching \"{}\".", pattern);
+            }
+            for m in &matches {
+                let literals: Vec<String> = m
+                    .literals
+                    .iter()
+                    .map(|literal| format!("{:?}", literal))
+                    .collect();
+                println!(
+                    "{}:{} {} ({}) {}",
+                    m.file_path,
+                    m.line_number,
+                    m.name,
+                    m.node_kind,
+                    literals.join(", ")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use log::{info, warn};
+
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::scip::ScipIndex;
+
+pub fn handle_import(scip_path: &str) -> Result<(), ContextMeshError> {
+    let scip = ScipIndex::decode(&std::fs::read(scip_path)?)?;
+    let config = Config::load()?;
+    let mut index = Index::load_index()?;
+
+    let precise = scip.references_in(&index);
+    if precise.covered_files.is_empty() {
+        warn!(
+            "None of the {} SCIP document(s) is indexed.",
+            scip.documents.len()
+        );
+        return Ok(());
+    }
+    let added = index.apply_precise_references(&precise.references, &precise.covered_files);
+    index.save_index(&config.index)?;
+    info!(
+        "Imported {} SCIP document(s): {} definition(s) matched, {} new edge(s).",
+        precise.covered_files.len(),
+        precise.definitions,
+        added
+    );
+    Ok(())
+}
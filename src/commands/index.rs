@@ -1,35 +1,438 @@
 use log::{error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
+use crate::build_targets;
+use crate::cargo;
+use crate::cfg;
+use crate::cochange;
+use crate::config::{Config, LanguageConfig};
+use crate::detect::LanguageDetector;
 use crate::errors::ContextMeshError;
+use crate::generated;
+use crate::git;
 use crate::index::Index;
-use crate::parser::CodeParser;
-use crate::utils::collect_files;
+use crate::limits;
+use crate::parser::{
+    components, css, docker, document, proto, shell, sql, structured, tags, CodeParser,
+};
+use crate::profile;
+use crate::rust_analyzer;
+use crate::symbol::Blame;
+use crate::third_party;
+use crate::timings;
+use crate::utils::{collect_all_files, collect_files, has_extension, project_root, root_relative};
 
-pub fn handle_index(dir_or_file: &str, language: &str) -> Result<(), ContextMeshError> {
-    ensure_index_directory_exists(".contextmesh")?;
+/// The `--language` value detecting the language of each file instead.
+const AUTO_LANGUAGE: &str = "auto";
+
+pub fn handle_index(
+    dir_or_file: &str,
+    language: &str,
+    blame: bool,
+    co_change: bool,
+    watch: bool,
+) -> Result<(), ContextMeshError> {
+    if let Some(dir) = profile::index_path().parent() {
+        ensure_index_directory_exists(dir)?;
+    }
+    let config = Config::load()?;
     let mut index = load_index()?;
+    // Parsers and their extensions by language, kept across the runs of --watch
+    let mut parsers = HashMap::new();
+    let options = RunOptions {
+        dir_or_file,
+        language,
+        blame,
+        co_change,
+        watch,
+    };
+    index_run(&mut index, &config, &mut parsers, &options)?;
+    if !watch {
+        return Ok(());
+    }
 
-    // Prepare parser
-    let (extensions, mut code_parser) = prepare_parser(language)?;
+    info!("Watching '{}' for changes; stop with Ctrl-C.", dir_or_file);
+    let mut seen = file_states(dir_or_file, &config);
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+        let current = file_states(dir_or_file, &config);
+        if current == seen {
+            continue;
+        }
+        seen = current;
+        if let Err(e) = index_run(&mut index, &config, &mut parsers, &options) {
+            error!("Failed to update the index: {}", e);
+        }
+    }
+}
+
+/// How often `index --watch` looks for changed files.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The arguments of `contextmesh index`.
+struct RunOptions<'a> {
+    dir_or_file: &'a str,
+    language: &'a str,
+    blame: bool,
+    co_change: bool,
+    watch: bool,
+}
 
-    // Gather all candidate files (based on extension)
-    let files = collect_files(dir_or_file, extensions);
+/// Path, modification time, and size of every file under `dir_or_file`, to
+/// tell when a watched project changed.
+fn file_states(dir_or_file: &str, config: &Config) -> Vec<(String, Option<SystemTime>, u64)> {
+    let mut states: Vec<_> = collect_all_files(dir_or_file, config.files.follow_symlinks)
+        .into_iter()
+        .map(|path| {
+            let metadata = fs::metadata(&path).ok();
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            let len = metadata.map_or(0, |m| m.len());
+            (path, modified, len)
+        })
+        .collect();
+    states.sort();
+    states
+}
+
+/// The parser of `language` from `parsers`, created on first use. Parsers of a
+/// watching run keep the syntax trees of the files they parse, so that only the
+/// edited parts of a changed file are parsed again.
+fn language_parser<'a>(
+    index: &mut Index,
+    config: &Config,
+    parsers: &'a mut HashMap<String, (Vec<String>, CodeParser)>,
+    language: &str,
+    watch: bool,
+) -> Result<&'a mut (Vec<String>, CodeParser), ContextMeshError> {
+    let language = language.to_lowercase();
+    if !parsers.contains_key(&language) {
+        let (extensions, mut code_parser) = prepare_language(index, config, &language)?;
+        if watch {
+            code_parser.enable_tree_cache();
+        }
+        parsers.insert(language.clone(), (extensions, code_parser));
+    }
+    Ok(parsers.get_mut(&language).expect("inserted above"))
+}
+
+/// Updates `index` with the files of `options` and saves it.
+fn index_run(
+    index: &mut Index,
+    config: &Config,
+    parsers: &mut HashMap<String, (Vec<String>, CodeParser)>,
+    options: &RunOptions,
+) -> Result<(), ContextMeshError> {
+    let RunOptions {
+        dir_or_file,
+        language,
+        blame,
+        co_change,
+        watch,
+    } = *options;
+    index.begin_run();
+
+    // Group the files indexed by language, each of which gets a parser
+    let timer = timings::start("file walk");
+    let mut runs = Vec::new();
+    if language.eq_ignore_ascii_case(AUTO_LANGUAGE) {
+        let files = collect_all_files(dir_or_file, config.files.follow_symlinks);
+        let mut groups: Vec<_> = LanguageDetector::new(config)
+            .group(files)
+            .into_iter()
+            .collect();
+        groups.sort();
+        // Only the languages found get a parser, created once for all their files
+        for (language, files) in groups {
+            match language_parser(index, config, parsers, &language, watch) {
+                Ok(_) => runs.push((language, files)),
+                Err(e) => warn!("Skipping {} {} file(s): {}", files.len(), language, e),
+            }
+        }
+    } else {
+        let (extensions, _) = language_parser(index, config, parsers, language, watch)?;
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        let files = collect_files(dir_or_file, &extensions, config.files.follow_symlinks);
+        runs.push((language.to_lowercase(), files));
+    }
+    timer.stop(runs.iter().map(|(_, files)| files.len()).sum());
+
+    let forgotten = index.forget_missing_files();
+    if forgotten > 0 {
+        info!("Removed {} file(s) that no longer exist.", forgotten);
+    }
+
+    let root = project_root()?;
+    let mut files = Vec::new();
+    let mut languages = Vec::new();
+    for (language, language_files) in runs {
+        let language_files = language_files
+            .iter()
+            .map(|path| root_relative(path, &root))
+            .collect();
+        let language_files = skip_files(index, language_files, config);
+        let (_, code_parser) = parsers.get_mut(&language).expect("prepared above");
+        index.index_files(&language_files, code_parser)?;
+        files.extend(language_files);
+        languages.push(language);
+    }
+
+    if blame {
+        let timer = timings::start("blame");
+        for file_path in &files {
+            blame_symbols(index, file_path);
+        }
+        timer.stop(files.len());
+    }
 
-    for file_path in files {
-        index.index_file(file_path, &mut code_parser)?;
+    if co_change {
+        timings::time("co-change", || record_co_changes(index));
     }
 
-    index.save_index()?;
+    let timer = timings::start("generated detection");
+    index.generated_files = index
+        .file_hashes
+        .keys()
+        .filter(|path| generated::is_generated(path, &config.generated))
+        .cloned()
+        .collect();
+    timer.stop(index.generated_files.len());
+
+    let files: Vec<&str> = index.file_hashes.keys().map(String::as_str).collect();
+    let timer = timings::start("third-party detection");
+    let third_party_files = third_party::third_party_files(&files, &config.third_party);
+    timer.stop(third_party_files.len());
+    index.file_cfgs = cfg::file_cfgs(&files);
+    index.third_party_files = third_party_files;
+
+    if Path::new("Cargo.toml").is_file() {
+        let timer = timings::start("cargo metadata");
+        record_crates(index);
+        timer.stop(index.file_crates.len());
+    }
+
+    let timer = timings::start("build targets");
+    record_build_targets(index);
+    timer.stop(index.target_deps.len());
+
+    if config.prune.is_enabled() {
+        let pruned = timings::time("prune", || index.prune(&config.prune));
+        if !pruned.is_empty() {
+            info!(
+                "Pruned {} doc comment(s) and {} symbol(s).",
+                pruned.docs,
+                pruned.excluded + pruned.capped
+            );
+        }
+    }
+
+    let timer = timings::start("ffi linking");
+    let linked = index.link_foreign_names(&config.aliases);
+    timer.stop(linked);
+    if linked > 0 {
+        info!(
+            "Linked {} declaration(s) to FFI bindings in another language.",
+            linked
+        );
+    }
+
+    // Forward references to files indexed later in the run are only resolvable now
+    let fixed = index.recheck_unresolved();
+    if fixed > 0 {
+        info!("Resolved {} previously unresolved reference(s).", fixed);
+    }
+
+    if languages.iter().any(|language| language == "rust") && config.language("rust").rust_analyzer
+    {
+        timings::time("rust-analyzer", || {
+            resolve_with_rust_analyzer(index, dir_or_file)
+        });
+    }
+
+    let timer = timings::start("reachability");
+    let entry_points = index.mark_reachable(&config.entry_points);
+    timer.stop(index.reachable.len());
+    if entry_points > 0 {
+        info!(
+            "{} of {} symbol(s) are reachable from {} entry point(s).",
+            index.reachable.len(),
+            index.symbols.len(),
+            entry_points
+        );
+    }
+
+    if !index.failed_files.is_empty() {
+        warn!(
+            "{} file(s) could not be indexed. Run `contextmesh stats --errors` for details.",
+            index.failed_files.len()
+        );
+    }
+
+    for language in &languages {
+        index.metadata.touch(language);
+    }
+    let timer = timings::start("save");
+    index.save_index(&config.index)?;
+    timer.stop(index.symbols.len());
 
     info!("Index updated successfully.");
 
     Ok(())
 }
 
-fn ensure_index_directory_exists(path: &str) -> Result<(), ContextMeshError> {
-    if !std::path::Path::new(path).exists() {
+/// Replaces the co-change edges of the index with those of the indexed files in
+/// git history. Without git, the previous edges are kept.
+fn record_co_changes(index: &mut Index) {
+    let mut co_changes = match cochange::from_git() {
+        Ok(co_changes) => co_changes,
+        Err(e) => {
+            warn!("Failed to analyze co-changes: {}", e);
+            return;
+        }
+    };
+    let indexed: HashSet<&str> = index
+        .file_hashes
+        .keys()
+        .map(|path| path.trim_start_matches("./"))
+        .collect();
+    co_changes.retain(|path, edges| {
+        edges.retain(|(other, _)| indexed.contains(other.as_str()));
+        indexed.contains(path.as_str()) && !edges.is_empty()
+    });
+    info!(
+        "Recorded co-changes of {} file(s) from git history.",
+        co_changes.len()
+    );
+    index.co_changes = co_changes;
+}
+
+/// Replaces the owning Cargo packages of the indexed files. Without cargo, the
+/// previous packages are kept.
+fn record_crates(index: &mut Index) {
+    let packages = match cargo::workspace_packages() {
+        Ok(packages) => packages,
+        Err(e) => {
+            warn!("Failed to read the Cargo workspace: {}", e);
+            return;
+        }
+    };
+    index.file_crates = index
+        .file_hashes
+        .keys()
+        .filter_map(|path| {
+            let package = cargo::package_of(&packages, path)?;
+            Some((path.clone(), package.name.clone()))
+        })
+        .collect();
+}
+
+/// Replaces the build targets of the indexed files with those declared in the
+/// project's Bazel or Buck build files.
+fn record_build_targets(index: &mut Index) {
+    let targets = match build_targets::load(Path::new(".")) {
+        Ok(targets) => targets,
+        Err(e) => {
+            warn!("Failed to read build files: {}", e);
+            return;
+        }
+    };
+    index.target_deps = targets
+        .iter()
+        .map(|target| (target.label.clone(), target.deps.clone()))
+        .collect();
+    index.file_targets = index
+        .file_hashes
+        .keys()
+        .filter_map(|path| {
+            let labels: Vec<String> = targets
+                .iter()
+                .filter(|target| target.owns(path))
+                .map(|target| target.label.clone())
+                .collect();
+            (!labels.is_empty()).then(|| (path.clone(), labels))
+        })
+        .collect();
+    if !targets.is_empty() {
+        info!(
+            "Attached {} file(s) to {} build target(s).",
+            index.file_targets.len(),
+            targets.len()
+        );
+    }
+}
+
+/// Records the last change to each symbol of `file_path` from `git blame`. Files
+/// whose symbols were all blamed on committed lines already are skipped, since
+/// their content (and so their blame) hasn't changed.
+fn blame_symbols(index: &mut Index, file_path: &str) {
+    let hashes: Vec<String> = index
+        .symbols_in_file(file_path)
+        .filter(|(_, sym)| sym.blame.as_ref().is_none_or(Blame::is_uncommitted))
+        .map(|(hash, _)| hash.clone())
+        .collect();
+    if hashes.is_empty() {
+        return;
+    }
+
+    let lines = match git::blame(Path::new(file_path)) {
+        Ok(lines) => lines,
+        Err(e) => {
+            warn!("Failed to blame '{}': {}", file_path, e);
+            return;
+        }
+    };
+    let line_starts: Vec<usize> = lines
+        .iter()
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len;
+            Some(start)
+        })
+        .collect();
+
+    for hash in hashes {
+        let Some(sym) = index.symbols.get_mut(&hash) else {
+            continue;
+        };
+        // Lines overlapping the symbol's byte range
+        let first = line_starts.partition_point(|&start| start <= sym.start_byte);
+        let last = line_starts.partition_point(|&start| start < sym.end_byte);
+        sym.blame = lines[first.saturating_sub(1)..last]
+            .iter()
+            .max_by_key(|line| line.time)
+            .map(|line| Blame {
+                commit: line.commit.clone(),
+                author: line.author.clone(),
+                time: line.time,
+            });
+    }
+}
+
+/// Replaces the name-based edges of the Rust symbols with rust-analyzer's. Keeps
+/// them, with a warning, if rust-analyzer isn't available.
+fn resolve_with_rust_analyzer(index: &mut Index, dir_or_file: &str) {
+    let output = profile::index_path().with_file_name("rust-analyzer.scip");
+    match rust_analyzer::scip_index(Path::new(dir_or_file), &output) {
+        Ok(scip) => {
+            let precise = scip.references_in(index);
+            let added = index.apply_precise_references(&precise.references, &precise.covered_files);
+            info!(
+                "Resolved references of {} file(s) with rust-analyzer ({} new edge(s)).",
+                precise.covered_files.len(),
+                added
+            );
+        }
+        Err(e) => warn!("{}; keeping name-based resolution.", e),
+    }
+}
+
+fn ensure_index_directory_exists(path: &Path) -> Result<(), ContextMeshError> {
+    if !path.exists() {
         std::fs::create_dir_all(path)?;
-        info!("Created directory: {}", path);
+        info!("Created directory: {}", path.display());
     }
     Ok(())
 }
@@ -47,27 +450,185 @@ fn load_index() -> Result<Index, ContextMeshError> {
 
 fn prepare_parser(
     language: &str,
-) -> Result<(&'static [&'static str], CodeParser), ContextMeshError> {
-    // Initialize code parser
-    let code_parser = match language.to_lowercase().as_str() {
-        "rust" => CodeParser::new_rust().map_err(|e| {
-            error!(
-                "Failed to initialize CodeParser for language '{}': {}",
-                language, e
-            );
-            e
-        })?,
-        _ => {
-            error!("Unsupported language: {}", language);
-            return Err(ContextMeshError::UnsupportedLanguage(language.to_string()));
+    config: &LanguageConfig,
+) -> Result<(Vec<String>, CodeParser), ContextMeshError> {
+    // Query files take precedence, so they can also replace a built-in indexer
+    if let Some(code_parser) = CodeParser::new_query(language, config)? {
+        return Ok((configured_extensions(language, config)?, code_parser));
+    }
+
+    match language.to_lowercase().as_str() {
+        "rust" => {
+            let code_parser = CodeParser::new_rust().map_err(|e| {
+                error!(
+                    "Failed to initialize CodeParser for language '{}': {}",
+                    language, e
+                );
+                e
+            })?;
+            Ok((vec!["rs".to_string()], code_parser))
         }
-    };
+        "markdown" | "docs" => {
+            let extensions = document::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_document()))
+        }
+        "config" => {
+            let extensions = structured::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_structured()))
+        }
+        "sql" => {
+            let extensions = sql::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_sql()))
+        }
+        "proto" | "protobuf" => {
+            let extensions = proto::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_proto()))
+        }
+        "shell" | "bash" | "sh" => {
+            let extensions = shell::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_shell()))
+        }
+        "docker" | "dockerfile" => {
+            let extensions = docker::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_docker()))
+        }
+        "jsx" | "tsx" | "react" | "html" => {
+            let extensions = components::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_components()))
+        }
+        "css" | "scss" | "less" => {
+            let extensions = css::EXTENSIONS.iter().map(|ext| ext.to_string());
+            Ok((extensions.collect(), CodeParser::new_css()))
+        }
+        // The Zig grammar isn't bundled; without a configured one, only definitions
+        // are indexed
+        "zig" => match CodeParser::new_zig(config)? {
+            Some(code_parser) => Ok((configured_extensions(language, config)?, code_parser)),
+            None => prepare_tags_parser(language, config),
+        },
+        "elixir" => Ok((
+            configured_extensions(language, config)?,
+            CodeParser::new_elixir(config)?,
+        )),
+        // Languages without a built-in indexer may have an external one configured
+        _ => match CodeParser::new_external(language, config) {
+            Some(code_parser) => Ok((configured_extensions(language, config)?, code_parser)),
+            None => prepare_tags_parser(language, config),
+        },
+    }
+}
+
+/// The files to index out of `files`, recording the others as skipped for their
+/// size or content (see [`limits`]).
+fn skip_files(index: &mut Index, files: Vec<String>, config: &Config) -> Vec<String> {
+    let mut kept = Vec::new();
+    for file in files {
+        match limits::skip_reason(&file, &config.files) {
+            Some(reason) => index.skip_file(&file, reason),
+            None => {
+                index.metadata.skipped_files.remove(&file);
+                kept.push(file);
+            }
+        }
+    }
+    kept
+}
+
+/// Creates the parser of `language` with its configured settings, returning it
+/// with the file extensions it indexes.
+pub(super) fn configured_parser(
+    config: &Config,
+    language: &str,
+) -> Result<(Vec<String>, CodeParser), ContextMeshError> {
+    let language_config = config.language(language);
+    let (extensions, mut code_parser) = prepare_parser(language, &language_config)?;
+    code_parser.configure(&language_config);
+    Ok((extensions, code_parser))
+}
+
+/// [`configured_parser`] for indexing into `index`. If the configured symbol
+/// kinds changed since the last run, all files are re-indexed.
+fn prepare_language(
+    index: &mut Index,
+    config: &Config,
+    language: &str,
+) -> Result<(Vec<String>, CodeParser), ContextMeshError> {
+    let (extensions, code_parser) = configured_parser(config, language)?;
+
+    // Symbols of unchanged files were collected with the previous kinds; re-parse
+    // the language's files if the configured kinds changed since
+    let language_key = language.to_lowercase();
+    let kinds = code_parser.definition_kinds();
+    if index
+        .metadata
+        .definition_kinds
+        .get(&language_key)
+        .is_some_and(|previous| previous != kinds)
+    {
+        info!(
+            "Indexed {} symbol kinds changed; re-indexing its files.",
+            language_key
+        );
+        let detector = LanguageDetector::new(config);
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        index.file_hashes.retain(|path, _| {
+            let file_name = Path::new(path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            !has_extension(file_name, &extensions)
+                && detector.detect(path).as_deref() != Some(&language_key)
+        });
+    }
+    index
+        .metadata
+        .definition_kinds
+        .insert(language_key, kinds.clone());
+    Ok((extensions, code_parser))
+}
 
-    // Determine extensions
-    let extensions = match language.to_lowercase().as_str() {
-        "rust" => &["rs"],
-        _ => return Err(ContextMeshError::UnsupportedLanguage(language.to_string())),
+/// Falls back to indexing only the definitions of a language without a parser,
+/// if its file extensions are configured or well known.
+fn prepare_tags_parser(
+    language: &str,
+    config: &LanguageConfig,
+) -> Result<(Vec<String>, CodeParser), ContextMeshError> {
+    let extensions = if config.extensions.is_empty() {
+        match tags::known_extensions(language) {
+            Some(extensions) => extensions.iter().map(|ext| ext.to_string()).collect(),
+            None => {
+                error!("Unsupported language: {}", language);
+                return Err(ContextMeshError::UnsupportedLanguage(language.to_string()));
+            }
+        }
+    } else {
+        config.extensions.clone()
     };
 
+    let code_parser = CodeParser::new_tags(language, config);
+    warn!(
+        "No parser for {}; indexing definitions only, with {}. References between \
+         its symbols won't be known.",
+        language,
+        code_parser.low_fidelity_source().unwrap_or_default()
+    );
     Ok((extensions, code_parser))
 }
+
+/// The file extensions of a language configured in the config file, or else
+/// those of a well-known language.
+fn configured_extensions(
+    language: &str,
+    config: &LanguageConfig,
+) -> Result<Vec<String>, ContextMeshError> {
+    if config.extensions.is_empty() {
+        if let Some(extensions) = tags::known_extensions(language) {
+            return Ok(extensions.iter().map(|ext| ext.to_string()).collect());
+        }
+        return Err(ContextMeshError::ConfigError(format!(
+            "[languages.{}] needs `extensions` to know which files to index.",
+            language.to_lowercase()
+        )));
+    }
+    Ok(config.extensions.clone())
+}
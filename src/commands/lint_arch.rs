@@ -0,0 +1,71 @@
+use super::ReportFormat;
+use crate::arch;
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::sarif::{self, Finding, Rule};
+
+pub fn handle_lint_arch(format: ReportFormat) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+    let config = Config::load()?;
+    let violations = arch::violations(&index, &config.architecture)?;
+
+    match format {
+        ReportFormat::Json => {
+            let json = serde_json::to_string_pretty(&violations)
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        ReportFormat::Sarif => {
+            let rules = [Rule {
+                id: "architecture",
+                description: "Dependency breaking the [architecture] rules of the config",
+                level: "error",
+            }];
+            let findings: Vec<Finding> = violations
+                .iter()
+                .map(|violation| Finding {
+                    rule_id: "architecture",
+                    message: format!(
+                        "{} depends on {}: {}",
+                        violation.from, violation.to, violation.reason
+                    ),
+                    file_path: violation.file_path.clone(),
+                    line_number: violation.line_number,
+                    related: Some((
+                        violation.to_file_path.clone(),
+                        violation.to_line_number,
+                        violation.to.clone(),
+                    )),
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&sarif::report(&rules, &findings))
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        ReportFormat::Text => {
+            if config.architecture.layers.is_empty() && config.architecture.deny.is_empty() {
+                println!("No architecture rules configured (see [architecture] in the config).");
+            } else if violations.is_empty() {
+                println!("No architecture violations.");
+            }
+            for violation in &violations {
+                println!(
+                    "{}:{}: {} -> {} ({}:{}): {}",
+                    violation.file_path,
+                    violation.line_number,
+                    violation.from,
+                    violation.to,
+                    violation.to_file_path,
+                    violation.to_line_number,
+                    violation.reason
+                );
+            }
+        }
+    }
+
+    if !violations.is_empty() {
+        return Err(ContextMeshError::CheckFailed(violations.len()));
+    }
+    Ok(())
+}
@@ -1,9 +1,46 @@
+mod api;
+mod ask;
+mod bench;
+mod changed;
+mod check;
 mod combine;
+mod completions;
+mod context;
+mod daemon;
+mod datalog;
+mod export;
+mod grep_sym;
+mod import;
 mod index;
+mod lint_arch;
+mod owners;
+mod pipe;
 mod print_index;
+mod prune;
+mod query;
+mod related;
+mod remote;
+mod replay;
+mod search;
+mod snapshot;
+mod stats;
+mod todos;
+mod tree;
+mod tui;
+mod verify;
 
+use crate::config::Recipe;
 use crate::errors::ContextMeshError;
-use clap::{Parser, Subcommand};
+use crate::index::EdgeConfidence;
+use crate::output::Table;
+use crate::profile;
+use crate::sink::{self, Sink};
+use crate::timings;
+use clap::builder::PossibleValuesParser;
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCandidates, ArgValueCompleter};
+use completions::Shell;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "contextmesh")]
@@ -11,6 +48,89 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Use the named index in `.contextmesh/profiles/` instead of the default one
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// Read and write the index at this path instead of the profile's
+    #[arg(
+        long,
+        global = true,
+        env = "CONTEXTMESH_INDEX_PATH",
+        conflicts_with = "profile"
+    )]
+    pub index_path: Option<PathBuf>,
+    /// Run as if started in this directory, the root of the project
+    #[arg(long, global = true)]
+    pub root: Option<PathBuf>,
+    /// Print errors as JSON lines with a stable code, for scripts and editors
+    #[arg(long, global = true)]
+    pub porcelain: bool,
+    /// Send bundles to the first of these that works, instead of those of
+    /// `[output]`; may be repeated, e.g. `--sink osc52 --sink stdout`
+    #[arg(long, global = true, value_enum, value_delimiter = ',')]
+    pub sink: Vec<Sink>,
+    /// Write bundles to this file; the only sink unless `--sink` names others
+    #[arg(long, global = true)]
+    pub output_file: Option<PathBuf>,
+    /// Print how long each phase of the command took to stderr, as a table or
+    /// with `--timings=json` as JSON
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    pub timings: Option<OutputFormat>,
+}
+
+/// How commands that support machine-readable output print their results.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How commands listing rows of results, e.g. symbols, print them.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Aligned columns
+    #[default]
+    Table,
+    /// An array of objects keyed by column
+    Json,
+    Csv,
+}
+
+impl TableFormat {
+    /// Prints `table`, or `empty` instead of a table without rows.
+    fn print(self, table: &Table, empty: &str) -> Result<(), ContextMeshError> {
+        match self {
+            TableFormat::Table if table.is_empty() => println!("{}", empty),
+            TableFormat::Table => print!("{}", table.to_text()),
+            TableFormat::Json => println!("{}", to_json(&table.to_json())?),
+            TableFormat::Csv => print!("{}", table.to_csv()),
+        }
+        Ok(())
+    }
+}
+
+/// `value` as pretty-printed JSON.
+fn to_json(value: &impl serde::Serialize) -> Result<String, ContextMeshError> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| ContextMeshError::SerializationError(e.to_string()))
+}
+
+/// How commands reporting findings print them.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Json,
+    /// SARIF 2.1.0, for code scanning UIs
+    Sarif,
 }
 
 #[derive(Subcommand)]
@@ -18,17 +138,605 @@ pub enum Commands {
     Index {
         #[arg(short, long, default_value = "./src")]
         file: String,
+        /// The language to index, or `auto` to detect each file's language
         #[arg(short, long, default_value = "rust")]
         language: String,
+        /// Record the last commit, author, and date of each symbol from git blame
+        #[arg(long)]
+        blame: bool,
+        /// Record which files evolve together from git history
+        #[arg(long)]
+        co_change: bool,
+        /// Keep running and update the index whenever a file changes, re-parsing
+        /// only the edited parts of changed files
+        #[arg(long)]
+        watch: bool,
+    },
+    #[command(group(ArgGroup::new("limit").args(["budget", "model"])))]
+    Combine {
+        /// Also include document sections that refer to the indexed code
+        #[arg(long)]
+        docs: bool,
+        /// Include only the most relevant symbols that fit in this many tokens
+        /// instead of whole files
+        #[arg(long)]
+        budget: Option<usize>,
+        /// Like --budget, with the budget the context window of this model
+        /// leaves, e.g. `claude-sonnet` or `gpt-4o-mini` (see `[models]`)
+        #[arg(long, add = ArgValueCandidates::new(completions::model_candidates))]
+        model: Option<String>,
+        /// With --budget or --model, prefer symbols that changed often and
+        /// recently in git
+        #[arg(long, requires = "limit")]
+        churn: bool,
+        /// With --budget or --model, prefer symbols with more branching, whose
+        /// behavior their signature tells the least about
+        #[arg(long, requires = "limit")]
+        complexity: bool,
+        /// Also include generated files
+        #[arg(long)]
+        include_generated: bool,
+        /// Also include vendored and other third-party files
+        #[arg(long)]
+        include_third_party: bool,
+        /// Only include files matching this glob, e.g. `src/indexer` or
+        /// `src/**/*.rs`; may be repeated
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+        /// Leave out files matching this glob, e.g. `**/tests`, even if
+        /// included; may be repeated
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+        /// Pick the symbols and files to include in a fuzzy finder
+        #[arg(short, long, conflicts_with = "limit")]
+        interactive: bool,
+    },
+    /// Copies the symbols and files selected by a recipe from the config, or by
+    /// the given options, to the clipboard
+    Context {
+        /// Name of a `[recipes.<name>]` section of the config
+        #[arg(long, conflicts_with = "list", add = ArgValueCandidates::new(completions::recipe_candidates))]
+        recipe: Option<String>,
+        /// Symbols to include, e.g. `crate::index::**`, `indexer::*`, or a name
+        #[arg(long = "symbol", add = ArgValueCompleter::new(completions::complete_symbol))]
+        symbols: Vec<String>,
+        /// Files to include whole
+        #[arg(long = "file")]
+        files: Vec<String>,
+        /// Only include code reachable from this entry symbol, e.g. `main`; all
+        /// of it if no `--symbol` is given
+        #[arg(long, add = ArgValueCompleter::new(completions::complete_symbol))]
+        from: Vec<String>,
+        /// Only follow dependencies from the entry symbols resolved at least this
+        /// surely, e.g. `imported` to skip edges guessed from a name alone
+        #[arg(long, value_enum)]
+        min_confidence: Option<EdgeConfidence>,
+        /// Only include code of this Bazel or Buck target and its direct deps,
+        /// e.g. `//services/foo:lib`; all of it if no `--symbol` is given
+        #[arg(long = "target")]
+        targets: Vec<String>,
+        /// Token budget, overriding the recipe's
+        #[arg(long)]
+        budget: Option<usize>,
+        /// Take the budget from the context window of this model, overriding
+        /// the recipe's
+        #[arg(long, conflicts_with = "budget", add = ArgValueCandidates::new(completions::model_candidates))]
+        model: Option<String>,
+        /// Within the budget, prefer symbols with more branching
+        #[arg(long)]
+        prefer_complex: bool,
+        /// Also include symbols of generated files
+        #[arg(long)]
+        include_generated: bool,
+        /// Also include symbols of vendored and other third-party files
+        #[arg(long)]
+        include_third_party: bool,
+        /// Write what went into the bundle to this JSON file, for `replay`
+        #[arg(long)]
+        manifest: Option<String>,
+        /// List the configured recipes
+        #[arg(long)]
+        list: bool,
+    },
+    /// Assembles the bundle recorded in a manifest (see `context --manifest`)
+    /// from the current code and reports the symbols that changed or
+    /// disappeared since, then copies it to the clipboard
+    Replay {
+        manifest: String,
+    },
+    /// Asks the configured LLM (see `[llm]` in the config) a question about the
+    /// code, sending the most relevant symbols along, and prints the answer
+    /// with the symbols it cites
+    Ask {
+        question: String,
+        /// Token budget of the code sent, overriding the config's
+        #[arg(long)]
+        budget: Option<usize>,
+        /// Print the prompt instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Answers `search`, `context`, and `show` requests given as JSON lines on
+    /// stdin with JSON lines on stdout, for agents keeping one process around
+    Pipe,
+    /// Browses the index interactively and collects symbols into a bundle, which
+    /// is copied to the clipboard on exit
+    Tui {
+        /// Write the bundle to this file instead
+        #[arg(short, long)]
+        output: Option<String>,
     },
-    Combine,
     PrintIndex,
+    Stats {
+        /// List files that failed to index and why
+        #[arg(long)]
+        errors: bool,
+        /// List the N (default 20) most complex symbols, by approximate
+        /// cyclomatic complexity
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "20")]
+        hotspots: Option<usize>,
+        /// Only look at files matching this glob or under this directory, e.g.
+        /// `src/parser`
+        #[arg(long)]
+        scope: Vec<String>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Table)]
+        format: TableFormat,
+    },
+    /// Lists the symbols selected by a query, e.g.
+    /// `users(depth<=2) of name:save_index and not file:tests/**`
+    ///
+    /// Predicates: `kind:<node kind>` (`_item` may be left out), `file:<glob>`,
+    /// `name:<name>` (`*` matches anything) or `name:/<regex>/`,
+    /// `vis:pub|crate|private|restricted`, and `target:<package>[:<name>]` (a
+    /// Bazel or Buck target and its direct deps). Combine them with `and` (or nothing),
+    /// `or`, `not`, and parentheses. `users of <query>` and `deps of <query>`
+    /// follow the graph one hop, or as far as `(depth<=N)`, `(depth=N)`, or `(*)`
+    /// allow. `(confidence>=imported)` or `(*, confidence>=exact)` skip edges that
+    /// are less sure.
+    Query {
+        expression: String,
+        /// Only symbols compiled with this cfg set, given like `rustc --cfg`:
+        /// `--cfg unix --cfg 'feature="otel"'`
+        #[arg(long = "cfg")]
+        cfgs: Vec<String>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Table)]
+        format: TableFormat,
+    },
+    /// Evaluates Datalog rule files over the symbol graph and prints the derived
+    /// relations, e.g. `reaches(X, Z) :- reaches(X, Y), depends(Y, Z).`
+    ///
+    /// Base relations, over qualified names: `symbol(S)`, `name(S, N)`,
+    /// `kind(S, K)`, `file(S, F)`, `module(S, M)`, `visibility(S, V)`,
+    /// `attribute(S, A)`, `parent(S, P)`, `depends(S, T)`, `confidence(S, T, C)`
+    /// (`name-only`, `imported`, or `exact`), `generated(S)`, `third_party(S)`,
+    /// `unresolved(S, N)`, `entry_point(S)`, `reachable(S)`, and `evolves_with(F, G)`
+    /// over files. Bodies may also use `!rel(...)`, `X = Y`, `X != Y`,
+    /// `match("<regex>", X)`, and `contains("<text>", X)`.
+    /// `.decl` names columns and `.output` picks the relations to print.
+    Datalog {
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Print this relation, base or derived, instead of the `.output` ones
+        #[arg(long)]
+        relation: Option<String>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Table)]
+        format: TableFormat,
+    },
+    /// Ranks the symbols related to a symbol by graph proximity, shared
+    /// dependencies, co-change in git history, and name similarity
+    Related {
+        /// Name or qualified name, e.g. `crate::index::Index::save_index`
+        #[arg(add = ArgValueCompleter::new(completions::complete_symbol))]
+        symbol: String,
+        /// Most symbols listed
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Leave git history out of the ranking
+        #[arg(long)]
+        no_git: bool,
+        #[arg(long, value_enum, default_value_t = TableFormat::Table)]
+        format: TableFormat,
+    },
+    /// Lists who owns a file, directory, or symbol: the owners CODEOWNERS
+    /// assigns and the authors of its lines according to git blame
+    Owners {
+        /// File or directory path, or a symbol's name or qualified name
+        target: String,
+        #[arg(long, value_enum, default_value_t = TableFormat::Table)]
+        format: TableFormat,
+    },
+    /// Lists the symbols whose name contains the given text, or whose qualified
+    /// name matches a module glob such as `crate::index::**`
+    Search {
+        pattern: String,
+        /// Only symbols of this node kind, e.g. `function_item`
+        #[arg(long)]
+        kind: Option<String>,
+        /// Only look at files matching this glob or under this directory, e.g.
+        /// `src/parser`
+        #[arg(long)]
+        scope: Vec<String>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Table)]
+        format: TableFormat,
+    },
+    /// Lists symbols added, modified, or removed by the last index run or since a git revision
+    Changed {
+        /// Compare against this git revision instead of the previous index run
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Prints the public API (signatures and docs) of the indexed code
+    Api {
+        /// Only include items of this Cargo package
+        #[arg(long)]
+        package: Option<String>,
+        #[arg(long, value_enum, default_value_t = api::ApiFormat::Markdown)]
+        format: api::ApiFormat,
+    },
+    /// Prints an outline of the symbols in a file, directory, or module with token counts
+    Tree {
+        /// File path, directory, or module path (e.g. `parser` or `crate::index::stored`)
+        target: Option<String>,
+    },
+    /// Lists the symbols using string literals (routes, env var names, error codes,
+    /// config keys) that contain the given text
+    GrepSym {
+        literal: String,
+        /// Only match literals equal to the given text
+        #[arg(long)]
+        exact: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Lists TODO/FIXME/HACK comments with the symbols containing them
+    Todos {
+        /// Only comments inside this symbol (by name), including its children
+        #[arg(long, add = ArgValueCompleter::new(completions::complete_symbol))]
+        symbol: Option<String>,
+        /// Only comments in this file, directory, or module
+        #[arg(long)]
+        file: Option<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Reports dependency rule violations from the config and, with --baseline,
+    /// unresolved references that are new since a snapshot
+    Check {
+        /// Snapshot (see `snapshot save`) whose unresolved references are accepted
+        #[arg(long)]
+        baseline: Option<String>,
+        /// Exit with an error if any problem is found
+        #[arg(long)]
+        ci: bool,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+    /// Reports dependency edges that break the `[architecture]` rules of the config
+    LintArch {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+    /// Writes the indexed symbols in a format other tools read
+    Export {
+        #[arg(long, value_enum)]
+        format: export::ExportFormat,
+        /// File to write, or `-` for stdout; defaults to `tags`/`TAGS`. For CSV,
+        /// the directory to write `symbols.csv` and `edges.csv` to
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Comma-separated columns of `symbols.csv`
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = PossibleValuesParser::new(export::SYMBOL_COLUMNS),
+        )]
+        columns: Vec<String>,
+        /// Only export the symbols of this Cargo package
+        #[arg(long = "crate")]
+        krate: Option<String>,
+        /// Only export the symbols compiled with this cfg set, given like
+        /// `rustc --cfg`: `--cfg unix --cfg 'feature="otel"'`
+        #[arg(long = "cfg")]
+        cfgs: Vec<String>,
+        /// Only look at files matching this glob or under this directory, e.g.
+        /// `src/parser`
+        #[arg(long)]
+        scope: Vec<String>,
+    },
+    /// Replaces heuristic references with precise ones from another indexer
+    Import {
+        /// SCIP index, e.g. from rust-analyzer or scip-typescript
+        #[arg(long)]
+        scip: String,
+    },
+    /// Saves the index of the selected profile under a name, or restores it from one
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+    /// Uploads the index of the selected profile so others can pull it
+    Push {
+        /// s3://bucket/prefix, http(s):// URL, ssh://host/path, host:path, or a directory
+        #[arg(long)]
+        remote: String,
+        /// Replace the remote index even if it is newer than the local one
+        #[arg(long)]
+        force: bool,
+    },
+    /// Downloads the index of the selected profile pushed to a remote
+    Pull {
+        /// s3://bucket/prefix, http(s):// URL, ssh://host/path, host:path, or a directory
+        #[arg(long)]
+        remote: String,
+        /// Replace the local index even if it is newer than the remote one
+        #[arg(long)]
+        force: bool,
+    },
+    /// Prints a script that enables completions, including symbol names from the
+    /// index, e.g. `source <(contextmesh completions bash)`
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Drops what the [prune] policies of the config leave out from the index
+    /// and reports the space reclaimed
+    Prune,
+    /// Checks that the dependency edges, name lookups, and per-file symbol lists
+    /// of the index agree with each other, and that the indexed files are still
+    /// there and unchanged
+    Verify {
+        /// Fix what can be fixed without parsing, save the index, and report
+        /// the rest
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Keeps the index in memory and serves the commands of other contextmesh
+    /// processes over a Unix socket next to it, until interrupted
+    Daemon {
+        /// Export a span per request to this OTLP/HTTP collector, e.g.
+        /// `http://localhost:4318`, else to `$OTEL_EXPORTER_OTLP_ENDPOINT` (needs
+        /// the `otel` feature)
+        #[arg(long)]
+        otlp_endpoint: Option<String>,
+    },
+    /// Times parsing, indexing, and save/load on a generated fixture project
+    #[command(hide = true)]
+    Bench {
+        #[arg(long, default_value_t = 100)]
+        files: usize,
+        #[arg(long, default_value_t = 20)]
+        fns_per_file: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotAction {
+    /// Copies the index to the snapshot `name`, replacing an existing one
+    Save { name: String },
+    /// Replaces the index with the snapshot `name`
+    Restore { name: String },
+    /// Lists the saved snapshots
+    List,
+}
+
+impl Commands {
+    /// Whether the command only reads the index, so a running daemon can run it.
+    fn reads_index(&self) -> bool {
+        matches!(
+            self,
+            Commands::Combine {
+                interactive: false,
+                ..
+            } | Commands::Context { .. }
+                | Commands::Replay { .. }
+                | Commands::PrintIndex
+                | Commands::Stats { .. }
+                | Commands::Search { .. }
+                | Commands::Query { .. }
+                | Commands::Related { .. }
+                | Commands::Owners { .. }
+                | Commands::Changed { .. }
+                | Commands::Api { .. }
+                | Commands::Tree { .. }
+                | Commands::GrepSym { .. }
+                | Commands::Todos { .. }
+                | Commands::Check { .. }
+                | Commands::LintArch { .. }
+                | Commands::Export { .. }
+        )
+    }
 }
 
 pub fn run_command(args: Cli) -> Result<(), ContextMeshError> {
+    let Some(format) = args.timings else {
+        return run(args);
+    };
+    timings::enable();
+    let result = timings::time("total", || run(args));
+    let report = timings::report();
+    match format {
+        OutputFormat::Text => eprint!("{}", report.to_text()),
+        OutputFormat::Json => eprintln!("{}", to_json(&report.to_json())?),
+    }
+    result
+}
+
+fn run(args: Cli) -> Result<(), ContextMeshError> {
+    let start_dir = std::env::current_dir()?;
+    if let Some(name) = &args.profile {
+        profile::select(name)?;
+    }
+    // Relative to where we were started, not to the root
+    if let Some(path) = &args.index_path {
+        profile::set_index_path(start_dir.join(path));
+    }
+    sink::select(
+        args.sink.clone(),
+        args.output_file.as_ref().map(|path| start_dir.join(path)),
+    );
+    if let Some(root) = &args.root {
+        std::env::set_current_dir(root).map_err(|source| ContextMeshError::RootNotFound {
+            path: root.display().to_string(),
+            source,
+        })?;
+    }
+    if args.command.reads_index() && daemon::forward(&start_dir)? {
+        return Ok(());
+    }
     match args.command {
-        Commands::Index { file, language } => index::handle_index(&file, &language),
-        Commands::Combine => combine::handle_combine(),
+        Commands::Index {
+            file,
+            language,
+            blame,
+            co_change,
+            watch,
+        } => index::handle_index(&file, &language, blame, co_change, watch),
+        Commands::Combine {
+            docs,
+            budget,
+            model,
+            churn,
+            complexity,
+            include_generated,
+            include_third_party,
+            include,
+            exclude,
+            interactive,
+        } => combine::handle_combine(
+            docs,
+            budget,
+            model.as_deref(),
+            churn,
+            complexity,
+            &combine::FileFilter {
+                include_generated,
+                include_third_party,
+                include,
+                exclude,
+            },
+            interactive,
+        ),
+        Commands::Context { list: true, .. } => context::handle_list_recipes(),
+        Commands::Context {
+            recipe,
+            symbols,
+            files,
+            from,
+            min_confidence,
+            targets,
+            budget,
+            model,
+            prefer_complex,
+            include_generated,
+            include_third_party,
+            manifest,
+            ..
+        } => context::handle_context(
+            recipe.as_deref(),
+            Recipe {
+                symbols,
+                files,
+                from,
+                min_confidence,
+                targets,
+                budget,
+                model,
+                prefer_complex,
+                ..Recipe::default()
+            },
+            include_generated,
+            include_third_party,
+            manifest.as_deref(),
+        ),
+        Commands::Replay { manifest } => replay::handle_replay(&manifest),
+        Commands::Ask {
+            question,
+            budget,
+            dry_run,
+        } => ask::handle_ask(&question, budget, dry_run),
+        Commands::Pipe => pipe::handle_pipe(),
+        Commands::Tui { output } => tui::handle_tui(output.as_deref()),
         Commands::PrintIndex => print_index::handle_print_index(),
+        Commands::Stats {
+            errors,
+            hotspots,
+            scope,
+            format,
+        } => stats::handle_stats(errors, hotspots, &scope, format),
+        Commands::Query {
+            expression,
+            cfgs,
+            format,
+        } => query::handle_query(&expression, &cfgs, format),
+        Commands::Datalog {
+            files,
+            relation,
+            format,
+        } => datalog::handle_datalog(&files, relation.as_deref(), format),
+        Commands::Related {
+            symbol,
+            limit,
+            no_git,
+            format,
+        } => related::handle_related(&symbol, limit, no_git, format),
+        Commands::Owners { target, format } => owners::handle_owners(&target, format),
+        Commands::Search {
+            pattern,
+            kind,
+            scope,
+            format,
+        } => search::handle_search(&pattern, kind.as_deref(), &scope, format),
+        Commands::Changed { since, format } => changed::handle_changed(since.as_deref(), format),
+        Commands::Api { package, format } => api::handle_api(package.as_deref(), format),
+        Commands::Tree { target } => tree::handle_tree(target.as_deref()),
+        Commands::GrepSym {
+            literal,
+            exact,
+            format,
+        } => grep_sym::handle_grep_sym(&literal, exact, format),
+        Commands::Todos {
+            symbol,
+            file,
+            format,
+        } => todos::handle_todos(symbol.as_deref(), file.as_deref(), format),
+        Commands::Check {
+            baseline,
+            ci,
+            format,
+        } => check::handle_check(baseline.as_deref(), ci, format),
+        Commands::LintArch { format } => lint_arch::handle_lint_arch(format),
+        Commands::Export {
+            format,
+            output,
+            columns,
+            krate,
+            cfgs,
+            scope,
+        } => export::handle_export(
+            format,
+            output.as_deref(),
+            &columns,
+            krate.as_deref(),
+            &cfgs,
+            &scope,
+        ),
+        Commands::Import { scip } => import::handle_import(&scip),
+        Commands::Snapshot { action } => match action {
+            SnapshotAction::Save { name } => snapshot::handle_save(&name),
+            SnapshotAction::Restore { name } => snapshot::handle_restore(&name),
+            SnapshotAction::List => snapshot::handle_list(),
+        },
+        Commands::Push { remote, force } => remote::handle_push(&remote, force),
+        Commands::Pull { remote, force } => remote::handle_pull(&remote, force),
+        Commands::Prune => prune::handle_prune(),
+        Commands::Verify { repair } => verify::handle_verify(repair),
+        Commands::Daemon { otlp_endpoint } => daemon::handle_daemon(otlp_endpoint.as_deref()),
+        Commands::Completions { shell } => completions::handle_completions(shell),
+        Commands::Bench {
+            files,
+            fns_per_file,
+        } => bench::handle_bench(files, fns_per_file),
     }
 }
@@ -0,0 +1,122 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use super::TableFormat;
+use crate::arch::qualified_name;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::{symbol_order, Table};
+use crate::owners::{blamed_lines, CodeOwners};
+use crate::symbol::Symbol;
+
+/// Lists the owners of `target`, a file, a directory, or a symbol by name or
+/// qualified name: those CODEOWNERS assigns its files to, and the authors who
+/// last changed its lines according to `git blame`. Where git can't blame a
+/// file, the blame recorded by `index --blame` counts instead.
+pub fn handle_owners(target: &str, format: TableFormat) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+    let codeowners = CodeOwners::load()?;
+
+    // (file, symbol to blame, or the whole file)
+    let mut parts: Vec<(String, Option<&Symbol>)> = Vec::new();
+    if Path::new(target).exists() {
+        let prefix = target.trim_start_matches("./").trim_end_matches('/');
+        let mut files: Vec<&String> = index
+            .file_hashes
+            .keys()
+            .filter(|path| {
+                let path = path.trim_start_matches("./");
+                path == prefix || prefix == "." || path.starts_with(&format!("{}/", prefix))
+            })
+            .collect();
+        files.sort();
+        parts.extend(files.into_iter().map(|path| (path.clone(), None)));
+    } else {
+        let mut symbols: Vec<&Symbol> = index
+            .symbols
+            .values()
+            .filter(|sym| sym.is_code())
+            .filter(|sym| {
+                sym.name == target
+                    || (target.contains("::") && qualified_name(&index, sym) == target)
+            })
+            .collect();
+        if symbols.is_empty() {
+            return Err(ContextMeshError::SymbolNotFound(target.to_string()));
+        }
+        symbols.sort_by(|a, b| symbol_order(a, b));
+        parts.extend(
+            symbols
+                .into_iter()
+                .map(|sym| (sym.file_path.to_string(), Some(sym))),
+        );
+    }
+
+    let mut owned_files: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut lines: BTreeMap<String, usize> = BTreeMap::new();
+    let mut unblamed = 0;
+    for (path, sym) in &parts {
+        for owner in codeowners.owners_of(path) {
+            owned_files.entry(owner).or_default().insert(path);
+        }
+        let range = sym.map(|sym| sym.start_byte..sym.end_byte);
+        match blamed_lines(path, range.as_ref()) {
+            Ok(blamed) => {
+                for (author, count) in blamed {
+                    *lines.entry(author).or_default() += count;
+                }
+            }
+            Err(_) => {
+                unblamed += 1;
+                let symbols: Vec<&Symbol> = match sym {
+                    Some(sym) => vec![sym],
+                    None => index.symbols_in_file(path).map(|(_, sym)| sym).collect(),
+                };
+                let content = fs::read(path).unwrap_or_default();
+                for sym in symbols {
+                    if let Some(blame) = &sym.blame {
+                        *lines.entry(blame.author.clone()).or_default() +=
+                            line_count(&content, sym);
+                    }
+                }
+            }
+        }
+    }
+    if unblamed > 0 && format == TableFormat::Table {
+        eprintln!(
+            "git couldn't blame {} file(s); using the blame recorded by `index --blame`.",
+            unblamed
+        );
+    }
+
+    let total: usize = lines.values().sum();
+    let mut owners: BTreeSet<&str> = owned_files.keys().copied().collect();
+    owners.extend(lines.keys().map(String::as_str));
+    let mut rows: Vec<(&str, usize, usize)> = owners
+        .into_iter()
+        .map(|owner| {
+            let files = owned_files.get(owner).map_or(0, BTreeSet::len);
+            (owner, files, lines.get(owner).copied().unwrap_or_default())
+        })
+        .collect();
+    // CODEOWNERS owners first, then the authors of the most lines
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(b.0)));
+
+    let mut table = Table::new(&["owner", "codeowners files", "blamed lines", "share %"]);
+    for (owner, files, count) in rows {
+        let share = match total {
+            0 => 0.0,
+            total => (count as f64 * 1000.0 / total as f64).round() / 10.0,
+        };
+        table.push(vec![owner.into(), files.into(), count.into(), share.into()]);
+    }
+    format.print(&table, &format!("No owners found for '{}'.", target))
+}
+
+/// Number of lines `sym` spans in `content`, at least one.
+fn line_count(content: &[u8], sym: &Symbol) -> usize {
+    let end = sym.end_byte.min(content.len());
+    let start = sym.start_byte.min(end);
+    1 + content[start..end].iter().filter(|&&b| b == b'\n').count()
+}
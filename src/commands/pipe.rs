@@ -0,0 +1,195 @@
+//! `contextmesh pipe` answers JSON requests on stdin with JSON responses on
+//! stdout, one per line, so an agent can keep one process around instead of
+//! running a command per tool call.
+//!
+//! A request names its operation in `op` and may carry an `id`, which its
+//! response repeats:
+//!
+//! - `{"op": "search", "pattern": "load", "kind": "function_item"}`: the symbols
+//!   `contextmesh search` lists, as `search --format json` prints them;
+//! - `{"op": "context", "symbols": [...], "files": [...], "budget": 4000}`: a
+//!   bundle as `contextmesh context` assembles it (also `from`, `targets`,
+//!   `prefer_complex`, `include_generated`, `include_third_party`), with secrets
+//!   masked, and what went into it;
+//! - `{"op": "show", "symbol": "Index::load_index"}`: the source of the symbols
+//!   with that name or qualified name, or a qualified name ending in it.
+//!
+//! A response is `{"id": ..., "result": ...}` or `{"id": ..., "error": {...}}`
+//! with the error as `--porcelain` prints it. The index is loaded once and
+//! reloaded when it changes on disk.
+
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::combine::scrub;
+use super::context::assemble;
+use super::search::matching_symbols;
+use crate::arch::qualified_name;
+use crate::config::Recipe;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::{symbol_order, Table};
+use crate::profile;
+use crate::utils::read_source;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    op: Op,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Op {
+    Search {
+        pattern: String,
+        kind: Option<String>,
+    },
+    Context {
+        #[serde(default)]
+        symbols: Vec<String>,
+        #[serde(default)]
+        files: Vec<String>,
+        #[serde(default)]
+        from: Vec<String>,
+        #[serde(default)]
+        targets: Vec<String>,
+        budget: Option<usize>,
+        #[serde(default)]
+        prefer_complex: bool,
+        #[serde(default)]
+        include_generated: bool,
+        #[serde(default)]
+        include_third_party: bool,
+    },
+    Show {
+        symbol: String,
+    },
+}
+
+/// Answers the requests on stdin until it is closed.
+pub fn handle_pipe() -> Result<(), ContextMeshError> {
+    let index_path = profile::index_path();
+    let mut index = Index::load_index()?;
+    let mut loaded_at = modified(&index_path);
+
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // A failed reload fails this request only; the next one tries again
+        let mut reloaded = Ok(());
+        if modified(&index_path) != loaded_at {
+            match Index::load_index() {
+                Ok(current) => {
+                    index = current;
+                    loaded_at = modified(&index_path);
+                }
+                Err(e) => reloaded = Err(e),
+            }
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => match reloaded.and_then(|()| answer(&index, request.op)) {
+                Ok(result) => json!({ "id": request.id, "result": result }),
+                Err(e) => json!({ "id": request.id, "error": e.to_json()["error"] }),
+            },
+            Err(e) => {
+                let id = serde_json::from_str::<Value>(&line)
+                    .map(|request| request["id"].clone())
+                    .unwrap_or_default();
+                let e = ContextMeshError::DeserializationError(format!("Request: {}", e));
+                json!({ "id": id, "error": e.to_json()["error"] })
+            }
+        };
+        writeln!(stdout, "{}", response)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// The result of the operation `op` on `index`.
+fn answer(index: &Index, op: Op) -> Result<Value, ContextMeshError> {
+    match op {
+        Op::Search { pattern, kind } => {
+            let symbols = matching_symbols(index, &pattern, kind.as_deref());
+            Ok(Table::of_symbols(symbols).to_json())
+        }
+        Op::Context {
+            symbols,
+            files,
+            from,
+            targets,
+            budget,
+            prefer_complex,
+            include_generated,
+            include_third_party,
+        } => {
+            let query = Recipe {
+                symbols,
+                files,
+                from,
+                targets,
+                budget,
+                prefer_complex,
+                ..Recipe::default()
+            };
+            let (bundle, manifest) =
+                assemble(index, &query, include_generated, include_third_party)?;
+            Ok(json!({
+                "content": scrub(&bundle.render())?,
+                "files": manifest.files,
+                "symbols": manifest.symbols,
+            }))
+        }
+        Op::Show { symbol } => {
+            let mut matching: Vec<_> = index
+                .symbols
+                .values()
+                .filter(|sym| sym.is_code())
+                .filter(|sym| {
+                    sym.name == symbol
+                        || (symbol.contains("::") && {
+                            let name = qualified_name(index, sym);
+                            name == symbol || name.ends_with(&format!("::{}", symbol))
+                        })
+                })
+                .collect();
+            if matching.is_empty() {
+                return Err(ContextMeshError::SymbolNotFound(symbol));
+            }
+            matching.sort_by(|a, b| symbol_order(a, b));
+            let mut shown = Vec::new();
+            for sym in matching {
+                let content = read_source(&sym.file_path)?;
+                let source = content
+                    .get(sym.start_byte..sym.end_byte)
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                shown.push(json!({
+                    "qualified_name": qualified_name(index, sym),
+                    "kind": sym.node_kind,
+                    "file": sym.file_path.trim_start_matches("./"),
+                    "line": sym.line_number,
+                    "signature": sym.signature,
+                    "doc": sym.doc,
+                    "source": scrub(&source)?,
+                }));
+            }
+            Ok(Value::Array(shown))
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -1,35 +1,54 @@
+use crate::config::Config;
 use crate::errors::ContextMeshError;
 use crate::index::Index;
-use arboard::Clipboard;
+use crate::output::{sorted_edges, sorted_symbols};
+use crate::sink::{self, Sink};
 
 pub fn handle_print_index() -> Result<(), ContextMeshError> {
     println!("Loading index...");
     let mut combined_content = String::new();
 
-    let indexer = Index::load_index().map_err(|e| {
-        eprintln!("Failed to load index: {}", e);
-        e
-    })?;
+    let indexer = Index::load_index()?;
 
     println!("Indexed symbols:");
-    for (hash, symbol) in indexer.symbols {
-        let s = format!("Hash: {}, Symbol: {:?}\n", hash, symbol);
-        combined_content.push_str(&format!("Hash: {}, Symbol: {:?}\n", hash, symbol));
+    for (hash, symbol) in sorted_symbols(&indexer) {
+        // Edges are stored as internal IDs; print them as the stable symbol hashes
+        let dependencies = sorted_edges(&indexer, &symbol.dependencies);
+        let used_by = sorted_edges(&indexer, &symbol.used_by);
+
+        let s = format!(
+            "Hash: {}, Symbol: {{ name: {:?}, node_kind: {:?}, file_path: {:?}, line_number: {}, start_byte: {}, end_byte: {}, visibility: {:?}, attributes: {:?}, parent: {:?}, blame: {:?}, dependencies: {:?}, used_by: {:?} }}\n",
+            hash,
+            symbol.name,
+            symbol.node_kind,
+            symbol.file_path,
+            symbol.line_number,
+            symbol.start_byte,
+            symbol.end_byte,
+            symbol.visibility,
+            symbol.attributes,
+            symbol.parent.and_then(|id| indexer.hash_of(id)),
+            symbol.blame,
+            dependencies,
+            used_by
+        );
+        combined_content.push_str(&s);
         println!("{}", s);
     }
 
     if !combined_content.is_empty() {
-        match Clipboard::new() {
-            Ok(mut clipboard) => {
-                clipboard
-                    .set_text(combined_content.clone())
-                    .map_err(|e| ContextMeshError::ClipboardError(e.to_string()))?;
-                println!("Combined content copied to clipboard.");
-            }
-            Err(e) => {
-                eprintln!("Failed to initialize clipboard: {}.", e);
-                return Err(ContextMeshError::ClipboardError(e.to_string()));
-            }
+        // Printed already, so stdout takes it as is and ends the sinks tried
+        let (mut sinks, file) = sink::chosen(&Config::load()?.output);
+        let stdout = sinks.iter().position(|sink| *sink == Sink::Stdout);
+        sinks.truncate(stdout.unwrap_or(sinks.len()));
+        match sink::send(&combined_content, &sinks, file.as_ref()) {
+            Ok(Sink::File) => println!(
+                "Combined content written to {}.",
+                file.unwrap_or_default().display()
+            ),
+            Ok(used) => println!("Combined content copied to the {}.", used.name()),
+            Err(_) if stdout.is_some() => {}
+            Err(e) => return Err(e),
         }
     } else {
         println!("No files found to combine.");
@@ -0,0 +1,42 @@
+use std::fs;
+
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::profile;
+
+/// Applies the `[prune]` policies of the config to the index of the selected
+/// profile and reports how much smaller it got on disk.
+pub fn handle_prune() -> Result<(), ContextMeshError> {
+    let config = Config::load()?;
+    if !config.prune.is_enabled() {
+        println!("No pruning policies configured under [prune] in .contextmesh/config.toml.");
+        return Ok(());
+    }
+
+    let index_path = profile::index_path();
+    let mut index = Index::load_index()?;
+    let report = index.prune(&config.prune);
+    if report.is_empty() {
+        println!("Nothing to prune.");
+        return Ok(());
+    }
+
+    let before = fs::metadata(&index_path)?.len();
+    index.save_index(&config.index)?;
+    let after = fs::metadata(&index_path)?.len();
+
+    println!("Dropped doc comments:       {}", report.docs);
+    println!("Dropped excluded symbols:   {}", report.excluded);
+    println!(
+        "Dropped over the file cap:  {} (from {} file(s))",
+        report.capped, report.capped_files
+    );
+    println!(
+        "Index size:                 {} -> {} bytes ({} reclaimed)",
+        before,
+        after,
+        before.saturating_sub(after)
+    );
+    Ok(())
+}
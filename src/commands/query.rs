@@ -0,0 +1,29 @@
+use super::TableFormat;
+use crate::cfg::CfgSet;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::{sorted_symbols, Table};
+use crate::query::Query;
+
+/// Lists the symbols selected by the query `expression`; see [`crate::query`].
+/// With `cfgs`, only symbols compiled with that cfg set are listed.
+pub fn handle_query(
+    expression: &str,
+    cfgs: &[String],
+    format: TableFormat,
+) -> Result<(), ContextMeshError> {
+    let query = Query::parse(expression)?;
+    let cfg_set = (!cfgs.is_empty())
+        .then(|| CfgSet::parse(cfgs))
+        .transpose()?;
+    let index = Index::load_index()?;
+
+    let selected = query.evaluate(&index);
+    let symbols = sorted_symbols(&index)
+        .into_iter()
+        .filter(|(hash, _)| selected.contains(hash.as_str()))
+        .map(|(_, sym)| sym)
+        .filter(|sym| cfg_set.as_ref().is_none_or(|set| set.enables(&index, sym)));
+
+    format.print(&Table::of_symbols(symbols), "No symbols match the query.")
+}
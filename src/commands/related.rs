@@ -0,0 +1,86 @@
+use std::collections::{HashMap, HashSet};
+
+use super::TableFormat;
+use crate::arch::qualified_name;
+use crate::cochange;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::Table;
+use crate::related::rank;
+
+/// Lists the `limit` symbols most related to `symbol`, a qualified name or a
+/// plain one; a plain name shared by several symbols ranks against all of them.
+/// See [`crate::related`].
+pub fn handle_related(
+    symbol: &str,
+    limit: usize,
+    no_git: bool,
+    format: TableFormat,
+) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+
+    let seeds: HashSet<&str> = index
+        .symbols
+        .iter()
+        .filter(|(_, sym)| sym.is_code())
+        .filter(|(_, sym)| {
+            sym.name == symbol || (symbol.contains("::") && qualified_name(&index, sym) == symbol)
+        })
+        .map(|(hash, _)| hash.as_str())
+        .collect();
+    if seeds.is_empty() {
+        return Err(ContextMeshError::SymbolNotFound(symbol.to_string()));
+    }
+    if seeds.len() > 1 && format == TableFormat::Table {
+        eprintln!(
+            "{} symbols are named '{}'; ranking against all of them.",
+            seeds.len(),
+            symbol
+        );
+    }
+
+    // Edges recorded by `index --co-change`, or else the history as it is now
+    let files: HashSet<&str> = seeds
+        .iter()
+        .map(|hash| &*index.symbols[*hash].file_path)
+        .collect();
+    let co_changes = if no_git {
+        HashMap::new()
+    } else if !index.co_changes.is_empty() {
+        cochange::evolving_with(&index.co_changes, &files)
+    } else {
+        match cochange::from_git() {
+            Ok(co_changes) => cochange::evolving_with(&co_changes, &files),
+            Err(e) => {
+                eprintln!("Ignoring co-changes: {}", e);
+                HashMap::new()
+            }
+        }
+    };
+
+    let mut table = Table::new(&[
+        "name",
+        "kind",
+        "location",
+        "score",
+        "proximity",
+        "shared deps",
+        "co-change",
+        "name similarity",
+    ]);
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+    for related in rank(&index, &seeds, &co_changes).into_iter().take(limit) {
+        let sym = related.symbol;
+        table.push(vec![
+            qualified_name(&index, sym).into(),
+            (&*sym.node_kind).into(),
+            format!("{}:{}", sym.file_path, sym.line_number).into(),
+            round(related.score).into(),
+            round(related.proximity).into(),
+            round(related.shared_dependencies).into(),
+            round(related.co_change).into(),
+            round(related.name_similarity).into(),
+        ]);
+    }
+    format.print(&table, "No related symbols found.")
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use log::{info, warn};
+
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::git::current_git_commit;
+use crate::index::Index;
+use crate::profile;
+use crate::remote::{manifest_key, shard_key, shards, Manifest, Remote, MANIFEST_FORMAT};
+use crate::utils::{hash_bytes, write_atomically};
+
+pub fn handle_push(url: &str, force: bool) -> Result<(), ContextMeshError> {
+    let remote = Remote::parse(url)?;
+    let index_path = profile::index_path();
+    if !index_path.exists() {
+        return Err(ContextMeshError::IndexNotFound(
+            index_path.display().to_string(),
+        ));
+    }
+    let data = Index::decompressed(std::fs::read(&index_path)?)?;
+    let metadata = Index::load_index()?.metadata;
+    let level = Config::load()?.index.compression_level;
+
+    let key = manifest_key(profile::active());
+    if let Some(existing) = remote.get(&key)? {
+        let existing = parse_manifest(&existing)?;
+        if existing.format == MANIFEST_FORMAT && existing.hash == hash_bytes(&data) {
+            info!("Remote index is already up to date.");
+            return Ok(());
+        }
+        if existing.metadata.updated_at > metadata.updated_at && !force {
+            return Err(ContextMeshError::RemoteError(
+                "The remote index is newer than the local one; use --force to replace it"
+                    .to_string(),
+            ));
+        }
+    }
+
+    let mut hashes = Vec::new();
+    let mut uploaded = 0;
+    for shard in shards(&data) {
+        let hash = hash_bytes(shard);
+        let shard_key = shard_key(&hash);
+        if !remote.exists(&shard_key)? {
+            let compressed = zstd::encode_all(shard, level)
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            remote.put(&shard_key, &compressed)?;
+            uploaded += 1;
+        }
+        hashes.push(hash);
+    }
+
+    let manifest = Manifest {
+        format: MANIFEST_FORMAT,
+        hash: hash_bytes(&data),
+        size: data.len(),
+        shards: hashes,
+        metadata,
+    };
+    let encoded = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+    remote.put(&key, &encoded)?;
+
+    info!(
+        "Pushed index of profile '{}': {} of {} shard(s) uploaded.",
+        profile::active(),
+        uploaded,
+        manifest.shards.len()
+    );
+    Ok(())
+}
+
+pub fn handle_pull(url: &str, force: bool) -> Result<(), ContextMeshError> {
+    let remote = Remote::parse(url)?;
+    let manifest = match remote.get(&manifest_key(profile::active()))? {
+        Some(data) => parse_manifest(&data)?,
+        None => {
+            return Err(ContextMeshError::RemoteError(format!(
+                "No index for profile '{}' on the remote",
+                profile::active()
+            )))
+        }
+    };
+    if manifest.format != MANIFEST_FORMAT {
+        return Err(ContextMeshError::RemoteError(format!(
+            "The remote index of profile '{}' was pushed by an older contextmesh; push it again",
+            profile::active()
+        )));
+    }
+
+    let index_path = profile::index_path();
+    let local = std::fs::read(&index_path)
+        .ok()
+        .and_then(|data| Index::decompressed(data).ok());
+    if let Some(local) = &local {
+        if hash_bytes(local) == manifest.hash {
+            info!("Local index is already up to date.");
+            return Ok(());
+        }
+        let local_updated = Index::load_index().map(|index| index.metadata.updated_at);
+        if local_updated.is_ok_and(|updated| updated > manifest.metadata.updated_at) && !force {
+            return Err(ContextMeshError::RemoteError(
+                "The local index is newer than the remote one; use --force to replace it"
+                    .to_string(),
+            ));
+        }
+    }
+
+    if manifest.metadata.tool_version != env!("CARGO_PKG_VERSION") {
+        warn!(
+            "Remote index was written by contextmesh {}; this is {}.",
+            manifest.metadata.tool_version,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+    if manifest.metadata.git_commit.is_some()
+        && manifest.metadata.git_commit != current_git_commit()
+    {
+        info!(
+            "Remote index was built at commit {}; the next index run updates changed files.",
+            manifest.metadata.git_commit.as_deref().unwrap_or_default()
+        );
+    }
+
+    // Shards of the current local index that the remote one shares need no download
+    let local_shards: HashMap<String, &[u8]> = local
+        .iter()
+        .flat_map(|data| shards(data))
+        .map(|shard| (hash_bytes(shard), shard))
+        .collect();
+
+    let mut data = Vec::with_capacity(manifest.size);
+    let mut downloaded = 0;
+    for hash in &manifest.shards {
+        if let Some(shard) = local_shards.get(hash) {
+            data.extend_from_slice(shard);
+            continue;
+        }
+        let shard = remote.get(&shard_key(hash))?.ok_or_else(|| {
+            ContextMeshError::RemoteError(format!("Shard {} is missing on the remote", hash))
+        })?;
+        let shard = zstd::decode_all(shard.as_slice()).unwrap_or_default();
+        if hash_bytes(&shard) != *hash {
+            return Err(ContextMeshError::RemoteError(format!(
+                "Shard {} is corrupt",
+                hash
+            )));
+        }
+        data.extend_from_slice(&shard);
+        downloaded += 1;
+    }
+    if hash_bytes(&data) != manifest.hash {
+        return Err(ContextMeshError::RemoteError(
+            "Downloaded index doesn't match the manifest".to_string(),
+        ));
+    }
+
+    if let Some(dir) = index_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    write_atomically(
+        &index_path,
+        &Index::compressed(data, &Config::load()?.index)?,
+    )?;
+    info!(
+        "Pulled index of profile '{}': {} of {} shard(s) downloaded.",
+        profile::active(),
+        downloaded,
+        manifest.shards.len()
+    );
+    Ok(())
+}
+
+fn parse_manifest(data: &[u8]) -> Result<Manifest, ContextMeshError> {
+    serde_json::from_slice(data).map_err(|e| {
+        ContextMeshError::RemoteError(format!("Invalid manifest on the remote: {}", e))
+    })
+}
@@ -0,0 +1,109 @@
+use std::fs;
+
+use super::combine::{deliver, Exclusions};
+use super::context::add_symbol;
+use crate::arch::qualified_name;
+use crate::bundle::{Bundle, Manifest, ManifestSymbol};
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::symbol::Symbol;
+
+/// What became of a symbol of a manifest in the current index.
+enum Fate<'a> {
+    Unchanged(&'a Symbol),
+    Changed(&'a Symbol),
+    Moved(&'a Symbol),
+    Gone,
+}
+
+/// Assembles the bundle recorded in the manifest at `path` from the current
+/// code: the same files, and the symbols by qualified name wherever they are now.
+/// Symbols whose source changed or that moved to another file are reported and
+/// included as they are now; those that no longer exist are reported and left
+/// out.
+pub fn handle_replay(path: &str) -> Result<(), ContextMeshError> {
+    let manifest = Manifest::load(path)?;
+    let index = Index::load_index()?;
+    let mut bundle = Bundle::default();
+
+    for file in &manifest.files {
+        match fs::metadata(file) {
+            Ok(_) => bundle.add_file(file),
+            Err(e) => eprintln!("File '{}' is gone: {}. Skipping.", file, e),
+        }
+    }
+
+    let (mut changed, mut moved, mut gone) = (0, 0, 0);
+    for entry in &manifest.symbols {
+        let location = format!("{}:{}", entry.file.trim_start_matches("./"), entry.line);
+        match fate(&index, entry) {
+            Fate::Unchanged(sym) => add_symbol(&mut bundle, &index, sym, entry.detail),
+            Fate::Changed(sym) => {
+                changed += 1;
+                eprintln!("Changed: `{}` ({})", entry.qualified_name, location);
+                add_symbol(&mut bundle, &index, sym, entry.detail);
+            }
+            Fate::Moved(sym) => {
+                moved += 1;
+                eprintln!(
+                    "Moved: `{}` ({} -> {}:{})",
+                    entry.qualified_name,
+                    location,
+                    sym.file_path.trim_start_matches("./"),
+                    sym.line_number
+                );
+                add_symbol(&mut bundle, &index, sym, entry.detail);
+            }
+            Fate::Gone => {
+                gone += 1;
+                eprintln!("Gone: `{}` ({})", entry.qualified_name, location);
+            }
+        }
+    }
+    let since = manifest
+        .git_commit
+        .as_deref()
+        .map_or(String::new(), |commit| format!(" since {}", commit));
+    eprintln!(
+        "{} of {} symbol(s) changed, {} moved, and {} disappeared{}.",
+        changed,
+        manifest.symbols.len(),
+        moved,
+        gone,
+        since
+    );
+
+    Exclusions::load()?.apply(&index, &mut bundle);
+    let combined_content = bundle.render();
+    if combined_content.is_empty() {
+        eprintln!("Nothing of the manifest is left.");
+        return Ok(());
+    }
+    deliver(&combined_content)
+}
+
+/// The current symbol `entry` names: of those with its qualified name and kind,
+/// preferably one in the same file, then one with the same source (a cfg
+/// variant), then the one closest to its old line.
+fn fate<'a>(index: &'a Index, entry: &ManifestSymbol) -> Fate<'a> {
+    let name = entry.qualified_name.rsplit("::").next().unwrap_or_default();
+    let same_file = |sym: &Symbol| *sym.file_path == *entry.file;
+    let best = index
+        .symbols
+        .values()
+        .filter(|sym| sym.name == name && *sym.node_kind == *entry.kind)
+        .filter(|sym| qualified_name(index, sym) == entry.qualified_name)
+        .min_by_key(|sym| {
+            (
+                !same_file(sym),
+                sym.body_hash != entry.body_hash,
+                sym.line_number.abs_diff(entry.line),
+            )
+        });
+    match best {
+        None => Fate::Gone,
+        Some(sym) if !same_file(sym) => Fate::Moved(sym),
+        Some(sym) if sym.body_hash != entry.body_hash => Fate::Changed(sym),
+        Some(sym) => Fate::Unchanged(sym),
+    }
+}
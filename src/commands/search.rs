@@ -0,0 +1,47 @@
+use super::TableFormat;
+use crate::arch::{glob_matches, qualified_name};
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::{sorted_symbols, Table};
+use crate::symbol::Symbol;
+
+/// Lists the code symbols whose name contains `pattern` (ignoring case) or, if it
+/// contains `::`, whose qualified name matches it as a module glob. With a
+/// `scope`, only symbols of the files in it are searched.
+pub fn handle_search(
+    pattern: &str,
+    kind: Option<&str>,
+    scope: &[String],
+    format: TableFormat,
+) -> Result<(), ContextMeshError> {
+    let mut index = Index::load_index()?;
+    if !scope.is_empty() {
+        index.restrict_to(scope);
+    }
+    format.print(
+        &Table::of_symbols(matching_symbols(&index, pattern, kind)),
+        &format!("No symbols match \"{}\".", pattern),
+    )
+}
+
+/// The code symbols `handle_search` lists, of node kind `kind` if given, sorted.
+pub(super) fn matching_symbols<'a>(
+    index: &'a Index,
+    pattern: &str,
+    kind: Option<&str>,
+) -> Vec<&'a Symbol> {
+    let lowercase = pattern.to_lowercase();
+    sorted_symbols(index)
+        .into_iter()
+        .map(|(_, sym)| sym)
+        .filter(|sym| sym.is_code() && !sym.name.is_empty())
+        .filter(|sym| kind.is_none_or(|kind| &*sym.node_kind == kind))
+        .filter(|sym| {
+            if pattern.contains("::") {
+                glob_matches(pattern, &qualified_name(index, sym), sym)
+            } else {
+                sym.name.to_lowercase().contains(&lowercase)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,55 @@
+use log::info;
+
+use crate::errors::ContextMeshError;
+use crate::profile;
+
+pub fn handle_save(name: &str) -> Result<(), ContextMeshError> {
+    let index_path = profile::index_path();
+    if !index_path.exists() {
+        return Err(ContextMeshError::IndexNotFound(
+            index_path.display().to_string(),
+        ));
+    }
+
+    let snapshot_path = profile::snapshot_path(name)?;
+    if let Some(dir) = snapshot_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::copy(&index_path, &snapshot_path)?;
+    info!(
+        "Saved index of profile '{}' as snapshot '{}'",
+        profile::active(),
+        name
+    );
+    Ok(())
+}
+
+pub fn handle_restore(name: &str) -> Result<(), ContextMeshError> {
+    let snapshot_path = profile::snapshot_path(name)?;
+    if !snapshot_path.exists() {
+        return Err(ContextMeshError::SnapshotNotFound(name.to_string()));
+    }
+
+    let index_path = profile::index_path();
+    if let Some(dir) = index_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::copy(&snapshot_path, &index_path)?;
+    info!(
+        "Restored snapshot '{}' into profile '{}'",
+        name,
+        profile::active()
+    );
+    Ok(())
+}
+
+pub fn handle_list() -> Result<(), ContextMeshError> {
+    let names = profile::snapshot_names();
+    if names.is_empty() {
+        println!("No snapshots saved.");
+    }
+    for name in names {
+        println!("{}", name);
+    }
+    Ok(())
+}
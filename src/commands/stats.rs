@@ -0,0 +1,203 @@
+use serde_json::json;
+use std::cmp::Reverse;
+
+use super::{to_json, TableFormat};
+use crate::arch::qualified_name;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::{sorted_symbols, Table};
+use crate::symbol::{Metrics, Symbol};
+use crate::utils::format_timestamp;
+
+/// Prints statistics of the index, with `errors` the files that failed to index,
+/// and with `hotspots` that many of the most complex symbols. With a `scope`,
+/// only the files in it are counted.
+pub fn handle_stats(
+    errors: bool,
+    hotspots: Option<usize>,
+    scope: &[String],
+    format: TableFormat,
+) -> Result<(), ContextMeshError> {
+    let mut index = Index::load_index()?;
+    if !scope.is_empty() {
+        index.restrict_to(scope);
+    }
+    let statistics = statistics(&index);
+    let failures = errors.then(|| failures(&index));
+    let hotspots = hotspots.map(|limit| hotspots_table(&index, limit));
+
+    match format {
+        TableFormat::Json => {
+            let mut value = json!({ "statistics": statistics.to_json() });
+            if let Some(failures) = &failures {
+                value["failures"] = failures.to_json();
+            }
+            if let Some(hotspots) = &hotspots {
+                value["hotspots"] = hotspots.to_json();
+            }
+            println!("{}", to_json(&value)?);
+        }
+        // CSV sections are separated by an empty line
+        TableFormat::Csv => {
+            print!("{}", statistics.to_csv());
+            if let Some(failures) = &failures {
+                print!("\r\n{}", failures.to_csv());
+            }
+            if let Some(hotspots) = &hotspots {
+                print!("\r\n{}", hotspots.to_csv());
+            }
+        }
+        TableFormat::Table => {
+            print!("{}", statistics.to_text());
+            if let Some(failures) = &failures {
+                println!();
+                if failures.is_empty() {
+                    println!("No files failed to index.");
+                } else {
+                    print!("{}", failures.to_text());
+                }
+            }
+            if let Some(hotspots) = &hotspots {
+                println!();
+                if hotspots.is_empty() {
+                    println!("No symbols have metrics.");
+                } else {
+                    print!("{}", hotspots.to_text());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn statistics(index: &Index) -> Table {
+    let metadata = &index.metadata;
+    let languages: Vec<&str> = metadata.languages.iter().map(String::as_str).collect();
+    let or_dash = |value: Option<&str>| value.unwrap_or("-").to_string();
+
+    let mut table = Table::new(&["statistic", "value"]);
+    let rows = [
+        ("Files", index.file_hashes.len().into()),
+        ("Symbols", index.symbols.len().into()),
+        ("Unresolved references", index.unresolved_count().into()),
+        ("Failed files", index.failed_files.len().into()),
+        ("Skipped files", metadata.skipped_files.len().into()),
+        ("Partially indexed", index.partial_files.len().into()),
+        ("Generated files", index.generated_files.len().into()),
+        ("Third-party files", index.third_party_files.len().into()),
+        ("Low-fidelity files", index.low_fidelity_files.len().into()),
+        ("Entry points", index.entry_points.len().into()),
+        (
+            "Unreachable symbols",
+            index
+                .symbols
+                .keys()
+                .filter(|hash| !index.is_reachable(hash))
+                .count()
+                .into(),
+        ),
+        ("Changed in last run", index.last_changes.len().into()),
+        ("Tool version", metadata.tool_version.clone().into()),
+        ("Created", format_timestamp(metadata.created_at).into()),
+        ("Updated", format_timestamp(metadata.updated_at).into()),
+        (
+            "Languages",
+            if languages.is_empty() {
+                "-".to_string()
+            } else {
+                languages.join(", ")
+            }
+            .into(),
+        ),
+        (
+            "Config hash",
+            or_dash(metadata.config_hash.as_deref()).into(),
+        ),
+        ("Git commit", or_dash(metadata.git_commit.as_deref()).into()),
+        ("Root", or_dash(metadata.root.as_deref()).into()),
+    ];
+    for (statistic, value) in rows {
+        table.push(vec![statistic.into(), value]);
+    }
+    table
+}
+
+/// Partially indexed files (syntax errors), files of which only definitions were
+/// indexed, files skipped for their size or content, and files that failed to
+/// index.
+fn failures(index: &Index) -> Table {
+    let mut table = Table::new(&["file", "error nodes", "failed at", "reason"]);
+
+    let mut partial: Vec<_> = index.partial_files.iter().collect();
+    partial.sort();
+    for (path, error_nodes) in partial {
+        table.push(vec![
+            path.clone().into(),
+            (*error_nodes).into(),
+            "".into(),
+            "partially indexed (syntax errors)".into(),
+        ]);
+    }
+
+    let mut low_fidelity: Vec<_> = index.low_fidelity_files.iter().collect();
+    low_fidelity.sort();
+    for path in low_fidelity {
+        table.push(vec![
+            path.clone().into(),
+            0.into(),
+            "".into(),
+            "low fidelity (definitions only, no parser)".into(),
+        ]);
+    }
+
+    for (path, reason) in &index.metadata.skipped_files {
+        table.push(vec![
+            path.clone().into(),
+            0.into(),
+            "".into(),
+            format!("skipped ({})", reason).into(),
+        ]);
+    }
+
+    let mut failures: Vec<_> = index.failed_files.iter().collect();
+    failures.sort_by(|a, b| a.0.cmp(b.0));
+    for (path, failure) in failures {
+        table.push(vec![
+            path.clone().into(),
+            failure.error_nodes.into(),
+            format_timestamp(failure.failed_at).into(),
+            failure.reason.clone().into(),
+        ]);
+    }
+    table
+}
+
+/// The `limit` symbols of highest complexity, ties broken by length.
+fn hotspots_table(index: &Index, limit: usize) -> Table {
+    let mut measured: Vec<(&Symbol, Metrics)> = sorted_symbols(index)
+        .into_iter()
+        .filter_map(|(_, sym)| Some((sym, sym.metrics?)))
+        .collect();
+    measured.sort_by_key(|(_, metrics)| Reverse((metrics.complexity, metrics.lines)));
+
+    let mut table = Table::new(&[
+        "symbol",
+        "location",
+        "lines",
+        "complexity",
+        "nesting",
+        "params",
+    ]);
+    for (sym, metrics) in measured.into_iter().take(limit) {
+        table.push(vec![
+            qualified_name(index, sym).into(),
+            format!("{}:{}", sym.file_path, sym.line_number).into(),
+            metrics.lines.into(),
+            metrics.complexity.into(),
+            metrics.nesting.into(),
+            metrics.parameters.into(),
+        ]);
+    }
+    table
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+
+use super::tree::file_matches;
+use super::OutputFormat;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::symbol::Symbol;
+
+/// A TODO-style comment with the symbols enclosing it, innermost first.
+#[derive(Serialize)]
+struct TodoEntry<'a> {
+    tag: &'a str,
+    text: &'a str,
+    file_path: &'a str,
+    line_number: usize,
+    symbols: Vec<&'a str>,
+}
+
+/// Prints the TODO/FIXME/HACK comments of the index, optionally only those inside
+/// the symbol named `symbol` (or its children) and/or the file, directory, or
+/// module `file`.
+pub fn handle_todos(
+    symbol: Option<&str>,
+    file: Option<&str>,
+    format: OutputFormat,
+) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+
+    let mut entries: Vec<TodoEntry> = index
+        .todos
+        .iter()
+        .filter(|(path, _)| file.is_none_or(|file| file_matches(path, file)))
+        .flat_map(|(path, todos)| {
+            let symbols: Vec<&Symbol> = index.symbols_in_file(path).map(|(_, sym)| sym).collect();
+            todos.iter().map(move |todo| {
+                let mut enclosing: Vec<&Symbol> = symbols
+                    .iter()
+                    .copied()
+                    .filter(|sym| sym.start_byte <= todo.byte && todo.byte < sym.end_byte)
+                    .collect();
+                enclosing.sort_by_key(|sym| sym.end_byte - sym.start_byte);
+                TodoEntry {
+                    tag: &todo.tag,
+                    text: &todo.text,
+                    file_path: path,
+                    line_number: todo.line_number,
+                    symbols: enclosing.iter().map(|sym| sym.name.as_str()).collect(),
+                }
+            })
+        })
+        .filter(|entry| symbol.is_none_or(|symbol| entry.symbols.contains(&symbol)))
+        .collect();
+    entries.sort_by(|a, b| (a.file_path, a.line_number).cmp(&(b.file_path, b.line_number)));
+
+    match format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&entries)
+                .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+            println!("{}", json);
+        }
+        OutputFormat::Text => {
+            if entries.is_empty() {
+                println!("No TODO comments found.");
+            }
+            for entry in &entries {
+                let location = match entry.symbols.first() {
+                    Some(name) => format!(" (in {})", name),
+                    None => String::new(),
+                };
+                let text = match entry.text {
+                    "" => String::new(),
+                    text => format!(": {}", text),
+                };
+                println!(
+                    "{}:{} {}{}{}",
+                    entry.file_path, entry.line_number, entry.tag, location, text
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
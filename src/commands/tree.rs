@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::symbol::Symbol;
+use crate::utils::{estimate_tokens, format_timestamp, module_path};
+
+/// Prints the indexed files matching `target` (all files if `None`) as an outline of
+/// their symbols, nesting fields, variants, and methods under their parents.
+pub fn handle_tree(target: Option<&str>) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+
+    let mut files: Vec<&str> = index
+        .file_hashes
+        .keys()
+        .map(String::as_str)
+        .filter(|path| target.is_none_or(|target| file_matches(path, target)))
+        .collect();
+    files.sort_unstable();
+
+    if files.is_empty() {
+        println!("No indexed files match {}.", target.unwrap_or("the index"));
+        return Ok(());
+    }
+
+    for path in files {
+        print_file(&index, path);
+    }
+
+    Ok(())
+}
+
+fn print_file(index: &Index, path: &str) {
+    let symbols: Vec<&Symbol> = index.symbols_in_file(path).map(|(_, sym)| sym).collect();
+
+    // Children of each symbol, keyed by the parent's hash; `None` holds the top level
+    let mut children: HashMap<Option<&str>, Vec<&Symbol>> = HashMap::new();
+    for sym in &symbols {
+        let parent = sym.parent.and_then(|id| index.hash_of(id));
+        children.entry(parent).or_default().push(sym);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|sym| (sym.line_number, sym.start_byte));
+    }
+
+    let file_bytes = fs::metadata(path)
+        .map(|meta| meta.len() as usize)
+        .unwrap_or_else(|_| symbols.iter().map(|sym| sym.end_byte).max().unwrap_or(0));
+    println!(
+        "{} [{}] ({} tokens)",
+        path,
+        module_path(path),
+        estimate_tokens(file_bytes)
+    );
+    print_children(&children, None, 1);
+}
+
+fn print_children(
+    children: &HashMap<Option<&str>, Vec<&Symbol>>,
+    parent: Option<&str>,
+    depth: usize,
+) {
+    for sym in children.get(&parent).into_iter().flatten() {
+        // Recorded by `index --blame`
+        let last_change = match &sym.blame {
+            Some(blame) => format!(
+                " [{}, {}, {}]",
+                blame.author,
+                format_timestamp(blame.time)
+                    .split(' ')
+                    .next()
+                    .unwrap_or_default(),
+                &blame.commit[..blame.commit.len().min(7)]
+            ),
+            None => String::new(),
+        };
+        println!(
+            "{}{} {} ({} tokens){}",
+            "  ".repeat(depth),
+            kind_label(&sym.node_kind),
+            sym.name,
+            estimate_tokens(sym.end_byte.saturating_sub(sym.start_byte)),
+            last_change
+        );
+        let hash = sym.hash();
+        print_children(children, Some(hash.as_str()), depth + 1);
+    }
+}
+
+/// Short, IDE-like label for a tree-sitter node kind.
+pub(super) fn kind_label(node_kind: &str) -> &str {
+    match node_kind {
+        "function_item" | "method_declaration" => "fn",
+        "field_declaration" => "field",
+        "enum_variant" => "variant",
+        "let_declaration" => "let",
+        other => other.strip_suffix("_item").unwrap_or(other),
+    }
+}
+
+/// Whether `path` is the file or directory `target`, or belongs to the Rust module
+/// `target` (e.g. `parser` or `crate::parser::language`).
+pub(super) fn file_matches(path: &str, target: &str) -> bool {
+    let path = path.trim_start_matches("./");
+    let target = target.trim_start_matches("./");
+    let dir = target.trim_end_matches('/');
+    if path == target || path.starts_with(&format!("{}/", dir)) {
+        return true;
+    }
+
+    let module = module_path(path);
+    let wanted = target.trim_start_matches("crate::");
+    let module = module.trim_start_matches("crate::");
+    module == wanted || module.starts_with(&format!("{}::", wanted))
+}
@@ -0,0 +1,595 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use super::combine::{deliver, scrub, Exclusions, FileFilter};
+use super::tree::kind_label;
+use crate::arch::qualified_name;
+use crate::bundle::Bundle;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::output::symbol_order;
+use crate::parser::document::is_document_file;
+use crate::symbol::{Symbol, SymbolId};
+use crate::utils::{estimate_tokens, read_source};
+
+const HELP: &str = "/ search  j/k move  d deps  u users  h back  space basket  tab switch pane  q done  ctrl-c abort";
+
+/// Opens an interactive browser of the index in which symbols and files can be
+/// collected into a basket. On exit, the basket is written to `output`, or
+/// copied to the clipboard like `combine` does.
+pub fn handle_tui(output: Option<&str>) -> Result<(), ContextMeshError> {
+    let index = Index::load_index()?;
+    let everything = FileFilter {
+        include_generated: true,
+        include_third_party: true,
+        ..Default::default()
+    };
+    let Some((bundle, picked)) = pick(&index, &everything)? else {
+        return Ok(());
+    };
+    match output {
+        Some(path) => {
+            fs::write(path, scrub(&bundle)?)?;
+            println!(
+                "Wrote {} item(s) (~{} tokens) to {}",
+                picked,
+                estimate_tokens(bundle.len()),
+                path
+            );
+            Ok(())
+        }
+        None => deliver(&bundle),
+    }
+}
+
+/// Lets the user pick symbols and whole files of those `filter` allows in the
+/// browser, returning the bundle of them and how many were picked, or `None`
+/// if they aborted or picked nothing.
+pub(super) fn pick(
+    index: &Index,
+    filter: &FileFilter,
+) -> Result<Option<(String, usize)>, ContextMeshError> {
+    let mut app = App::new(index, filter, Exclusions::load()?);
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    if !result? {
+        eprintln!("Aborted.");
+        return Ok(None);
+    }
+
+    let bundle = app.bundle();
+    if bundle.is_empty() {
+        eprintln!("Nothing in the basket.");
+        return Ok(None);
+    }
+    Ok(Some((bundle, app.basket.len())))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Results,
+    Basket,
+}
+
+/// A list of symbols shown in the results pane, as entry positions.
+struct View {
+    title: String,
+    items: Vec<usize>,
+    state: ListState,
+}
+
+impl View {
+    fn new(title: String, items: Vec<usize>) -> Self {
+        let mut state = ListState::default();
+        if !items.is_empty() {
+            state.select(Some(0));
+        }
+        View {
+            title,
+            items,
+            state,
+        }
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .copied()
+    }
+}
+
+/// What an entry of the browser stands for.
+#[derive(Clone, Copy)]
+enum Item<'a> {
+    Symbol(&'a Symbol),
+    /// A whole file, by path
+    File(&'a str),
+}
+
+struct Entry<'a> {
+    item: Item<'a>,
+    label: String,
+    /// Estimated tokens of its source
+    tokens: usize,
+}
+
+struct App<'a> {
+    index: &'a Index,
+    entries: Vec<Entry<'a>>,
+    by_hash: HashMap<String, usize>,
+    query: String,
+    searching: bool,
+    /// The search results at the bottom, followed by the dependency and user
+    /// lists navigated into.
+    views: Vec<View>,
+    basket: Vec<usize>,
+    basket_state: ListState,
+    focus: Focus,
+    sources: HashMap<String, Vec<u8>>,
+    exclusions: Exclusions,
+}
+
+impl<'a> App<'a> {
+    fn new(index: &'a Index, filter: &FileFilter, exclusions: Exclusions) -> Self {
+        let mut entries: Vec<Entry> = index
+            .symbols
+            .values()
+            .filter(|sym| sym.is_code() && !sym.name.is_empty())
+            .filter(|sym| filter.allows(index, &sym.file_path))
+            .filter(|sym| !exclusions.excludes(index, sym))
+            .map(|sym| Entry {
+                item: Item::Symbol(sym),
+                label: format!(
+                    "{} {}",
+                    kind_label(&sym.node_kind),
+                    qualified_name(index, sym)
+                ),
+                tokens: estimate_tokens(sym.end_byte - sym.start_byte),
+            })
+            .collect();
+        entries.extend(
+            index
+                .file_hashes
+                .keys()
+                .filter(|path| !is_document_file(path) && filter.allows(index, path))
+                .map(|path| Entry {
+                    item: Item::File(path),
+                    label: format!("file {}", path.trim_start_matches("./")),
+                    tokens: fs::metadata(path)
+                        .map_or(0, |meta| estimate_tokens(meta.len() as usize)),
+                }),
+        );
+        entries.sort_by(|a, b| {
+            a.label.cmp(&b.label).then_with(|| match (a.item, b.item) {
+                (Item::Symbol(a), Item::Symbol(b)) => symbol_order(a, b),
+                _ => Ordering::Equal,
+            })
+        });
+        let by_hash = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| match entry.item {
+                Item::Symbol(sym) => Some((sym.hash(), i)),
+                Item::File(_) => None,
+            })
+            .collect();
+
+        let mut app = App {
+            index,
+            entries,
+            by_hash,
+            query: String::new(),
+            searching: false,
+            views: Vec::new(),
+            basket: Vec::new(),
+            basket_state: ListState::default(),
+            focus: Focus::Results,
+            sources: HashMap::new(),
+            exclusions,
+        };
+        app.search();
+        app
+    }
+
+    /// Handles input until the user finishes (`true`) or aborts (`false`).
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<bool, ContextMeshError> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(false);
+            }
+            if self.searching {
+                self.handle_search_key(key);
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(true),
+                KeyCode::Char('/') => {
+                    self.searching = true;
+                    self.focus = Focus::Results;
+                }
+                KeyCode::Tab => {
+                    self.focus = match self.focus {
+                        Focus::Results if !self.basket.is_empty() => Focus::Basket,
+                        _ => Focus::Results,
+                    };
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::PageDown => self.move_selection(10),
+                KeyCode::PageUp => self.move_selection(-10),
+                KeyCode::Char(' ') => self.toggle_basket(),
+                KeyCode::Char('d') => self.navigate(true),
+                KeyCode::Char('u') => self.navigate(false),
+                KeyCode::Backspace | KeyCode::Char('h') | KeyCode::Left if self.views.len() > 1 => {
+                    self.views.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc => self.searching = false,
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.search();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.search();
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces all views with the entries matching the query (see
+    /// [`fuzzy_score`]), the best matches first.
+    fn search(&mut self) {
+        let mut scored: Vec<(i64, usize)> = (0..self.entries.len())
+            .filter_map(|i| Some((fuzzy_score(&self.query, &self.entries[i].label)?, i)))
+            .collect();
+        scored.sort_by_key(|&(score, i)| (Reverse(score), i));
+        let items = scored.into_iter().map(|(_, i)| i).collect();
+        self.views = vec![View::new("Symbols and files".to_string(), items)];
+    }
+
+    /// Opens the dependencies or the users of the selected symbol.
+    fn navigate(&mut self, dependencies: bool) {
+        let Some(selected) = self.selected() else {
+            return;
+        };
+        let Item::Symbol(symbol) = self.entries[selected].item else {
+            return;
+        };
+        let ids: &HashSet<SymbolId> = if dependencies {
+            &symbol.dependencies
+        } else {
+            &symbol.used_by
+        };
+        let mut items: Vec<usize> = ids
+            .iter()
+            .filter_map(|id| self.index.hash_of(*id))
+            .filter_map(|hash| self.by_hash.get(hash).copied())
+            .collect();
+        items.sort_unstable();
+        let title = format!(
+            "{} of {}",
+            if dependencies {
+                "Dependencies"
+            } else {
+                "Users"
+            },
+            symbol.name
+        );
+        self.focus = Focus::Results;
+        self.views.push(View::new(title, items));
+    }
+
+    fn view(&mut self) -> &mut View {
+        self.views
+            .last_mut()
+            .expect("the search results are always present")
+    }
+
+    fn selected(&self) -> Option<usize> {
+        match self.focus {
+            Focus::Results => self.views.last().and_then(View::selected),
+            Focus::Basket => self
+                .basket_state
+                .selected()
+                .and_then(|i| self.basket.get(i))
+                .copied(),
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (len, state) = match self.focus {
+            Focus::Results => {
+                let view = self.view();
+                (view.items.len(), &mut view.state)
+            }
+            Focus::Basket => (self.basket.len(), &mut self.basket_state),
+        };
+        if len == 0 {
+            return;
+        }
+        let current = state.selected().unwrap_or(0) as isize;
+        state.select(Some((current + delta).clamp(0, len as isize - 1) as usize));
+    }
+
+    fn toggle_basket(&mut self) {
+        let Some(selected) = self.selected() else {
+            return;
+        };
+        match self.basket.iter().position(|&i| i == selected) {
+            Some(position) => {
+                self.basket.remove(position);
+                if self.basket.is_empty() {
+                    self.focus = Focus::Results;
+                    self.basket_state.select(None);
+                } else if self.basket_state.selected() >= Some(self.basket.len()) {
+                    self.basket_state.select(Some(self.basket.len() - 1));
+                }
+            }
+            None => {
+                self.basket.push(selected);
+                if self.basket_state.selected().is_none() {
+                    self.basket_state.select(Some(0));
+                }
+            }
+        }
+    }
+
+    /// The files in the basket.
+    fn basket_files(&self) -> HashSet<&'a str> {
+        self.basket
+            .iter()
+            .filter_map(|&i| match self.entries[i].item {
+                Item::File(path) => Some(path),
+                Item::Symbol(_) => None,
+            })
+            .collect()
+    }
+
+    /// The basket symbols in source order, leaving out those inside another one
+    /// or in a file of the basket.
+    fn basket_symbols(&self) -> Vec<&'a Symbol> {
+        let files = self.basket_files();
+        let mut symbols: Vec<&Symbol> = self
+            .basket
+            .iter()
+            .filter_map(|&i| match self.entries[i].item {
+                Item::Symbol(sym) => Some(sym),
+                Item::File(_) => None,
+            })
+            .filter(|sym| !files.contains(&*sym.file_path))
+            .collect();
+        symbols.sort_by(|a, b| {
+            (&a.file_path, a.start_byte, std::cmp::Reverse(a.end_byte)).cmp(&(
+                &b.file_path,
+                b.start_byte,
+                std::cmp::Reverse(b.end_byte),
+            ))
+        });
+        let mut outermost: Vec<&Symbol> = Vec::new();
+        for sym in symbols {
+            let nested = outermost.last().is_some_and(|outer| {
+                outer.file_path == sym.file_path && sym.end_byte <= outer.end_byte
+            });
+            if !nested {
+                outermost.push(sym);
+            }
+        }
+        outermost
+    }
+
+    /// The whole files and the symbols of the basket, files after those they
+    /// depend on (see [`Index::dependency_order`]).
+    fn bundle(&self) -> String {
+        let files = self.basket_files();
+        let symbols = self.basket_symbols();
+        let mut paths: Vec<&str> = files
+            .iter()
+            .copied()
+            .chain(symbols.iter().map(|sym| &*sym.file_path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        let mut bundle = Bundle::default();
+        for path in self.index.dependency_order(&paths) {
+            if files.contains(path) {
+                bundle.add_file(path);
+                continue;
+            }
+            for sym in symbols.iter().filter(|sym| *sym.file_path == *path) {
+                bundle.add_range(path, sym.start_byte..sym.end_byte);
+            }
+        }
+        self.exclusions.apply(self.index, &mut bundle);
+        bundle.render()
+    }
+
+    fn basket_tokens(&self) -> usize {
+        let file_tokens: usize = self
+            .basket
+            .iter()
+            .filter(|&&i| matches!(self.entries[i].item, Item::File(_)))
+            .map(|&i| self.entries[i].tokens)
+            .sum();
+        let symbol_tokens: usize = self
+            .basket_symbols()
+            .iter()
+            .map(|sym| estimate_tokens(sym.end_byte - sym.start_byte))
+            .sum();
+        file_tokens + symbol_tokens
+    }
+
+    fn preview(&mut self, entry: usize) -> String {
+        let item = self.entries[entry].item;
+        let path = match item {
+            Item::Symbol(sym) => &*sym.file_path,
+            Item::File(path) => path,
+        };
+        let source = self
+            .sources
+            .entry(path.to_string())
+            .or_insert_with(|| read_source(path).unwrap_or_default());
+        match item {
+            Item::Symbol(sym) => match source.get(sym.start_byte..sym.end_byte) {
+                Some(slice) => String::from_utf8_lossy(slice).into_owned(),
+                None => sym.signature.clone(),
+            },
+            Item::File(_) => String::from_utf8_lossy(source).into_owned(),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search_area, main_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [results_area, side_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(main_area);
+        let [preview_area, basket_area] =
+            Layout::vertical([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .areas(side_area);
+
+        let search_style = if self.searching {
+            Style::new().yellow()
+        } else {
+            Style::new()
+        };
+        let search = Paragraph::new(self.query.as_str())
+            .block(Block::bordered().title("Search").border_style(search_style));
+        frame.render_widget(search, search_area);
+
+        let focused = Style::new().cyan();
+        let highlight = Style::new().add_modifier(Modifier::REVERSED);
+        let in_basket: HashSet<usize> = self.basket.iter().copied().collect();
+
+        let results_focused = self.focus == Focus::Results;
+        let view = self
+            .views
+            .last_mut()
+            .expect("the search results are always present");
+        let items: Vec<ListItem> = view
+            .items
+            .iter()
+            .map(|&i| {
+                let marker = if in_basket.contains(&i) { "+ " } else { "  " };
+                let entry = &self.entries[i];
+                ListItem::new(format!("{}{}  ~{}", marker, entry.label, entry.tokens))
+            })
+            .collect();
+        let results = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(format!("{} ({})", view.title, view.items.len()))
+                    .border_style(if results_focused {
+                        focused
+                    } else {
+                        Style::new()
+                    }),
+            )
+            .highlight_style(highlight);
+        frame.render_stateful_widget(results, results_area, &mut view.state);
+
+        let (preview_title, preview) = match self.selected() {
+            Some(entry) => {
+                let title = match self.entries[entry].item {
+                    Item::Symbol(sym) => format!("{}:{}", sym.file_path, sym.line_number),
+                    Item::File(path) => path.to_string(),
+                };
+                (title, self.preview(entry))
+            }
+            None => ("Preview".to_string(), String::new()),
+        };
+        frame.render_widget(
+            Paragraph::new(preview).block(Block::bordered().title(preview_title)),
+            preview_area,
+        );
+
+        let basket_items: Vec<ListItem> = self
+            .basket
+            .iter()
+            .map(|&i| ListItem::new(self.entries[i].label.as_str()))
+            .collect();
+        let basket = List::new(basket_items)
+            .block(
+                Block::bordered()
+                    .title(format!(
+                        "Basket ({}, ~{} tokens)",
+                        self.basket.len(),
+                        self.basket_tokens()
+                    ))
+                    .border_style(if results_focused {
+                        Style::new()
+                    } else {
+                        focused
+                    }),
+            )
+            .highlight_style(if results_focused {
+                Style::new()
+            } else {
+                highlight
+            });
+        frame.render_stateful_widget(basket, basket_area, &mut self.basket_state);
+
+        frame.render_widget(Line::from(HELP).dim(), help_area);
+    }
+}
+
+/// How well `text` matches `query`, fzf-style, or `None` if it doesn't: each
+/// whitespace-separated term of the query must appear in `text` in order,
+/// though not necessarily adjacent, ignoring case. Runs of adjacent characters
+/// and matches at the start of a word score higher, gaps lower.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    for term in query.split_whitespace() {
+        let mut position = 0;
+        let mut previous: Option<usize> = None;
+        for wanted in term.chars().flat_map(char::to_lowercase) {
+            let found = (position..text.len())
+                .find(|&i| text[i].to_lowercase().eq(std::iter::once(wanted)))?;
+            score += 16;
+            if previous.is_some_and(|previous| previous + 1 == found) {
+                score += 8;
+            } else if let Some(previous) = previous {
+                score -= (found - previous - 1).min(8) as i64;
+            }
+            let word_start = found == 0
+                || !text[found - 1].is_alphanumeric()
+                || (text[found - 1].is_lowercase() && text[found].is_uppercase());
+            if word_start {
+                score += 12;
+            }
+            previous = Some(found);
+            position = found + 1;
+        }
+    }
+    Some(score)
+}
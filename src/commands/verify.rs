@@ -0,0 +1,56 @@
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::index::{Index, IntegrityReport};
+
+/// Checks the index of the selected profile against itself and the files on
+/// disk (see [`Index::check_integrity`]), reporting each problem, and with
+/// `repair` fixes what it can, saves the index, and reports the rest.
+pub fn handle_verify(repair: bool) -> Result<(), ContextMeshError> {
+    let mut index = Index::load_index()?;
+    let report = index.check_integrity();
+    if report.is_empty() {
+        println!("No problems found.");
+        return Ok(());
+    }
+    print_report(&report, "");
+    if !repair {
+        println!("Run `contextmesh verify --repair` to fix them.");
+        return Err(ContextMeshError::CheckFailed(report.len()));
+    }
+
+    let remaining = index.repair_integrity();
+    index.save_index(&Config::load()?.index)?;
+    println!(
+        "Repaired {} problem(s).",
+        report.len().saturating_sub(remaining.len())
+    );
+    if remaining.is_empty() {
+        return Ok(());
+    }
+    print_report(&remaining, "Not repaired: ");
+    if !remaining.stale_files.is_empty() {
+        println!(
+            "Run `contextmesh index` to parse the {} changed file(s) again.",
+            remaining.stale_files.len()
+        );
+    }
+    Err(ContextMeshError::CheckFailed(remaining.len()))
+}
+
+fn print_report(report: &IntegrityReport, prefix: &str) {
+    for violation in &report.invariants {
+        println!("{}{}", prefix, violation);
+    }
+    for path in &report.missing_files {
+        println!("{}{}: indexed, but no longer exists", prefix, path);
+    }
+    for path in &report.stale_files {
+        println!("{}{}: changed since it was indexed", prefix, path);
+    }
+    for (path, line, name) in &report.duplicates {
+        println!(
+            "{}{}:{}: '{}' is indexed more than once",
+            prefix, path, line, name
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::errors::ContextMeshError;
+use crate::index::EdgeConfidence;
+use crate::models::ModelProfile;
+use crate::sink::Sink;
+
+/// User configuration loaded from `.contextmesh/config.toml`.
+///
+/// Every section and key is optional; missing values fall back to their defaults so
+/// that a project without a config file behaves exactly like one with an empty file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Settings controlling how the index is stored on disk.
+    pub index: IndexConfig,
+
+    /// Per-language settings, keyed by language name (e.g. `[languages.rust]`).
+    pub languages: HashMap<String, LanguageConfig>,
+
+    /// Languages of file extensions or names for `index --language auto`
+    /// (`[file_types]`), e.g. `inc = "php"`; see [`crate::detect`].
+    pub file_types: HashMap<String, String>,
+
+    /// Dependency rules enforced by `contextmesh check` (`[[rules]]`).
+    pub rules: Vec<DependencyRule>,
+
+    /// Layers and allowed/forbidden edges checked by `contextmesh lint-arch`.
+    pub architecture: ArchitectureConfig,
+
+    /// Named context queries run by `contextmesh context --recipe <name>`
+    /// (`[recipes.<name>]`).
+    pub recipes: HashMap<String, Recipe>,
+
+    /// Names symbols are known by in other languages (`[[aliases]]`), for FFI
+    /// bindings whose attributes don't say.
+    pub aliases: Vec<Alias>,
+
+    /// Which files are too large or not text enough to index, and whether links
+    /// are followed to find them (`[files]`).
+    pub files: FilesConfig,
+
+    /// Which files count as generated code (`[generated]`).
+    pub generated: GeneratedConfig,
+
+    /// Which files count as vendored or third-party code (`[third_party]`).
+    pub third_party: ThirdPartyConfig,
+
+    /// What to leave out of the index to keep it small (`[prune]`).
+    pub prune: PruneConfig,
+
+    /// The roots reachability is computed from (`[entry_points]`).
+    pub entry_points: EntryPointsConfig,
+
+    /// Secrets masked in bundles before they leave the tool (`[redaction]`).
+    pub redaction: RedactionConfig,
+
+    /// Where bundles go (`[output]`); see [`crate::sink`].
+    pub output: OutputConfig,
+
+    /// Symbols never put in bundles (`[exclude]`).
+    pub exclude: ExcludeConfig,
+
+    /// The LLM `contextmesh ask` sends questions to (`[llm]`).
+    pub llm: LlmConfig,
+
+    /// Context window profiles of models, for `--model <name>`
+    /// (`[models.<name>]`); see [`crate::models`].
+    pub models: HashMap<String, ModelProfile>,
+}
+
+/// The `[index]` section of the config file.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexConfig {
+    /// Whether the on-disk index is zstd-compressed.
+    pub compression: bool,
+
+    /// The zstd compression level (1-22); higher is smaller but slower.
+    pub compression_level: i32,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        IndexConfig {
+            compression: true,
+            compression_level: 3,
+        }
+    }
+}
+
+/// The `[files]` section of the config file; see [`crate::limits`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesConfig {
+    /// Files larger than this many bytes aren't indexed.
+    pub max_bytes: u64,
+
+    /// File globs never indexed, e.g. `**/*.min.js`.
+    pub skip: Vec<String>,
+
+    /// Whether to leave out files that look binary (a NUL byte near the start).
+    pub skip_binary: bool,
+
+    /// Whether symbolic links are followed when collecting the files to index.
+    pub follow_symlinks: bool,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        FilesConfig {
+            max_bytes: 2_000_000,
+            skip: Vec::new(),
+            skip_binary: true,
+            follow_symlinks: true,
+        }
+    }
+}
+
+/// The `[generated]` section of the config file; see [`crate::generated`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeneratedConfig {
+    /// File globs of generated files, e.g. `src/bindings/**`.
+    pub paths: Vec<String>,
+
+    /// Whether to also detect generated files by their names and header comments.
+    pub detect: bool,
+}
+
+impl Default for GeneratedConfig {
+    fn default() -> Self {
+        GeneratedConfig {
+            paths: Vec::new(),
+            detect: true,
+        }
+    }
+}
+
+/// The `[third_party]` section of the config file; see [`crate::third_party`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThirdPartyConfig {
+    /// File globs of third-party files, e.g. `libs/upstream/**`.
+    pub paths: Vec<String>,
+
+    /// Whether to also detect them by vendor directories and license headers.
+    pub detect: bool,
+
+    /// Copyright holders whose headers mark the project's own code, e.g.
+    /// `Acme Inc.`, for projects with several.
+    pub own_copyright: Vec<String>,
+}
+
+impl Default for ThirdPartyConfig {
+    fn default() -> Self {
+        ThirdPartyConfig {
+            paths: Vec::new(),
+            detect: true,
+            own_copyright: Vec::new(),
+        }
+    }
+}
+
+/// The `[prune]` section of the config file. The policies are applied after
+/// every index run and by `contextmesh prune`.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PruneConfig {
+    /// Whether to drop the doc comments of symbols.
+    pub drop_docs: bool,
+
+    /// File globs whose symbols are dropped, e.g. `vendor/**`.
+    pub exclude: Vec<String>,
+
+    /// Most symbols kept per file; top-level symbols are kept first, then
+    /// symbols in source order.
+    pub max_symbols_per_file: Option<usize>,
+}
+
+impl PruneConfig {
+    /// Whether any policy is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.drop_docs || !self.exclude.is_empty() || self.max_symbols_per_file.is_some()
+    }
+}
+
+/// The `[redaction]` section of the config file; see [`crate::redact`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    /// Whether bundles are scrubbed before being copied or written.
+    pub enabled: bool,
+
+    /// Names of built-in patterns to turn off, e.g. `jwt`.
+    pub disable: Vec<String>,
+
+    /// Additional patterns (`[[redaction.patterns]]`).
+    pub patterns: Vec<RedactionPattern>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        RedactionConfig {
+            enabled: true,
+            disable: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// The `[output]` section of the config file; see [`crate::sink`].
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// The sinks bundles are sent to, the first that works, e.g.
+    /// `["osc52", "stdout"]`.
+    pub sinks: Option<Vec<Sink>>,
+
+    /// The file of the `file` sink, relative to the project root.
+    pub file: Option<String>,
+}
+
+/// The `[exclude]` section of the config file: what is kept out of bundles
+/// besides the definitions marked with a `contextmesh:ignore` comment.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExcludeConfig {
+    /// Module or file globs of the symbols to leave out, e.g.
+    /// `crate::tables::**` or `src/**/*_table.rs`.
+    pub symbols: Vec<String>,
+}
+
+/// A pattern of secrets to mask: all of a match, or only its first capture
+/// group if it has one (e.g. the value of `password = "..."`).
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionPattern {
+    /// Reported for each match, e.g. `internal-token`.
+    pub name: String,
+    pub regex: String,
+}
+
+/// The `[llm]` section of the config file; see [`crate::llm`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmConfig {
+    /// `anthropic` or `openai`.
+    pub provider: String,
+
+    /// Model name; defaults to a current general-purpose model of the provider.
+    pub model: Option<String>,
+
+    /// Base URL of the API, e.g. `http://localhost:8080` for a local server
+    /// speaking the provider's API.
+    pub endpoint: Option<String>,
+
+    /// Environment variable holding the API key; `ANTHROPIC_API_KEY` or
+    /// `OPENAI_API_KEY` by default.
+    pub api_key_env: Option<String>,
+
+    /// Most tokens of an answer.
+    pub max_tokens: u32,
+
+    /// Token budget of the code sent with a question.
+    pub budget: usize,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        LlmConfig {
+            provider: "anthropic".to_string(),
+            model: None,
+            endpoint: None,
+            api_key_env: None,
+            max_tokens: 2048,
+            budget: 12000,
+        }
+    }
+}
+
+/// The `[entry_points]` section of the config file: the symbols the rest of the
+/// code is reached from, such as `main`, request handlers, or exported APIs.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EntryPointsConfig {
+    /// Module globs (`crate::handlers::*`) or file globs, as in `[architecture]`.
+    pub symbols: Vec<String>,
+
+    /// Attributes marking entry points, e.g. `test` or `tokio::main`; arguments
+    /// are ignored, so `get` also matches `get("/users")`.
+    pub attributes: Vec<String>,
+
+    /// Whether `main` functions and items exported through FFI bindings are
+    /// entry points too.
+    pub detect: bool,
+}
+
+impl Default for EntryPointsConfig {
+    fn default() -> Self {
+        EntryPointsConfig {
+            symbols: Vec::new(),
+            attributes: Vec::new(),
+            detect: true,
+        }
+    }
+}
+
+/// A `[languages.<name>]` section of the config file.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LanguageConfig {
+    /// Replaces the language's built-in set of node kinds indexed as symbols.
+    pub kinds: Option<Vec<String>>,
+
+    /// Node kinds to index in addition to the base set (e.g. `let_declaration`).
+    pub extra_kinds: Vec<String>,
+
+    /// Node kinds to leave out of the base set (e.g. `field_declaration`).
+    pub skip_kinds: Vec<String>,
+
+    /// Program and arguments of an external indexer for a language without a
+    /// built-in one; see [`crate::parser::external`] for the protocol.
+    pub command: Option<Vec<String>>,
+
+    /// Directory with tree-sitter query files (`tags.scm`, optionally `imports.scm`)
+    /// defining the language declaratively; see [`crate::parser::query`]. Python
+    /// and Scala default to the built-in queries.
+    pub queries: Option<String>,
+
+    /// Grammar used with `queries`: a bundled one (`rust`, `python`, `elixir`) or the
+    /// path of a compiled grammar library. Defaults to the language name. Zig is
+    /// indexed with the library of tree-sitter-zig set here; Elixir with the bundled
+    /// grammar unless another is set.
+    /// A library runs with the user's rights once loaded, like `command` does.
+    pub grammar: Option<String>,
+
+    /// File extensions (without the dot) of a language indexed by an external
+    /// command, by queries, or, without either, by its definitions only.
+    /// Well-known languages (e.g. `scala`) default to their usual extensions.
+    pub extensions: Vec<String>,
+
+    /// For a language indexed by its definitions only: whether to find them
+    /// with universal-ctags if it is installed (the default), rather than with
+    /// the keyword regex.
+    pub ctags: Option<bool>,
+
+    /// Rust only: after indexing, resolve references precisely with
+    /// `rust-analyzer scip`, keeping the name-based resolution if it isn't
+    /// installed or fails.
+    pub rust_analyzer: bool,
+}
+
+/// A `[[rules]]` entry: symbols in `from` must not depend on symbols in `deny`,
+/// except those in `allow`.
+///
+/// Patterns are globs over qualified names (`crate::commands`) or, if they don't
+/// start with `crate`, file paths (`src/parser/`), and cover everything inside
+/// what they match, as in the `[architecture]` section (see [`crate::arch`]).
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DependencyRule {
+    pub from: String,
+    pub deny: Vec<String>,
+    pub allow: Vec<String>,
+
+    /// Explanation printed with violations, e.g. "commands must not depend on
+    /// parser internals".
+    pub message: Option<String>,
+}
+
+/// The `[architecture]` section of the config file; see [`crate::arch`].
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchitectureConfig {
+    /// Named groups of modules (`[[architecture.layers]]`).
+    pub layers: Vec<Layer>,
+
+    /// Forbidden edges (`[[architecture.deny]]`).
+    pub deny: Vec<EdgeRule>,
+
+    /// Edges allowed despite a `deny` rule or layer restriction
+    /// (`[[architecture.allow]]`).
+    pub allow: Vec<EdgeRule>,
+}
+
+/// A named group of modules, e.g. `commands = ["crate::commands::**"]`.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layer {
+    pub name: String,
+
+    /// Module or file globs of the symbols in the layer.
+    pub modules: Vec<String>,
+
+    /// The other layers this layer may depend on; any if unset. Symbols outside
+    /// every layer may always be used.
+    pub may_use: Option<Vec<String>>,
+}
+
+/// An edge between symbols matching `from` and `to`, each a layer name or a
+/// module or file glob.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EdgeRule {
+    pub from: String,
+    pub to: String,
+
+    /// Explanation printed with violations.
+    pub message: Option<String>,
+}
+
+/// A `[recipes.<name>]` section: a saved selection of context.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Recipe {
+    /// Shown by `contextmesh context --list`.
+    pub description: Option<String>,
+
+    /// Symbols to include: module globs (`crate::index::**`, or `indexer::*` for a
+    /// module at any depth), file globs (`src/parser/*.rs`), or symbol names.
+    pub symbols: Vec<String>,
+
+    /// Files included whole, e.g. `docs/INDEXING.md`.
+    pub files: Vec<String>,
+
+    /// Entry symbols, matched like `symbols`; only code reachable from them is
+    /// included, and without `symbols` all of it is.
+    pub from: Vec<String>,
+
+    /// Only follow dependencies from `from` resolved at least this surely:
+    /// `exact`, `imported`, or `name-only` (every edge, the default).
+    pub min_confidence: Option<EdgeConfidence>,
+
+    /// Bazel or Buck target labels (`//services/foo:lib`); only code of these
+    /// targets and their direct deps is included, and without `symbols` all of it.
+    pub targets: Vec<String>,
+
+    /// Token budget; the highest-ranked symbols that fit are included.
+    pub budget: Option<usize>,
+
+    /// Model profile whose context window sets the budget if `budget` isn't
+    /// given, e.g. `claude-sonnet`.
+    pub model: Option<String>,
+
+    /// Rank symbols with more branching higher within the budget.
+    pub prefer_complex: bool,
+}
+
+/// An `[[aliases]]` entry: code in other languages refers to the symbols
+/// matching `symbol` by `names`, e.g. a Rust type exposed to Python under a name
+/// set at runtime.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Alias {
+    /// A module glob (`crate::bindings::PyIndex`) or file glob, as in
+    /// `[architecture]`.
+    pub symbol: String,
+    pub names: Vec<String>,
+}
+
+impl LanguageConfig {
+    /// The node kinds to index as symbols, given the language's built-in set.
+    pub fn definition_kinds(&self, defaults: &[&str]) -> BTreeSet<String> {
+        let mut kinds: BTreeSet<String> = match &self.kinds {
+            Some(kinds) => kinds.iter().cloned().collect(),
+            None => defaults.iter().map(|kind| kind.to_string()).collect(),
+        };
+        kinds.extend(self.extra_kinds.iter().cloned());
+        for kind in &self.skip_kinds {
+            kinds.remove(kind);
+        }
+        kinds
+    }
+}
+
+impl Config {
+    pub const CONFIG_FILE_PATH: &'static str = ".contextmesh/config.toml";
+
+    /// Loads the config file, returning defaults if it does not exist.
+    pub fn load() -> Result<Self, ContextMeshError> {
+        if !Path::new(Self::CONFIG_FILE_PATH).exists() {
+            return Ok(Config::default());
+        }
+
+        let text = fs::read_to_string(Self::CONFIG_FILE_PATH)?;
+        toml::from_str(&text).map_err(|e| {
+            ContextMeshError::ConfigError(format!("{}: {}", Self::CONFIG_FILE_PATH, e))
+        })
+    }
+
+    /// The settings for `language`, or defaults if it has no section.
+    pub fn language(&self, language: &str) -> LanguageConfig {
+        self.languages
+            .get(&language.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,887 @@
+//! Datalog rules over the symbol graph, in the style of Soufflé.
+//!
+//! Rule files derive relations from the base relations the index provides,
+//! with symbols identified by their qualified names:
+//!
+//! ```text
+//! symbol(S)  name(S, Name)  kind(S, Kind)  file(S, Path)  module(S, Module)
+//! visibility(S, V)  attribute(S, Attr)  parent(S, P)  depends(S, T)
+//! confidence(S, T, C)  generated(S)  third_party(S)  unresolved(S, Name)  entry_point(S)
+//! reachable(S)  evolves_with(File, File)
+//! ```
+//!
+//! `confidence` gives how sure each `depends` edge is: `name-only`, `imported`, or
+//! `exact`.
+//!
+//! For example:
+//!
+//! ```text
+//! .decl reaches(from: symbol, to: symbol)
+//! reaches(X, Y) :- depends(X, Y).
+//! reaches(X, Z) :- reaches(X, Y), depends(Y, Z).
+//! cycle(X) :- reaches(X, X).
+//! leaks(X, Y) :- depends(X, Y), module(X, M), match("crate::parser.*", M),
+//!                module(Y, N), contains("commands", N), !generated(X).
+//! .output cycle, leaks
+//! ```
+//!
+//! Body literals are atoms, negated atoms (`!rel(...)`) over relations that don't
+//! depend on the rule's own, `X = Y`, `X != Y`, `match("regex", X)`, and
+//! `contains("text", X)`. Every variable has to appear in a positive atom.
+//! Evaluation is bottom-up and semi-naive, one stratum at a time.
+
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+use crate::arch::qualified_name;
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::symbol::Visibility;
+use crate::utils::module_path;
+
+/// The base relations [`Database::from_index`] fills, with their arities.
+pub const BASE_RELATIONS: &[(&str, usize)] = &[
+    ("symbol", 1),
+    ("name", 2),
+    ("kind", 2),
+    ("file", 2),
+    ("module", 2),
+    ("visibility", 2),
+    ("attribute", 2),
+    ("parent", 2),
+    ("depends", 2),
+    ("confidence", 3),
+    ("generated", 1),
+    ("third_party", 1),
+    ("unresolved", 2),
+    ("entry_point", 1),
+    ("reachable", 1),
+    ("evolves_with", 2),
+];
+
+/// Rules and directives parsed from one or more rule files.
+#[derive(Debug, Default)]
+pub struct Program {
+    rules: Vec<Rule>,
+    /// Relations named by `.output`, in order
+    pub outputs: Vec<String>,
+    /// Column names from `.decl`
+    declarations: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    head: Atom,
+    body: Vec<Literal>,
+}
+
+#[derive(Debug, Clone)]
+struct Atom {
+    relation: String,
+    args: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Variable(String),
+    Constant(String),
+    Wildcard,
+}
+
+#[derive(Debug, Clone)]
+enum Literal {
+    Positive(Atom),
+    Negative(Atom),
+    Equal(Term, Term),
+    NotEqual(Term, Term),
+    Match(Term, Term),
+    Contains(Term, Term),
+}
+
+impl Program {
+    /// Adds the rules and directives of `source`, named `origin` in errors.
+    pub fn parse(&mut self, source: &str, origin: &str) -> Result<(), ContextMeshError> {
+        let tokens = tokenize(source).map_err(|e| rule_error(origin, e))?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        parser.program(self).map_err(|e| rule_error(origin, e))
+    }
+
+    /// Relations derived by the rules, in the order they're first defined.
+    pub fn derived(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.rules
+            .iter()
+            .map(|rule| rule.head.relation.as_str())
+            .filter(|relation| seen.insert(*relation))
+            .collect()
+    }
+
+    /// Column names of `relation`: declared ones, or `1`, `2`, ... by position.
+    pub fn columns(&self, relation: &str, arity: usize) -> Vec<String> {
+        match self.declarations.get(relation) {
+            Some(columns) if columns.len() == arity => columns.clone(),
+            _ => (1..=arity).map(|i| i.to_string()).collect(),
+        }
+    }
+}
+
+type Tuple = Vec<u32>;
+
+/// Facts by relation, with every value interned.
+#[derive(Debug, Default)]
+pub struct Database {
+    strings: Vec<String>,
+    ids: HashMap<String, u32>,
+    relations: HashMap<String, HashSet<Tuple>>,
+    arities: HashMap<String, usize>,
+}
+
+impl Database {
+    /// The base relations of `index`.
+    pub fn from_index(index: &Index) -> Self {
+        let mut db = Database::default();
+        for (relation, arity) in BASE_RELATIONS {
+            db.relations.insert(relation.to_string(), HashSet::new());
+            db.arities.insert(relation.to_string(), *arity);
+        }
+
+        let names: HashMap<&str, String> = index
+            .symbols
+            .iter()
+            .map(|(hash, sym)| (hash.as_str(), qualified_name(index, sym)))
+            .collect();
+        for (hash, sym) in &index.symbols {
+            let id = names[hash.as_str()].as_str();
+            let path = sym.file_path.trim_start_matches("./");
+            db.insert("symbol", &[id]);
+            db.insert("name", &[id, &sym.name]);
+            db.insert("kind", &[id, &sym.node_kind]);
+            db.insert("file", &[id, path]);
+            db.insert("module", &[id, &module_path(path)]);
+            let visibility = match &sym.visibility {
+                Visibility::Public => "pub",
+                Visibility::Crate => "crate",
+                Visibility::Private => "private",
+                Visibility::Restricted(_) => "restricted",
+            };
+            db.insert("visibility", &[id, visibility]);
+            for attribute in &sym.attributes {
+                db.insert("attribute", &[id, attribute]);
+            }
+            if let Some(parent) = sym.parent.and_then(|p| index.hash_of(p)) {
+                if let Some(parent) = names.get(parent) {
+                    db.insert("parent", &[id, parent]);
+                }
+            }
+            for dep_hash in sym.dependencies.iter().filter_map(|d| index.hash_of(*d)) {
+                if let Some(dependency) = names.get(dep_hash) {
+                    db.insert("depends", &[id, dependency]);
+                    let confidence = index.edge_confidence(hash, dep_hash).name();
+                    db.insert("confidence", &[id, dependency, confidence]);
+                }
+            }
+            if index.is_generated(sym) {
+                db.insert("generated", &[id]);
+            }
+            if index.is_third_party(sym) {
+                db.insert("third_party", &[id]);
+            }
+            if index.entry_points.contains(hash) {
+                db.insert("entry_point", &[id]);
+            }
+            if index.is_reachable(hash) {
+                db.insert("reachable", &[id]);
+            }
+        }
+        for (path, edges) in &index.co_changes {
+            for (other, _) in edges {
+                db.insert("evolves_with", &[path, other]);
+            }
+        }
+        for (caller, name) in index.unresolved_references() {
+            let caller = qualified_name(index, caller);
+            db.insert("unresolved", &[&caller, name]);
+        }
+        db
+    }
+
+    fn intern(&mut self, value: &str) -> u32 {
+        if let Some(&id) = self.ids.get(value) {
+            return id;
+        }
+        let id = self.strings.len() as u32;
+        self.strings.push(value.to_string());
+        self.ids.insert(value.to_string(), id);
+        id
+    }
+
+    fn insert(&mut self, relation: &str, values: &[&str]) {
+        let tuple: Tuple = values.iter().map(|value| self.intern(value)).collect();
+        self.relations
+            .entry(relation.to_string())
+            .or_default()
+            .insert(tuple);
+    }
+
+    /// Number of columns of `relation`, if it exists.
+    pub fn arity(&self, relation: &str) -> Option<usize> {
+        self.arities.get(relation).copied()
+    }
+
+    /// The tuples of `relation`, sorted.
+    pub fn tuples(&self, relation: &str) -> Vec<Vec<&str>> {
+        let mut tuples: Vec<Vec<&str>> = self
+            .relations
+            .get(relation)
+            .into_iter()
+            .flatten()
+            .map(|tuple| {
+                tuple
+                    .iter()
+                    .map(|&id| self.strings[id as usize].as_str())
+                    .collect()
+            })
+            .collect();
+        tuples.sort();
+        tuples
+    }
+
+    /// Derives every relation of `program` into the database.
+    pub fn evaluate(&mut self, program: &Program) -> Result<(), ContextMeshError> {
+        for rule in &program.rules {
+            for atom in
+                std::iter::once(&rule.head).chain(rule.body.iter().filter_map(|l| match l {
+                    Literal::Positive(atom) | Literal::Negative(atom) => Some(atom),
+                    _ => None,
+                }))
+            {
+                match self.arities.get(&atom.relation) {
+                    Some(&arity) if arity != atom.args.len() => {
+                        return Err(ContextMeshError::RuleError(format!(
+                            "'{}' is used with {} and {} arguments",
+                            atom.relation,
+                            arity,
+                            atom.args.len()
+                        )));
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.arities.insert(atom.relation.clone(), atom.args.len());
+                        self.relations.entry(atom.relation.clone()).or_default();
+                    }
+                }
+            }
+        }
+
+        for stratum in stratify(program)? {
+            let compiled = stratum
+                .iter()
+                .map(|rule| self.compile(rule))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.evaluate_stratum(&compiled);
+        }
+        Ok(())
+    }
+
+    /// Semi-naive evaluation: after a first pass over the full relations, each
+    /// round only joins in the tuples derived by the previous one.
+    fn evaluate_stratum(&mut self, rules: &[CompiledRule]) {
+        let derived: HashSet<&str> = rules.iter().map(|rule| rule.head.as_str()).collect();
+
+        let mut delta: HashMap<String, HashSet<Tuple>> = HashMap::new();
+        for rule in rules {
+            let sources: Vec<&HashSet<Tuple>> = rule
+                .atoms
+                .iter()
+                .map(|atom| &self.relations[&atom.relation])
+                .collect();
+            let found = self.solve(rule, &sources);
+            delta.entry(rule.head.clone()).or_default().extend(found);
+        }
+
+        loop {
+            let mut added = false;
+            for (relation, tuples) in &mut delta {
+                let full = self.relations.get_mut(relation).expect("declared");
+                tuples.retain(|tuple| full.insert(tuple.clone()));
+                added |= !tuples.is_empty();
+            }
+            if !added {
+                return;
+            }
+
+            let mut next: HashMap<String, HashSet<Tuple>> = HashMap::new();
+            for rule in rules {
+                for (i, atom) in rule.atoms.iter().enumerate() {
+                    if !derived.contains(atom.relation.as_str())
+                        || delta.get(&atom.relation).is_none_or(HashSet::is_empty)
+                    {
+                        continue;
+                    }
+                    let sources: Vec<&HashSet<Tuple>> = rule
+                        .atoms
+                        .iter()
+                        .enumerate()
+                        .map(|(j, other)| match j == i {
+                            true => &delta[&atom.relation],
+                            false => &self.relations[&other.relation],
+                        })
+                        .collect();
+                    let found = self.solve(rule, &sources);
+                    next.entry(rule.head.clone()).or_default().extend(found);
+                }
+            }
+            delta = next;
+        }
+    }
+
+    /// The head tuples of `rule` with its atoms matched against `sources`.
+    fn solve(&self, rule: &CompiledRule, sources: &[&HashSet<Tuple>]) -> HashSet<Tuple> {
+        let indexes: Vec<HashMap<Tuple, Vec<&Tuple>>> = rule
+            .atoms
+            .iter()
+            .zip(sources)
+            .map(|(atom, source)| {
+                let mut index: HashMap<Tuple, Vec<&Tuple>> = HashMap::new();
+                for tuple in source.iter() {
+                    let key = atom.key_columns.iter().map(|&c| tuple[c]).collect();
+                    index.entry(key).or_default().push(tuple);
+                }
+                index
+            })
+            .collect();
+
+        let mut found = HashSet::new();
+        let mut slots = vec![0; rule.slots];
+        self.join(rule, &indexes, 0, &mut slots, &mut found);
+        found
+    }
+
+    fn join(
+        &self,
+        rule: &CompiledRule,
+        indexes: &[HashMap<Tuple, Vec<&Tuple>>],
+        position: usize,
+        slots: &mut Vec<u32>,
+        found: &mut HashSet<Tuple>,
+    ) {
+        let Some(atom) = rule.atoms.get(position) else {
+            if rule.checks.iter().all(|check| self.check(check, slots)) {
+                found.insert(rule.head_args.iter().map(|arg| arg.value(slots)).collect());
+            }
+            return;
+        };
+
+        let key: Tuple = atom
+            .key_columns
+            .iter()
+            .map(|&c| atom.columns[c].value(slots))
+            .collect();
+        for tuple in indexes[position].get(&key).into_iter().flatten() {
+            let unifies =
+                atom.columns
+                    .iter()
+                    .zip(tuple.iter())
+                    .all(|(column, &value)| match column {
+                        Column::Bind(slot) => {
+                            slots[*slot] = value;
+                            true
+                        }
+                        Column::Check(slot) => slots[*slot] == value,
+                        Column::Key(_) | Column::Any => true,
+                    });
+            if unifies {
+                self.join(rule, indexes, position + 1, slots, found);
+            }
+        }
+    }
+
+    fn check(&self, check: &Check, slots: &[u32]) -> bool {
+        match check {
+            Check::Negative(relation, args) => {
+                let tuples = &self.relations[relation];
+                if args.iter().all(|arg| !matches!(arg, Arg::Any)) {
+                    let tuple: Tuple = args.iter().map(|arg| arg.value(slots)).collect();
+                    return !tuples.contains(&tuple);
+                }
+                !tuples.iter().any(|tuple| {
+                    args.iter()
+                        .zip(tuple)
+                        .all(|(arg, &value)| matches!(arg, Arg::Any) || arg.value(slots) == value)
+                })
+            }
+            Check::Equal(a, b) => a.value(slots) == b.value(slots),
+            Check::NotEqual(a, b) => a.value(slots) != b.value(slots),
+            Check::Match(regex, arg) => regex.is_match(&self.strings[arg.value(slots) as usize]),
+            Check::Contains(needle, haystack) => self.strings[haystack.value(slots) as usize]
+                .contains(self.strings[needle.value(slots) as usize].as_str()),
+        }
+    }
+
+    /// Assigns variables to slots and works out, for each atom, which columns
+    /// are known before it is matched.
+    fn compile(&mut self, rule: &Rule) -> Result<CompiledRule, ContextMeshError> {
+        let mut slots: HashMap<String, usize> = HashMap::new();
+        let mut atoms = Vec::new();
+
+        for literal in &rule.body {
+            let Literal::Positive(atom) = literal else {
+                continue;
+            };
+            let bound_before = slots.len();
+            let mut columns = Vec::new();
+            let mut key_columns = Vec::new();
+            for (c, term) in atom.args.iter().enumerate() {
+                columns.push(match term {
+                    Term::Wildcard => Column::Any,
+                    Term::Constant(value) => {
+                        key_columns.push(c);
+                        Column::Key(Arg::Constant(self.intern(value)))
+                    }
+                    Term::Variable(name) => match slots.get(name) {
+                        Some(&slot) if slot < bound_before => {
+                            key_columns.push(c);
+                            Column::Key(Arg::Slot(slot))
+                        }
+                        // Repeated within the atom
+                        Some(&slot) => Column::Check(slot),
+                        None => {
+                            let slot = slots.len();
+                            slots.insert(name.clone(), slot);
+                            Column::Bind(slot)
+                        }
+                    },
+                });
+            }
+            atoms.push(CompiledAtom {
+                relation: atom.relation.clone(),
+                columns,
+                key_columns,
+            });
+        }
+
+        let arg = |term: &Term, db: &mut Database| -> Result<Arg, ContextMeshError> {
+            match term {
+                Term::Wildcard => Ok(Arg::Any),
+                Term::Constant(value) => Ok(Arg::Constant(db.intern(value))),
+                Term::Variable(name) => {
+                    slots.get(name).map(|&slot| Arg::Slot(slot)).ok_or_else(|| {
+                        ContextMeshError::RuleError(format!(
+                            "variable {} in a rule for '{}' isn't bound by a positive atom",
+                            name, rule.head.relation
+                        ))
+                    })
+                }
+            }
+        };
+        let mut head_args = Vec::new();
+        for term in &rule.head.args {
+            match arg(term, self)? {
+                Arg::Any => {
+                    return Err(ContextMeshError::RuleError(format!(
+                        "the head of a rule for '{}' can't contain _",
+                        rule.head.relation
+                    )))
+                }
+                bound => head_args.push(bound),
+            }
+        }
+        let mut checks = Vec::new();
+        for literal in &rule.body {
+            checks.push(match literal {
+                Literal::Positive(_) => continue,
+                Literal::Negative(atom) => Check::Negative(
+                    atom.relation.clone(),
+                    atom.args
+                        .iter()
+                        .map(|term| arg(term, self))
+                        .collect::<Result<_, _>>()?,
+                ),
+                Literal::Equal(a, b) => Check::Equal(arg(a, self)?, arg(b, self)?),
+                Literal::NotEqual(a, b) => Check::NotEqual(arg(a, self)?, arg(b, self)?),
+                Literal::Match(Term::Constant(pattern), value) => Check::Match(
+                    Regex::new(&format!("^(?:{})$", pattern))
+                        .map_err(|e| ContextMeshError::RuleError(e.to_string()))?,
+                    arg(value, self)?,
+                ),
+                Literal::Match(..) => {
+                    return Err(ContextMeshError::RuleError(
+                        "the pattern of match() must be a string".to_string(),
+                    ))
+                }
+                Literal::Contains(needle, haystack) => {
+                    Check::Contains(arg(needle, self)?, arg(haystack, self)?)
+                }
+            });
+        }
+        if checks.iter().any(|check| match check {
+            Check::Equal(a, b) | Check::NotEqual(a, b) | Check::Contains(a, b) => {
+                matches!(a, Arg::Any) || matches!(b, Arg::Any)
+            }
+            Check::Match(_, arg) => matches!(arg, Arg::Any),
+            Check::Negative(..) => false,
+        }) {
+            return Err(ContextMeshError::RuleError(format!(
+                "comparisons in a rule for '{}' can't use _",
+                rule.head.relation
+            )));
+        }
+
+        Ok(CompiledRule {
+            head: rule.head.relation.clone(),
+            head_args,
+            slots: slots.len(),
+            atoms,
+            checks,
+        })
+    }
+}
+
+/// Groups the rules into strata so every relation used negatively is complete
+/// before the rules negating it run.
+fn stratify(program: &Program) -> Result<Vec<Vec<&Rule>>, ContextMeshError> {
+    let derived = program.derived();
+    let mut strata: HashMap<&str, usize> = derived.iter().map(|r| (*r, 0)).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for rule in &program.rules {
+            let mut level = strata[rule.head.relation.as_str()];
+            for literal in &rule.body {
+                let (atom, offset) = match literal {
+                    Literal::Positive(atom) => (atom, 0),
+                    Literal::Negative(atom) => (atom, 1),
+                    _ => continue,
+                };
+                if let Some(&other) = strata.get(atom.relation.as_str()) {
+                    level = level.max(other + offset);
+                }
+            }
+            if level > derived.len() {
+                return Err(ContextMeshError::RuleError(format!(
+                    "'{}' depends on its own negation",
+                    rule.head.relation
+                )));
+            }
+            if level != strata[rule.head.relation.as_str()] {
+                strata.insert(&rule.head.relation, level);
+                changed = true;
+            }
+        }
+    }
+
+    let depth = strata.values().copied().max().unwrap_or(0);
+    Ok((0..=depth)
+        .map(|level| {
+            program
+                .rules
+                .iter()
+                .filter(|rule| strata[rule.head.relation.as_str()] == level)
+                .collect()
+        })
+        .collect())
+}
+
+struct CompiledRule {
+    head: String,
+    head_args: Vec<Arg>,
+    slots: usize,
+    atoms: Vec<CompiledAtom>,
+    checks: Vec<Check>,
+}
+
+struct CompiledAtom {
+    relation: String,
+    columns: Vec<Column>,
+    /// Columns whose values are known before the atom is matched
+    key_columns: Vec<usize>,
+}
+
+enum Column {
+    /// Known beforehand, so part of the lookup key
+    Key(Arg),
+    /// First occurrence of a variable
+    Bind(usize),
+    /// A variable bound earlier in the same atom
+    Check(usize),
+    Any,
+}
+
+#[derive(Clone, Copy)]
+enum Arg {
+    Slot(usize),
+    Constant(u32),
+    Any,
+}
+
+impl Arg {
+    fn value(&self, slots: &[u32]) -> u32 {
+        match self {
+            Arg::Slot(slot) => slots[*slot],
+            Arg::Constant(value) => *value,
+            Arg::Any => unreachable!("wildcards are never read"),
+        }
+    }
+}
+
+impl Column {
+    fn value(&self, slots: &[u32]) -> u32 {
+        match self {
+            Column::Key(arg) => arg.value(slots),
+            _ => unreachable!("only key columns are read"),
+        }
+    }
+}
+
+enum Check {
+    Negative(String, Vec<Arg>),
+    Equal(Arg, Arg),
+    NotEqual(Arg, Arg),
+    Match(Regex, Arg),
+    Contains(Arg, Arg),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    Str(String),
+    Directive(String),
+    LeftParen,
+    RightParen,
+    Comma,
+    Dot,
+    Colon,
+    If,
+    Bang,
+    Equal,
+    NotEqual,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '(' => tokens.push(Token::LeftParen),
+            ')' => tokens.push(Token::RightParen),
+            ',' => tokens.push(Token::Comma),
+            ':' if chars.peek() == Some(&'-') => {
+                chars.next();
+                tokens.push(Token::If);
+            }
+            ':' => tokens.push(Token::Colon),
+            '!' if chars.peek() == Some(&'=') => {
+                chars.next();
+                tokens.push(Token::NotEqual);
+            }
+            '!' => tokens.push(Token::Bang),
+            '=' => tokens.push(Token::Equal),
+            '.' if chars.peek().is_some_and(|c| c.is_alphabetic()) => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_alphanumeric() {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Directive(name));
+            }
+            '.' => tokens.push(Token::Dot),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                        None => return Err("missing closing \"".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(text));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Identifier(word));
+            }
+            other => return Err(format!("unexpected '{}'", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or("unexpected end of file")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(format!("expected {:?} but found {:?}", expected, token)),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Identifier(name) => Ok(name),
+            token => Err(format!("expected a name but found {:?}", token)),
+        }
+    }
+
+    fn program(&mut self, program: &mut Program) -> Result<(), String> {
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Directive(directive) if directive == "decl" => {
+                    self.position += 1;
+                    let relation = self.identifier()?;
+                    self.expect(Token::LeftParen)?;
+                    let mut columns = Vec::new();
+                    while self.peek() != Some(&Token::RightParen) {
+                        columns.push(self.identifier()?);
+                        if self.peek() == Some(&Token::Colon) {
+                            self.position += 1;
+                            self.identifier()?;
+                        }
+                        if self.peek() == Some(&Token::Comma) {
+                            self.position += 1;
+                        }
+                    }
+                    self.position += 1;
+                    program.declarations.insert(relation, columns);
+                }
+                Token::Directive(directive) if directive == "output" => {
+                    self.position += 1;
+                    program.outputs.push(self.identifier()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        program.outputs.push(self.identifier()?);
+                    }
+                }
+                Token::Directive(directive) => {
+                    return Err(format!("unknown directive .{}", directive))
+                }
+                _ => {
+                    let head = self.atom()?;
+                    let mut body = Vec::new();
+                    if self.peek() == Some(&Token::If) {
+                        self.position += 1;
+                        body.push(self.literal()?);
+                        while self.peek() == Some(&Token::Comma) {
+                            self.position += 1;
+                            body.push(self.literal()?);
+                        }
+                    }
+                    self.expect(Token::Dot)?;
+                    program.rules.push(Rule { head, body });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn atom(&mut self) -> Result<Atom, String> {
+        let relation = self.identifier()?;
+        self.expect(Token::LeftParen)?;
+        let mut args = vec![self.term()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.position += 1;
+            args.push(self.term()?);
+        }
+        self.expect(Token::RightParen)?;
+        Ok(Atom { relation, args })
+    }
+
+    fn literal(&mut self) -> Result<Literal, String> {
+        if self.peek() == Some(&Token::Bang) {
+            self.position += 1;
+            return Ok(Literal::Negative(self.atom()?));
+        }
+        if let (Some(Token::Identifier(_)), Some(Token::LeftParen)) =
+            (self.peek(), self.tokens.get(self.position + 1))
+        {
+            let atom = self.atom()?;
+            return match (
+                atom.relation.as_str(),
+                <[Term; 2]>::try_from(atom.args.clone()),
+            ) {
+                ("match", Ok([pattern, value])) => Ok(Literal::Match(pattern, value)),
+                ("contains", Ok([needle, haystack])) => Ok(Literal::Contains(needle, haystack)),
+                ("match" | "contains", Err(_)) => {
+                    Err(format!("{}() takes two arguments", atom.relation))
+                }
+                _ => Ok(Literal::Positive(atom)),
+            };
+        }
+        let left = self.term()?;
+        match self.next()? {
+            Token::Equal => Ok(Literal::Equal(left, self.term()?)),
+            Token::NotEqual => Ok(Literal::NotEqual(left, self.term()?)),
+            token => Err(format!("expected = or != but found {:?}", token)),
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        match self.next()? {
+            Token::Str(text) => Ok(Term::Constant(text)),
+            Token::Identifier(name) if name == "_" => Ok(Term::Wildcard),
+            Token::Identifier(name) if name.starts_with(|c: char| c.is_uppercase() || c == '_') => {
+                Ok(Term::Variable(name))
+            }
+            // Numbers and bare words are constants
+            Token::Identifier(name) => Ok(Term::Constant(name)),
+            token => Err(format!(
+                "expected a variable or constant but found {:?}",
+                token
+            )),
+        }
+    }
+}
+
+fn rule_error(origin: &str, message: String) -> ContextMeshError {
+    ContextMeshError::RuleError(format!("{}: {}", origin, message))
+}
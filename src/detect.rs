@@ -0,0 +1,133 @@
+//! Detection of the language of each file, for `index --language auto`.
+//!
+//! A file's language is, in order of precedence:
+//!
+//! 1. the one the `[file_types]` config section maps its name or extension to
+//!    (`inc = "php"`, `Jenkinsfile = "groovy"`),
+//! 2. the one whose `[languages.<name>]` section lists its extension,
+//! 3. the built-in indexer handling its name or extension, or a well-known
+//!    language indexed by its definitions (see [`crate::parser::tags`]),
+//! 4. for files without an extension, the interpreter of its shebang line
+//!    (`#!/usr/bin/env python3`).
+//!
+//! Files of no detected language aren't indexed.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::parser::{components, css, docker, document, proto, shell, sql, structured, tags};
+use crate::utils::has_extension;
+
+/// Built-in indexers by the `--language` name that selects them, with the file
+/// extensions and names they handle.
+const BUILT_IN: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("markdown", document::EXTENSIONS),
+    ("config", structured::EXTENSIONS),
+    ("sql", sql::EXTENSIONS),
+    ("proto", proto::EXTENSIONS),
+    ("shell", shell::EXTENSIONS),
+    ("docker", docker::EXTENSIONS),
+    ("jsx", components::EXTENSIONS),
+    ("css", css::EXTENSIONS),
+];
+
+/// Interpreters named by shebang lines, and their languages.
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("dash", "shell"),
+    ("python", "python"),
+    ("node", "javascript"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("lua", "lua"),
+];
+
+/// Detects the language of files from their names, the config, and shebangs.
+pub struct LanguageDetector {
+    /// Languages by lowercase file name or extension, from `[file_types]` and
+    /// the `extensions` of `[languages.<name>]` sections
+    overrides: HashMap<String, String>,
+}
+
+impl LanguageDetector {
+    pub fn new(config: &Config) -> Self {
+        let mut overrides = HashMap::new();
+        for (language, language_config) in &config.languages {
+            for extension in &language_config.extensions {
+                overrides.insert(extension.to_lowercase(), language.to_lowercase());
+            }
+        }
+        // Explicit mappings win over the extensions of language sections
+        for (name, language) in &config.file_types {
+            overrides.insert(
+                name.trim_start_matches('.').to_lowercase(),
+                language.to_lowercase(),
+            );
+        }
+        LanguageDetector { overrides }
+    }
+
+    /// The language of `file_path`, or `None` if it isn't one to index.
+    pub fn detect(&self, file_path: &str) -> Option<String> {
+        let path = Path::new(file_path);
+        let file_name = path.file_name()?.to_str()?;
+        let extension = path.extension().and_then(|ext| ext.to_str());
+
+        let overridden = [Some(file_name), extension]
+            .into_iter()
+            .flatten()
+            .find_map(|key| self.overrides.get(&key.to_lowercase()));
+        if let Some(language) = overridden {
+            return Some(language.clone());
+        }
+        if let Some((language, _)) = BUILT_IN
+            .iter()
+            .find(|(_, extensions)| has_extension(file_name, extensions))
+        {
+            return Some(language.to_string());
+        }
+        match extension {
+            Some(extension) => tags::language_of_extension(extension).map(str::to_string),
+            None => shebang_language(file_path).map(str::to_string),
+        }
+    }
+
+    /// Groups `files` by their detected language, dropping those of none.
+    pub fn group(&self, files: Vec<String>) -> HashMap<String, Vec<String>> {
+        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+        for file in files {
+            if let Some(language) = self.detect(&file) {
+                groups.entry(language).or_default().push(file);
+            }
+        }
+        groups
+    }
+}
+
+/// The language of the interpreter a script's shebang line names, e.g. `python`
+/// for `#!/usr/bin/env python3` or `shell` for `#!/bin/bash -e`.
+fn shebang_language(file_path: &str) -> Option<&'static str> {
+    let mut first_line = String::new();
+    BufReader::new(File::open(file_path).ok()?)
+        .read_line(&mut first_line)
+        .ok()?;
+    let command = first_line.strip_prefix("#!")?;
+    let mut words = command.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    // `python3.12` is `python`
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS
+        .iter()
+        .find(|(interpreter, _)| *interpreter == program)
+        .map(|(_, language)| *language)
+}
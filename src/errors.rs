@@ -1,17 +1,193 @@
+use serde_json::json;
+use std::error::Error;
 use std::fmt;
 use std::io;
 
 /// Represents all possible errors in the ContextMesh application.
+///
+/// Every variant has a stable [code](ContextMeshError::code) that scripts can
+/// match on, and many have a [hint](ContextMeshError::hint) on how to fix them.
 #[derive(Debug)]
 pub enum ContextMeshError {
     IoError(io::Error),
     SerdeError(bincode::Error),
     TreeSitterError(String),
     UnsupportedLanguage(String),
+    /// A `contextmesh query` expression that doesn't parse.
+    QueryError(String),
+    /// A `contextmesh datalog` rule file that doesn't parse or can't be evaluated.
+    RuleError(String),
     SerializationError(String),
     DeserializationError(String),
     ClipboardError(String),
     IndexNotFound(String),
+    /// The index file exists but can't be decoded, e.g. because it was damaged.
+    IndexCorrupt {
+        path: String,
+        reason: String,
+    },
+    /// The index file was written in another [format](crate::index::FORMAT_VERSION),
+    /// by an older or newer contextmesh; `found` is `None` for indexes written
+    /// before the format was versioned.
+    IndexFormat {
+        path: String,
+        found: Option<u32>,
+    },
+    SnapshotNotFound(String),
+    /// No symbol has the name or qualified name given on the command line.
+    SymbolNotFound(String),
+    /// No Bazel or Buck target recorded in the index has the given label.
+    TargetNotFound(String),
+    /// No indexed file belongs to a Cargo package of the given name.
+    CrateNotFound(String),
+    ConfigError(String),
+    RecipeNotFound(String),
+    /// The directory given with `--root` can't be entered.
+    RootNotFound {
+        path: String,
+        source: io::Error,
+    },
+    GitError(String),
+    PluginError(String),
+    ProfileError(String),
+    RemoteError(String),
+    CheckFailed(usize),
+    ToolError(String),
+    DaemonError(String),
+    /// An LLM API request of `contextmesh ask` that failed.
+    LlmError(String),
+}
+
+impl ContextMeshError {
+    /// Stable identifier of the kind of error, grouped by subsystem: `CM00x`
+    /// I/O and encoding, `CM01x` the index, `CM02x` parsing, `CM03x`
+    /// configuration, `CM04x` external tools, `CM05x` checks.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContextMeshError::IoError(_) => "CM001",
+            ContextMeshError::SerdeError(_) => "CM002",
+            ContextMeshError::SerializationError(_) => "CM003",
+            ContextMeshError::DeserializationError(_) => "CM004",
+            ContextMeshError::IndexNotFound(_) => "CM010",
+            ContextMeshError::IndexCorrupt { .. } => "CM011",
+            ContextMeshError::SnapshotNotFound(_) => "CM012",
+            ContextMeshError::SymbolNotFound(_) => "CM013",
+            ContextMeshError::TargetNotFound(_) => "CM014",
+            ContextMeshError::CrateNotFound(_) => "CM015",
+            ContextMeshError::IndexFormat { .. } => "CM016",
+            ContextMeshError::TreeSitterError(_) => "CM020",
+            ContextMeshError::UnsupportedLanguage(_) => "CM021",
+            ContextMeshError::PluginError(_) => "CM022",
+            ContextMeshError::QueryError(_) => "CM023",
+            ContextMeshError::RuleError(_) => "CM024",
+            ContextMeshError::ConfigError(_) => "CM030",
+            ContextMeshError::RecipeNotFound(_) => "CM031",
+            ContextMeshError::ProfileError(_) => "CM032",
+            ContextMeshError::RootNotFound { .. } => "CM033",
+            ContextMeshError::GitError(_) => "CM040",
+            ContextMeshError::ToolError(_) => "CM041",
+            ContextMeshError::RemoteError(_) => "CM042",
+            ContextMeshError::ClipboardError(_) => "CM043",
+            ContextMeshError::DaemonError(_) => "CM044",
+            ContextMeshError::LlmError(_) => "CM045",
+            ContextMeshError::CheckFailed(_) => "CM050",
+        }
+    }
+
+    /// What the user can do about the error, if there's a usual fix.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            ContextMeshError::IndexNotFound(_) => {
+                Some("Run `contextmesh index` first to build the index.")
+            }
+            ContextMeshError::IndexCorrupt { .. } => Some(
+                "The index may be damaged; delete it and run `contextmesh index` to rebuild it.",
+            ),
+            ContextMeshError::IndexFormat { .. } => {
+                Some("Run `contextmesh index` to rebuild the index for this version.")
+            }
+            ContextMeshError::SnapshotNotFound(_) => {
+                Some("See the saved snapshots with `contextmesh snapshot list`.")
+            }
+            ContextMeshError::SymbolNotFound(_) => {
+                Some("Find the symbol's name with `contextmesh search <text>`.")
+            }
+            ContextMeshError::TargetNotFound(_) => Some(
+                "Targets are read from BUILD and BUCK files by `contextmesh index`; re-run it \
+                 after changing them.",
+            ),
+            ContextMeshError::CrateNotFound(_) => Some(
+                "Crates are recorded by `contextmesh index` in a Cargo workspace; `cargo \
+                 metadata` lists their names.",
+            ),
+            ContextMeshError::UnsupportedLanguage(_) => Some(
+                "Use one of the built-in languages, or add a grammar or the language's \
+                 `extensions` under [languages] in .contextmesh/config.toml.",
+            ),
+            ContextMeshError::PluginError(_) => {
+                Some("Check the plugin paths in .contextmesh/config.toml.")
+            }
+            ContextMeshError::QueryError(_) => {
+                Some("See `contextmesh query --help` for the query syntax.")
+            }
+            ContextMeshError::RuleError(_) => {
+                Some("See `contextmesh datalog --help` for the rule syntax.")
+            }
+            ContextMeshError::ConfigError(_) => Some("Check .contextmesh/config.toml."),
+            ContextMeshError::RecipeNotFound(_) => {
+                Some("See the configured recipes with `contextmesh context --list`.")
+            }
+            ContextMeshError::GitError(_) => {
+                Some("Make sure git is installed and the project is a git repository.")
+            }
+            ContextMeshError::RemoteError(_) => Some(
+                "Check the remote URL and that the tool it needs (curl, ssh, or aws) is installed.",
+            ),
+            ContextMeshError::ClipboardError(_) => Some(
+                "No clipboard may be available, e.g. over SSH or without a display; choose \
+                      another sink with --sink or in the [output] section of the config.",
+            ),
+            ContextMeshError::LlmError(_) => Some(
+                "Check the [llm] section of .contextmesh/config.toml, that its API key variable \
+                 is set, and that curl is installed.",
+            ),
+            ContextMeshError::CheckFailed(_) => Some(
+                "Fix the problems reported above, or adjust the rules in .contextmesh/config.toml.",
+            ),
+            _ => None,
+        }
+    }
+
+    /// The error as JSON: its code, message, hint, and the messages of the
+    /// errors that caused it.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut causes = Vec::new();
+        let mut source = self.source();
+        while let Some(error) = source {
+            causes.push(error.to_string());
+            source = error.source();
+        }
+        json!({
+            "error": {
+                "code": self.code(),
+                "message": self.to_string(),
+                "hint": self.hint(),
+                "causes": causes,
+            }
+        })
+    }
+
+    /// Prints the error to stderr, as a JSON line with `porcelain`.
+    pub fn report(&self, porcelain: bool) {
+        if porcelain {
+            eprintln!("{}", self.to_json());
+            return;
+        }
+        eprintln!("Error [{}]: {}", self.code(), self);
+        if let Some(hint) = self.hint() {
+            eprintln!("Hint: {}", hint);
+        }
+    }
 }
 
 impl fmt::Display for ContextMeshError {
@@ -25,17 +201,62 @@ impl fmt::Display for ContextMeshError {
             ContextMeshError::UnsupportedLanguage(lang) => {
                 write!(f, "Unsupported language: {}", lang)
             }
+            ContextMeshError::QueryError(e) => write!(f, "Query Error: {}", e),
+            ContextMeshError::RuleError(e) => write!(f, "Rule Error: {}", e),
             ContextMeshError::SerializationError(e) => write!(f, "Serialization Error: {}", e),
             ContextMeshError::DeserializationError(e) => write!(f, "Deserialization Error: {}", e),
             ContextMeshError::ClipboardError(e) => write!(f, "Clipboard Error: {}", e),
             ContextMeshError::IndexNotFound(path) => {
                 write!(f, "Index file not found at path: {}", path)
             }
+            ContextMeshError::IndexCorrupt { path, reason } => {
+                write!(f, "Index file {} can't be read: {}", path, reason)
+            }
+            ContextMeshError::IndexFormat { path, found } => match found {
+                Some(found) => write!(
+                    f,
+                    "Index file {} is in format {}, but this contextmesh reads format {}",
+                    path,
+                    found,
+                    crate::index::FORMAT_VERSION
+                ),
+                None => write!(f, "Index file {} was written by an older contextmesh", path),
+            },
+            ContextMeshError::SnapshotNotFound(name) => write!(f, "No snapshot named '{}'", name),
+            ContextMeshError::SymbolNotFound(name) => write!(f, "No symbol named '{}'", name),
+            ContextMeshError::TargetNotFound(label) => write!(f, "No build target '{}'", label),
+            ContextMeshError::CrateNotFound(name) => {
+                write!(f, "No indexed file belongs to crate '{}'", name)
+            }
+            ContextMeshError::ConfigError(e) => write!(f, "Config Error: {}", e),
+            ContextMeshError::RecipeNotFound(name) => write!(f, "No recipe named '{}'", name),
+            ContextMeshError::RootNotFound { path, source } => {
+                write!(f, "Can't change to root '{}': {}", path, source)
+            }
+            ContextMeshError::GitError(e) => write!(f, "Git Error: {}", e),
+            ContextMeshError::PluginError(e) => write!(f, "Plugin Error: {}", e),
+            ContextMeshError::ProfileError(e) => write!(f, "Profile Error: {}", e),
+            ContextMeshError::RemoteError(e) => write!(f, "Remote Error: {}", e),
+            ContextMeshError::ToolError(e) => write!(f, "Tool Error: {}", e),
+            ContextMeshError::DaemonError(e) => write!(f, "Daemon Error: {}", e),
+            ContextMeshError::LlmError(e) => write!(f, "LLM Error: {}", e),
+            ContextMeshError::CheckFailed(count) => {
+                write!(f, "Check failed with {} problem(s)", count)
+            }
         }
     }
 }
 
-impl std::error::Error for ContextMeshError {}
+impl Error for ContextMeshError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ContextMeshError::IoError(e) => Some(e),
+            ContextMeshError::SerdeError(e) => Some(e),
+            ContextMeshError::RootNotFound { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for ContextMeshError {
     fn from(error: io::Error) -> Self {
@@ -0,0 +1,63 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Writes a synthetic Rust project under `root` for benchmarking the indexer.
+///
+/// Each of the `files` modules defines a struct plus `fns_per_file` functions; every
+/// function calls its neighbour in the same file and a function in the previous
+/// module, so the generated graph has both local and cross-file edges. Returns the
+/// paths of the generated source files.
+pub fn generate_rust_fixture(
+    root: &Path,
+    files: usize,
+    fns_per_file: usize,
+) -> io::Result<Vec<PathBuf>> {
+    let src = root.join("src");
+    fs::create_dir_all(&src)?;
+
+    let mut paths = Vec::with_capacity(files);
+    for file_idx in 0..files {
+        let path = src.join(format!("module_{}.rs", file_idx));
+        fs::write(&path, fixture_module(file_idx, fns_per_file))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Source of the `file_idx`-th fixture module.
+pub fn fixture_module(file_idx: usize, fns_per_file: usize) -> String {
+    let mut code = format!(
+        "pub struct Record{idx} {{\n    pub id: u64,\n    pub label: String,\n}}\n\n",
+        idx = file_idx
+    );
+
+    for fn_idx in 0..fns_per_file {
+        let mut body = String::new();
+        if fn_idx > 0 {
+            body.push_str(&format!(
+                "    let local = module_{}_fn_{}(value);\n",
+                file_idx,
+                fn_idx - 1
+            ));
+        } else {
+            body.push_str("    let local = value;\n");
+        }
+        if file_idx > 0 {
+            body.push_str(&format!(
+                "    let remote = module_{}_fn_{}(local);\n",
+                file_idx - 1,
+                fn_idx
+            ));
+        } else {
+            body.push_str("    let remote = local;\n");
+        }
+        body.push_str("    remote.wrapping_add(1)\n");
+
+        code.push_str(&format!(
+            "pub fn module_{}_fn_{}(value: u64) -> u64 {{\n{}}}\n\n",
+            file_idx, fn_idx, body
+        ));
+    }
+    code
+}
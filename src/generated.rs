@@ -0,0 +1,71 @@
+//! Detection of generated source files.
+//!
+//! A file counts as generated if it matches a glob of the `[generated]` config
+//! section or, unless `detect = false`, if its name follows a generator's
+//! convention (`*_generated.rs`, `*.pb.go`, `*_pb2.py`, ...) or one of its first
+//! three lines carries a generator's marker (see [`HEADER_MARKERS`]).
+//!
+//! Symbols of generated files are indexed and resolved like any others, but
+//! left out of bundles unless asked for.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::arch::path_matches;
+use crate::config::GeneratedConfig;
+
+/// How much of a file is searched for a header marker.
+const HEADER_BYTES: u64 = 1024;
+
+/// Lowercase header comments of generated files.
+const HEADER_MARKERS: &[&str] = &[
+    "@generated",
+    "do not edit",
+    "code generated",
+    "auto-generated",
+    "autogenerated",
+    "automatically generated",
+];
+
+/// Lowercase file name suffixes of generated files.
+const NAME_SUFFIXES: &[&str] = &[
+    "_generated.rs",
+    ".generated.ts",
+    ".generated.js",
+    ".pb.go",
+    ".pb.rs",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    ".g.dart",
+    ".designer.cs",
+];
+
+pub fn is_generated(path: &str, config: &GeneratedConfig) -> bool {
+    config
+        .paths
+        .iter()
+        .any(|pattern| path_matches(pattern, path))
+        || (config.detect && (has_generated_name(path) || has_generated_header(path)))
+}
+
+fn has_generated_name(path: &str) -> bool {
+    let Some(name) = Path::new(path).file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let name = name.to_lowercase();
+    NAME_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+fn has_generated_header(path: &str) -> bool {
+    let mut header = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(HEADER_BYTES).read_to_end(&mut header));
+    if read.is_err() {
+        return false;
+    }
+    let header = String::from_utf8_lossy(&header).to_lowercase();
+    header
+        .lines()
+        .take(3)
+        .any(|line| HEADER_MARKERS.iter().any(|marker| line.contains(marker)))
+}
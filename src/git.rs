@@ -0,0 +1,127 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::errors::ContextMeshError;
+
+/// Runs `git` with `args` in the current directory, returning its stdout.
+fn run_git(args: &[&str]) -> Result<Vec<u8>, ContextMeshError> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| ContextMeshError::GitError(format!("Failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(ContextMeshError::GitError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Returns the commit `HEAD` points at, or `None` outside a git repository.
+pub fn current_git_commit() -> Option<String> {
+    let stdout = run_git(&["rev-parse", "HEAD"]).ok()?;
+    let commit = String::from_utf8_lossy(&stdout).trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
+
+/// A file touched between a revision and the working tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    /// Path relative to the current directory.
+    pub path: String,
+    /// Whether the file no longer exists in the working tree.
+    pub deleted: bool,
+}
+
+/// Lists files that differ between `rev` and the working tree.
+pub fn changed_files_since(rev: &str) -> Result<Vec<ChangedFile>, ContextMeshError> {
+    let stdout = run_git(&["diff", "--name-status", "--no-renames", "--relative", rev])?;
+    Ok(String::from_utf8_lossy(&stdout)
+        .lines()
+        .filter_map(|line| {
+            let (status, path) = line.split_once('\t')?;
+            Some(ChangedFile {
+                path: path.to_string(),
+                deleted: status.starts_with('D'),
+            })
+        })
+        .collect())
+}
+
+/// Returns the content of `path` (relative to the current directory) at `rev`, or
+/// `None` if it did not exist there.
+pub fn file_at_revision(rev: &str, path: &Path) -> Option<Vec<u8>> {
+    let spec = format!("{}:./{}", rev, path.display());
+    run_git(&["show", &spec]).ok()
+}
+
+/// The commit that last changed one line of a file, according to `git blame`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub commit: String,
+    pub author: String,
+    /// Author time as seconds since the Unix epoch.
+    pub time: u64,
+    /// Length of the line in bytes, including its newline.
+    pub len: usize,
+}
+
+/// Blames every line of `path` in the working tree.
+pub fn blame(path: &Path) -> Result<Vec<BlameLine>, ContextMeshError> {
+    let path = path.to_string_lossy();
+    let stdout = run_git(&["blame", "--line-porcelain", "--", &path])?;
+    let mut lines = Vec::new();
+    let mut current: Option<BlameLine> = None;
+
+    // Each line is a header (`<commit> <orig> <final>`), `key value` fields, and
+    // finally the line's content prefixed with a tab
+    for line in stdout.split(|&b| b == b'\n') {
+        if let Some(content) = line.strip_prefix(b"\t") {
+            if let Some(mut blamed) = current.take() {
+                blamed.len = content.len() + 1;
+                lines.push(blamed);
+            }
+            continue;
+        }
+        let line = String::from_utf8_lossy(line);
+        match (&mut current, line.split_once(' ')) {
+            (None, Some((commit, _))) => {
+                current = Some(BlameLine {
+                    commit: commit.to_string(),
+                    author: String::new(),
+                    time: 0,
+                    len: 0,
+                });
+            }
+            (Some(blamed), Some(("author", author))) => blamed.author = author.to_string(),
+            (Some(blamed), Some(("author-time", time))) => {
+                blamed.time = time.parse().unwrap_or_default()
+            }
+            _ => {}
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Returns the author time and touched files (relative to the current directory)
+/// of the last `max_commits` commits, newest first.
+pub fn file_history(max_commits: usize) -> Result<Vec<(u64, Vec<String>)>, ContextMeshError> {
+    let count = format!("-n{}", max_commits);
+    let stdout = run_git(&[
+        "log",
+        &count,
+        "--format=%x00%at",
+        "--name-only",
+        "--no-renames",
+        "--relative",
+    ])?;
+    Ok(String::from_utf8_lossy(&stdout)
+        .split('\0')
+        .filter_map(|commit| {
+            let mut lines = commit.lines().filter(|line| !line.is_empty());
+            let time = lines.next()?.trim().parse().ok()?;
+            Some((time, lines.map(str::to_string).collect()))
+        })
+        .collect())
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::symbol::Symbol;
+
+/// How a symbol changed between two versions of its file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Removed => "removed",
+        }
+    }
+}
+
+/// A symbol that was added, modified, or removed by an index run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SymbolChange {
+    pub kind: ChangeKind,
+    pub name: String,
+    pub node_kind: String,
+    pub file_path: String,
+    /// Line of the new definition, or of the old one for removed symbols.
+    pub line_number: usize,
+}
+
+impl SymbolChange {
+    fn new(kind: ChangeKind, sym: &Symbol) -> Self {
+        SymbolChange {
+            kind,
+            name: sym.name.clone(),
+            node_kind: sym.node_kind.to_string(),
+            file_path: sym.file_path.to_string(),
+            line_number: sym.line_number,
+        }
+    }
+}
+
+/// Compares the old and new symbols of one file by their body hashes.
+///
+/// Symbols are matched by `(name, node_kind)`; repeated pairs (e.g. identically
+/// named fields of different structs) are matched in source order. Symbols that
+/// merely moved keep their body hash and are not reported.
+pub fn diff_symbols(old: &[&Symbol], new: &[Symbol]) -> Vec<SymbolChange> {
+    let mut old_by_key: HashMap<(&str, &str), Vec<&Symbol>> = HashMap::new();
+    let mut old_sorted: Vec<&Symbol> = old.to_vec();
+    old_sorted.sort_by_key(|sym| sym.start_byte);
+    // Reverse so `pop` hands out matches in source order
+    for sym in old_sorted.into_iter().rev() {
+        old_by_key
+            .entry((sym.name.as_str(), &*sym.node_kind))
+            .or_default()
+            .push(sym);
+    }
+
+    let mut new_sorted: Vec<&Symbol> = new.iter().collect();
+    new_sorted.sort_by_key(|sym| sym.start_byte);
+
+    let mut changes = Vec::new();
+    for sym in new_sorted {
+        let key = (sym.name.as_str(), &*sym.node_kind);
+        match old_by_key.get_mut(&key).and_then(Vec::pop) {
+            Some(old_sym) if old_sym.body_hash == sym.body_hash => {}
+            Some(_) => changes.push(SymbolChange::new(ChangeKind::Modified, sym)),
+            None => changes.push(SymbolChange::new(ChangeKind::Added, sym)),
+        }
+    }
+
+    let mut removed: Vec<&Symbol> = old_by_key.into_values().flatten().collect();
+    removed.sort_by_key(|sym| sym.start_byte);
+    changes.extend(
+        removed
+            .into_iter()
+            .map(|sym| SymbolChange::new(ChangeKind::Removed, sym)),
+    );
+
+    changes
+}
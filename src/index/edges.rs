@@ -0,0 +1,131 @@
+//! The dependency edges between symbols. Each edge is recorded at both ends, in
+//! the user's `dependencies` and the dependency's `used_by`. The methods here
+//! are the only ones changing either set, so each stays the inverse of the other.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use super::Index;
+use crate::symbol::{Symbol, SymbolId};
+
+/// How sure resolution is that an edge points at the right symbol. Ordered from
+/// least to most sure.
+#[derive(
+    ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeConfidence {
+    /// One of several same-named symbols that nothing narrowed down
+    NameOnly,
+    /// A same-named symbol in a glob-imported module
+    Imported,
+    /// The only symbol of that name, or the one in the named module, type, or
+    /// file
+    Exact,
+}
+
+impl EdgeConfidence {
+    pub fn name(self) -> &'static str {
+        match self {
+            EdgeConfidence::NameOnly => "name-only",
+            EdgeConfidence::Imported => "imported",
+            EdgeConfidence::Exact => "exact",
+        }
+    }
+}
+
+impl Index {
+    /// Records at both ends that the symbol `user_hash` depends on `dep_hash`,
+    /// returning whether the edge is new. A symbol doesn't depend on itself, and
+    /// symbols that aren't indexed get no edges.
+    pub(super) fn link(&mut self, user_hash: &str, dep_hash: &str) -> bool {
+        self.link_with(user_hash, dep_hash, EdgeConfidence::Exact)
+    }
+
+    /// [`Index::link`] with the `confidence` of the edge. Linking an existing edge
+    /// again keeps the higher of its confidences.
+    pub(super) fn link_with(
+        &mut self,
+        user_hash: &str,
+        dep_hash: &str,
+        confidence: EdgeConfidence,
+    ) -> bool {
+        if user_hash == dep_hash
+            || !self.symbols.contains_key(user_hash)
+            || !self.symbols.contains_key(dep_hash)
+        {
+            return false;
+        }
+        let user_id = self.symbol_table.id_for(user_hash);
+        let dep_id = self.symbol_table.id_for(dep_hash);
+        if let Some(dep) = self.symbols.get_mut(dep_hash) {
+            dep.used_by.insert(user_id);
+        }
+        let added = self
+            .symbols
+            .get_mut(user_hash)
+            .is_some_and(|user| user.dependencies.insert(dep_id));
+        let confidence = match added {
+            true => confidence,
+            false => confidence.max(self.confidence(user_id, dep_id)),
+        };
+        match confidence {
+            EdgeConfidence::Exact => self.weak_edges.remove(&(user_id, dep_id)),
+            weak => self.weak_edges.insert((user_id, dep_id), weak),
+        };
+        added
+    }
+
+    /// Removes the edge from `user_id` to `dep_id` at both ends, or at the end
+    /// still indexed if the other was removed. Returns whether the user had it.
+    pub(super) fn unlink(&mut self, user_id: SymbolId, dep_id: SymbolId) -> bool {
+        self.weak_edges.remove(&(user_id, dep_id));
+        if let Some(dep) = self.symbol_mut(dep_id) {
+            dep.used_by.remove(&user_id);
+        }
+        self.symbol_mut(user_id)
+            .is_some_and(|user| user.dependencies.remove(&dep_id))
+    }
+
+    /// How sure resolution is of the edge from the symbol `user_hash` to its
+    /// dependency `dep_hash`.
+    pub fn edge_confidence(&self, user_hash: &str, dep_hash: &str) -> EdgeConfidence {
+        match (
+            self.symbol_table.get(user_hash),
+            self.symbol_table.get(dep_hash),
+        ) {
+            (Some(user_id), Some(dep_id)) => self.confidence(user_id, dep_id),
+            _ => EdgeConfidence::Exact,
+        }
+    }
+
+    pub(super) fn confidence(&self, user_id: SymbolId, dep_id: SymbolId) -> EdgeConfidence {
+        self.weak_edges
+            .get(&(user_id, dep_id))
+            .copied()
+            .unwrap_or(EdgeConfidence::Exact)
+    }
+
+    /// The dependencies of the symbol `user_hash` linked with at least
+    /// `min_confidence`.
+    pub fn dependencies_with(
+        &self,
+        user_hash: &str,
+        min_confidence: EdgeConfidence,
+    ) -> impl Iterator<Item = SymbolId> + '_ {
+        let user_id = self.symbol_table.get(user_hash);
+        self.symbols
+            .get(user_hash)
+            .into_iter()
+            .flat_map(|user| &user.dependencies)
+            .copied()
+            .filter(move |dep_id| {
+                user_id.is_some_and(|user_id| self.confidence(user_id, *dep_id) >= min_confidence)
+            })
+    }
+
+    pub(super) fn symbol_mut(&mut self, id: SymbolId) -> Option<&mut Symbol> {
+        let hash = self.hash_of(id)?.to_string();
+        self.symbols.get_mut(&hash)
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Records why a file could not be indexed during its last attempt.
+///
+/// Failed files keep no symbols and no content hash, so they are retried on every
+/// run until they parse cleanly.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileFailure {
+    /// Human-readable description of the failure.
+    pub reason: String,
+
+    /// Number of `ERROR`/missing nodes tree-sitter produced while recovering.
+    pub error_nodes: usize,
+
+    /// Unix timestamp (seconds) of the failed attempt.
+    pub failed_at: u64,
+}
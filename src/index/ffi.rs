@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use super::Index;
+use crate::arch::{glob_matches, qualified_name};
+use crate::config::Alias;
+use crate::symbol::Symbol;
+
+impl Index {
+    /// Links the two sides of FFI bindings: symbols exported to other languages
+    /// under the names from their attributes ([`crate::symbol::Symbol::foreign_names`])
+    /// or from `aliases`, and the code in other languages referring to or
+    /// declaring those names (e.g. Python calls, `.pyi` stubs, or TypeScript
+    /// declarations).
+    ///
+    /// Methods of exported types are only linked to counterparts in a class named
+    /// like the type, so that `new`, `get` or `run` don't link every method of
+    /// that name in other languages.
+    ///
+    /// Declared names become resolvable like attribute ones until the index is
+    /// reloaded, so this should run before unresolved references are rechecked.
+    /// Returns the number of edges added from declarations in other languages to
+    /// the symbols they correspond to.
+    pub fn link_foreign_names(&mut self, aliases: &[Alias]) -> usize {
+        // (hash, exported name, names of the exported type of a method)
+        let mut exported: Vec<(String, String, Vec<String>)> = Vec::new();
+        for (hash, sym) in &self.symbols {
+            if !sym.is_code() {
+                continue;
+            }
+            for name in sym.foreign_names() {
+                exported.push((hash.clone(), name, Vec::new()));
+            }
+            // Methods of exported types are exported too
+            if let Some(parent) = sym.parent.and_then(|id| self.symbol(id)) {
+                let classes = parent.foreign_names();
+                if &*sym.node_kind == "function_item" && !classes.is_empty() {
+                    exported.push((hash.clone(), sym.name.clone(), classes));
+                }
+            }
+            if aliases.is_empty() {
+                continue;
+            }
+            let qualified = qualified_name(self, sym);
+            for alias in aliases {
+                if glob_matches(&alias.symbol, &qualified, sym) {
+                    for name in &alias.names {
+                        exported.push((hash.clone(), name.clone(), Vec::new()));
+                    }
+                }
+            }
+        }
+
+        let mut added = 0;
+        for (hash, name, classes) in exported {
+            self.name_map
+                .entry(name.clone())
+                .or_default()
+                .insert(hash.clone());
+            let keys = &self.name_map[&name];
+            let in_class = |other: &Symbol| {
+                classes.is_empty()
+                    || other
+                        .parent
+                        .and_then(|id| self.symbol(id))
+                        .is_some_and(|class| classes.contains(&class.name))
+            };
+            let counterparts: Vec<String> = keys
+                .iter()
+                .filter(|other| {
+                    self.symbols.get(*other).is_some_and(|other| {
+                        other.is_code()
+                            && other.name == name
+                            && in_class(other)
+                            && extension(&other.file_path)
+                                != extension(&self.symbols[&hash].file_path)
+                    })
+                })
+                .cloned()
+                .collect();
+
+            for counterpart_hash in counterparts {
+                if self.link(&counterpart_hash, &hash) {
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+}
+
+fn extension(path: &str) -> Option<&str> {
+    Path::new(path).extension()?.to_str()
+}
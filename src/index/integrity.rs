@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::Index;
+use crate::utils::calculate_file_hash;
+
+/// What `contextmesh verify` finds wrong with an index.
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    /// Broken invariants between the maps of the index; see
+    /// [`Index::invariant_violations`].
+    pub invariants: Vec<String>,
+    /// Indexed files that no longer exist.
+    pub missing_files: Vec<String>,
+    /// Indexed files whose content changed since they were indexed.
+    pub stale_files: Vec<String>,
+    /// Symbols indexed more than once at the same place of a file, as
+    /// `(file, line, name)`.
+    pub duplicates: Vec<(String, usize, String)>,
+}
+
+impl IntegrityReport {
+    pub fn len(&self) -> usize {
+        self.invariants.len()
+            + self.missing_files.len()
+            + self.stale_files.len()
+            + self.duplicates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Index {
+    /// Checks the index against itself and against the files on disk.
+    pub fn check_integrity(&self) -> IntegrityReport {
+        let mut report = IntegrityReport {
+            invariants: self.invariant_violations(),
+            ..Default::default()
+        };
+        for (path, hash) in &self.file_hashes {
+            if !Path::new(path).exists() {
+                report.missing_files.push(path.clone());
+            } else if calculate_file_hash(path).is_some_and(|current| current != *hash) {
+                report.stale_files.push(path.clone());
+            }
+        }
+        report.missing_files.sort();
+        report.stale_files.sort();
+
+        report.duplicates = self
+            .duplicate_symbols()
+            .into_iter()
+            .map(|((file, _, name), hashes)| {
+                let line = self.symbols[&hashes[0]].line_number;
+                (file.to_string(), line, name.to_string())
+            })
+            .collect();
+        report.duplicates.sort();
+        report
+    }
+
+    /// Fixes what [`Index::check_integrity`] reports as far as that can be done
+    /// without parsing: drops missing files, keeps one symbol of each duplicate
+    /// (the one with the most edges, whose users the others' are relinked to),
+    /// and restores the invariants. Returns what is still wrong, e.g. stale files,
+    /// which only the next index run parses again.
+    pub fn repair_integrity(&mut self) -> IntegrityReport {
+        self.forget_missing_files();
+
+        let extras: Vec<String> = self
+            .duplicate_symbols()
+            .into_values()
+            .flat_map(|mut hashes| {
+                hashes.sort_by_key(|hash| {
+                    let sym = &self.symbols[hash];
+                    (
+                        std::cmp::Reverse(sym.dependencies.len() + sym.used_by.len()),
+                        hash.clone(),
+                    )
+                });
+                hashes.split_off(1)
+            })
+            .collect();
+        for hash in &extras {
+            self.remove_symbol(hash);
+        }
+        if !extras.is_empty() {
+            self.recheck_unresolved();
+        }
+
+        self.repair_invariants();
+
+        self.check_integrity()
+    }
+
+    /// The hashes of the symbols indexed more than once under the same name at
+    /// the same place, by file, start byte, and name.
+    fn duplicate_symbols(&self) -> HashMap<(&str, usize, &str), Vec<String>> {
+        let mut by_place: HashMap<(&str, usize, &str), Vec<String>> = HashMap::new();
+        for (hash, sym) in &self.symbols {
+            let place = (&*sym.file_path, sym.start_byte, sym.name.as_str());
+            by_place.entry(place).or_default().push(hash.clone());
+        }
+        by_place.retain(|_, hashes| hashes.len() > 1);
+        by_place
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+use std::mem::take;
+
+use super::{EdgeConfidence, Index};
+use crate::symbol::SymbolId;
+
+impl Index {
+    /// Describes every way the index breaks the invariants its maps are kept
+    /// under: each edge is recorded at both ends between live symbols and never
+    /// from a symbol to itself, only edges in the graph have a confidence, and
+    /// the name map and per-file symbol sets list exactly the live symbols.
+    /// Empty for a consistent index.
+    pub fn invariant_violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        for (hash, sym) in &self.symbols {
+            let Some(id) = self.symbol_table.get(hash) else {
+                violations.push(format!("'{}' ({}) has no symbol ID", sym.name, hash));
+                continue;
+            };
+            // Self-edges are reported on their own below
+            for dep_id in sym.dependencies.iter().filter(|dep_id| **dep_id != id) {
+                match self.symbol(*dep_id) {
+                    None => violations.push(format!(
+                        "'{}' ({}) depends on a symbol that isn't indexed",
+                        sym.name, hash
+                    )),
+                    Some(dep) if !dep.used_by.contains(&id) => violations.push(format!(
+                        "'{}' depends on '{}', which doesn't list it as a user",
+                        sym.name, dep.name
+                    )),
+                    Some(_) => {}
+                }
+            }
+            for user_id in sym.used_by.iter().filter(|user_id| **user_id != id) {
+                match self.symbol(*user_id) {
+                    None => violations.push(format!(
+                        "'{}' ({}) is used by a symbol that isn't indexed",
+                        sym.name, hash
+                    )),
+                    Some(user) if !user.dependencies.contains(&id) => violations.push(format!(
+                        "'{}' lists '{}' as a user, which doesn't depend on it",
+                        sym.name, user.name
+                    )),
+                    Some(_) => {}
+                }
+            }
+            if sym.dependencies.contains(&id) || sym.used_by.contains(&id) {
+                violations.push(format!("'{}' ({}) depends on itself", sym.name, hash));
+            }
+            for key in Self::name_keys(sym) {
+                if !self
+                    .name_map
+                    .get(&key)
+                    .is_some_and(|hashes| hashes.contains(hash))
+                {
+                    violations.push(format!("'{}' ({}) is missing from the name map", key, hash));
+                }
+            }
+            let in_file = self
+                .file_symbols
+                .get(&*sym.file_path)
+                .is_some_and(|hashes| hashes.contains(hash));
+            if !in_file {
+                violations.push(format!(
+                    "'{}' ({}) is missing from the symbols of '{}'",
+                    sym.name, hash, sym.file_path
+                ));
+            }
+        }
+        for (name, hashes) in &self.name_map {
+            for hash in hashes
+                .iter()
+                .filter(|hash| !self.symbols.contains_key(*hash))
+            {
+                violations.push(format!(
+                    "The name map maps '{}' to the removed {}",
+                    name, hash
+                ));
+            }
+        }
+        for (file_path, hashes) in &self.file_symbols {
+            for hash in hashes
+                .iter()
+                .filter(|hash| !self.symbols.contains_key(*hash))
+            {
+                violations.push(format!(
+                    "The symbols of '{}' include the removed {}",
+                    file_path, hash
+                ));
+            }
+        }
+        for hash in self.unresolved_dependencies.keys() {
+            if !self.symbols.contains_key(hash) {
+                violations.push(format!("The removed {} has unresolved references", hash));
+            }
+        }
+        for (user_id, dep_id) in self.weak_edges.keys() {
+            if !self
+                .symbol(*user_id)
+                .is_some_and(|user| user.dependencies.contains(dep_id))
+            {
+                violations.push(format!(
+                    "Edge {} -> {} has a confidence but isn't in the graph",
+                    user_id.0, dep_id.0
+                ));
+            }
+        }
+        violations
+    }
+
+    /// Restores what [`Index::invariant_violations`] checks, returning how many
+    /// violations there were. Edges to symbols that aren't indexed and from
+    /// symbols to themselves are dropped, `used_by` is derived again from
+    /// `dependencies` (which resolution wrote, so they are taken as right), and
+    /// the name map and per-file symbol sets are rebuilt from the symbols.
+    pub fn repair_invariants(&mut self) -> usize {
+        let violations = self.invariant_violations().len();
+        if violations == 0 {
+            return 0;
+        }
+
+        let mut dependencies: HashMap<String, HashSet<SymbolId>> = HashMap::new();
+        for (hash, sym) in &self.symbols {
+            let id = self.symbol_table.get(hash);
+            let live = sym
+                .dependencies
+                .iter()
+                .copied()
+                .filter(|dep_id| Some(*dep_id) != id && self.symbol(*dep_id).is_some())
+                .collect();
+            dependencies.insert(hash.clone(), live);
+        }
+        for sym in self.symbols.values_mut() {
+            sym.dependencies.clear();
+            sym.used_by.clear();
+        }
+        let weak_edges = take(&mut self.weak_edges);
+        for (user_hash, dep_ids) in dependencies {
+            let user_id = self.symbol_table.get(&user_hash);
+            for dep_id in dep_ids {
+                let confidence = user_id
+                    .and_then(|user_id| weak_edges.get(&(user_id, dep_id)).copied())
+                    .unwrap_or(EdgeConfidence::Exact);
+                if let Some(dep_hash) = self.hash_of(dep_id).map(str::to_string) {
+                    self.link_with(&user_hash, &dep_hash, confidence);
+                }
+            }
+        }
+
+        self.build_name_map();
+        self.file_symbols.clear();
+        for (hash, sym) in &self.symbols {
+            self.file_symbols
+                .entry(sym.file_path.to_string())
+                .or_default()
+                .insert(hash.clone());
+        }
+        let symbols = &self.symbols;
+        self.unresolved_dependencies
+            .retain(|hash, _| symbols.contains_key(hash));
+        violations
+    }
+}
@@ -0,0 +1,1106 @@
+use log::{debug, info, warn};
+use rayon::prelude::*;
+use std::mem::take;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+use crate::cochange::CoChanges;
+use crate::config::IndexConfig;
+use crate::interner::SharedStrings;
+use crate::metadata::IndexMetadata;
+use crate::parser::todos::Todo;
+use crate::parser::{css, docker, openapi, proto, CodeParser};
+use crate::profile;
+use crate::timings;
+use crate::utils::{
+    calculate_file_hash, module_path, project_root, unix_timestamp, write_atomically,
+};
+use crate::{
+    errors::ContextMeshError,
+    symbol::{Symbol, SymbolId},
+};
+
+mod changes;
+mod edges;
+mod failure;
+mod ffi;
+mod integrity;
+mod invariants;
+mod order;
+mod precise;
+mod prune;
+mod reachability;
+mod scope;
+mod stored;
+mod symbol_table;
+
+pub use changes::{diff_symbols, ChangeKind, SymbolChange};
+pub use edges::EdgeConfidence;
+pub use failure::FileFailure;
+pub use integrity::IntegrityReport;
+pub use prune::PruneReport;
+pub use stored::FORMAT_VERSION;
+use symbol_table::SymbolTable;
+
+/// A user's hash and its raw references, each with the hashes of the symbols it
+/// resolves to and how sure that is.
+type ResolvedReferences = (String, Vec<(String, HashMap<String, EdgeConfidence>)>);
+
+/// An index already in memory, handed out by the next [`Index::load_index`]
+/// instead of reading the file.
+static PRELOADED: Mutex<Option<Index>> = Mutex::new(None);
+
+/// The symbol store: every indexed file and symbol plus the dependency graph
+/// between them. In memory, the symbols of a file share its path and those of a
+/// kind share their node kind; names and everything else are owned per symbol.
+/// Serialized through [`stored`], which interns every repeated string.
+#[derive(Default, Debug)]
+pub struct Index {
+    /// Tool version, timestamps, and settings describing this index
+    pub metadata: IndexMetadata,
+
+    /// Maps file paths -> their SHA256 content hashes
+    pub file_hashes: HashMap<String, String>,
+
+    /// Maps unique symbol hashes -> their Symbol structure
+    pub symbols: HashMap<String, Symbol>,
+
+    /// Files whose last indexing attempt failed, with the reason
+    pub failed_files: HashMap<String, FileFailure>,
+
+    /// Files that contained syntax errors and were only partially indexed, with
+    /// their error node counts. They are re-parsed on every run until clean.
+    pub partial_files: HashMap<String, usize>,
+
+    /// Files of languages without a parser, of which only the definitions were
+    /// indexed (no references); see [`crate::parser::tags`]
+    pub low_fidelity_files: HashSet<String>,
+
+    /// Symbols added, modified, or removed by the most recent index run
+    pub last_changes: Vec<SymbolChange>,
+
+    /// TODO/FIXME/HACK comments of each file that has any
+    pub todos: HashMap<String, Vec<Todo>>,
+
+    /// Files of generated code, whose symbols are left out of bundles by default
+    pub generated_files: HashSet<String>,
+
+    /// Files of vendored or third-party code, whose symbols are left out of
+    /// bundles by default; see [`crate::third_party`]
+    pub third_party_files: HashSet<String>,
+
+    /// Hashes of the symbols declared or detected as entry points
+    pub entry_points: HashSet<String>,
+
+    /// Hashes of the symbols reachable from an entry point, including them
+    pub reachable: HashSet<String>,
+
+    /// Files evolving with each file in git history; recorded by
+    /// `index --co-change`
+    pub co_changes: CoChanges,
+
+    /// Labels of the Bazel or Buck targets owning each file; see
+    /// [`crate::build_targets`]
+    pub file_targets: HashMap<String, Vec<String>>,
+
+    /// Labels of the targets each build target directly depends on
+    pub target_deps: HashMap<String, Vec<String>>,
+
+    /// Name of the Cargo package owning each file; see [`crate::cargo`]
+    pub file_crates: HashMap<String, String>,
+
+    /// `cfg` attributes guarding whole files through their `mod` declarations;
+    /// see [`crate::cfg`]
+    pub file_cfgs: HashMap<String, Vec<String>>,
+
+    /// Maps file paths -> hashes of the symbols defined in them, so file-scoped
+    /// operations don't have to scan every symbol
+    file_symbols: HashMap<String, HashSet<String>>,
+
+    /// Maps file paths -> modules they glob-import (`use foo::*;`), used to pick
+    /// among same-named symbols when resolving their unqualified references
+    file_globs: HashMap<String, Vec<String>>,
+
+    /// Records references that can't be resolved yet (e.g., forward references).
+    /// Key = caller hash symbol, Value = list of raw names that don't exist yet.
+    unresolved_dependencies: HashMap<String, Vec<String>>,
+
+    /// Maps compact symbol IDs used by graph edges <-> symbol hashes
+    symbol_table: SymbolTable,
+
+    /// Confidence of the (user, dependency) edges resolution wasn't sure of;
+    /// every other edge is [exact](EdgeConfidence::Exact)
+    weak_edges: HashMap<(SymbolId, SymbolId), EdgeConfidence>,
+
+    /// Node kinds of the symbols, shared between all symbols of the same kind
+    node_kinds: SharedStrings,
+
+    /// Live name map for quick name->symbol lookups. Built once on load and then
+    /// maintained incrementally by `add_symbol`/`remove_symbol`, so re-indexing a
+    /// file only touches the entries of that file's symbols.
+    name_map: HashMap<String, HashSet<String>>,
+}
+
+impl Index {
+    /// Header written in front of zstd-compressed indexes. Files without it are
+    /// treated as plain bincode written by older versions.
+    const COMPRESSED_MAGIC: &'static [u8] = b"CMZ\x01";
+
+    /// Header in front of every serialized index, followed by its
+    /// [`FORMAT_VERSION`] as a little-endian `u32`.
+    const FORMAT_MAGIC: &'static [u8] = b"CMIX";
+
+    pub fn new() -> Self {
+        Index {
+            metadata: IndexMetadata::new(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the stable hash of the symbol with the given ID.
+    pub fn hash_of(&self, id: SymbolId) -> Option<&str> {
+        self.symbol_table.hash(id)
+    }
+
+    /// Looks up a live symbol by ID. Returns `None` for IDs of removed symbols.
+    pub fn symbol(&self, id: SymbolId) -> Option<&Symbol> {
+        self.hash_of(id).and_then(|hash| self.symbols.get(hash))
+    }
+
+    /// Starts a new index run, forgetting the changes recorded by the previous one.
+    pub fn begin_run(&mut self) {
+        self.last_changes.clear();
+    }
+
+    /// Iterates over the (hash, symbol) pairs defined in `file_path`.
+    pub fn symbols_in_file<'a>(
+        &'a self,
+        file_path: &str,
+    ) -> impl Iterator<Item = (&'a String, &'a Symbol)> + 'a {
+        self.file_symbols
+            .get(file_path)
+            .into_iter()
+            .flatten()
+            .filter_map(|hash| self.symbols.get_key_value(hash))
+    }
+
+    /// Number of callers that still have unresolved references.
+    pub fn unresolved_count(&self) -> usize {
+        self.unresolved_dependencies.len()
+    }
+
+    /// Whether `sym` is defined in a generated file.
+    pub fn is_generated(&self, sym: &Symbol) -> bool {
+        self.generated_files.contains(&*sym.file_path)
+    }
+
+    /// Whether `sym` is defined in a third-party file.
+    pub fn is_third_party(&self, sym: &Symbol) -> bool {
+        self.third_party_files.contains(&*sym.file_path)
+    }
+
+    /// The files of the build target `label` and of the targets it directly
+    /// depends on, or `None` if the index doesn't know the target.
+    pub fn target_files(&self, label: &str) -> Option<HashSet<&str>> {
+        let deps = self.target_deps.get(label)?;
+        let labels: HashSet<&str> = std::iter::once(label)
+            .chain(deps.iter().map(String::as_str))
+            .collect();
+        Some(
+            self.file_targets
+                .iter()
+                .filter(|(_, targets)| targets.iter().any(|t| labels.contains(t.as_str())))
+                .map(|(path, _)| path.as_str())
+                .collect(),
+        )
+    }
+
+    /// Iterates over the (caller, raw name) pairs of references that couldn't be
+    /// resolved.
+    pub fn unresolved_references(&self) -> impl Iterator<Item = (&Symbol, &str)> {
+        self.unresolved_dependencies
+            .iter()
+            .filter_map(|(caller, names)| Some((self.symbols.get(caller)?, names)))
+            .flat_map(|(caller, names)| names.iter().map(move |name| (caller, name.as_str())))
+    }
+
+    /// Loads the index of the selected profile.
+    pub fn load_index() -> Result<Self, ContextMeshError> {
+        let preloaded = PRELOADED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(index) = preloaded {
+            return Ok(index);
+        }
+        let index = timings::time("index load", || {
+            Self::load_index_from(&profile::index_path())
+        })?;
+        if let (Some(indexed), Ok(root)) = (&index.metadata.root, project_root()) {
+            if Path::new(indexed) != root {
+                info!(
+                    "The index was built in '{}'; reading its files from '{}'.",
+                    indexed,
+                    root.display()
+                );
+            }
+        }
+        Ok(index)
+    }
+
+    /// Makes the next [`Index::load_index`] return `index`, e.g. one the daemon
+    /// kept in memory.
+    pub fn preload(index: Index) {
+        *PRELOADED.lock().unwrap_or_else(PoisonError::into_inner) = Some(index);
+    }
+
+    pub fn load_index_from(path: &Path) -> Result<Self, ContextMeshError> {
+        if !path.exists() {
+            return Err(ContextMeshError::IndexNotFound(path.display().to_string()));
+        }
+
+        let data = fs::read(path).map_err(ContextMeshError::IoError)?;
+        let corrupt = |reason: String| ContextMeshError::IndexCorrupt {
+            path: path.display().to_string(),
+            reason,
+        };
+        let data = Self::decompressed(data).map_err(|e| corrupt(e.to_string()))?;
+        let (format, encoded) = match Self::split_format(&data) {
+            Some((format, encoded)) => (Some(format), encoded),
+            None => (None, &data[..]),
+        };
+        if format != Some(FORMAT_VERSION) {
+            return Err(ContextMeshError::IndexFormat {
+                path: path.display().to_string(),
+                found: format,
+            });
+        }
+        let mut index: Index = bincode::deserialize(encoded).map_err(|e| corrupt(e.to_string()))?;
+
+        index.build_name_map();
+
+        info!(
+            "Loaded index: {} file(s), {} symbol(s).",
+            index.file_hashes.len(),
+            index.symbols.len()
+        );
+
+        Ok(index)
+    }
+
+    /// The format version of a serialized index and its encoding, or `None` if
+    /// it has no format header.
+    fn split_format(data: &[u8]) -> Option<(u32, &[u8])> {
+        let rest = data.strip_prefix(Self::FORMAT_MAGIC)?;
+        let (version, encoded) = rest.split_first_chunk::<4>()?;
+        Some((u32::from_le_bytes(*version), encoded))
+    }
+
+    /// The serialized index in an index file's contents, which are compressed
+    /// if they start with the compression header.
+    pub fn decompressed(data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        match data.strip_prefix(Self::COMPRESSED_MAGIC) {
+            Some(compressed) => zstd::decode_all(compressed),
+            None => Ok(data),
+        }
+    }
+
+    /// The contents of an index file for the serialized index `encoded`,
+    /// compressed if `config` says so.
+    pub fn compressed(encoded: Vec<u8>, config: &IndexConfig) -> Result<Vec<u8>, ContextMeshError> {
+        if !config.compression {
+            return Ok(encoded);
+        }
+        let compressed = zstd::encode_all(encoded.as_slice(), config.compression_level)
+            .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+        debug!(
+            "Compressed index from {} to {} bytes (level {}).",
+            encoded.len(),
+            compressed.len(),
+            config.compression_level
+        );
+        Ok([Self::COMPRESSED_MAGIC, compressed.as_slice()].concat())
+    }
+
+    /// Saves the index of the selected profile.
+    pub fn save_index(&self, config: &IndexConfig) -> Result<(), ContextMeshError> {
+        self.save_index_to(&profile::index_path(), config)
+    }
+
+    pub fn save_index_to(&self, path: &Path, config: &IndexConfig) -> Result<(), ContextMeshError> {
+        let mut encoded = [Self::FORMAT_MAGIC, &FORMAT_VERSION.to_le_bytes()].concat();
+        bincode::serialize_into(&mut encoded, self)
+            .map_err(|e| ContextMeshError::SerializationError(e.to_string()))?;
+        let encoded = Self::compressed(encoded, config)?;
+        write_atomically(path, &encoded)?;
+
+        info!(
+            "Index saved: {} file(s), {} symbol(s), unresolved references: {}.",
+            self.file_hashes.len(),
+            self.symbols.len(),
+            self.unresolved_dependencies.len()
+        );
+
+        Ok(())
+    }
+
+    /// Indexes `file_path` if it changed since the last run, resolving the
+    /// references of its new symbols against the symbols indexed so far.
+    pub fn index_file(
+        &mut self,
+        file_path: String,
+        code_parser: &mut CodeParser,
+    ) -> Result<(), ContextMeshError> {
+        if let Some(parsed_syms) = self.update_file(file_path, code_parser)? {
+            self.resolve_new_symbols(&parsed_syms);
+        }
+        Ok(())
+    }
+
+    /// Indexes the changed files among `file_paths` like [`Index::index_file`],
+    /// but resolves the references of their new symbols in one pass once all of
+    /// them are in, so references between them resolve right away instead of in
+    /// [`Index::recheck_unresolved`].
+    pub fn index_files(
+        &mut self,
+        file_paths: &[String],
+        code_parser: &mut CodeParser,
+    ) -> Result<(), ContextMeshError> {
+        let mut parsed_syms = Vec::new();
+        for file_path in file_paths {
+            if let Some(syms) = self.update_file(file_path.clone(), code_parser)? {
+                parsed_syms.extend(syms);
+            }
+        }
+        self.resolve_new_symbols(&parsed_syms);
+        Ok(())
+    }
+
+    /// Replaces the symbols of `file_path` with freshly parsed ones if its content
+    /// changed, returning the new symbols, whose references are still to be
+    /// resolved. Returns `None` if the file is unchanged, unreadable, or failed to
+    /// parse.
+    fn update_file(
+        &mut self,
+        file_path: String,
+        code_parser: &mut CodeParser,
+    ) -> Result<Option<Vec<Symbol>>, ContextMeshError> {
+        let new_hash = match timings::time("hashing", || calculate_file_hash(&file_path)) {
+            Some(h) => h,
+            None => {
+                warn!("Could not read/hash file '{}'. Skipping.", file_path);
+                return Ok(None);
+            }
+        };
+
+        let file_has_changed = self.file_hashes.get(&file_path) != Some(&new_hash)
+            || self.partial_files.contains_key(&file_path);
+        if !file_has_changed {
+            debug!("File '{}' is up-to-date. Skipping parse.", file_path);
+            return Ok(None);
+        }
+
+        info!("File '{}' changed. Parsing now...", file_path);
+
+        // Parse all symbols from changed file
+        let parse_result = timings::time("parsing", || code_parser.parse_file(&file_path));
+
+        // Snapshot the old symbols so the run can report what actually changed
+        let old_syms: Vec<Symbol> = self
+            .symbols_in_file(&file_path)
+            .map(|(_, sym)| sym.clone())
+            .collect();
+
+        // Remove old symbols associated with the file
+        let removed = self.remove_file_symbols(&file_path);
+        debug!("Removed {} old symbols from '{}'.", removed, file_path);
+
+        let (parsed_syms, parents, globs, todos) = match parse_result {
+            Ok(parsed) => {
+                if parsed.error_nodes == 0 {
+                    self.partial_files.remove(&file_path);
+                } else {
+                    warn!(
+                        "File '{}' has {} syntax error node(s); indexing the valid parts.",
+                        file_path, parsed.error_nodes
+                    );
+                    self.partial_files
+                        .insert(file_path.clone(), parsed.error_nodes);
+                }
+                if code_parser.low_fidelity_source().is_some() {
+                    self.low_fidelity_files.insert(file_path.clone());
+                } else {
+                    self.low_fidelity_files.remove(&file_path);
+                }
+                (
+                    parsed.symbols,
+                    parsed.parents,
+                    parsed.imports.globs,
+                    parsed.todos,
+                )
+            }
+            Err(e) => {
+                self.record_failure(&file_path, e.to_string(), 0);
+                return Ok(None);
+            }
+        };
+        debug!("Parsed {} symbols from '{}'.", parsed_syms.len(), file_path);
+        self.failed_files.remove(&file_path);
+        if globs.is_empty() {
+            self.file_globs.remove(&file_path);
+        } else {
+            self.file_globs.insert(file_path.clone(), globs);
+        }
+        if todos.is_empty() {
+            self.todos.remove(&file_path);
+        } else {
+            self.todos.insert(file_path.clone(), todos);
+        }
+
+        let old_refs: Vec<&Symbol> = old_syms.iter().collect();
+        let changes = diff_symbols(&old_refs, &parsed_syms);
+        debug!("{} symbol(s) changed in '{}'.", changes.len(), file_path);
+        self.last_changes.extend(changes);
+
+        // Insert new symbols
+        for sym in &parsed_syms {
+            self.add_symbol(sym.clone());
+        }
+        self.link_parents(&parsed_syms, &parents);
+
+        // Update the file hashes
+        self.file_hashes.insert(file_path.clone(), new_hash);
+        debug!("Finished incremental update for '{}'.", &file_path);
+
+        Ok(Some(parsed_syms))
+    }
+
+    /// Finds the hashes of the symbols that the raw reference `raw_name` of the
+    /// symbol `user_hash` can refer to, each with how sure that is.
+    ///
+    /// Candidates are all symbols named like the last path segment. When there are
+    /// several, a qualified reference (`module::item`, `Type::method`) keeps those
+    /// in the named module or type, and an unqualified one keeps those in the same
+    /// file or in a glob-imported module. If that rules out every candidate, all of
+    /// them are kept, as [name-only](EdgeConfidence::NameOnly) matches. Re-exports
+    /// are followed to the item they re-export.
+    fn resolve_reference(
+        &self,
+        raw_name: &str,
+        user_hash: &str,
+    ) -> HashMap<String, EdgeConfidence> {
+        let exact = |hashes: HashSet<String>| -> HashMap<String, EdgeConfidence> {
+            hashes
+                .into_iter()
+                .map(|hash| (hash, EdgeConfidence::Exact))
+                .collect()
+        };
+        if let Some(route) = openapi::route_key(raw_name) {
+            let mut endpoints = self.name_map.get(&route).cloned().unwrap_or_default();
+            endpoints.retain(|hash| self.symbols.get(hash).is_some_and(Symbol::is_endpoint));
+            return exact(endpoints);
+        }
+        if let Some(path) = docker::referenced_path(raw_name) {
+            return exact(self.symbols_under(path, user_hash, Symbol::is_docker));
+        }
+        // Stage and service names are local to their Dockerfile or Compose file
+        if let Some(user) = self.symbols.get(user_hash).filter(|user| user.is_docker()) {
+            let mut candidates = self.name_map.get(raw_name).cloned().unwrap_or_default();
+            candidates.retain(|hash| {
+                hash != user_hash
+                    && self.symbols.get(hash).is_some_and(|candidate| {
+                        candidate.is_docker() && candidate.file_path == user.file_path
+                    })
+            });
+            return exact(candidates);
+        }
+
+        let (scope, name) = match raw_name.rsplit_once("::") {
+            Some((scope, name)) => (Some(scope), name),
+            None => (None, raw_name),
+        };
+
+        // A re-export of the user resolves to the user, which isn't a dependency
+        let follow = |hashes: HashMap<String, EdgeConfidence>| {
+            let mut resolved: HashMap<String, EdgeConfidence> = HashMap::new();
+            for (hash, confidence) in hashes {
+                for target in self.follow_reexports(HashSet::from([hash])) {
+                    let known = resolved.entry(target).or_insert(confidence);
+                    *known = confidence.max(*known);
+                }
+            }
+            resolved.remove(user_hash);
+            resolved
+        };
+        let mut candidates = self.name_map.get(name).cloned().unwrap_or_default();
+        candidates.remove(user_hash);
+        // Documents and config files can refer to code, but are never dependencies
+        // themselves, and neither are stages and services
+        candidates.retain(|hash| {
+            self.symbols
+                .get(hash)
+                .is_some_and(|candidate| candidate.is_code() && !candidate.is_docker())
+        });
+        if candidates.is_empty() {
+            // e.g. `UserServiceClient::connect` uses the service `UserService`
+            let scope_name = scope.and_then(|scope| scope.rsplit("::").next());
+            candidates = self.proto_definitions(name);
+            if let (true, Some(scope_name)) = (candidates.is_empty(), scope_name) {
+                candidates = self.proto_definitions(scope_name);
+            }
+        }
+        let Some(user) = self.symbols.get(user_hash) else {
+            return follow(exact(candidates));
+        };
+        if candidates.len() <= 1 {
+            return follow(exact(candidates));
+        }
+
+        let narrowed: HashMap<String, EdgeConfidence> = candidates
+            .iter()
+            .filter_map(|hash| {
+                let candidate = self.symbols.get(hash)?;
+                let confidence = match scope {
+                    Some(scope) => self
+                        .in_scope(candidate, scope, user)
+                        .then_some(EdgeConfidence::Exact),
+                    None if candidate.file_path == user.file_path => Some(EdgeConfidence::Exact),
+                    None => self
+                        .file_globs
+                        .get(&*user.file_path)
+                        .into_iter()
+                        .flatten()
+                        .any(|glob| self.in_scope(candidate, glob, user))
+                        .then_some(EdgeConfidence::Imported),
+                };
+                Some((hash.clone(), confidence?))
+            })
+            .collect();
+
+        let resolved = if narrowed.is_empty() {
+            candidates
+                .into_iter()
+                .map(|hash| (hash, EdgeConfidence::NameOnly))
+                .collect()
+        } else {
+            narrowed
+        };
+        follow(resolved)
+    }
+
+    /// The top-level code symbols of the files at or under `path` that `keep`
+    /// accepts, other than those in the file of `user_hash`.
+    fn symbols_under(
+        &self,
+        path: &str,
+        user_hash: &str,
+        keep: impl Fn(&Symbol) -> bool,
+    ) -> HashSet<String> {
+        let user_file = self.symbols.get(user_hash).map(|user| &*user.file_path);
+        self.file_symbols
+            .iter()
+            .filter(|(file, _)| {
+                let file = file.trim_start_matches("./");
+                Some(file) != user_file.map(|user_file| user_file.trim_start_matches("./"))
+                    && file
+                        .strip_prefix(path)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .flat_map(|(_, hashes)| hashes)
+            .filter(|hash| {
+                self.symbols.get(*hash).is_some_and(|symbol| {
+                    symbol.parent.is_none() && symbol.is_code() && keep(symbol)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The top-level code symbols of the files that the Dockerfile stage
+    /// `hash` copies, e.g. everything under `src` for `COPY src/ ./src/`.
+    ///
+    /// These aren't dependencies of the stage, so copying a directory doesn't
+    /// count as using everything in it; [`Index::reachable_from`] follows them
+    /// instead.
+    pub fn copied_symbols(&self, hash: &str) -> HashSet<String> {
+        let Some(stage) = self
+            .symbols
+            .get(hash)
+            .filter(|sym| &*sym.node_kind == Symbol::DOCKER_STAGE_KIND)
+        else {
+            return HashSet::new();
+        };
+        stage
+            .literals
+            .iter()
+            .flat_map(|path| self.symbols_under(path, hash, |sym| !sym.is_docker()))
+            .collect()
+    }
+
+    /// Protobuf definitions that code generated from them names `name`, for
+    /// references that don't match anything indexed directly (the generated code
+    /// itself usually isn't indexed).
+    fn proto_definitions(&self, name: &str) -> HashSet<String> {
+        proto::generated_names(name)
+            .iter()
+            .map(|generated| {
+                self.name_map
+                    .get(generated)
+                    .into_iter()
+                    .flatten()
+                    .filter(|hash| self.symbols.get(*hash).is_some_and(Symbol::is_proto))
+                    .cloned()
+                    .collect::<HashSet<String>>()
+            })
+            .find(|found| !found.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// Replaces re-export symbols among `hashes` with the items they re-export,
+    /// following chains of re-exports. Re-exports whose target isn't indexed
+    /// (yet) are kept as they are.
+    fn follow_reexports(&self, hashes: HashSet<String>) -> HashSet<String> {
+        let mut resolved = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut pending: Vec<String> = hashes.into_iter().collect();
+
+        while let Some(hash) = pending.pop() {
+            if !visited.insert(hash.clone()) {
+                continue;
+            }
+            let targets: Vec<String> = match self.symbols.get(&hash) {
+                Some(sym) if sym.is_reexport() => sym
+                    .dependencies
+                    .iter()
+                    .filter_map(|id| self.hash_of(*id).map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if targets.is_empty() {
+                resolved.insert(hash);
+            } else {
+                pending.extend(targets);
+            }
+        }
+
+        resolved
+    }
+
+    /// Whether `candidate` is defined in the module or type `scope`, as written in
+    /// the source of `user` (e.g. `crate::parser`, `Symbol`, or `Self`).
+    fn in_scope(&self, candidate: &Symbol, scope: &str, user: &Symbol) -> bool {
+        let parent_name = |sym: &Symbol| -> Option<&str> {
+            sym.parent
+                .and_then(|id| self.symbol(id))
+                .map(|parent| parent.name.as_str())
+        };
+
+        // `Type::method` names the candidate's parent type
+        let last = scope.rsplit("::").next().unwrap_or(scope);
+        let type_name = if last == "Self" {
+            parent_name(user)
+        } else {
+            Some(last)
+        };
+        if type_name.is_some() && parent_name(candidate) == type_name {
+            return true;
+        }
+
+        // Otherwise `scope` is a module path, possibly relative to the user's file
+        let wanted: Vec<&str> = scope
+            .split("::")
+            .filter(|segment| !matches!(*segment, "crate" | "self" | "super"))
+            .collect();
+        if wanted.is_empty() {
+            return match scope {
+                "crate" => module_path(&candidate.file_path) == "crate",
+                "self" => candidate.file_path == user.file_path,
+                _ => false,
+            };
+        }
+        let module = module_path(&candidate.file_path);
+        let segments: Vec<&str> = module.split("::").collect();
+        segments.ends_with(&wanted)
+    }
+
+    /// Links freshly added symbols to their enclosing symbols.
+    fn link_parents(&mut self, parsed_syms: &[Symbol], parents: &[(usize, usize)]) {
+        for &(child, parent) in parents {
+            let parent_id = self.symbol_table.id_for(&parsed_syms[parent].hash());
+            if let Some(child_sym) = self.symbols.get_mut(&parsed_syms[child].hash()) {
+                child_sym.parent = Some(parent_id);
+            }
+        }
+    }
+
+    /// Leaves `file_path` out of the index for `reason` (see [`crate::limits`]),
+    /// dropping whatever an earlier run indexed of it.
+    pub fn skip_file(&mut self, file_path: &str, reason: String) {
+        if !self.metadata.skipped_files.contains_key(file_path) {
+            info!("Skipping '{}': {}.", file_path, reason);
+        }
+        self.forget_file(file_path);
+        self.metadata
+            .skipped_files
+            .insert(file_path.to_string(), reason);
+    }
+
+    /// Drops the indexed files that no longer exist, e.g. deleted or renamed
+    /// ones, with their symbols. Returns the number of files dropped.
+    pub fn forget_missing_files(&mut self) -> usize {
+        let missing: Vec<String> = self
+            .file_hashes
+            .keys()
+            .chain(self.failed_files.keys())
+            .chain(self.metadata.skipped_files.keys())
+            .filter(|path| !Path::new(path).exists())
+            .cloned()
+            .collect();
+        for file_path in &missing {
+            debug!("File '{}' no longer exists. Removing it.", file_path);
+            self.forget_file(file_path);
+            self.metadata.skipped_files.remove(file_path);
+        }
+        missing.len()
+    }
+
+    /// Removes `file_path` and everything indexed of it.
+    fn forget_file(&mut self, file_path: &str) {
+        self.remove_file_symbols(file_path);
+        self.file_hashes.remove(file_path);
+        self.partial_files.remove(file_path);
+        self.low_fidelity_files.remove(file_path);
+        self.failed_files.remove(file_path);
+        self.file_globs.remove(file_path);
+        self.todos.remove(file_path);
+    }
+
+    /// Marks `file_path` as failed. Its content hash is dropped so that the next
+    /// run tries it again.
+    fn record_failure(&mut self, file_path: &str, reason: String, error_nodes: usize) {
+        warn!(
+            "Failed to index '{}': {} ({} error node(s)). Skipping.",
+            file_path, reason, error_nodes
+        );
+        self.file_hashes.remove(file_path);
+        self.partial_files.remove(file_path);
+        self.low_fidelity_files.remove(file_path);
+        self.file_globs.remove(file_path);
+        self.todos.remove(file_path);
+        self.failed_files.insert(
+            file_path.to_string(),
+            FileFailure {
+                reason,
+                error_nodes,
+                failed_at: unix_timestamp(),
+            },
+        );
+    }
+
+    /// Retries every unresolved reference against the current name map, linking
+    /// those whose target has since been indexed (e.g. forward references to files
+    /// indexed later in the run). Returns the number of references fixed.
+    pub fn recheck_unresolved(&mut self) -> usize {
+        let timer = timings::start("recheck unresolved");
+        let pending: Vec<(String, Vec<String>)> = take(&mut self.unresolved_dependencies)
+            .into_iter()
+            .filter(|(user_hash, _)| self.symbols.contains_key(user_hash))
+            .collect();
+        let resolved = self.resolve_references(pending);
+
+        let mut fixed = 0;
+        let mut resolved_reexports = Vec::new();
+        for (user_hash, references) in resolved {
+            let mut still_unresolved = Vec::new();
+            for (raw_name, candidates) in references {
+                if candidates.is_empty() {
+                    still_unresolved.push(raw_name);
+                    continue;
+                }
+
+                fixed += 1;
+                if self.symbols[&user_hash].is_reexport() {
+                    resolved_reexports.push(user_hash.clone());
+                }
+                for (dep_hash, confidence) in candidates {
+                    self.link_with(&user_hash, &dep_hash, confidence);
+                }
+            }
+
+            if !still_unresolved.is_empty() {
+                self.unresolved_dependencies
+                    .insert(user_hash, still_unresolved);
+            }
+        }
+
+        // Symbols linked to these re-exports while they were still unresolved now
+        // depend on the re-exported items instead
+        for reexport_hash in resolved_reexports {
+            self.redirect_reexport_users(&reexport_hash);
+        }
+
+        timer.stop(fixed);
+        fixed
+    }
+
+    /// Resolves the raw references of each user in parallel, without changing the
+    /// index: the map phase of resolution. Returns the candidates of every
+    /// reference, empty if it is unresolved, for the caller to apply.
+    fn resolve_references(&self, pending: Vec<(String, Vec<String>)>) -> Vec<ResolvedReferences> {
+        pending
+            .into_par_iter()
+            .map(|(user_hash, names)| {
+                let references = names
+                    .into_iter()
+                    .map(|raw_name| {
+                        let candidates = self.resolve_reference(&raw_name, &user_hash);
+                        (raw_name, candidates)
+                    })
+                    .collect();
+                (user_hash, references)
+            })
+            .collect()
+    }
+
+    /// Moves the users of the re-export `reexport_hash` over to the items it
+    /// re-exports.
+    fn redirect_reexport_users(&mut self, reexport_hash: &str) {
+        let Some(reexport_id) = self.symbol_table.get(reexport_hash) else {
+            return;
+        };
+        let Some(reexport) = self.symbols.get(reexport_hash) else {
+            return;
+        };
+        let users: Vec<SymbolId> = reexport.used_by.iter().copied().collect();
+        let targets = self.follow_reexports(HashSet::from([reexport_hash.to_string()]));
+
+        for user_id in users {
+            let Some(user_hash) = self.hash_of(user_id).map(str::to_string) else {
+                continue;
+            };
+            let confidence = self.confidence(user_id, reexport_id);
+            self.unlink(user_id, reexport_id);
+            // `link` leaves out a symbol re-exported next to a use of itself
+            for target_hash in &targets {
+                self.link_with(&user_hash, target_hash, confidence);
+            }
+        }
+    }
+
+    /// Links freshly added symbols to what their raw references resolve to,
+    /// recording the rest as unresolved.
+    fn resolve_new_symbols(&mut self, new_symbols: &[Symbol]) {
+        let timer = timings::start("resolution");
+        // Extract and clear the raw references collected by the parser
+        let mut pending = Vec::new();
+        for sym in new_symbols {
+            let hash = sym.hash();
+            if let Some(sym_mut) = self.symbols.get_mut(&hash) {
+                let raw_names = take(&mut sym_mut.references);
+                pending.push((hash, raw_names.into_iter().collect()));
+            }
+        }
+        let resolved = self.resolve_references(pending);
+
+        let mut resolved_reexports = Vec::new();
+
+        for (this_hash, references) in resolved {
+            let mut linked = false;
+
+            for (raw_name, candidates) in references {
+                if candidates.is_empty() {
+                    let sym = &self.symbols[&this_hash];
+                    // Most route-like strings aren't endpoints of an indexed spec, so
+                    // they aren't kept as unresolved; `link_route_users` links them
+                    // to endpoints indexed later
+                    if openapi::route_key(&raw_name).is_some() {
+                        debug!(
+                            "Route '{}' of symbol '{}' matches no endpoint. (File: {})",
+                            raw_name, sym.name, sym.file_path
+                        );
+                        continue;
+                    }
+                    if let Some(class) = css::referenced_class(&raw_name) {
+                        // e.g. utility classes of a CSS framework
+                        debug!(
+                            "Class '{}' of symbol '{}' matches no indexed style. (File: {})",
+                            class, sym.name, sym.file_path
+                        );
+                    } else if let Some(path) = docker::referenced_path(&raw_name) {
+                        debug!(
+                            "Path '{}' of symbol '{}' matches no indexed Dockerfile. (File: {})",
+                            path, sym.name, sym.file_path
+                        );
+                    } else {
+                        warn!(
+                            "Dependency '{}' not found for symbol '{}'. (File: {})",
+                            raw_name, sym.name, sym.file_path
+                        );
+                    }
+                    // Add to unresolved dependencies
+                    self.unresolved_dependencies
+                        .entry(this_hash.clone())
+                        .or_default()
+                        .push(raw_name);
+                } else {
+                    // Add all candidates as edges
+                    for (dep_hash, confidence) in candidates {
+                        linked |= self.link_with(&this_hash, &dep_hash, confidence);
+                    }
+                }
+            }
+
+            if linked && self.symbols[&this_hash].is_reexport() {
+                resolved_reexports.push(this_hash);
+            }
+        }
+
+        // Re-exports resolved in the same pass as their users were still
+        // unresolved when the users were resolved
+        for reexport_hash in resolved_reexports {
+            self.redirect_reexport_users(&reexport_hash);
+        }
+        if new_symbols.iter().any(Symbol::is_endpoint) {
+            self.link_route_users();
+        }
+        timer.stop(new_symbols.len());
+    }
+
+    /// Links the symbols using a route, in a string literal or a route attribute,
+    /// to the endpoints serving it. Unmatched routes aren't kept as unresolved
+    /// references, so this catches up on code indexed before its spec.
+    fn link_route_users(&mut self) {
+        let mut edges = Vec::new();
+        for (hash, sym) in &self.symbols {
+            let routes = sym
+                .literals
+                .iter()
+                .cloned()
+                .chain(
+                    sym.attributes
+                        .iter()
+                        .filter_map(|attr| openapi::route_in_attribute(attr)),
+                )
+                .filter(|route| openapi::route_key(route).is_some());
+            for route in routes {
+                for (endpoint, confidence) in self.resolve_reference(&route, hash) {
+                    edges.push((hash.clone(), endpoint, confidence));
+                }
+            }
+        }
+        for (user, endpoint, confidence) in edges {
+            self.link_with(&user, &endpoint, confidence);
+        }
+    }
+
+    /// The names `sym` is found by: its own, the names FFI bindings export it
+    /// under, and the routes of endpoints.
+    fn name_keys(sym: &Symbol) -> Vec<String> {
+        let mut keys = vec![sym.name.clone()];
+        keys.extend(
+            sym.foreign_names()
+                .into_iter()
+                .filter(|name| *name != sym.name),
+        );
+        if sym.is_endpoint() {
+            keys.extend(openapi::endpoint_keys(sym));
+        }
+        keys
+    }
+
+    fn build_name_map(&mut self) {
+        self.name_map.clear();
+        for (hash, sym) in &self.symbols {
+            for key in Self::name_keys(sym) {
+                self.name_map.entry(key).or_default().insert(hash.clone());
+            }
+        }
+    }
+
+    fn remove_hash_from_name_map(&mut self, sym: &Symbol, sym_hash: &str) {
+        for key in Self::name_keys(sym) {
+            if let Some(hashes) = self.name_map.get_mut(&key) {
+                hashes.remove(sym_hash);
+                if hashes.is_empty() {
+                    self.name_map.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn add_symbol(&mut self, mut sym: Symbol) {
+        sym.node_kind = self.node_kinds.share(&sym.node_kind);
+        let hash = sym.hash();
+        self.symbol_table.id_for(&hash);
+
+        let keys = Self::name_keys(&sym);
+        self.file_symbols
+            .entry(sym.file_path.to_string())
+            .or_default()
+            .insert(hash.clone());
+        if let Some(old_sym) = self.symbols.insert(hash.clone(), sym) {
+            self.remove_hash_from_name_map(&old_sym, &hash);
+        }
+
+        for key in keys {
+            self.name_map.entry(key).or_default().insert(hash.clone());
+        }
+    }
+
+    /// Removes every symbol defined in `file_path`, returning how many were removed.
+    fn remove_file_symbols(&mut self, file_path: &str) -> usize {
+        let hashes = self.file_symbols.remove(file_path).unwrap_or_default();
+        let removed = hashes.len();
+        for hash in hashes {
+            self.remove_symbol(&hash);
+        }
+        removed
+    }
+
+    /// Removes a symbol and detaches it from the graph.
+    ///
+    /// Only the neighbours recorded in the symbol's own `dependencies`/`used_by` sets
+    /// are touched, so the cost is proportional to its degree rather than the size of
+    /// the index. Users of the removed symbol get its name back as an unresolved
+    /// reference so they can be re-linked to a replacement definition.
+    fn remove_symbol(&mut self, sym_hash: &str) -> Option<Symbol> {
+        let removed_sym = self.symbols.remove(sym_hash)?;
+        self.remove_hash_from_name_map(&removed_sym, sym_hash);
+        if let Some(hashes) = self.file_symbols.get_mut(&*removed_sym.file_path) {
+            hashes.remove(sym_hash);
+            if hashes.is_empty() {
+                self.file_symbols.remove(&*removed_sym.file_path);
+            }
+        }
+        self.unresolved_dependencies.remove(sym_hash);
+
+        let Some(removed_id) = self.symbol_table.get(sym_hash) else {
+            return Some(removed_sym);
+        };
+
+        for dep_id in &removed_sym.dependencies {
+            self.unlink(removed_id, *dep_id);
+        }
+
+        for user_id in &removed_sym.used_by {
+            if self.unlink(*user_id, removed_id) {
+                let Some(user_hash) = self.symbol_table.hash(*user_id) else {
+                    continue;
+                };
+                let pending = self
+                    .unresolved_dependencies
+                    .entry(user_hash.to_string())
+                    .or_default();
+                if !pending.contains(&removed_sym.name) {
+                    pending.push(removed_sym.name.clone());
+                }
+            }
+        }
+
+        Some(removed_sym)
+    }
+}
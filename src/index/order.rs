@@ -0,0 +1,61 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::Index;
+
+impl Index {
+    /// `files` ordered so that each comes after the files it depends on, as far
+    /// as that goes: a dependency cycle is entered at the file with the fewest
+    /// dependencies left in it. Files that are free to go in any order go by
+    /// path, so the order is the same on every run.
+    pub fn dependency_order<'a>(&self, files: &[&'a str]) -> Vec<&'a str> {
+        let included: HashSet<&str> = files.iter().copied().collect();
+        let mut dependencies: HashMap<&'a str, HashSet<&str>> = HashMap::new();
+        let mut users: HashMap<&str, Vec<&'a str>> = HashMap::new();
+        for &file in files {
+            let file_dependencies: HashSet<&str> = self
+                .symbols_in_file(file)
+                .flat_map(|(_, sym)| &sym.dependencies)
+                .filter_map(|id| self.symbol(*id))
+                .map(|dep| &*dep.file_path)
+                .filter(|dep_file| *dep_file != file && included.contains(dep_file))
+                .collect();
+            for &dep_file in &file_dependencies {
+                users.entry(dep_file).or_default().push(file);
+            }
+            dependencies.insert(file, file_dependencies);
+        }
+
+        let mut ready: BTreeSet<&'a str> = files
+            .iter()
+            .copied()
+            .filter(|file| dependencies[file].is_empty())
+            .collect();
+        let mut ordered = Vec::with_capacity(files.len());
+        while ordered.len() < dependencies.len() {
+            // Without a file ready, only cycles are left
+            let cycle_entry = || {
+                dependencies
+                    .iter()
+                    .filter(|(_, left)| !left.is_empty())
+                    .min_by_key(|(file, left)| (left.len(), **file))
+                    .map(|(file, _)| *file)
+            };
+            let Some(next) = ready.pop_first().or_else(cycle_entry) else {
+                break;
+            };
+            if let Some(left) = dependencies.get_mut(next) {
+                left.clear();
+            }
+            ordered.push(next);
+            for &user in users.get(next).into_iter().flatten() {
+                let Some(left) = dependencies.get_mut(user) else {
+                    continue;
+                };
+                if left.remove(next) && left.is_empty() {
+                    ready.insert(user);
+                }
+            }
+        }
+        ordered
+    }
+}
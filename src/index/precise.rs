@@ -0,0 +1,68 @@
+use std::collections::{HashMap, HashSet};
+
+use super::Index;
+
+impl Index {
+    /// Replaces the heuristic dependencies of symbols with precise ones, e.g.
+    /// imported from a compiler-backed indexer.
+    ///
+    /// `references` maps caller hashes to the hashes of the symbols they refer to.
+    /// For every symbol of `covered_files`, edges into those files are replaced by
+    /// its precise ones; edges into other files (documents, configs, other
+    /// languages) are kept, as are unresolved references not matching a precise
+    /// target by name. Returns the number of edges that weren't known before.
+    pub fn apply_precise_references(
+        &mut self,
+        references: &HashMap<String, HashSet<String>>,
+        covered_files: &HashSet<String>,
+    ) -> usize {
+        let callers: Vec<String> = covered_files
+            .iter()
+            .flat_map(|path| self.file_symbols.get(path).into_iter().flatten())
+            .cloned()
+            .collect();
+        let no_targets = HashSet::new();
+
+        let mut added = 0;
+        for caller_hash in &callers {
+            let targets = references.get(caller_hash).unwrap_or(&no_targets);
+            let caller_id = self.symbol_table.id_for(caller_hash);
+
+            let previous = self.symbols[caller_hash].dependencies.clone();
+            let stale: Vec<_> = previous
+                .iter()
+                .copied()
+                .filter(|id| {
+                    self.symbol(*id)
+                        .is_some_and(|dep| covered_files.contains(&*dep.file_path))
+                })
+                .collect();
+            for dep_id in stale {
+                self.unlink(caller_id, dep_id);
+            }
+
+            let mut target_names = HashSet::new();
+            for target_hash in targets {
+                let Some(target) = self.symbols.get(target_hash) else {
+                    continue;
+                };
+                target_names.insert(target.name.clone());
+                let target_id = self.symbol_table.id_for(target_hash);
+                if self.link(caller_hash, target_hash) && !previous.contains(&target_id) {
+                    added += 1;
+                }
+            }
+
+            if let Some(names) = self.unresolved_dependencies.get_mut(caller_hash) {
+                names.retain(|name| {
+                    let last = name.rsplit("::").next().unwrap_or(name);
+                    !target_names.contains(last)
+                });
+                if names.is_empty() {
+                    self.unresolved_dependencies.remove(caller_hash);
+                }
+            }
+        }
+        added
+    }
+}
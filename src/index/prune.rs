@@ -0,0 +1,91 @@
+use log::warn;
+
+use super::Index;
+use crate::arch::path_matches;
+use crate::config::PruneConfig;
+
+/// What [`Index::prune`] removed.
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    /// Doc comments dropped
+    pub docs: usize,
+    /// Symbols of excluded files dropped
+    pub excluded: usize,
+    /// Symbols dropped from files over the per-file cap
+    pub capped: usize,
+    /// Files that had symbols dropped by the cap
+    pub capped_files: usize,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.docs == 0 && self.excluded == 0 && self.capped == 0
+    }
+}
+
+impl Index {
+    /// Applies the pruning policies of `config`, warning about every file whose
+    /// symbols are capped.
+    ///
+    /// Dropped symbols are detached from the graph like removed ones, so their
+    /// users keep the references as unresolved. Files stay recorded with their
+    /// hashes and aren't re-parsed until they change.
+    pub fn prune(&mut self, config: &PruneConfig) -> PruneReport {
+        let mut report = PruneReport::default();
+
+        if config.drop_docs {
+            for sym in self.symbols.values_mut() {
+                if sym.doc.take().is_some() {
+                    report.docs += 1;
+                }
+            }
+        }
+
+        let mut paths: Vec<String> = self.file_symbols.keys().cloned().collect();
+        paths.sort();
+        for path in paths {
+            if config
+                .exclude
+                .iter()
+                .any(|pattern| path_matches(pattern, &path))
+            {
+                report.excluded += self.remove_file_symbols(&path);
+                continue;
+            }
+
+            let Some(max) = config.max_symbols_per_file else {
+                continue;
+            };
+            let mut symbols: Vec<(&String, _)> = self
+                .symbols_in_file(&path)
+                .map(|(hash, sym)| {
+                    (
+                        hash,
+                        (sym.parent.is_some(), sym.line_number, sym.start_byte),
+                    )
+                })
+                .collect();
+            if symbols.len() <= max {
+                continue;
+            }
+            symbols.sort_by_key(|(_, order)| *order);
+            let dropped: Vec<String> = symbols[max..]
+                .iter()
+                .map(|(hash, _)| (*hash).clone())
+                .collect();
+            warn!(
+                "'{}' has {} symbols; keeping the first {} of them.",
+                path,
+                symbols.len(),
+                max
+            );
+            for hash in &dropped {
+                self.remove_symbol(hash);
+            }
+            report.capped += dropped.len();
+            report.capped_files += 1;
+        }
+
+        report
+    }
+}
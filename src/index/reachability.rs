@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use super::{EdgeConfidence, Index};
+use crate::arch::{glob_matches, qualified_name};
+use crate::config::EntryPointsConfig;
+use crate::symbol::Symbol;
+
+impl Index {
+    /// Finds the entry points `config` declares or detects and marks every
+    /// symbol [reachable](Index::reachable_from) from them. Returns the number of
+    /// entry points.
+    pub fn mark_reachable(&mut self, config: &EntryPointsConfig) -> usize {
+        self.entry_points = self
+            .symbols
+            .iter()
+            .filter(|(_, sym)| sym.is_code() && is_entry_point(self, sym, config))
+            .map(|(hash, _)| hash.clone())
+            .collect();
+
+        let roots: Vec<&str> = self.entry_points.iter().map(String::as_str).collect();
+        self.reachable = self.reachable_from(roots, EdgeConfidence::NameOnly);
+        self.entry_points.len()
+    }
+
+    /// Hashes of the symbols reachable from the `roots` hashes through
+    /// dependencies linked with at least `min_confidence`, including the roots. A
+    /// used method also makes the type it belongs to reachable, and a Dockerfile
+    /// stage the [code it copies](Index::copied_symbols).
+    pub fn reachable_from<'a>(
+        &self,
+        roots: impl IntoIterator<Item = &'a str>,
+        min_confidence: EdgeConfidence,
+    ) -> HashSet<String> {
+        let mut reachable: HashSet<String> = HashSet::new();
+        let mut stack: Vec<&str> = roots.into_iter().collect();
+        while let Some(hash) = stack.pop() {
+            let Some(sym) = self.symbols.get(hash) else {
+                continue;
+            };
+            if !reachable.insert(hash.to_string()) {
+                continue;
+            }
+            for id in self
+                .dependencies_with(hash, min_confidence)
+                .chain(sym.parent)
+            {
+                if let Some(next) = self.hash_of(id) {
+                    if !reachable.contains(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            for copied in self.copied_symbols(hash) {
+                if let Some((next, _)) = self.symbols.get_key_value(&copied) {
+                    if !reachable.contains(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Whether the symbol with the given hash is reachable from an entry point.
+    /// Without any entry points, there is nothing to tell, so every symbol is.
+    pub fn is_reachable(&self, hash: &str) -> bool {
+        self.entry_points.is_empty() || self.reachable.contains(hash)
+    }
+}
+
+fn is_entry_point(index: &Index, sym: &Symbol, config: &EntryPointsConfig) -> bool {
+    if config.detect
+        && ((sym.name == "main" && &*sym.node_kind == "function_item" && sym.parent.is_none())
+            || !sym.foreign_names().is_empty())
+    {
+        return true;
+    }
+    let marked = sym.attributes.iter().any(|attr| {
+        let path = attr.split_once('(').map_or(attr.as_str(), |(path, _)| path);
+        config
+            .attributes
+            .iter()
+            .any(|pattern| path.trim() == pattern)
+    });
+    marked
+        || (!config.symbols.is_empty() && {
+            let qualified = qualified_name(index, sym);
+            config
+                .symbols
+                .iter()
+                .any(|pattern| glob_matches(pattern, &qualified, sym))
+        })
+}
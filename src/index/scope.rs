@@ -0,0 +1,50 @@
+use super::Index;
+use crate::arch::path_matches;
+
+impl Index {
+    /// Narrows the loaded index to the files matching any of the `scope` globs
+    /// (a plain directory matches everything under it), for commands analysing
+    /// a subtree. Returns the number of files kept.
+    ///
+    /// Symbols outside the scope are dropped without detaching them, so the
+    /// graph keeps the edges to them (e.g. in the user counts of the symbols
+    /// kept) and reachability stays that of the whole project. A narrowed index
+    /// must not be saved.
+    pub fn restrict_to(&mut self, scope: &[String]) -> usize {
+        let in_scope = |path: &str| {
+            scope.iter().any(|pattern| {
+                let pattern = pattern.trim_end_matches('/');
+                path_matches(pattern, path) || path_matches(&format!("{}/**", pattern), path)
+            })
+        };
+
+        let outside: Vec<String> = self
+            .file_hashes
+            .keys()
+            .chain(self.file_symbols.keys())
+            .filter(|path| !in_scope(path))
+            .cloned()
+            .collect();
+        for path in &outside {
+            for hash in self.file_symbols.remove(path).unwrap_or_default() {
+                if let Some(sym) = self.symbols.remove(&hash) {
+                    self.remove_hash_from_name_map(&sym, &hash);
+                }
+                self.unresolved_dependencies.remove(&hash);
+                self.entry_points.remove(&hash);
+                self.reachable.remove(&hash);
+            }
+            self.file_hashes.remove(path);
+        }
+
+        self.failed_files.retain(|path, _| in_scope(path));
+        self.partial_files.retain(|path, _| in_scope(path));
+        self.low_fidelity_files.retain(|path| in_scope(path));
+        self.generated_files.retain(|path| in_scope(path));
+        self.third_party_files.retain(|path| in_scope(path));
+        self.todos.retain(|path, _| in_scope(path));
+        self.last_changes
+            .retain(|change| in_scope(&change.file_path));
+        self.file_hashes.len()
+    }
+}
@@ -0,0 +1,468 @@
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use super::{EdgeConfidence, FileFailure, Index, SymbolChange};
+use crate::interner::StringInterner;
+use crate::metadata::IndexMetadata;
+use crate::output::symbol_order;
+use crate::parser::todos::Todo;
+use crate::symbol::{Blame, Metrics, Symbol, SymbolId, Visibility};
+
+/// Version of the on-disk format, written in front of every index so that
+/// indexes of another version ask for a rebuild instead of failing to decode.
+/// Bump it with every change to [`StoredIndex`] or the types it contains.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The on-disk representation of an [`Index`].
+///
+/// Every string that tends to repeat (file paths, symbol names, node kinds) lives
+/// once in `strings`; everything else refers to it by position. Symbols are stored
+/// grouped by file, which doubles as the serialized per-file symbol index. Graph
+/// edges refer to symbols by their position in the flattened file order, which
+/// becomes their [`SymbolId`] on load. Symbol hashes are not stored at all since
+/// they are recomputed from the symbol.
+#[derive(Serialize, Deserialize)]
+struct StoredIndex {
+    metadata: IndexMetadata,
+    strings: Vec<String>,
+    files: Vec<StoredFile>,
+    /// (caller hash, raw name IDs)
+    unresolved_dependencies: Vec<(String, Vec<u32>)>,
+    /// (file path ID, failure)
+    failed_files: Vec<(u32, FileFailure)>,
+    last_changes: Vec<SymbolChange>,
+    /// (file path ID, [(file path ID, weight)])
+    co_changes: Vec<(u32, Vec<(u32, f32)>)>,
+    /// (target label ID, [dependency label ID])
+    target_deps: Vec<(u32, Vec<u32>)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredFile {
+    path: u32,
+    /// `None` for files that have symbols but no recorded content hash
+    content_hash: Option<String>,
+    /// Syntax error nodes of a partially indexed file; zero if it parsed cleanly
+    error_nodes: usize,
+    /// Modules glob-imported by the file
+    glob_imports: Vec<u32>,
+    todos: Vec<Todo>,
+    generated: bool,
+    third_party: bool,
+    /// Only definitions were indexed
+    low_fidelity: bool,
+    /// Labels of the build targets owning the file
+    targets: Vec<u32>,
+    /// Name of the Cargo package owning the file
+    krate: Option<u32>,
+    /// `cfg` attributes guarding the whole file
+    cfgs: Vec<u32>,
+    symbols: Vec<StoredSymbol>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSymbol {
+    name: u32,
+    node_kind: u32,
+    line_number: usize,
+    start_byte: usize,
+    end_byte: usize,
+    visibility: Visibility,
+    attributes: Vec<u32>,
+    signature: String,
+    doc: Option<String>,
+    /// Raw SHA256 bytes; hex-encoded in memory
+    body_hash: Vec<u8>,
+    parent: Option<u32>,
+    literals: Vec<u32>,
+    /// (commit ID, author ID, time)
+    blame: Option<(u32, u32, u64)>,
+    metrics: Option<Metrics>,
+    dependencies: Vec<u32>,
+    /// Those of `dependencies` resolution wasn't sure of; the others are exact
+    weak_dependencies: Vec<(u32, EdgeConfidence)>,
+    used_by: Vec<u32>,
+    entry_point: bool,
+    reachable: bool,
+}
+
+impl StoredIndex {
+    fn from_index(index: &Index) -> Self {
+        let mut interner = StringInterner::new();
+
+        let paths: BTreeSet<&String> = index
+            .file_hashes
+            .keys()
+            .chain(index.file_symbols.keys())
+            .collect();
+
+        // Renumber live symbols densely in file order; edges to removed symbols are
+        // dropped here. Everything is sorted so the same index encodes the same way.
+        let ordered: Vec<(&String, Vec<&String>)> = paths
+            .into_iter()
+            .map(|path| {
+                let mut hashes: Vec<&String> = index
+                    .file_symbols
+                    .get(path)
+                    .map(|hashes| hashes.iter().collect())
+                    .unwrap_or_default();
+                hashes.sort_by(|a, b| {
+                    symbol_order(&index.symbols[*a], &index.symbols[*b]).then_with(|| a.cmp(b))
+                });
+                (path, hashes)
+            })
+            .collect();
+        let positions: HashMap<SymbolId, u32> = ordered
+            .iter()
+            .flat_map(|(_, hashes)| hashes.iter())
+            .enumerate()
+            .filter_map(|(pos, hash)| Some((index.symbol_table.get(hash)?, pos as u32)))
+            .collect();
+        let renumber = |ids: &HashSet<SymbolId>| -> Vec<u32> {
+            let mut positions: Vec<u32> = ids
+                .iter()
+                .filter_map(|id| positions.get(id).copied())
+                .collect();
+            positions.sort_unstable();
+            positions
+        };
+        let weak = |hash: &String, ids: &HashSet<SymbolId>| -> Vec<(u32, EdgeConfidence)> {
+            let Some(user_id) = index.symbol_table.get(hash) else {
+                return Vec::new();
+            };
+            let mut weak: Vec<(u32, EdgeConfidence)> = ids
+                .iter()
+                .filter_map(|id| {
+                    let confidence = index.weak_edges.get(&(user_id, *id))?;
+                    Some((*positions.get(id)?, *confidence))
+                })
+                .collect();
+            weak.sort_unstable();
+            weak
+        };
+
+        let files = ordered
+            .into_iter()
+            .map(|(path, hashes)| StoredFile {
+                path: interner.intern(path),
+                content_hash: index.file_hashes.get(path).cloned(),
+                error_nodes: index.partial_files.get(path).copied().unwrap_or_default(),
+                glob_imports: index
+                    .file_globs
+                    .get(path)
+                    .into_iter()
+                    .flatten()
+                    .map(|glob| interner.intern(glob))
+                    .collect(),
+                todos: index.todos.get(path).cloned().unwrap_or_default(),
+                generated: index.generated_files.contains(path.as_str()),
+                third_party: index.third_party_files.contains(path.as_str()),
+                low_fidelity: index.low_fidelity_files.contains(path.as_str()),
+                targets: index
+                    .file_targets
+                    .get(path)
+                    .into_iter()
+                    .flatten()
+                    .map(|label| interner.intern(label))
+                    .collect(),
+                krate: index
+                    .file_crates
+                    .get(path)
+                    .map(|name| interner.intern(name)),
+                cfgs: index
+                    .file_cfgs
+                    .get(path)
+                    .into_iter()
+                    .flatten()
+                    .map(|attribute| interner.intern(attribute))
+                    .collect(),
+                symbols: hashes
+                    .into_iter()
+                    .map(|hash| {
+                        let sym = &index.symbols[hash];
+                        StoredSymbol {
+                            name: interner.intern(&sym.name),
+                            node_kind: interner.intern(&sym.node_kind),
+                            line_number: sym.line_number,
+                            start_byte: sym.start_byte,
+                            end_byte: sym.end_byte,
+                            visibility: sym.visibility.clone(),
+                            attributes: sym
+                                .attributes
+                                .iter()
+                                .map(|attr| interner.intern(attr))
+                                .collect(),
+                            signature: sym.signature.clone(),
+                            doc: sym.doc.clone(),
+                            body_hash: hex::decode(&sym.body_hash).unwrap_or_default(),
+                            parent: sym.parent.and_then(|id| positions.get(&id).copied()),
+                            literals: sym
+                                .literals
+                                .iter()
+                                .map(|literal| interner.intern(literal))
+                                .collect(),
+                            blame: sym.blame.as_ref().map(|blame| {
+                                (
+                                    interner.intern(&blame.commit),
+                                    interner.intern(&blame.author),
+                                    blame.time,
+                                )
+                            }),
+                            metrics: sym.metrics,
+                            dependencies: renumber(&sym.dependencies),
+                            weak_dependencies: weak(hash, &sym.dependencies),
+                            used_by: renumber(&sym.used_by),
+                            entry_point: index.entry_points.contains(hash),
+                            reachable: index.reachable.contains(hash),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let mut unresolved: Vec<(&String, &Vec<String>)> =
+            index.unresolved_dependencies.iter().collect();
+        unresolved.sort();
+        let unresolved_dependencies = unresolved
+            .into_iter()
+            .map(|(caller, names)| {
+                (
+                    caller.clone(),
+                    names.iter().map(|n| interner.intern(n)).collect(),
+                )
+            })
+            .collect();
+
+        let mut failed: Vec<(&String, &FileFailure)> = index.failed_files.iter().collect();
+        failed.sort_by(|a, b| a.0.cmp(b.0));
+        let failed_files = failed
+            .into_iter()
+            .map(|(path, failure)| (interner.intern(path), failure.clone()))
+            .collect();
+
+        let mut co_changes: Vec<(&String, &Vec<(String, f32)>)> = index.co_changes.iter().collect();
+        co_changes.sort_by(|a, b| a.0.cmp(b.0));
+        let co_changes = co_changes
+            .into_iter()
+            .map(|(path, edges)| {
+                let edges = edges
+                    .iter()
+                    .map(|(other, weight)| (interner.intern(other), *weight))
+                    .collect();
+                (interner.intern(path), edges)
+            })
+            .collect();
+
+        let mut target_deps: Vec<(&String, &Vec<String>)> = index.target_deps.iter().collect();
+        target_deps.sort_by(|a, b| a.0.cmp(b.0));
+        let target_deps = target_deps
+            .into_iter()
+            .map(|(label, deps)| {
+                let deps = deps.iter().map(|dep| interner.intern(dep)).collect();
+                (interner.intern(label), deps)
+            })
+            .collect();
+
+        StoredIndex {
+            metadata: index.metadata.clone(),
+            strings: interner.into_strings(),
+            files,
+            unresolved_dependencies,
+            failed_files,
+            last_changes: index.last_changes.clone(),
+            co_changes,
+            target_deps,
+        }
+    }
+
+    fn into_index(self) -> Result<Index, String> {
+        let strings = &self.strings;
+        let lookup = |id: u32| -> Result<&String, String> {
+            strings
+                .get(id as usize)
+                .ok_or_else(|| format!("String table has no entry {}.", id))
+        };
+
+        let symbol_count: usize = self.files.iter().map(|f| f.symbols.len()).sum();
+        let edge = |pos: u32| -> Result<SymbolId, String> {
+            if (pos as usize) < symbol_count {
+                Ok(SymbolId(pos))
+            } else {
+                Err(format!("Edge points at missing symbol {}.", pos))
+            }
+        };
+
+        let mut index = Index {
+            metadata: self.metadata,
+            last_changes: self.last_changes,
+            ..Default::default()
+        };
+
+        // Symbols are inserted in stored order so each one's ID equals its position
+        for file in self.files {
+            // One allocation of the path shared by all of the file's symbols
+            let file_path: Arc<str> = Arc::from(lookup(file.path)?.as_str());
+            if let Some(content_hash) = file.content_hash {
+                index
+                    .file_hashes
+                    .insert(file_path.to_string(), content_hash);
+            }
+            if file.error_nodes > 0 {
+                index
+                    .partial_files
+                    .insert(file_path.to_string(), file.error_nodes);
+            }
+
+            if !file.glob_imports.is_empty() {
+                let globs = file
+                    .glob_imports
+                    .into_iter()
+                    .map(|id| lookup(id).cloned())
+                    .collect::<Result<_, _>>()?;
+                index.file_globs.insert(file_path.to_string(), globs);
+            }
+            if !file.todos.is_empty() {
+                index.todos.insert(file_path.to_string(), file.todos);
+            }
+            if file.generated {
+                index.generated_files.insert(file_path.to_string());
+            }
+            if file.third_party {
+                index.third_party_files.insert(file_path.to_string());
+            }
+            if file.low_fidelity {
+                index.low_fidelity_files.insert(file_path.to_string());
+            }
+            if !file.targets.is_empty() {
+                let targets = file
+                    .targets
+                    .into_iter()
+                    .map(|id| lookup(id).cloned())
+                    .collect::<Result<_, _>>()?;
+                index.file_targets.insert(file_path.to_string(), targets);
+            }
+            if let Some(krate) = file.krate {
+                index
+                    .file_crates
+                    .insert(file_path.to_string(), lookup(krate)?.clone());
+            }
+            if !file.cfgs.is_empty() {
+                let cfgs = file
+                    .cfgs
+                    .into_iter()
+                    .map(|id| lookup(id).cloned())
+                    .collect::<Result<_, _>>()?;
+                index.file_cfgs.insert(file_path.to_string(), cfgs);
+            }
+
+            for stored in file.symbols {
+                let sym = Symbol {
+                    name: lookup(stored.name)?.clone(),
+                    node_kind: index.node_kinds.share(lookup(stored.node_kind)?),
+                    file_path: file_path.clone(),
+                    line_number: stored.line_number,
+                    start_byte: stored.start_byte,
+                    end_byte: stored.end_byte,
+                    visibility: stored.visibility,
+                    attributes: stored
+                        .attributes
+                        .into_iter()
+                        .map(|id| lookup(id).cloned())
+                        .collect::<Result<_, _>>()?,
+                    signature: stored.signature,
+                    doc: stored.doc,
+                    body_hash: hex::encode(stored.body_hash),
+                    parent: stored.parent.map(edge).transpose()?,
+                    references: Default::default(),
+                    literals: stored
+                        .literals
+                        .into_iter()
+                        .map(|id| lookup(id).cloned())
+                        .collect::<Result<_, _>>()?,
+                    blame: match stored.blame {
+                        Some((commit, author, time)) => Some(Blame {
+                            commit: lookup(commit)?.clone(),
+                            author: lookup(author)?.clone(),
+                            time,
+                        }),
+                        None => None,
+                    },
+                    metrics: stored.metrics,
+                    dependencies: stored
+                        .dependencies
+                        .into_iter()
+                        .map(edge)
+                        .collect::<Result<_, _>>()?,
+                    used_by: stored
+                        .used_by
+                        .into_iter()
+                        .map(edge)
+                        .collect::<Result<_, _>>()?,
+                };
+                let hash = sym.hash();
+                let id = index.symbol_table.id_for(&hash);
+                for (dep, confidence) in stored.weak_dependencies {
+                    index.weak_edges.insert((id, edge(dep)?), confidence);
+                }
+                if stored.entry_point {
+                    index.entry_points.insert(hash.clone());
+                }
+                if stored.reachable {
+                    index.reachable.insert(hash.clone());
+                }
+                index
+                    .file_symbols
+                    .entry(file_path.to_string())
+                    .or_default()
+                    .insert(hash.clone());
+                index.symbols.insert(hash, sym);
+            }
+        }
+
+        for (caller, names) in self.unresolved_dependencies {
+            let names = names
+                .into_iter()
+                .map(|id| lookup(id).cloned())
+                .collect::<Result<_, _>>()?;
+            index.unresolved_dependencies.insert(caller, names);
+        }
+
+        for (path, failure) in self.failed_files {
+            index.failed_files.insert(lookup(path)?.clone(), failure);
+        }
+
+        for (path, edges) in self.co_changes {
+            let edges = edges
+                .into_iter()
+                .map(|(other, weight)| Ok((lookup(other)?.clone(), weight)))
+                .collect::<Result<_, String>>()?;
+            index.co_changes.insert(lookup(path)?.clone(), edges);
+        }
+
+        for (label, deps) in self.target_deps {
+            let deps = deps
+                .into_iter()
+                .map(|id| lookup(id).cloned())
+                .collect::<Result<_, _>>()?;
+            index.target_deps.insert(lookup(label)?.clone(), deps);
+        }
+
+        Ok(index)
+    }
+}
+
+impl Serialize for Index {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredIndex::from_index(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Index {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        StoredIndex::deserialize(deserializer)?
+            .into_index()
+            .map_err(D::Error::custom)
+    }
+}
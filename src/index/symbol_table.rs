@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use crate::symbol::SymbolId;
+
+/// Side table mapping compact [`SymbolId`]s to the stable SHA256 symbol hashes.
+///
+/// Graph edges are stored as IDs to keep `dependencies`/`used_by` small; the hash
+/// remains the external identity used in output and exports. IDs are never reused
+/// within a session so a stale edge can't silently point at a different symbol;
+/// they are compacted whenever the index is written and loaded again.
+#[derive(Default, Debug)]
+pub struct SymbolTable {
+    /// ID -> hash
+    hashes: Vec<String>,
+
+    /// hash -> ID
+    ids: HashMap<String, SymbolId>,
+}
+
+impl SymbolTable {
+    /// Returns the ID for `hash`, allocating a new one if it has none yet.
+    pub fn id_for(&mut self, hash: &str) -> SymbolId {
+        if let Some(&id) = self.ids.get(hash) {
+            return id;
+        }
+        let id = SymbolId(self.hashes.len() as u32);
+        self.hashes.push(hash.to_string());
+        self.ids.insert(hash.to_string(), id);
+        id
+    }
+
+    /// Looks up the ID of `hash` without allocating.
+    pub fn get(&self, hash: &str) -> Option<SymbolId> {
+        self.ids.get(hash).copied()
+    }
+
+    /// Returns the hash an ID was allocated for.
+    pub fn hash(&self, id: SymbolId) -> Option<&str> {
+        self.hashes.get(id.0 as usize).map(String::as_str)
+    }
+}
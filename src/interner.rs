@@ -0,0 +1,59 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Deduplicates strings by assigning each distinct value a compact `u32` ID.
+///
+/// Used when writing the index to disk so that file paths, symbol names, node
+/// kinds, and symbol hashes repeated across many symbols and edges are stored once
+/// in a string table and referenced by ID everywhere else.
+#[derive(Default, Debug)]
+pub struct StringInterner {
+    /// ID -> string
+    strings: Vec<String>,
+
+    /// string -> ID
+    ids: HashMap<String, u32>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        StringInterner::default()
+    }
+
+    /// Returns the ID for `value`, adding it to the table if it is new.
+    pub fn intern(&mut self, value: &str) -> u32 {
+        if let Some(&id) = self.ids.get(value) {
+            return id;
+        }
+        let id = self.strings.len() as u32;
+        self.strings.push(value.to_string());
+        self.ids.insert(value.to_string(), id);
+        id
+    }
+
+    /// Consumes the interner, returning the string table ordered by ID.
+    pub fn into_strings(self) -> Vec<String> {
+        self.strings
+    }
+}
+
+/// Hands out one shared allocation per distinct string.
+///
+/// Used by the live [`Index`](crate::index::Index) for the node kinds of its
+/// symbols, of which there are only a few distinct values across all symbols.
+#[derive(Default, Debug)]
+pub struct SharedStrings {
+    strings: HashSet<Arc<str>>,
+}
+
+impl SharedStrings {
+    /// Returns the shared copy of `value`, adding it if it is new.
+    pub fn share(&mut self, value: &str) -> Arc<str> {
+        if let Some(shared) = self.strings.get(value) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(value);
+        self.strings.insert(shared.clone());
+        shared
+    }
+}
@@ -0,0 +1,41 @@
+pub mod arch;
+pub mod build_targets;
+pub mod bundle;
+pub mod cargo;
+pub mod cfg;
+pub mod churn;
+pub mod cochange;
+pub mod commands;
+pub mod config;
+pub mod datalog;
+pub mod detect;
+pub mod errors;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(not(feature = "fixtures"))]
+mod fixtures;
+pub mod generated;
+pub mod git;
+pub mod index;
+pub mod interner;
+pub mod limits;
+pub mod llm;
+pub mod metadata;
+pub mod models;
+pub mod output;
+pub mod owners;
+pub mod parser;
+pub mod profile;
+pub mod query;
+pub mod redact;
+pub mod related;
+pub mod remote;
+pub mod rust_analyzer;
+pub mod sarif;
+pub mod scip;
+pub mod sink;
+pub mod symbol;
+pub mod telemetry;
+pub mod third_party;
+pub mod timings;
+pub mod utils;
@@ -0,0 +1,68 @@
+//! Files left out of the index for their size or content.
+//!
+//! Before a file is parsed, it is checked against the `[files]` config section:
+//! files matching a `skip` glob, larger than `max_bytes` (multi-megabyte
+//! generated files), or, with `skip_binary`, with a NUL byte among their first
+//! bytes (blobs that happen to have a source extension) aren't indexed. The
+//! reasons are recorded in the index metadata and listed by
+//! `contextmesh stats --errors`.
+//!
+//! Text that isn't valid UTF-8 (e.g. Latin-1 comments) is indexed anyway, with
+//! each invalid byte read as `?` (see [`replace_invalid_utf8`]), which keeps the
+//! byte ranges of symbols pointing into the file.
+
+use std::fs::File;
+use std::io::Read;
+
+use crate::arch::path_matches;
+use crate::config::FilesConfig;
+
+/// How much of a file is searched for a NUL byte.
+const BINARY_PROBE_BYTES: u64 = 8192;
+
+/// Why `path` isn't to be indexed, or `None` if it is.
+pub fn skip_reason(path: &str, config: &FilesConfig) -> Option<String> {
+    if let Some(pattern) = config
+        .skip
+        .iter()
+        .find(|pattern| path_matches(pattern, path))
+    {
+        return Some(format!("matches the skip pattern `{}`", pattern));
+    }
+    let size = std::fs::metadata(path).ok()?.len();
+    if size > config.max_bytes {
+        return Some(format!(
+            "{} bytes, over the limit of {}",
+            size, config.max_bytes
+        ));
+    }
+    if config.skip_binary && is_binary(path) {
+        return Some("binary content".to_string());
+    }
+    None
+}
+
+/// Whether the start of the file at `path` contains a NUL byte, which text in
+/// any of the indexed languages doesn't.
+fn is_binary(path: &str) -> bool {
+    let mut start = Vec::new();
+    let read =
+        File::open(path).and_then(|file| file.take(BINARY_PROBE_BYTES).read_to_end(&mut start));
+    read.is_ok() && start.contains(&0)
+}
+
+/// Replaces each byte of `code` that isn't part of valid UTF-8 with `?`, keeping
+/// the length (and every offset) unchanged. Valid UTF-8 is returned as is.
+pub fn replace_invalid_utf8(mut code: Vec<u8>) -> Vec<u8> {
+    let mut pos = 0;
+    while let Err(error) = std::str::from_utf8(&code[pos..]) {
+        let invalid_start = pos + error.valid_up_to();
+        // `None` for a sequence cut off by the end of the input
+        let invalid_len = error.error_len().unwrap_or(code.len() - invalid_start);
+        for byte in &mut code[invalid_start..invalid_start + invalid_len] {
+            *byte = b'?';
+        }
+        pos = invalid_start + invalid_len;
+    }
+    code
+}
@@ -0,0 +1,206 @@
+//! LLM providers answering questions about bundles, used by `contextmesh ask`.
+//!
+//! A [`Provider`] turns a system prompt and a user prompt into the HTTP request
+//! of its API and reads the answer back out of the response; sending it is
+//! shared. Requests go through `curl`, like `push` and `pull` do, with the
+//! request fed on stdin so the API key doesn't show up in the process list.
+//!
+//! The `[llm]` section of the config picks the provider, model, and endpoint,
+//! which can also be any server speaking one of the two APIs (e.g. a local
+//! OpenAI-compatible one). The API key is read from an environment variable.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+use crate::config::LlmConfig;
+use crate::errors::ContextMeshError;
+
+/// An HTTP POST of a JSON body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+/// An LLM API.
+pub trait Provider {
+    /// The request asking the model to answer `prompt` following `system`.
+    fn request(&self, system: &str, prompt: &str) -> Request;
+
+    /// The text of the answer in a successful `response`.
+    fn answer(&self, response: &Value) -> Option<String>;
+
+    /// Sends the request for `prompt` and returns the answer.
+    fn complete(&self, system: &str, prompt: &str) -> Result<String, ContextMeshError> {
+        let response = send(&self.request(system, prompt))?;
+        self.answer(&response).ok_or_else(|| {
+            ContextMeshError::LlmError(format!("Response without an answer: {}", response))
+        })
+    }
+}
+
+/// The Anthropic Messages API.
+pub struct Anthropic {
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: String,
+    pub max_tokens: u32,
+}
+
+impl Provider for Anthropic {
+    fn request(&self, system: &str, prompt: &str) -> Request {
+        Request {
+            url: format!("{}/v1/messages", self.endpoint),
+            headers: vec![
+                ("x-api-key".to_string(), self.api_key.clone()),
+                ("anthropic-version".to_string(), "2023-06-01".to_string()),
+            ],
+            body: json!({
+                "model": self.model,
+                "max_tokens": self.max_tokens,
+                "system": system,
+                "messages": [{ "role": "user", "content": prompt }],
+            }),
+        }
+    }
+
+    fn answer(&self, response: &Value) -> Option<String> {
+        let text: Vec<&str> = response["content"]
+            .as_array()?
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+        (!text.is_empty()).then(|| text.concat())
+    }
+}
+
+/// The OpenAI Chat Completions API.
+pub struct OpenAi {
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: String,
+    pub max_tokens: u32,
+}
+
+impl Provider for OpenAi {
+    fn request(&self, system: &str, prompt: &str) -> Request {
+        Request {
+            url: format!("{}/v1/chat/completions", self.endpoint),
+            headers: vec![(
+                "Authorization".to_string(),
+                format!("Bearer {}", self.api_key),
+            )],
+            body: json!({
+                "model": self.model,
+                "max_tokens": self.max_tokens,
+                "messages": [
+                    { "role": "system", "content": system },
+                    { "role": "user", "content": prompt },
+                ],
+            }),
+        }
+    }
+
+    fn answer(&self, response: &Value) -> Option<String> {
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+    }
+}
+
+/// The provider `config` names, with its API key read from the environment.
+pub fn provider(config: &LlmConfig) -> Result<Box<dyn Provider>, ContextMeshError> {
+    let (default_endpoint, default_model, default_key_env) = match config.provider.as_str() {
+        "anthropic" => (
+            "https://api.anthropic.com",
+            "claude-sonnet-4-5",
+            "ANTHROPIC_API_KEY",
+        ),
+        "openai" => ("https://api.openai.com", "gpt-4o", "OPENAI_API_KEY"),
+        other => {
+            return Err(ContextMeshError::ConfigError(format!(
+                "Unknown LLM provider '{}' (expected `anthropic` or `openai`)",
+                other
+            )))
+        }
+    };
+    let key_env = config.api_key_env.as_deref().unwrap_or(default_key_env);
+    let api_key = std::env::var(key_env)
+        .map_err(|_| {
+            ContextMeshError::LlmError(format!("The API key variable {} is not set", key_env))
+        })?
+        .trim()
+        .to_string();
+    let endpoint = config
+        .endpoint
+        .as_deref()
+        .unwrap_or(default_endpoint)
+        .trim_end_matches('/')
+        .to_string();
+    let model = config.model.as_deref().unwrap_or(default_model).to_string();
+    Ok(match config.provider.as_str() {
+        "anthropic" => Box::new(Anthropic {
+            endpoint,
+            model,
+            api_key,
+            max_tokens: config.max_tokens,
+        }),
+        _ => Box::new(OpenAi {
+            endpoint,
+            model,
+            api_key,
+            max_tokens: config.max_tokens,
+        }),
+    })
+}
+
+/// Posts `request` with curl and returns the JSON response.
+fn send(request: &Request) -> Result<Value, ContextMeshError> {
+    // A curl config file (see `curl --config`) carrying the URL, headers, and body
+    let mut config = format!("url = {}\n", curl_quote(&request.url));
+    for (name, value) in &request.headers {
+        config.push_str(&format!(
+            "header = {}\n",
+            curl_quote(&format!("{}: {}", name, value))
+        ));
+    }
+    config.push_str("header = \"content-type: application/json\"\n");
+    config.push_str(&format!(
+        "data-binary = {}\n",
+        curl_quote(&request.body.to_string())
+    ));
+
+    let mut child = Command::new("curl")
+        .args(["-sS", "--fail-with-body", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ContextMeshError::LlmError(format!("Failed to run curl: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let body = String::from_utf8_lossy(&output.stdout);
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|error| error["error"]["message"].as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&output.stderr).trim().to_string());
+        return Err(ContextMeshError::LlmError(format!(
+            "Request to {} failed: {}",
+            request.url, message
+        )));
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| ContextMeshError::DeserializationError(format!("LLM response: {}", e)))
+}
+
+/// `value` as a double-quoted string of a curl config file.
+fn curl_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
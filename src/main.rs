@@ -1,21 +1,19 @@
-use clap::Parser;
-use commands::Cli;
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
+use contextmesh::commands::{self, Cli};
 use env_logger::Env;
 
-mod commands;
-mod errors;
-mod index;
-mod parser;
-mod symbol;
-mod utils;
-
 fn main() {
+    // Answers completion requests from the shell, before anything is logged
+    CompleteEnv::with_factory(Cli::command).complete();
+
     // Initialize logger
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
     let args = Cli::parse();
+    let porcelain = args.porcelain;
     if let Err(e) = commands::run_command(args) {
-        eprintln!("Error: {}", e);
+        e.report(porcelain);
         std::process::exit(1);
     }
 }
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::config::Config;
+use crate::git::current_git_commit;
+use crate::utils::{calculate_file_hash, project_root, unix_timestamp};
+
+/// Describes how and when an index was produced.
+///
+/// The metadata block is stored alongside the symbols so that a loaded index can
+/// be checked for staleness (tool upgrades, config edits, different commits) and
+/// so that `contextmesh stats` can report where an index came from.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexMetadata {
+    /// The contextmesh version that last wrote the index.
+    pub tool_version: String,
+
+    /// Unix timestamp (seconds) of when the index was first created.
+    pub created_at: u64,
+
+    /// Unix timestamp (seconds) of the last index run.
+    pub updated_at: u64,
+
+    /// Languages that have been indexed into this index.
+    pub languages: BTreeSet<String>,
+
+    /// SHA256 of the config file used during the last run, if one existed.
+    pub config_hash: Option<String>,
+
+    /// The git commit `HEAD` pointed at during the last run, if inside a repository.
+    pub git_commit: Option<String>,
+
+    /// The absolute path of the project root during the last run. Indexed paths
+    /// are relative to the root (see [`crate::utils::root_relative`]), so they
+    /// resolve against wherever the project is now; this records where it was.
+    pub root: Option<String>,
+
+    /// Node kinds indexed as symbols during the last run of each language, so that
+    /// a change of the configured kinds can trigger a full re-index.
+    pub definition_kinds: BTreeMap<String, BTreeSet<String>>,
+
+    /// Files left out of the index for their size or content, with the reason;
+    /// see [`crate::limits`].
+    pub skipped_files: BTreeMap<String, String>,
+}
+
+impl IndexMetadata {
+    pub fn new() -> Self {
+        let now = unix_timestamp();
+        IndexMetadata {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: now,
+            updated_at: now,
+            ..Default::default()
+        }
+    }
+
+    /// Records a completed index run for `language`.
+    pub fn touch(&mut self, language: &str) {
+        self.tool_version = env!("CARGO_PKG_VERSION").to_string();
+        self.updated_at = unix_timestamp();
+        self.languages.insert(language.to_lowercase());
+        self.config_hash = calculate_file_hash(Config::CONFIG_FILE_PATH);
+        self.git_commit = current_git_commit();
+        self.root = project_root()
+            .ok()
+            .map(|root| root.to_string_lossy().into_owned());
+    }
+}
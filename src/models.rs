@@ -0,0 +1,120 @@
+//! Context window profiles of LLMs, which turn `--model <name>` into a token
+//! budget.
+//!
+//! A profile gives the model's context size, the tokens kept free for its
+//! answer, and the tokenizer it counts with. Token counts everywhere else are
+//! [estimated](crate::utils::estimate_tokens) at four bytes per token, so a
+//! profile's budget is converted to that estimate: a model whose tokenizer
+//! packs fewer bytes into a token fits less code. Profiles of common models are
+//! built in; `[models.<name>]` sections of the config add more or replace them.
+
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::errors::ContextMeshError;
+use crate::utils::estimate_tokens;
+
+/// The tokenizer a model counts with, by how many bytes of source code a token
+/// holds on average.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Tokenizer {
+    /// Four bytes per token, the estimate used elsewhere
+    #[default]
+    Approximate,
+    /// Anthropic's Claude models
+    Claude,
+    /// OpenAI's GPT-4 and GPT-3.5 models
+    Cl100k,
+    /// OpenAI's GPT-4o and later models
+    O200k,
+}
+
+impl Tokenizer {
+    pub fn bytes_per_token(self) -> f64 {
+        match self {
+            Tokenizer::Approximate => 4.0,
+            Tokenizer::Claude => 3.5,
+            Tokenizer::Cl100k => 3.8,
+            Tokenizer::O200k => 4.1,
+        }
+    }
+}
+
+/// A `[models.<name>]` section of the config file.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ModelProfile {
+    /// Size of the context window in tokens.
+    pub context_tokens: usize,
+
+    /// Tokens kept free for the model's answer.
+    #[serde(default = "default_reserved_output")]
+    pub reserved_output: usize,
+
+    #[serde(default)]
+    pub tokenizer: Tokenizer,
+}
+
+fn default_reserved_output() -> usize {
+    4096
+}
+
+/// (name, context tokens, reserved output tokens, tokenizer) of the built-in
+/// profiles.
+const BUILTIN_PROFILES: &[(&str, usize, usize, Tokenizer)] = &[
+    ("claude-opus", 200_000, 32_000, Tokenizer::Claude),
+    ("claude-sonnet", 200_000, 16_000, Tokenizer::Claude),
+    ("claude-haiku", 200_000, 8_000, Tokenizer::Claude),
+    ("gpt-4.1", 1_047_576, 32_768, Tokenizer::O200k),
+    ("gpt-4.1-mini", 1_047_576, 32_768, Tokenizer::O200k),
+    ("gpt-4o", 128_000, 16_384, Tokenizer::O200k),
+    ("gpt-4o-mini", 128_000, 16_384, Tokenizer::O200k),
+    ("gpt-4-turbo", 128_000, 4_096, Tokenizer::Cl100k),
+    ("o3", 200_000, 100_000, Tokenizer::O200k),
+];
+
+impl ModelProfile {
+    /// The profile named `name` in the config, or else the built-in one.
+    pub fn named(config: &Config, name: &str) -> Result<ModelProfile, ContextMeshError> {
+        if let Some(profile) = config.models.get(name) {
+            return Ok(profile.clone());
+        }
+        BUILTIN_PROFILES
+            .iter()
+            .find(|(builtin, ..)| *builtin == name)
+            .map(
+                |&(_, context_tokens, reserved_output, tokenizer)| ModelProfile {
+                    context_tokens,
+                    reserved_output,
+                    tokenizer,
+                },
+            )
+            .ok_or_else(|| {
+                ContextMeshError::ConfigError(format!(
+                    "No model profile '{}' (known: {})",
+                    name,
+                    ModelProfile::names(config).join(", ")
+                ))
+            })
+    }
+
+    /// The names of the built-in profiles and those of the config, sorted.
+    pub fn names(config: &Config) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_PROFILES
+            .iter()
+            .map(|(name, ..)| name.to_string())
+            .chain(config.models.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The tokens left for context once the answer's are reserved, as counted
+    /// by [`estimate_tokens`].
+    pub fn budget(&self) -> usize {
+        let tokens = self.context_tokens.saturating_sub(self.reserved_output);
+        estimate_tokens((tokens as f64 * self.tokenizer.bytes_per_token()) as usize)
+    }
+}
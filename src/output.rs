@@ -0,0 +1,189 @@
+//! What commands print: a canonical ordering, so output is the same from run to
+//! run whatever order the index's hash maps iterate in, and [`Table`] for
+//! results with the same fields per row.
+
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::index::Index;
+use crate::symbol::{Symbol, SymbolId};
+use crate::utils::estimate_tokens;
+
+/// Orders symbols by file, line, name, and then span and kind, which tells
+/// apart everything but identical definitions.
+pub fn symbol_order(a: &Symbol, b: &Symbol) -> Ordering {
+    sort_key(a).cmp(&sort_key(b))
+}
+
+fn sort_key(sym: &Symbol) -> (&str, usize, &str, usize, usize, &str) {
+    (
+        &sym.file_path,
+        sym.line_number,
+        &sym.name,
+        sym.start_byte,
+        sym.end_byte,
+        &sym.node_kind,
+    )
+}
+
+/// The (hash, symbol) pairs of `index` in [`symbol_order`], ties broken by hash.
+pub fn sorted_symbols(index: &Index) -> Vec<(&String, &Symbol)> {
+    let mut symbols: Vec<(&String, &Symbol)> = index.symbols.iter().collect();
+    symbols.sort_by(|(a_hash, a), (b_hash, b)| symbol_order(a, b).then_with(|| a_hash.cmp(b_hash)));
+    symbols
+}
+
+/// The hashes of the live symbols among `ids`, e.g. a symbol's dependencies,
+/// in [`symbol_order`].
+pub fn sorted_edges<'a>(index: &'a Index, ids: &HashSet<SymbolId>) -> Vec<&'a str> {
+    let mut edges: Vec<(&str, &Symbol)> = ids
+        .iter()
+        .filter_map(|id| Some((index.hash_of(*id)?, index.symbol(*id)?)))
+        .collect();
+    edges.sort_by(|(a_hash, a), (b_hash, b)| symbol_order(a, b).then_with(|| a_hash.cmp(b_hash)));
+    edges.into_iter().map(|(hash, _)| hash).collect()
+}
+
+/// Rows of values under named columns, printed as an aligned text table, CSV,
+/// or a JSON array of objects keyed by column.
+#[derive(Debug, Clone)]
+pub struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new(columns: &[&str]) -> Self {
+        Table {
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// The usual columns for a list of symbols: name, kind, location, the
+    /// number of dependencies and users, and the estimated tokens of the source.
+    pub fn of_symbols<'a>(symbols: impl IntoIterator<Item = &'a Symbol>) -> Self {
+        let mut table = Table::new(&["name", "kind", "location", "deps", "users", "tokens"]);
+        for sym in symbols {
+            table.push(vec![
+                sym.name.clone().into(),
+                (&*sym.node_kind).into(),
+                format!("{}:{}", sym.file_path, sym.line_number).into(),
+                sym.dependencies.len().into(),
+                sym.used_by.len().into(),
+                estimate_tokens(sym.end_byte.saturating_sub(sym.start_byte)).into(),
+            ]);
+        }
+        table
+    }
+
+    /// Appends a row with a value for each column.
+    pub fn push(&mut self, row: Vec<Value>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Columns padded to their widest value, numbers aligned to the right.
+    pub fn to_text(&self) -> String {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(cell_text).collect())
+            .collect();
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|i| {
+                cells
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([self.columns[i].chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let numeric: Vec<bool> = (0..self.columns.len())
+            .map(|i| !self.rows.is_empty() && self.rows.iter().all(|row| row[i].is_number()))
+            .collect();
+
+        let line = |values: &[String]| {
+            let padded: Vec<String> = values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let padding = " ".repeat(widths[i] - value.chars().count());
+                    if numeric[i] {
+                        format!("{}{}", padding, value)
+                    } else {
+                        format!("{}{}", value, padding)
+                    }
+                })
+                .collect();
+            format!("{}\n", padded.join("  ").trim_end())
+        };
+
+        let mut out = line(&self.columns);
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        out.push_str(&line(&rule));
+        for row in &cells {
+            out.push_str(&line(row));
+        }
+        out
+    }
+
+    /// RFC 4180 CSV with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        let mut write_row = |values: Vec<String>| {
+            let escaped: Vec<String> = values.iter().map(|value| csv_field(value)).collect();
+            out.push_str(&escaped.join(","));
+            out.push_str("\r\n");
+        };
+        write_row(self.columns.clone());
+        for row in &self.rows {
+            write_row(row.iter().map(cell_text).collect());
+        }
+        out
+    }
+
+    /// The rows as JSON objects keyed by column.
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.rows
+                .iter()
+                .map(|row| {
+                    let object: Map<String, Value> = self
+                        .columns
+                        .iter()
+                        .cloned()
+                        .zip(row.iter().cloned())
+                        .collect();
+                    Value::Object(object)
+                })
+                .collect(),
+        )
+    }
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,107 @@
+//! Code ownership from CODEOWNERS files and git blame.
+//!
+//! The CODEOWNERS file is looked for where GitHub and GitLab look for it (see
+//! [`CODEOWNERS_PATHS`]) and, as there, the last rule matching a file decides its
+//! owners. Patterns follow gitignore: a leading or inner `/` anchors a pattern
+//! at the root, other patterns match at any depth, and a pattern matching a
+//! directory covers everything in it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::arch::path_matches;
+use crate::errors::ContextMeshError;
+use crate::git;
+
+/// Where a CODEOWNERS file is looked for, in order.
+pub const CODEOWNERS_PATHS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// The rules of a CODEOWNERS file.
+#[derive(Debug, Default)]
+pub struct CodeOwners {
+    /// (file glob, owners) in file order
+    rules: Vec<(String, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Loads the first CODEOWNERS file found; without one, nothing has owners.
+    pub fn load() -> Result<Self, ContextMeshError> {
+        match CODEOWNERS_PATHS
+            .iter()
+            .find(|path| Path::new(path).is_file())
+        {
+            Some(path) => Ok(Self::parse(&fs::read_to_string(path)?)),
+            None => Ok(CodeOwners::default()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim)
+            // GitLab section headers (`[Docs]`) aren't rules
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('['))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                let owners = fields
+                    .take_while(|field| !field.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                Some((glob(pattern), owners))
+            })
+            .collect();
+        CodeOwners { rules }
+    }
+
+    /// The owners of the file at `path`; empty if no rule names any.
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        let path = path.trim_start_matches("./");
+        self.rules
+            .iter()
+            .rev()
+            .find(|(glob, _)| {
+                path_matches(glob, path) || path_matches(&format!("{}/**", glob), path)
+            })
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// A CODEOWNERS pattern as a [`path_matches`] glob.
+fn glob(pattern: &str) -> String {
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    match anchored {
+        true => trimmed.to_string(),
+        false => format!("**/{}", trimmed),
+    }
+}
+
+/// How many lines of the file at `path` each author changed last, according to
+/// `git blame`, counting only the lines starting within bytes `range` if given.
+pub fn blamed_lines(
+    path: &str,
+    range: Option<&Range<usize>>,
+) -> Result<BTreeMap<String, usize>, ContextMeshError> {
+    let mut lines = BTreeMap::new();
+    let mut offset = 0;
+    for line in git::blame(Path::new(path))? {
+        if range.is_none_or(|range| range.contains(&offset)) {
+            *lines.entry(line.author).or_default() += 1;
+        }
+        offset += line.len;
+    }
+    Ok(lines)
+}
@@ -0,0 +1,411 @@
+//! Indexing of UI components in JSX and TSX files, and of HTML pages.
+//!
+//! Top-level PascalCase functions, arrow functions, `memo`/`forwardRef`
+//! wrappers, styled components, and `React.Component` classes become `component`
+//! symbols. A component depends on the components it renders (`<Button>`,
+//! `<Menu.Item>`) and on the CSS classes it applies through `className` (or
+//! `class`): string literals in the attribute, e.g. `className={clsx("btn",
+//! active && "btn-active")}`, and members of imported CSS modules
+//! (`styles.card`). Classes are referred to by their dotted names and resolve to
+//! the rules indexed by the [stylesheet indexer](super::css). Components imported
+//! from packages rather than relative paths aren't recorded as references, and
+//! renaming imports (`import { Button as Btn }`) are followed back to the
+//! original name.
+//!
+//! An HTML page becomes a single `html_page` symbol, named after its file, that
+//! depends on the classes its elements use.
+//!
+//! Files are scanned rather than parsed: comments and the contents of string and
+//! template literals are masked out, and a top-level definition runs until the
+//! next line starting a new top-level statement.
+
+use regex::Regex;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use super::css;
+use super::{Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions handled by the component indexer.
+pub const EXTENSIONS: &[&str] = &["jsx", "tsx", "html"];
+
+/// `function Name`, `const Name = (...) =>`, `const Name = memo(...)`,
+/// `const Name = styled.div`, or `class Name extends React.Component`, at the
+/// start of a line.
+static DEFINITION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^(export[ \t]+(?:default[ \t]+)?)?(?:(?:async[ \t]+)?function\*?[ \t]+([A-Z][\w$]*)|(?:const|let|var)[ \t]+([A-Z][\w$]*)\b[^=\n]*=[ \t]*(?:async\b|function\b|\(|[\w$]+[ \t]*=>|<|(?:React\.)?(?:memo|forwardRef)\b|styled\b)|class[ \t]+([A-Z][\w$]*)[ \t]+extends[ \t]+(?:React\.)?(?:Pure)?Component\b)",
+    )
+    .expect("valid regex")
+});
+
+/// An element of a component, e.g. `<Button` or `<Menu.Item`.
+static ELEMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<([A-Z][\w$]*(?:\.[\w$]+)*)").expect("valid regex"));
+
+/// The start of a class attribute, before its value.
+static CLASS_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:className|class)[ \t]*=[ \t]*").expect("valid regex"));
+
+/// A string or template literal in a class attribute's expression.
+static STRING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""([^"\n]*)"|'([^'\n]*)'|`([^`]*)`"#).expect("valid regex"));
+
+/// A member of an object, e.g. `styles.card` or `styles["card-title"]`.
+static MEMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\b([A-Za-z_$][\w$]*)(?:\.([A-Za-z_][\w-]*)|\[\s*["']([\w-]+)["']\s*\])"#)
+        .expect("valid regex")
+});
+
+/// `import Default, { Named as Alias } from "source"` or
+/// `import * as Namespace from "source"`.
+static IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?m)^import[ \t]+(?:type[ \t]+)?(?:([\w$]+)[ \t]*,?[ \t]*)?(?:\{([^}]*)\})?[ \t]*(?:\*[ \t]*as[ \t]+([\w$]+))?[ \t]*from[ \t]*["']([^"']+)["']"#,
+    )
+    .expect("valid regex")
+});
+
+/// A valid class name.
+static CLASS_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^-?[_a-zA-Z][\w-]*$").expect("valid regex"));
+
+/// What a file's imports tell about the names it uses.
+#[derive(Default)]
+struct ImportedNames {
+    /// Local names imported from packages, which aren't indexed
+    external: HashSet<String>,
+    /// Local names of imported CSS modules
+    stylesheets: HashSet<String>,
+    imports: Imports,
+}
+
+/// Indexes JSX and TSX files into component symbols, and HTML files into pages.
+pub struct ComponentIndexer;
+
+impl ComponentIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        let new_symbol = |name: String, kind: &str, range: (usize, usize), signature, doc| {
+            let source = &text[range.0..range.1];
+            Symbol {
+                name,
+                node_kind: kind.into(),
+                file_path: shared_path.clone(),
+                line_number: line_starts.partition_point(|&start| start <= range.0),
+                start_byte: range.0,
+                end_byte: range.1,
+                visibility: Visibility::Private,
+                attributes: Vec::new(),
+                signature,
+                doc,
+                body_hash: hash_bytes(source.as_bytes()),
+                parent: None,
+                references: HashSet::new(),
+                literals: BTreeSet::new(),
+                blame: None,
+                metrics: None,
+                dependencies: HashSet::new(),
+                used_by: HashSet::new(),
+            }
+        };
+
+        if file_path.ends_with(".html") {
+            let name = Path::new(file_path)
+                .file_name()
+                .map_or(file_path.to_string(), |name| {
+                    name.to_string_lossy().into_owned()
+                });
+            let title = text
+                .split_once("<title>")
+                .and_then(|(_, rest)| rest.split_once("</title>"))
+                .map(|(title, _)| title.trim().to_string())
+                .filter(|title| !title.is_empty());
+            let mut page = new_symbol(
+                name.clone(),
+                Symbol::HTML_PAGE_KIND,
+                (0, text.len()),
+                name,
+                title,
+            );
+            page.visibility = Visibility::Public;
+            page.references = class_references(&text, &text, &HashSet::new());
+            return ParsedFile {
+                symbols: vec![page],
+                imports: Imports::default(),
+                parents: Vec::new(),
+                error_nodes: 0,
+                todos: Vec::new(),
+            };
+        }
+
+        let masked = mask(&text);
+        let names = imported_names(&text);
+        let mut symbols = Vec::new();
+        for definition in DEFINITION.captures_iter(&masked) {
+            let whole = definition.get(0).expect("match");
+            let Some(name) = (2..=4).find_map(|group| definition.get(group)) else {
+                continue;
+            };
+            let name = name.as_str();
+            // PascalCase rather than a constant like `API_URL`
+            if !name.contains(|c: char| c.is_ascii_lowercase()) {
+                continue;
+            }
+            let start = whole.start();
+            let end = statement_end(&masked, start);
+            let signature = text[start..end]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .trim_end_matches('{')
+                .trim_end()
+                .to_string();
+            let mut component = new_symbol(
+                name.to_string(),
+                Symbol::COMPONENT_KIND,
+                (start, end),
+                signature,
+                css::leading_comment(&text[..start]),
+            );
+            if definition.get(1).is_some() {
+                component.visibility = Visibility::Public;
+            }
+            component.references = element_references(&masked[start..end], &names);
+            component.references.extend(class_references(
+                &text[start..end],
+                &masked[start..end],
+                &names.stylesheets,
+            ));
+            symbols.push(component);
+        }
+
+        ParsedFile {
+            symbols,
+            imports: names.imports,
+            parents: Vec::new(),
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+/// The local names a file imports from packages and stylesheets, and its
+/// renaming imports.
+fn imported_names(text: &str) -> ImportedNames {
+    let mut names = ImportedNames::default();
+    for import in IMPORT.captures_iter(text) {
+        let source = &import[4];
+        let mut local_names: Vec<String> = Vec::new();
+        for group in [1, 3] {
+            if let Some(name) = import.get(group) {
+                local_names.push(name.as_str().to_string());
+            }
+        }
+        if let Some(named) = import.get(2) {
+            for item in named.as_str().split(',') {
+                let item = item.trim().trim_start_matches("type ");
+                let (original, local) = match item.split_once(" as ") {
+                    Some((original, local)) => (original.trim(), local.trim()),
+                    None => (item, item),
+                };
+                if local.is_empty() {
+                    continue;
+                }
+                if local != original {
+                    names
+                        .imports
+                        .aliases
+                        .insert(local.to_string(), original.to_string());
+                }
+                local_names.push(local.to_string());
+            }
+        }
+
+        let is_stylesheet = css::EXTENSIONS
+            .iter()
+            .any(|ext| source.ends_with(&format!(".{}", ext)));
+        // `@/` and `~/` are the usual aliases of the project's source directory
+        let is_package = !["./", "../", "/", "@/", "~/"]
+            .iter()
+            .any(|prefix| source.starts_with(prefix));
+        if is_stylesheet {
+            names.stylesheets.extend(local_names);
+        } else if is_package {
+            names.external.extend(local_names);
+        }
+    }
+    names
+}
+
+/// The components rendered in `masked`, e.g. `Menu::Item` for `<Menu.Item>`.
+/// Type arguments such as `useState<User>` directly follow a name and aren't
+/// elements.
+fn element_references(masked: &str, names: &ImportedNames) -> HashSet<String> {
+    let mut references = HashSet::new();
+    for element in ELEMENT.captures_iter(masked) {
+        let start = element.get(0).expect("match").start();
+        let preceding = masked[..start].chars().next_back();
+        if preceding.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '.')) {
+            continue;
+        }
+        let mut segments: Vec<&str> = element[1].split('.').collect();
+        if names.external.contains(segments[0]) {
+            continue;
+        }
+        if let Some(original) = names.imports.aliases.get(segments[0]) {
+            segments[0] = original;
+        }
+        references.insert(segments.join("::"));
+    }
+    references
+}
+
+/// The classes applied by the class attributes in `text`, as class references.
+/// `masked` is `text` with strings masked out, used to find attributes and the
+/// end of expressions; members of `stylesheets` are CSS module classes.
+fn class_references(text: &str, masked: &str, stylesheets: &HashSet<String>) -> HashSet<String> {
+    let mut classes = Vec::new();
+    for attribute in CLASS_ATTRIBUTE.find_iter(masked) {
+        let start = attribute.end();
+        let value = match masked.as_bytes().get(start) {
+            Some(quote @ (b'"' | b'\'')) => {
+                let end = text[start + 1..]
+                    .find(*quote as char)
+                    .map_or(text.len(), |end| start + 1 + end);
+                &text[start..(end + 1).min(text.len())]
+            }
+            Some(b'{') => match matching_brace(masked, start) {
+                Some(end) => &text[start + 1..end],
+                None => continue,
+            },
+            _ => continue,
+        };
+        for literal in STRING.captures_iter(value) {
+            let literal = (1..=3)
+                .find_map(|group| literal.get(group))
+                .map_or("", |m| m.as_str());
+            classes.extend(
+                literal
+                    .split_whitespace()
+                    .filter(|class| CLASS_NAME.is_match(class))
+                    .map(str::to_string),
+            );
+        }
+        for member in MEMBER.captures_iter(value) {
+            if stylesheets.contains(&member[1]) {
+                let class = member.get(2).or_else(|| member.get(3)).expect("member");
+                classes.push(class.as_str().to_string());
+            }
+        }
+    }
+    classes
+        .iter()
+        .map(|class| css::class_reference(class))
+        .collect()
+}
+
+/// The position of the brace closing the one at `open`.
+fn matching_brace(masked: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, byte) in masked.bytes().enumerate().skip(open) {
+        match byte {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The end of the top-level statement starting at `start`: a `;` outside any
+/// brackets, or the last line before a line starting a new statement at the
+/// start of a line.
+fn statement_end(masked: &str, start: usize) -> usize {
+    let bytes = masked.as_bytes();
+    let mut depth = 0isize;
+    let mut end = bytes.len();
+    for idx in start..bytes.len() {
+        match bytes[idx] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b';' if depth <= 0 => {
+                end = idx + 1;
+                break;
+            }
+            b'\n' if depth <= 0 => {
+                let next = bytes.get(idx + 1).copied();
+                let continues = next.is_none_or(|byte| {
+                    byte.is_ascii_whitespace() || b")]}.?:<>/|&=+-*".contains(&byte)
+                });
+                if !continues {
+                    end = idx;
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    start + masked[start..end].trim_end().len()
+}
+
+/// Replaces comments and the contents of string and template literals with
+/// spaces, keeping byte offsets and newlines. A quote directly after a word is
+/// an apostrophe in JSX text (`Don't`) rather than the start of a string.
+fn mask(text: &str) -> String {
+    fn blank(masked: &mut [u8], range: std::ops::Range<usize>) {
+        for byte in &mut masked[range] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+
+    let bytes = text.as_bytes();
+    let mut masked = bytes.to_vec();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                let end = text[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| pos + 2 + end + 2);
+                blank(&mut masked, pos..end);
+                pos = end;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                let end = text[pos..].find('\n').map_or(bytes.len(), |end| pos + end);
+                blank(&mut masked, pos..end);
+                pos = end;
+            }
+            b'\'' | b'"' if pos > 0 && bytes[pos - 1].is_ascii_alphanumeric() => pos += 1,
+            quote @ (b'\'' | b'"' | b'`') => {
+                let mut end = pos + 1;
+                while end < bytes.len()
+                    && bytes[end] != quote
+                    && (quote == b'`' || bytes[end] != b'\n')
+                {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                let end = end.min(bytes.len());
+                blank(&mut masked, pos + 1..end);
+                pos = end + 1;
+            }
+            _ => pos += 1,
+        }
+    }
+    String::from_utf8_lossy(&masked).into_owned()
+}
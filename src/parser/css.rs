@@ -0,0 +1,240 @@
+//! Indexing of stylesheets (`.css`, `.scss`, and `.less` files).
+//!
+//! Every class selector of a rule becomes a `css_class` symbol named with its dot
+//! (`.btn`) and spanning the rule, so a class styled by several rules has one
+//! symbol per rule. Markup refers to classes by the same dotted names (see
+//! [`class_reference`]), which keeps them apart from code symbols of the same
+//! name. In nested SCSS and Less rules, `&`-suffixed selectors (`&__title`) are
+//! expanded with the classes of the enclosing rule. Keyframe selectors aren't
+//! classes and are skipped.
+
+use regex::Regex;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::{Arc, LazyLock};
+
+use super::{Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions handled by the stylesheet indexer.
+pub const EXTENSIONS: &[&str] = &["css", "scss", "less"];
+
+/// A class in a selector, e.g. `btn` in `a.btn:hover`.
+static CLASS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\.(-?[_a-zA-Z][\w-]*)").expect("valid regex"));
+
+/// A parent selector followed by a suffix, e.g. `&__title` or `&-active`.
+static PARENT_SUFFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&([\w-]+)").expect("valid regex"));
+
+/// A rule or at-rule block found in the stylesheet.
+struct Block {
+    start: usize,
+    /// Classes the rule's selector names; empty for at-rules
+    classes: Vec<String>,
+    /// Whether the block holds keyframes rather than rules
+    keyframes: bool,
+}
+
+/// Indexes stylesheets into class symbols.
+pub struct CssIndexer;
+
+impl CssIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let line_comments = !file_path.ends_with(".css");
+        let masked = mask(&text, line_comments);
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+
+        let mut symbols = Vec::new();
+        let mut open: Vec<Block> = Vec::new();
+        let mut segment_start = 0;
+        for (idx, byte) in masked.bytes().enumerate() {
+            match byte {
+                b'{' => {
+                    let selector = masked[segment_start..idx].trim();
+                    let start =
+                        segment_start + masked[segment_start..idx].find(selector).unwrap_or(0);
+                    let in_keyframes = open.iter().any(|block| block.keyframes);
+                    let block = if selector.starts_with('@') || in_keyframes {
+                        Block {
+                            start,
+                            classes: Vec::new(),
+                            keyframes: in_keyframes || selector.contains("keyframes"),
+                        }
+                    } else {
+                        let parent_classes = open
+                            .iter()
+                            .rev()
+                            .find(|block| !block.classes.is_empty())
+                            .map(|block| block.classes.as_slice())
+                            .unwrap_or_default();
+                        Block {
+                            start,
+                            classes: selector_classes(selector, parent_classes),
+                            keyframes: false,
+                        }
+                    };
+                    open.push(block);
+                    segment_start = idx + 1;
+                }
+                b'}' => {
+                    if let Some(block) = open.pop() {
+                        let end = idx + 1;
+                        let source = &text[block.start..end];
+                        let selector = masked[block.start..end]
+                            .split('{')
+                            .next()
+                            .unwrap_or_default();
+                        let signature = selector.split_whitespace().collect::<Vec<_>>().join(" ");
+                        let doc = leading_comment(&text[..block.start]);
+                        for class in block.classes {
+                            symbols.push(Symbol {
+                                name: class_reference(&class),
+                                node_kind: Symbol::CSS_CLASS_KIND.into(),
+                                file_path: shared_path.clone(),
+                                line_number: line_starts
+                                    .partition_point(|&start| start <= block.start),
+                                start_byte: block.start,
+                                end_byte: end,
+                                visibility: Visibility::Public,
+                                attributes: Vec::new(),
+                                signature: signature.clone(),
+                                doc: doc.clone(),
+                                body_hash: hash_bytes(source.as_bytes()),
+                                parent: None,
+                                references: HashSet::new(),
+                                literals: BTreeSet::new(),
+                                blame: None,
+                                metrics: None,
+                                dependencies: HashSet::new(),
+                                used_by: HashSet::new(),
+                            });
+                        }
+                    }
+                    segment_start = idx + 1;
+                }
+                b';' => segment_start = idx + 1,
+                _ => {}
+            }
+        }
+        // Nested rules close before the rules around them
+        symbols.sort_by_key(|symbol| symbol.start_byte);
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents: Vec::new(),
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+/// The name markup refers to a class by, e.g. `.btn` for `btn`.
+pub fn class_reference(class: &str) -> String {
+    format!(".{}", class)
+}
+
+/// The class of a class reference, or `None` for other references.
+pub fn referenced_class(reference: &str) -> Option<&str> {
+    reference
+        .strip_prefix('.')
+        .filter(|class| !class.is_empty() && !class.contains(['.', ':', '/']))
+}
+
+/// The distinct classes a selector names, with `&` suffixes appended to the
+/// classes of the enclosing rule.
+fn selector_classes(selector: &str, parent_classes: &[String]) -> Vec<String> {
+    let mut classes: Vec<String> = Vec::new();
+    for suffix in PARENT_SUFFIX.captures_iter(selector) {
+        for parent in parent_classes {
+            classes.push(format!("{}{}", parent, &suffix[1]));
+        }
+    }
+    for class in CLASS.captures_iter(selector) {
+        classes.push(class[1].to_string());
+    }
+    let mut seen = HashSet::new();
+    classes.retain(|class| seen.insert(class.clone()));
+    classes
+}
+
+/// The block comment or `//` comments directly before a rule (or, in markup, a
+/// definition), as documentation.
+pub(super) fn leading_comment(before: &str) -> Option<String> {
+    let before = before.trim_end();
+    let doc = if let Some(comment) = before.strip_suffix("*/") {
+        let start = comment.rfind("/*")?;
+        comment[start + 2..]
+            .lines()
+            .map(|line| line.trim().trim_start_matches('*').trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        let mut lines: Vec<&str> = before
+            .lines()
+            .rev()
+            .map(str::trim)
+            .take_while(|line| line.starts_with("//"))
+            .map(|line| line.trim_start_matches('/').trim())
+            .collect();
+        lines.reverse();
+        lines.join("\n")
+    };
+    let doc = doc.trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Replaces comments and the contents of quoted strings with spaces, keeping
+/// byte offsets and newlines, so that only selectors and declarations are
+/// scanned. `//` comments are only recognized in SCSS and Less.
+fn mask(text: &str, line_comments: bool) -> String {
+    fn blank(masked: &mut [u8], range: std::ops::Range<usize>) {
+        for byte in &mut masked[range] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+
+    let bytes = text.as_bytes();
+    let mut masked = bytes.to_vec();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                let end = text[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| pos + 2 + end + 2);
+                blank(&mut masked, pos..end);
+                pos = end;
+            }
+            // Not `url(http://...)`
+            b'/' if line_comments
+                && bytes.get(pos + 1) == Some(&b'/')
+                && (pos == 0
+                    || matches!(bytes[pos - 1], b' ' | b'\t' | b'\n' | b';' | b'{' | b'}')) =>
+            {
+                let end = text[pos..].find('\n').map_or(bytes.len(), |end| pos + end);
+                blank(&mut masked, pos..end);
+                pos = end;
+            }
+            quote @ (b'\'' | b'"') => {
+                let mut end = pos + 1;
+                while end < bytes.len() && bytes[end] != quote && bytes[end] != b'\n' {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                let end = end.min(bytes.len());
+                blank(&mut masked, pos + 1..end);
+                pos = end + 1;
+            }
+            _ => pos += 1,
+        }
+    }
+    String::from_utf8_lossy(&masked).into_owned()
+}
@@ -0,0 +1,390 @@
+//! Indexing of Dockerfiles and Docker Compose services.
+//!
+//! Each build stage of a Dockerfile (`FROM <image> [AS <name>]` up to the next
+//! `FROM`) becomes a `docker_stage` symbol named after its alias, or its base
+//! image if it has none. A stage depends on the stages it builds on or copies
+//! from. The sources it copies (`COPY` and `ADD`, relative to the Dockerfile's
+//! directory) are kept as its literals rather than as dependencies, so that a
+//! `COPY src/` doesn't make every item under `src` used; see
+//! [`Index::copied_symbols`](crate::index::Index::copied_symbols).
+//!
+//! Compose files are indexed as configuration files; [`mark_compose_services`]
+//! then turns each entry under `services` into a `compose_service` symbol that
+//! depends on the services in its `depends_on` and on the stages of the
+//! Dockerfile it is built from. Stage and service names only resolve to stages
+//! and services of the same file.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::{Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions and names handled by the Dockerfile indexer; names ending in
+/// a dot are prefixes (`Dockerfile.dev`).
+pub const EXTENSIONS: &[&str] = &[
+    "dockerfile",
+    "Dockerfile",
+    "Dockerfile.",
+    "Containerfile",
+    "Containerfile.",
+];
+
+/// Prefix of references to the files at or under a path, e.g. `path:src/server`.
+const PATH_PREFIX: &str = "path:";
+
+/// A build stage found in the Dockerfile, before conversion.
+struct Stage {
+    name: String,
+    start: usize,
+    end: usize,
+    /// End of the `FROM` instruction
+    header_end: usize,
+    doc: Option<String>,
+    references: HashSet<String>,
+    /// Paths of the copied sources, relative to the root of the index
+    copies: BTreeSet<String>,
+}
+
+/// Indexes Dockerfiles into build stage symbols.
+pub struct DockerIndexer;
+
+impl DockerIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let context = Path::new(file_path.trim_start_matches("./"))
+            .parent()
+            .unwrap_or(Path::new(""));
+
+        let mut stages: Vec<Stage> = Vec::new();
+        let mut comments: Vec<String> = Vec::new();
+        for (start, end, instruction) in instructions(&text) {
+            if let Some(comment) = instruction.strip_prefix('#') {
+                comments.push(comment.trim().to_string());
+                continue;
+            }
+            let doc = std::mem::take(&mut comments);
+            let mut words = instruction.split_whitespace();
+            let keyword = words.next().unwrap_or_default().to_uppercase();
+            let args: Vec<&str> = words.collect();
+
+            if keyword == "FROM" {
+                let Some(image) = args.iter().find(|arg| !arg.starts_with("--")) else {
+                    continue;
+                };
+                let alias = args
+                    .iter()
+                    .position(|arg| arg.eq_ignore_ascii_case("as"))
+                    .and_then(|idx| args.get(idx + 1));
+                let mut references = HashSet::new();
+                // Building on an earlier stage
+                if stages.iter().any(|stage| stage.name == *image) {
+                    references.insert(image.to_string());
+                }
+                let doc = doc.join("\n").trim().to_string();
+                stages.push(Stage {
+                    name: alias.unwrap_or(image).to_string(),
+                    start,
+                    end,
+                    header_end: end,
+                    doc: (!doc.is_empty()).then_some(doc),
+                    references,
+                    copies: BTreeSet::new(),
+                });
+                continue;
+            }
+
+            if keyword != "COPY" && keyword != "ADD" {
+                if let Some(stage) = stages.last_mut() {
+                    stage.end = end;
+                }
+                continue;
+            }
+            // `--from=1` names a stage by its position
+            let from = args
+                .iter()
+                .find_map(|arg| arg.strip_prefix("--from="))
+                .map(|from| {
+                    from.parse::<usize>()
+                        .ok()
+                        .and_then(|idx| stages.get(idx))
+                        .map_or(from.to_string(), |stage| stage.name.clone())
+                });
+            if let Some(stage) = stages.last_mut() {
+                stage.end = end;
+                match from {
+                    Some(from) => {
+                        stage.references.insert(from);
+                    }
+                    None => stage.copies.extend(
+                        copied_paths(&args)
+                            .iter()
+                            .filter_map(|path| source_path(context, path)),
+                    ),
+                }
+            }
+        }
+
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        let symbols = stages
+            .into_iter()
+            .map(|stage| {
+                let source = &text[stage.start..stage.end];
+                Symbol {
+                    name: stage.name,
+                    node_kind: Symbol::DOCKER_STAGE_KIND.into(),
+                    file_path: shared_path.clone(),
+                    line_number: line_starts.partition_point(|&start| start <= stage.start),
+                    start_byte: stage.start,
+                    end_byte: stage.end,
+                    visibility: Visibility::Public,
+                    attributes: Vec::new(),
+                    signature: text[stage.start..stage.header_end].trim().to_string(),
+                    doc: stage.doc,
+                    body_hash: hash_bytes(source.as_bytes()),
+                    parent: None,
+                    references: stage.references,
+                    literals: stage.copies,
+                    blame: None,
+                    metrics: None,
+                    dependencies: HashSet::new(),
+                    used_by: HashSet::new(),
+                }
+            })
+            .collect();
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents: Vec::new(),
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+/// The (start, end, text) of each instruction and comment line, with line
+/// continuations joined and here-documents (`RUN <<EOF`) kept in their
+/// instruction.
+fn instructions(text: &str) -> Vec<(usize, usize, String)> {
+    let mut instructions = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    let mut heredoc: Option<String> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+
+        if let Some(delimiter) = &heredoc {
+            if trimmed == delimiter {
+                heredoc = None;
+                if let Some((instruction_start, content)) = current.take() {
+                    instructions.push((instruction_start, offset, content));
+                }
+            }
+            continue;
+        }
+        if current.is_none() && (trimmed.is_empty() || trimmed.starts_with('#')) {
+            if trimmed.starts_with('#') {
+                instructions.push((start, offset, trimmed.to_string()));
+            }
+            continue;
+        }
+
+        let (instruction_start, mut content) = current.take().unwrap_or((start, String::new()));
+        if trimmed.starts_with('#') {
+            // Comments inside a continued instruction are dropped
+            current = Some((instruction_start, content));
+            continue;
+        }
+        content.push(' ');
+        content.push_str(trimmed.trim_end_matches('\\'));
+        if trimmed.ends_with('\\') {
+            current = Some((instruction_start, content));
+            continue;
+        }
+        match heredoc_delimiter(trimmed) {
+            Some(delimiter) => {
+                heredoc = Some(delimiter);
+                current = Some((instruction_start, content));
+            }
+            None => instructions.push((instruction_start, offset, content.trim().to_string())),
+        }
+    }
+    if let Some((instruction_start, content)) = current {
+        instructions.push((instruction_start, offset, content.trim().to_string()));
+    }
+    instructions
+}
+
+/// The delimiter of a here-document started on `line`, e.g. `EOF` for
+/// `RUN <<EOF` or `COPY <<-"EOT" /etc/config`.
+fn heredoc_delimiter(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("<<")?;
+    let rest = rest.trim_start_matches('-').trim_start_matches(['"', '\'']);
+    let delimiter: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    (!delimiter.is_empty()).then_some(delimiter)
+}
+
+/// The source paths of `COPY`/`ADD` arguments in shell or JSON form, without
+/// flags and the destination.
+fn copied_paths(args: &[&str]) -> Vec<String> {
+    let args: Vec<&str> = args
+        .iter()
+        .copied()
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let joined = args.join(" ");
+    let mut paths: Vec<String> = if joined.starts_with('[') {
+        serde_json::from_str(&joined).unwrap_or_default()
+    } else {
+        args.iter().map(|arg| arg.to_string()).collect()
+    };
+    paths.pop();
+    paths
+}
+
+/// The path of a copied source, relative to the root of the index;
+/// `None` for URLs, variables, here-documents, and the whole build context.
+fn source_path(context: &Path, source: &str) -> Option<String> {
+    if source.contains("://") || source.contains('$') || source.starts_with("<<") {
+        return None;
+    }
+    // Only the part before the first wildcard names something to link to
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in context
+        .to_str()?
+        .split('/')
+        .chain(source.trim_start_matches('/').split('/'))
+    {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            _ if segment.contains(['*', '?', '[']) => break,
+            _ => segments.push(segment),
+        }
+    }
+    let root = if context.has_root() { "/" } else { "" };
+    let path = format!("{}{}", root, segments.join("/"));
+    if segments.is_empty() || path == context.to_str()? {
+        return None;
+    }
+    Some(path)
+}
+
+/// A reference to the stages of the Dockerfiles at or under `path` (relative
+/// to the index root).
+pub fn path_reference(path: &str) -> String {
+    format!("{}{}", PATH_PREFIX, path.trim_start_matches("./"))
+}
+
+/// The path of a path reference, or `None` for other references.
+pub fn referenced_path(reference: &str) -> Option<&str> {
+    reference.strip_prefix(PATH_PREFIX)
+}
+
+/// Whether a file is a Docker Compose file, e.g. `compose.yaml` or
+/// `docker-compose.prod.yml`.
+pub fn is_compose_file(file_path: &str) -> bool {
+    let name = Path::new(file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    (name.starts_with("compose.") || name.starts_with("docker-compose."))
+        && (name.ends_with(".yml") || name.ends_with(".yaml"))
+}
+
+/// Turns the entries under `services` of a Compose file into `compose_service`
+/// symbols depending on the services they depend on and on the Dockerfile they
+/// are built from. Does nothing for other files.
+pub fn mark_compose_services(
+    file_path: &str,
+    text: &str,
+    symbols: &mut [Symbol],
+    parents: &[(usize, usize)],
+) {
+    if !is_compose_file(file_path) {
+        return;
+    }
+    let parent_of: HashMap<usize, usize> = parents.iter().copied().collect();
+    let count = symbols.len();
+    let children = |parent: usize| -> Vec<usize> {
+        (0..count)
+            .filter(|idx| parent_of.get(idx) == Some(&parent))
+            .collect()
+    };
+    let Some(services) = (0..symbols.len())
+        .find(|idx| !parent_of.contains_key(idx) && symbols[*idx].name == "services")
+    else {
+        return;
+    };
+    let directory = Path::new(file_path.trim_start_matches("./"))
+        .parent()
+        .unwrap_or(Path::new(""));
+
+    for service in children(services) {
+        let mut references = HashSet::new();
+        for setting in children(service) {
+            let symbol = &symbols[setting];
+            match symbol.name.as_str() {
+                "build" => {
+                    let settings = children(setting);
+                    let value = |key: &str| {
+                        settings
+                            .iter()
+                            .find(|idx| symbols[**idx].name == key)
+                            .and_then(|idx| scalar_value(&symbols[*idx].signature))
+                    };
+                    let Some(context) =
+                        scalar_value(&symbol.signature).or_else(|| value("context"))
+                    else {
+                        continue;
+                    };
+                    let dockerfile =
+                        value("dockerfile").unwrap_or_else(|| "Dockerfile".to_string());
+                    if let Some(path) =
+                        source_path(directory, &format!("{}/{}", context, dockerfile))
+                    {
+                        references.insert(path_reference(&path));
+                    }
+                }
+                "depends_on" | "links" => {
+                    // The long form names the services as keys, the short one as a list
+                    references.extend(
+                        children(setting)
+                            .iter()
+                            .map(|idx| symbols[*idx].name.clone()),
+                    );
+                    let source = &text[symbol.start_byte..symbol.end_byte];
+                    references.extend(source.lines().skip(1).filter_map(|line| {
+                        let item = line.trim().strip_prefix("- ")?;
+                        let name = item.split(':').next()?.trim().trim_matches(['"', '\'']);
+                        (!name.is_empty()).then(|| name.to_string())
+                    }));
+                }
+                _ => {}
+            }
+        }
+        let symbol = &mut symbols[service];
+        symbol.node_kind = Symbol::COMPOSE_SERVICE_KIND.into();
+        symbol.references.extend(references);
+    }
+}
+
+/// The value of a one-line `key: value` entry.
+fn scalar_value(line: &str) -> Option<String> {
+    let (_, value) = line.split_once(':')?;
+    let value = value.trim().trim_matches(['"', '\'']);
+    (!value.is_empty()).then(|| value.to_string())
+}
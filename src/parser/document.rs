@@ -0,0 +1,292 @@
+//! Indexing of prose documents (READMEs, ADRs, design docs).
+//!
+//! Markdown files are split at their headings into section symbols, nested under
+//! the closest preceding heading of a higher level. Files without headings, such as
+//! plain text, become a single document symbol. Inline code spans (`` `Index` ``,
+//! `` `load_index()` ``) are recorded as references, so sections get linked to the
+//! code they talk about.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::{Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions handled by the document indexer.
+pub const EXTENSIONS: &[&str] = &["md", "markdown", "txt"];
+
+/// Whether `file_path` is a document rather than source code.
+pub fn is_document_file(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// A heading found in a Markdown document.
+struct Heading {
+    level: usize,
+    title: String,
+    /// 0-based index of the heading's first line
+    line: usize,
+    start_byte: usize,
+    /// End of the heading line(s) and start of the section body
+    body_byte: usize,
+}
+
+/// Splits documents into section symbols.
+pub struct DocumentIndexer;
+
+impl DocumentIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let is_markdown = !file_path.to_lowercase().ends_with(".txt");
+        let headings = if is_markdown {
+            find_headings(&text)
+        } else {
+            Vec::new()
+        };
+
+        let new_symbol =
+            |name: String, kind: &str, line: usize, range: (usize, usize), signature| {
+                let body = &text[range.0..range.1];
+                Symbol {
+                    name,
+                    node_kind: kind.into(),
+                    file_path: shared_path.clone(),
+                    line_number: line + 1,
+                    start_byte: range.0,
+                    end_byte: range.1,
+                    // Documents aren't API; keep them out of `api`
+                    visibility: Visibility::Private,
+                    attributes: Vec::new(),
+                    signature,
+                    doc: first_paragraph(body),
+                    body_hash: hash_bytes(body.as_bytes()),
+                    parent: None,
+                    references: HashSet::new(),
+                    literals: BTreeSet::new(),
+                    blame: None,
+                    metrics: None,
+                    dependencies: HashSet::new(),
+                    used_by: HashSet::new(),
+                }
+            };
+
+        if headings.is_empty() {
+            let name = Path::new(file_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| file_path.to_string());
+            let mut sym = new_symbol(
+                name,
+                Symbol::DOCUMENT_KIND,
+                0,
+                (0, text.len()),
+                String::new(),
+            );
+            sym.references = code_references(&text, is_markdown);
+            return ParsedFile {
+                symbols: vec![sym],
+                imports: Imports::default(),
+                parents: Vec::new(),
+                error_nodes: 0,
+                todos: Vec::new(),
+            };
+        }
+
+        let mut symbols = Vec::with_capacity(headings.len());
+        let mut parents = Vec::new();
+        for (idx, heading) in headings.iter().enumerate() {
+            // A section runs until the next heading of the same or a higher level;
+            // its own text stops at the next heading of any level
+            let end = headings[idx + 1..]
+                .iter()
+                .find(|next| next.level <= heading.level)
+                .map_or(text.len(), |next| next.start_byte);
+            let own_end = headings
+                .get(idx + 1)
+                .map_or(text.len(), |next| next.start_byte);
+
+            let mut sym = new_symbol(
+                heading.title.clone(),
+                Symbol::SECTION_KIND,
+                heading.line,
+                (heading.start_byte, end),
+                format!("{} {}", "#".repeat(heading.level), heading.title),
+            );
+            sym.doc = first_paragraph(&text[heading.body_byte..own_end]);
+            sym.references = code_references(&text[heading.body_byte..own_end], true);
+            symbols.push(sym);
+
+            if let Some(parent) = headings[..idx]
+                .iter()
+                .rposition(|prev| prev.level < heading.level)
+            {
+                parents.push((idx, parent));
+            }
+        }
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents,
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+/// Finds ATX (`## Title`) and setext (`Title` underlined by `===`/`---`) headings,
+/// ignoring fenced code blocks.
+fn find_headings(text: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        lines.push((offset, line));
+        offset += line.len();
+    }
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let (start, raw) = lines[idx];
+        let line = raw.trim_end();
+        let trimmed = line.trim_start();
+
+        // Toggle fenced code blocks
+        let marker = if trimmed.starts_with("```") {
+            Some("```")
+        } else if trimmed.starts_with("~~~") {
+            Some("~~~")
+        } else {
+            None
+        };
+        match (fence, marker) {
+            (None, Some(marker)) => {
+                fence = Some(marker);
+                idx += 1;
+                continue;
+            }
+            (Some(open), Some(marker)) if open == marker => {
+                fence = None;
+                idx += 1;
+                continue;
+            }
+            (Some(_), _) => {
+                idx += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        let indent = line.len() - trimmed.len();
+        if (1..=6).contains(&level)
+            && indent < 4
+            && trimmed[level..]
+                .chars()
+                .next()
+                .is_none_or(char::is_whitespace)
+        {
+            let title = trimmed[level..].trim().trim_end_matches('#').trim();
+            if !title.is_empty() {
+                headings.push(Heading {
+                    level,
+                    title: title.to_string(),
+                    line: idx,
+                    start_byte: start,
+                    body_byte: start + raw.len(),
+                });
+            }
+            idx += 1;
+            continue;
+        }
+
+        // Setext headings: a text line underlined by `===` (level 1) or `---` (level 2)
+        if let Some(&(under_start, under_raw)) = lines.get(idx + 1) {
+            let underline = under_raw.trim();
+            let setext_level = if !underline.is_empty() && underline.chars().all(|c| c == '=') {
+                Some(1)
+            } else if underline.len() >= 2 && underline.chars().all(|c| c == '-') {
+                Some(2)
+            } else {
+                None
+            };
+            if let (Some(level), false) = (setext_level, trimmed.is_empty()) {
+                headings.push(Heading {
+                    level,
+                    title: trimmed.to_string(),
+                    line: idx,
+                    start_byte: start,
+                    body_byte: under_start + under_raw.len(),
+                });
+                idx += 2;
+                continue;
+            }
+        }
+
+        idx += 1;
+    }
+
+    headings
+}
+
+/// The first paragraph of a section body, joined into one line.
+fn first_paragraph(body: &str) -> Option<String> {
+    let paragraph: Vec<&str> = body
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    (!paragraph.is_empty()).then(|| paragraph.join(" "))
+}
+
+/// Names in inline code spans that look like code identifiers or paths, e.g.
+/// `` `Index::load_index()` `` -> `Index::load_index`. Plain text has no spans.
+fn code_references(text: &str, is_markdown: bool) -> HashSet<String> {
+    if !is_markdown {
+        return HashSet::new();
+    }
+
+    strip_fenced_blocks(text)
+        .split('`')
+        // Odd pieces are inside backticks
+        .skip(1)
+        .step_by(2)
+        .filter_map(|span| {
+            let span = span.trim().trim_end_matches("()");
+            let name = span.rsplit('.').next().unwrap_or(span);
+            let is_identifier = name
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_')
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == ':');
+            is_identifier.then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// `text` without fenced code blocks, whose backticks would throw off span parsing.
+fn strip_fenced_blocks(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_fence = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
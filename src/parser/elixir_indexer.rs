@@ -0,0 +1,456 @@
+use crate::errors::ContextMeshError;
+use crate::symbol::Visibility;
+
+use super::language::LanguageIndexer;
+use super::Imports;
+use tree_sitter::Node;
+
+/// Elixir-specific implementation of the `LanguageIndexer` trait, for the
+/// [tree-sitter-elixir] grammar.
+///
+/// Everything in Elixir is a call: `defmodule`, `def`, and `alias` are macros
+/// called with the module or function head as their first argument. Definitions
+/// are therefore told apart by the name of the macro they call, which is also
+/// their kind. Modules are named by their full alias (`MyApp.Accounts`,
+/// including the aliases of enclosing modules), and calls through a module
+/// (`Accounts.get_user(id)`) refer to `MyApp.Accounts::get_user`, so they
+/// resolve to the function of that module.
+///
+/// [tree-sitter-elixir]: https://github.com/elixir-lang/tree-sitter-elixir
+pub struct ElixirIndexer;
+
+/// Macros defining modules.
+const MODULE_MACROS: &[&str] = &["defmodule", "defprotocol"];
+
+/// Macros defining functions, by whether the function is private.
+const FUNCTION_MACROS: &[(&str, bool)] = &[
+    ("def", false),
+    ("defp", true),
+    ("defmacro", false),
+    ("defmacrop", true),
+    ("defdelegate", false),
+    ("defguard", false),
+    ("defguardp", true),
+];
+
+/// Macros and special forms that are called like functions but never name a
+/// function of the project.
+const KERNEL_MACROS: &[&str] = &[
+    "alias",
+    "import",
+    "require",
+    "use",
+    "if",
+    "unless",
+    "case",
+    "cond",
+    "with",
+    "for",
+    "fn",
+    "try",
+    "receive",
+    "quote",
+    "unquote",
+    "raise",
+    "defstruct",
+    "defexception",
+    "defimpl",
+];
+
+impl LanguageIndexer for ElixirIndexer {
+    fn language_name(&self) -> &'static str {
+        "elixir"
+    }
+
+    fn allowed_definition_kinds(&self) -> &'static [&'static str] {
+        &[
+            "defmodule",
+            "defprotocol",
+            "def",
+            "defp",
+            "defmacro",
+            "defmacrop",
+            "defdelegate",
+            "defguard",
+            "defguardp",
+        ]
+    }
+
+    /// The defining macro of a `call` node, e.g. `def` or `defmodule`.
+    fn definition_kind(&self, node: Node, code: &[u8]) -> &'static str {
+        let Some(called) = macro_name(node, code) else {
+            return node.kind();
+        };
+        MODULE_MACROS
+            .iter()
+            .copied()
+            .chain(FUNCTION_MACROS.iter().map(|(name, _)| *name))
+            .find(|name| *name == called)
+            .unwrap_or(node.kind())
+    }
+
+    fn definition_name<'tree>(&self, node: Node<'tree>) -> Option<Node<'tree>> {
+        node.child_by_field_name("target")
+    }
+
+    fn call_target<'tree>(&self, node: Node<'tree>) -> Option<Node<'tree>> {
+        if node.kind() != "call" {
+            return None;
+        }
+        node.child_by_field_name("target")
+    }
+
+    /// Modules are named by their alias prefixed with those of the modules they
+    /// are nested in, functions by the name in their head.
+    fn build_qualified_name(&self, node: Node, code: &[u8]) -> Result<String, ContextMeshError> {
+        let name = if MODULE_MACROS.contains(&self.definition_kind(node, code)) {
+            module_name(node, code)
+        } else {
+            first_argument(node).and_then(|head| function_name(head, code))
+        };
+        name.ok_or_else(|| {
+            ContextMeshError::DeserializationError("Skipping empty-named item.".to_string())
+        })
+    }
+
+    /// `defp`, `defmacrop`, and `defguardp` define private functions.
+    fn extract_visibility(&self, node: Node, code: &[u8]) -> Visibility {
+        let kind = self.definition_kind(node, code);
+        let private = FUNCTION_MACROS
+            .iter()
+            .any(|&(name, private)| private && name == kind);
+        if private {
+            Visibility::Private
+        } else {
+            Visibility::Public
+        }
+    }
+
+    /// Collects the module attributes directly preceding a function, e.g.
+    /// `impl true` or `spec get_user(integer()) :: User.t()`, skipping its `@doc`.
+    fn extract_attributes(&self, node: Node, code: &[u8]) -> Vec<String> {
+        let mut attributes = Vec::new();
+        let mut sibling = node.prev_sibling();
+        while let Some(prev) = sibling {
+            match attribute(prev, code) {
+                Some(("doc", _)) => {}
+                Some((_, text)) => attributes.push(text.to_string()),
+                None if prev.kind() == "comment" => {}
+                None => break,
+            }
+            sibling = prev.prev_sibling();
+        }
+        // Restore source order
+        attributes.reverse();
+        attributes
+    }
+
+    /// The definition up to its `do` block, e.g. `def get_user(id) when is_integer(id)`.
+    fn extract_signature(&self, node: Node, code: &[u8]) -> String {
+        let end = node
+            .children(&mut node.walk())
+            .find(|child| child.kind() == "do_block")
+            .map_or(node.end_byte(), |block| block.start_byte());
+        String::from_utf8_lossy(&code[node.start_byte()..end])
+            .trim()
+            .to_string()
+    }
+
+    /// The `@doc` preceding a function, or the `@moduledoc` at the start of a
+    /// module.
+    fn extract_doc_comment(&self, node: Node, code: &[u8]) -> Option<String> {
+        let doc = if MODULE_MACROS.contains(&self.definition_kind(node, code)) {
+            module_doc(node, code)
+        } else {
+            function_doc(node, code)
+        }?;
+        let doc = doc
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+        (!doc.is_empty()).then_some(doc)
+    }
+
+    /// Parses `alias` (with `as:` and `{...}` lists), `import`, and `use`. Imported
+    /// and used modules are recorded as globs, since they bring in functions
+    /// callable without their module.
+    fn process_import_declaration(
+        &self,
+        node: Node,
+        code: &[u8],
+        imports: &mut Imports,
+    ) -> Result<(), ContextMeshError> {
+        let Some(called) = macro_name(node, code) else {
+            return Ok(());
+        };
+        let Some(argument) = first_argument(node) else {
+            return Ok(());
+        };
+        match called {
+            "alias" => {
+                let renamed = keyword_argument(node, "as", code);
+                match argument.kind() {
+                    "alias" => {
+                        let path = node_text(argument, code)?.to_string();
+                        let name = renamed.unwrap_or_else(|| last_segment(&path).to_string());
+                        imports.aliases.insert(name, path);
+                    }
+                    // alias MyApp.{Accounts, Repo}
+                    "dot" => {
+                        let (Some(left), Some(right)) = (
+                            argument.child_by_field_name("left"),
+                            argument.child_by_field_name("right"),
+                        ) else {
+                            return Ok(());
+                        };
+                        let prefix = node_text(left, code)?;
+                        for member in right.named_children(&mut right.walk()) {
+                            if member.kind() == "alias" {
+                                let member = node_text(member, code)?;
+                                let path = format!("{}.{}", prefix, member);
+                                imports
+                                    .aliases
+                                    .insert(last_segment(&path).to_string(), path);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "import" | "use" if argument.kind() == "alias" => {
+                let module = node_text(argument, code)?;
+                imports
+                    .globs
+                    .push(expand_alias(module, imports).unwrap_or_else(|| module.to_string()));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Calls name a local or imported function (`get_user(id)`) or a function of a
+    /// module (`Accounts.get_user(id)`, with aliases expanded). Calls on values
+    /// (`user.name`, `fun.(x)`) and kernel macros name nothing of the project.
+    fn extract_callable_name(
+        &self,
+        node: Node,
+        code: &[u8],
+        imports: &Imports,
+    ) -> Result<String, ContextMeshError> {
+        match node.kind() {
+            "identifier" => {
+                let name = node_text(node, code)?;
+                // Definitions nested in another call (e.g. in a `quote`) aren't calls
+                if KERNEL_MACROS.contains(&name) || self.allowed_definition_kinds().contains(&name)
+                {
+                    return Ok(String::new());
+                }
+                Ok(name.to_string())
+            }
+            "dot" => {
+                let (Some(left), Some(right)) = (
+                    node.child_by_field_name("left"),
+                    node.child_by_field_name("right"),
+                ) else {
+                    return Ok(String::new());
+                };
+                if left.kind() != "alias" || right.kind() != "identifier" {
+                    return Ok(String::new());
+                }
+                let module = node_text(left, code)?;
+                let module = expand_alias(module, imports).unwrap_or_else(|| module.to_string());
+                Ok(format!("{}::{}", module, node_text(right, code)?))
+            }
+            _ => Ok(String::new()),
+        }
+    }
+
+    /// Handles `"..."` strings without interpolation; escapes are kept as written.
+    fn extract_string_literal(&self, node: Node, code: &[u8]) -> Option<String> {
+        if node.kind() != "string" || node.named_child_count() != 1 {
+            return None;
+        }
+        let content = node
+            .named_child(0)
+            .filter(|part| part.kind() == "quoted_content")?;
+        node_text(content, code).ok().map(str::to_string)
+    }
+
+    /// Nested modules are named after their enclosing ones by
+    /// `build_qualified_name`, so no module scope is tracked here.
+    fn enter_module(
+        &self,
+        _node: Node,
+        _code: &[u8],
+        _current_module: &mut Vec<String>,
+    ) -> Result<(), ContextMeshError> {
+        Ok(())
+    }
+
+    fn exit_module(&self, _current_module: &mut Vec<String>) -> Result<(), ContextMeshError> {
+        Ok(())
+    }
+}
+
+/// The name of the macro or function a `call` node calls directly, e.g. `def`.
+fn macro_name<'a>(node: Node, code: &'a [u8]) -> Option<&'a str> {
+    if node.kind() != "call" {
+        return None;
+    }
+    let target = node.child_by_field_name("target")?;
+    if target.kind() != "identifier" {
+        return None;
+    }
+    target.utf8_text(code).ok()
+}
+
+/// The first argument of a call, e.g. the head of a `def`.
+fn first_argument(node: Node) -> Option<Node> {
+    let arguments = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "arguments")?;
+    arguments.named_child(0)
+}
+
+/// The value of the `key:` keyword argument of a call, e.g. `as: Acc`.
+fn keyword_argument(node: Node, key: &str, code: &[u8]) -> Option<String> {
+    let arguments = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "arguments")?;
+    let keywords = arguments
+        .named_children(&mut arguments.walk())
+        .find(|child| child.kind() == "keywords")?;
+    keywords
+        .named_children(&mut keywords.walk())
+        .find_map(|pair| {
+            let name = pair.child_by_field_name("key")?.utf8_text(code).ok()?;
+            if name.trim().trim_end_matches(':') != key {
+                return None;
+            }
+            let value = pair.child_by_field_name("value")?;
+            value.utf8_text(code).ok().map(str::to_string)
+        })
+}
+
+/// The full name of a module definition, e.g. `MyApp.Accounts.User` for
+/// `defmodule User` nested in `defmodule MyApp.Accounts`.
+fn module_name(node: Node, code: &[u8]) -> Option<String> {
+    let own = first_argument(node).filter(|name| name.kind() == "alias")?;
+    let mut segments = vec![own.utf8_text(code).ok()?.to_string()];
+    let mut ancestor = node.parent();
+    while let Some(current) = ancestor {
+        if macro_name(current, code).is_some_and(|name| MODULE_MACROS.contains(&name)) {
+            if let Some(outer) = first_argument(current).filter(|name| name.kind() == "alias") {
+                segments.push(outer.utf8_text(code).ok()?.to_string());
+            }
+        }
+        ancestor = current.parent();
+    }
+    segments.reverse();
+    Some(segments.join("."))
+}
+
+/// The name of a function from its head: `get_user` for `get_user(id)`,
+/// `get_user(id) when is_integer(id)`, or a bare `get_user`.
+fn function_name(head: Node, code: &[u8]) -> Option<String> {
+    match head.kind() {
+        "identifier" => head.utf8_text(code).ok().map(str::to_string),
+        "call" => {
+            let target = head
+                .child_by_field_name("target")
+                .filter(|target| target.kind() == "identifier")?;
+            target.utf8_text(code).ok().map(str::to_string)
+        }
+        "binary_operator" => function_name(head.child_by_field_name("left")?, code),
+        _ => None,
+    }
+}
+
+/// The name and text (without `@`) of a module attribute, e.g. `("doc", "doc
+/// \"...\"")`, or `None` if `node` isn't one.
+fn attribute<'a>(node: Node, code: &'a [u8]) -> Option<(&'a str, &'a str)> {
+    if node.kind() != "unary_operator" {
+        return None;
+    }
+    let operand = node.child_by_field_name("operand")?;
+    let text = node.utf8_text(code).ok()?.strip_prefix('@')?;
+    let name = match operand.kind() {
+        "call" => operand
+            .child_by_field_name("target")?
+            .utf8_text(code)
+            .ok()?,
+        "identifier" => operand.utf8_text(code).ok()?,
+        _ => return None,
+    };
+    Some((name, text))
+}
+
+/// The `@moduledoc` among the first expressions of a module's `do` block.
+fn module_doc(node: Node, code: &[u8]) -> Option<String> {
+    let block = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "do_block")?;
+    block
+        .named_children(&mut block.walk())
+        .find_map(|child| match attribute(child, code) {
+            Some(("moduledoc", _)) => attribute_value(child, code),
+            _ => None,
+        })
+}
+
+/// The `@doc` among the attributes and comments preceding a function.
+fn function_doc(node: Node, code: &[u8]) -> Option<String> {
+    let mut sibling = node.prev_sibling();
+    while let Some(prev) = sibling {
+        match attribute(prev, code) {
+            Some(("doc", _)) => return attribute_value(prev, code),
+            Some(_) => {}
+            None if prev.kind() == "comment" => {}
+            None => return None,
+        }
+        sibling = prev.prev_sibling();
+    }
+    None
+}
+
+/// The contents of the string an attribute is set to, e.g. the text of a
+/// `@doc """..."""`. `@doc false` has none.
+fn attribute_value(node: Node, code: &[u8]) -> Option<String> {
+    let operand = node.child_by_field_name("operand")?;
+    let string = first_argument(operand).filter(|value| value.kind() == "string")?;
+    let mut cursor = string.walk();
+    let content: String = string
+        .named_children(&mut cursor)
+        .filter(|part| part.kind() == "quoted_content")
+        .filter_map(|part| part.utf8_text(code).ok())
+        .collect();
+    Some(content)
+}
+
+/// The full module an alias stands for, if it starts with an aliased name, e.g.
+/// `MyApp.Accounts.User` for `Accounts.User` after `alias MyApp.Accounts`.
+pub fn expand_alias(module: &str, imports: &Imports) -> Option<String> {
+    let (first, rest) = match module.split_once('.') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (module, None),
+    };
+    let full = imports.aliases.get(first)?;
+    Some(match rest {
+        Some(rest) => format!("{}.{}", full, rest),
+        None => full.clone(),
+    })
+}
+
+/// The last segment of a module name, e.g. `User` for `MyApp.Accounts.User`.
+pub fn last_segment(module: &str) -> &str {
+    module.rsplit('.').next().unwrap_or(module)
+}
+
+fn node_text<'a>(node: Node, code: &'a [u8]) -> Result<&'a str, ContextMeshError> {
+    node.utf8_text(code).map_err(|_| {
+        ContextMeshError::DeserializationError(format!("Failed to extract {} text.", node.kind()))
+    })
+}
@@ -0,0 +1,207 @@
+//! Language indexers implemented as external programs.
+//!
+//! Languages without a built-in indexer can be added by configuring a command in
+//! `.contextmesh/config.toml`:
+//!
+//! ```toml
+//! [languages.lua]
+//! command = ["lua-indexer", "--json"]
+//! extensions = ["lua"]
+//! ```
+//!
+//! For every file, the command is run with the file's path as its last argument
+//! and the file's contents on stdin. It must exit successfully and print a single
+//! JSON object to stdout:
+//!
+//! ```json
+//! {
+//!   "symbols": [
+//!     { "name": "Player", "kind": "class", "line": 3, "start_byte": 20, "end_byte": 410,
+//!       "public": true, "signature": "local Player = {}", "references": ["Entity"] },
+//!     { "name": "move", "kind": "method", "line": 8, "start_byte": 90, "end_byte": 200,
+//!       "parent": 0, "references": ["clamp"] }
+//!   ],
+//!   "imports": { "util": "lib.util" },
+//!   "globs": [],
+//!   "error_nodes": 0
+//! }
+//! ```
+//!
+//! Every field except a symbol's `name`, `kind`, and `line` is optional. `parent`
+//! is the position of the enclosing symbol in `symbols`, and `references` are raw
+//! names resolved against the whole index like those of built-in languages.
+
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use super::{Imports, KindFilter, ParsedFile};
+use crate::config::LanguageConfig;
+use crate::errors::ContextMeshError;
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// Runs a user-configured program to index the files of one language.
+pub struct ExternalIndexer {
+    language: String,
+    command: Vec<String>,
+    kinds: KindFilter,
+}
+
+#[derive(Deserialize)]
+struct ExternalOutput {
+    #[serde(default)]
+    symbols: Vec<ExternalSymbol>,
+    #[serde(default)]
+    imports: HashMap<String, String>,
+    #[serde(default)]
+    globs: Vec<String>,
+    #[serde(default)]
+    error_nodes: usize,
+}
+
+#[derive(Deserialize)]
+struct ExternalSymbol {
+    name: String,
+    kind: String,
+    line: usize,
+    #[serde(default)]
+    start_byte: usize,
+    #[serde(default)]
+    end_byte: usize,
+    #[serde(default)]
+    public: bool,
+    #[serde(default)]
+    signature: String,
+    doc: Option<String>,
+    parent: Option<usize>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+impl ExternalIndexer {
+    /// Creates the indexer for `language`, or `None` if its config has no command.
+    pub fn from_config(language: &str, config: &LanguageConfig) -> Option<Self> {
+        let command = config
+            .command
+            .clone()
+            .filter(|command| !command.is_empty())?;
+        Some(ExternalIndexer {
+            language: language.to_lowercase(),
+            command,
+            kinds: KindFilter::from_config(config),
+        })
+    }
+
+    pub fn language_name(&self) -> &str {
+        &self.language
+    }
+
+    /// Runs the command on `code` and converts its output.
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> Result<ParsedFile, ContextMeshError> {
+        let mut child = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .arg(file_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                ContextMeshError::PluginError(format!("Failed to run '{}': {}", self.command[0], e))
+            })?;
+
+        // Feed stdin from another thread so a plugin that writes before it has
+        // read everything can't deadlock against us
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = code.to_vec();
+        let writer = std::thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output()?;
+        // A plugin may legitimately exit without reading its input
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(ContextMeshError::PluginError(format!(
+                "'{}' failed on '{}' ({}): {}",
+                self.command[0],
+                file_path,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let parsed: ExternalOutput = serde_json::from_slice(&output.stdout).map_err(|e| {
+            ContextMeshError::PluginError(format!(
+                "'{}' printed invalid output for '{}': {}",
+                self.command[0], file_path, e
+            ))
+        })?;
+        Ok(self.convert(file_path, code, parsed))
+    }
+
+    fn convert(&self, file_path: &str, code: &[u8], output: ExternalOutput) -> ParsedFile {
+        let shared_path: Arc<str> = Arc::from(file_path);
+
+        // New positions of the kept symbols, for remapping parent links
+        let mut positions = HashMap::new();
+        let mut symbols = Vec::new();
+        let mut parent_links = Vec::new();
+        for (pos, ext) in output.symbols.into_iter().enumerate() {
+            if !self.kinds.keeps(&ext.kind) {
+                continue;
+            }
+            positions.insert(pos, symbols.len());
+            if let Some(parent) = ext.parent {
+                parent_links.push((symbols.len(), parent));
+            }
+
+            let source = code.get(ext.start_byte..ext.end_byte).unwrap_or_default();
+            symbols.push(Symbol {
+                body_hash: hash_bytes(if source.is_empty() {
+                    ext.signature.as_bytes()
+                } else {
+                    source
+                }),
+                name: ext.name,
+                node_kind: ext.kind.into(),
+                file_path: shared_path.clone(),
+                line_number: ext.line,
+                start_byte: ext.start_byte,
+                end_byte: ext.end_byte,
+                visibility: if ext.public {
+                    Visibility::Public
+                } else {
+                    Visibility::Private
+                },
+                attributes: Vec::new(),
+                signature: ext.signature,
+                doc: ext.doc,
+                parent: None,
+                references: ext.references.into_iter().collect::<HashSet<_>>(),
+                literals: BTreeSet::new(),
+                blame: None,
+                metrics: None,
+                dependencies: HashSet::new(),
+                used_by: HashSet::new(),
+            });
+        }
+
+        let parents = parent_links
+            .into_iter()
+            .filter_map(|(child, parent)| Some((child, *positions.get(&parent)?)))
+            .filter(|(child, parent)| child != parent)
+            .collect();
+
+        ParsedFile {
+            symbols,
+            imports: Imports {
+                aliases: output.imports,
+                globs: output.globs,
+            },
+            parents,
+            error_nodes: output.error_nodes,
+            todos: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! `contextmesh:ignore` comments, which keep a definition out of every bundle.
+//!
+//! A marker applies to the definition starting on its line before it (a
+//! trailing comment), else to a definition starting right below it, with only
+//! comments and attributes in between, else to the innermost definition
+//! containing it; and to everything nested in that definition. Like the TODO
+//! scan (see [`super::todos`]), the scan is textual and language-agnostic: the
+//! marker counts when it directly follows a comment marker.
+
+use super::todos::COMMENT_MARKERS;
+use crate::symbol::Symbol;
+
+/// The comment text marking the definition it applies to.
+pub const MARKER: &str = "contextmesh:ignore";
+
+/// A `contextmesh:ignore` comment found by [`scan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub line: usize,
+    /// Byte offset of the marker text
+    pub byte: usize,
+    /// For a marker on a line of its own, the end of the first line of code
+    /// below it: a definition starting before that is the one marked.
+    pub leads_to: Option<usize>,
+}
+
+/// The `contextmesh:ignore` comments of `code`.
+pub fn scan(code: &[u8]) -> Vec<Marker> {
+    let text = String::from_utf8_lossy(code);
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut markers = Vec::new();
+    let mut offset = 0;
+    for (idx, line) in lines.iter().enumerate() {
+        let in_comment = line.match_indices(MARKER).find(|(column, _)| {
+            let before = line[..*column].trim_end();
+            COMMENT_MARKERS
+                .iter()
+                .any(|marker| before.ends_with(marker))
+        });
+        if let Some((column, _)) = in_comment {
+            // Nothing but the comment marker before it
+            let own_line = line[..column]
+                .trim()
+                .chars()
+                .all(|c| c.is_ascii_punctuation());
+            let leads_to = match own_line {
+                true => first_code_line_end(&lines[idx + 1..], offset + line.len()),
+                false => None,
+            };
+            markers.push(Marker {
+                line: idx + 1,
+                byte: offset + column,
+                leads_to,
+            });
+        }
+        offset += line.len();
+    }
+    markers
+}
+
+/// The end of the first of `lines`, starting at byte `offset`, that isn't blank,
+/// a comment, or an attribute.
+fn first_code_line_end(lines: &[&str], mut offset: usize) -> Option<usize> {
+    for line in lines {
+        offset += line.len();
+        let trimmed = line.trim();
+        let skipped = trimmed.is_empty()
+            || trimmed.starts_with('@')
+            || COMMENT_MARKERS
+                .iter()
+                .any(|marker| trimmed.starts_with(marker));
+        if !skipped {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// Records in the attributes of the `symbols` the `markers` (see [`scan`])
+/// apply to, and of the symbols nested in them, that they are excluded (see
+/// [`Symbol::is_excluded`]).
+pub fn mark_excluded(markers: &[Marker], symbols: &mut [Symbol]) {
+    let outermost_first = |sym: &&Symbol| (sym.start_byte, std::cmp::Reverse(sym.end_byte));
+    let mut excluded = Vec::new();
+    for marker in markers {
+        let trailed = || {
+            symbols
+                .iter()
+                .filter(|sym| sym.line_number == marker.line && sym.start_byte < marker.byte)
+                .min_by_key(outermost_first)
+        };
+        let next = || {
+            let end = marker.leads_to?;
+            symbols
+                .iter()
+                .filter(|sym| marker.byte < sym.start_byte && sym.start_byte < end)
+                .min_by_key(outermost_first)
+        };
+        let enclosing = || {
+            symbols
+                .iter()
+                .filter(|sym| sym.start_byte < marker.byte && marker.byte < sym.end_byte)
+                .min_by_key(|sym| sym.end_byte - sym.start_byte)
+        };
+        if let Some(target) = trailed().or_else(next).or_else(enclosing) {
+            excluded.push(target.start_byte..target.end_byte);
+        }
+    }
+    for sym in symbols {
+        let nested = excluded
+            .iter()
+            .any(|range| range.start <= sym.start_byte && sym.end_byte <= range.end);
+        if nested && !sym.is_excluded() {
+            sym.attributes.push(Symbol::EXCLUDED_ATTRIBUTE.to_string());
+        }
+    }
+}
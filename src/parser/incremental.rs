@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use tree_sitter::{InputEdit, Point, Tree};
+
+/// Keeps the last source and syntax tree of each parsed file so that re-parsing an
+/// edited file can reuse the unchanged parts of the old tree.
+///
+/// Tree-sitter trees can't be serialized, so the cache only lives as long as the
+/// `CodeParser` that owns it; it pays off for long-lived processes that parse the
+/// same files repeatedly, like `index --watch` and the benchmarks.
+#[derive(Default)]
+pub struct TreeCache {
+    entries: HashMap<String, (Vec<u8>, Tree)>,
+}
+
+impl TreeCache {
+    /// Returns the cached tree for `file_path`, edited to match `new_code`.
+    ///
+    /// Returns `None` if the file was never parsed or is byte-for-byte unchanged
+    /// (in which case the caller may as well reuse nothing and parse from scratch).
+    pub fn edited_tree(&self, file_path: &str, new_code: &[u8]) -> Option<Tree> {
+        let (old_code, old_tree) = self.entries.get(file_path)?;
+        let edit = compute_edit(old_code, new_code)?;
+        let mut tree = old_tree.clone();
+        tree.edit(&edit);
+        Some(tree)
+    }
+
+    pub fn insert(&mut self, file_path: &str, code: Vec<u8>, tree: Tree) {
+        self.entries.insert(file_path.to_string(), (code, tree));
+    }
+}
+
+/// Describes the change from `old` to `new` as a single edit spanning everything
+/// between their common prefix and common suffix.
+fn compute_edit(old: &[u8], new: &[u8]) -> Option<InputEdit> {
+    if old == new {
+        return None;
+    }
+
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_end_byte = old.len() - suffix;
+    let new_end_byte = new.len() - suffix;
+
+    Some(InputEdit {
+        start_byte: prefix,
+        old_end_byte,
+        new_end_byte,
+        start_position: point_at(old, prefix),
+        old_end_position: point_at(old, old_end_byte),
+        new_end_position: point_at(new, new_end_byte),
+    })
+}
+
+/// Converts a byte offset into a tree-sitter row/column position.
+fn point_at(code: &[u8], byte: usize) -> Point {
+    let before = &code[..byte];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let column = match before.iter().rposition(|&b| b == b'\n') {
+        Some(newline) => byte - newline - 1,
+        None => byte,
+    };
+    Point { row, column }
+}
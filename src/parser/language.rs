@@ -1,7 +1,8 @@
-use std::collections::HashMap;
 use tree_sitter::Node;
 
+use super::Imports;
 use crate::errors::ContextMeshError;
+use crate::symbol::Visibility;
 
 /// Defines how to parse a specific programming language's code (e.g., Rust, Python),
 /// constructing "fully qualified" names and references for symbols within the codebase.
@@ -14,28 +15,114 @@ pub trait LanguageIndexer {
 
     /// Provides a list of node kinds that represent top-level definitions in the language.
     /// Top-level definitions include constructs like functions, classes, structs, enums, etc.,
-    /// depending on the language's syntax.
+    /// depending on the language's syntax. This is the default set; users can extend or
+    /// restrict it per language through `LanguageConfig`.
     fn allowed_definition_kinds(&self) -> &'static [&'static str];
 
+    /// The kind of definition a node is, matched against the definition kinds and
+    /// recorded on its symbol. Defaults to the node kind; languages whose
+    /// definitions are all one kind of node (e.g. Elixir's `def` and `defmodule`
+    /// calls) tell them apart here.
+    fn definition_kind(&self, node: Node, _code: &[u8]) -> &'static str {
+        node.kind()
+    }
+
+    /// The node naming a definition, whose line identifies the definition's symbol
+    /// when gathering references. Defaults to the `name` field.
+    fn definition_name<'tree>(&self, node: Node<'tree>) -> Option<Node<'tree>> {
+        node.child_by_field_name("name")
+    }
+
+    /// The callee of a function call node, or `None` for other nodes. Defaults to
+    /// the `function` field of a `call_expression`.
+    fn call_target<'tree>(&self, node: Node<'tree>) -> Option<Node<'tree>> {
+        if node.kind() != "call_expression" {
+            return None;
+        }
+        node.child_by_field_name("function")
+    }
+
+    /// Node kinds of type definitions that other definitions can be attached to
+    /// through `container_type_name` (e.g. the type of a Rust `impl` block).
+    fn type_definition_kinds(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// For nodes that group definitions under a type defined elsewhere (e.g. Rust
+    /// `impl` blocks), returns the name of that type.
+    fn container_type_name(&self, _node: Node, _code: &[u8]) -> Option<String> {
+        None
+    }
+
     /// Constructs the fully qualified name of a symbol given its AST node.
     fn build_qualified_name(&self, node: Node, code: &[u8]) -> Result<String, ContextMeshError>;
 
-    /// Parses import or use declarations in the code to populate the `imports` map.
+    /// Extracts the visibility modifier of a definition node. Languages without
+    /// visibility modifiers treat everything as public.
+    fn extract_visibility(&self, _node: Node, _code: &[u8]) -> Visibility {
+        Visibility::Public
+    }
+
+    /// Extracts the attributes/annotations/decorators attached to a definition node.
+    fn extract_attributes(&self, _node: Node, _code: &[u8]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Extracts the declaration of a definition without its body. The default
+    /// takes everything before the node's `body` field, or the whole node if it
+    /// has none.
+    fn extract_signature(&self, node: Node, code: &[u8]) -> String {
+        let end = node
+            .child_by_field_name("body")
+            .map_or(node.end_byte(), |body| body.start_byte());
+        String::from_utf8_lossy(&code[node.start_byte()..end])
+            .trim()
+            .to_string()
+    }
+
+    /// Extracts the documentation comment attached to a definition node.
+    fn extract_doc_comment(&self, _node: Node, _code: &[u8]) -> Option<String> {
+        None
+    }
+
+    /// Parses import or use declarations in the code to populate `imports`.
     fn process_import_declaration(
         &self,
         node: Node,
         code: &[u8],
-        imports: &mut HashMap<String, String>,
+        imports: &mut Imports,
     ) -> Result<(), ContextMeshError>;
 
+    /// Returns the (exported name, full path) pairs of a re-export node, such as a
+    /// Rust `pub use` declaration. Returns an empty list for other nodes.
+    fn extract_reexports(&self, _node: Node, _code: &[u8]) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
     /// Extracts the name of a callable entity (e.g., function, method) from a reference node.
+    /// Qualified names keep their path (e.g. `module::function` or `Type::method`),
+    /// with imported aliases expanded to the full path. Returns an empty string for
+    /// nodes that don't name a callable.
     fn extract_callable_name(
         &self,
         node: Node,
         code: &[u8],
-        imports: &HashMap<String, String>,
+        imports: &Imports,
     ) -> Result<String, ContextMeshError>;
 
+    /// Extracts the names of traits used as bounds by a reference node (e.g. generic
+    /// parameter bounds or where-clauses), so they can be recorded as dependencies.
+    /// Returns an empty list for nodes that aren't bounds.
+    fn extract_bound_names(&self, _node: Node, _code: &[u8]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the contents of a string literal node, used to find SQL queries
+    /// embedded in code. Returns `None` for other nodes.
+    fn extract_string_literal(&self, _node: Node, _code: &[u8]) -> Option<String> {
+        None
+    }
+
     /// Handles entering a new module or namespace scope during parsing.
     fn enter_module(
         &self,
@@ -0,0 +1,138 @@
+//! Size and complexity metrics of definitions, measured on their syntax trees.
+//!
+//! Node kinds are matched by the names tree-sitter grammars commonly use, so the
+//! metrics work the same for the built-in Rust indexer and the query-based
+//! languages. Definitions nested in the measured one (methods of an `impl`,
+//! inner functions) are left to their own metrics; closures count towards the
+//! enclosing definition.
+
+use tree_sitter::Node;
+
+use crate::symbol::Metrics;
+
+/// Conditionals and loops, each adding a branch and a level of nesting.
+const BRANCH_KINDS: &[&str] = &[
+    "if_expression",
+    "if_statement",
+    "elif_clause",
+    "else_if_clause",
+    "conditional_expression",
+    "ternary_expression",
+    "while_expression",
+    "while_statement",
+    "for_expression",
+    "for_statement",
+    "for_in_statement",
+    "enhanced_for_statement",
+    "loop_expression",
+    "do_statement",
+    "catch_clause",
+    "except_clause",
+];
+
+/// Arms of a `match`/`switch`, each after the first adding a branch.
+const ARM_KINDS: &[&str] = &[
+    "match_arm",
+    "case_clause",
+    "switch_case",
+    "switch_default",
+    "switch_block_statement_group",
+    "expression_case",
+    "type_case",
+    "default_case",
+    "communication_case",
+];
+
+/// `match`/`switch` constructs, adding a level of nesting.
+const SWITCH_KINDS: &[&str] = &[
+    "match_expression",
+    "match_statement",
+    "switch_statement",
+    "switch_expression",
+    "expression_switch_statement",
+    "type_switch_statement",
+    "select_statement",
+];
+
+/// Definitions with metrics of their own.
+const DEFINITION_KINDS: &[&str] = &[
+    "function_item",
+    "impl_item",
+    "trait_item",
+    "mod_item",
+    "function_definition",
+    "function_declaration",
+    "method_definition",
+    "method_declaration",
+    "class_definition",
+    "class_declaration",
+];
+
+/// Boolean operators, each adding a branch by short-circuiting.
+const BOOLEAN_OPERATORS: &[&str] = &["&&", "||", "and", "or"];
+
+/// Measures the definition `node`.
+pub fn measure(node: Node) -> Metrics {
+    let mut metrics = Metrics {
+        lines: (node.end_position().row - node.start_position().row + 1) as u32,
+        complexity: 1,
+        nesting: 0,
+        parameters: parameter_count(node),
+    };
+
+    let mut pending: Vec<(Node, u32)> = children(node).map(|child| (child, 0)).collect();
+    while let Some((node, depth)) = pending.pop() {
+        let kind = node.kind();
+        if DEFINITION_KINDS.contains(&kind) {
+            continue;
+        }
+
+        let is_branch = BRANCH_KINDS.contains(&kind);
+        if is_branch || is_later_arm(node) || is_boolean_operation(node) {
+            metrics.complexity += 1;
+        }
+        // `else if` continues the chain rather than nesting in it
+        let chained = matches!(kind, "elif_clause" | "else_if_clause")
+            || node
+                .parent()
+                .is_some_and(|parent| parent.kind() == "else_clause");
+        let depth = if (is_branch || SWITCH_KINDS.contains(&kind)) && !chained {
+            depth + 1
+        } else {
+            depth
+        };
+        metrics.nesting = metrics.nesting.max(depth);
+        pending.extend(children(node).map(|child| (child, depth)));
+    }
+    metrics
+}
+
+fn children(node: Node) -> impl Iterator<Item = Node> {
+    (0..node.named_child_count()).filter_map(move |i| node.named_child(i))
+}
+
+fn parameter_count(node: Node) -> u32 {
+    let Some(parameters) = node.child_by_field_name("parameters") else {
+        return 0;
+    };
+    children(parameters)
+        .filter(|parameter| {
+            !parameter.kind().contains("comment") && parameter.kind() != "attribute_item"
+        })
+        .count() as u32
+}
+
+/// Whether `node` is an arm of a `match`/`switch` other than its first.
+fn is_later_arm(node: Node) -> bool {
+    ARM_KINDS.contains(&node.kind())
+        && node
+            .prev_named_sibling()
+            .is_some_and(|sibling| ARM_KINDS.contains(&sibling.kind()))
+}
+
+fn is_boolean_operation(node: Node) -> bool {
+    matches!(node.kind(), "binary_expression" | "boolean_operator")
+        && node
+            .child_by_field_name("operator")
+            .is_some_and(|operator| BOOLEAN_OPERATORS.contains(&operator.kind()))
+}
@@ -1,12 +1,100 @@
+pub mod components; // JSX/TSX components and HTML pages
+pub mod css; // Class selectors of stylesheets
+pub mod docker; // Dockerfiles and compose services
+pub mod document; // Markdown and plain-text documents
+pub mod elixir_indexer; // The Elixir plugin
+pub mod external; // Indexers run as external programs
+pub mod ignore; // contextmesh:ignore comments
+pub mod incremental; // Cached trees for incremental re-parsing
 pub mod language; // The trait
+pub mod metrics; // Size and complexity of definitions
+pub mod notebook; // Code cells of Jupyter notebooks
+pub mod openapi; // Endpoints of OpenAPI specs
+pub mod proto; // Protocol Buffers definitions
+pub mod query; // Languages defined by tree-sitter query files
 pub mod rust_indexer; // The Rust plugin
+pub mod shell; // Shell scripts
+pub mod sql; // SQL schema and migration files
+pub mod structured; // TOML, YAML, and JSON configuration files
+pub mod tags; // Definitions only, for languages without a parser
+pub mod todos; // TODO/FIXME comments
+pub mod zig_indexer; // The Zig plugin
 
+use crate::config::LanguageConfig;
 use crate::errors::ContextMeshError;
+use crate::limits;
 use crate::symbol::Symbol;
+use crate::utils::hash_bytes;
+use components::ComponentIndexer;
+use css::CssIndexer;
+use docker::DockerIndexer;
+use document::DocumentIndexer;
+use elixir_indexer::ElixirIndexer;
+use external::ExternalIndexer;
+use incremental::TreeCache;
 use language::LanguageIndexer;
+use libloading::Library;
+use log::debug;
+use proto::ProtoIndexer;
+use query::QueryIndexer;
 use rust_indexer::RustIndexer;
-use std::collections::{HashMap, HashSet};
+use shell::ShellIndexer;
+use sql::SqlIndexer;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use structured::StructuredIndexer;
+use tags::TagsIndexer;
 use tree_sitter::{Node, Parser};
+use zig_indexer::ZigIndexer;
+
+/// Names brought into scope by a file's import declarations.
+#[derive(Debug, Default, Clone)]
+pub struct Imports {
+    /// Local name -> full path, e.g. `Baz` -> `crate::foo::Bar` for
+    /// `use crate::foo::Bar as Baz;`
+    pub aliases: HashMap<String, String>,
+
+    /// Modules whose items are all imported, e.g. `crate::foo` for `use crate::foo::*;`
+    pub globs: Vec<String>,
+}
+
+/// Which symbol kinds to keep for languages whose kinds are only known once parsed
+/// (external and query-based indexers).
+pub struct KindFilter {
+    /// If non-empty, only these kinds are kept
+    only: BTreeSet<String>,
+    skip: BTreeSet<String>,
+}
+
+impl KindFilter {
+    pub fn from_config(config: &LanguageConfig) -> Self {
+        KindFilter {
+            only: config.definition_kinds(&[]),
+            skip: config.skip_kinds.iter().cloned().collect(),
+        }
+    }
+
+    pub fn keeps(&self, kind: &str) -> bool {
+        (self.only.is_empty() || self.only.contains(kind)) && !self.skip.contains(kind)
+    }
+}
+
+/// The symbols and imports extracted from a single source file.
+pub struct ParsedFile {
+    pub symbols: Vec<Symbol>,
+    pub imports: Imports,
+
+    /// (child, parent) index pairs into `symbols`, e.g. a field and its struct.
+    pub parents: Vec<(usize, usize)>,
+
+    /// Number of `ERROR`/missing nodes tree-sitter inserted to recover from syntax
+    /// errors. Zero for a file that parsed cleanly; otherwise `symbols` only holds
+    /// the definitions tree-sitter could recover around the erroneous regions.
+    pub error_nodes: usize,
+
+    /// TODO/FIXME/HACK comments in the file.
+    pub todos: Vec<todos::Todo>,
+}
 
 /// `CodeParser` is responsible for parsing source files, extracting symbols,
 /// and managing dependencies using a language-specific indexer.
@@ -15,12 +103,52 @@ use tree_sitter::{Node, Parser};
 /// of source files and utilizes implementations of the `LanguageIndexer` trait
 /// to handle language-specific parsing logic.
 pub struct CodeParser {
-    /// The Tree-sitter parser used to parse source code into an AST.
-    parser: Parser,
+    /// How source files are turned into symbols.
+    backend: Backend,
+
+    /// Node kinds indexed as symbols: the plugin's `allowed_definition_kinds`
+    /// unless changed by the user's `LanguageConfig`.
+    definition_kinds: BTreeSet<String>,
+}
+
+enum Backend {
+    /// A built-in language parsed with tree-sitter.
+    TreeSitter {
+        /// The Tree-sitter parser used to parse source code into an AST.
+        parser: Parser,
+
+        /// A boxed trait object implementing `LanguageIndexer`, allowing for
+        /// language-specific parsing strategies (e.g., Rust, Python).
+        plugin: Box<dyn LanguageIndexer>,
+
+        /// Previously parsed trees, reused for incremental parsing when enabled.
+        tree_cache: Option<TreeCache>,
 
-    /// A boxed trait object implementing `LanguageIndexer`, allowing for
-    /// language-specific parsing strategies (e.g., Rust, Python).
-    plugin: Box<dyn LanguageIndexer>, // Language-specific implementation
+        /// Keeps a grammar loaded from a library alive for as long as it is used.
+        _grammar: Option<Library>,
+    },
+    /// A language defined by a grammar and tree-sitter query files.
+    Query(Box<QueryIndexer>),
+    /// A language indexed by a user-configured program.
+    External(ExternalIndexer),
+    /// Prose documents split into sections.
+    Document(DocumentIndexer),
+    /// Configuration files split into tables and keys.
+    Structured(StructuredIndexer),
+    /// SQL schema files split into tables, columns, indexes, and views.
+    Sql(SqlIndexer),
+    /// Protocol Buffers files split into messages, enums, and services.
+    Proto(ProtoIndexer),
+    /// Shell scripts split into the script and its functions.
+    Shell(ShellIndexer),
+    /// Dockerfiles split into build stages.
+    Docker(DockerIndexer),
+    /// JSX and TSX components, and HTML pages.
+    Components(ComponentIndexer),
+    /// Stylesheets split into class rules.
+    Css(CssIndexer),
+    /// Definitions found by ctags or a regex, for languages without a parser.
+    Tags(TagsIndexer),
 }
 
 impl CodeParser {
@@ -33,21 +161,222 @@ impl CodeParser {
                 ContextMeshError::TreeSitterError("Failed to set Rust language.".to_string())
             })?;
 
+        let plugin = RustIndexer;
         Ok(CodeParser {
-            parser,
-            plugin: Box::new(RustIndexer),
+            definition_kinds: LanguageConfig::default()
+                .definition_kinds(plugin.allowed_definition_kinds()),
+            backend: Backend::TreeSitter {
+                parser,
+                plugin: Box::new(plugin),
+                tree_cache: None,
+                _grammar: None,
+            },
+        })
+    }
+
+    /// Creates a `CodeParser` for Zig with the grammar library configured as
+    /// `grammar`, or `None` if the config doesn't set one.
+    pub fn new_zig(config: &LanguageConfig) -> Result<Option<Self>, ContextMeshError> {
+        let Some(grammar) = &config.grammar else {
+            return Ok(None);
+        };
+        Self::with_grammar(Box::new(ZigIndexer), grammar, config).map(Some)
+    }
+
+    /// Creates a `CodeParser` for Elixir with the bundled grammar, or the grammar
+    /// library configured as `grammar`.
+    pub fn new_elixir(config: &LanguageConfig) -> Result<Self, ContextMeshError> {
+        let grammar = config.grammar.as_deref().unwrap_or("elixir");
+        Self::with_grammar(Box::new(ElixirIndexer), grammar, config)
+    }
+
+    /// A parser for a built-in plugin whose grammar is bundled or loaded from a
+    /// library, as [`query::load_grammar`] does.
+    fn with_grammar(
+        plugin: Box<dyn LanguageIndexer>,
+        grammar: &str,
+        config: &LanguageConfig,
+    ) -> Result<Self, ContextMeshError> {
+        let (language, library) = query::load_grammar(plugin.language_name(), grammar)?;
+        let mut parser = Parser::new();
+        parser.set_language(language).map_err(|e| {
+            ContextMeshError::TreeSitterError(format!("Failed to set grammar '{}': {}", grammar, e))
+        })?;
+
+        Ok(CodeParser {
+            definition_kinds: config.definition_kinds(plugin.allowed_definition_kinds()),
+            backend: Backend::TreeSitter {
+                parser,
+                plugin,
+                tree_cache: None,
+                _grammar: library,
+            },
+        })
+    }
+
+    /// Creates a `CodeParser` for Markdown and plain-text documents.
+    pub fn new_document() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Document(DocumentIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` for TOML, YAML, and JSON configuration files.
+    pub fn new_structured() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Structured(StructuredIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` for SQL schema and migration files.
+    pub fn new_sql() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Sql(SqlIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` for Protocol Buffers (`.proto`) files.
+    pub fn new_proto() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Proto(ProtoIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` for shell scripts.
+    pub fn new_shell() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Shell(ShellIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` for Dockerfiles.
+    pub fn new_docker() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Docker(DockerIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` for JSX and TSX components and HTML pages.
+    pub fn new_components() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Components(ComponentIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` for stylesheets.
+    pub fn new_css() -> Self {
+        CodeParser {
+            definition_kinds: LanguageConfig::default().definition_kinds(&[]),
+            backend: Backend::Css(CssIndexer),
+        }
+    }
+
+    /// Creates a `CodeParser` driven by the query files configured for `language`,
+    /// or `None` if the config doesn't set any.
+    pub fn new_query(
+        language: &str,
+        config: &LanguageConfig,
+    ) -> Result<Option<Self>, ContextMeshError> {
+        let Some(indexer) = QueryIndexer::from_config(language, config)? else {
+            return Ok(None);
+        };
+        Ok(Some(CodeParser {
+            definition_kinds: config.definition_kinds(&[]),
+            backend: Backend::Query(Box::new(indexer)),
+        }))
+    }
+
+    /// Creates a `CodeParser` that runs the external indexer configured for
+    /// `language`, or `None` if the config doesn't define one.
+    pub fn new_external(language: &str, config: &LanguageConfig) -> Option<Self> {
+        let external = ExternalIndexer::from_config(language, config)?;
+        Some(CodeParser {
+            definition_kinds: config.definition_kinds(&[]),
+            backend: Backend::External(external),
         })
     }
 
+    /// Creates a `CodeParser` that only finds the definitions of `language`, for
+    /// languages without any other indexer; see [`tags`].
+    pub fn new_tags(language: &str, config: &LanguageConfig) -> Self {
+        CodeParser {
+            definition_kinds: config.definition_kinds(&[]),
+            backend: Backend::Tags(TagsIndexer::new(language, config)),
+        }
+    }
+
+    /// For parsers that only find definitions, what they find them with (e.g.
+    /// `ctags`); their files are indexed with low fidelity.
+    pub fn low_fidelity_source(&self) -> Option<&'static str> {
+        match &self.backend {
+            Backend::Tags(indexer) => Some(indexer.source()),
+            _ => None,
+        }
+    }
+
+    /// Applies the user's settings for this parser's language.
+    pub fn configure(&mut self, config: &LanguageConfig) {
+        let defaults = match &self.backend {
+            Backend::TreeSitter { plugin, .. } => plugin.allowed_definition_kinds(),
+            Backend::Query(_)
+            | Backend::External(_)
+            | Backend::Document(_)
+            | Backend::Structured(_)
+            | Backend::Sql(_)
+            | Backend::Proto(_)
+            | Backend::Shell(_)
+            | Backend::Docker(_)
+            | Backend::Components(_)
+            | Backend::Css(_)
+            | Backend::Tags(_) => &[],
+        };
+        self.definition_kinds = config.definition_kinds(defaults);
+    }
+
+    /// Node kinds this parser indexes as symbols.
+    pub fn definition_kinds(&self) -> &BTreeSet<String> {
+        &self.definition_kinds
+    }
+
+    /// Keeps each parsed tree so that later parses of the same file only re-parse
+    /// the edited region. Worth enabling for parsers that live across many runs
+    /// over the same files; a one-shot index run gains nothing from it.
+    pub fn enable_tree_cache(&mut self) {
+        if let Backend::TreeSitter { tree_cache, .. } = &mut self.backend {
+            tree_cache.get_or_insert_with(TreeCache::default);
+        }
+    }
+
+    fn language_name(&self) -> &str {
+        match &self.backend {
+            Backend::TreeSitter { plugin, .. } => plugin.language_name(),
+            Backend::Query(indexer) => indexer.language_name(),
+            Backend::External(external) => external.language_name(),
+            Backend::Document(_) => "markdown",
+            Backend::Structured(_) => "config",
+            Backend::Sql(_) => "sql",
+            Backend::Proto(_) => "proto",
+            Backend::Shell(_) => "shell",
+            Backend::Docker(_) => "docker",
+            Backend::Components(_) => "components",
+            Backend::Css(_) => "css",
+            Backend::Tags(indexer) => indexer.language_name(),
+        }
+    }
+
     /// Parses a single source file, extracting symbols and imports.
-    pub fn parse_file(
-        &mut self,
-        file_path: &str,
-    ) -> Result<(Vec<Symbol>, HashMap<String, String>), ContextMeshError> {
-        println!(
+    pub fn parse_file(&mut self, file_path: &str) -> Result<ParsedFile, ContextMeshError> {
+        debug!(
             "Parsing file '{}' using {} indexer...",
             file_path,
-            self.plugin.language_name()
+            self.language_name()
         );
 
         // Read the source file into a byte vector
@@ -56,35 +385,97 @@ impl CodeParser {
             ContextMeshError::IoError(e)
         })?;
 
-        // Parse the source code into an AST
-        let tree = self.parser.parse(&code, None).ok_or_else(|| {
+        self.parse_source(file_path, code)
+    }
+
+    /// Parses source code that is attributed to `file_path` but not read from it,
+    /// e.g. an older revision of the file.
+    pub fn parse_source(
+        &mut self,
+        file_path: &str,
+        code: Vec<u8>,
+    ) -> Result<ParsedFile, ContextMeshError> {
+        let code = limits::replace_invalid_utf8(code);
+        // Notebooks are indexed as the script of their code cells
+        let cells = notebook::is_notebook(file_path);
+        let code = match cells {
+            true => notebook::script(&code)?.into_bytes(),
+            false => code,
+        };
+        let cells = cells.then(|| notebook::cells(&code));
+        let todos = todos::scan(&code);
+        let ignored = ignore::scan(&code);
+        let mut parsed = self.parse_with_backend(file_path, code)?;
+        if let Some(cells) = cells {
+            notebook::mark_cells(&cells, &mut parsed.symbols);
+        }
+        ignore::mark_excluded(&ignored, &mut parsed.symbols);
+        parsed.todos = todos;
+        Ok(parsed)
+    }
+
+    fn parse_with_backend(
+        &mut self,
+        file_path: &str,
+        code: Vec<u8>,
+    ) -> Result<ParsedFile, ContextMeshError> {
+        let (parser, plugin, tree_cache) = match &mut self.backend {
+            Backend::TreeSitter {
+                parser,
+                plugin,
+                tree_cache,
+                ..
+            } => (parser, &**plugin, tree_cache),
+            Backend::Query(indexer) => return indexer.parse(file_path, &code),
+            Backend::External(external) => return external.parse(file_path, &code),
+            Backend::Document(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Structured(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Sql(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Proto(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Shell(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Docker(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Components(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Css(indexer) => return Ok(indexer.parse(file_path, &code)),
+            Backend::Tags(indexer) => return indexer.parse(file_path, &code),
+        };
+
+        // Parse the source code into an AST, reusing the previous tree if cached
+        let old_tree = tree_cache
+            .as_ref()
+            .and_then(|cache| cache.edited_tree(file_path, &code));
+        let tree = parser.parse(&code, old_tree.as_ref()).ok_or_else(|| {
             eprintln!("Failed to parse file {}.", file_path);
             ContextMeshError::TreeSitterError("Parsing returned no tree.".to_string())
         })?;
 
         let root = tree.root_node();
+        let error_nodes = count_error_nodes(root);
 
-        let mut symbols = Vec::new();
-        let mut imports = HashMap::new();
-
-        // Initialize module stack to keep track of nested modules
-        let mut current_module = Vec::new();
+        // One shared allocation of the path for every symbol in this file
+        let shared_path: Arc<str> = Arc::from(file_path);
 
         // 1) Collect definitions and imports in one pass
-        collect_definitions_and_imports(
-            &*self.plugin,
-            root,
-            &code,
-            file_path,
-            &mut symbols,
-            &mut imports,
-            &mut current_module,
-        )?;
+        let mut state = CollectState {
+            file_path: &shared_path,
+            definition_kinds: &self.definition_kinds,
+            symbols: Vec::new(),
+            imports: Imports::default(),
+            current_module: Vec::new(),
+            containers: Vec::new(),
+            parent_links: Vec::new(),
+        };
+        collect_definitions_and_imports(plugin, root, &code, &mut state)?;
+        let parents = state.resolve_parents(plugin);
+        let CollectState {
+            mut symbols,
+            imports,
+            ..
+        } = state;
 
         // 2) Gather references to establish dependencies
         let mut symbol_stack = Vec::new();
         gather_references(
-            &*self.plugin,
+            plugin,
             root,
             &code,
             file_path,
@@ -93,7 +484,83 @@ impl CodeParser {
             &mut symbol_stack,
         )?;
 
-        Ok((symbols, imports))
+        // Route attributes (e.g. `#[get("/users")]`) link handlers to their endpoints
+        for symbol in &mut symbols {
+            let routes: Vec<String> = symbol
+                .attributes
+                .iter()
+                .filter_map(|attr| openapi::route_in_attribute(attr))
+                .collect();
+            symbol.references.extend(routes);
+        }
+
+        if let Some(cache) = tree_cache.as_mut() {
+            cache.insert(file_path, code, tree);
+        }
+
+        Ok(ParsedFile {
+            symbols,
+            imports,
+            parents,
+            error_nodes,
+            todos: Vec::new(),
+        })
+    }
+}
+
+/// Counts the `ERROR` and missing nodes in the subtree rooted at `node`.
+fn count_error_nodes(node: Node) -> usize {
+    if !node.has_error() {
+        return 0;
+    }
+    let own = usize::from(node.is_error() || node.is_missing());
+    own + node
+        .children(&mut node.walk())
+        .map(count_error_nodes)
+        .sum::<usize>()
+}
+
+/// Something definitions can be nested in while collecting a file.
+#[derive(Clone)]
+enum Container {
+    /// A symbol of this file, by index.
+    Symbol(usize),
+    /// A type named by a construct that isn't a symbol itself (e.g. a Rust `impl`
+    /// block), resolved against the file's type definitions afterwards.
+    Named(String),
+}
+
+/// Mutable state threaded through `collect_definitions_and_imports`.
+struct CollectState<'a> {
+    file_path: &'a Arc<str>,
+    definition_kinds: &'a BTreeSet<String>,
+    symbols: Vec<Symbol>,
+    imports: Imports,
+    /// Stack of nested modules
+    current_module: Vec<String>,
+    /// Stack of enclosing containers
+    containers: Vec<Container>,
+    /// (child symbol index, its innermost container)
+    parent_links: Vec<(usize, Container)>,
+}
+
+impl CollectState<'_> {
+    /// Resolves the recorded containers to (child, parent) symbol index pairs.
+    fn resolve_parents(&self, lang: &dyn LanguageIndexer) -> Vec<(usize, usize)> {
+        self.parent_links
+            .iter()
+            .filter_map(|(child, container)| match container {
+                Container::Symbol(parent) => Some((*child, *parent)),
+                Container::Named(type_name) => self
+                    .symbols
+                    .iter()
+                    .position(|sym| {
+                        sym.name == *type_name
+                            && lang.type_definition_kinds().contains(&&*sym.node_kind)
+                    })
+                    .map(|parent| (*child, parent)),
+            })
+            .collect()
     }
 }
 
@@ -102,55 +569,130 @@ fn collect_definitions_and_imports(
     lang: &dyn LanguageIndexer,
     node: Node,
     code: &[u8],
-    file_path: &str,
-    symbols: &mut Vec<Symbol>,
-    imports: &mut HashMap<String, String>,
-    current_module: &mut Vec<String>,
+    state: &mut CollectState,
 ) -> Result<(), ContextMeshError> {
+    // Definition nodes nested inside an `ERROR` node are still complete (e.g. a
+    // well-formed function next to a broken one), so keep descending into them
+    // rather than dropping the whole region.
+
     // Enter module scope if the current node represents a module
-    lang.enter_module(node, code, current_module)?;
+    lang.enter_module(node, code, &mut state.current_module)?;
 
-    let node_kind = node.kind();
+    let node_kind = lang.definition_kind(node, code);
 
     // If the node is an import declaration, process it
-    lang.process_import_declaration(node, code, imports)?;
+    lang.process_import_declaration(node, code, &mut state.imports)?;
 
     // If the node kind is among the allowed definitions, build and store the symbol
-    if lang.allowed_definition_kinds().contains(&node_kind) {
-        let start = node.start_position();
+    let mut entered_container = false;
+    if state.definition_kinds.contains(node_kind) {
         if let Ok(full_name) = lang.build_qualified_name(node, code) {
-            symbols.push(Symbol {
-                name: full_name,
-                node_kind: node_kind.to_string(),
-                file_path: file_path.to_string(),
-                line_number: start.row + 1,
-                start_byte: node.start_byte(),
-                end_byte: node.end_byte(),
-                dependencies: HashSet::new(),
-                used_by: HashSet::new(),
-            });
+            let idx = state.symbols.len();
+            let sym = new_symbol(lang, node, code, state.file_path, full_name, node_kind);
+            state.symbols.push(sym);
+
+            if let Some(container) = state.containers.last() {
+                state.parent_links.push((idx, container.clone()));
+            }
+            state.containers.push(Container::Symbol(idx));
+            entered_container = true;
         }
     }
 
-    // Recursively traverse all child nodes
-    for child in node.children(&mut node.walk()) {
-        collect_definitions_and_imports(
+    // Re-exports become symbols of their own that depend on the original item
+    for (name, path) in lang.extract_reexports(node, code) {
+        let mut sym = new_symbol(
             lang,
-            child,
+            node,
             code,
-            file_path,
-            symbols,
-            imports,
-            current_module,
-        )?;
+            state.file_path,
+            name,
+            Symbol::REEXPORT_KIND,
+        );
+        sym.references.insert(path);
+        state.symbols.push(sym);
+    }
+    if !entered_container {
+        if let Some(type_name) = lang.container_type_name(node, code) {
+            state.containers.push(Container::Named(type_name));
+            entered_container = true;
+        }
+    }
+
+    // Recursively traverse all child nodes
+    for child in node.children(&mut node.walk()) {
+        collect_definitions_and_imports(lang, child, code, state)?;
+    }
+
+    if entered_container {
+        state.containers.pop();
     }
 
     // Exit module scope if applicable
-    lang.exit_module(current_module)?;
+    lang.exit_module(&mut state.current_module)?;
 
     Ok(())
 }
 
+/// Builds the symbol named `name` for a definition node.
+fn new_symbol(
+    lang: &dyn LanguageIndexer,
+    node: Node,
+    code: &[u8],
+    file_path: &Arc<str>,
+    name: String,
+    node_kind: &str,
+) -> Symbol {
+    Symbol {
+        name,
+        node_kind: node_kind.into(),
+        file_path: file_path.clone(),
+        line_number: node.start_position().row + 1,
+        start_byte: node.start_byte(),
+        end_byte: node.end_byte(),
+        visibility: lang.extract_visibility(node, code),
+        attributes: lang.extract_attributes(node, code),
+        signature: lang.extract_signature(node, code),
+        doc: lang.extract_doc_comment(node, code),
+        body_hash: hash_bytes(&code[node.start_byte()..node.end_byte()]),
+        parent: None,
+        references: HashSet::new(),
+        literals: BTreeSet::new(),
+        blame: None,
+        metrics: Some(metrics::measure(node)),
+        dependencies: HashSet::new(),
+        used_by: HashSet::new(),
+    }
+}
+
+/// Whether a string literal is worth recording on its symbol for `grep-sym`: a
+/// route (`/users/:id`), an environment variable name or error code
+/// (`DATABASE_URL`, `E0308`), or a dotted configuration key (`index.compression`).
+fn is_notable_literal(literal: &str) -> bool {
+    if !(3..=120).contains(&literal.len()) || literal.contains(char::is_whitespace) {
+        return false;
+    }
+    if openapi::route_key(literal).is_some() {
+        return true;
+    }
+
+    let starts_with_letter = literal.starts_with(|c: char| c.is_ascii_alphabetic());
+    let constant_like = literal
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if starts_with_letter && constant_like {
+        return literal.contains(['_', '-']) || literal.len() >= 4;
+    }
+
+    let key_like = literal
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.".contains(c));
+    starts_with_letter
+        && key_like
+        && literal.split('.').count() > 1
+        && literal.split('.').all(|part| !part.is_empty())
+}
+
 /// Traverses the AST to gather references to previously collected symbols.
 fn gather_references(
     lang: &dyn LanguageIndexer,
@@ -158,16 +700,18 @@ fn gather_references(
     code: &[u8],
     file_path: &str,
     symbols: &mut Vec<Symbol>,
-    imports: &HashMap<String, String>,
+    imports: &Imports,
     symbol_stack: &mut Vec<usize>,
 ) -> Result<(), ContextMeshError> {
-    let node_kind = node.kind();
+    let node_kind = lang.definition_kind(node, code);
 
-    // If the node has a 'name' field, it might represent a new symbol scope
-    if let Some(name_node) = node.child_by_field_name("name") {
+    // If the node is named, it might represent a new symbol scope
+    if let Some(name_node) = lang.definition_name(node) {
         let start = name_node.start_position();
         if let Some((idx, _sym)) = symbols.iter().enumerate().find(|(_, s)| {
-            s.file_path == file_path && s.line_number == start.row + 1 && s.node_kind == node_kind
+            &*s.file_path == file_path
+                && s.line_number == start.row + 1
+                && &*s.node_kind == node_kind
         }) {
             symbol_stack.push(idx);
 
@@ -181,23 +725,45 @@ fn gather_references(
         }
     }
 
+    // Traits used as bounds (generics, where-clauses, impl/dyn Trait) are
+    // dependencies of the enclosing symbol
+    if let Some(&parent_idx) = symbol_stack.last() {
+        for bound in lang.extract_bound_names(node, code) {
+            symbols[parent_idx].references.insert(bound);
+        }
+    }
+
+    // SQL in string literals makes the enclosing symbol depend on the tables it
+    // uses, and route literals on the endpoints they serve or call
+    if let (Some(&parent_idx), Some(literal)) =
+        (symbol_stack.last(), lang.extract_string_literal(node, code))
+    {
+        let symbol = &mut symbols[parent_idx];
+        symbol.references.extend(sql::table_references(&literal));
+        if openapi::route_key(&literal).is_some() {
+            symbol.references.insert(literal.clone());
+        }
+        if is_notable_literal(&literal) {
+            symbol.literals.insert(literal);
+        }
+    }
+
     // Handle function call expressions
-    if node_kind == "call_expression" {
-        if let Some(func_node) = node.child_by_field_name("function") {
-            match lang.extract_callable_name(func_node, code, imports) {
-                Ok(call_name) => {
-                    if let Some(&parent_idx) = symbol_stack.last() {
-                        symbols[parent_idx].dependencies.insert(call_name);
-                    }
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Failed to extract callable name in file '{}': {}",
-                        file_path, e
-                    );
-                    // Depending on requirements, you might choose to continue or return
+    if let Some(func_node) = lang.call_target(node) {
+        match lang.extract_callable_name(func_node, code, imports) {
+            Ok(call_name) if !call_name.is_empty() => {
+                if let Some(&parent_idx) = symbol_stack.last() {
+                    symbols[parent_idx].references.insert(call_name);
                 }
             }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!(
+                    "Failed to extract callable name in file '{}': {}",
+                    file_path, e
+                );
+                // Depending on requirements, you might choose to continue or return
+            }
         }
     }
     // Handle method call expressions (e.g., foo.bar(...))
@@ -208,7 +774,7 @@ fn gather_references(
                 Ok(method_str) => {
                     if let Some(&parent_idx) = symbol_stack.last() {
                         symbols[parent_idx]
-                            .dependencies
+                            .references
                             .insert(method_str.to_string());
                     }
                 }
@@ -221,6 +787,23 @@ fn gather_references(
             }
         }
     }
+    // Handle struct literals (e.g., Foo { .. }), which use the struct like a
+    // constructor call would
+    else if node_kind == "struct_expression" {
+        if let (Some(name_node), Some(&parent_idx)) =
+            (node.child_by_field_name("name"), symbol_stack.last())
+        {
+            if let Ok(name) = name_node.utf8_text(code) {
+                // Drop generic arguments, e.g. `Foo::<T>` or `Foo<T>`
+                let name = name
+                    .split('<')
+                    .next()
+                    .unwrap_or(name)
+                    .trim_end_matches("::");
+                symbols[parent_idx].references.insert(name.to_string());
+            }
+        }
+    }
 
     // Recursively traverse all child nodes
     for child in node.children(&mut node.walk()) {
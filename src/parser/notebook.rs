@@ -0,0 +1,109 @@
+//! Code cells of Jupyter notebooks (`.ipynb` files).
+//!
+//! A notebook is indexed as the script its code cells make up, in the order they
+//! were executed: cells with an execution count first, by that count, then the
+//! cells that never ran, in notebook order. Each cell starts with a header in
+//! the style of `jupyter nbconvert --to script` (`# In[3]: cell 5`), and IPython
+//! magics and shell escapes are commented out so that the script parses as
+//! Python. Symbol ranges, and therefore bundles and `combine`, refer to this
+//! script rather than the notebook's JSON (see [`crate::utils::read_source`]);
+//! each symbol records the cell defining it as a `notebook_cell(<n>)` attribute.
+
+use regex::Regex;
+use serde_json::Value;
+use std::io;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::symbol::Symbol;
+
+/// The file extension of notebooks.
+pub const EXTENSION: &str = "ipynb";
+
+/// A cell header, capturing the cell's 1-based position in the notebook.
+static CELL_HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^# In\[[ \d]*\]: cell (\d+)$").expect("valid regex"));
+
+/// Whether `file_path` is a notebook.
+pub fn is_notebook(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .is_some_and(|ext| ext == EXTENSION)
+}
+
+/// The script made of a notebook's code cells, in execution order.
+pub fn script(notebook: &[u8]) -> io::Result<String> {
+    let notebook: Value = serde_json::from_slice(notebook)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Notebook without cells."))?;
+
+    let mut code_cells: Vec<(usize, Option<u64>, String)> = cells
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| cell.get("cell_type").and_then(Value::as_str) == Some("code"))
+        .map(|(idx, cell)| {
+            let count = cell.get("execution_count").and_then(Value::as_u64);
+            (idx + 1, count, cell_source(cell))
+        })
+        .collect();
+    // Cells that never ran go last
+    code_cells.sort_by_key(|(position, count, _)| (count.is_none(), *count, *position));
+
+    let mut script = String::new();
+    for (position, count, source) in code_cells {
+        let count = count.map_or(" ".to_string(), |count| count.to_string());
+        script.push_str(&format!("# In[{}]: cell {}\n", count, position));
+        let is_cell_magic = source.starts_with("%%");
+        for line in source.lines() {
+            let trimmed = line.trim_start();
+            if is_cell_magic || trimmed.starts_with('%') || trimmed.starts_with('!') {
+                script.push_str("# ");
+            }
+            script.push_str(line);
+            script.push('\n');
+        }
+        script.push('\n');
+    }
+    Ok(script)
+}
+
+/// Where each cell of a notebook's script starts, with the cell's position in
+/// the notebook.
+pub fn cells(script: &[u8]) -> Vec<(usize, usize)> {
+    let script = String::from_utf8_lossy(script);
+    CELL_HEADER
+        .captures_iter(&script)
+        .filter_map(|header| {
+            let start = header.get(0).expect("match").start();
+            Some((start, header[1].parse().ok()?))
+        })
+        .collect()
+}
+
+/// Records in each symbol's attributes the cell it is defined in, given the
+/// [`cells`] of the script it was parsed from.
+pub fn mark_cells(cells: &[(usize, usize)], symbols: &mut [Symbol]) {
+    for symbol in symbols {
+        let cell = cells
+            .iter()
+            .take_while(|(start, _)| *start <= symbol.start_byte)
+            .last();
+        if let Some((_, position)) = cell {
+            symbol
+                .attributes
+                .push(format!("notebook_cell({})", position));
+        }
+    }
+}
+
+/// A cell's source, stored either as one string or as a list of lines.
+fn cell_source(cell: &Value) -> String {
+    match cell.get("source") {
+        Some(Value::String(source)) => source.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,136 @@
+//! Endpoints of OpenAPI (and Swagger) specifications.
+//!
+//! Specs are YAML/JSON files, so they are indexed by the structured config indexer;
+//! [`mark_endpoints`] then turns each operation under `paths` into an `endpoint`
+//! symbol named like `POST /users/{id}`.
+//!
+//! Code is linked to endpoints by route strings: a string literal such as
+//! `"/users/:id"` or a route attribute such as `#[post("/users/{id}")]` refers to
+//! every endpoint whose path matches, with parameters compared by position only
+//! (`{id}`, `:id`, and `<id>` are all the same). References with a method only
+//! match the endpoint of that method.
+
+use std::collections::HashMap;
+
+use crate::symbol::Symbol;
+
+/// Operation keys of an OpenAPI path item.
+const HTTP_METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Renames the operations of an OpenAPI spec to `METHOD /path` endpoints. Does
+/// nothing unless the top level has an `openapi` or `swagger` key.
+pub fn mark_endpoints(symbols: &mut [Symbol], parents: &[(usize, usize)]) {
+    let parent_of: HashMap<usize, usize> = parents.iter().copied().collect();
+    let top_level = |name: &str| {
+        (0..symbols.len()).find(|idx| !parent_of.contains_key(idx) && symbols[*idx].name == name)
+    };
+    if top_level("openapi").is_none() && top_level("swagger").is_none() {
+        return;
+    }
+    let Some(paths) = top_level("paths") else {
+        return;
+    };
+
+    for idx in 0..symbols.len() {
+        let Some(&path) = parent_of.get(&idx) else {
+            continue;
+        };
+        let method = symbols[idx].name.to_lowercase();
+        if parent_of.get(&path) != Some(&paths) || !HTTP_METHODS.contains(&method.as_str()) {
+            continue;
+        }
+
+        let summary = parents
+            .iter()
+            .filter(|(_, parent)| *parent == idx)
+            .map(|(child, _)| &symbols[*child])
+            .find(|child| child.name == "summary" || child.name == "description")
+            .and_then(|child| scalar_value(&child.signature));
+
+        let name = format!("{} {}", method.to_uppercase(), symbols[path].name);
+        let endpoint = &mut symbols[idx];
+        endpoint.node_kind = Symbol::ENDPOINT_KIND.into();
+        endpoint.signature = name.clone();
+        endpoint.name = name;
+        endpoint.doc = summary;
+    }
+}
+
+/// The value of a one-line `key: value` (YAML) or `"key": "value",` (JSON) entry.
+fn scalar_value(line: &str) -> Option<String> {
+    let (_, value) = line.split_once(':')?;
+    let value = value
+        .trim()
+        .trim_end_matches(',')
+        .trim_matches(|c| c == '"' || c == '\'');
+    (!value.is_empty() && value != "|" && value != ">").then(|| value.to_string())
+}
+
+/// Normalizes a route, optionally preceded by an HTTP method, for matching
+/// references against endpoints: `GET /users/:id/` -> `GET /users/{}`. Returns
+/// `None` if `route` doesn't look like one.
+pub fn route_key(route: &str) -> Option<String> {
+    let (method, path) = match route.split_once(' ') {
+        Some((method, path)) if HTTP_METHODS.contains(&method.to_lowercase().as_str()) => {
+            (Some(method.to_uppercase()), path)
+        }
+        _ => (None, route),
+    };
+
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<&str> = path.strip_prefix('/')?.split('/').collect();
+    let is_param = |segment: &str| segment.starts_with(['{', ':', '<', '*']);
+    let is_route = segments
+        .iter()
+        .any(|segment| !is_param(segment) && segment.contains(|c: char| c.is_ascii_alphanumeric()))
+        && segments.iter().all(|segment| {
+            segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.~{}:<>*".contains(c))
+        });
+    if !is_route {
+        return None;
+    }
+
+    let normalized: Vec<&str> = segments
+        .iter()
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| if is_param(segment) { "{}" } else { segment })
+        .collect();
+    let path = format!("/{}", normalized.join("/"));
+    Some(match method {
+        Some(method) => format!("{} {}", method, path),
+        None => path,
+    })
+}
+
+/// The keys under which an endpoint symbol is found by route references: with
+/// and without its method.
+pub fn endpoint_keys(endpoint: &Symbol) -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Some(key) = route_key(&endpoint.name) {
+        if let Some((_, path)) = key.split_once(' ') {
+            keys.push(path.to_string());
+        }
+        keys.push(key);
+    }
+    keys
+}
+
+/// The route of a route attribute, e.g. `get("/users/{id}")` -> `GET /users/{id}`
+/// or `route("/users")` -> `/users`.
+pub fn route_in_attribute(attribute: &str) -> Option<String> {
+    let (path, args) = attribute.split_once('(')?;
+    let (_, rest) = args.split_once('"')?;
+    let (route, _) = rest.split_once('"')?;
+    route_key(route)?;
+
+    let name = path.rsplit("::").next().unwrap_or(path).trim();
+    Some(if HTTP_METHODS.contains(&name) {
+        format!("{} {}", name.to_uppercase(), route)
+    } else {
+        route.to_string()
+    })
+}
@@ -0,0 +1,494 @@
+//! Indexing of Protocol Buffers definitions (`.proto` files).
+//!
+//! Messages, enums, and services become symbols, with their fields, enum values,
+//! and RPCs as children. Fields and RPCs depend on the message types they use, so
+//! pulling in an RPC also brings its request and response messages.
+//!
+//! Code generated from a `.proto` file usually isn't indexed (it lives in the build
+//! output), so usages in Rust, Go, or TypeScript would otherwise be unresolved.
+//! [`generated_names`] maps the names protoc plugins derive from a definition back
+//! to it, which lets the index link those usages to the contract.
+
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Arc;
+
+use super::{Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions handled by the protobuf indexer.
+pub const EXTENSIONS: &[&str] = &["proto"];
+
+/// Field types that don't refer to a message or enum.
+const SCALAR_TYPES: &[&str] = &[
+    "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
+    "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    String,
+    Comment,
+    Punct(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl Token<'_> {
+    fn is_punct(&self, c: char) -> bool {
+        self.kind == TokenKind::Punct(c)
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let start = pos;
+        let c = bytes[pos];
+        let kind = match c {
+            b if b.is_ascii_whitespace() => {
+                pos += 1;
+                continue;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                pos = text[pos..].find('\n').map_or(bytes.len(), |end| pos + end);
+                TokenKind::Comment
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos = text[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| pos + 2 + end + 2);
+                TokenKind::Comment
+            }
+            b'"' | b'\'' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != c {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+                TokenKind::String
+            }
+            // Qualified names (`google.protobuf.Timestamp`) are read as one word
+            b if b.is_ascii_alphanumeric() || b == b'_' || b == b'.' => {
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric()
+                        || bytes[pos] == b'_'
+                        || bytes[pos] == b'.')
+                {
+                    pos += 1;
+                }
+                TokenKind::Word
+            }
+            _ => {
+                pos += text[pos..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Punct(c as char)
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: &text[start..pos],
+            start,
+            end: pos,
+        });
+    }
+
+    tokens
+}
+
+/// A definition found in the file, before conversion.
+struct Definition {
+    name: String,
+    kind: &'static str,
+    start: usize,
+    end: usize,
+    /// End of the declaration itself, i.e. without a `{ ... }` body
+    header_end: usize,
+    doc: Option<String>,
+    parent: Option<usize>,
+    references: HashSet<String>,
+}
+
+/// Indexes `.proto` files into message, enum, and service symbols.
+pub struct ProtoIndexer;
+
+impl ProtoIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let tokens = tokenize(&text);
+        let mut scanner = Scanner {
+            text: &text,
+            tokens: &tokens,
+            pos: 0,
+            definitions: Vec::new(),
+        };
+        scanner.scan_block(None, BlockKind::File);
+
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        let parents = scanner
+            .definitions
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, def)| Some((idx, def.parent?)))
+            .collect();
+        let symbols = scanner
+            .definitions
+            .into_iter()
+            .map(|def| {
+                let source = &text[def.start..def.end];
+                Symbol {
+                    name: def.name,
+                    node_kind: def.kind.into(),
+                    file_path: shared_path.clone(),
+                    line_number: line_starts.partition_point(|&start| start <= def.start),
+                    start_byte: def.start,
+                    end_byte: def.end,
+                    visibility: Visibility::Private,
+                    attributes: Vec::new(),
+                    signature: text[def.start..def.header_end]
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    doc: def.doc,
+                    body_hash: hash_bytes(source.as_bytes()),
+                    parent: None,
+                    references: def.references,
+                    literals: BTreeSet::new(),
+                    blame: None,
+                    metrics: None,
+                    dependencies: HashSet::new(),
+                    used_by: HashSet::new(),
+                }
+            })
+            .collect();
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents,
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    File,
+    Message,
+    Enum,
+    Service,
+}
+
+struct Scanner<'t, 'a> {
+    text: &'a str,
+    tokens: &'t [Token<'a>],
+    pos: usize,
+    definitions: Vec<Definition>,
+}
+
+impl<'a> Scanner<'_, 'a> {
+    fn peek(&self, offset: usize) -> Option<Token<'a>> {
+        self.tokens.get(self.pos + offset).copied()
+    }
+
+    /// Scans statements until the `}` closing the current block (or the end of the
+    /// file), attributing definitions to `parent`.
+    fn scan_block(&mut self, parent: Option<usize>, block: BlockKind) {
+        let mut doc: Vec<&str> = Vec::new();
+        while let Some(token) = self.peek(0) {
+            if token.kind == TokenKind::Comment {
+                // A comment trailing the previous statement doesn't document the next one
+                let trailing = self.pos > 0
+                    && !self.text[self.tokens[self.pos - 1].end..token.start].contains('\n');
+                if !trailing {
+                    doc.push(token.text);
+                }
+                self.pos += 1;
+                continue;
+            }
+            if token.is_punct('}') {
+                self.pos += 1;
+                return;
+            }
+            let doc = clean_doc(&std::mem::take(&mut doc));
+
+            match (block, token.text) {
+                (_, "message") => self.scan_type(Symbol::PROTO_MESSAGE_KIND, parent, doc),
+                (_, "enum") => self.scan_type(Symbol::PROTO_ENUM_KIND, parent, doc),
+                (BlockKind::File, "service") => {
+                    self.scan_type(Symbol::PROTO_SERVICE_KIND, parent, doc)
+                }
+                (BlockKind::Service, "rpc") => self.scan_rpc(parent, doc),
+                // Fields of a `oneof` belong to the enclosing message
+                (BlockKind::Message, "oneof") => {
+                    self.skip_until_block();
+                    self.scan_block(parent, BlockKind::Message);
+                }
+                (
+                    _,
+                    "syntax" | "edition" | "package" | "import" | "option" | "reserved"
+                    | "extensions" | "extend",
+                ) => {
+                    self.skip_statement();
+                }
+                (BlockKind::Message, _) => self.scan_field(parent, doc),
+                (BlockKind::Enum, _) => self.scan_enum_value(parent, doc),
+                _ => {
+                    self.skip_statement();
+                }
+            }
+        }
+    }
+
+    /// `message Name { ... }`, `enum Name { ... }`, or `service Name { ... }`.
+    fn scan_type(&mut self, kind: &'static str, parent: Option<usize>, doc: Option<String>) {
+        let start = self.tokens[self.pos].start;
+        let Some(name) = self.peek(1).filter(|t| t.kind == TokenKind::Word) else {
+            self.skip_statement();
+            return;
+        };
+        self.pos += 2;
+        if !self.peek(0).is_some_and(|t| t.is_punct('{')) {
+            self.skip_statement();
+            return;
+        }
+        self.pos += 1;
+
+        let idx = self.definitions.len();
+        self.definitions.push(Definition {
+            name: name.text.to_string(),
+            kind,
+            start,
+            end: name.end,
+            header_end: name.end,
+            doc,
+            parent,
+            references: HashSet::new(),
+        });
+        let block = match kind {
+            Symbol::PROTO_ENUM_KIND => BlockKind::Enum,
+            Symbol::PROTO_SERVICE_KIND => BlockKind::Service,
+            _ => BlockKind::Message,
+        };
+        self.scan_block(Some(idx), block);
+        self.definitions[idx].end = self.tokens[self.pos - 1].end;
+    }
+
+    /// `rpc Name (Request) returns (Response);`, optionally with an options block.
+    fn scan_rpc(&mut self, parent: Option<usize>, doc: Option<String>) {
+        let start_pos = self.pos;
+        let Some(name) = self.peek(1).filter(|t| t.kind == TokenKind::Word) else {
+            self.skip_statement();
+            return;
+        };
+        let end_pos = self.skip_statement();
+        let header = &self.tokens[start_pos..end_pos];
+
+        // The message types are the words inside the parentheses, minus `stream`
+        let mut references = HashSet::new();
+        let mut depth = 0;
+        for token in header {
+            match token.kind {
+                TokenKind::Punct('(') => depth += 1,
+                TokenKind::Punct(')') => depth -= 1,
+                TokenKind::Word if depth > 0 && token.text != "stream" => {
+                    references.insert(normalize_type(token.text));
+                }
+                _ => {}
+            }
+        }
+
+        let header_end = header
+            .iter()
+            .rposition(|t| t.is_punct(')'))
+            .map_or(name.end, |idx| header[idx].end);
+        self.definitions.push(Definition {
+            name: name.text.to_string(),
+            kind: Symbol::PROTO_RPC_KIND,
+            start: self.tokens[start_pos].start,
+            end: self.tokens[self.pos - 1].end,
+            header_end,
+            doc,
+            parent,
+            references,
+        });
+    }
+
+    /// `[repeated|optional] Type name = 1 [options];` or `map<K, V> name = 1;`
+    fn scan_field(&mut self, parent: Option<usize>, doc: Option<String>) {
+        let start_pos = self.pos;
+        let end_pos = self.skip_statement();
+        let statement = &self.tokens[start_pos..end_pos];
+        let Some(eq) = statement.iter().position(|t| t.is_punct('=')) else {
+            return;
+        };
+        let Some(name) = eq.checked_sub(1).map(|idx| statement[idx]) else {
+            return;
+        };
+        if name.kind != TokenKind::Word {
+            return;
+        }
+
+        let references = statement[..eq - 1]
+            .iter()
+            .filter(|t| t.kind == TokenKind::Word)
+            .filter(|t| !matches!(t.text, "repeated" | "optional" | "required" | "map"))
+            .filter(|t| !SCALAR_TYPES.contains(&t.text))
+            .map(|t| normalize_type(t.text))
+            .collect();
+
+        self.definitions.push(Definition {
+            name: name.text.to_string(),
+            kind: Symbol::PROTO_FIELD_KIND,
+            start: statement[0].start,
+            end: self.tokens[self.pos - 1].end,
+            header_end: self.tokens[self.pos - 1].end,
+            doc,
+            parent,
+            references,
+        });
+    }
+
+    /// `NAME = 1;`
+    fn scan_enum_value(&mut self, parent: Option<usize>, doc: Option<String>) {
+        let name = self.tokens[self.pos];
+        self.skip_statement();
+        if name.kind != TokenKind::Word {
+            return;
+        }
+        self.definitions.push(Definition {
+            name: name.text.to_string(),
+            kind: Symbol::PROTO_ENUM_VALUE_KIND,
+            start: name.start,
+            end: self.tokens[self.pos - 1].end,
+            header_end: self.tokens[self.pos - 1].end,
+            doc,
+            parent,
+            references: HashSet::new(),
+        });
+    }
+
+    /// Advances past the `{` opening the statement's block.
+    fn skip_until_block(&mut self) {
+        while let Some(token) = self.peek(0) {
+            self.pos += 1;
+            if token.is_punct('{') {
+                return;
+            }
+        }
+    }
+
+    /// Advances past the current statement: up to its `;`, or past its balanced
+    /// `{ ... }` block. Returns the position of the `;` or `{` (the statement's end
+    /// without its body).
+    fn skip_statement(&mut self) -> usize {
+        while let Some(token) = self.peek(0) {
+            if token.is_punct(';') {
+                self.pos += 1;
+                return self.pos - 1;
+            }
+            if token.is_punct('{') {
+                let header_end = self.pos;
+                let mut depth = 0;
+                while let Some(token) = self.peek(0) {
+                    self.pos += 1;
+                    if token.is_punct('{') {
+                        depth += 1;
+                    } else if token.is_punct('}') {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                }
+                // An optional `;` after the block
+                if self.peek(0).is_some_and(|t| t.is_punct(';')) {
+                    self.pos += 1;
+                }
+                return header_end;
+            }
+            // Leave the closing brace of the enclosing block to the caller
+            if token.is_punct('}') {
+                return self.pos;
+            }
+            self.pos += 1;
+        }
+        self.pos
+    }
+}
+
+/// `google.protobuf.Timestamp` -> `google::protobuf::Timestamp`, so references
+/// resolve like qualified paths of other languages.
+fn normalize_type(name: &str) -> String {
+    name.trim_start_matches('.').replace('.', "::")
+}
+
+/// Joins the comment lines preceding a definition without their markers.
+fn clean_doc(comments: &[&str]) -> Option<String> {
+    let lines: Vec<&str> = comments
+        .iter()
+        .flat_map(|comment| comment.lines())
+        .map(|line| {
+            line.trim()
+                .trim_start_matches("//")
+                .trim_start_matches("/*")
+                .trim_end_matches("*/")
+                .trim_start_matches('*')
+                .trim()
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Names of the definitions that protoc plugins generate `name` from, in the order
+/// to try them, e.g. `get_user`/`getUser` -> `GetUser` (an RPC),
+/// `UserServiceClient`/`RegisterUserServiceServer` -> `UserService`.
+pub fn generated_names(name: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let pascal = to_pascal_case(name);
+
+    let mut service = pascal.as_str();
+    for prefix in ["New", "Register", "Unimplemented", "Unsafe"] {
+        service = service.strip_prefix(prefix).unwrap_or(service);
+    }
+    for suffix in ["Client", "Server", "Handlers", "Definition", "Impl"] {
+        if let Some(stripped) = service.strip_suffix(suffix) {
+            names.push(stripped.to_string());
+            break;
+        }
+    }
+
+    if pascal != name {
+        names.push(pascal);
+    }
+    names.retain(|candidate| !candidate.is_empty());
+    names
+}
+
+/// `get_user` / `getUser` -> `GetUser`
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
@@ -0,0 +1,463 @@
+//! Languages defined declaratively by a tree-sitter grammar and query files.
+//!
+//! Instead of walking the syntax tree in Rust, a language can be described by
+//! queries in the style of tree-sitter's tags queries, placed in a directory that
+//! is configured in `.contextmesh/config.toml`:
+//!
+//! ```toml
+//! [languages.python]
+//! queries = "queries/python"
+//! grammar = "python"          # bundled grammar, or a path to a grammar library
+//! extensions = ["py"]
+//! ```
+//!
+//! `tags.scm` defines symbols and references:
+//!
+//! - `@definition.<kind>` captures a definition node, which becomes a symbol of that
+//!   kind. `@name` in the same pattern captures its name, and optional `@doc`
+//!   captures its documentation.
+//! - `@reference.<kind>` captures a reference; `@name` in the same pattern captures
+//!   the referenced name. It is attributed to the innermost enclosing definition.
+//!
+//! The optional `imports.scm` captures `@import.path` with an optional
+//! `@import.alias`, and `@import.glob` for modules whose items are all imported.
+//! Several `@import.path` (or `@import.glob`) captures in one match are the
+//! segments of one path, for grammars without a node spanning the whole path
+//! (captured as `[(identifier) "."]+ @import.path`, the dots being skipped), and
+//! `@import.name` appends the imported name to it (e.g. for each name in Scala's
+//! `import a.b.{C, D}`).
+//!
+//! Definitions nested in another definition's range get it as their parent.
+//! The repository's `queries/` directory has query files for Python and for Scala,
+//! which are built in and used when a language's config sets no `queries`. Python
+//! then uses its bundled grammar, Scala needs `grammar` set to a compiled
+//! [tree-sitter-scala] grammar.
+//! A grammar library must export a `tree_sitter_<language>` function.
+//!
+//! [tree-sitter-scala]: https://github.com/tree-sitter/tree-sitter-scala
+
+use libloading::Library;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tree_sitter::{
+    Language, Node, Parser, Query, QueryCursor, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION,
+};
+
+use super::{count_error_nodes, metrics, Imports, KindFilter, ParsedFile};
+use crate::config::LanguageConfig;
+use crate::errors::ContextMeshError;
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// Indexes a language using the queries in a configured directory.
+pub struct QueryIndexer {
+    language: String,
+    parser: Parser,
+    tags: Query,
+    imports: Option<Query>,
+    kinds: KindFilter,
+    /// Keeps a grammar loaded from a library alive for as long as it is used
+    _library: Option<Library>,
+}
+
+/// A definition matched by the tags query.
+struct Definition<'tree> {
+    node: Node<'tree>,
+    kind: String,
+    name: String,
+    doc: Vec<String>,
+}
+
+impl QueryIndexer {
+    /// Creates the indexer for `language`, or `None` if its config sets no queries
+    /// and none are bundled for it, or they are but its grammar isn't and the
+    /// config sets none.
+    pub fn from_config(
+        language: &str,
+        config: &LanguageConfig,
+    ) -> Result<Option<Self>, ContextMeshError> {
+        let language_key = language.to_lowercase();
+        let bundled = BUNDLED_QUERIES
+            .iter()
+            .find(|(name, ..)| *name == language_key);
+        let grammar = config.grammar.as_deref().unwrap_or(language);
+        let (ts_language, library) = match (&config.queries, bundled) {
+            (Some(_), _) => load_grammar(language, grammar)?,
+            (None, Some(_)) if config.grammar.is_some() || is_bundled_grammar(grammar) => {
+                load_grammar(language, grammar)?
+            }
+            _ => return Ok(None),
+        };
+
+        let mut parser = Parser::new();
+        parser.set_language(ts_language).map_err(|e| {
+            ContextMeshError::TreeSitterError(format!("Failed to set grammar '{}': {}", grammar, e))
+        })?;
+
+        let (tags, imports) = match (&config.queries, bundled) {
+            (Some(dir), _) => {
+                let dir = Path::new(dir);
+                let tags = load_query(ts_language, &dir.join("tags.scm"))?.ok_or_else(|| {
+                    ContextMeshError::ConfigError(format!("{} has no tags.scm.", dir.display()))
+                })?;
+                (tags, load_query(ts_language, &dir.join("imports.scm"))?)
+            }
+            (None, Some((name, tags, imports))) => {
+                let label = format!("bundled queries of {}", name);
+                let tags = parse_query(ts_language, tags, &label)?;
+                (tags, Some(parse_query(ts_language, imports, &label)?))
+            }
+            (None, None) => unreachable!("returned above"),
+        };
+
+        Ok(Some(QueryIndexer {
+            language: language.to_lowercase(),
+            parser,
+            tags,
+            imports,
+            kinds: KindFilter::from_config(config),
+            _library: library,
+        }))
+    }
+
+    pub fn language_name(&self) -> &str {
+        &self.language
+    }
+
+    pub fn parse(&mut self, file_path: &str, code: &[u8]) -> Result<ParsedFile, ContextMeshError> {
+        let tree = self.parser.parse(code, None).ok_or_else(|| {
+            ContextMeshError::TreeSitterError("Parsing returned no tree.".to_string())
+        })?;
+        let root = tree.root_node();
+        let error_nodes = count_error_nodes(root);
+
+        let imports = self.collect_imports(root, code);
+        let (mut definitions, references) = self.collect_tags(root, code, &imports);
+
+        // Outer definitions first, so each one's parent precedes it
+        definitions.sort_by_key(|def| {
+            (
+                def.node.start_byte(),
+                std::cmp::Reverse(def.node.end_byte()),
+            )
+        });
+        definitions.dedup_by(|a, b| a.node == b.node && a.name == b.name);
+
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let mut symbols: Vec<Symbol> = definitions
+            .iter()
+            .map(|def| {
+                let source = &code[def.node.start_byte()..def.node.end_byte()];
+                let text = String::from_utf8_lossy(source);
+                Symbol {
+                    name: def.name.clone(),
+                    node_kind: def.kind.as_str().into(),
+                    file_path: shared_path.clone(),
+                    line_number: def.node.start_position().row + 1,
+                    start_byte: def.node.start_byte(),
+                    end_byte: def.node.end_byte(),
+                    visibility: Visibility::Public,
+                    attributes: Vec::new(),
+                    signature: text.lines().next().unwrap_or_default().trim().to_string(),
+                    doc: clean_doc(&def.doc),
+                    body_hash: hash_bytes(source),
+                    parent: None,
+                    references: HashSet::new(),
+                    literals: BTreeSet::new(),
+                    blame: None,
+                    metrics: Some(metrics::measure(def.node)),
+                    dependencies: HashSet::new(),
+                    used_by: HashSet::new(),
+                }
+            })
+            .collect();
+
+        let parents: Vec<(usize, usize)> = (0..definitions.len())
+            .filter_map(|child| {
+                let parent = innermost_enclosing(&definitions[..child], definitions[child].node)?;
+                Some((child, parent))
+            })
+            .collect();
+
+        for (node, name) in references {
+            if let Some(idx) = innermost_enclosing(&definitions, node) {
+                if symbols[idx].name != name {
+                    symbols[idx].references.insert(name);
+                }
+            }
+        }
+
+        // Drop filtered kinds, remapping parent links to the remaining symbols
+        let mut kept = Vec::with_capacity(symbols.len());
+        let mut next = 0;
+        for sym in &symbols {
+            let keep = self.kinds.keeps(&sym.node_kind);
+            kept.push(keep.then_some(next));
+            next += usize::from(keep);
+        }
+        let parents = parents
+            .into_iter()
+            .filter_map(|(child, parent)| Some((kept[child]?, kept[parent]?)))
+            .collect();
+        let symbols = symbols
+            .into_iter()
+            .zip(&kept)
+            .filter_map(|(sym, keep)| keep.map(|_| sym))
+            .collect();
+
+        Ok(ParsedFile {
+            symbols,
+            imports,
+            parents,
+            error_nodes,
+            todos: Vec::new(),
+        })
+    }
+
+    fn collect_imports(&self, root: Node, code: &[u8]) -> Imports {
+        let mut imports = Imports::default();
+        let Some(query) = &self.imports else {
+            return imports;
+        };
+        let names = query.capture_names();
+
+        let mut cursor = QueryCursor::new();
+        for m in cursor.matches(query, root, code) {
+            let mut path = Vec::new();
+            let mut glob = Vec::new();
+            let mut alias = None;
+            let mut named = false;
+            let mut last_path = None;
+            for capture in m.captures {
+                let text = capture.node.utf8_text(code).unwrap_or_default();
+                match names[capture.index as usize].as_str() {
+                    "import.path" => {
+                        path.push(normalize_path(text));
+                        last_path = Some(capture.node);
+                    }
+                    "import.name" => {
+                        path.push(normalize_path(text));
+                        named = true;
+                    }
+                    "import.alias" => alias = Some(text.to_string()),
+                    "import.glob" => glob.push(normalize_path(text)),
+                    _ => {}
+                }
+            }
+            // A bare path stopping at a separator is the package of selectors or a
+            // wildcard that follow, which another pattern imports
+            if !named && last_path.is_some_and(|node| continues_past(node, code)) {
+                continue;
+            }
+            // Separators captured along with the segments are left empty
+            path.retain(|segment| !segment.is_empty());
+            glob.retain(|segment| !segment.is_empty());
+            if !glob.is_empty() {
+                imports.globs.push(glob.join("::"));
+            }
+            if !path.is_empty() {
+                let path = path.join("::");
+                let alias =
+                    alias.unwrap_or_else(|| path.rsplit("::").next().unwrap_or(&path).to_string());
+                imports.aliases.insert(alias, path);
+            }
+        }
+        imports
+    }
+
+    /// Returns the definitions and the (node, name) references matched by the tags
+    /// query, with imported aliases expanded in reference names.
+    fn collect_tags<'tree>(
+        &self,
+        root: Node<'tree>,
+        code: &[u8],
+        imports: &Imports,
+    ) -> (Vec<Definition<'tree>>, Vec<(Node<'tree>, String)>) {
+        let names = self.tags.capture_names();
+        let mut definitions = Vec::new();
+        let mut references = Vec::new();
+
+        let mut cursor = QueryCursor::new();
+        for m in cursor.matches(&self.tags, root, code) {
+            let mut definition = None;
+            let mut reference = None;
+            let mut name = None;
+            let mut doc = Vec::new();
+            for capture in m.captures {
+                let capture_name = names[capture.index as usize].as_str();
+                let text = capture.node.utf8_text(code).unwrap_or_default();
+                if let Some(kind) = capture_name.strip_prefix("definition.") {
+                    definition = Some((capture.node, kind.to_string()));
+                } else if capture_name.starts_with("reference.") {
+                    reference = Some(capture.node);
+                } else if capture_name == "name" {
+                    name = Some(text.to_string());
+                } else if capture_name == "doc" {
+                    doc.push(text.to_string());
+                }
+            }
+
+            let Some(name) = name else {
+                continue;
+            };
+            if let Some((node, kind)) = definition {
+                definitions.push(Definition {
+                    node,
+                    kind,
+                    name,
+                    doc,
+                });
+            } else if let Some(node) = reference {
+                let name = imports.aliases.get(&name).cloned().unwrap_or(name);
+                references.push((node, name));
+            }
+        }
+
+        (definitions, references)
+    }
+}
+
+/// Index of the smallest definition whose range contains `node`, other than `node`
+/// itself.
+fn innermost_enclosing(definitions: &[Definition], node: Node) -> Option<usize> {
+    definitions
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| {
+            def.node != node
+                && def.node.start_byte() <= node.start_byte()
+                && node.end_byte() <= def.node.end_byte()
+        })
+        .min_by_key(|(_, def)| def.node.end_byte() - def.node.start_byte())
+        .map(|(idx, _)| idx)
+}
+
+/// Whether the path segment `node` is a `.` separator or is followed by one.
+fn continues_past(node: Node, code: &[u8]) -> bool {
+    let is_separator = |node: Node| node.utf8_text(code) == Ok(".");
+    is_separator(node) || node.next_sibling().is_some_and(is_separator)
+}
+
+/// Writes an import path with `::` separators, as used by dependency resolution.
+fn normalize_path(path: &str) -> String {
+    path.trim_start_matches('.').replace('.', "::")
+}
+
+/// Joins `@doc` captures, stripping comment markers and string quotes.
+fn clean_doc(parts: &[String]) -> Option<String> {
+    let lines: Vec<&str> = parts
+        .iter()
+        .flat_map(|part| part.lines())
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(['#', '/', '-', ';', '*'])
+                .trim_start_matches('!')
+                .trim_matches(['"', '\''])
+                .trim()
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn load_query(language: Language, path: &Path) -> Result<Option<Query>, ContextMeshError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let source = fs::read_to_string(path)?;
+    parse_query(language, &source, &path.display().to_string()).map(Some)
+}
+
+/// Parses the query `source`, read from `origin`.
+fn parse_query(language: Language, source: &str, origin: &str) -> Result<Query, ContextMeshError> {
+    Query::new(language, source)
+        .map_err(|e| ContextMeshError::ConfigError(format!("{}: {}", origin, e)))
+}
+
+/// Query files of the repository's `queries/` directory, used for a language
+/// whose config sets no `queries`: (language, `tags.scm`, `imports.scm`).
+const BUNDLED_QUERIES: &[(&str, &str, &str)] = &[
+    (
+        "python",
+        include_str!("../../queries/python/tags.scm"),
+        include_str!("../../queries/python/imports.scm"),
+    ),
+    (
+        "scala",
+        include_str!("../../queries/scala/tags.scm"),
+        include_str!("../../queries/scala/imports.scm"),
+    ),
+];
+
+/// Whether `grammar` names a grammar built into contextmesh.
+fn is_bundled_grammar(grammar: &str) -> bool {
+    matches!(grammar, "rust" | "python" | "elixir")
+}
+
+/// Returns a bundled grammar by name, or loads `tree_sitter_<language>` from the
+/// grammar library at `grammar`.
+///
+/// Loading a library runs its code with the user's rights, so the config naming
+/// it is trusted as much as the indexer `command`s it may also set: review the
+/// `.contextmesh/config.toml` of a repository before indexing it, as you would
+/// its build scripts. Beyond that, only the grammar's ABI version is checked.
+pub(super) fn load_grammar(
+    language: &str,
+    grammar: &str,
+) -> Result<(Language, Option<Library>), ContextMeshError> {
+    let (ts_language, library) = match grammar {
+        "rust" => (tree_sitter_rust::language(), None),
+        "python" => (tree_sitter_python::language(), None),
+        "elixir" => (elixir_language(), None),
+        _ => {
+            let (ts_language, library) = load_grammar_library(language, grammar)?;
+            (ts_language, Some(library))
+        }
+    };
+
+    // Grammars generated by another tree-sitter lay out their data differently
+    let version = ts_language.version();
+    if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
+        return Err(ContextMeshError::ConfigError(format!(
+            "Grammar '{}' has language version {}, but this build of contextmesh reads \
+             versions {} to {}; regenerate it with a matching tree-sitter",
+            grammar, version, MIN_COMPATIBLE_LANGUAGE_VERSION, LANGUAGE_VERSION
+        )));
+    }
+    Ok((ts_language, library))
+}
+
+/// Loads `tree_sitter_<language>` from the grammar library at `grammar`.
+fn load_grammar_library(
+    language: &str,
+    grammar: &str,
+) -> Result<(Language, Library), ContextMeshError> {
+    let load_error = |e: libloading::Error| {
+        ContextMeshError::ConfigError(format!("Failed to load grammar '{}': {}", grammar, e))
+    };
+    let symbol = format!("tree_sitter_{}", language.to_lowercase().replace('-', "_"));
+    // SAFETY: opening the library runs its initializers, which is the trust put in
+    // the configured path (see `load_grammar`). The entry point of a tree-sitter
+    // grammar takes no arguments and returns a pointer to language data owned by
+    // the library; a symbol of that name with another signature can't be detected
+    // and is undefined behavior. The library is returned to be kept alive for as
+    // long as the language is used.
+    unsafe {
+        let library = Library::new(grammar).map_err(load_error)?;
+        let entry: libloading::Symbol<unsafe extern "C" fn() -> Language> =
+            library.get(symbol.as_bytes()).map_err(load_error)?;
+        let ts_language = entry();
+        Ok((ts_language, library))
+    }
+}
+
+/// The bundled tree-sitter-elixir grammar, which is built for a newer tree-sitter
+/// API but generated with a language version this one reads.
+fn elixir_language() -> Language {
+    let entry = tree_sitter_elixir::LANGUAGE.into_raw();
+    // SAFETY: the entry point returns a pointer to the grammar's static
+    // `TSLanguage`, which is all `Language` wraps
+    unsafe { std::mem::transmute::<*const (), Language>(entry()) }
+}
@@ -1,7 +1,8 @@
 use crate::errors::ContextMeshError;
+use crate::symbol::Visibility;
 
 use super::language::LanguageIndexer;
-use std::collections::HashMap;
+use super::Imports;
 use tree_sitter::Node;
 
 /// Rust-specific implementation of the `LanguageIndexer` trait.
@@ -27,15 +28,42 @@ impl LanguageIndexer for RustIndexer {
             "struct_item",
             "enum_item",
             "field_declaration",
+            "enum_variant",
             "static_item",
             "const_item",
         ]
     }
 
+    /// Rust types whose `impl` blocks attach methods to them.
+    fn type_definition_kinds(&self) -> &'static [&'static str] {
+        &["struct_item", "enum_item", "trait_item", "union_item"]
+    }
+
+    /// Returns the implemented type of an `impl` block, without generics or path.
+    fn container_type_name(&self, node: Node, code: &[u8]) -> Option<String> {
+        if node.kind() != "impl_item" {
+            return None;
+        }
+        let mut type_node = node.child_by_field_name("type")?;
+        loop {
+            type_node = match type_node.kind() {
+                "generic_type" => type_node.child_by_field_name("type")?,
+                "scoped_type_identifier" => type_node.child_by_field_name("name")?,
+                _ => break,
+            };
+        }
+        type_node.utf8_text(code).ok().map(str::to_string)
+    }
+
     /// Constructs the fully qualified name of a Rust symbol given its AST node.
     fn build_qualified_name(&self, node: Node, code: &[u8]) -> Result<String, ContextMeshError> {
-        // Extract the symbol's short name
-        if let Some(name_node) = node.child_by_field_name("name") {
+        // Extract the symbol's short name; `let` bindings are named by a simple
+        // identifier pattern (destructuring patterns are skipped)
+        let name_node = node.child_by_field_name("name").or_else(|| {
+            node.child_by_field_name("pattern")
+                .filter(|pattern| pattern.kind() == "identifier")
+        });
+        if let Some(name_node) = name_node {
             let short_name = name_node.utf8_text(code).map_err(|_| {
                 ContextMeshError::DeserializationError("Failed to extract name text.".to_string())
             })?;
@@ -48,98 +76,203 @@ impl LanguageIndexer for RustIndexer {
         }
     }
 
-    /// Parses Rust import declarations (`use` statements) to populate the `imports` map.
+    /// Reads the `visibility_modifier` child of a Rust item, if any.
+    fn extract_visibility(&self, node: Node, code: &[u8]) -> Visibility {
+        let modifier = node
+            .children(&mut node.walk())
+            .find(|child| child.kind() == "visibility_modifier");
+        let Some(text) = modifier.and_then(|m| m.utf8_text(code).ok()) else {
+            return Visibility::Private;
+        };
+
+        let compact: String = text.split_whitespace().collect();
+        match compact.as_str() {
+            "pub" => Visibility::Public,
+            "pub(crate)" => Visibility::Crate,
+            _ => Visibility::Restricted(compact),
+        }
+    }
+
+    /// Collects the `#[...]` attributes directly preceding a Rust item, skipping
+    /// interleaved comments (doc comments are comments in tree-sitter-rust).
+    fn extract_attributes(&self, node: Node, code: &[u8]) -> Vec<String> {
+        let mut attributes = Vec::new();
+        let mut sibling = node.prev_sibling();
+        while let Some(prev) = sibling {
+            match prev.kind() {
+                "attribute_item" => {
+                    if let Some(attr) = prev
+                        .children(&mut prev.walk())
+                        .find(|child| child.kind() == "attribute")
+                        .and_then(|attr| attr.utf8_text(code).ok())
+                    {
+                        attributes.push(attr.to_string());
+                    }
+                }
+                "line_comment" | "block_comment" => {}
+                _ => break,
+            }
+            sibling = prev.prev_sibling();
+        }
+        // Restore source order
+        attributes.reverse();
+        attributes
+    }
+
+    /// Collects the `///` or `/** */` comments directly preceding a Rust item,
+    /// skipping interleaved attributes.
+    fn extract_doc_comment(&self, node: Node, code: &[u8]) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut sibling = node.prev_sibling();
+        while let Some(prev) = sibling {
+            match prev.kind() {
+                "line_comment" => {
+                    let text = prev.utf8_text(code).ok()?;
+                    match text.strip_prefix("///") {
+                        // `////` is an ordinary comment, not documentation
+                        Some(doc) if !doc.starts_with('/') => {
+                            lines.push(doc.strip_prefix(' ').unwrap_or(doc).trim_end().to_string())
+                        }
+                        _ => break,
+                    }
+                }
+                "block_comment" => {
+                    let text = prev.utf8_text(code).ok()?;
+                    match text.strip_prefix("/**").and_then(|t| t.strip_suffix("*/")) {
+                        Some(doc) => lines.extend(
+                            doc.lines()
+                                .rev()
+                                .map(|line| line.trim().trim_start_matches('*').trim().to_string()),
+                        ),
+                        None => break,
+                    }
+                }
+                "attribute_item" => {}
+                _ => break,
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        // Restore source order
+        lines.reverse();
+        let doc = lines.join("\n").trim().to_string();
+        (!doc.is_empty()).then_some(doc)
+    }
+
+    /// Parses Rust import declarations (`use` statements) to populate `imports`,
+    /// including aliases (`use a::B as C;`), nested lists (`use a::{b, c::D};`),
+    /// and glob imports (`use a::*;`).
     fn process_import_declaration(
         &self,
         node: Node,
         code: &[u8],
-        imports: &mut HashMap<String, String>,
+        imports: &mut Imports,
     ) -> Result<(), ContextMeshError> {
         if node.kind() != "use_declaration" {
             return Ok(());
         }
 
-        // Handle 'use' declarations with potential aliases
-        // e.g., use crate::foo::Bar as Baz;
-        // or use crate::foo::Bar;
-
-        // Extract the path
-        if let Some(path_node) = node.child_by_field_name("path") {
-            let path_text = path_node
-                .utf8_text(code)
-                .map_err(|_| {
-                    ContextMeshError::DeserializationError(
-                        "Failed to extract path text.".to_string(),
-                    )
-                })?
-                .to_string();
-
-            // Check for an alias
-            if let Some(alias_node) = node.child_by_field_name("alias") {
-                let alias_text = alias_node
-                    .utf8_text(code)
-                    .map_err(|_| {
-                        ContextMeshError::DeserializationError(
-                            "Failed to extract alias text.".to_string(),
-                        )
-                    })?
-                    .to_string();
-                imports.insert(alias_text.to_string(), path_text);
-            } else {
-                // No alias; insert the last segment as the identifier
-                if let Some(last_segment) = path_text.split("::").last() {
-                    imports.insert(last_segment.to_string(), path_text);
-                }
-            }
+        if let Some(argument) = node.child_by_field_name("argument") {
+            collect_use_tree(argument, code, "", imports)?;
         }
 
         Ok(())
     }
 
-    /// Extracts the name of a callable entity (e.g., function, method) from a reference node.
+    /// Returns the items re-exported by a `pub use` declaration (any visibility
+    /// other than private). Glob re-exports (`pub use foo::*;`) name no single item
+    /// and are skipped.
+    fn extract_reexports(&self, node: Node, code: &[u8]) -> Vec<(String, String)> {
+        if node.kind() != "use_declaration"
+            || self.extract_visibility(node, code) == Visibility::Private
+        {
+            return Vec::new();
+        }
+        let mut exported = Imports::default();
+        if let Some(argument) = node.child_by_field_name("argument") {
+            if collect_use_tree(argument, code, "", &mut exported).is_err() {
+                return Vec::new();
+            }
+        }
+        let mut reexports: Vec<(String, String)> = exported.aliases.into_iter().collect();
+        reexports.sort();
+        reexports
+    }
+
+    /// Extracts the name of a callable entity from a Rust AST node.
     fn extract_callable_name(
         &self,
         node: Node,
         code: &[u8],
-        imports: &HashMap<String, String>,
+        imports: &Imports,
     ) -> Result<String, ContextMeshError> {
-        let node_kind = node.kind();
-        match node_kind {
-            "identifier" => {
-                let text = node.utf8_text(code).map_err(|_| {
-                    ContextMeshError::DeserializationError(
-                        "Failed to extract identifier text.".to_string(),
-                    )
-                })?;
-                // Replace with full path if alias exists
-                if let Some(full_path) = imports.get(text) {
-                    Ok(full_path.clone())
-                } else {
-                    Ok(text.to_string())
-                }
-            }
-            "scoped_identifier" => {
-                // e.g., "commands::run_command"
-                let raw = node.utf8_text(code).map_err(|_| {
-                    ContextMeshError::DeserializationError(
-                        "Failed to extract scoped identifier text.".to_string(),
-                    )
-                })?;
-
-                Ok(raw
+        match node.kind() {
+            "identifier" | "scoped_identifier" => {
+                // e.g., "run_command" or "commands::run_command"
+                let raw = node_text(node, code)?;
+
+                // Drop turbofish segments (e.g. "Vec::<u8>::new")
+                let mut segments = raw
                     .split("::")
-                    .last()
-                    .map(|s| s.to_string())
-                    .ok_or_else(|| {
-                        ContextMeshError::DeserializationError(
-                            "Failed to extract last segment of scoped identifier.".to_string(),
-                        )
-                    })?)
+                    .map(str::trim)
+                    .filter(|segment| !segment.starts_with('<'));
+                let first = segments.next().unwrap_or_default();
+
+                // Replace an imported alias with the full path it stands for
+                let mut name = imports
+                    .aliases
+                    .get(first)
+                    .cloned()
+                    .unwrap_or_else(|| first.to_string());
+                for segment in segments {
+                    name.push_str("::");
+                    name.push_str(segment);
+                }
+                Ok(name)
             }
+            // e.g., "parse::<T>(...)"
+            "generic_function" => match node.child_by_field_name("function") {
+                Some(function) => self.extract_callable_name(function, code, imports),
+                None => Ok(String::new()),
+            },
             _ => Ok(String::new()),
         }
     }
 
+    /// Handles `"..."` and raw `r#"..."#` string literals; escapes are kept as written.
+    fn extract_string_literal(&self, node: Node, code: &[u8]) -> Option<String> {
+        if !matches!(node.kind(), "string_literal" | "raw_string_literal") {
+            return None;
+        }
+        let text = node_text(node, code).ok()?;
+        let contents = text
+            .trim_start_matches(['r', '#'])
+            .trim_end_matches('#')
+            .strip_prefix('"')?
+            .strip_suffix('"')?;
+        Some(contents.to_string())
+    }
+
+    /// Collects trait names from `trait_bounds` (generics and where-clauses),
+    /// `impl Trait` and `dyn Trait`. Lifetime bounds are ignored.
+    fn extract_bound_names(&self, node: Node, code: &[u8]) -> Vec<String> {
+        let mut names = Vec::new();
+        match node.kind() {
+            "trait_bounds" => {
+                for bound in node.named_children(&mut node.walk()) {
+                    collect_trait_name(bound, code, &mut names);
+                }
+            }
+            "abstract_type" | "dynamic_type" => {
+                if let Some(bound) = node.child_by_field_name("trait") {
+                    collect_trait_name(bound, code, &mut names);
+                }
+            }
+            _ => {}
+        }
+        names
+    }
+
     /// Handles entering a new module or namespace scope during parsing.
     fn enter_module(
         &self,
@@ -172,3 +305,117 @@ impl LanguageIndexer for RustIndexer {
         Ok(())
     }
 }
+
+/// Resolves a single bound to the trait's short name, e.g. `Serialize` for
+/// `serde::Serialize`, `Into` for `Into<String>` and `Fn` for `for<'a> Fn(&'a u8)`.
+fn collect_trait_name(bound: Node, code: &[u8], names: &mut Vec<String>) {
+    let name_node = match bound.kind() {
+        "type_identifier" => Some(bound),
+        "scoped_type_identifier" => bound.child_by_field_name("name"),
+        "generic_type" => bound.child_by_field_name("type"),
+        "function_type" => bound.child_by_field_name("trait"),
+        "higher_ranked_trait_bound" => {
+            if let Some(inner) = bound.child_by_field_name("type") {
+                collect_trait_name(inner, code, names);
+            }
+            None
+        }
+        // Lifetimes and anything else don't name a trait
+        _ => None,
+    };
+
+    match name_node.map(|n| (n.kind(), n)) {
+        // `generic_type`'s type may itself be scoped (e.g. `serde::Serialize<T>`)
+        Some(("scoped_type_identifier", n)) => collect_trait_name(n, code, names),
+        Some((_, n)) => {
+            if let Ok(text) = n.utf8_text(code) {
+                names.push(text.to_string());
+            }
+        }
+        None => {}
+    }
+}
+
+/// Records the names brought into scope by a `use` tree under the path `prefix`.
+fn collect_use_tree(
+    node: Node,
+    code: &[u8],
+    prefix: &str,
+    imports: &mut Imports,
+) -> Result<(), ContextMeshError> {
+    let join = |path: &str| {
+        if prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}::{}", prefix, path)
+        }
+    };
+
+    match node.kind() {
+        // e.g., `use crate::foo::Bar;` or `bar` inside a list
+        "identifier" | "scoped_identifier" | "crate" | "super" | "self" => {
+            let path = join(node_text(node, code)?);
+            let Some((module, last)) = path.rsplit_once("::") else {
+                imports.aliases.insert(path.clone(), path);
+                return Ok(());
+            };
+            if last == "self" {
+                // `use foo::{self}` imports the module itself
+                let name = module.rsplit("::").next().unwrap_or(module);
+                imports.aliases.insert(name.to_string(), module.to_string());
+            } else {
+                imports.aliases.insert(last.to_string(), path.clone());
+            }
+        }
+        // e.g., `use crate::foo::Bar as Baz;`
+        "use_as_clause" => {
+            let (Some(path), Some(alias)) = (
+                node.child_by_field_name("path"),
+                node.child_by_field_name("alias"),
+            ) else {
+                return Ok(());
+            };
+            let alias = node_text(alias, code)?;
+            // `use Trait as _;` only brings methods into scope
+            if alias != "_" {
+                imports
+                    .aliases
+                    .insert(alias.to_string(), join(node_text(path, code)?));
+            }
+        }
+        // e.g., `use crate::foo::*;`
+        "use_wildcard" => {
+            let module = match node.named_child(0) {
+                Some(path) => join(node_text(path, code)?),
+                None => prefix.to_string(),
+            };
+            if !module.is_empty() {
+                imports.globs.push(module);
+            }
+        }
+        // e.g., `use crate::foo::{Bar, baz::Qux};`
+        "scoped_use_list" => {
+            let prefix = match node.child_by_field_name("path") {
+                Some(path) => join(node_text(path, code)?),
+                None => prefix.to_string(),
+            };
+            if let Some(list) = node.child_by_field_name("list") {
+                collect_use_tree(list, code, &prefix, imports)?;
+            }
+        }
+        "use_list" => {
+            for child in node.named_children(&mut node.walk()) {
+                collect_use_tree(child, code, prefix, imports)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn node_text<'a>(node: Node, code: &'a [u8]) -> Result<&'a str, ContextMeshError> {
+    node.utf8_text(code).map_err(|_| {
+        ContextMeshError::DeserializationError(format!("Failed to extract {} text.", node.kind()))
+    })
+}
@@ -0,0 +1,367 @@
+//! Indexing of shell scripts (`.sh` and `.bash` files).
+//!
+//! Each script becomes a symbol named after its file (e.g. `deploy.sh`) holding
+//! its top-level commands, with its functions as children. Commands make the
+//! enclosing function or script depend on the functions they call, on the
+//! scripts they `source` (or `.`), and on the scripts they run (`./build.sh`,
+//! `bash ci/test.sh`), so pulling in a CI script also brings the helpers and
+//! scripts it uses. Builtins and common utilities aren't recorded as references.
+//!
+//! Scripts are scanned rather than parsed: comments, quoted strings, and here
+//! documents are masked out, function bodies are delimited by their braces, and
+//! a command is the first word of each pipeline element.
+
+use regex::Regex;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use super::{Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions handled by the shell indexer.
+pub const EXTENSIONS: &[&str] = &["sh", "bash"];
+
+/// `name() {`, `function name {`, or `function name() {`.
+static FUNCTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*(?:function[ \t]+([A-Za-z_][\w:.-]*)(?:[ \t]*\([ \t]*\))?|([A-Za-z_][\w:.-]*)[ \t]*\([ \t]*\))",
+    )
+    .expect("valid regex")
+});
+
+/// `<<EOF`, `<<-EOF`, `<< 'EOF'`, or `<<"EOF"`.
+static HEREDOC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<<-?[ \t]*['"]?([A-Za-z_]\w*)['"]?"#).expect("valid regex"));
+
+/// Words that may precede the command of a pipeline element.
+const PREFIXES: &[&str] = &[
+    "if", "then", "else", "elif", "while", "until", "do", "!", "time", "exec", "command", "sudo",
+    "nohup", "xargs", "env",
+];
+
+/// Builtins, keywords, and utilities that never name a function of the project.
+const COMMON_COMMANDS: &[&str] = &[
+    "alias", "awk", "basename", "break", "case", "cat", "cd", "chmod", "chown", "continue", "cp",
+    "curl", "cut", "date", "declare", "dirname", "done", "echo", "esac", "eval", "exit", "export",
+    "false", "fi", "find", "for", "function", "getopts", "grep", "head", "in", "local", "ls",
+    "mkdir", "mktemp", "mv", "printf", "pwd", "read", "readonly", "return", "rm", "sed", "select",
+    "set", "shift", "sleep", "sort", "tail", "tee", "test", "touch", "tr", "trap", "true", "type",
+    "uniq", "unset", "wait", "wc", "which", ":", "[", "[[", "]]", "{", "}",
+];
+
+/// Shells that run the script given as their first argument.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash"];
+
+/// A definition found in the script, before conversion.
+struct Definition {
+    name: String,
+    kind: &'static str,
+    start: usize,
+    end: usize,
+    /// End of the declaration itself, i.e. without the body
+    header_end: usize,
+    doc: Option<String>,
+    references: HashSet<String>,
+}
+
+/// Indexes shell scripts into script and function symbols.
+pub struct ShellIndexer;
+
+impl ShellIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let masked = mask(&text);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        let file_name = Path::new(file_path)
+            .file_name()
+            .map_or(file_path.to_string(), |name| {
+                name.to_string_lossy().to_string()
+            });
+
+        let header_end = text.find('\n').unwrap_or(text.len());
+        let mut definitions = vec![Definition {
+            name: file_name,
+            kind: Symbol::SHELL_SCRIPT_KIND,
+            start: 0,
+            end: text.len(),
+            header_end,
+            doc: leading_comments(&text, &line_starts, 0),
+            references: HashSet::new(),
+        }];
+        for function in FUNCTION.captures_iter(&masked) {
+            let whole = function.get(0).expect("match");
+            let name = function.get(1).or(function.get(2)).expect("name group");
+            let Some(end) = body_end(&masked, whole.end()) else {
+                continue;
+            };
+            let line = line_starts.partition_point(|&start| start <= whole.start()) - 1;
+            definitions.push(Definition {
+                name: name.as_str().to_string(),
+                kind: Symbol::SHELL_FUNCTION_KIND,
+                start: line_starts[line],
+                end,
+                header_end: whole.end(),
+                doc: leading_comments(&text, &line_starts, line),
+                references: HashSet::new(),
+            });
+        }
+
+        // Each command is attributed to the innermost definition containing it
+        for range in commands(&masked) {
+            let start = range.start;
+            let words: Vec<&str> = text[range].split_whitespace().collect();
+            let Some(reference) = referenced_name(&words) else {
+                continue;
+            };
+            let owner = (0..definitions.len())
+                .filter(|&idx| definitions[idx].start <= start && start < definitions[idx].end)
+                .min_by_key(|&idx| definitions[idx].end - definitions[idx].start)
+                .unwrap_or(0);
+            if definitions[owner].name != reference {
+                definitions[owner].references.insert(reference);
+            }
+        }
+
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let parents = (1..definitions.len()).map(|idx| (idx, 0)).collect();
+        let symbols = definitions
+            .into_iter()
+            .map(|def| {
+                let source = &text[def.start..def.end];
+                Symbol {
+                    name: def.name,
+                    node_kind: def.kind.into(),
+                    file_path: shared_path.clone(),
+                    line_number: line_starts.partition_point(|&start| start <= def.start),
+                    start_byte: def.start,
+                    end_byte: def.end,
+                    visibility: Visibility::Public,
+                    attributes: Vec::new(),
+                    signature: text[def.start..def.header_end].trim().to_string(),
+                    doc: def.doc,
+                    body_hash: hash_bytes(source.as_bytes()),
+                    parent: None,
+                    references: def.references,
+                    literals: BTreeSet::new(),
+                    blame: None,
+                    metrics: None,
+                    dependencies: HashSet::new(),
+                    used_by: HashSet::new(),
+                }
+            })
+            .collect();
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents,
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+/// Replaces comments, the contents of quoted strings, and here-document bodies
+/// with spaces, keeping byte offsets and newlines, so that only code is scanned.
+fn mask(text: &str) -> String {
+    fn blank(masked: &mut [u8], range: std::ops::Range<usize>) {
+        for byte in &mut masked[range] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+
+    let bytes = text.as_bytes();
+    let mut masked = bytes.to_vec();
+
+    let mut pos = 0;
+    let mut heredocs: Vec<String> = Vec::new();
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'#' if pos == 0
+                || matches!(
+                    bytes[pos - 1],
+                    b' ' | b'\t' | b'\n' | b';' | b'&' | b'|' | b'('
+                ) =>
+            {
+                let end = text[pos..].find('\n').map_or(bytes.len(), |end| pos + end);
+                blank(&mut masked, pos..end);
+                pos = end;
+            }
+            quote @ (b'\'' | b'"') => {
+                let mut end = pos + 1;
+                while end < bytes.len() && bytes[end] != quote {
+                    end += if quote == b'"' && bytes[end] == b'\\' {
+                        2
+                    } else {
+                        1
+                    };
+                }
+                let end = end.min(bytes.len());
+                blank(&mut masked, pos + 1..end);
+                pos = end + 1;
+            }
+            b'<' if bytes.get(pos + 1) == Some(&b'<') && bytes.get(pos + 2) != Some(&b'<') => {
+                if let Some(heredoc) = HEREDOC
+                    .captures(&text[pos..])
+                    .filter(|m| m.get(0).expect("match").start() == 0)
+                {
+                    heredocs.push(heredoc[1].to_string());
+                    pos += heredoc[0].len();
+                } else {
+                    pos += 2;
+                }
+            }
+            // Here-document bodies start on the line after their operator
+            b'\n' if !heredocs.is_empty() => {
+                let mut line_start = pos + 1;
+                for delimiter in std::mem::take(&mut heredocs) {
+                    let body_start = line_start;
+                    loop {
+                        let line_end = text[line_start..]
+                            .find('\n')
+                            .map_or(bytes.len(), |end| line_start + end);
+                        let done = text[line_start..line_end].trim() == delimiter;
+                        line_start = (line_end + 1).min(bytes.len());
+                        if done || line_end == bytes.len() {
+                            blank(&mut masked, body_start..line_end);
+                            break;
+                        }
+                    }
+                }
+                pos = line_start;
+            }
+            _ => pos += 1,
+        }
+    }
+    String::from_utf8_lossy(&masked).into_owned()
+}
+
+/// The end of a function body starting after `header_end`: the brace (or, for a
+/// subshell body, the parenthesis) matching the first one.
+fn body_end(masked: &str, header_end: usize) -> Option<usize> {
+    let rest = &masked[header_end..];
+    let offset = rest.find(|c: char| !c.is_whitespace())?;
+    let (open, close) = match rest.as_bytes()[offset] {
+        b'{' => (b'{', b'}'),
+        b'(' => (b'(', b')'),
+        _ => return None,
+    };
+    let mut depth = 0usize;
+    for (idx, &byte) in rest.as_bytes()[offset..].iter().enumerate() {
+        if byte == open {
+            depth += 1;
+        } else if byte == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(header_end + offset + idx + 1);
+            }
+        }
+    }
+    None
+}
+
+/// The comment lines directly above line `line` (after a shebang for the first
+/// line), as documentation.
+fn leading_comments(text: &str, line_starts: &[usize], line: usize) -> Option<String> {
+    let line_text = |idx: usize| {
+        let end = line_starts.get(idx + 1).map_or(text.len(), |&end| end);
+        text[line_starts[idx]..end].trim()
+    };
+    let mut lines = Vec::new();
+    if line == 0 {
+        // A script is documented by the comments at its top
+        let mut idx = usize::from(line_text(0).starts_with("#!"));
+        while idx < line_starts.len() && line_text(idx).starts_with('#') {
+            lines.push(line_text(idx));
+            idx += 1;
+        }
+    } else {
+        let mut idx = line;
+        while idx > 0
+            && line_text(idx - 1).starts_with('#')
+            && !line_text(idx - 1).starts_with("#!")
+        {
+            idx -= 1;
+            lines.push(line_text(idx));
+        }
+        lines.reverse();
+    }
+    let doc: Vec<&str> = lines
+        .iter()
+        .map(|line| line.trim_start_matches('#').trim())
+        .collect();
+    let doc = doc.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// The byte range of each simple command, split at the separators of the masked
+/// script (so that quoted separators don't split a command).
+fn commands(masked: &str) -> Vec<std::ops::Range<usize>> {
+    let mut commands = Vec::new();
+    let mut start = 0;
+    for (idx, c) in masked
+        .char_indices()
+        .chain(std::iter::once((masked.len(), '\n')))
+    {
+        if matches!(c, '\n' | ';' | '|' | '&' | '(' | ')' | '`' | '{' | '}') {
+            if !masked[start..idx].trim().is_empty() {
+                commands.push(start..idx);
+            }
+            start = idx + c.len_utf8();
+        }
+    }
+    commands
+}
+
+/// What a command refers to: the file name of a sourced or run script, or the
+/// name of a function it calls.
+fn referenced_name(words: &[&str]) -> Option<String> {
+    let mut words = words
+        .iter()
+        .copied()
+        // Variable assignments before the command, e.g. `RUST_LOG=debug cargo test`
+        .skip_while(|word| word.contains('=') && !word.starts_with('='))
+        .skip_while(|word| PREFIXES.contains(word) || word.starts_with('-'));
+    let command = words.next()?;
+    match command {
+        // The path may be built from a command substitution, e.g.
+        // `source "$(dirname "$0")/lib.sh"`
+        "source" | "." => script_name(&words.collect::<Vec<_>>().join(" ")),
+        _ if SHELLS.contains(&command) => {
+            let path = words.find(|word| !word.starts_with('-'))?;
+            is_script(path).then(|| script_name(path))?
+        }
+        _ if command.contains('/') => is_script(command).then(|| script_name(command))?,
+        _ if COMMON_COMMANDS.contains(&command) || command.starts_with('$') => None,
+        _ if command
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':' | '.')) =>
+        {
+            Some(command.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Whether a path names a shell script by its extension.
+fn is_script(path: &str) -> bool {
+    let path = path.trim_end_matches(['"', '\'']);
+    EXTENSIONS
+        .iter()
+        .any(|ext| path.ends_with(&format!(".{}", ext)))
+}
+
+/// The file name of a script path, without quotes; `None` if it depends on a
+/// variable.
+fn script_name(path: &str) -> Option<String> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let name = name.split_whitespace().next()?.trim_matches(['"', '\'']);
+    (!name.is_empty() && !name.contains('$')).then(|| name.to_string())
+}
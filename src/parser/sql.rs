@@ -0,0 +1,452 @@
+//! Indexing of SQL schema and migration files.
+//!
+//! `CREATE TABLE` statements become table symbols with their columns as children,
+//! `ALTER TABLE ... ADD COLUMN` adds columns, and `CREATE INDEX`/`CREATE VIEW`
+//! become index and view symbols that depend on the tables they cover. The indexer
+//! works on tokens rather than a full grammar, so it copes with the DDL of the
+//! common dialects (PostgreSQL, MySQL, SQLite) and skips statements it doesn't
+//! understand.
+//!
+//! [`table_references`] finds the tables used by SQL embedded in string literals
+//! of other languages, which links the code running a query to the schema.
+
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Arc;
+
+use super::{Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions handled by the SQL indexer.
+pub const EXTENSIONS: &[&str] = &["sql"];
+
+/// Keywords that start a table constraint rather than a column definition.
+const CONSTRAINT_KEYWORDS: &[&str] = &[
+    "constraint",
+    "primary",
+    "foreign",
+    "unique",
+    "check",
+    "key",
+    "index",
+    "exclude",
+    "like",
+    "fulltext",
+    "spatial",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    /// A quoted identifier (`"name"`, `` `name` ``, `[name]`)
+    Quoted,
+    String,
+    Punct(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Token<'a> {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.kind == TokenKind::Punct(c)
+    }
+
+    fn is_identifier(&self) -> bool {
+        matches!(self.kind, TokenKind::Word | TokenKind::Quoted)
+    }
+
+    /// The identifier without its quotes.
+    fn name(&self) -> &'a str {
+        let text = self.text;
+        match self.kind {
+            TokenKind::Quoted => {
+                // An unterminated identifier runs to the end of the text
+                let close = match text.as_bytes()[0] {
+                    b'[' => ']',
+                    open => open as char,
+                };
+                let inner = &text[1..];
+                inner.strip_suffix(close).unwrap_or(inner)
+            }
+            _ => text,
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    // Advances `pos` past the closing `close` byte, honoring doubled quotes
+    let skip_quoted = |mut pos: usize, close: u8| -> usize {
+        while pos < bytes.len() {
+            if bytes[pos] == close {
+                if bytes.get(pos + 1) == Some(&close) && close != b']' {
+                    pos += 2;
+                    continue;
+                }
+                return pos + 1;
+            }
+            pos += 1;
+        }
+        pos
+    };
+
+    while pos < bytes.len() {
+        let start = pos;
+        let c = bytes[pos];
+        let kind = match c {
+            b if b.is_ascii_whitespace() => {
+                pos += 1;
+                continue;
+            }
+            b'-' if bytes.get(pos + 1) == Some(&b'-') => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos = text[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| pos + 2 + end + 2);
+                continue;
+            }
+            b'\'' => {
+                pos = skip_quoted(pos + 1, b'\'');
+                TokenKind::String
+            }
+            b'"' | b'`' | b'[' => {
+                let close = if c == b'[' { b']' } else { c };
+                pos = skip_quoted(pos + 1, close);
+                TokenKind::Quoted
+            }
+            b if b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80 => {
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric()
+                        || bytes[pos] == b'_'
+                        || bytes[pos] == b'$'
+                        || bytes[pos] >= 0x80)
+                {
+                    pos += 1;
+                }
+                TokenKind::Word
+            }
+            _ => {
+                // Non-ASCII bytes are part of words, so this is a single ASCII character
+                pos += 1;
+                TokenKind::Punct(c as char)
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: &text[start..pos],
+            start,
+            end: pos,
+        });
+    }
+
+    tokens
+}
+
+/// Reads a possibly schema-qualified name (`public.users`) starting at `*idx`,
+/// returning its last part.
+fn qualified_name<'a>(tokens: &[Token<'a>], idx: &mut usize) -> Option<&'a str> {
+    let mut name = None;
+    while let Some(token) = tokens.get(*idx) {
+        if !token.is_identifier() {
+            break;
+        }
+        name = Some(token.name());
+        *idx += 1;
+        if tokens.get(*idx).is_some_and(|t| t.is_punct('.')) {
+            *idx += 1;
+        } else {
+            break;
+        }
+    }
+    name
+}
+
+/// Skips the given keywords (in order, each optional) starting at `*idx`.
+fn skip_keywords(tokens: &[Token], idx: &mut usize, keywords: &[&str]) {
+    for keyword in keywords {
+        if tokens.get(*idx).is_some_and(|t| t.is_keyword(keyword)) {
+            *idx += 1;
+        }
+    }
+}
+
+/// A symbol found in the file, before conversion.
+struct Definition {
+    name: String,
+    kind: &'static str,
+    start: usize,
+    end: usize,
+    parent: Option<usize>,
+    references: HashSet<String>,
+}
+
+/// Indexes SQL files into table, column, index, and view symbols.
+pub struct SqlIndexer;
+
+impl SqlIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let tokens = tokenize(&text);
+        let mut definitions = Vec::new();
+
+        for statement in tokens.split(|t| t.is_punct(';')) {
+            scan_statement(statement, &mut definitions);
+        }
+
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        let parents = definitions
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, def)| Some((idx, def.parent?)))
+            .collect();
+        let symbols = definitions
+            .into_iter()
+            .map(|def| {
+                let source = &text[def.start..def.end];
+                Symbol {
+                    name: def.name,
+                    node_kind: def.kind.into(),
+                    file_path: shared_path.clone(),
+                    line_number: line_starts.partition_point(|&start| start <= def.start),
+                    start_byte: def.start,
+                    end_byte: def.end,
+                    visibility: Visibility::Private,
+                    attributes: Vec::new(),
+                    signature: source.split_whitespace().collect::<Vec<_>>().join(" "),
+                    doc: None,
+                    body_hash: hash_bytes(source.as_bytes()),
+                    parent: None,
+                    references: def.references,
+                    literals: BTreeSet::new(),
+                    blame: None,
+                    metrics: None,
+                    dependencies: HashSet::new(),
+                    used_by: HashSet::new(),
+                }
+            })
+            .collect();
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents,
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+fn scan_statement(tokens: &[Token], definitions: &mut Vec<Definition>) {
+    let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+        return;
+    };
+    let (start, end) = (first.start, last.end);
+    let mut idx = 1;
+
+    if first.is_keyword("create") {
+        skip_keywords(tokens, &mut idx, &["or", "replace"]);
+        skip_keywords(
+            tokens,
+            &mut idx,
+            &["temp", "temporary", "unlogged", "materialized"],
+        );
+        skip_keywords(tokens, &mut idx, &["unique", "fulltext", "spatial"]);
+        let Some(object) = tokens.get(idx) else {
+            return;
+        };
+        idx += 1;
+        skip_keywords(tokens, &mut idx, &["concurrently", "if", "not", "exists"]);
+
+        if object.is_keyword("table") {
+            let Some(name) = qualified_name(tokens, &mut idx) else {
+                return;
+            };
+            let table = definitions.len();
+            definitions.push(Definition {
+                name: name.to_string(),
+                kind: Symbol::SQL_TABLE_KIND,
+                start,
+                end,
+                parent: None,
+                references: HashSet::new(),
+            });
+            if tokens.get(idx).is_some_and(|t| t.is_punct('(')) {
+                scan_columns(&tokens[idx + 1..], table, definitions);
+            }
+        } else if object.is_keyword("index") {
+            let name = qualified_name(tokens, &mut idx)
+                .unwrap_or("index")
+                .to_string();
+            skip_keywords(tokens, &mut idx, &["on", "only"]);
+            let references = qualified_name(tokens, &mut idx)
+                .map(|table| HashSet::from([table.to_string()]))
+                .unwrap_or_default();
+            definitions.push(Definition {
+                name,
+                kind: Symbol::SQL_INDEX_KIND,
+                start,
+                end,
+                parent: None,
+                references,
+            });
+        } else if object.is_keyword("view") {
+            let Some(name) = qualified_name(tokens, &mut idx) else {
+                return;
+            };
+            definitions.push(Definition {
+                name: name.to_string(),
+                kind: Symbol::SQL_VIEW_KIND,
+                start,
+                end,
+                parent: None,
+                references: tables_in(&tokens[idx..]),
+            });
+        }
+    } else if first.is_keyword("alter") {
+        skip_keywords(tokens, &mut idx, &["table", "if", "exists", "only"]);
+        let Some(table_name) = qualified_name(tokens, &mut idx) else {
+            return;
+        };
+        let table = definitions
+            .iter()
+            .rposition(|def| def.kind == Symbol::SQL_TABLE_KIND && def.name == table_name);
+
+        // Each `ADD [COLUMN] name type ...` clause, separated by commas
+        for clause in tokens[idx..].split(|t| t.is_punct(',')) {
+            let mut pos = 0;
+            if !clause.first().is_some_and(|t| t.is_keyword("add")) {
+                continue;
+            }
+            pos += 1;
+            skip_keywords(clause, &mut pos, &["column", "if", "not", "exists"]);
+            let Some(column) = clause.get(pos).filter(|t| t.is_identifier()) else {
+                continue;
+            };
+            if CONSTRAINT_KEYWORDS.contains(&column.text.to_lowercase().as_str()) {
+                continue;
+            }
+            definitions.push(Definition {
+                name: column.name().to_string(),
+                kind: Symbol::SQL_COLUMN_KIND,
+                start: column.start,
+                end: clause.last().map_or(column.end, |t| t.end),
+                parent: table,
+                // Columns added to a table defined in another file still link to it
+                references: if table.is_none() {
+                    HashSet::from([table_name.to_string()])
+                } else {
+                    HashSet::new()
+                },
+            });
+        }
+    }
+}
+
+/// Adds the column definitions of a `CREATE TABLE` body (starting after its `(`).
+fn scan_columns(tokens: &[Token], table: usize, definitions: &mut Vec<Definition>) {
+    let mut depth = 0;
+    let mut element_start = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::Punct('(') => depth += 1,
+            TokenKind::Punct(')') if depth > 0 => depth -= 1,
+            TokenKind::Punct(',' | ')') if depth == 0 => {
+                add_column(&tokens[element_start..idx], table, definitions);
+                element_start = idx + 1;
+                if token.is_punct(')') {
+                    return;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn add_column(element: &[Token], table: usize, definitions: &mut Vec<Definition>) {
+    let (Some(first), Some(last)) = (element.first(), element.last()) else {
+        return;
+    };
+    if !first.is_identifier()
+        || (first.kind == TokenKind::Word
+            && CONSTRAINT_KEYWORDS.contains(&first.text.to_lowercase().as_str()))
+    {
+        return;
+    }
+
+    // `REFERENCES other(id)` makes the column depend on the other table
+    let references = element
+        .windows(2)
+        .filter(|pair| pair[0].is_keyword("references") && pair[1].is_identifier())
+        .map(|pair| pair[1].name().to_string())
+        .collect();
+
+    definitions.push(Definition {
+        name: first.name().to_string(),
+        kind: Symbol::SQL_COLUMN_KIND,
+        start: first.start,
+        end: last.end,
+        parent: Some(table),
+        references,
+    });
+}
+
+/// Names of the tables following `FROM`, `JOIN`, `INTO`, `UPDATE`, and `TABLE`.
+fn tables_in(tokens: &[Token]) -> HashSet<String> {
+    let mut tables = HashSet::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        let introduces_table = ["from", "join", "into", "update", "table"]
+            .iter()
+            .any(|keyword| token.is_keyword(keyword));
+        if !introduces_table {
+            continue;
+        }
+        let mut pos = idx + 1;
+        skip_keywords(tokens, &mut pos, &["only", "if", "exists"]);
+        if let Some(name) = qualified_name(tokens, &mut pos) {
+            // Skip subqueries and keywords mistaken for names (`DELETE FROM` etc.)
+            if !["select", "lateral", "set", "values"].contains(&name.to_lowercase().as_str()) {
+                tables.insert(name.to_string());
+            }
+        }
+    }
+    tables
+}
+
+/// Tables used by `literal` if it looks like an SQL statement, e.g.
+/// `"SELECT id FROM users JOIN teams ..."` -> `users`, `teams`.
+pub fn table_references(literal: &str) -> HashSet<String> {
+    let tokens = tokenize(literal);
+    let is_sql = tokens.first().is_some_and(|first| {
+        [
+            "select", "insert", "update", "delete", "with", "create", "alter", "drop",
+        ]
+        .iter()
+        .any(|keyword| first.is_keyword(keyword))
+    }) && tokens.len() > 2;
+    if is_sql {
+        tables_in(&tokens)
+    } else {
+        HashSet::new()
+    }
+}
@@ -0,0 +1,445 @@
+//! Indexing of configuration files (TOML, YAML, JSON).
+//!
+//! Tables/objects and their keys become symbols nested like the file itself, so
+//! e.g. `[features]` in a `Cargo.toml` or a job in a CI workflow can be pulled into
+//! context on its own. Keys whose value is a table are `table` symbols; all other
+//! keys are `key` symbols. The scanners are line- and token-based rather than full
+//! parsers: they only need to find keys and their extent, and keep going on input a
+//! strict parser would reject.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::{docker, openapi, Imports, ParsedFile};
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions handled by the structured config indexer.
+pub const EXTENSIONS: &[&str] = &["toml", "yaml", "yml", "json"];
+
+fn extension(file_path: &str) -> Option<String> {
+    Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+}
+
+/// A key found by one of the scanners.
+struct Entry {
+    name: String,
+    start: usize,
+    end: usize,
+    parent: Option<usize>,
+    /// Whether the value is a table/object (rather than a scalar or list)
+    is_table: bool,
+}
+
+/// Indexes configuration files as trees of table and key symbols.
+pub struct StructuredIndexer;
+
+impl StructuredIndexer {
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> ParsedFile {
+        let text = String::from_utf8_lossy(code);
+        let entries = match extension(file_path).as_deref() {
+            Some("toml") => scan_toml(&text),
+            Some("yaml" | "yml") => scan_yaml(&text),
+            Some("json") => scan_json(&text),
+            _ => Vec::new(),
+        };
+
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+
+        let mut parents = Vec::new();
+        let mut symbols: Vec<Symbol> = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                if let Some(parent) = entry.parent {
+                    parents.push((idx, parent));
+                }
+                let source = &text[entry.start..entry.end];
+                let first_line = source.lines().next().unwrap_or_default().trim();
+                Symbol {
+                    name: entry.name.clone(),
+                    node_kind: if entry.is_table {
+                        Symbol::CONFIG_TABLE_KIND
+                    } else {
+                        Symbol::CONFIG_KEY_KIND
+                    }
+                    .into(),
+                    file_path: shared_path.clone(),
+                    line_number: line_starts.partition_point(|&start| start <= entry.start),
+                    start_byte: entry.start,
+                    end_byte: entry.end,
+                    visibility: Visibility::Private,
+                    attributes: Vec::new(),
+                    signature: truncate(first_line, 120),
+                    doc: None,
+                    body_hash: hash_bytes(source.as_bytes()),
+                    parent: None,
+                    references: HashSet::new(),
+                    literals: BTreeSet::new(),
+                    blame: None,
+                    metrics: None,
+                    dependencies: HashSet::new(),
+                    used_by: HashSet::new(),
+                }
+            })
+            .collect();
+        openapi::mark_endpoints(&mut symbols, &parents);
+        docker::mark_compose_services(file_path, &text, &mut symbols, &parents);
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents,
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Splits `text` into (byte offset, line without terminator) pairs.
+fn lines_with_offsets(text: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+    text.split_inclusive('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len();
+            (start, line.trim_end_matches(['\n', '\r']))
+        })
+        .collect()
+}
+
+/// Strips a trailing `#` comment that isn't inside a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '#') => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn unquote(key: &str) -> String {
+    key.trim().trim_matches(['"', '\'']).to_string()
+}
+
+/// Tables (`[a.b]`, `[[a]]`) and `key = value` lines of a TOML file.
+fn scan_toml(text: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut table: Option<usize> = None;
+    // The last key, which ends where the next key or table starts
+    let mut open_key: Option<usize> = None;
+    // Closing delimiter of a multi-line string or the bracket depth of an array
+    let mut in_string: Option<&str> = None;
+    let mut depth = 0i32;
+
+    let lines = lines_with_offsets(text);
+    for &(start, line) in &lines {
+        if let Some(delimiter) = in_string {
+            if line.contains(delimiter) {
+                in_string = None;
+            }
+            continue;
+        }
+        let content = strip_comment(line).trim();
+        if depth > 0 {
+            depth += bracket_balance(content);
+            continue;
+        }
+        if content.is_empty() {
+            continue;
+        }
+
+        if content.starts_with('[') {
+            let header = content.trim_start_matches('[');
+            let Some(close) = header.find(']') else {
+                continue;
+            };
+            let name = header[..close].trim().to_string();
+            close_entry(&mut entries, open_key.take(), start);
+            close_entry(&mut entries, table.take(), start);
+            table = Some(entries.len());
+            entries.push(Entry {
+                name,
+                start,
+                end: text.len(),
+                parent: None,
+                is_table: true,
+            });
+            continue;
+        }
+
+        let Some((key, value)) = content.split_once('=') else {
+            continue;
+        };
+        close_entry(&mut entries, open_key.take(), start);
+        let value = value.trim();
+        open_key = Some(entries.len());
+        entries.push(Entry {
+            name: unquote(key),
+            start,
+            end: text.len(),
+            parent: table,
+            is_table: value.starts_with('{'),
+        });
+
+        for delimiter in ["\"\"\"", "'''"] {
+            if let Some(rest) = value.strip_prefix(delimiter) {
+                if !rest.contains(delimiter) {
+                    in_string = Some(delimiter);
+                }
+            }
+        }
+        depth = bracket_balance(value).max(0);
+    }
+
+    entries
+}
+
+/// Net count of opening minus closing brackets outside of strings.
+fn bracket_balance(text: &str) -> i32 {
+    let mut balance = 0;
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '[' | '{') => balance += 1,
+            (None, ']' | '}') => balance -= 1,
+            _ => {}
+        }
+    }
+    balance
+}
+
+/// Ends the entry at `idx` (if any) at byte `end`.
+fn close_entry(entries: &mut [Entry], idx: Option<usize>, end: usize) {
+    if let Some(idx) = idx {
+        entries[idx].end = end;
+    }
+}
+
+/// `key:` lines of a YAML file, nested by indentation.
+fn scan_yaml(text: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    // (indent, entry index) of the keys enclosing the current line
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    // Lines indented deeper than this belong to a block scalar (`key: |`)
+    let mut block_indent: Option<usize> = None;
+
+    for (start, line) in lines_with_offsets(text) {
+        let trimmed = line.trim_start();
+        let mut indent = line.len() - trimmed.len();
+        if let Some(block) = block_indent {
+            if trimmed.is_empty() || indent > block {
+                continue;
+            }
+            block_indent = None;
+        }
+
+        let mut content = strip_comment(trimmed).trim_end();
+        if content.is_empty() {
+            continue;
+        }
+        if content == "---" || content == "..." {
+            // A new document: everything open ends here
+            for (_, idx) in stack.drain(..) {
+                entries[idx].end = start;
+            }
+            continue;
+        }
+        // List items nest their keys one level deeper
+        while let Some(rest) = content.strip_prefix("- ") {
+            indent += 2;
+            content = rest.trim_start();
+        }
+
+        let Some((key, value)) = split_yaml_key(content) else {
+            continue;
+        };
+
+        while let Some(&(open_indent, idx)) = stack.last() {
+            if open_indent < indent {
+                break;
+            }
+            entries[idx].end = start;
+            stack.pop();
+        }
+        let parent = stack.last().map(|&(_, idx)| idx);
+        if let Some(parent) = parent {
+            entries[parent].is_table = true;
+        }
+
+        stack.push((indent, entries.len()));
+        entries.push(Entry {
+            name: unquote(key),
+            start,
+            end: text.len(),
+            parent,
+            is_table: false,
+        });
+
+        if value.starts_with('|') || value.starts_with('>') {
+            block_indent = Some(indent);
+        }
+    }
+
+    entries
+}
+
+/// Splits `key: value` (or `key:`) into key and value.
+fn split_yaml_key(content: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+    for (idx, c) in content.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') if idx == 0 => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ':') => {
+                let rest = &content[idx + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    let key = &content[..idx];
+                    // Flow mappings and the like aren't keys we can name
+                    if key.is_empty() || key.starts_with(['{', '[', '&', '*', '!', '?']) {
+                        return None;
+                    }
+                    return Some((key, rest.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Object members of a JSON file, found by a lenient tokenizer.
+fn scan_json(text: &str) -> Vec<Entry> {
+    let mut scanner = JsonScanner {
+        bytes: text.as_bytes(),
+        pos: 0,
+        entries: Vec::new(),
+    };
+    scanner.value(None);
+    scanner.entries
+}
+
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    entries: Vec<Entry>,
+}
+
+impl JsonScanner<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    /// Scans one value; members of objects in it become children of `parent`.
+    fn value(&mut self, parent: Option<usize>) {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.object(parent),
+            Some(b'[') => {
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        None => return,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return;
+                        }
+                        Some(b',') => self.pos += 1,
+                        Some(_) => {
+                            let before = self.pos;
+                            self.value(parent);
+                            if self.pos == before {
+                                self.pos += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            Some(b'"') => {
+                self.string();
+            }
+            Some(_) => {
+                // Numbers, literals, or garbage: up to the next delimiter
+                while self.pos < self.bytes.len() && !b",}]".contains(&self.bytes[self.pos]) {
+                    self.pos += 1;
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn object(&mut self, parent: Option<usize>) {
+        self.pos += 1;
+        loop {
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                None => return,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => self.pos += 1,
+                Some(b'"') => {
+                    let start = self.pos;
+                    let name = self.string();
+                    self.skip_whitespace();
+                    if self.bytes.get(self.pos) == Some(&b':') {
+                        self.pos += 1;
+                    }
+                    self.skip_whitespace();
+                    let is_table = self.bytes.get(self.pos) == Some(&b'{');
+                    let idx = self.entries.len();
+                    self.entries.push(Entry {
+                        name,
+                        start,
+                        end: start,
+                        parent,
+                        is_table,
+                    });
+                    self.value(Some(idx));
+                    self.entries[idx].end = self.pos;
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Scans a string literal, returning its contents with escapes left as is.
+    fn string(&mut self) -> String {
+        let start = self.pos + 1;
+        self.pos += 1;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        let end = self.pos.min(self.bytes.len());
+        self.pos = (self.pos + 1).min(self.bytes.len());
+        String::from_utf8_lossy(&self.bytes[start..end]).to_string()
+    }
+}
@@ -0,0 +1,343 @@
+//! Degraded-mode indexing of languages without a parser.
+//!
+//! Files of a language with no built-in, query-based, or external indexer still
+//! get their definitions into the index: from [universal-ctags] when it is
+//! installed, otherwise from a regex matching common definition keywords (`def`,
+//! `fn`, `func`, `function`, `class`, ...). Either way only definitions, their
+//! line ranges, and their nesting are known; there are no references or imports,
+//! so the symbols have no dependency edges. Files indexed this way are recorded as
+//! low-fidelity in the index and reported by `contextmesh stats`.
+//!
+//! [universal-ctags]: https://ctags.io
+
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, LazyLock};
+
+use super::{Imports, KindFilter, ParsedFile};
+use crate::config::LanguageConfig;
+use crate::errors::ContextMeshError;
+use crate::symbol::{Symbol, Visibility};
+use crate::utils::hash_bytes;
+
+/// File extensions of well-known languages, used when the config doesn't set
+/// `extensions` for them.
+const KNOWN_EXTENSIONS: &[(&str, &[&str])] = &[
+    ("c", &["c", "h"]),
+    ("cpp", &["cc", "cpp", "cxx", "hh", "hpp", "hxx"]),
+    ("csharp", &["cs"]),
+    ("elixir", &["ex", "exs"]),
+    ("go", &["go"]),
+    ("haskell", &["hs"]),
+    ("java", &["java"]),
+    ("javascript", &["js", "mjs", "cjs", "jsx"]),
+    ("kotlin", &["kt", "kts"]),
+    ("lua", &["lua"]),
+    ("ocaml", &["ml", "mli"]),
+    ("perl", &["pl", "pm"]),
+    ("php", &["php"]),
+    ("python", &["py", "pyi", "ipynb"]),
+    ("ruby", &["rb"]),
+    ("scala", &["scala", "sc"]),
+    ("swift", &["swift"]),
+    ("typescript", &["ts", "tsx"]),
+    ("zig", &["zig"]),
+];
+
+/// A definition keyword, after any modifiers, followed by the defined name and
+/// possibly preceded by a receiver (`func (p Point) Add`).
+static DEFINITION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*((?:(?:export|default|public|private|protected|internal|static|async|pub(?:\([^)]*\))?|abstract|final|open|override|sealed|data|local)\s+)*)(def|defp|fn|func|function|sub|proc|class|struct|interface|enum|trait|type|module|object|record|protocol|impl)\s+(?:\([^)]*\)\s*)?([A-Za-z_$][\w$]*)",
+    )
+    .expect("valid regex")
+});
+
+/// The file extensions of `language` if it is a well-known one.
+pub fn known_extensions(language: &str) -> Option<&'static [&'static str]> {
+    KNOWN_EXTENSIONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(language))
+        .map(|(_, extensions)| *extensions)
+}
+
+/// The well-known language with the file extension `extension`, if any.
+pub fn language_of_extension(extension: &str) -> Option<&'static str> {
+    KNOWN_EXTENSIONS
+        .iter()
+        .find(|(_, extensions)| extensions.contains(&extension))
+        .map(|(name, _)| *name)
+}
+
+/// Indexes the definitions of one language with ctags or the keyword regex.
+pub struct TagsIndexer {
+    language: String,
+    kinds: KindFilter,
+    /// Whether universal-ctags is installed
+    ctags: bool,
+}
+
+/// A line of `ctags --output-format=json` output.
+#[derive(Deserialize)]
+struct Tag {
+    #[serde(rename = "_type")]
+    kind_of_line: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    line: usize,
+    end: Option<usize>,
+    scope: Option<String>,
+    access: Option<String>,
+}
+
+/// A definition found in a file, with 1-based lines.
+struct Definition {
+    name: String,
+    kind: String,
+    line: usize,
+    end_line: usize,
+    /// The enclosing definition's name, if known by name only (ctags scopes)
+    scope: Option<String>,
+    public: bool,
+}
+
+impl TagsIndexer {
+    pub fn new(language: &str, config: &LanguageConfig) -> Self {
+        let ctags = config.ctags != Some(false)
+            && Command::new("ctags")
+                .arg("--version")
+                .output()
+                .is_ok_and(|output| {
+                    output.status.success()
+                        && String::from_utf8_lossy(&output.stdout).contains("Universal Ctags")
+                });
+        TagsIndexer {
+            language: language.to_lowercase(),
+            kinds: KindFilter::from_config(config),
+            ctags,
+        }
+    }
+
+    pub fn language_name(&self) -> &str {
+        &self.language
+    }
+
+    /// What the definitions are found with, for messages.
+    pub fn source(&self) -> &'static str {
+        if self.ctags {
+            "ctags"
+        } else {
+            "a keyword regex"
+        }
+    }
+
+    pub fn parse(&self, file_path: &str, code: &[u8]) -> Result<ParsedFile, ContextMeshError> {
+        let definitions = if self.ctags {
+            run_ctags(file_path, code)?
+        } else {
+            scan_definitions(&String::from_utf8_lossy(code))
+        };
+        Ok(self.convert(file_path, code, definitions))
+    }
+
+    /// The symbols of `definitions`, with byte offsets into `code` itself
+    /// rather than into its lossy decoding, which differ after invalid UTF-8.
+    fn convert(&self, file_path: &str, code: &[u8], definitions: Vec<Definition>) -> ParsedFile {
+        let shared_path: Arc<str> = Arc::from(file_path);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(
+                code.iter()
+                    .enumerate()
+                    .filter(|(_, byte)| **byte == b'\n')
+                    .map(|(pos, _)| pos + 1),
+            )
+            .collect();
+        let line_start = |line: usize| line_starts.get(line - 1).copied().unwrap_or(code.len());
+        let line_end = |line: usize| {
+            line_starts
+                .get(line)
+                .map_or(code.len(), |&next| next - 1)
+                .max(line_start(line))
+        };
+
+        let definitions: Vec<Definition> = definitions
+            .into_iter()
+            .filter(|def| def.line >= 1 && def.line <= line_starts.len())
+            .filter(|def| self.kinds.keeps(&def.kind))
+            .collect();
+
+        let mut symbols = Vec::new();
+        for def in &definitions {
+            let start_byte = line_start(def.line);
+            let end_byte = line_end(def.end_line.clamp(def.line, line_starts.len()));
+            symbols.push(Symbol {
+                name: def.name.clone(),
+                node_kind: def.kind.as_str().into(),
+                file_path: shared_path.clone(),
+                line_number: def.line,
+                start_byte,
+                end_byte,
+                visibility: if def.public {
+                    Visibility::Public
+                } else {
+                    Visibility::Private
+                },
+                attributes: Vec::new(),
+                signature: String::from_utf8_lossy(&code[start_byte..line_end(def.line)])
+                    .trim()
+                    .to_string(),
+                doc: None,
+                body_hash: hash_bytes(&code[start_byte..end_byte]),
+                parent: None,
+                references: HashSet::new(),
+                literals: BTreeSet::new(),
+                blame: None,
+                metrics: None,
+                dependencies: HashSet::new(),
+                used_by: HashSet::new(),
+            });
+        }
+
+        // The innermost other definition whose lines enclose each one, named like
+        // its ctags scope if it has one
+        let parents = definitions
+            .iter()
+            .enumerate()
+            .filter_map(|(child, def)| {
+                let enclosing = |other: &(usize, &Definition)| {
+                    other.0 != child
+                        && other.1.line <= def.line
+                        && def.end_line <= other.1.end_line
+                        && (other.1.line, other.1.end_line) != (def.line, def.end_line)
+                };
+                let candidates = definitions.iter().enumerate().filter(enclosing);
+                let parent = match &def.scope {
+                    Some(scope) => candidates
+                        .filter(|(_, other)| {
+                            scope.rsplit(['.', ':']).next() == Some(other.name.as_str())
+                        })
+                        .max_by_key(|(_, other)| other.line),
+                    None => candidates.max_by_key(|(_, other)| other.line),
+                }?;
+                Some((child, parent.0))
+            })
+            .collect();
+
+        ParsedFile {
+            symbols,
+            imports: Imports::default(),
+            parents,
+            error_nodes: 0,
+            todos: Vec::new(),
+        }
+    }
+}
+
+/// Runs ctags on a copy of `code` (which may differ from the file, e.g. the
+/// script of a notebook) whose name ends like `file_path`, so ctags picks the
+/// language from its extension. The copy is a fresh temporary file only this
+/// process can open, removed afterwards.
+fn run_ctags(file_path: &str, code: &[u8]) -> Result<Vec<Definition>, ContextMeshError> {
+    let file_name = Path::new(file_path)
+        .file_name()
+        .map_or_else(|| "source".into(), |name| name.to_string_lossy());
+    let mut copy = tempfile::Builder::new()
+        .prefix("contextmesh-")
+        .suffix(&format!("-{}", file_name))
+        .tempfile()?;
+    copy.write_all(code)?;
+    copy.flush()?;
+    let output = Command::new("ctags")
+        .args(["--output-format=json", "--fields=+nea", "-f", "-"])
+        .arg(copy.path())
+        .output();
+    drop(copy);
+    let output = output
+        .map_err(|e| ContextMeshError::PluginError(format!("Failed to run 'ctags': {}", e)))?;
+    if !output.status.success() {
+        return Err(ContextMeshError::PluginError(format!(
+            "'ctags' failed on '{}' ({}): {}",
+            file_path,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let mut definitions = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let tag: Tag = serde_json::from_str(line).map_err(|e| {
+            ContextMeshError::PluginError(format!(
+                "'ctags' printed invalid output for '{}': {}",
+                file_path, e
+            ))
+        })?;
+        if tag.kind_of_line != "tag" || tag.line == 0 {
+            continue;
+        }
+        definitions.push(Definition {
+            end_line: tag.end.unwrap_or(tag.line),
+            public: !matches!(tag.access.as_deref(), Some("private" | "protected")),
+            name: tag.name,
+            kind: tag.kind,
+            line: tag.line,
+            scope: tag.scope,
+        });
+    }
+    Ok(definitions)
+}
+
+/// Finds definitions by keyword. A definition ends before the next non-blank
+/// line indented no deeper than it, or on that line if it only closes the
+/// definition (`}`, `end`, ...).
+fn scan_definitions(text: &str) -> Vec<Definition> {
+    let lines: Vec<&str> = text.lines().collect();
+    let indent = |line: &str| line.len() - line.trim_start().len();
+
+    let mut definitions = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(captures) = DEFINITION.captures(line) else {
+            continue;
+        };
+        let modifiers = &captures[1];
+        let kind = match &captures[2] {
+            "def" | "defp" | "fn" | "func" | "function" | "sub" | "proc" => "function",
+            keyword => keyword,
+        };
+        let name = &captures[3];
+
+        let own_indent = indent(line);
+        let mut end = i;
+        for (j, next) in lines.iter().enumerate().skip(i + 1) {
+            let trimmed = next.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if indent(next) <= own_indent {
+                if trimmed.starts_with(['}', ')', ']']) || trimmed == "end" {
+                    end = j;
+                }
+                break;
+            }
+            end = j;
+        }
+
+        definitions.push(Definition {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            line: i + 1,
+            end_line: end + 1,
+            scope: None,
+            public: !(modifiers.contains("private")
+                || captures[2].eq("defp")
+                || (name.starts_with('_') && !name.ends_with("__"))),
+        });
+    }
+    definitions
+}
@@ -0,0 +1,73 @@
+//! Harvesting of TODO/FIXME/HACK/XXX comments.
+//!
+//! The scan is textual and language-agnostic: a tag counts when it directly follows
+//! a comment marker (`//`, `#`, `--`, `/*`, `*`, `<!--`, `;`), so it works the same
+//! for every indexer and ignores tags that merely appear in code or prose.
+
+use serde::{Deserialize, Serialize};
+
+/// Tags that mark a comment as known debt.
+pub const TAGS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+pub(super) const COMMENT_MARKERS: &[&str] = &["//", "//!", "#", "--", "/*", "*", "<!--", ";"];
+
+/// A TODO-style comment found in a source file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Todo {
+    /// `TODO`, `FIXME`, `HACK`, or `XXX`
+    pub tag: String,
+
+    /// The rest of the comment line, e.g. `(alice) handle retries`.
+    pub text: String,
+
+    pub line_number: usize,
+
+    /// Byte offset of the tag, used to find the enclosing symbol.
+    pub byte: usize,
+}
+
+/// Finds the TODO-style comments in `code`.
+pub fn scan(code: &[u8]) -> Vec<Todo> {
+    let text = String::from_utf8_lossy(code);
+    let mut todos = Vec::new();
+    let mut offset = 0;
+
+    for (idx, line) in text.split_inclusive('\n').enumerate() {
+        if let Some((column, tag)) = find_tag(line) {
+            let rest = &line[column + tag.len()..];
+            let text = rest
+                .trim()
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim_start_matches(':')
+                .trim();
+            todos.push(Todo {
+                tag: tag.to_string(),
+                text: text.to_string(),
+                line_number: idx + 1,
+                byte: offset + column,
+            });
+        }
+        offset += line.len();
+    }
+
+    todos
+}
+
+/// The first tag on `line` that starts a comment, with its byte position.
+fn find_tag(line: &str) -> Option<(usize, &'static str)> {
+    TAGS.iter()
+        .filter_map(|tag| {
+            line.match_indices(tag).find_map(|(column, _)| {
+                let before = line[..column].trim_end();
+                let after = line[column + tag.len()..].chars().next();
+                // `TODO:`, `TODO(alice)`, `TODO fix`, but not `TODO/FIXME` or `TODOS`
+                let is_word = after.is_none_or(|c| c.is_whitespace() || ":(-!".contains(c));
+                let in_comment = COMMENT_MARKERS
+                    .iter()
+                    .any(|marker| before.ends_with(marker));
+                (is_word && in_comment).then_some((column, *tag))
+            })
+        })
+        .min()
+}
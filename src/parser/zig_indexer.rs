@@ -0,0 +1,274 @@
+use crate::errors::ContextMeshError;
+use crate::symbol::Visibility;
+
+use super::language::LanguageIndexer;
+use super::Imports;
+use tree_sitter::Node;
+
+/// Zig-specific implementation of the `LanguageIndexer` trait, for the
+/// [tree-sitter-zig] grammar.
+///
+/// Zig has no declaration keywords for types: a struct, enum, or union is an
+/// anonymous container assigned to a `const`. The container becomes the symbol,
+/// named after that `const`, while other `const` and `var` declarations outside
+/// function bodies become symbols of their own. Declarations of the form
+/// `const name = @import("path")` are imports rather than symbols.
+///
+/// [tree-sitter-zig]: https://github.com/tree-sitter-grammars/tree-sitter-zig
+pub struct ZigIndexer;
+
+/// Node kinds of the anonymous containers that `const` declarations name.
+const CONTAINER_KINDS: &[&str] = &[
+    "struct_declaration",
+    "enum_declaration",
+    "union_declaration",
+    "opaque_declaration",
+];
+
+impl LanguageIndexer for ZigIndexer {
+    fn language_name(&self) -> &'static str {
+        "zig"
+    }
+
+    fn allowed_definition_kinds(&self) -> &'static [&'static str] {
+        &[
+            "function_declaration",
+            "variable_declaration",
+            "struct_declaration",
+            "enum_declaration",
+            "union_declaration",
+            "opaque_declaration",
+        ]
+    }
+
+    fn type_definition_kinds(&self) -> &'static [&'static str] {
+        CONTAINER_KINDS
+    }
+
+    /// Functions are named by their `name` field, containers by the declaration
+    /// they are assigned to. Local variables, imports, and declarations naming a
+    /// container (which is the symbol instead) are skipped.
+    fn build_qualified_name(&self, node: Node, code: &[u8]) -> Result<String, ContextMeshError> {
+        let skip = |reason: &str| Err(ContextMeshError::DeserializationError(reason.to_string()));
+        let name_node = match node.kind() {
+            "variable_declaration" => {
+                if in_function_body(node) {
+                    return skip("Skipping local variable.");
+                }
+                let value = declared_value(node);
+                if value.is_some_and(|value| CONTAINER_KINDS.contains(&value.kind())) {
+                    return skip("Skipping declaration of a container.");
+                }
+                if value.and_then(|value| imported_path(value, code)).is_some() {
+                    return skip("Skipping import.");
+                }
+                declared_name(node)
+            }
+            kind if CONTAINER_KINDS.contains(&kind) => node
+                .parent()
+                .filter(|parent| parent.kind() == "variable_declaration")
+                .and_then(declared_name),
+            _ => node.child_by_field_name("name"),
+        };
+        match name_node {
+            Some(name_node) => Ok(node_text(name_node, code)?.to_string()),
+            None => skip("Skipping anonymous item."),
+        }
+    }
+
+    /// `pub` declarations are public, everything else is private to its file.
+    fn extract_visibility(&self, node: Node, _code: &[u8]) -> Visibility {
+        let declaration = declaration_of(node);
+        let public = declaration
+            .children(&mut declaration.walk())
+            .any(|child| child.kind() == "pub");
+        if public {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        }
+    }
+
+    /// The declaration up to its body; for a container, up to its fields, e.g.
+    /// `pub const Point = struct`.
+    fn extract_signature(&self, node: Node, code: &[u8]) -> String {
+        let declaration = declaration_of(node);
+        let end = node
+            .child_by_field_name("body")
+            .map(|body| body.start_byte())
+            .or_else(|| {
+                node.children(&mut node.walk())
+                    .find(|child| child.kind() == "{")
+                    .map(|brace| brace.start_byte())
+            })
+            .unwrap_or(node.end_byte());
+        String::from_utf8_lossy(&code[declaration.start_byte()..end])
+            .trim()
+            .to_string()
+    }
+
+    /// Collects the `///` comments directly preceding a declaration.
+    fn extract_doc_comment(&self, node: Node, code: &[u8]) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut sibling = declaration_of(node).prev_sibling();
+        while let Some(prev) = sibling {
+            if prev.kind() != "comment" {
+                break;
+            }
+            match doc_comment_line(prev.utf8_text(code).ok()?) {
+                Some(doc) => lines.push(doc.to_string()),
+                None => break,
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        // Restore source order
+        lines.reverse();
+        let doc = lines.join("\n").trim().to_string();
+        (!doc.is_empty()).then_some(doc)
+    }
+
+    /// Records `const name = @import("path")` declarations, with the file's path
+    /// written as a module path (`@import("net/http.zig")` is `net::http`).
+    fn process_import_declaration(
+        &self,
+        node: Node,
+        code: &[u8],
+        imports: &mut Imports,
+    ) -> Result<(), ContextMeshError> {
+        if node.kind() != "variable_declaration" {
+            return Ok(());
+        }
+        let Some(path) = declared_value(node).and_then(|value| imported_path(value, code)) else {
+            return Ok(());
+        };
+        if let Some(name_node) = declared_name(node) {
+            imports
+                .aliases
+                .insert(node_text(name_node, code)?.to_string(), module_path(&path));
+        }
+        Ok(())
+    }
+
+    /// Calls name a function directly (`parse(...)`) or as a member of a value,
+    /// type, or imported file (`list.append(...)`, `math.add(...)`), which is
+    /// resolved by the member's name.
+    fn extract_callable_name(
+        &self,
+        node: Node,
+        code: &[u8],
+        _imports: &Imports,
+    ) -> Result<String, ContextMeshError> {
+        match node.kind() {
+            "identifier" => Ok(node_text(node, code)?.to_string()),
+            "field_expression" => match node.child_by_field_name("member") {
+                Some(member) => Ok(node_text(member, code)?.to_string()),
+                None => Ok(String::new()),
+            },
+            _ => Ok(String::new()),
+        }
+    }
+
+    /// Handles `"..."` string literals; escapes are kept as written.
+    fn extract_string_literal(&self, node: Node, code: &[u8]) -> Option<String> {
+        if node.kind() != "string" {
+            return None;
+        }
+        let text = node_text(node, code).ok()?;
+        Some(text.strip_prefix('"')?.strip_suffix('"')?.to_string())
+    }
+
+    /// Zig files are modules of their own; there are no nested module scopes.
+    fn enter_module(
+        &self,
+        _node: Node,
+        _code: &[u8],
+        _current_module: &mut Vec<String>,
+    ) -> Result<(), ContextMeshError> {
+        Ok(())
+    }
+
+    fn exit_module(&self, _current_module: &mut Vec<String>) -> Result<(), ContextMeshError> {
+        Ok(())
+    }
+}
+
+/// The text of a `///` doc comment line, or `None` for any other comment. `////`
+/// is an ordinary comment, not documentation.
+pub fn doc_comment_line(comment: &str) -> Option<&str> {
+    let doc = comment.strip_prefix("///")?;
+    if doc.starts_with('/') {
+        return None;
+    }
+    Some(doc.strip_prefix(' ').unwrap_or(doc).trim_end())
+}
+
+/// The module path of an imported file, e.g. `net::http` for `net/http.zig`.
+pub fn module_path(import: &str) -> String {
+    import.trim_end_matches(".zig").replace('/', "::")
+}
+
+/// The `const`/`var` declaration a container is assigned to, or the node itself.
+fn declaration_of(node: Node) -> Node {
+    match node.parent() {
+        Some(parent)
+            if CONTAINER_KINDS.contains(&node.kind())
+                && parent.kind() == "variable_declaration" =>
+        {
+            parent
+        }
+        _ => node,
+    }
+}
+
+/// The identifier a `const`/`var` declaration declares.
+fn declared_name(node: Node) -> Option<Node> {
+    node.children(&mut node.walk())
+        .find(|child| child.kind() == "identifier")
+}
+
+/// The expression a `const`/`var` declaration is initialized with.
+fn declared_value(node: Node) -> Option<Node> {
+    let mut cursor = node.walk();
+    let mut children = node.children(&mut cursor);
+    children.find(|child| child.kind() == "=")?;
+    children.find(|child| child.is_named())
+}
+
+/// The path of an `@import("...")` expression.
+fn imported_path(node: Node, code: &[u8]) -> Option<String> {
+    if node.kind() != "builtin_function" {
+        return None;
+    }
+    let builtin = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "builtin_identifier")?;
+    if builtin.utf8_text(code).ok()? != "@import" {
+        return None;
+    }
+    let arguments = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "arguments")?;
+    let path = arguments
+        .named_children(&mut arguments.walk())
+        .find(|child| child.kind() == "string")?;
+    Some(path.utf8_text(code).ok()?.trim_matches('"').to_string())
+}
+
+/// Whether a node is inside the body of a function, where declarations are locals.
+fn in_function_body(node: Node) -> bool {
+    let mut ancestor = node.parent();
+    while let Some(current) = ancestor {
+        if current.kind() == "block" {
+            return true;
+        }
+        ancestor = current.parent();
+    }
+    false
+}
+
+fn node_text<'a>(node: Node, code: &'a [u8]) -> Result<&'a str, ContextMeshError> {
+    node.utf8_text(code).map_err(|_| {
+        ContextMeshError::DeserializationError(format!("Failed to extract {} text.", node.kind()))
+    })
+}
@@ -0,0 +1,98 @@
+//! Named indexes ("profiles") and snapshots of them under `.contextmesh/`.
+//!
+//! The default profile is `.contextmesh/index.bin`; every other profile lives in
+//! `.contextmesh/profiles/<name>/index.bin`, so e.g. an index including
+//! dependencies can be kept next to the fast one. Snapshots are copies of a
+//! profile's index in `.contextmesh/snapshots/<name>.bin` that can be restored
+//! into any profile.
+//!
+//! An index path given explicitly (`--index-path`) replaces the profile's, e.g.
+//! to keep the index in a CI cache directory.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::errors::ContextMeshError;
+
+/// Name of the profile used when none is selected.
+pub const DEFAULT_PROFILE: &str = "default";
+
+const DEFAULT_INDEX_PATH: &str = ".contextmesh/index.bin";
+const PROFILES_DIR: &str = ".contextmesh/profiles";
+const SNAPSHOTS_DIR: &str = ".contextmesh/snapshots";
+
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+static INDEX_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Makes every command of this process use the index of profile `name`. Must be
+/// called before any index is loaded; only the first call has an effect.
+pub fn select(name: &str) -> Result<(), ContextMeshError> {
+    validate_name(name)?;
+    let _ = ACTIVE_PROFILE.set(name.to_string());
+    Ok(())
+}
+
+/// Makes every command of this process use the index at `path` instead of the
+/// profile's. Like [`select`], only the first call has an effect.
+pub fn set_index_path(path: PathBuf) {
+    let _ = INDEX_PATH.set(path);
+}
+
+/// The selected profile.
+pub fn active() -> &'static str {
+    ACTIVE_PROFILE
+        .get()
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_PROFILE)
+}
+
+/// Where the index of the selected profile, or the one set with
+/// [`set_index_path`], is stored.
+pub fn index_path() -> PathBuf {
+    if let Some(path) = INDEX_PATH.get() {
+        return path.clone();
+    }
+    match active() {
+        DEFAULT_PROFILE => PathBuf::from(DEFAULT_INDEX_PATH),
+        name => PathBuf::from(PROFILES_DIR).join(name).join("index.bin"),
+    }
+}
+
+/// Where the snapshot `name` is stored.
+pub fn snapshot_path(name: &str) -> Result<PathBuf, ContextMeshError> {
+    validate_name(name)?;
+    Ok(PathBuf::from(SNAPSHOTS_DIR).join(format!("{}.bin", name)))
+}
+
+/// The names of the saved snapshots, sorted.
+pub fn snapshot_names() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(SNAPSHOTS_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            (path.extension()? == "bin").then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Profile and snapshot names become path components, so they are restricted to
+/// characters that can't escape `.contextmesh/`.
+fn validate_name(name: &str) -> Result<(), ContextMeshError> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(ContextMeshError::ProfileError(format!(
+            "Invalid name '{}': use letters, digits, '-', '_', and '.'",
+            name
+        )))
+    }
+}
@@ -0,0 +1,511 @@
+//! The query language of `contextmesh query`: predicates over symbols combined
+//! with `and`, `or`, and `not`, and traversals of the dependency graph.
+//!
+//! ```text
+//! kind:function file:src/index/**        predicates next to each other must all hold
+//! name:/^load_/ or name:"save_index"     a regex or an exact name (`*` is a wildcard)
+//! users(depth<=2) of name:save_index     users up to two hops away
+//! deps(*) of name:main and not vis:pub   everything `main` needs, transitively
+//! deps(*, confidence>=imported) of name:main   ...leaving out name-only guesses
+//! ```
+//!
+//! Predicates are `kind:` (node kind, `_item` may be left out), `file:` (a file
+//! glob), `name:`, `vis:` (`pub`, `crate`, `private`, or `restricted`), and
+//! `target:` (code of a Bazel or Buck target and its direct deps, e.g.
+//! `target:"//services/foo:lib"` or `target:services/foo`).
+//! Traversals take `depth<=N`, `depth=N`, or `*`; the default is one hop. They
+//! follow every edge unless given `confidence>=` the least sure kind of edge to
+//! follow (`name-only`, `imported`, or `exact`), after the depth if both are given.
+
+use clap::ValueEnum;
+use regex::Regex;
+use std::collections::{HashSet, VecDeque};
+
+use crate::arch::path_matches;
+use crate::build_targets::absolute_label;
+use crate::errors::ContextMeshError;
+use crate::index::{EdgeConfidence, Index};
+use crate::symbol::{Symbol, Visibility};
+
+/// A parsed query.
+#[derive(Debug, Clone)]
+pub enum Query {
+    Kind(String),
+    File(String),
+    Name(Regex),
+    Visibility(String),
+    /// An absolute build target label
+    Target(String),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    /// The symbols reached from those of `of` along the edges of `direction`
+    /// that are at least as sure as `confidence`, not counting the starting
+    /// symbols themselves.
+    Traverse {
+        direction: Direction,
+        depth: Depth,
+        confidence: EdgeConfidence,
+        of: Box<Query>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Along `used_by`
+    Users,
+    /// Along `dependencies`
+    Deps,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Depth {
+    AtMost(usize),
+    Exactly(usize),
+    Unlimited,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, ContextMeshError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let query = parser.or()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(token) => Err(syntax_error(format!("unexpected {}", token))),
+        }
+    }
+
+    /// The hashes of the symbols of `index` the query selects.
+    pub fn evaluate<'a>(&self, index: &'a Index) -> HashSet<&'a str> {
+        match self {
+            Query::And(a, b) => {
+                let a = a.evaluate(index);
+                b.evaluate(index).intersection(&a).copied().collect()
+            }
+            Query::Or(a, b) => {
+                let mut a = a.evaluate(index);
+                a.extend(b.evaluate(index));
+                a
+            }
+            Query::Not(query) => {
+                let excluded = query.evaluate(index);
+                index
+                    .symbols
+                    .keys()
+                    .map(String::as_str)
+                    .filter(|hash| !excluded.contains(hash))
+                    .collect()
+            }
+            Query::Traverse {
+                direction,
+                depth,
+                confidence,
+                of,
+            } => traverse(index, &of.evaluate(index), *direction, *depth, *confidence),
+            Query::Target(label) => {
+                let files = index.target_files(label).unwrap_or_default();
+                index
+                    .symbols
+                    .iter()
+                    .filter(|(_, sym)| files.contains(&*sym.file_path))
+                    .map(|(hash, _)| hash.as_str())
+                    .collect()
+            }
+            predicate => index
+                .symbols
+                .iter()
+                .filter(|(_, sym)| predicate.matches(sym))
+                .map(|(hash, _)| hash.as_str())
+                .collect(),
+        }
+    }
+
+    /// Whether `sym` satisfies a predicate; `false` for other queries.
+    fn matches(&self, sym: &Symbol) -> bool {
+        match self {
+            Query::Kind(kind) => {
+                *sym.node_kind == **kind || sym.node_kind.strip_suffix("_item") == Some(kind)
+            }
+            Query::File(glob) => path_matches(glob, &sym.file_path),
+            Query::Name(regex) => regex.is_match(&sym.name),
+            Query::Visibility(visibility) => {
+                let actual = match sym.visibility {
+                    Visibility::Public => "pub",
+                    Visibility::Crate => "crate",
+                    Visibility::Private => "private",
+                    Visibility::Restricted(_) => "restricted",
+                };
+                actual == visibility
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Breadth-first search from `start` along edges at least as sure as
+/// `confidence`, returning the symbols first reached within (or, for
+/// [`Depth::Exactly`], at) `depth` hops.
+fn traverse<'a>(
+    index: &'a Index,
+    start: &HashSet<&'a str>,
+    direction: Direction,
+    depth: Depth,
+    confidence: EdgeConfidence,
+) -> HashSet<&'a str> {
+    let max = match depth {
+        Depth::AtMost(max) | Depth::Exactly(max) => max,
+        Depth::Unlimited => usize::MAX,
+    };
+    let mut visited: HashSet<&str> = start.clone();
+    let mut reached = HashSet::new();
+    let mut queue: VecDeque<(&str, usize)> = start.iter().map(|hash| (*hash, 0)).collect();
+
+    while let Some((hash, distance)) = queue.pop_front() {
+        if distance == max {
+            continue;
+        }
+        let Some(sym) = index.symbols.get(hash) else {
+            continue;
+        };
+        let edges = match direction {
+            Direction::Users => &sym.used_by,
+            Direction::Deps => &sym.dependencies,
+        };
+        for next in edges.iter().filter_map(|id| index.hash_of(*id)) {
+            let (user, dep) = match direction {
+                Direction::Users => (next, hash),
+                Direction::Deps => (hash, next),
+            };
+            if !index.symbols.contains_key(next)
+                || index.edge_confidence(user, dep) < confidence
+                || !visited.insert(next)
+            {
+                continue;
+            }
+            if depth != Depth::Exactly(max) || distance + 1 == max {
+                reached.insert(next);
+            }
+            queue.push_back((next, distance + 1));
+        }
+    }
+    reached
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    /// The value of a predicate: a quoted string or a bare word
+    Value(String),
+    Regex(String),
+    Colon,
+    LeftParen,
+    RightParen,
+    LessEqual,
+    GreaterEqual,
+    Equal,
+    Star,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Value(value) => write!(f, "\"{}\"", value),
+            Token::Regex(regex) => write!(f, "/{}/", regex),
+            Token::Colon => write!(f, "':'"),
+            Token::LeftParen => write!(f, "'('"),
+            Token::RightParen => write!(f, "')'"),
+            Token::LessEqual => write!(f, "'<='"),
+            Token::GreaterEqual => write!(f, "'>='"),
+            Token::Equal => write!(f, "'='"),
+            Token::Star => write!(f, "'*'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ContextMeshError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        // Values after `:` may contain characters that are tokens elsewhere, e.g.
+        // the `/` and `*` of file globs
+        if tokens.last() == Some(&Token::Colon) {
+            tokens.push(match c {
+                '"' | '/' => {
+                    chars.next();
+                    let text = delimited(&mut chars, c)?;
+                    if c == '"' {
+                        Token::Value(text)
+                    } else {
+                        Token::Regex(text)
+                    }
+                }
+                _ => {
+                    let mut value = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == ')' {
+                            break;
+                        }
+                        value.push(c);
+                        chars.next();
+                    }
+                    Token::Value(value)
+                }
+            });
+            continue;
+        }
+
+        chars.next();
+        tokens.push(match c {
+            ':' => Token::Colon,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '=' => Token::Equal,
+            '*' => Token::Star,
+            ',' => Token::Comma,
+            '<' if chars.peek() == Some(&'=') => {
+                chars.next();
+                Token::LessEqual
+            }
+            '>' if chars.peek() == Some(&'=') => {
+                chars.next();
+                Token::GreaterEqual
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                // Words may contain dashes, e.g. `name-only`
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '-') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+            other => return Err(syntax_error(format!("unexpected '{}'", other))),
+        });
+    }
+    Ok(tokens)
+}
+
+/// The text up to the next unescaped `end`, which is consumed. `\` escapes `end`
+/// and itself; other escapes are kept for the regex syntax.
+fn delimited(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    end: char,
+) -> Result<String, ContextMeshError> {
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped == end || escaped == '\\' && end == '"' => {
+                    text.push(escaped)
+                }
+                Some(escaped) => {
+                    text.push('\\');
+                    text.push(escaped);
+                }
+                None => break,
+            },
+            c if c == end => return Ok(text),
+            c => text.push(c),
+        }
+    }
+    Err(syntax_error(format!("missing closing {}", end)))
+}
+
+/// Recursive descent over the tokens; `or` binds loosest, then `and` (or
+/// nothing between two operands), then `not` and traversals.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == word)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ContextMeshError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(syntax_error(format!(
+                "expected {} but found {}",
+                expected, token
+            ))),
+            None => Err(syntax_error(format!("expected {} at the end", expected))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Query, ContextMeshError> {
+        let mut query = self.and()?;
+        while self.peek_word("or") {
+            self.position += 1;
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, ContextMeshError> {
+        let mut query = self.unary()?;
+        loop {
+            if self.peek_word("and") {
+                self.position += 1;
+            } else if self.peek().is_none()
+                || self.peek_word("or")
+                || self.peek() == Some(&Token::RightParen)
+            {
+                return Ok(query);
+            }
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Query, ContextMeshError> {
+        match self.next() {
+            Some(Token::Word(word)) if word == "not" => Ok(Query::Not(Box::new(self.unary()?))),
+            Some(Token::Word(word)) if word == "users" || word == "deps" => {
+                let direction = if word == "users" {
+                    Direction::Users
+                } else {
+                    Direction::Deps
+                };
+                let mut depth = Depth::AtMost(1);
+                let mut confidence = EdgeConfidence::NameOnly;
+                if self.peek() == Some(&Token::LeftParen) {
+                    self.position += 1;
+                    if !self.peek_word("confidence") {
+                        depth = self.depth()?;
+                        if self.peek() == Some(&Token::Comma) {
+                            self.position += 1;
+                            confidence = self.confidence()?;
+                        }
+                    } else {
+                        confidence = self.confidence()?;
+                    }
+                    self.expect(Token::RightParen)?;
+                }
+                self.expect(Token::Word("of".to_string()))?;
+                Ok(Query::Traverse {
+                    direction,
+                    depth,
+                    confidence,
+                    of: Box::new(self.unary()?),
+                })
+            }
+            Some(Token::Word(field)) => {
+                self.expect(Token::Colon)?;
+                let value = self.next();
+                predicate(&field, value)
+            }
+            Some(Token::LeftParen) => {
+                let query = self.or()?;
+                self.expect(Token::RightParen)?;
+                Ok(query)
+            }
+            Some(token) => Err(syntax_error(format!("unexpected {}", token))),
+            None => Err(syntax_error("unexpected end of the query".to_string())),
+        }
+    }
+
+    fn depth(&mut self) -> Result<Depth, ContextMeshError> {
+        if self.peek() == Some(&Token::Star) {
+            self.position += 1;
+            return Ok(Depth::Unlimited);
+        }
+        self.expect(Token::Word("depth".to_string()))?;
+        let exact = match self.next() {
+            Some(Token::LessEqual) => false,
+            Some(Token::Equal) => true,
+            _ => return Err(syntax_error("expected 'depth<=N' or 'depth=N'".to_string())),
+        };
+        let hops = match self.next() {
+            Some(Token::Word(number)) => number.parse::<usize>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| syntax_error("expected a number of hops".to_string()))?;
+        Ok(if exact {
+            Depth::Exactly(hops)
+        } else {
+            Depth::AtMost(hops)
+        })
+    }
+
+    fn confidence(&mut self) -> Result<EdgeConfidence, ContextMeshError> {
+        self.expect(Token::Word("confidence".to_string()))?;
+        self.expect(Token::GreaterEqual)?;
+        match self.next() {
+            Some(Token::Word(name)) => EdgeConfidence::from_str(&name, false).map_err(|_| {
+                syntax_error(format!(
+                    "unknown confidence '{}', expected 'name-only', 'imported', or 'exact'",
+                    name
+                ))
+            }),
+            _ => Err(syntax_error("expected a confidence".to_string())),
+        }
+    }
+}
+
+fn predicate(field: &str, value: Option<Token>) -> Result<Query, ContextMeshError> {
+    let (text, is_regex) = match value {
+        Some(Token::Value(text)) => (text, false),
+        Some(Token::Regex(text)) => (text, true),
+        _ => return Err(syntax_error(format!("'{}:' needs a value", field))),
+    };
+    if is_regex && field != "name" {
+        return Err(syntax_error(format!("'{}:' doesn't take a regex", field)));
+    }
+    match field {
+        "kind" => Ok(Query::Kind(text)),
+        "file" => Ok(Query::File(text)),
+        "target" => Ok(Query::Target(absolute_label(&text))),
+        "name" => {
+            let pattern = if is_regex {
+                text
+            } else {
+                // Exact, with `*` matching anything
+                let parts: Vec<String> = text.split('*').map(regex::escape).collect();
+                format!("^{}$", parts.join(".*"))
+            };
+            Regex::new(&pattern)
+                .map(Query::Name)
+                .map_err(|e| syntax_error(e.to_string()))
+        }
+        "vis" => match text.as_str() {
+            "pub" | "crate" | "private" | "restricted" => Ok(Query::Visibility(text)),
+            other => Err(syntax_error(format!(
+                "unknown visibility '{}'; use pub, crate, private, or restricted",
+                other
+            ))),
+        },
+        other => Err(syntax_error(format!(
+            "unknown predicate '{}:'; use kind:, file:, name:, vis:, or target:",
+            other
+        ))),
+    }
+}
+
+fn syntax_error(message: String) -> ContextMeshError {
+    ContextMeshError::QueryError(message)
+}
@@ -0,0 +1,126 @@
+//! Masking of secrets in bundles before they are copied to the clipboard or
+//! written out, so API keys and passwords committed by mistake don't end up in an
+//! LLM's context.
+//!
+//! Built-in patterns cover common credential formats (cloud and SaaS API keys,
+//! private keys, JWTs, passwords in URLs) and secret-looking assignments such as
+//! `.env` lines and `api_key = "..."`. The `[redaction]` section of the config
+//! adds patterns or turns built-in ones off. A match is replaced by
+//! `[REDACTED:<pattern>]`; for patterns with a capture group only the group is,
+//! so `DATABASE_PASSWORD=hunter22` keeps its key.
+
+use regex::Regex;
+
+use crate::config::RedactionConfig;
+use crate::errors::ContextMeshError;
+
+/// (name, regex) of the built-in patterns.
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    (
+        "private-key",
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+    ),
+    ("aws-access-key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    (
+        "github-token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+    ),
+    ("slack-token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}"),
+    ("google-api-key", r"\bAIza[0-9A-Za-z_-]{35}"),
+    ("stripe-key", r"\b[rs]k_(?:live|test)_[A-Za-z0-9]{16,}"),
+    ("api-key", r"\bsk-[A-Za-z0-9_-]{20,}"),
+    (
+        "jwt",
+        r"\beyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}",
+    ),
+    (
+        "url-password",
+        r"\b[a-z][a-z0-9+.-]*://[^/\s:@]+:([^/\s@]+)@",
+    ),
+    // `.env` and shell lines, e.g. `export STRIPE_SECRET=...`
+    (
+        "env-secret",
+        r"(?m)^\s*(?:export\s+)?[A-Z0-9_]*(?:SECRET|PASSWORD|PASSWD|TOKEN|API_?KEY|PRIVATE_KEY|ACCESS_KEY|CREDENTIALS?)[A-Z0-9_]*\s*=\s*([^\s#]+)",
+    ),
+    // String literals assigned to secret-looking names in code and config
+    (
+        "secret-assignment",
+        r#"(?i)\b[\w.-]*(?:secret|password|passwd|token|api_?key)[\w.-]*["']?\s*[:=]\s*["']([^"'\s]{8,})["']"#,
+    ),
+];
+
+/// A masked secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redaction {
+    /// Name of the pattern that matched
+    pub pattern: String,
+    /// 1-based line of the text it was on
+    pub line: usize,
+}
+
+/// The enabled patterns of a config.
+pub struct Redactor {
+    patterns: Vec<(String, Regex)>,
+}
+
+impl Redactor {
+    /// The built-in patterns not disabled by `config` and its own patterns, or
+    /// none if redaction is disabled.
+    pub fn from_config(config: &RedactionConfig) -> Result<Redactor, ContextMeshError> {
+        let mut patterns = Vec::new();
+        if !config.enabled {
+            return Ok(Redactor { patterns });
+        }
+        for (name, regex) in BUILTIN_PATTERNS {
+            if !config.disable.iter().any(|disabled| disabled == name) {
+                patterns.push((name.to_string(), Regex::new(regex).expect("valid regex")));
+            }
+        }
+        for pattern in &config.patterns {
+            let regex = Regex::new(&pattern.regex).map_err(|e| {
+                ContextMeshError::ConfigError(format!(
+                    "Invalid regex of redaction pattern '{}': {}",
+                    pattern.name, e
+                ))
+            })?;
+            patterns.push((pattern.name.clone(), regex));
+        }
+        Ok(Redactor { patterns })
+    }
+
+    /// `text` with every match of the patterns masked, and what was masked in
+    /// order of position.
+    pub fn redact(&self, text: &str) -> (String, Vec<Redaction>) {
+        // Byte ranges to mask, with the pattern; earlier patterns win overlaps
+        let mut masked: Vec<(usize, usize, &str)> = Vec::new();
+        for (name, regex) in &self.patterns {
+            for captures in regex.captures_iter(text) {
+                let Some(secret) = captures.get(1).or_else(|| captures.get(0)) else {
+                    continue;
+                };
+                let overlaps = masked
+                    .iter()
+                    .any(|(start, end, _)| secret.start() < *end && *start < secret.end());
+                if !secret.is_empty() && !overlaps {
+                    masked.push((secret.start(), secret.end(), name));
+                }
+            }
+        }
+        masked.sort();
+
+        let mut out = String::with_capacity(text.len());
+        let mut redactions = Vec::new();
+        let mut last = 0;
+        for (start, end, name) in masked {
+            out.push_str(&text[last..start]);
+            out.push_str(&format!("[REDACTED:{}]", name));
+            redactions.push(Redaction {
+                pattern: name.to_string(),
+                line: 1 + text[..start].matches('\n').count(),
+            });
+            last = end;
+        }
+        out.push_str(&text[last..]);
+        (out, redactions)
+    }
+}
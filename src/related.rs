@@ -0,0 +1,150 @@
+//! Ranking of the symbols related to a given one.
+//!
+//! Four signals between 0 and 1 are combined in a weighted sum:
+//!
+//! - graph proximity: `1 / d` for symbols up to [`MAX_DISTANCE`] edges away,
+//!   following dependencies in either direction;
+//! - shared dependencies: the Jaccard similarity of the two dependency sets;
+//! - co-change: how strongly the other symbol's file evolves with the
+//!   symbol's (see [`crate::cochange`]);
+//! - name similarity: the Jaccard similarity of the words of the two names, so
+//!   `save_index` and `IndexSaver` share `index`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::index::Index;
+use crate::output::symbol_order;
+use crate::symbol::{Symbol, SymbolId};
+
+/// How many edges away graph proximity is looked for.
+pub const MAX_DISTANCE: usize = 3;
+
+const PROXIMITY_WEIGHT: f64 = 0.4;
+const SHARED_WEIGHT: f64 = 0.25;
+const CO_CHANGE_WEIGHT: f64 = 0.2;
+const NAME_WEIGHT: f64 = 0.15;
+
+/// A symbol related to the ranked ones, with its score and the signals making
+/// it up.
+pub struct Related<'a> {
+    pub symbol: &'a Symbol,
+    pub score: f64,
+    pub proximity: f64,
+    pub shared_dependencies: f64,
+    pub co_change: f64,
+    pub name_similarity: f64,
+}
+
+/// The code symbols related to the `seeds` (hashes), best first. `co_changes`
+/// maps file paths to their co-change score; see
+/// [`crate::cochange::evolving_with`].
+pub fn rank<'a>(
+    index: &'a Index,
+    seeds: &HashSet<&str>,
+    co_changes: &HashMap<String, f64>,
+) -> Vec<Related<'a>> {
+    let distances = distances(index, seeds);
+    let seed_symbols: Vec<&Symbol> = seeds.iter().filter_map(|h| index.symbols.get(*h)).collect();
+    let seed_dependencies: HashSet<SymbolId> = seed_symbols
+        .iter()
+        .flat_map(|sym| sym.dependencies.iter().copied())
+        .collect();
+    let seed_words: HashSet<String> = seed_symbols
+        .iter()
+        .flat_map(|sym| name_words(&sym.name))
+        .collect();
+
+    let mut related: Vec<Related> = index
+        .symbols
+        .iter()
+        .filter(|(hash, sym)| sym.is_code() && !seeds.contains(hash.as_str()))
+        .filter_map(|(hash, sym)| {
+            let proximity = distances
+                .get(hash.as_str())
+                .map_or(0.0, |&distance| 1.0 / distance as f64);
+            let shared_dependencies = jaccard(&seed_dependencies, &sym.dependencies);
+            let co_change = co_changes
+                .get(sym.file_path.trim_start_matches("./"))
+                .copied()
+                .unwrap_or_default();
+            let name_similarity = jaccard(&seed_words, &name_words(&sym.name));
+            let score = PROXIMITY_WEIGHT * proximity
+                + SHARED_WEIGHT * shared_dependencies
+                + CO_CHANGE_WEIGHT * co_change
+                + NAME_WEIGHT * name_similarity;
+            (score > 0.0).then_some(Related {
+                symbol: sym,
+                score,
+                proximity,
+                shared_dependencies,
+                co_change,
+                name_similarity,
+            })
+        })
+        .collect();
+    related.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| symbol_order(a.symbol, b.symbol))
+    });
+    related
+}
+
+/// Undirected distances from the `seeds` to the symbols up to
+/// [`MAX_DISTANCE`] edges away.
+fn distances<'a>(index: &'a Index, seeds: &HashSet<&str>) -> HashMap<&'a str, usize> {
+    let mut distances: HashMap<&str, usize> = HashMap::new();
+    let mut queue: VecDeque<(&str, usize)> = seeds
+        .iter()
+        .filter_map(|hash| index.symbols.get_key_value(*hash))
+        .map(|(hash, _)| (hash.as_str(), 0))
+        .collect();
+    let mut seen: HashSet<&str> = queue.iter().map(|(hash, _)| *hash).collect();
+    while let Some((hash, distance)) = queue.pop_front() {
+        if distance > 0 {
+            distances.insert(hash, distance);
+        }
+        if distance == MAX_DISTANCE {
+            continue;
+        }
+        let sym = &index.symbols[hash];
+        for id in sym.dependencies.iter().chain(&sym.used_by) {
+            if let Some(next) = index.hash_of(*id) {
+                if index.symbols.contains_key(next) && seen.insert(next) {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// The lowercase words of an identifier in `snake_case` or `CamelCase`.
+pub fn name_words(name: &str) -> HashSet<String> {
+    let mut words = HashSet::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            previous_lower = false;
+            words.insert(std::mem::take(&mut word));
+            continue;
+        }
+        if c.is_uppercase() && previous_lower {
+            words.insert(std::mem::take(&mut word));
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        word.extend(c.to_lowercase());
+    }
+    words.insert(word);
+    words.remove("");
+    words
+}
+
+fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
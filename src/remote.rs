@@ -0,0 +1,281 @@
+//! Remote storage for sharing indexes, used by `contextmesh push` and `pull`.
+//!
+//! An index is uploaded as content-addressed shards (`shards/<sha256>`) plus a
+//! manifest per profile (`manifests/<profile>.json`) listing them together with
+//! the index metadata. Shards that already exist are not uploaded again, and a
+//! pull only downloads the shards that differ from the local index.
+//!
+//! Shards are cut from the uncompressed index where its content says so (see
+//! [`shards`]), and then compressed one by one, so a change to one symbol only
+//! changes the shard around it rather than shifting every later one.
+//!
+//! Transfers go through the usual command line tools, like git access does:
+//! `aws` for `s3://` remotes, `curl` for `http(s)://`, and `ssh` for `ssh://host/path`
+//! and `host:path`. Anything else is a local directory (optionally `file://`).
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ContextMeshError;
+use crate::metadata::IndexMetadata;
+
+/// Smallest and largest size of the shards an index is split into.
+pub const MIN_SHARD_SIZE: usize = 256 * 1024;
+pub const MAX_SHARD_SIZE: usize = 4 * 1024 * 1024;
+
+/// Bits of the rolling hash that are clear at a shard boundary; with 20, one
+/// comes about every MiB after the minimum size.
+const BOUNDARY_MASK: u64 = !(u64::MAX >> 20);
+
+/// Version of the manifest layout: 1 for compressed shards of the uncompressed
+/// index; manifests without one list slices of the index file.
+pub const MANIFEST_FORMAT: u32 = 1;
+
+/// Random values for the rolling hash of [`shards`], one per byte value.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    // splitmix64, so the table is the same in every build
+    let mut table = [0; 256];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Splits `data` into shards at content-defined boundaries: where a rolling
+/// hash of the last 64 bytes has its top bits clear, between [`MIN_SHARD_SIZE`]
+/// and [`MAX_SHARD_SIZE`] apart. Inserting or removing bytes only moves the
+/// boundaries near the change, so the other shards stay the same.
+pub fn shards(data: &[u8]) -> Vec<&[u8]> {
+    let mut shards = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let mut hash: u64 = 0;
+        let mut end = rest.len().min(MAX_SHARD_SIZE);
+        for (i, byte) in rest.iter().enumerate().take(end).skip(MIN_SHARD_SIZE) {
+            hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+            if hash & BOUNDARY_MASK == 0 {
+                end = i + 1;
+                break;
+            }
+        }
+        let (shard, tail) = rest.split_at(end);
+        shards.push(shard);
+        rest = tail;
+    }
+    shards
+}
+
+/// Describes an uploaded index.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// [`MANIFEST_FORMAT`] of the manifest.
+    #[serde(default)]
+    pub format: u32,
+    /// SHA256 of the whole uncompressed index.
+    pub hash: String,
+    /// Size of the uncompressed index in bytes.
+    pub size: usize,
+    /// SHA256 of each uncompressed shard, in order.
+    pub shards: Vec<String>,
+    /// Metadata of the uploaded index, for freshness checks.
+    pub metadata: IndexMetadata,
+}
+
+/// Where indexes are pushed to and pulled from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remote {
+    Dir(PathBuf),
+    Http(String),
+    Ssh { host: String, path: String },
+    S3(String),
+}
+
+impl Remote {
+    pub fn parse(url: &str) -> Result<Self, ContextMeshError> {
+        let url = url.trim_end_matches('/');
+        if url.is_empty() {
+            return Err(ContextMeshError::RemoteError(
+                "Empty remote URL".to_string(),
+            ));
+        }
+
+        if let Some(path) = url.strip_prefix("file://") {
+            return Ok(Remote::Dir(PathBuf::from(path)));
+        }
+        if url.starts_with("s3://") {
+            return Ok(Remote::S3(url.to_string()));
+        }
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return Ok(Remote::Http(url.to_string()));
+        }
+        if let Some(rest) = url.strip_prefix("ssh://") {
+            let (host, path) = rest.split_once('/').ok_or_else(|| {
+                ContextMeshError::RemoteError(format!("Missing path in remote '{}'", url))
+            })?;
+            return Ok(Remote::Ssh {
+                host: ssh_host(host)?,
+                path: format!("/{}", path),
+            });
+        }
+        // scp-like `host:path`, unless the colon is part of a local path
+        if let Some((host, path)) = url.split_once(':') {
+            if !host.is_empty() && !host.contains('/') {
+                return Ok(Remote::Ssh {
+                    host: ssh_host(host)?,
+                    path: path.to_string(),
+                });
+            }
+        }
+        Ok(Remote::Dir(PathBuf::from(url)))
+    }
+
+    /// Reads the object `key`, or `None` if it doesn't exist.
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, ContextMeshError> {
+        if !self.exists(key)? {
+            return Ok(None);
+        }
+        let data = match self {
+            Remote::Dir(root) => std::fs::read(root.join(key))?,
+            Remote::Http(base) => run("curl", &["-fsSL", &format!("{}/{}", base, key)], None)?,
+            Remote::Ssh { host, path } => {
+                let remote_path = format!("{}/{}", path, key);
+                run(
+                    "ssh",
+                    &["--", host, "cat", &shell_quote(&remote_path)],
+                    None,
+                )?
+            }
+            Remote::S3(base) => run(
+                "aws",
+                &["s3", "cp", &format!("{}/{}", base, key), "-"],
+                None,
+            )?,
+        };
+        Ok(Some(data))
+    }
+
+    /// Writes `data` as the object `key`, replacing an existing one.
+    pub fn put(&self, key: &str, data: &[u8]) -> Result<(), ContextMeshError> {
+        match self {
+            Remote::Dir(root) => {
+                let path = root.join(key);
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(path, data)?;
+            }
+            Remote::Http(base) => {
+                run(
+                    "curl",
+                    &["-fsS", "-T", "-", &format!("{}/{}", base, key)],
+                    Some(data),
+                )?;
+            }
+            Remote::Ssh { host, path } => {
+                let remote_path = shell_quote(&format!("{}/{}", path, key));
+                let script = format!("mkdir -p \"$(dirname {0})\" && cat > {0}", remote_path);
+                run("ssh", &["--", host, &script], Some(data))?;
+            }
+            Remote::S3(base) => {
+                run(
+                    "aws",
+                    &["s3", "cp", "-", &format!("{}/{}", base, key)],
+                    Some(data),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the object `key` exists.
+    pub fn exists(&self, key: &str) -> Result<bool, ContextMeshError> {
+        Ok(match self {
+            Remote::Dir(root) => root.join(key).exists(),
+            Remote::Http(base) => {
+                run("curl", &["-fsSI", &format!("{}/{}", base, key)], None).is_ok()
+            }
+            Remote::Ssh { host, path } => {
+                let remote_path = format!("{}/{}", path, key);
+                run(
+                    "ssh",
+                    &["--", host, "test", "-e", &shell_quote(&remote_path)],
+                    None,
+                )
+                .is_ok()
+            }
+            Remote::S3(base) => {
+                run("aws", &["s3", "ls", &format!("{}/{}", base, key)], None).is_ok()
+            }
+        })
+    }
+}
+
+/// Checks the host of an ssh remote, which mustn't pass for an ssh option
+/// (`-oProxyCommand=...`).
+fn ssh_host(host: &str) -> Result<String, ContextMeshError> {
+    if host.is_empty() || host.starts_with('-') {
+        return Err(ContextMeshError::RemoteError(format!(
+            "Invalid ssh host '{}'",
+            host
+        )));
+    }
+    Ok(host.to_string())
+}
+
+/// Key of the manifest of `profile`.
+pub fn manifest_key(profile: &str) -> String {
+    format!("manifests/{}.json", profile)
+}
+
+/// Key of the shard with hash `hash`.
+pub fn shard_key(hash: &str) -> String {
+    format!("shards/{}", hash)
+}
+
+/// Runs `program` with `args`, feeding it `stdin`, and returns its stdout.
+fn run(program: &str, args: &[&str], stdin: Option<&[u8]>) -> Result<Vec<u8>, ContextMeshError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ContextMeshError::RemoteError(format!("Failed to run {}: {}", program, e)))?;
+
+    // A failing program may close stdin early, so its exit status is checked first
+    let written = match (stdin, child.stdin.take()) {
+        (Some(data), Some(mut pipe)) => pipe.write_all(data),
+        _ => Ok(()),
+    };
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(ContextMeshError::RemoteError(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    written?;
+    Ok(output.stdout)
+}
+
+/// Quotes `arg` for the remote shell ssh runs commands in.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
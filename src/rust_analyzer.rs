@@ -0,0 +1,63 @@
+//! Precise resolution of Rust references with rust-analyzer.
+//!
+//! rust-analyzer is driven through its `scip` subcommand, which type-checks the
+//! workspace and writes every definition and reference as a SCIP index. That is
+//! then mapped onto the indexed symbols (see [`ScipIndex::references_in`]),
+//! replacing the name-based edges that can't tell same-named items apart.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::errors::ContextMeshError;
+use crate::scip::ScipIndex;
+
+/// Runs `rust-analyzer scip` on the Cargo workspace containing `path`, writing the
+/// SCIP index to `output`, and returns it.
+pub fn scip_index(path: &Path, output: &Path) -> Result<ScipIndex, ContextMeshError> {
+    let root = workspace_root(path)?;
+
+    let result = Command::new("rust-analyzer")
+        .arg("scip")
+        .arg(&root)
+        .arg("--output")
+        .arg(output)
+        .output()
+        .map_err(|e| ContextMeshError::ToolError(format!("Failed to run rust-analyzer: {}", e)))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(ContextMeshError::ToolError(format!(
+            "rust-analyzer failed: {}",
+            stderr.lines().next().unwrap_or_default()
+        )));
+    }
+
+    let index = ScipIndex::decode(&std::fs::read(output)?)?;
+    let _ = std::fs::remove_file(output);
+    Ok(index)
+}
+
+/// The root of the Cargo workspace containing `path`, from `cargo
+/// locate-project`: the directory of the workspace's root manifest, which for a
+/// member crate is above the crate's own `Cargo.toml`.
+fn workspace_root(path: &Path) -> Result<PathBuf, ContextMeshError> {
+    let path = path.canonicalize()?;
+    let dir = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent().unwrap_or(&path)
+    };
+    let output = Command::new("cargo")
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| ContextMeshError::ToolError(format!("Failed to run cargo: {}", e)))?;
+    if !output.status.success() {
+        return Err(ContextMeshError::ToolError(format!(
+            "No Cargo workspace above {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let manifest = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(manifest.parent().map(Path::to_path_buf).unwrap_or(manifest))
+}
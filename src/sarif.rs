@@ -0,0 +1,84 @@
+//! SARIF 2.1.0 output of findings, for CI annotations and code scanning UIs
+//! such as GitHub code scanning.
+
+use serde_json::{json, Value};
+
+/// A problem at a location in the source.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    /// ID of the rule broken, one of the `rules` passed to [`report`].
+    pub rule_id: &'static str,
+    pub message: String,
+    pub file_path: String,
+    pub line_number: usize,
+    /// Another location involved, e.g. the symbol depended on, with a description.
+    pub related: Option<(String, usize, String)>,
+}
+
+/// A rule findings can refer to: ID, description, and SARIF level (`error`,
+/// `warning`, or `note`).
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub level: &'static str,
+}
+
+/// Builds a SARIF log with one run containing `findings`.
+pub fn report(rules: &[Rule], findings: &[Finding]) -> Value {
+    let rule_descriptors: Vec<Value> = rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": rule.level },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let mut result = json!({
+                "ruleId": finding.rule_id,
+                "message": { "text": finding.message },
+                "locations": [location(&finding.file_path, finding.line_number)],
+            });
+            if let Some(index) = rules.iter().position(|rule| rule.id == finding.rule_id) {
+                result["ruleIndex"] = json!(index);
+                result["level"] = json!(rules[index].level);
+            }
+            if let Some((path, line, description)) = &finding.related {
+                let mut related = location(path, *line);
+                related["id"] = json!(0);
+                related["message"] = json!({ "text": description });
+                result["relatedLocations"] = json!([related]);
+            }
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "contextmesh",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rule_descriptors,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn location(path: &str, line: usize) -> Value {
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": path.trim_start_matches("./") },
+            "region": { "startLine": line.max(1) },
+        }
+    })
+}
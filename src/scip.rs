@@ -0,0 +1,791 @@
+//! SCIP (SCIP Code Intelligence Protocol) indexes, as consumed by Sourcegraph and
+//! other code intelligence tools.
+//!
+//! Only the parts of the schema contextmesh produces or imports are modeled:
+//! documents with definition and reference occurrences, and symbol information
+//! carrying the signature and doc comment. The protobuf wire format is read and
+//! written by hand, as it only takes varints and length-delimited fields.
+//!
+//! Symbols are named `contextmesh cargo <package> . <descriptors>`, where the
+//! descriptors are the module path followed by the enclosing symbols, e.g.
+//! `index/Index#load_index().`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::errors::ContextMeshError;
+use crate::index::Index;
+use crate::parser::docker;
+use crate::symbol::Symbol;
+use crate::utils::{has_extension, module_path, read_source};
+
+/// `SymbolRole.Definition`.
+pub const DEFINITION_ROLE: i32 = 1;
+
+/// `TextEncoding.UTF8`, also used for `PositionEncoding.UTF8CodeUnitOffsetFromLineStart`.
+const UTF8: u64 = 1;
+
+/// What the columns of a document's ranges count, from its `position_encoding`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionEncoding {
+    /// Bytes; also assumed when unspecified, as most indexers use it.
+    #[default]
+    Utf8,
+    /// UTF-16 code units, e.g. from indexers written in TypeScript or Java.
+    Utf16,
+    /// Characters.
+    Utf32,
+}
+
+impl PositionEncoding {
+    fn from_wire(value: u64) -> Self {
+        match value {
+            2 => PositionEncoding::Utf16,
+            3 => PositionEncoding::Utf32,
+            _ => PositionEncoding::Utf8,
+        }
+    }
+
+    fn to_wire(self) -> u64 {
+        match self {
+            PositionEncoding::Utf8 => UTF8,
+            PositionEncoding::Utf16 => 2,
+            PositionEncoding::Utf32 => 3,
+        }
+    }
+
+    /// How many units of this encoding `c` takes.
+    fn units(self, c: char) -> usize {
+        match self {
+            PositionEncoding::Utf8 => c.len_utf8(),
+            PositionEncoding::Utf16 => c.len_utf16(),
+            PositionEncoding::Utf32 => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScipIndex {
+    pub tool_version: String,
+    /// URI of the directory document paths are relative to.
+    pub project_root: String,
+    pub documents: Vec<Document>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Document {
+    pub relative_path: String,
+    pub language: String,
+    pub occurrences: Vec<Occurrence>,
+    pub symbols: Vec<SymbolInformation>,
+    pub position_encoding: PositionEncoding,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Occurrence {
+    /// `[start line, start column, end line, end column]`, zero-based, or three
+    /// elements if the range is on one line.
+    pub range: Vec<i32>,
+    pub symbol: String,
+    pub roles: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolInformation {
+    pub symbol: String,
+    /// Markdown paragraphs: the signature in a code block, then the doc comment.
+    pub documentation: Vec<String>,
+    pub display_name: String,
+    pub enclosing_symbol: Option<String>,
+}
+
+impl ScipIndex {
+    /// Serializes the index to the protobuf wire format of `scip.Index`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut tool_info = Vec::new();
+        put_string(&mut tool_info, 1, "contextmesh");
+        put_string(&mut tool_info, 2, &self.tool_version);
+
+        let mut metadata = Vec::new();
+        put_message(&mut metadata, 2, &tool_info);
+        put_string(&mut metadata, 3, &self.project_root);
+        put_varint_field(&mut metadata, 4, UTF8);
+
+        let mut out = Vec::new();
+        put_message(&mut out, 1, &metadata);
+        for document in &self.documents {
+            put_message(&mut out, 2, &document.encode());
+        }
+        out
+    }
+}
+
+impl ScipIndex {
+    /// Parses a `scip.Index` in the protobuf wire format, ignoring the fields that
+    /// aren't modeled.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ContextMeshError> {
+        let mut index = ScipIndex::default();
+        for field in Fields(bytes) {
+            match field? {
+                (1, Value::Bytes(metadata)) => {
+                    for field in Fields(metadata) {
+                        match field? {
+                            (2, Value::Bytes(tool_info)) => {
+                                for field in Fields(tool_info) {
+                                    if let (2, Value::Bytes(version)) = field? {
+                                        index.tool_version = decode_string(version)?;
+                                    }
+                                }
+                            }
+                            (3, Value::Bytes(root)) => index.project_root = decode_string(root)?,
+                            _ => {}
+                        }
+                    }
+                }
+                (2, Value::Bytes(document)) => index.documents.push(Document::decode(document)?),
+                _ => {}
+            }
+        }
+        Ok(index)
+    }
+}
+
+impl Document {
+    fn decode(bytes: &[u8]) -> Result<Self, ContextMeshError> {
+        let mut document = Document::default();
+        for field in Fields(bytes) {
+            match field? {
+                (1, Value::Bytes(path)) => document.relative_path = decode_string(path)?,
+                (2, Value::Bytes(occurrence)) => {
+                    document.occurrences.push(Occurrence::decode(occurrence)?)
+                }
+                (3, Value::Bytes(info)) => document.symbols.push(SymbolInformation::decode(info)?),
+                (4, Value::Bytes(language)) => document.language = decode_string(language)?,
+                (6, Value::Varint(encoding)) => {
+                    document.position_encoding = PositionEncoding::from_wire(encoding)
+                }
+                _ => {}
+            }
+        }
+        Ok(document)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_string(&mut out, 1, &self.relative_path);
+        for occurrence in &self.occurrences {
+            let mut encoded = Vec::new();
+            let mut range = Vec::new();
+            for value in &occurrence.range {
+                put_varint(&mut range, *value as u64);
+            }
+            put_message(&mut encoded, 1, &range);
+            put_string(&mut encoded, 2, &occurrence.symbol);
+            if occurrence.roles != 0 {
+                put_varint_field(&mut encoded, 3, occurrence.roles as u64);
+            }
+            put_message(&mut out, 2, &encoded);
+        }
+        for info in &self.symbols {
+            let mut encoded = Vec::new();
+            put_string(&mut encoded, 1, &info.symbol);
+            for paragraph in &info.documentation {
+                put_string(&mut encoded, 3, paragraph);
+            }
+            put_string(&mut encoded, 6, &info.display_name);
+            if let Some(enclosing) = &info.enclosing_symbol {
+                put_string(&mut encoded, 8, enclosing);
+            }
+            put_message(&mut out, 3, &encoded);
+        }
+        put_string(&mut out, 4, &self.language);
+        put_varint_field(&mut out, 6, self.position_encoding.to_wire());
+        out
+    }
+}
+
+impl Occurrence {
+    fn decode(bytes: &[u8]) -> Result<Self, ContextMeshError> {
+        let mut occurrence = Occurrence::default();
+        for field in Fields(bytes) {
+            match field? {
+                // Packed, or one element per field from encoders that don't pack
+                (1, Value::Bytes(packed)) => {
+                    let mut rest = packed;
+                    while !rest.is_empty() {
+                        let (value, len) = read_varint(rest)?;
+                        occurrence.range.push(value as i32);
+                        rest = &rest[len..];
+                    }
+                }
+                (1, Value::Varint(value)) => occurrence.range.push(value as i32),
+                (2, Value::Bytes(symbol)) => occurrence.symbol = decode_string(symbol)?,
+                (3, Value::Varint(roles)) => occurrence.roles = roles as i32,
+                _ => {}
+            }
+        }
+        Ok(occurrence)
+    }
+
+    /// Zero-based line and column of the start of the range, or `None` if the
+    /// range is malformed.
+    pub fn start(&self) -> Option<(usize, usize)> {
+        match self.range[..] {
+            [line, column, _] | [line, column, _, _] => {
+                Some((usize::try_from(line).ok()?, usize::try_from(column).ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_definition(&self) -> bool {
+        self.roles & DEFINITION_ROLE != 0
+    }
+}
+
+impl SymbolInformation {
+    fn decode(bytes: &[u8]) -> Result<Self, ContextMeshError> {
+        let mut info = SymbolInformation::default();
+        for field in Fields(bytes) {
+            match field? {
+                (1, Value::Bytes(symbol)) => info.symbol = decode_string(symbol)?,
+                (3, Value::Bytes(paragraph)) => info.documentation.push(decode_string(paragraph)?),
+                (6, Value::Bytes(name)) => info.display_name = decode_string(name)?,
+                (8, Value::Bytes(enclosing)) => {
+                    info.enclosing_symbol = Some(decode_string(enclosing)?)
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// References of a SCIP index mapped onto the symbols of an [`Index`].
+#[derive(Debug, Default)]
+pub struct PreciseReferences {
+    /// Caller hash -> hashes of the symbols it refers to.
+    pub references: HashMap<String, HashSet<String>>,
+    /// Indexed paths of the files the SCIP index covers.
+    pub covered_files: HashSet<String>,
+    /// Number of SCIP definitions matched to indexed symbols.
+    pub definitions: usize,
+}
+
+impl ScipIndex {
+    /// Maps the occurrences of the documents that are also indexed onto the
+    /// symbols of `index`: a definition names the innermost symbol around it with
+    /// the same name, and a reference links the innermost symbol around it to the
+    /// defined one.
+    pub fn references_in(&self, index: &Index) -> PreciseReferences {
+        // Indexed paths are relative to the current directory, possibly with a
+        // leading "./"; document paths are relative to the project root
+        let indexed_paths: HashMap<&str, &str> = index
+            .file_hashes
+            .keys()
+            .map(|path| (path.trim_start_matches("./"), path.as_str()))
+            .collect();
+        let root = self.project_root.strip_prefix("file://").map(PathBuf::from);
+        let cwd = std::env::current_dir()
+            .ok()
+            .and_then(|dir| dir.canonicalize().ok());
+        let documents: Vec<(&str, Vec<u8>, &Document)> = self
+            .documents
+            .iter()
+            .filter_map(|document| {
+                let relative = match (&root, &cwd) {
+                    (Some(root), Some(cwd)) => root
+                        .join(&document.relative_path)
+                        .strip_prefix(cwd)
+                        .ok()?
+                        .to_string_lossy()
+                        .into_owned(),
+                    _ => document.relative_path.clone(),
+                };
+                let path = *indexed_paths.get(relative.as_str())?;
+                let source = std::fs::read(path).ok()?;
+                Some((path, source, document))
+            })
+            .collect();
+
+        let mut definitions: HashMap<&str, String> = HashMap::new();
+        for (path, source, document) in &documents {
+            for occurrence in document.occurrences.iter().filter(|o| o.is_definition()) {
+                let encoding = document.position_encoding;
+                if let Some(hash) = defined_symbol(index, path, source, occurrence, encoding) {
+                    definitions.insert(&occurrence.symbol, hash);
+                }
+            }
+        }
+
+        let mut references: HashMap<String, HashSet<String>> = HashMap::new();
+        for (path, source, document) in &documents {
+            for occurrence in document.occurrences.iter().filter(|o| !o.is_definition()) {
+                let Some(target) = definitions.get(occurrence.symbol.as_str()) else {
+                    continue;
+                };
+                let Some(offset) = occurrence.start().and_then(|(line, column)| {
+                    byte_offset(source, line, column, document.position_encoding)
+                }) else {
+                    continue;
+                };
+                if let Some((caller, _)) = innermost_symbol(index, path, offset) {
+                    if caller != target {
+                        references
+                            .entry(caller.clone())
+                            .or_default()
+                            .insert(target.clone());
+                    }
+                }
+            }
+        }
+
+        PreciseReferences {
+            references,
+            covered_files: documents
+                .iter()
+                .map(|(path, ..)| path.to_string())
+                .collect(),
+            definitions: definitions.len(),
+        }
+    }
+}
+
+/// The indexed symbol a definition occurrence names.
+fn defined_symbol(
+    index: &Index,
+    path: &str,
+    source: &[u8],
+    occurrence: &Occurrence,
+    encoding: PositionEncoding,
+) -> Option<String> {
+    let (line, column) = occurrence.start()?;
+    let offset = byte_offset(source, line, column, encoding)?;
+    let name_len = source[offset..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+    let name = std::str::from_utf8(&source[offset..offset + name_len]).ok()?;
+
+    index
+        .symbols_in_file(path)
+        .filter(|(_, sym)| sym.name == name)
+        .filter(|(_, sym)| contains(sym, offset) || sym.line_number == line + 1)
+        .min_by_key(|(_, sym)| sym.end_byte - sym.start_byte)
+        .map(|(hash, _)| hash.clone())
+}
+
+/// The smallest code symbol of `path` whose source contains `offset`.
+fn innermost_symbol<'a>(
+    index: &'a Index,
+    path: &str,
+    offset: usize,
+) -> Option<(&'a String, &'a Symbol)> {
+    index
+        .symbols_in_file(path)
+        .filter(|(_, sym)| sym.is_code() && contains(sym, offset))
+        .min_by_key(|(_, sym)| sym.end_byte - sym.start_byte)
+}
+
+fn contains(sym: &Symbol, offset: usize) -> bool {
+    sym.start_byte <= offset && offset < sym.end_byte
+}
+
+/// Converts the code symbols of `index` passing `keep` to a SCIP index.
+/// `package_of` names the package owning a file, which becomes part of the symbol
+/// names.
+pub fn from_index(
+    index: &Index,
+    project_root: String,
+    mut package_of: impl FnMut(&str) -> Option<String>,
+    keep: impl Fn(&Symbol) -> bool,
+) -> ScipIndex {
+    let code = |sym: &&Symbol| sym.is_code() && !sym.name.is_empty() && keep(sym);
+    let names: HashMap<&str, String> = index
+        .symbols
+        .iter()
+        .filter(|(_, sym)| code(sym))
+        .map(|(hash, sym)| {
+            let package = package_of(&sym.file_path).unwrap_or_else(|| ".".to_string());
+            (hash.as_str(), symbol_name(index, sym, &package))
+        })
+        .collect();
+
+    let mut by_file: BTreeMap<&str, Vec<(&str, &Symbol)>> = BTreeMap::new();
+    for (hash, sym) in index.symbols.iter().filter(|(_, sym)| code(sym)) {
+        by_file
+            .entry(&sym.file_path)
+            .or_default()
+            .push((hash.as_str(), sym));
+    }
+
+    let documents = by_file
+        .into_iter()
+        .map(|(path, symbols)| {
+            let source = read_source(path).unwrap_or_default();
+            let lines = LineIndex::new(&source);
+            let mut occurrences = HashSet::new();
+            let mut infos = Vec::new();
+
+            for (hash, sym) in symbols {
+                let name = &names[hash];
+                let definition = find_identifier(&source, &sym.name, sym.start_byte, sym.end_byte)
+                    .unwrap_or(sym.start_byte);
+                occurrences.insert(Occurrence {
+                    range: lines.range(definition, definition + sym.name.len()),
+                    symbol: name.clone(),
+                    roles: DEFINITION_ROLE,
+                });
+
+                // Dependency edges don't record where the reference is, so every
+                // mention of the dependency's name in the symbol's source counts
+                for dependency in &sym.dependencies {
+                    let Some(dep_hash) = index.hash_of(*dependency) else {
+                        continue;
+                    };
+                    let (Some(dep_name), Some(dep)) =
+                        (names.get(dep_hash), index.symbol(*dependency))
+                    else {
+                        continue;
+                    };
+                    let mut from = sym.start_byte;
+                    while let Some(start) = find_identifier(&source, &dep.name, from, sym.end_byte)
+                    {
+                        if start != definition {
+                            occurrences.insert(Occurrence {
+                                range: lines.range(start, start + dep.name.len()),
+                                symbol: dep_name.clone(),
+                                roles: 0,
+                            });
+                        }
+                        from = start + dep.name.len();
+                    }
+                }
+
+                let mut documentation =
+                    vec![format!("```{}\n{}\n```", language_of(path), sym.signature)];
+                documentation.extend(sym.doc.clone());
+                infos.push(SymbolInformation {
+                    symbol: name.clone(),
+                    documentation,
+                    display_name: sym.name.clone(),
+                    enclosing_symbol: sym
+                        .parent
+                        .and_then(|id| index.hash_of(id))
+                        .and_then(|hash| names.get(hash))
+                        .cloned(),
+                });
+            }
+
+            let mut occurrences: Vec<Occurrence> = occurrences.into_iter().collect();
+            occurrences.sort();
+            infos.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+            Document {
+                relative_path: path.trim_start_matches("./").to_string(),
+                language: language_of(path).to_string(),
+                occurrences,
+                symbols: infos,
+                position_encoding: PositionEncoding::Utf8,
+            }
+        })
+        .collect();
+
+    ScipIndex {
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        project_root,
+        documents,
+    }
+}
+
+/// The SCIP symbol of `sym`, e.g. `contextmesh cargo mycrate . index/Index#load_index().`.
+pub fn symbol_name(index: &Index, sym: &Symbol, package: &str) -> String {
+    let mut descriptors = String::new();
+    for segment in module_path(&sym.file_path).split("::").skip(1) {
+        descriptors.push_str(&escape(segment));
+        descriptors.push('/');
+    }
+
+    let mut chain = vec![sym];
+    while let Some(parent) = chain
+        .last()
+        .and_then(|s| s.parent)
+        .and_then(|id| index.symbol(id))
+    {
+        chain.push(parent);
+    }
+    for symbol in chain.iter().rev() {
+        descriptors.push_str(&escape(&symbol.name));
+        descriptors.push_str(descriptor_suffix(&symbol.node_kind));
+    }
+
+    format!(
+        "contextmesh cargo {} . {}",
+        escape_package(package),
+        descriptors
+    )
+}
+
+/// The suffix marking the descriptor kind: namespace, type, method, macro, or term.
+fn descriptor_suffix(node_kind: &str) -> &'static str {
+    match node_kind {
+        "mod_item" => "/",
+        "struct_item" | "enum_item" | "union_item" | "trait_item" | "type_item" | "impl_item"
+        | "class_definition" => "#",
+        "function_item"
+        | "function_signature_item"
+        | "function_definition"
+        | "method_declaration" => "().",
+        "macro_definition" => "!",
+        kind if kind == Symbol::PROTO_MESSAGE_KIND
+            || kind == Symbol::PROTO_ENUM_KIND
+            || kind == Symbol::PROTO_SERVICE_KIND
+            || kind == Symbol::SQL_TABLE_KIND =>
+        {
+            "#"
+        }
+        kind if kind == Symbol::PROTO_RPC_KIND => "().",
+        _ => ".",
+    }
+}
+
+/// Backtick-quotes identifiers with characters outside `[A-Za-z0-9_+-$]`.
+fn escape(name: &str) -> String {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_+-$".contains(c))
+    {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+/// Spaces separate the parts of a symbol, so they're doubled in package names.
+fn escape_package(name: &str) -> String {
+    name.replace(' ', "  ")
+}
+
+fn language_of(path: &str) -> &'static str {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    if has_extension(file_name, docker::EXTENSIONS) {
+        return "dockerfile";
+    }
+    match path.rsplit('.').next() {
+        Some("rs") => "rust",
+        Some("py") => "python",
+        Some("sql") => "sql",
+        Some("proto") => "protobuf",
+        Some("sh" | "bash") => "shellscript",
+        Some("jsx") => "javascriptreact",
+        Some("tsx") => "typescriptreact",
+        Some("html") => "html",
+        Some("css") => "css",
+        Some("scss") => "scss",
+        Some("less") => "less",
+        Some("yaml" | "yml") => "yaml",
+        Some("json") => "json",
+        Some("toml") => "toml",
+        _ => "",
+    }
+}
+
+/// Finds `name` as a whole identifier in `source[from..to]`.
+fn find_identifier(source: &[u8], name: &str, from: usize, to: usize) -> Option<usize> {
+    let to = to.min(source.len());
+    if name.is_empty() || from >= to {
+        return None;
+    }
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let haystack = &source[from..to];
+    let needle = name.as_bytes();
+    (0..haystack.len().saturating_sub(needle.len() - 1))
+        .filter(|i| haystack[*i..].starts_with(needle))
+        .find(|i| {
+            let before = (from + i).checked_sub(1).map(|b| source[b]);
+            let after = source.get(from + i + needle.len()).copied();
+            !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+        })
+        .map(|i| from + i)
+}
+
+/// Byte offset of a zero-based line and column, counted in `encoding`, in
+/// `source`; `None` past the end of the line or inside a character.
+pub fn byte_offset(
+    source: &[u8],
+    line: usize,
+    column: usize,
+    encoding: PositionEncoding,
+) -> Option<usize> {
+    let line_start = if line == 0 {
+        0
+    } else {
+        source
+            .iter()
+            .enumerate()
+            .filter(|(_, &b)| b == b'\n')
+            .nth(line - 1)?
+            .0
+            + 1
+    };
+    let line_text = source[line_start..]
+        .split(|&b| b == b'\n')
+        .next()
+        .unwrap_or_default();
+
+    // Invalid bytes count as one unit each
+    let mut units = 0;
+    let mut bytes = 0;
+    for chunk in line_text.utf8_chunks() {
+        let chars = chunk
+            .valid()
+            .chars()
+            .map(|c| (encoding.units(c), c.len_utf8()));
+        for (char_units, char_bytes) in chars.chain(chunk.invalid().iter().map(|_| (1, 1))) {
+            if units >= column {
+                break;
+            }
+            units += char_units;
+            bytes += char_bytes;
+        }
+    }
+    (units == column).then_some(line_start + bytes)
+}
+
+/// Converts byte offsets to zero-based line and column positions.
+struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(source: &[u8]) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .iter()
+                .enumerate()
+                .filter(|(_, &b)| b == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        LineIndex { line_starts }
+    }
+
+    fn position(&self, byte: usize) -> (i32, i32) {
+        let line = self.line_starts.partition_point(|&start| start <= byte) - 1;
+        (line as i32, (byte - self.line_starts[line]) as i32)
+    }
+
+    fn range(&self, start: usize, end: usize) -> Vec<i32> {
+        let (start_line, start_column) = self.position(start);
+        let (end_line, end_column) = self.position(end);
+        if start_line == end_line {
+            vec![start_line, start_column, end_column]
+        } else {
+            vec![start_line, start_column, end_line, end_column]
+        }
+    }
+}
+
+/// A decoded protobuf field value. Fixed-width values are skipped.
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Iterates over the `(field number, value)` pairs of an encoded message.
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>), ContextMeshError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        Some(self.read_field())
+    }
+}
+
+impl<'a> Fields<'a> {
+    fn read_field(&mut self) -> Result<(u64, Value<'a>), ContextMeshError> {
+        let (key, len) = read_varint(self.0)?;
+        self.0 = &self.0[len..];
+        let value = match key & 7 {
+            0 => {
+                let (value, len) = read_varint(self.0)?;
+                self.0 = &self.0[len..];
+                Value::Varint(value)
+            }
+            2 => {
+                let (size, len) = read_varint(self.0)?;
+                let end = len
+                    .checked_add(size as usize)
+                    .filter(|end| *end <= self.0.len())
+                    .ok_or_else(truncated)?;
+                let bytes = &self.0[len..end];
+                self.0 = &self.0[end..];
+                Value::Bytes(bytes)
+            }
+            wire_type @ (1 | 5) => {
+                let size = if wire_type == 1 { 8 } else { 4 };
+                if self.0.len() < size {
+                    return Err(truncated());
+                }
+                self.0 = &self.0[size..];
+                Value::Fixed
+            }
+            wire_type => {
+                return Err(ContextMeshError::DeserializationError(format!(
+                    "Unsupported protobuf wire type {}",
+                    wire_type
+                )))
+            }
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+/// Reads a varint, returning it and the number of bytes it took.
+fn read_varint(bytes: &[u8]) -> Result<(u64, usize), ContextMeshError> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(truncated())
+}
+
+fn decode_string(bytes: &[u8]) -> Result<String, ContextMeshError> {
+    String::from_utf8(bytes.to_vec())
+        .map_err(|e| ContextMeshError::DeserializationError(format!("Invalid SCIP string: {}", e)))
+}
+
+fn truncated() -> ContextMeshError {
+    ContextMeshError::DeserializationError("Truncated SCIP index".to_string())
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3);
+    put_varint(out, value);
+}
+
+fn put_message(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, (field << 3) | 2);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn put_string(out: &mut Vec<u8>, field: u64, value: &str) {
+    put_message(out, field, value.as_bytes());
+}
@@ -0,0 +1,190 @@
+//! Where bundles go: the system clipboard, the terminal's clipboard through an
+//! OSC 52 escape sequence (which works over SSH), a tmux paste buffer, a file,
+//! or standard output.
+//!
+//! Sinks are tried in order until one takes the bundle, so that e.g. a bundle
+//! made over SSH, where there is no system clipboard, still reaches the
+//! terminal's. The order comes from `--sink`, else from the `[output]` config
+//! section, else is [`DEFAULT_SINKS`]. Sinks that can't work in the current
+//! session (tmux outside of tmux, OSC 52 without a terminal, a file without a
+//! path) are passed over.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use crate::config::OutputConfig;
+use crate::errors::ContextMeshError;
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sink {
+    /// The system clipboard
+    Clipboard,
+    /// The terminal's clipboard, through an OSC 52 escape sequence
+    Osc52,
+    /// The paste buffer of the tmux server of the session
+    Tmux,
+    /// The file set with `--output-file` or `[output] file`
+    File,
+    /// Standard output, without anything else printed to it
+    Stdout,
+}
+
+impl Sink {
+    pub fn name(self) -> &'static str {
+        match self {
+            Sink::Clipboard => "clipboard",
+            Sink::Osc52 => "terminal clipboard (OSC 52)",
+            Sink::Tmux => "tmux buffer",
+            Sink::File => "file",
+            Sink::Stdout => "stdout",
+        }
+    }
+}
+
+/// The order sinks are tried in when none is chosen.
+pub const DEFAULT_SINKS: &[Sink] = &[Sink::Clipboard, Sink::Tmux, Sink::Osc52, Sink::Stdout];
+
+static SINKS: OnceLock<Vec<Sink>> = OnceLock::new();
+static OUTPUT_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Makes every command of this process send bundles to `sinks` (in this order)
+/// and, for [`Sink::File`], to `file`. Only the first call has an effect.
+pub fn select(sinks: Vec<Sink>, file: Option<PathBuf>) {
+    if !sinks.is_empty() {
+        let _ = SINKS.set(sinks);
+    }
+    if let Some(file) = file {
+        let _ = OUTPUT_FILE.set(file);
+    }
+}
+
+/// The sinks to try, in order, and the file of [`Sink::File`], from what was
+/// [selected](select) or else from `config`. An output file alone selects the
+/// file sink.
+pub fn chosen(config: &OutputConfig) -> (Vec<Sink>, Option<PathBuf>) {
+    let file = OUTPUT_FILE
+        .get()
+        .cloned()
+        .or_else(|| config.file.clone().map(PathBuf::from));
+    let sinks = match (SINKS.get(), &config.sinks) {
+        (Some(sinks), _) => sinks.clone(),
+        (None, Some(sinks)) => sinks.clone(),
+        (None, None) if OUTPUT_FILE.get().is_some() => vec![Sink::File],
+        (None, None) => DEFAULT_SINKS.to_vec(),
+    };
+    (sinks, file)
+}
+
+/// Sends `content` to the first of `sinks` that takes it and returns that
+/// sink. Fails, with why each sink didn't work, if none does.
+pub fn send(
+    content: &str,
+    sinks: &[Sink],
+    file: Option<&PathBuf>,
+) -> Result<Sink, ContextMeshError> {
+    let mut failures = Vec::new();
+    for &sink in sinks {
+        match send_to(sink, content, file) {
+            Ok(()) => return Ok(sink),
+            Err(reason) => {
+                log::debug!("Not sending to the {}: {}", sink.name(), reason);
+                failures.push(format!("{}: {}", sink.name(), reason));
+            }
+        }
+    }
+    Err(ContextMeshError::ClipboardError(
+        match failures.is_empty() {
+            true => "no output sink chosen".to_string(),
+            false => failures.join("; "),
+        },
+    ))
+}
+
+fn send_to(sink: Sink, content: &str, file: Option<&PathBuf>) -> Result<(), String> {
+    match sink {
+        Sink::Clipboard => arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(content.to_string()))
+            .map_err(|e| e.to_string()),
+        Sink::Osc52 => osc52(content),
+        Sink::Tmux => tmux(content),
+        Sink::File => {
+            let path = file.ok_or("no output file set")?;
+            fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+        Sink::Stdout => {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(content.as_bytes())
+                .and_then(|()| stdout.flush())
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Writes `content` as an OSC 52 "set clipboard" sequence to the terminal on
+/// stdout or, if that is redirected, on stderr. Inside tmux, the sequence is
+/// wrapped to pass through to the outer terminal.
+fn osc52(content: &str) -> Result<(), String> {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(content.as_bytes()));
+    let sequence = match std::env::var_os("TMUX") {
+        Some(_) => format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b")),
+        None => sequence,
+    };
+    let written = if std::io::stdout().is_terminal() {
+        std::io::stdout().write_all(sequence.as_bytes())
+    } else if std::io::stderr().is_terminal() {
+        std::io::stderr().write_all(sequence.as_bytes())
+    } else {
+        return Err("not attached to a terminal".to_string());
+    };
+    written.map_err(|e| e.to_string())
+}
+
+/// Loads `content` into a tmux paste buffer, if running inside tmux.
+fn tmux(content: &str) -> Result<(), String> {
+    if std::env::var_os("TMUX").is_none() {
+        return Err("not running inside tmux".to_string());
+    }
+    let mut child = Command::new("tmux")
+        .args(["load-buffer", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("can't run tmux: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(content.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+    }
+}
+
+/// Standard base64 with padding, as OSC 52 expects.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
@@ -1,6 +1,65 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Arc;
+
+/// Compact identifier of a symbol within a loaded index.
+///
+/// IDs are only meaningful inside the index that issued them; the symbol hash is
+/// the stable identity used in output and exports.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(pub u32);
+
+/// The visibility modifier a symbol was declared with.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Visibility {
+    /// No modifier; visible only within the enclosing module.
+    #[default]
+    Private,
+    /// `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// Any other restriction, e.g. `pub(super)` or `pub(in crate::foo)`, verbatim.
+    Restricted(String),
+}
+
+impl Visibility {
+    pub fn is_public(&self) -> bool {
+        matches!(self, Visibility::Public)
+    }
+}
+
+/// The most recent commit touching a symbol's lines, according to `git blame`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Blame {
+    pub commit: String,
+    pub author: String,
+    /// Author time as seconds since the Unix epoch.
+    pub time: u64,
+}
+
+impl Blame {
+    /// Whether the lines have uncommitted changes, which `git blame` attributes
+    /// to an all-zero commit.
+    pub fn is_uncommitted(&self) -> bool {
+        self.commit.bytes().all(|b| b == b'0')
+    }
+}
+
+/// Size and complexity of a symbol's source, measured on its syntax tree.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Lines spanned by the definition.
+    pub lines: u32,
+    /// Approximate cyclomatic complexity: one plus the number of branches
+    /// (conditionals, loops, match arms beyond the first, `&&`/`||`, ...).
+    pub complexity: u32,
+    /// Deepest nesting of branches and loops.
+    pub nesting: u32,
+    /// Number of parameters, including `self`.
+    pub parameters: u32,
+}
 
 /// Represents a symbol extracted from the codebase.
 ///
@@ -17,10 +76,14 @@ pub struct Symbol {
     pub name: String,
 
     /// The kind of AST node representing the symbol (e.g., `function_item`, `struct_item`).
-    pub node_kind: String,
+    ///
+    /// Shared between all indexed symbols of the same kind.
+    pub node_kind: Arc<str>,
 
     /// The file path where the symbol is defined.
-    pub file_path: String,
+    ///
+    /// Shared between all symbols of the same file so each path is allocated once.
+    pub file_path: Arc<str>,
 
     /// The line number in the source file where the symbol is located.
     pub line_number: usize,
@@ -31,20 +94,191 @@ pub struct Symbol {
     /// The ending byte offset of the symbol in the source file.
     pub end_byte: usize,
 
-    /// A list of hashes representing symbols that this symbol depends on.
+    /// The visibility modifier of the definition.
+    pub visibility: Visibility,
+
+    /// Outer attributes attached to the definition, without the `#[...]` wrapper
+    /// (e.g. `test`, `derive(Debug, Clone)`, `cfg(feature = "x")`).
+    pub attributes: Vec<String>,
+
+    /// The declaration without its body, e.g. `pub fn load(path: &Path) -> Index`.
+    pub signature: String,
+
+    /// The doc comment attached to the definition, without comment markers.
+    pub doc: Option<String>,
+
+    /// SHA256 of the symbol's source text, used to tell whether its body changed
+    /// between index runs independently of where it sits in the file.
+    pub body_hash: String,
+
+    /// The enclosing symbol, e.g. the struct of a field, the enum of a variant,
+    /// or the type whose `impl` block (in the same file) defines a method.
+    pub parent: Option<SymbolId>,
+
+    /// Raw names referenced by this symbol, as collected by the parser.
+    ///
+    /// These are consumed during dependency resolution, which turns them into
+    /// `dependencies` edges or records them as unresolved.
+    #[serde(skip)]
+    pub references: HashSet<String>,
+
+    /// Notable string literals used by the symbol: routes, environment variable
+    /// names, error codes, and configuration keys. For a Dockerfile stage, the
+    /// paths it copies.
+    pub literals: BTreeSet<String>,
+
+    /// Last change to the symbol's lines; only recorded by `index --blame`.
+    pub blame: Option<Blame>,
+
+    /// Size and complexity; only measured for code parsed with tree-sitter.
+    pub metrics: Option<Metrics>,
+
+    /// IDs of the symbols that this symbol depends on.
     ///
     /// Dependencies indicate relationships where this symbol relies on other symbols,
     /// such as function calls, trait implementations, or struct field types.
-    pub dependencies: HashSet<String>,
+    pub dependencies: HashSet<SymbolId>,
 
-    /// A list of hashes representing symbols that depend on this symbol.
+    /// IDs of the symbols that depend on this symbol.
     ///
     /// The `used_by` field establishes reverse dependencies, showing which symbols
     /// are influenced or utilize this symbol.
-    pub used_by: HashSet<String>,
+    pub used_by: HashSet<SymbolId>,
 }
 
 impl Symbol {
+    /// Node kind of re-export symbols (e.g. Rust `pub use`), which depend on the
+    /// item they re-export.
+    pub const REEXPORT_KIND: &'static str = "reexport";
+
+    /// Node kind of a heading-delimited section of a document (e.g. Markdown).
+    pub const SECTION_KIND: &'static str = "section";
+
+    /// Node kind of a document without headings, indexed as a whole.
+    pub const DOCUMENT_KIND: &'static str = "document";
+
+    /// Node kind of a table/object in a configuration file (TOML, YAML, JSON).
+    pub const CONFIG_TABLE_KIND: &'static str = "table";
+
+    /// Node kind of a non-table key in a configuration file.
+    pub const CONFIG_KEY_KIND: &'static str = "key";
+
+    /// Node kinds of SQL schema objects; columns are children of their table.
+    pub const SQL_TABLE_KIND: &'static str = "sql_table";
+    pub const SQL_COLUMN_KIND: &'static str = "sql_column";
+    pub const SQL_INDEX_KIND: &'static str = "sql_index";
+    pub const SQL_VIEW_KIND: &'static str = "sql_view";
+
+    /// Node kinds of Protocol Buffers definitions. Fields, enum values, and RPCs
+    /// are children of their message, enum, or service.
+    pub const PROTO_MESSAGE_KIND: &'static str = "proto_message";
+    pub const PROTO_FIELD_KIND: &'static str = "proto_field";
+    pub const PROTO_ENUM_KIND: &'static str = "proto_enum";
+    pub const PROTO_ENUM_VALUE_KIND: &'static str = "proto_enum_value";
+    pub const PROTO_SERVICE_KIND: &'static str = "proto_service";
+    pub const PROTO_RPC_KIND: &'static str = "proto_rpc";
+
+    /// Node kinds of shell scripts, named after their file, and of the functions
+    /// they define.
+    pub const SHELL_SCRIPT_KIND: &'static str = "shell_script";
+    pub const SHELL_FUNCTION_KIND: &'static str = "shell_function";
+
+    /// Node kinds of the build stages of a Dockerfile and of the services of a
+    /// Docker Compose file.
+    pub const DOCKER_STAGE_KIND: &'static str = "docker_stage";
+    pub const COMPOSE_SERVICE_KIND: &'static str = "compose_service";
+
+    /// Node kinds of UI components (JSX/TSX), of HTML pages, named after their
+    /// file, and of the rules styling a CSS class, named like `.btn`.
+    pub const COMPONENT_KIND: &'static str = "component";
+    pub const HTML_PAGE_KIND: &'static str = "html_page";
+    pub const CSS_CLASS_KIND: &'static str = "css_class";
+
+    /// Node kind of an operation of an OpenAPI spec, named like `POST /users`.
+    pub const ENDPOINT_KIND: &'static str = "endpoint";
+
+    /// Attribute of symbols a `contextmesh:ignore` comment applies to.
+    pub const EXCLUDED_ATTRIBUTE: &'static str = "contextmesh:ignore";
+
+    pub fn is_reexport(&self) -> bool {
+        &*self.node_kind == Self::REEXPORT_KIND
+    }
+
+    /// Returns `true` for symbols of prose documents rather than code.
+    pub fn is_document(&self) -> bool {
+        &*self.node_kind == Self::SECTION_KIND || &*self.node_kind == Self::DOCUMENT_KIND
+    }
+
+    /// Returns `true` for tables and keys of configuration files.
+    pub fn is_config(&self) -> bool {
+        &*self.node_kind == Self::CONFIG_TABLE_KIND || &*self.node_kind == Self::CONFIG_KEY_KIND
+    }
+
+    /// Returns `true` for Dockerfile stages and Compose services, whose names
+    /// (`builder`, `api`, `db`) only mean something in their own file.
+    pub fn is_docker(&self) -> bool {
+        &*self.node_kind == Self::DOCKER_STAGE_KIND
+            || &*self.node_kind == Self::COMPOSE_SERVICE_KIND
+    }
+
+    /// Returns `true` for definitions in `.proto` files.
+    pub fn is_proto(&self) -> bool {
+        self.node_kind.starts_with("proto_")
+    }
+
+    pub fn is_endpoint(&self) -> bool {
+        &*self.node_kind == Self::ENDPOINT_KIND
+    }
+
+    /// Returns `true` for symbols of source code, as opposed to documents and
+    /// configuration files.
+    pub fn is_code(&self) -> bool {
+        !self.is_document() && !self.is_config()
+    }
+
+    /// Returns `true` for test functions and items compiled only for tests.
+    pub fn is_test(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attr| attr == "test" || attr.ends_with("::test") || attr == "cfg(test)")
+    }
+
+    /// Returns `true` for symbols kept out of bundles by a `contextmesh:ignore`
+    /// comment (see [`crate::parser::ignore`]).
+    pub fn is_excluded(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attr| attr == Self::EXCLUDED_ATTRIBUTE)
+    }
+
+    /// Names other languages know the symbol by through FFI bindings: its own for
+    /// `#[pyfunction]`, `#[pyclass]`, `#[pymethods]`, and `#[wasm_bindgen]` items,
+    /// or the one set with `name = "..."` (`js_name = ...` for wasm-bindgen).
+    pub fn foreign_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for attr in &self.attributes {
+            let (path, args) = attr.split_once('(').unwrap_or((attr, ""));
+            let key = match path.rsplit("::").next().unwrap_or(path).trim() {
+                "pyo3" | "pyclass" | "pyfunction" | "pymethods" | "pymodule" => "name",
+                "wasm_bindgen" => "js_name",
+                _ => continue,
+            };
+            let renamed = args.trim_end_matches(')').split(',').find_map(|arg| {
+                let (name, value) = arg.split_once('=')?;
+                (name.trim() == key).then(|| value.trim().trim_matches('"').to_string())
+            });
+            match renamed {
+                Some(name) if !name.is_empty() => names.push(name),
+                // `#[pyo3(...)]` only tweaks an item exported by another attribute
+                _ if path.trim() != "pyo3" => names.push(self.name.clone()),
+                _ => {}
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
     pub fn hash(&self) -> String {
         let mut hasher = Sha256::new();
         hasher.update(&self.name);
@@ -0,0 +1,92 @@
+//! Tracing of the requests the daemon serves.
+//!
+//! Every request runs in a `request` span recording the command, the number of
+//! symbols in the served index, and the exit code; its duration is the span's.
+//! Spans go nowhere unless contextmesh was built with the `otel` feature and the
+//! daemon was given an OTLP endpoint, in which case they are exported over
+//! OTLP/HTTP as the request finishes. Requests run in forked children, so each
+//! child sets up and flushes its own exporter.
+
+use crate::errors::ContextMeshError;
+
+/// Whether spans can be exported, i.e. contextmesh was built with `otel`.
+pub const CAN_EXPORT: bool = cfg!(feature = "otel");
+
+/// The environment variable OTLP exporters take their endpoint from.
+pub const ENDPOINT_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// The endpoint to export spans to: the one given on the command line, else
+/// the value of [`ENDPOINT_VAR`] in `env`. Without `otel`, a given endpoint is
+/// an error, but the variable, often set for other programs, is ignored.
+pub fn endpoint(
+    given: Option<&str>,
+    env: Option<&str>,
+) -> Result<Option<String>, ContextMeshError> {
+    match (given, env.filter(|value| !value.is_empty())) {
+        (Some(_), _) if !CAN_EXPORT => Err(ContextMeshError::DaemonError(
+            "Exporting spans needs contextmesh built with the `otel` feature".to_string(),
+        )),
+        (Some(endpoint), _) => Ok(Some(endpoint.to_string())),
+        (None, Some(_)) if !CAN_EXPORT => {
+            log::warn!(
+                "Ignoring {}: contextmesh was built without the `otel` feature",
+                ENDPOINT_VAR
+            );
+            Ok(None)
+        }
+        (None, env) => Ok(env.map(str::to_string)),
+    }
+}
+
+/// Exports the spans of the current process until dropped.
+pub struct Exporter {
+    #[cfg(feature = "otel")]
+    provider: opentelemetry_sdk::trace::TracerProvider,
+    #[cfg(feature = "otel")]
+    _guard: tracing::subscriber::DefaultGuard,
+}
+
+/// Starts exporting spans to the OTLP collector at `endpoint`, e.g.
+/// `http://localhost:4318`.
+#[cfg(feature = "otel")]
+pub fn export_to(endpoint: &str) -> Result<Exporter, ContextMeshError> {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+        .map_err(|e| ContextMeshError::DaemonError(format!("OTLP exporter: {}", e)))?;
+    let provider = opentelemetry_sdk::trace::TracerProvider::builder()
+        .with_simple_exporter(exporter)
+        .with_resource(opentelemetry_sdk::Resource::new([KeyValue::new(
+            "service.name",
+            "contextmesh",
+        )]))
+        .build();
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("contextmesh"));
+    let subscriber = tracing_subscriber::registry().with(layer);
+    Ok(Exporter {
+        _guard: tracing::subscriber::set_default(subscriber),
+        provider,
+    })
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn export_to(_endpoint: &str) -> Result<Exporter, ContextMeshError> {
+    Err(ContextMeshError::DaemonError(
+        "contextmesh was built without the `otel` feature".to_string(),
+    ))
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Err(e) = self.provider.shutdown() {
+            log::warn!("Failed to flush spans: {}", e);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Detection of vendored and other third-party source files.
+//!
+//! A file counts as third-party if it matches a glob of the `[third_party]`
+//! config section or, unless `detect = false`, if a directory on its path is a
+//! conventional place for vendored code (`vendor/`, `third_party/`,
+//! `node_modules/`, ...) or it has a foreign license header. A header is
+//! foreign if it names neither an `own_copyright` holder nor the project's
+//! holder, the one most files with a header name; if no holder is named most,
+//! only `own_copyright` tells. Files without a header are never foreign,
+//! however many there are. Documents are only judged by their path.
+//!
+//! Like generated code, third-party code is indexed and resolved like any other,
+//! but left out of bundles unless asked for.
+
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::arch::path_matches;
+use crate::config::ThirdPartyConfig;
+use crate::parser::document::is_document_file;
+
+/// How much of a file is searched for a license header.
+const HEADER_BYTES: u64 = 2048;
+
+/// Lowercase directory names of vendored code.
+const VENDOR_DIRS: &[&str] = &[
+    "vendor",
+    "vendored",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "3rdparty",
+    "node_modules",
+    "bower_components",
+];
+
+/// A copyright line of a comment, capturing the holder after the years.
+static COPYRIGHT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?im)^[\s/*#;!-]*copyright\s*(?:\(c\)|©)?\s*(?:\d{4}(?:\s*[-,]\s*\d{4})*,?)?\s*(?:by\s+)?([^\n*]+)",
+    )
+    .expect("valid regex")
+});
+
+/// An SPDX license identifier line of a comment, capturing the expression.
+static SPDX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^[\s/*#;!-]*SPDX-License-Identifier:\s*([^\n*]+)").expect("valid regex")
+});
+
+/// The third-party files among `files`.
+pub fn third_party_files(files: &[&str], config: &ThirdPartyConfig) -> HashSet<String> {
+    let mut third_party: HashSet<String> = files
+        .iter()
+        .filter(|path| {
+            config
+                .paths
+                .iter()
+                .any(|pattern| path_matches(pattern, path))
+                || (config.detect && in_vendor_dir(path))
+        })
+        .map(|path| path.to_string())
+        .collect();
+    if !config.detect {
+        return third_party;
+    }
+
+    let notices: Vec<(&str, Option<String>)> = files
+        .iter()
+        .filter(|path| !third_party.contains(**path) && !is_document_file(path))
+        .map(|path| (*path, license_notice(path)))
+        .collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for notice in notices.iter().filter_map(|(_, notice)| notice.as_deref()) {
+        *counts.entry(notice).or_default() += 1;
+    }
+    // With a tie, there is no telling the project's header from a foreign one
+    let most = counts.values().copied().max().unwrap_or_default();
+    let mut most_common = counts.iter().filter(|(_, count)| **count == most);
+    let project = match (most_common.next(), most_common.next()) {
+        (Some((notice, _)), None) => Some(*notice),
+        _ => None,
+    };
+
+    for (path, notice) in &notices {
+        let Some(notice) = notice else {
+            continue;
+        };
+        let own = config
+            .own_copyright
+            .iter()
+            .any(|holder| notice.contains(&holder.to_lowercase()));
+        let foreign = match project {
+            Some(project) => notice != project,
+            // Only the configured holders tell
+            None => !config.own_copyright.is_empty(),
+        };
+        if foreign && !own {
+            third_party.insert(path.to_string());
+        }
+    }
+    third_party
+}
+
+fn in_vendor_dir(path: &str) -> bool {
+    let path = Path::new(path);
+    let dirs = path.parent().into_iter().flat_map(Path::components);
+    dirs.filter_map(|dir| dir.as_os_str().to_str())
+        .any(|dir| VENDOR_DIRS.contains(&dir.to_lowercase().as_str()))
+}
+
+/// The copyright holder, or else the SPDX license expression, of the file's
+/// header, lowercased.
+fn license_notice(path: &str) -> Option<String> {
+    let mut header = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(HEADER_BYTES).read_to_end(&mut header))
+        .ok()?;
+    let header = String::from_utf8_lossy(&header);
+    let notice = COPYRIGHT
+        .captures(&header)
+        .or_else(|| SPDX.captures(&header))?;
+    let notice = notice[1].to_lowercase();
+    let notice = notice
+        .split("all rights reserved")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .trim_end_matches(['.', ','])
+        .to_string();
+    (!notice.is_empty()).then_some(notice)
+}
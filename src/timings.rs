@@ -0,0 +1,78 @@
+//! Durations and counts of the phases of a command, reported with `--timings`.
+//!
+//! Recording is off until [`enable`] is called, so instrumented code costs an
+//! atomic load when no report was asked for. Phases recorded more than once,
+//! like hashing each file, add up under one name.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::output::Table;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// (phase, total duration, count) in the order phases were first recorded
+static PHASES: Mutex<Vec<(&'static str, Duration, usize)>> = Mutex::new(Vec::new());
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Adds `duration` and `count` (e.g. files or symbols handled) to `phase`.
+pub fn record(phase: &'static str, duration: Duration, count: usize) {
+    if !is_enabled() {
+        return;
+    }
+    let mut phases = PHASES.lock().unwrap_or_else(PoisonError::into_inner);
+    match phases.iter_mut().find(|(name, ..)| *name == phase) {
+        Some((_, total, total_count)) => {
+            *total += duration;
+            *total_count += count;
+        }
+        None => phases.push((phase, duration, count)),
+    }
+}
+
+/// Runs `f` as one occurrence of `phase`.
+pub fn time<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    record(phase, start.elapsed(), 1);
+    result
+}
+
+/// A phase in progress; see [`start`].
+pub struct Timer {
+    phase: &'static str,
+    start: Instant,
+}
+
+/// Starts timing `phase`, for phases whose count is only known at the end.
+pub fn start(phase: &'static str) -> Timer {
+    Timer {
+        phase,
+        start: Instant::now(),
+    }
+}
+
+impl Timer {
+    pub fn stop(self, count: usize) {
+        record(self.phase, self.start.elapsed(), count);
+    }
+}
+
+/// The phases recorded so far, with their durations in milliseconds.
+pub fn report() -> Table {
+    let phases = PHASES.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut table = Table::new(&["phase", "ms", "count"]);
+    for (phase, duration, count) in phases.iter() {
+        let ms = (duration.as_secs_f64() * 10_000.0).round() / 10.0;
+        table.push(vec![(*phase).into(), ms.into(), (*count).into()]);
+    }
+    table
+}
@@ -1,10 +1,69 @@
+use crate::parser::notebook;
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn collect_files(directory: &str, extensions: &[&str]) -> Vec<String> {
-    let mut files = Vec::new();
-    if let Ok(entries) = fs::read_dir(directory) {
-        for entry in entries.flatten() {
+/// Collects the files under `directory` with one of `extensions`. A capitalized
+/// entry may also be a whole file name (`Dockerfile`), and an entry ending in a
+/// dot is a file name prefix (`Dockerfile.` for `Dockerfile.dev`); a file named
+/// `sh` doesn't have the extension `sh`.
+///
+/// Symbolic links are followed only with `follow_symlinks`. Either way, each
+/// file is collected once, by its canonical path, so link cycles end and linked
+/// files aren't indexed twice; hard links are separate files. A file is
+/// collected by its path without links if the walk reaches it that way, and
+/// entries are walked in name order, so the path kept is the same every run.
+pub fn collect_files(directory: &str, extensions: &[&str], follow_symlinks: bool) -> Vec<String> {
+    let keep = |file_name: &str| has_extension(file_name, extensions);
+    FileWalk::collect(directory, &keep, follow_symlinks)
+}
+
+/// Collects all files under `directory`, whatever their names, e.g. to detect
+/// their languages; links are handled as by [`collect_files`].
+pub fn collect_all_files(directory: &str, follow_symlinks: bool) -> Vec<String> {
+    FileWalk::collect(directory, &|_| true, follow_symlinks)
+}
+
+/// A walk of a directory tree, remembering what it has reached already.
+#[derive(Default)]
+struct FileWalk {
+    follow_symlinks: bool,
+    /// The canonical paths of the directories walked.
+    directories: HashSet<PathBuf>,
+    /// The canonical paths of the files collected.
+    files: HashSet<PathBuf>,
+}
+
+impl FileWalk {
+    /// Collects the files under `directory` whose names `keep` accepts: first
+    /// those reached without links, then, with `follow_symlinks`, those only
+    /// reached through them.
+    fn collect(directory: &str, keep: &dyn Fn(&str) -> bool, follow_symlinks: bool) -> Vec<String> {
+        let mut walk = FileWalk::default();
+        let mut files = walk.walk(directory, keep);
+        if follow_symlinks {
+            walk.follow_symlinks = true;
+            walk.directories.clear();
+            files.extend(walk.walk(directory, keep));
+        }
+        files
+    }
+
+    fn walk(&mut self, directory: &str, keep: &dyn Fn(&str) -> bool) -> Vec<String> {
+        let mut files = Vec::new();
+        let canonical = fs::canonicalize(directory);
+        if !canonical.is_ok_and(|canonical| self.directories.insert(canonical)) {
+            return files;
+        }
+        let Ok(entries) = fs::read_dir(directory) else {
+            return files;
+        };
+        let mut entries: Vec<fs::DirEntry> = entries.flatten().collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
             let path = entry.path();
             let file_name = path.file_name().unwrap_or_default().to_string_lossy();
 
@@ -16,21 +75,172 @@ pub fn collect_files(directory: &str, extensions: &[&str]) -> Vec<String> {
             {
                 continue;
             }
-            if path.is_dir() {
-                files.extend(collect_files(path.to_str().unwrap(), extensions));
-            } else if let Some(ext) = path.extension() {
-                if extensions.contains(&ext.to_str().unwrap()) {
-                    files.push(path.to_string_lossy().to_string());
-                }
+            let is_link = entry.file_type().is_ok_and(|kind| kind.is_symlink());
+            if is_link && !self.follow_symlinks {
+                continue;
+            }
+            // Follows links; a dangling one has no metadata
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                files.extend(self.walk(&path.to_string_lossy(), keep));
+            } else if keep(&file_name)
+                && fs::canonicalize(&path).is_ok_and(|canonical| self.files.insert(canonical))
+            {
+                files.push(path.to_string_lossy().to_string());
             }
         }
+        files
+    }
+}
+
+/// Whether a file name has one of `extensions`, or is named by one of them as
+/// described for [`collect_files`].
+pub fn has_extension(file_name: &str, extensions: &[&str]) -> bool {
+    extensions.iter().any(|ext| match ext.strip_suffix('.') {
+        Some(prefix) => file_name.starts_with(ext) && file_name.len() > prefix.len() + 1,
+        None => {
+            (ext.starts_with(|c: char| c.is_ascii_uppercase()) && file_name == *ext)
+                || file_name
+                    .rsplit_once('.')
+                    .is_some_and(|(stem, extension)| !stem.is_empty() && extension == *ext)
+        }
+    })
+}
+
+/// The project root: the directory commands run in (see `--root`), which the
+/// paths in the index are relative to.
+pub fn project_root() -> std::io::Result<PathBuf> {
+    std::env::current_dir()?.canonicalize()
+}
+
+/// The form `path` is stored in the index in: relative to `root`, as
+/// `./src/lib.rs`, whether it was given as `src/lib.rs`, `/home/me/repo/src/lib.rs`
+/// or `../repo/src/lib.rs`. That keeps an index valid when the project is moved
+/// or the index is shared. A path outside `root` is kept as given.
+pub fn root_relative(path: &str, root: &Path) -> String {
+    let absolute = normalize(&root.join(path));
+    let relative = absolute
+        .strip_prefix(root)
+        .ok()
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            // `root` is canonical; the path may reach it through a link
+            let canonical = fs::canonicalize(&absolute).ok()?;
+            Some(canonical.strip_prefix(root).ok()?.to_path_buf())
+        });
+    match relative {
+        Some(relative) => format!("./{}", relative.to_string_lossy()),
+        None => path.to_string(),
     }
-    files
+}
+
+/// Removes the `.` and `..` components of `path` without reading the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Reads the source a file is indexed as: the script of a notebook's code cells
+/// (see [`notebook::script`]), or else the file's content.
+pub fn read_source(path: &str) -> std::io::Result<Vec<u8>> {
+    let content = fs::read(path)?;
+    match notebook::is_notebook(path) {
+        true => Ok(notebook::script(&content)?.into_bytes()),
+        false => Ok(content),
+    }
+}
+
+/// Replaces the file at `path` with `data` in one step, through a temporary file
+/// next to it, so readers never see it half-written.
+pub fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(data)?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
 }
 
 pub fn calculate_file_hash(file_path: &str) -> Option<String> {
     let content = fs::read(file_path).ok()?;
+    Some(hash_bytes(&content))
+}
+
+/// Returns the hex-encoded SHA256 of `bytes`.
+pub fn hash_bytes(bytes: &[u8]) -> String {
     let mut hasher = Sha256::new();
-    hasher.update(content);
-    Some(format!("{:x}", hasher.finalize()))
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Returns the current time as seconds since the Unix epoch.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        (rem % 3_600) / 60,
+        rem % 60
+    )
+}
+
+/// Rough token count of `byte_len` bytes of source text (about four bytes per
+/// token), good enough for budgeting how much context fits into a prompt.
+pub fn estimate_tokens(byte_len: usize) -> usize {
+    byte_len.div_ceil(4)
+}
+
+/// Rust module path of a source file, e.g. `src/parser/mod.rs` -> `crate::parser`.
+pub fn module_path(path: &str) -> String {
+    let path = path.trim_start_matches("./");
+    let path = path.strip_prefix("src/").unwrap_or(path);
+    let path = path.strip_suffix(".rs").unwrap_or(path);
+    let path = path.strip_suffix("/mod").unwrap_or(path);
+
+    let mut module = String::from("crate");
+    if path != "lib" && path != "main" {
+        for segment in path.split('/') {
+            module.push_str("::");
+            module.push_str(segment);
+        }
+    }
+    module
 }
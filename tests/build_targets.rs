@@ -0,0 +1,49 @@
+use contextmesh::build_targets::{absolute_label, parse};
+
+const BUILD: &str = r#"load("@rules_rust//rust:defs.bzl", "rust_library")
+
+rust_library(
+    name = "lib",  # the service itself
+    srcs = glob(["src/**/*.rs"], exclude = ["src/bin/*.rs"]),
+    deps = [
+        ":proto",
+        "//common/log",
+        "//common/db:client",
+        "@crates//:serde",
+    ],
+)
+
+proto_library(name = "proto", srcs = ["api.proto"])
+"#;
+
+#[test]
+fn build_files_declare_sources_and_deps() {
+    let targets = parse("services/foo", BUILD);
+    assert_eq!(targets.len(), 2);
+
+    let lib = &targets[0];
+    assert_eq!(lib.label, "//services/foo:lib");
+    assert_eq!(
+        lib.deps,
+        [
+            "//services/foo:proto",
+            "//common/log:log",
+            "//common/db:client",
+            "@crates//:serde",
+        ]
+    );
+    assert!(lib.owns("services/foo/src/handlers/users.rs"));
+    assert!(lib.owns("./services/foo/src/lib.rs"));
+    assert!(!lib.owns("services/foo/src/bin/main.rs"));
+    assert!(!lib.owns("services/bar/src/lib.rs"));
+
+    assert_eq!(targets[1].label, "//services/foo:proto");
+    assert!(targets[1].owns("services/foo/api.proto"));
+}
+
+#[test]
+fn command_line_labels_are_made_absolute() {
+    assert_eq!(absolute_label("services/foo"), "//services/foo:foo");
+    assert_eq!(absolute_label("//services/foo"), "//services/foo:foo");
+    assert_eq!(absolute_label("services/foo:lib"), "//services/foo:lib");
+}
@@ -0,0 +1,66 @@
+use contextmesh::bundle::{Bundle, Detail, Manifest};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn symbols_left_out_are_outlined_after_the_code() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(
+        &path,
+        "fn kept() {}\n\nfn outlined(x: u32) -> u32 {\n    x\n}\n",
+    )
+    .unwrap();
+    let path = path.to_string_lossy();
+
+    let mut bundle = Bundle::default();
+    bundle.add_range(&path, 0..12);
+    bundle.add_signature(&path, "fn outlined(x: u32) -> u32", Some("Returns `x`."));
+    bundle.add_name("`crate::named` (lib.rs:9)".to_string());
+    let rendered = bundle.render();
+
+    let code = rendered.find("fn kept() {}").unwrap();
+    let signature = rendered
+        .find("// Returns `x`.\nfn outlined(x: u32) -> u32\n")
+        .unwrap();
+    let name = rendered.find("- `crate::named` (lib.rs:9)").unwrap();
+    assert!(code < signature && signature < name);
+    assert!(!rendered.contains("    x\n"));
+}
+
+#[test]
+fn manifests_without_details_include_whole_symbols() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("manifest.json");
+    fs::write(
+        &path,
+        r#"{"tool_version": "0.1.0", "created_at": 0, "git_commit": null, "files": [],
+            "symbols": [{"qualified_name": "crate::f", "kind": "function_item",
+                         "file": "src/lib.rs", "line": 1, "body_hash": "abc"}]}"#,
+    )
+    .unwrap();
+    let manifest = Manifest::load(&path.to_string_lossy()).unwrap();
+    assert_eq!(manifest.symbols[0].detail, Detail::Full);
+}
+
+#[test]
+fn excluded_ranges_are_elided_even_from_whole_files() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(
+        &path,
+        "fn a() {}\n\nfn big() {\n    0\n}\n\nfn b() {}\n\nfn tail() {}\n",
+    )
+    .unwrap();
+    let path = path.to_string_lossy();
+
+    let mut bundle = Bundle::default();
+    bundle.add_file(&path);
+    bundle.exclude_range(&path, 11..29);
+    bundle.exclude_range(&path, 42..54);
+    let rendered = bundle.render();
+
+    assert!(rendered.contains("fn a() {}\n\n// ... lines 2-6 elided ...\n\nfn b() {}\n\n"));
+    assert!(rendered.ends_with("// ... lines 8-9 elided ...\n\n"));
+    assert!(!rendered.contains("big") && !rendered.contains("tail"));
+}
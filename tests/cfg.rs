@@ -0,0 +1,86 @@
+use contextmesh::cfg::{Cfg, CfgSet};
+
+fn set(options: &[&str]) -> CfgSet {
+    let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+    CfgSet::parse(&options).unwrap()
+}
+
+#[test]
+fn predicates_are_parsed_from_attributes() {
+    let attributes = vec![
+        "cfg(all(unix, not(feature = \"otel\")))".to_string(),
+        "derive(Debug)".to_string(),
+        "cfg(any(target_os=\"linux\", windows))".to_string(),
+    ];
+    let cfgs: Vec<String> = Cfg::of_attributes(&attributes)
+        .iter()
+        .map(Cfg::to_string)
+        .collect();
+    assert_eq!(
+        cfgs,
+        [
+            "all(unix, not(feature = \"otel\"))",
+            "any(target_os = \"linux\", windows)",
+        ]
+    );
+    assert_eq!(Cfg::parse("not(unix, windows)"), None);
+    assert_eq!(Cfg::parse("unix)"), None);
+}
+
+#[test]
+fn exclusive_variants_hold_for_different_cfg_sets() {
+    let unix = Cfg::parse("all(unix, not(feature = \"otel\"))").unwrap();
+    let other = Cfg::parse("any(not(unix), feature = \"otel\")").unwrap();
+
+    let plain = set(&["unix"]);
+    assert!(unix.holds(&plain) && !other.holds(&plain));
+    let with_otel = set(&["unix", "feature=\"otel\""]);
+    assert!(!unix.holds(&with_otel) && other.holds(&with_otel));
+    assert!(!unix.holds(&CfgSet::default()) && other.holds(&CfgSet::default()));
+
+    assert!(CfgSet::parse(&["not(unix)".to_string()]).is_err());
+}
+
+#[test]
+fn feature_gates_are_what_a_predicate_requires() {
+    let gates = |predicate: &str| -> Vec<String> {
+        Cfg::parse(predicate)
+            .unwrap()
+            .feature_gates()
+            .into_iter()
+            .collect()
+    };
+    assert_eq!(gates("feature = \"otel\""), ["otel"]);
+    assert_eq!(gates("all(unix, not(feature = \"otel\"))"), ["!otel"]);
+    assert_eq!(
+        gates("all(feature = \"a\", not(feature = \"b\"))"),
+        ["!b", "a"]
+    );
+    // Either feature is enough, so neither is required
+    assert!(gates("any(feature = \"a\", feature = \"b\")").is_empty());
+    assert_eq!(
+        gates("any(all(feature = \"a\", feature = \"b\"), feature = \"a\")"),
+        ["a"]
+    );
+    assert_eq!(
+        gates("not(any(feature = \"a\", feature = \"b\"))"),
+        ["!a", "!b"]
+    );
+    assert!(gates("not(all(feature = \"a\", feature = \"b\"))").is_empty());
+    assert_eq!(gates("not(not(feature = \"a\"))"), ["a"]);
+    assert!(gates("any(unix, feature = \"a\")").is_empty());
+}
+
+#[test]
+fn feature_gates_combine_the_cfg_attributes() {
+    let attributes = vec![
+        "cfg(any(feature = \"a\", test))".to_string(),
+        "cfg(not(any(feature = \"b\", feature = \"c\")))".to_string(),
+        "cfg(feature = \"d\")".to_string(),
+        "doc = \"feature = \\\"e\\\"\"".to_string(),
+    ];
+    let gates: Vec<String> = contextmesh::cargo::feature_gates(&attributes)
+        .into_iter()
+        .collect();
+    assert_eq!(gates, ["!b", "!c", "d"]);
+}
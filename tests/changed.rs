@@ -0,0 +1,71 @@
+use std::fs;
+use std::process::Command;
+
+use serde_json::Value;
+use tempfile::TempDir;
+
+#[test]
+fn changes_since_a_revision_parse_each_file_as_its_language() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+    fs::write(
+        dir.path().join("src/tool.py"),
+        "def load():\n    pass\n\n\ndef save():\n    pass\n",
+    )
+    .unwrap();
+    let run = |program: &str, args: &[&str]| {
+        let output = Command::new(program)
+            .args(args)
+            .current_dir(dir.path())
+            .env("GIT_AUTHOR_NAME", "Ada")
+            .env("GIT_AUTHOR_EMAIL", "ada@example.com")
+            .env("GIT_COMMITTER_NAME", "Ada")
+            .env("GIT_COMMITTER_EMAIL", "ada@example.com")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output.stdout
+    };
+    run("git", &["init", "-q"]);
+    run("git", &["add", "-A"]);
+    run("git", &["commit", "-qm", "Initial commit"]);
+
+    fs::write(
+        dir.path().join("src/tool.py"),
+        "def load():\n    pass\n\n\ndef check():\n    pass\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn start() {}\n").unwrap();
+    let contextmesh = env!("CARGO_BIN_EXE_contextmesh");
+    run(contextmesh, &["index", "--language", "auto"]);
+
+    let changes: Vec<Value> = serde_json::from_slice(&run(
+        contextmesh,
+        &["changed", "--since", "HEAD", "--format", "json"],
+    ))
+    .unwrap();
+    let mut changes: Vec<(&str, &str)> = changes
+        .iter()
+        .map(|change| {
+            (
+                change["kind"].as_str().unwrap(),
+                change["name"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    changes.sort();
+    assert_eq!(
+        changes,
+        [
+            ("added", "check"),
+            ("added", "start"),
+            ("removed", "run"),
+            ("removed", "save"),
+        ]
+    );
+}
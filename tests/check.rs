@@ -0,0 +1,54 @@
+use std::fs;
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+mod common;
+use common::project;
+
+#[test]
+fn ci_checks_fail_on_unresolved_references_new_since_the_baseline() {
+    let dir = project("multi_module");
+    let contextmesh = |args: &[&str]| -> Output {
+        Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap()
+    };
+    assert!(contextmesh(&["index"]).status.success());
+    assert!(contextmesh(&["snapshot", "save", "base"]).status.success());
+    let check = ["check", "--ci", "--baseline", "base", "--format", "json"];
+
+    let output = contextmesh(&check);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let main = dir.path().join("src/main.rs");
+    let source = fs::read_to_string(&main).unwrap();
+    fs::write(
+        &main,
+        source.replace("client.send(", "missing_helper();\n    client.send("),
+    )
+    .unwrap();
+    assert!(contextmesh(&["index"]).status.success());
+
+    let output = contextmesh(&check);
+    assert!(!output.status.success());
+    let problems: Vec<Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert_eq!(problems[0]["kind"], "unresolved");
+    assert_eq!(problems[0]["file_path"], "./src/main.rs");
+    assert!(problems[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("`missing_helper`"));
+
+    // Without `--ci`, problems are reported but don't fail the command
+    let output = contextmesh(&["check", "--baseline", "base"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("missing_helper"));
+}
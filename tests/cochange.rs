@@ -0,0 +1,45 @@
+use std::collections::HashSet;
+
+use contextmesh::cochange::{evolving_with, from_history, MAX_FILES_PER_COMMIT};
+
+fn commit(time: u64, files: &[&str]) -> (u64, Vec<String>) {
+    (time, files.iter().map(|file| file.to_string()).collect())
+}
+
+#[test]
+fn files_changed_together_are_linked_by_share_of_commits() {
+    let huge: Vec<String> = (0..=MAX_FILES_PER_COMMIT)
+        .map(|n| format!("src/gen_{}.rs", n))
+        .chain(["src/a.rs".to_string(), "src/c.rs".to_string()])
+        .collect();
+    let history = [
+        commit(1, &["src/a.rs", "src/b.rs"]),
+        commit(2, &["src/a.rs", "src/b.rs", "src/c.rs"]),
+        commit(3, &["src/a.rs", "src/c.rs"]),
+        commit(4, &["src/a.rs"]),
+        commit(5, &["src/b.rs", "src/d.rs"]),
+        (6, huge.clone()),
+        (7, huge),
+    ];
+    let co_changes = from_history(&history);
+
+    // `a` changed 4 times, twice with `b` and twice with `c`; large commits don't count
+    assert_eq!(
+        co_changes["src/a.rs"],
+        [("src/b.rs".to_string(), 0.5), ("src/c.rs".to_string(), 0.5)]
+    );
+    // `b` changed 3 times; `d` only once with it, below the minimum support
+    assert_eq!(
+        co_changes["src/b.rs"],
+        [("src/a.rs".to_string(), 2.0 / 3.0)]
+    );
+    assert!(!co_changes.contains_key("src/d.rs"));
+    assert!(!co_changes.contains_key("src/gen_0.rs"));
+
+    let scores = evolving_with(&co_changes, &HashSet::from(["./src/b.rs", "src/c.rs"]));
+    assert_eq!(scores["src/b.rs"], 1.0);
+    assert_eq!(scores["src/c.rs"], 1.0);
+    // The heavier of the edges from `b` (2/3) and `c` (1)
+    assert_eq!(scores["src/a.rs"], 1.0);
+    assert!(!scores.contains_key("src/d.rs"));
+}
@@ -0,0 +1,115 @@
+use std::fs;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+mod common;
+use common::project;
+
+/// Runs `contextmesh` in `dir`, returning its standard output.
+fn contextmesh(dir: &TempDir, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// The header lines between the `# <path>` line of `path` and its content.
+fn header<'a>(bundle: &'a str, path: &str) -> Vec<&'a str> {
+    bundle
+        .lines()
+        .skip_while(|line| *line != format!("# {}", path))
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+fn whole_files_are_preceded_by_their_exports_dependencies_and_size() {
+    let dir = project("multi_module");
+    contextmesh(&dir, &["index"]);
+    let bundle = contextmesh(&dir, &["combine", "--sink", "stdout"]);
+
+    assert_eq!(
+        header(&bundle, "./src/net/client.rs"),
+        [
+            "Exports: Client, Client::connect, Client::send",
+            "Depends on: ./src/net/retry.rs",
+            "Tokens: 143",
+        ]
+    );
+    assert_eq!(
+        header(&bundle, "./src/main.rs"),
+        [
+            "Depends on: ./src/config.rs, ./src/net/client.rs",
+            "Tokens: 48"
+        ]
+    );
+    assert_eq!(header(&bundle, "./src/net/mod.rs"), ["Tokens: 8"]);
+}
+
+#[test]
+fn headers_leave_out_excluded_symbols() {
+    let dir = project("multi_module");
+    fs::create_dir_all(dir.path().join(".contextmesh")).unwrap();
+    fs::write(
+        dir.path().join(".contextmesh/config.toml"),
+        "[exclude]\nsymbols = [\"crate::net::client::Client::send\"]\n",
+    )
+    .unwrap();
+    contextmesh(&dir, &["index"]);
+    let bundle = contextmesh(&dir, &["combine", "--sink", "stdout"]);
+
+    // `send` was the only user of `retry.rs`
+    assert_eq!(
+        header(&bundle, "./src/net/client.rs"),
+        ["Exports: Client, Client::connect", "Tokens: 143"]
+    );
+    assert!(!bundle.contains("pub fn send"), "{}", bundle);
+}
+
+/// The paths of the files in a bundle, sorted.
+fn files(bundle: &str) -> Vec<&str> {
+    let mut files: Vec<&str> = bundle
+        .lines()
+        .filter_map(|line| line.strip_prefix("# ./"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn include_and_exclude_globs_pick_the_files_with_or_without_an_index() {
+    let dir = project("multi_module");
+    let args = [
+        "combine",
+        "--sink",
+        "stdout",
+        "--include",
+        "src/net/",
+        "--include",
+        "src/main.rs",
+        "--exclude",
+        "src/net/retry.rs",
+    ];
+    let expected = ["src/main.rs", "src/net/client.rs", "src/net/mod.rs"];
+
+    // Without an index, the files are collected from `./src`
+    assert_eq!(files(&contextmesh(&dir, &args)), expected);
+
+    contextmesh(&dir, &["index"]);
+    assert_eq!(files(&contextmesh(&dir, &args)), expected);
+
+    let bundle = contextmesh(
+        &dir,
+        &["combine", "--sink", "stdout", "--exclude", "src/net"],
+    );
+    assert_eq!(files(&bundle), ["src/config.rs", "src/main.rs"]);
+}
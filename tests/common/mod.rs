@@ -0,0 +1,75 @@
+//! Helpers shared by the integration tests.
+
+// Each test crate compiles this module but uses only some of the helpers
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+
+#[cfg(feature = "fixtures")]
+use contextmesh::fixtures::generate_rust_fixture;
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use contextmesh::symbol::Symbol;
+use tempfile::TempDir;
+
+/// Copies the fixture project `name` into a new temporary directory.
+pub fn project(name: &str) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    copy_dir(&fixture, dir.path());
+    dir
+}
+
+pub fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap().flatten() {
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// Writes `files` (relative path, source) into `dir` and indexes them one by one
+/// with the Rust parser.
+pub fn index_sources(dir: &TempDir, files: &[(&str, &str)]) -> Index {
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = Index::new();
+    for (name, source) in files {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+        index
+            .index_file(path.to_string_lossy().to_string(), &mut code_parser)
+            .unwrap();
+    }
+    index.recheck_unresolved();
+    index
+}
+
+/// Indexes a generated Rust project of `files` files with `fns_per_file`
+/// functions each.
+#[cfg(feature = "fixtures")]
+pub fn fixture_index(dir: &TempDir, files: usize, fns_per_file: usize) -> Index {
+    let paths: Vec<String> = generate_rust_fixture(dir.path(), files, fns_per_file)
+        .unwrap()
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = Index::new();
+    index.index_files(&paths, &mut code_parser).unwrap();
+    index.recheck_unresolved();
+    index
+}
+
+pub fn sorted_references(symbol: &Symbol) -> Vec<&str> {
+    let mut references: Vec<&str> = symbol.references.iter().map(String::as_str).collect();
+    references.sort();
+    references
+}
@@ -0,0 +1,90 @@
+use contextmesh::parser::components::ComponentIndexer;
+use contextmesh::parser::css::CssIndexer;
+use contextmesh::symbol::Symbol;
+
+mod common;
+use common::sorted_references;
+
+#[test]
+fn components_depend_on_rendered_components_and_applied_classes() {
+    let code = br#"import { Button as Btn } from "./ui/Button";
+import { Dialog } from "@mui/material";
+import styles from "./App.module.css";
+
+/** The application shell. */
+export const App = () => {
+  const [user] = useState<User>(null);
+  return (
+    <div className={clsx(styles.shell, "app app--dark")}>
+      <Btn label="Don't" />
+      <Dialog open />
+      <Menu.Item />
+    </div>
+  );
+};
+
+const API_URL = "https://example.com";
+
+class Sidebar extends React.Component {
+  render() {
+    return <aside className="sidebar" />;
+  }
+}
+"#;
+    let parsed = ComponentIndexer.parse("src/App.tsx", code);
+    let names: Vec<&str> = parsed.symbols.iter().map(|sym| sym.name.as_str()).collect();
+    assert_eq!(names, ["App", "Sidebar"]);
+    assert_eq!(
+        sorted_references(&parsed.symbols[0]),
+        [".app", ".app--dark", ".shell", "Button", "Menu::Item"]
+    );
+    assert_eq!(sorted_references(&parsed.symbols[1]), [".sidebar"]);
+    assert_eq!(
+        parsed.symbols[0].doc.as_deref(),
+        Some("The application shell.")
+    );
+    let app = &parsed.symbols[0];
+    assert!(code[app.start_byte..app.end_byte].ends_with(b"};"));
+}
+
+#[test]
+fn stylesheets_have_a_symbol_per_class_and_rule() {
+    let stylesheet = br#"/* Cards on the dashboard. */
+.card, .panel > .title:hover { padding: 1rem; }
+
+.card {
+  // The card's header.
+  &__header { font-weight: bold; }
+  &:hover { color: blue; }
+}
+
+@media (max-width: 600px) {
+  .card { padding: 0; }
+}
+
+@keyframes fade { from { opacity: 0; } to { opacity: 1; } }
+"#;
+    let parsed = CssIndexer.parse("src/dashboard.scss", stylesheet);
+    let names: Vec<&str> = parsed.symbols.iter().map(|sym| sym.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            ".card",
+            ".panel",
+            ".title",
+            ".card",
+            ".card__header",
+            ".card"
+        ]
+    );
+    assert!(parsed
+        .symbols
+        .iter()
+        .all(|sym| &*sym.node_kind == Symbol::CSS_CLASS_KIND));
+    assert_eq!(parsed.symbols[0].signature, ".card, .panel > .title:hover");
+    assert_eq!(
+        parsed.symbols[0].doc.as_deref(),
+        Some("Cards on the dashboard.")
+    );
+    assert_eq!(parsed.symbols[4].doc.as_deref(), Some("The card's header."));
+}
@@ -0,0 +1,117 @@
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use std::fs;
+use tempfile::TempDir;
+
+const PROTO: &str = r#"syntax = "proto3";
+
+message GetUserRequest { int64 id = 1; }
+message User { string email = 1; }
+
+service UserService {
+  rpc GetUser(GetUserRequest) returns (User);
+}
+"#;
+
+const CLIENT: &str = r#"async fn fetch() {
+    let mut client = UserServiceClient::connect("http://localhost:50051").await.unwrap();
+    client.get_user(GetUserRequest { id: 1 }).await.unwrap();
+}
+"#;
+
+fn dependency_names(index: &Index, name: &str) -> Vec<String> {
+    let sym = index.symbols.values().find(|sym| sym.name == name).unwrap();
+    let mut names: Vec<String> = sym
+        .dependencies
+        .iter()
+        .map(|id| index.symbol(*id).unwrap().name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn generated_code_usages_link_to_proto_definitions() {
+    let dir = TempDir::new().unwrap();
+    let proto_path = dir.path().join("user.proto");
+    let client_path = dir.path().join("client.rs");
+    fs::write(&proto_path, PROTO).unwrap();
+    fs::write(&client_path, CLIENT).unwrap();
+
+    let mut index = Index::new();
+    index
+        .index_file(
+            client_path.to_string_lossy().to_string(),
+            &mut CodeParser::new_rust().unwrap(),
+        )
+        .unwrap();
+    index
+        .index_file(
+            proto_path.to_string_lossy().to_string(),
+            &mut CodeParser::new_proto(),
+        )
+        .unwrap();
+    index.recheck_unresolved();
+
+    assert_eq!(
+        dependency_names(&index, "fetch"),
+        vec!["GetUserRequest", "UserService"]
+    );
+    assert_eq!(
+        dependency_names(&index, "GetUser"),
+        vec!["GetUserRequest", "User"]
+    );
+}
+
+const SPEC: &str = r#"openapi: 3.0.0
+paths:
+  /users/{id}:
+    get:
+      summary: Fetch one user
+    delete:
+      summary: Delete a user
+"#;
+
+const HANDLERS: &str = r#"fn routes() -> Router {
+    Router::new().route("/users/:id", get(show_user))
+}
+
+#[delete("/users/{id}")]
+async fn delete_user() {}
+"#;
+
+#[test]
+fn route_strings_link_code_to_openapi_endpoints() {
+    let dir = TempDir::new().unwrap();
+    let spec_path = dir.path().join("openapi.yaml");
+    let handlers_path = dir.path().join("handlers.rs");
+    fs::write(&spec_path, SPEC).unwrap();
+    fs::write(&handlers_path, HANDLERS).unwrap();
+
+    let mut index = Index::new();
+    index
+        .index_file(
+            handlers_path.to_string_lossy().to_string(),
+            &mut CodeParser::new_rust().unwrap(),
+        )
+        .unwrap();
+    // Routes without an endpoint aren't kept as unresolved references
+    assert!(index
+        .unresolved_references()
+        .all(|(_, name)| !name.contains("/users")));
+    index
+        .index_file(
+            spec_path.to_string_lossy().to_string(),
+            &mut CodeParser::new_structured(),
+        )
+        .unwrap();
+
+    // Without a method the route matches every operation of the path
+    let routes = dependency_names(&index, "routes");
+    assert!(routes.contains(&"GET /users/{id}".to_string()));
+    assert!(routes.contains(&"DELETE /users/{id}".to_string()));
+    assert_eq!(
+        dependency_names(&index, "delete_user"),
+        vec!["DELETE /users/{id}"]
+    );
+}
@@ -0,0 +1,69 @@
+//! Runs commands through `contextmesh daemon` on a copy of a fixture project.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::project;
+
+fn contextmesh(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+/// Stops the daemon when the test ends, however it ends.
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn commands_reading_the_index_are_forwarded_to_a_private_socket() {
+    let dir = project("multi_module");
+    assert!(contextmesh(dir.path(), &["index"]).status.success());
+
+    let _daemon = Daemon(
+        Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .arg("daemon")
+            // Often set for other programs; without `otel`, it is ignored
+            .env("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318")
+            .current_dir(dir.path())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    let socket = dir.path().join(".contextmesh/index.sock");
+    let started = Instant::now();
+    while !socket.exists() {
+        assert!(
+            started.elapsed() < Duration::from_secs(20),
+            "the daemon never listened"
+        );
+        sleep(Duration::from_millis(50));
+    }
+    let mode = fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // Only the daemon still has the index, in memory
+    fs::remove_file(dir.path().join(".contextmesh/index.bin")).unwrap();
+    let output = contextmesh(dir.path(), &["search", "connect", "--format", "csv"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("connect"));
+}
@@ -0,0 +1,82 @@
+use contextmesh::datalog::{Database, Program};
+use contextmesh::index::Index;
+use tempfile::TempDir;
+
+mod common;
+use common::fixture_index;
+
+/// The first column of `relation` after evaluating `rules`.
+fn evaluate(index: &Index, rules: &str, relation: &str) -> Vec<String> {
+    let mut program = Program::default();
+    program.parse(rules, "test.dl").unwrap();
+    let mut db = Database::from_index(index);
+    db.evaluate(&program).unwrap();
+    db.tuples(relation)
+        .into_iter()
+        .map(|tuple| tuple[0].to_string())
+        .collect()
+}
+
+#[test]
+fn recursive_rules_reach_a_fixpoint() {
+    let dir = TempDir::new().unwrap();
+    let index = fixture_index(&dir, 3, 2);
+
+    // module_N_fn_M calls module_N_fn_{M-1} and module_{N-1}_fn_M
+    let rules = r#"
+        reaches(X, Y) :- depends(X, Y).
+        reaches(X, Z) :- reaches(X, Y), depends(Y, Z).
+        from_top(N) :- name(X, "module_2_fn_1"), reaches(X, Y), name(Y, N).
+    "#;
+    assert_eq!(
+        evaluate(&index, rules, "from_top"),
+        [
+            "module_0_fn_0",
+            "module_0_fn_1",
+            "module_1_fn_0",
+            "module_1_fn_1",
+            "module_2_fn_0"
+        ]
+    );
+}
+
+#[test]
+fn negation_and_builtins_filter_tuples() {
+    let dir = TempDir::new().unwrap();
+    let index = fixture_index(&dir, 3, 2);
+
+    let rules = r#"
+        .decl calls(caller: symbol)
+        calls(X) :- depends(X, _).
+        leaf(N) :- kind(X, "function_item"), !calls(X), name(X, N).
+        other(N) :- name(X, N), match("module_[12]_fn_.*", N), N != "module_1_fn_0",
+                    contains("fn_0", N).
+    "#;
+    assert_eq!(evaluate(&index, rules, "leaf"), ["module_0_fn_0"]);
+    assert_eq!(evaluate(&index, rules, "other"), ["module_2_fn_0"]);
+}
+
+#[test]
+fn invalid_programs_are_rejected() {
+    let dir = TempDir::new().unwrap();
+    let index = fixture_index(&dir, 3, 2);
+
+    for rules in ["p(X) :- symbol(X)", "p(X) :- q(X", ".input p", "p(X) :- @."] {
+        assert!(
+            Program::default().parse(rules, "test.dl").is_err(),
+            "{}",
+            rules
+        );
+    }
+    for rules in [
+        "p(X) :- symbol(X), !p(X).",
+        "p(X, Y) :- symbol(X).",
+        "p(X) :- depends(X).",
+        "p(_) :- symbol(X).",
+    ] {
+        let mut program = Program::default();
+        program.parse(rules, "test.dl").unwrap();
+        let mut db = Database::from_index(&index);
+        assert!(db.evaluate(&program).is_err(), "{}", rules);
+    }
+}
@@ -0,0 +1,44 @@
+use tempfile::TempDir;
+
+mod common;
+use common::index_sources;
+
+/// The paths `sources` are written to by `index_sources`.
+fn paths(dir: &TempDir, sources: &[(&str, &str)]) -> Vec<String> {
+    sources
+        .iter()
+        .map(|(name, _)| dir.path().join(name).to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn dependencies_come_before_their_users() {
+    let dir = TempDir::new().unwrap();
+    let sources = [
+        ("a.rs", "fn a() { b(); c(); }\n"),
+        ("b.rs", "fn b() { c(); }\n"),
+        ("c.rs", "fn c() {}\n"),
+        ("d.rs", "fn d() {}\n"),
+    ];
+    let index = index_sources(&dir, &sources);
+    let paths = paths(&dir, &sources);
+    let files: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let order = index.dependency_order(&files);
+    assert_eq!(order, [&*paths[2], &*paths[1], &*paths[0], &*paths[3]]);
+}
+
+#[test]
+fn cycles_still_give_every_file_once() {
+    let dir = TempDir::new().unwrap();
+    let sources = [
+        ("a.rs", "fn a() { b(); }\n"),
+        ("b.rs", "fn b() { a(); c(); }\n"),
+        ("c.rs", "fn c() {}\n"),
+    ];
+    let index = index_sources(&dir, &sources);
+    let paths = paths(&dir, &sources);
+    let files: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let order = index.dependency_order(&files);
+    // `c` first, then the cycle, entered at `a` as the first by path
+    assert_eq!(order, [&*paths[2], &*paths[0], &*paths[1]]);
+}
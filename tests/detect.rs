@@ -0,0 +1,40 @@
+use std::fs;
+
+use contextmesh::config::{Config, LanguageConfig};
+use contextmesh::detect::LanguageDetector;
+use tempfile::TempDir;
+
+#[test]
+fn languages_are_detected_from_overrides_names_and_shebangs() {
+    let mut config = Config::default();
+    config
+        .file_types
+        .insert(".inc".to_string(), "php".to_string());
+    config
+        .file_types
+        .insert("Jenkinsfile".to_string(), "groovy".to_string());
+    let starlark = LanguageConfig {
+        extensions: vec!["bzl".to_string()],
+        ..Default::default()
+    };
+    config.languages.insert("starlark".to_string(), starlark);
+    let detector = LanguageDetector::new(&config);
+
+    let detect = |path: &str| detector.detect(path);
+    assert_eq!(detect("src/lib.rs").as_deref(), Some("rust"));
+    assert_eq!(detect("web/App.tsx").as_deref(), Some("jsx"));
+    assert_eq!(detect("deploy/Dockerfile.prod").as_deref(), Some("docker"));
+    assert_eq!(detect("lib/server.go").as_deref(), Some("go"));
+    assert_eq!(detect("templates/header.inc").as_deref(), Some("php"));
+    assert_eq!(detect("ci/Jenkinsfile").as_deref(), Some("groovy"));
+    assert_eq!(detect("build/defs.bzl").as_deref(), Some("starlark"));
+    assert_eq!(detect("assets/logo.png"), None);
+
+    let dir = TempDir::new().unwrap();
+    let script = dir.path().join("release");
+    fs::write(&script, "#!/usr/bin/env -S python3.12 -u\nprint()\n").unwrap();
+    let binary = dir.path().join("data");
+    fs::write(&binary, "no shebang").unwrap();
+    assert_eq!(detect(script.to_str().unwrap()).as_deref(), Some("python"));
+    assert_eq!(detect(binary.to_str().unwrap()), None);
+}
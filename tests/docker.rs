@@ -0,0 +1,214 @@
+use contextmesh::index::{EdgeConfidence, Index};
+use contextmesh::parser::docker::DockerIndexer;
+use contextmesh::parser::structured::StructuredIndexer;
+use contextmesh::parser::CodeParser;
+use contextmesh::symbol::Symbol;
+use contextmesh::utils::collect_files;
+use std::fs;
+use tempfile::TempDir;
+
+mod common;
+use common::sorted_references;
+
+#[test]
+fn stages_depend_on_earlier_stages_and_keep_copied_paths() {
+    let dockerfile = br#"# Compiles the server.
+FROM rust:1.80 AS builder
+COPY Cargo.toml ./
+COPY src/ ./src/
+COPY --chown=app assets/*.png ../shared/config.toml /app/
+RUN cargo build \
+    --release
+
+FROM debian:bookworm-slim
+COPY --from=0 /app/target/release/api /usr/local/bin/api
+COPY ["migrations", "https://example.com/x", "/app/"]
+RUN <<EOF
+FROM nothing
+EOF
+"#;
+    let parsed = DockerIndexer.parse("services/api/Dockerfile", dockerfile);
+    let names: Vec<&str> = parsed.symbols.iter().map(|sym| sym.name.as_str()).collect();
+    assert_eq!(names, ["builder", "debian:bookworm-slim"]);
+    assert!(parsed
+        .symbols
+        .iter()
+        .all(|sym| &*sym.node_kind == Symbol::DOCKER_STAGE_KIND));
+
+    // Copied paths are kept, but aren't references to resolve
+    assert!(parsed.symbols[0].references.is_empty());
+    assert_eq!(
+        parsed.symbols[0].literals.iter().collect::<Vec<_>>(),
+        [
+            "services/api/Cargo.toml",
+            "services/api/assets",
+            "services/api/src",
+            "services/shared/config.toml",
+        ]
+    );
+    assert_eq!(sorted_references(&parsed.symbols[1]), ["builder"]);
+    assert_eq!(
+        parsed.symbols[1].literals.iter().collect::<Vec<_>>(),
+        ["services/api/migrations"]
+    );
+    assert_eq!(parsed.symbols[0].signature, "FROM rust:1.80 AS builder");
+    assert_eq!(
+        parsed.symbols[0].doc.as_deref(),
+        Some("Compiles the server.")
+    );
+}
+
+#[test]
+fn compose_services_depend_on_their_dependencies_and_dockerfiles() {
+    let compose = br#"services:
+  api:
+    build: ./services/api
+    depends_on:
+      - db
+  web:
+    build:
+      context: web
+      dockerfile: Dockerfile.dev
+    depends_on:
+      api:
+        condition: service_started
+  db:
+    image: postgres
+"#;
+    let parsed = StructuredIndexer.parse("deploy/compose.yaml", compose);
+    let service = |name: &str| {
+        parsed
+            .symbols
+            .iter()
+            .find(|sym| sym.name == name)
+            .expect(name)
+    };
+    for name in ["api", "web", "db"] {
+        assert_eq!(&*service(name).node_kind, Symbol::COMPOSE_SERVICE_KIND);
+    }
+    assert_eq!(
+        sorted_references(service("api")),
+        ["db", "path:deploy/services/api/Dockerfile"]
+    );
+    assert_eq!(
+        sorted_references(service("web")),
+        ["api", "path:deploy/web/Dockerfile.dev"]
+    );
+
+    // Other YAML files are left alone
+    let parsed = StructuredIndexer.parse("config.yaml", compose);
+    assert!(parsed
+        .symbols
+        .iter()
+        .all(|sym| &*sym.node_kind != Symbol::COMPOSE_SERVICE_KIND));
+}
+
+const DOCKERFILE: &str = "FROM rust:1.80 AS builder\nCOPY src/ ./src/\n\nFROM debian AS api\nCOPY --from=builder /app /app\n";
+
+const COMPOSE: &str =
+    "services:\n  api:\n    build: .\n    depends_on:\n      - db\n  db:\n    image: postgres\n";
+
+const CODE: &str =
+    "pub fn builder() {}\n\npub fn db() {}\n\npub fn unused() {}\n\npub fn api() {\n    db();\n}\n";
+
+fn dependency_names(index: &Index, name: &str, kind: &str) -> Vec<String> {
+    let sym = index
+        .symbols
+        .values()
+        .find(|sym| sym.name == name && &*sym.node_kind == kind)
+        .unwrap();
+    let mut names: Vec<String> = sym
+        .dependencies
+        .iter()
+        .map(|id| index.symbol(*id).unwrap().name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn stage_and_service_names_stay_in_their_file_and_copies_use_nothing() {
+    let dir = TempDir::new().unwrap();
+    let files = [
+        ("Dockerfile", DOCKERFILE),
+        ("compose.yaml", COMPOSE),
+        ("src/lib.rs", CODE),
+    ];
+    for (name, source) in files {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+    let mut index = Index::new();
+    index
+        .index_file(path("Dockerfile"), &mut CodeParser::new_docker())
+        .unwrap();
+    index
+        .index_file(path("compose.yaml"), &mut CodeParser::new_structured())
+        .unwrap();
+    index
+        .index_file(path("src/lib.rs"), &mut CodeParser::new_rust().unwrap())
+        .unwrap();
+    index.recheck_unresolved();
+
+    // Names resolve among the stages or services of the same file only
+    assert_eq!(
+        dependency_names(&index, "api", Symbol::DOCKER_STAGE_KIND),
+        ["builder"]
+    );
+    assert_eq!(
+        dependency_names(&index, "api", Symbol::COMPOSE_SERVICE_KIND),
+        ["api", "builder", "db"]
+    );
+    assert_eq!(dependency_names(&index, "api", "function_item"), ["db"]);
+    let code = |name: &str| {
+        index
+            .symbols
+            .iter()
+            .find(|(_, sym)| sym.name == name && &*sym.node_kind == "function_item")
+            .unwrap()
+    };
+    for name in ["builder", "db", "unused"] {
+        assert!(code(name)
+            .1
+            .used_by
+            .iter()
+            .all(|id| { &*index.symbol(*id).unwrap().node_kind == "function_item" }));
+    }
+
+    // Copying `src/` doesn't use what's in it, but the stage still reaches it
+    let (builder, _) = index
+        .symbols
+        .iter()
+        .find(|(_, sym)| sym.name == "builder" && sym.is_docker())
+        .unwrap();
+    assert!(code("unused").1.used_by.is_empty());
+    let mut copied: Vec<&str> = index
+        .copied_symbols(builder)
+        .iter()
+        .map(|hash| index.symbols[hash].name.as_str())
+        .collect();
+    copied.sort();
+    assert_eq!(copied, ["api", "builder", "db", "unused"]);
+    let reachable = index.reachable_from([builder.as_str()], EdgeConfidence::Exact);
+    assert!(reachable.contains(code("unused").0));
+}
+
+#[test]
+fn files_named_like_an_extension_are_not_collected() {
+    let dir = TempDir::new().unwrap();
+    for name in ["sh", "run.sh", "Dockerfile", "Dockerfile.dev", "dockerfile"] {
+        fs::write(dir.path().join(name), "").unwrap();
+    }
+    let mut names: Vec<String> = collect_files(
+        dir.path().to_str().unwrap(),
+        &["sh", "Dockerfile", "Dockerfile."],
+        false,
+    )
+    .iter()
+    .map(|path| path.rsplit('/').next().unwrap().to_string())
+    .collect();
+    names.sort();
+    assert_eq!(names, ["Dockerfile", "Dockerfile.dev", "run.sh"]);
+}
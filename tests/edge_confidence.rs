@@ -0,0 +1,176 @@
+use contextmesh::config::IndexConfig;
+use contextmesh::datalog::{Database, Program};
+use contextmesh::index::{EdgeConfidence, Index};
+use contextmesh::query::Query;
+use tempfile::TempDir;
+
+mod common;
+use common::index_sources;
+
+/// (file name, confidence) of every dependency named `dep` of the symbol `user`.
+fn edges(index: &Index, user: &str, dep: &str) -> Vec<(String, EdgeConfidence)> {
+    let (user_hash, sym) = index
+        .symbols
+        .iter()
+        .find(|(_, sym)| sym.name == user)
+        .unwrap();
+    let mut edges: Vec<(String, EdgeConfidence)> = sym
+        .dependencies
+        .iter()
+        .filter_map(|id| {
+            let dep_hash = index.hash_of(*id)?;
+            let target = index.symbol(*id).filter(|target| target.name == dep)?;
+            let file = target.file_path.rsplit('/').next().unwrap().to_string();
+            Some((file, index.edge_confidence(user_hash, dep_hash)))
+        })
+        .collect();
+    edges.sort();
+    edges
+}
+
+const SOURCES: &[(&str, &str)] = &[
+    ("src/a.rs", "pub fn run() {}\n"),
+    ("src/b.rs", "pub fn run() {}\n"),
+    (
+        "src/c.rs",
+        "use crate::a::*;\n\nfn start() {\n    run();\n    helper();\n}\n\nfn helper() {}\n",
+    ),
+    ("src/d.rs", "fn guess() {\n    run();\n}\n"),
+    ("src/e.rs", "fn scoped() {\n    crate::b::run();\n}\n"),
+];
+
+#[test]
+fn edges_record_how_they_were_resolved() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(&dir, SOURCES);
+
+    assert_eq!(
+        edges(&index, "start", "run"),
+        vec![("a.rs".to_string(), EdgeConfidence::Imported)]
+    );
+    assert_eq!(
+        edges(&index, "start", "helper"),
+        vec![("c.rs".to_string(), EdgeConfidence::Exact)]
+    );
+    assert_eq!(
+        edges(&index, "scoped", "run"),
+        vec![("b.rs".to_string(), EdgeConfidence::Exact)]
+    );
+    assert_eq!(
+        edges(&index, "guess", "run"),
+        vec![
+            ("a.rs".to_string(), EdgeConfidence::NameOnly),
+            ("b.rs".to_string(), EdgeConfidence::NameOnly),
+        ]
+    );
+}
+
+#[test]
+fn confidences_survive_a_save_and_load() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(&dir, SOURCES);
+    let path = dir.path().join("index.bin");
+    index.save_index_to(&path, &IndexConfig::default()).unwrap();
+    let loaded = Index::load_index_from(&path).unwrap();
+
+    assert_eq!(
+        edges(&loaded, "guess", "run"),
+        vec![
+            ("a.rs".to_string(), EdgeConfidence::NameOnly),
+            ("b.rs".to_string(), EdgeConfidence::NameOnly),
+        ]
+    );
+    assert_eq!(
+        edges(&loaded, "start", "run"),
+        vec![("a.rs".to_string(), EdgeConfidence::Imported)]
+    );
+    assert!(loaded.invariant_violations().is_empty());
+}
+
+#[test]
+fn reachability_can_skip_name_only_edges() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(&dir, SOURCES);
+    let guess = index
+        .symbols
+        .iter()
+        .find(|(_, sym)| sym.name == "guess")
+        .map(|(hash, _)| hash.as_str())
+        .unwrap();
+
+    assert_eq!(
+        index
+            .reachable_from([guess], EdgeConfidence::NameOnly)
+            .len(),
+        3
+    );
+    assert_eq!(
+        index
+            .reachable_from([guess], EdgeConfidence::Imported)
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn queries_can_skip_less_sure_edges() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(&dir, SOURCES);
+    let files = |expression: &str| {
+        let mut files: Vec<&str> = Query::parse(expression)
+            .unwrap()
+            .evaluate(&index)
+            .into_iter()
+            .map(|hash| index.symbols[hash].file_path.rsplit('/').next().unwrap())
+            .collect();
+        files.sort();
+        files
+    };
+
+    assert_eq!(files("deps of name:guess"), ["a.rs", "b.rs"]);
+    assert!(files("deps(confidence>=imported) of name:guess").is_empty());
+    assert_eq!(
+        files("deps(depth<=1, confidence>=imported) of name:start"),
+        ["a.rs", "c.rs"]
+    );
+    assert_eq!(files("deps(*, confidence>=exact) of name:start"), ["c.rs"]);
+    assert_eq!(
+        files("users(confidence>=name-only) of name:run"),
+        ["c.rs", "d.rs", "e.rs"]
+    );
+    assert!(Query::parse("deps(confidence>=sure) of name:start").is_err());
+}
+
+#[test]
+fn datalog_sees_the_confidence_of_each_edge() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(&dir, SOURCES);
+    let mut program = Program::default();
+    program
+        .parse(
+            r#"guessed(U, F) :- confidence(X, Y, "name-only"), name(X, U), file(Y, F)."#,
+            "test.dl",
+        )
+        .unwrap();
+    let mut db = Database::from_index(&index);
+    db.evaluate(&program).unwrap();
+
+    let mut guessed: Vec<(String, String)> = db
+        .tuples("guessed")
+        .into_iter()
+        .map(|tuple| {
+            (
+                tuple[0].to_string(),
+                tuple[1].rsplit('/').next().unwrap().to_string(),
+            )
+        })
+        .collect();
+    guessed.sort();
+    assert_eq!(
+        guessed,
+        [
+            ("guess".to_string(), "a.rs".to_string()),
+            ("guess".to_string(), "b.rs".to_string()),
+        ]
+    );
+}
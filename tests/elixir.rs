@@ -0,0 +1,81 @@
+use contextmesh::config::LanguageConfig;
+use contextmesh::parser::elixir_indexer::{expand_alias, last_segment};
+use contextmesh::parser::{CodeParser, Imports};
+use contextmesh::symbol::Visibility;
+
+#[test]
+fn aliases_expand_to_their_full_module() {
+    let mut imports = Imports::default();
+    imports
+        .aliases
+        .insert("Accounts".to_string(), "MyApp.Accounts".to_string());
+
+    assert_eq!(
+        expand_alias("Accounts", &imports).as_deref(),
+        Some("MyApp.Accounts")
+    );
+    assert_eq!(
+        expand_alias("Accounts.User", &imports).as_deref(),
+        Some("MyApp.Accounts.User")
+    );
+    assert_eq!(expand_alias("Repo", &imports), None);
+    assert_eq!(last_segment("MyApp.Accounts.User"), "User");
+    assert_eq!(last_segment("Repo"), "Repo");
+}
+
+const SOURCE: &str = r#"defmodule MyApp.Accounts do
+  @moduledoc """
+  Manages accounts.
+  """
+  alias MyApp.{Repo, Mailer}
+  alias MyApp.Accounts.User, as: Account
+
+  @doc "Fetches a user."
+  @spec get_user(integer()) :: Account.t()
+  def get_user(id) when is_integer(id) do
+    Repo.get(Account, id)
+  end
+
+  defp notify(user) do
+    Mailer.deliver(user)
+  end
+
+  defmodule Admin do
+  end
+end
+"#;
+
+#[test]
+fn elixir_definitions_are_told_apart_by_their_macro() {
+    let mut parser = CodeParser::new_elixir(&LanguageConfig::default()).unwrap();
+    let parsed = parser
+        .parse_source("lib/accounts.ex", SOURCE.as_bytes().to_vec())
+        .unwrap();
+    let symbol = |name: &str| {
+        parsed
+            .symbols
+            .iter()
+            .find(|sym| sym.name == name)
+            .unwrap_or_else(|| panic!("no symbol {}", name))
+    };
+
+    let module = symbol("MyApp.Accounts");
+    assert_eq!(&*module.node_kind, "defmodule");
+    assert_eq!(module.doc.as_deref(), Some("Manages accounts."));
+    assert_eq!(&*symbol("MyApp.Accounts.Admin").node_kind, "defmodule");
+
+    let get_user = symbol("get_user");
+    assert_eq!(&*get_user.node_kind, "def");
+    assert_eq!(get_user.visibility, Visibility::Public);
+    assert_eq!(get_user.signature, "def get_user(id) when is_integer(id)");
+    assert_eq!(get_user.doc.as_deref(), Some("Fetches a user."));
+    assert!(get_user.references.contains("MyApp.Repo::get"));
+
+    let notify = symbol("notify");
+    assert_eq!(&*notify.node_kind, "defp");
+    assert_eq!(notify.visibility, Visibility::Private);
+    assert!(notify.references.contains("MyApp.Mailer::deliver"));
+
+    assert_eq!(parsed.imports.aliases["Account"], "MyApp.Accounts.User");
+    assert_eq!(parsed.imports.aliases["Repo"], "MyApp.Repo");
+}
@@ -0,0 +1,308 @@
+//! Runs `contextmesh index` on copies of the projects in `tests/fixtures/`,
+//! changes them, and re-indexes, checking the index after every run.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use contextmesh::errors::ContextMeshError;
+use contextmesh::index::{Index, FORMAT_VERSION};
+use tempfile::TempDir;
+
+mod common;
+use common::{index_sources, project};
+
+/// Indexes the project in `dir` and loads the index, checking its invariants.
+fn index(dir: &Path) -> Index {
+    let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+        .arg("index")
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let index = Index::load_index_from(&dir.join(".contextmesh/index.bin")).unwrap();
+    assert_invariants(&index, dir);
+    index
+}
+
+/// Every edge points at a live symbol and is mirrored by the other end, and
+/// every indexed file and symbol belongs to a file that exists.
+fn assert_invariants(index: &Index, dir: &Path) {
+    for (hash, sym) in &index.symbols {
+        assert_eq!(
+            *hash,
+            sym.hash(),
+            "'{}' is stored under a stale hash",
+            sym.name
+        );
+        for dep_id in &sym.dependencies {
+            let dep = index
+                .symbol(*dep_id)
+                .unwrap_or_else(|| panic!("'{}' depends on a removed symbol", sym.name));
+            assert!(
+                dep.used_by
+                    .iter()
+                    .any(|id| index.hash_of(*id) == Some(hash)),
+                "'{}' is missing the backlink from '{}'",
+                dep.name,
+                sym.name
+            );
+        }
+        for user_id in &sym.used_by {
+            let user = index
+                .symbol(*user_id)
+                .unwrap_or_else(|| panic!("'{}' is used by a removed symbol", sym.name));
+            assert!(
+                user.dependencies
+                    .iter()
+                    .any(|id| index.hash_of(*id) == Some(hash)),
+                "'{}' has a stale backlink from '{}'",
+                sym.name,
+                user.name
+            );
+        }
+        assert!(
+            index.file_hashes.contains_key(&*sym.file_path),
+            "'{}' is in the unindexed file '{}'",
+            sym.name,
+            sym.file_path
+        );
+    }
+    for path in index.file_hashes.keys() {
+        assert!(path.starts_with("./"), "'{}' isn't root-relative", path);
+        assert!(dir.join(path).is_file(), "'{}' no longer exists", path);
+    }
+}
+
+/// The `(name, file)` pairs of the symbols `name` depends on.
+fn dependencies(index: &Index, name: &str) -> BTreeSet<(String, String)> {
+    index
+        .symbols
+        .values()
+        .filter(|sym| sym.name == name)
+        .flat_map(|sym| &sym.dependencies)
+        .filter_map(|id| index.symbol(*id))
+        .map(|dep| (dep.name.clone(), dep.file_path.to_string()))
+        .collect()
+}
+
+fn names_in(index: &Index, file: &str) -> BTreeSet<String> {
+    index
+        .symbols
+        .values()
+        .filter(|sym| *sym.file_path == *file)
+        .map(|sym| sym.name.clone())
+        .collect()
+}
+
+/// Each symbol hash with the hashes of the symbols it depends on.
+fn graph(index: &Index) -> BTreeSet<(String, BTreeSet<String>)> {
+    index
+        .symbols
+        .iter()
+        .map(|(hash, sym)| {
+            let deps = sym
+                .dependencies
+                .iter()
+                .filter_map(|id| index.hash_of(*id))
+                .map(str::to_string)
+                .collect();
+            (hash.clone(), deps)
+        })
+        .collect()
+}
+
+fn pair(name: &str, file: &str) -> (String, String) {
+    (name.to_string(), file.to_string())
+}
+
+#[test]
+fn modules_are_linked_across_files() {
+    let dir = project("multi_module");
+    let index = index(dir.path());
+
+    assert_eq!(index.file_hashes.len(), 5);
+    assert_eq!(
+        dependencies(&index, "send"),
+        BTreeSet::from([
+            pair("with_retries", "./src/net/retry.rs"),
+            pair("transmit", "./src/net/client.rs"),
+        ])
+    );
+    assert!(dependencies(&index, "main").contains(&pair("load", "./src/config.rs")));
+}
+
+#[test]
+fn indexing_is_deterministic_and_independent_of_the_location() {
+    let first = project("multi_module");
+    let second = project("multi_module");
+    let graph_first = graph(&index(first.path()));
+    assert_eq!(graph_first, graph(&index(second.path())));
+
+    // Re-indexing unchanged files changes nothing
+    assert_eq!(graph_first, graph(&index(first.path())));
+}
+
+#[test]
+fn a_modified_file_is_reparsed_and_relinked() {
+    let dir = project("multi_module");
+    index(dir.path());
+
+    let retry = dir.path().join("src/net/retry.rs");
+    let mut source = fs::read_to_string(&retry).unwrap();
+    source.push_str("\npub fn backoff(attempt: u32) -> u64 {\n    1 << attempt\n}\n");
+    source = source.replace(
+        "(0..retries).any(|_| attempt())",
+        "(0..retries).any(|n| { backoff(n); attempt() })",
+    );
+    fs::write(&retry, source).unwrap();
+    let index = index(dir.path());
+
+    assert_eq!(
+        names_in(&index, "./src/net/retry.rs"),
+        BTreeSet::from(["backoff".to_string(), "with_retries".to_string()])
+    );
+    assert_eq!(
+        dependencies(&index, "with_retries"),
+        BTreeSet::from([pair("backoff", "./src/net/retry.rs")])
+    );
+    // The users in unchanged files still point at the reparsed symbol
+    assert!(dependencies(&index, "send").contains(&pair("with_retries", "./src/net/retry.rs")));
+}
+
+#[test]
+fn a_renamed_file_replaces_the_old_one() {
+    let dir = project("multi_module");
+    index(dir.path());
+
+    let src = dir.path().join("src");
+    fs::rename(src.join("config.rs"), src.join("settings.rs")).unwrap();
+    let main = fs::read_to_string(src.join("main.rs")).unwrap();
+    fs::write(src.join("main.rs"), main.replace("config", "settings")).unwrap();
+    let client = fs::read_to_string(src.join("net/client.rs")).unwrap();
+    fs::write(
+        src.join("net/client.rs"),
+        client.replace("config", "settings"),
+    )
+    .unwrap();
+    let index = index(dir.path());
+
+    assert!(names_in(&index, "./src/config.rs").is_empty());
+    assert!(names_in(&index, "./src/settings.rs").contains("Settings"));
+    assert!(dependencies(&index, "main").contains(&pair("load", "./src/settings.rs")));
+}
+
+#[test]
+fn a_deleted_file_leaves_its_users_unresolved() {
+    let dir = project("multi_module");
+    index(dir.path());
+
+    fs::remove_file(dir.path().join("src/net/retry.rs")).unwrap();
+    let index = index(dir.path());
+
+    assert_eq!(index.file_hashes.len(), 4);
+    assert!(names_in(&index, "./src/net/retry.rs").is_empty());
+    assert_eq!(
+        dependencies(&index, "send"),
+        BTreeSet::from([pair("transmit", "./src/net/client.rs")])
+    );
+    assert!(index
+        .unresolved_references()
+        .any(|(user, name)| user.name == "send" && name.ends_with("with_retries")));
+}
+
+#[test]
+fn symbols_of_a_kind_share_their_node_kind() {
+    let shared = |index: &Index| {
+        let functions: Vec<_> = index
+            .symbols
+            .values()
+            .filter(|sym| &*sym.node_kind == "function_item")
+            .collect();
+        assert!(functions.len() > 1);
+        functions
+            .iter()
+            .all(|sym| Arc::ptr_eq(&sym.node_kind, &functions[0].node_kind))
+    };
+
+    let dir = project("multi_module");
+    assert!(shared(&index(dir.path())));
+    let dir = TempDir::new().unwrap();
+    let parsed = index_sources(
+        &dir,
+        &[
+            (
+                "src/a.rs",
+                "fn one() {}
+fn two() {}
+",
+            ),
+            (
+                "src/b.rs",
+                "fn three() {}
+",
+            ),
+        ],
+    );
+    assert!(shared(&parsed));
+}
+
+#[test]
+fn an_index_of_another_format_asks_for_a_rebuild() {
+    let dir = project("multi_module");
+    index(dir.path());
+    let path = dir.path().join(".contextmesh/index.bin");
+    let data = Index::decompressed(fs::read(&path).unwrap()).unwrap();
+    let load = |data: &[u8]| {
+        fs::write(&path, data).unwrap();
+        match Index::load_index_from(&path) {
+            Err(ContextMeshError::IndexFormat { found, .. }) => found,
+            other => panic!("expected a format error, got {:?}", other.map(|_| ())),
+        }
+    };
+
+    // Written before indexes had a format header
+    assert_eq!(load(&data[8..]), None);
+    let mut newer = data.clone();
+    newer[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert_eq!(load(&newer), Some(FORMAT_VERSION + 1));
+
+    // Indexing again starts over
+    assert_eq!(index(dir.path()).file_hashes.len(), 5);
+}
+
+#[test]
+fn changed_symbol_kinds_reindex_only_their_language() {
+    let dir = project("multi_module");
+    fs::write(dir.path().join("src/tool.py"), "def load():\n    pass\n").unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Index::load_index_from(&dir.path().join(".contextmesh/index.bin")).unwrap()
+    };
+    run(&["index", "--language", "auto"]);
+
+    fs::write(
+        dir.path().join(".contextmesh/config.toml"),
+        "[languages.rust]\nskip_kinds = [\"struct_item\"]\n",
+    )
+    .unwrap();
+    let index = run(&["index", "--language", "rust"]);
+    assert!(index.file_hashes.contains_key("./src/tool.py"));
+    assert!(!names_in(&index, "./src/config.rs").contains("Settings"));
+    assert!(names_in(&index, "./src/tool.py").contains("load"));
+}
@@ -0,0 +1,79 @@
+use contextmesh::config::{EntryPointsConfig, IndexConfig};
+use contextmesh::index::Index;
+use tempfile::TempDir;
+
+mod common;
+use common::index_sources;
+
+const SOURCES: &[(&str, &str)] = &[
+    (
+        "src/main.rs",
+        "fn main() {\n    start();\n}\n\nfn start() {}\n",
+    ),
+    (
+        "src/handlers.rs",
+        "#[get(\"/users\")]\nfn list_users() {\n    load();\n}\n\nfn load() {}\n\nfn unused() {}\n",
+    ),
+    (
+        "src/api.rs",
+        "pub fn exported() {\n    helper();\n}\n\nfn helper() {}\n",
+    ),
+];
+
+/// Sorted names of the symbols `select` picks by hash.
+fn names(index: &Index, select: impl Fn(&str) -> bool) -> Vec<&str> {
+    let mut names: Vec<&str> = index
+        .symbols
+        .iter()
+        .filter(|(hash, _)| select(hash))
+        .map(|(_, sym)| sym.name.as_str())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn reachability_starts_at_declared_and_detected_entry_points() {
+    let dir = TempDir::new().unwrap();
+    let mut index = index_sources(&dir, SOURCES);
+    // Before any entry points are known, everything counts as reachable
+    assert!(index.symbols.keys().all(|hash| index.is_reachable(hash)));
+
+    let config = EntryPointsConfig {
+        symbols: vec!["**/api.rs".to_string()],
+        attributes: vec!["get".to_string()],
+        detect: true,
+    };
+    // A file glob makes every symbol of the file an entry point
+    assert_eq!(index.mark_reachable(&config), 4);
+    assert_eq!(
+        names(&index, |hash| index.entry_points.contains(hash)),
+        ["exported", "helper", "list_users", "main"]
+    );
+    assert_eq!(names(&index, |hash| !index.is_reachable(hash)), ["unused"]);
+
+    // The flags are stored with the index
+    let path = dir.path().join("index.bin");
+    index.save_index_to(&path, &IndexConfig::default()).unwrap();
+    let loaded = Index::load_index_from(&path).unwrap();
+    assert_eq!(
+        names(&loaded, |hash| !loaded.is_reachable(hash)),
+        ["unused"]
+    );
+}
+
+#[test]
+fn detection_can_be_turned_off() {
+    let dir = TempDir::new().unwrap();
+    let mut index = index_sources(&dir, SOURCES);
+    let config = EntryPointsConfig {
+        symbols: Vec::new(),
+        attributes: vec!["get".to_string()],
+        detect: false,
+    };
+    assert_eq!(index.mark_reachable(&config), 1);
+    assert_eq!(
+        names(&index, |hash| index.is_reachable(hash)),
+        ["list_users", "load"]
+    );
+}
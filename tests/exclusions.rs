@@ -0,0 +1,71 @@
+use std::fs;
+
+use contextmesh::parser::CodeParser;
+use tempfile::TempDir;
+
+#[test]
+fn ignore_comments_mark_the_definition_they_apply_to() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(
+        &path,
+        "fn kept() {}\n\
+         \n\
+         // contextmesh:ignore: generated\n\
+         #[rustfmt::skip]\n\
+         fn table() {}\n\
+         \n\
+         struct Noisy { // contextmesh:ignore\n    \
+             field: u32,\n\
+         }\n\
+         \n\
+         /// Mentions contextmesh:ignore in its docs only\n\
+         fn documented() {}\n",
+    )
+    .unwrap();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let parsed = code_parser.parse_file(&path.to_string_lossy()).unwrap();
+
+    let mut excluded: Vec<&str> = parsed
+        .symbols
+        .iter()
+        .filter(|sym| sym.is_excluded())
+        .map(|sym| sym.name.as_str())
+        .collect();
+    excluded.sort();
+    assert_eq!(excluded, ["Noisy", "field", "table"]);
+}
+
+#[test]
+fn an_ignore_comment_inside_a_body_marks_the_enclosing_definition() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(
+        &path,
+        "fn lookup(code: u32) -> &'static str {\n    \
+             match code {\n        \
+                 // contextmesh:ignore\n        \
+                 0 => \"zero\",\n        \
+                 _ => \"many\",\n    \
+             }\n\
+         }\n\
+         \n\
+         fn unrelated() {}\n\
+         \n\
+         // contextmesh:ignore\n\
+         let_me_be();\n\
+         \n\
+         fn after_code() {}\n",
+    )
+    .unwrap();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let parsed = code_parser.parse_file(&path.to_string_lossy()).unwrap();
+
+    let excluded: Vec<&str> = parsed
+        .symbols
+        .iter()
+        .filter(|sym| sym.is_excluded())
+        .map(|sym| sym.name.as_str())
+        .collect();
+    assert_eq!(excluded, ["lookup"]);
+}
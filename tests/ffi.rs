@@ -0,0 +1,99 @@
+use contextmesh::config::LanguageConfig;
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use std::fs;
+use tempfile::TempDir;
+
+const BINDINGS: &str = r#"#[pyclass]
+pub struct Counter {
+    count: u32,
+}
+
+#[pymethods]
+impl Counter {
+    pub fn get(&self) -> u32 {
+        self.count
+    }
+}
+
+#[pyfunction(name = "run_all")]
+fn run() {}
+"#;
+
+const STUBS: &str = r#"class Counter:
+    def get(self) -> int: ...
+
+class Cache:
+    def get(self, key): ...
+
+def run_all() -> None: ...
+
+def run() -> None: ...
+"#;
+
+/// The (file extension, parent name) of each dependency of the symbols named
+/// `name` in `file`.
+fn linked(index: &Index, file: &str, name: &str, parent: Option<&str>) -> Vec<String> {
+    let sym = index
+        .symbols
+        .values()
+        .find(|sym| {
+            sym.file_path.ends_with(file)
+                && sym.name == name
+                && sym
+                    .parent
+                    .and_then(|id| index.symbol(id))
+                    .map(|p| p.name.as_str())
+                    == parent
+        })
+        .unwrap();
+    let mut names: Vec<String> = sym
+        .dependencies
+        .iter()
+        .map(|id| index.symbol(*id).unwrap())
+        .map(|dep| format!("{}:{}", dep.file_path.rsplit('/').next().unwrap(), dep.name))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn bindings_link_by_exported_name_and_class() {
+    let dir = TempDir::new().unwrap();
+    let rust = dir.path().join("lib.rs");
+    let stubs = dir.path().join("lib.pyi");
+    fs::write(&rust, BINDINGS).unwrap();
+    fs::write(&stubs, STUBS).unwrap();
+
+    let mut index = Index::new();
+    index
+        .index_file(
+            rust.to_string_lossy().to_string(),
+            &mut CodeParser::new_rust().unwrap(),
+        )
+        .unwrap();
+    let config = LanguageConfig {
+        ctags: Some(false),
+        ..Default::default()
+    };
+    index
+        .index_file(
+            stubs.to_string_lossy().to_string(),
+            &mut CodeParser::new_tags("python", &config),
+        )
+        .unwrap();
+    assert_eq!(index.link_foreign_names(&[]), 3);
+
+    assert_eq!(
+        linked(&index, "lib.pyi", "Counter", None),
+        ["lib.rs:Counter"]
+    );
+    assert_eq!(
+        linked(&index, "lib.pyi", "get", Some("Counter")),
+        ["lib.rs:get"]
+    );
+    assert_eq!(linked(&index, "lib.pyi", "run_all", None), ["lib.rs:run"]);
+    // Same method name in another class, and the Rust name of a renamed function
+    assert!(linked(&index, "lib.pyi", "get", Some("Cache")).is_empty());
+    assert!(linked(&index, "lib.pyi", "run", None).is_empty());
+}
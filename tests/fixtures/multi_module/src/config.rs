@@ -0,0 +1,18 @@
+/// Settings read from the environment.
+pub struct Settings {
+    pub address: String,
+    pub retries: u32,
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        Settings {
+            address: default_address(),
+            retries: 3,
+        }
+    }
+}
+
+fn default_address() -> String {
+    "127.0.0.1:7000".to_string()
+}
@@ -0,0 +1,11 @@
+mod config;
+mod net;
+
+use config::Settings;
+use net::client::Client;
+
+fn main() {
+    let settings = Settings::load();
+    let client = Client::connect(&settings);
+    client.send(b"ping");
+}
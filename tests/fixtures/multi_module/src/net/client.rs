@@ -0,0 +1,25 @@
+use crate::config::Settings;
+use crate::net::retry::with_retries;
+
+/// A connection to the server.
+pub struct Client {
+    address: String,
+    retries: u32,
+}
+
+impl Client {
+    pub fn connect(settings: &Settings) -> Client {
+        Client {
+            address: settings.address.clone(),
+            retries: settings.retries,
+        }
+    }
+
+    pub fn send(&self, payload: &[u8]) {
+        with_retries(self.retries, || transmit(&self.address, payload));
+    }
+}
+
+fn transmit(address: &str, payload: &[u8]) -> bool {
+    !address.is_empty() && !payload.is_empty()
+}
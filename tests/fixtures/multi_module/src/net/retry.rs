@@ -0,0 +1,4 @@
+/// Calls `attempt` until it succeeds, at most `retries` times.
+pub fn with_retries(retries: u32, mut attempt: impl FnMut() -> bool) -> bool {
+    (0..retries).any(|_| attempt())
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::process::Command;
+
+use contextmesh::config::GeneratedConfig;
+use contextmesh::generated::is_generated;
+use tempfile::TempDir;
+
+mod common;
+use common::project;
+
+#[test]
+fn generated_files_are_found_by_name_header_or_glob() {
+    let dir = TempDir::new().unwrap();
+    let write = |name: &str, content: &str| {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    };
+    let named = write("api_generated.rs", "pub fn call() {}\n");
+    let marked = write("bindings.rs", "// Code generated by protoc. DO NOT EDIT.\n");
+    let late = write("late.rs", "\n\n\n// @generated\n");
+    let plain = write("plain.rs", "// Hand written.\n");
+
+    let config = GeneratedConfig::default();
+    assert!(is_generated(&named, &config));
+    assert!(is_generated(&marked, &config));
+    // Markers only count in the first three lines
+    assert!(!is_generated(&late, &config));
+    assert!(!is_generated(&plain, &config));
+
+    let config = GeneratedConfig {
+        paths: vec!["**/plain.rs".to_string()],
+        detect: false,
+    };
+    assert!(is_generated(&plain, &config));
+    assert!(!is_generated(&named, &config));
+    assert!(!is_generated(&marked, &config));
+}
+
+#[test]
+fn generated_files_are_resolved_through_but_left_out_of_bundles() {
+    let dir = project("multi_module");
+    fs::write(
+        dir.path().join("src/net/wire_generated.rs"),
+        "pub fn encode() {}\n",
+    )
+    .unwrap();
+    let retry = dir.path().join("src/net/retry.rs");
+    let source = fs::read_to_string(&retry).unwrap();
+    fs::write(&retry, source.replacen("{\n", "{\n    encode();\n", 1)).unwrap();
+    let contextmesh = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+    contextmesh(&["index"]);
+
+    let bundle = contextmesh(&["combine", "--sink", "stdout"]);
+    assert!(
+        !bundle.contains("# ./src/net/wire_generated.rs"),
+        "{}",
+        bundle
+    );
+    // The edge into the generated file still shows in the header of its user
+    assert!(
+        bundle.contains("Depends on: ./src/net/wire_generated.rs"),
+        "{}",
+        bundle
+    );
+
+    let bundle = contextmesh(&["combine", "--sink", "stdout", "--include-generated"]);
+    assert!(
+        bundle.contains("# ./src/net/wire_generated.rs"),
+        "{}",
+        bundle
+    );
+}
@@ -0,0 +1,127 @@
+//! Random edit sequences over small Rust projects, checking the invariants of
+//! the index (see `Index::invariant_violations`) after every step.
+
+use std::fs;
+use std::path::Path;
+
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use proptest::prelude::*;
+use tempfile::TempDir;
+
+const FILES: usize = 4;
+const NAMES: usize = 6;
+
+/// An item of a generated module.
+#[derive(Debug, Clone)]
+enum Item {
+    /// A function `f<name>` calling the functions `f<call>`, possibly itself
+    Function { name: usize, calls: Vec<usize> },
+    /// The same as a method of a struct `S<name>`
+    Method { name: usize, calls: Vec<usize> },
+    /// `pub use crate::module_<file>::f<name>;`
+    Reexport { file: usize, name: usize },
+}
+
+#[derive(Debug, Clone)]
+enum Edit {
+    /// Rewrites one file and indexes it alone
+    Write(usize, Vec<Item>),
+    /// Rewrites several files and indexes them together
+    WriteMany(Vec<(usize, Vec<Item>)>),
+    /// Deletes a file and drops it from the index
+    Delete(usize),
+    Recheck,
+}
+
+fn item() -> impl Strategy<Value = Item> {
+    let calls = || prop::collection::vec(0..NAMES, 0..4);
+    prop_oneof![
+        4 => (0..NAMES, calls()).prop_map(|(name, calls)| Item::Function { name, calls }),
+        1 => (0..NAMES, calls()).prop_map(|(name, calls)| Item::Method { name, calls }),
+        1 => (0..FILES, 0..NAMES).prop_map(|(file, name)| Item::Reexport { file, name }),
+    ]
+}
+
+fn module() -> impl Strategy<Value = Vec<Item>> {
+    prop::collection::vec(item(), 0..5)
+}
+
+fn edit() -> impl Strategy<Value = Edit> {
+    prop_oneof![
+        4 => (0..FILES, module()).prop_map(|(file, items)| Edit::Write(file, items)),
+        2 => prop::collection::vec((0..FILES, module()), 1..FILES).prop_map(Edit::WriteMany),
+        1 => (0..FILES).prop_map(Edit::Delete),
+        1 => Just(Edit::Recheck),
+    ]
+}
+
+fn source(items: &[Item]) -> String {
+    let body = |calls: &[usize]| -> String {
+        calls
+            .iter()
+            .map(|call| format!("    f{}();\n", call))
+            .collect()
+    };
+    items
+        .iter()
+        .map(|item| match item {
+            Item::Function { name, calls } => {
+                format!("pub fn f{}() {{\n{}}}\n\n", name, body(calls))
+            }
+            Item::Method { name, calls } => format!(
+                "pub struct S{name};\n\nimpl S{name} {{\n    pub fn f{name}(&self) {{\n{}    }}\n}}\n\n",
+                body(calls),
+                name = name
+            ),
+            Item::Reexport { file, name } => {
+                format!("pub use crate::module_{}::f{};\n\n", file, name)
+            }
+        })
+        .collect()
+}
+
+fn write(dir: &Path, file: usize, items: &[Item]) -> String {
+    let path = dir.join(format!("module_{}.rs", file));
+    fs::write(&path, source(items)).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+fn apply(index: &mut Index, code_parser: &mut CodeParser, dir: &Path, edit: &Edit) {
+    match edit {
+        Edit::Write(file, items) => {
+            let path = write(dir, *file, items);
+            index.index_file(path, code_parser).unwrap();
+        }
+        Edit::WriteMany(files) => {
+            let paths: Vec<String> = files
+                .iter()
+                .map(|(file, items)| write(dir, *file, items))
+                .collect();
+            index.index_files(&paths, code_parser).unwrap();
+        }
+        Edit::Delete(file) => {
+            let _ = fs::remove_file(dir.join(format!("module_{}.rs", file)));
+            index.forget_missing_files();
+        }
+        Edit::Recheck => {
+            index.recheck_unresolved();
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn edits_keep_the_graph_consistent(edits in prop::collection::vec(edit(), 1..12)) {
+        let dir = TempDir::new().unwrap();
+        let mut code_parser = CodeParser::new_rust().unwrap();
+        let mut index = Index::new();
+        for edit in &edits {
+            apply(&mut index, &mut code_parser, dir.path(), edit);
+            let violations = index.invariant_violations();
+            prop_assert!(violations.is_empty(), "after {:?}: {:#?}", edit, violations);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::process::Command;
+
+use serde_json::Value;
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"pub fn connect() -> String {
+    std::env::var("DATABASE_URL").unwrap_or_else(|_| "hello world".to_string())
+}
+
+pub fn route() -> &'static str {
+    "/users/:id"
+}
+
+pub fn setting() -> &'static str {
+    "index.compression"
+}
+
+pub fn greeting() -> &'static str {
+    "hi"
+}
+"#;
+
+#[test]
+fn symbols_are_found_by_their_notable_literals() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), SOURCE).unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output.stdout
+    };
+    run(&["index"]);
+    let grep = |args: &[&str]| -> Vec<(String, Vec<String>)> {
+        let args = [&["grep-sym"], args, &["--format", "json"]].concat();
+        let matches: Vec<Value> = serde_json::from_slice(&run(&args)).unwrap();
+        matches
+            .iter()
+            .map(|m| {
+                let literals = m["literals"].as_array().unwrap();
+                (
+                    m["name"].as_str().unwrap().to_string(),
+                    literals
+                        .iter()
+                        .map(|literal| literal.as_str().unwrap().to_string())
+                        .collect(),
+                )
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        grep(&["DATABASE"]),
+        [("connect".to_string(), vec!["DATABASE_URL".to_string()])]
+    );
+    assert_eq!(
+        grep(&["/users/:id", "--exact"]),
+        [("route".to_string(), vec!["/users/:id".to_string()])]
+    );
+    assert_eq!(
+        grep(&["compression"]),
+        [("setting".to_string(), vec!["index.compression".to_string()])]
+    );
+    // Prose and short strings aren't recorded
+    assert!(grep(&["hello"]).is_empty());
+    assert!(grep(&["hi", "--exact"]).is_empty());
+    assert!(grep(&["/users", "--exact"]).is_empty());
+}
@@ -0,0 +1,110 @@
+use contextmesh::index::Index;
+use contextmesh::symbol::Symbol;
+use tempfile::TempDir;
+
+mod common;
+use common::index_sources;
+
+/// (file name, symbol name) of every dependency of the symbol `name`.
+fn dependencies_of(index: &Index, name: &str) -> Vec<(String, String)> {
+    let sym = index.symbols.values().find(|sym| sym.name == name).unwrap();
+    dependencies_of_symbol(index, sym)
+}
+
+fn dependencies_of_symbol(index: &Index, sym: &Symbol) -> Vec<(String, String)> {
+    let mut deps: Vec<(String, String)> = sym
+        .dependencies
+        .iter()
+        .map(|id| {
+            let dep = index.symbol(*id).unwrap();
+            let file = dep.file_path.rsplit('/').next().unwrap().to_string();
+            (file, dep.name.clone())
+        })
+        .collect();
+    deps.sort();
+    deps
+}
+
+const A: &str =
+    "pub struct Bar;\nimpl Bar {\n    pub fn new() -> Self { Bar }\n}\npub fn run() {}\n";
+const B: &str =
+    "pub struct Qux;\nimpl Qux {\n    pub fn new() -> Self { Qux }\n}\npub fn run() {}\n";
+
+#[test]
+fn aliased_imports_resolve_to_the_original_item() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(
+        &dir,
+        &[
+            ("src/a.rs", A),
+            ("src/b.rs", B),
+            (
+                "src/c.rs",
+                "use crate::a::Bar as Baz;\nuse crate::b::run as go;\n\nfn start() {\n    Baz::new();\n    go();\n}\n",
+            ),
+        ],
+    );
+
+    assert_eq!(
+        dependencies_of(&index, "start"),
+        vec![
+            ("a.rs".to_string(), "new".to_string()),
+            ("b.rs".to_string(), "run".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn glob_imports_pick_the_imported_module() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(
+        &dir,
+        &[
+            ("src/a.rs", A),
+            ("src/b.rs", B),
+            (
+                "src/c.rs",
+                "use crate::b::*;\n\nfn start() {\n    run();\n    Qux::new();\n}\n",
+            ),
+        ],
+    );
+
+    assert_eq!(
+        dependencies_of(&index, "start"),
+        vec![
+            ("b.rs".to_string(), "new".to_string()),
+            ("b.rs".to_string(), "run".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn reexports_are_symbols_that_resolve_to_the_original_item() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(
+        &dir,
+        &[
+            (
+                "src/lib.rs",
+                "mod a;\nmod b;\npub use a::Bar;\npub use b::run as run_b;\n",
+            ),
+            ("src/c.rs", "fn start() {\n    crate::run_b();\n}\n"),
+            ("src/a.rs", A),
+            ("src/b.rs", B),
+        ],
+    );
+
+    let reexport = index
+        .symbols
+        .values()
+        .find(|sym| sym.name == "Bar" && sym.is_reexport())
+        .unwrap();
+    assert_eq!(
+        dependencies_of_symbol(&index, reexport),
+        vec![("a.rs".to_string(), "Bar".to_string())]
+    );
+    assert_eq!(
+        dependencies_of(&index, "start"),
+        vec![("b.rs".to_string(), "run".to_string())]
+    );
+}
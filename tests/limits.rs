@@ -0,0 +1,52 @@
+use std::fs;
+
+use contextmesh::config::FilesConfig;
+use contextmesh::limits::{replace_invalid_utf8, skip_reason};
+use tempfile::TempDir;
+
+#[test]
+fn invalid_utf8_is_replaced_byte_for_byte() {
+    let latin1 = b"// caf\xe9\nfn main() {}\n".to_vec();
+    assert_eq!(replace_invalid_utf8(latin1), b"// caf?\nfn main() {}\n");
+
+    let valid = "// café\n".as_bytes().to_vec();
+    assert_eq!(replace_invalid_utf8(valid.clone()), valid);
+
+    // A multi-byte sequence cut off at the end
+    assert_eq!(replace_invalid_utf8(b"ok\xe2\x82".to_vec()), b"ok??");
+}
+
+#[test]
+fn large_binary_and_excluded_files_are_skipped() {
+    let dir = TempDir::new().unwrap();
+    let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+    fs::write(path("small.rs"), "fn main() {}\n").unwrap();
+    fs::write(path("big.rs"), "x".repeat(200)).unwrap();
+    fs::write(path("blob.rs"), b"\x7fELF\x00\x01").unwrap();
+    fs::write(path("app.min.js"), "f()").unwrap();
+
+    let config = FilesConfig {
+        max_bytes: 100,
+        skip: vec!["**/*.min.js".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(skip_reason(&path("small.rs"), &config), None);
+    assert_eq!(
+        skip_reason(&path("big.rs"), &config).as_deref(),
+        Some("200 bytes, over the limit of 100")
+    );
+    assert_eq!(
+        skip_reason(&path("blob.rs"), &config).as_deref(),
+        Some("binary content")
+    );
+    assert_eq!(
+        skip_reason(&path("app.min.js"), &config).as_deref(),
+        Some("matches the skip pattern `**/*.min.js`")
+    );
+
+    let config = FilesConfig {
+        skip_binary: false,
+        ..Default::default()
+    };
+    assert_eq!(skip_reason(&path("blob.rs"), &config), None);
+}
@@ -0,0 +1,54 @@
+use contextmesh::config::LlmConfig;
+use contextmesh::llm::{provider, Anthropic, OpenAi, Provider};
+use serde_json::json;
+
+#[test]
+fn providers_build_requests_and_read_answers() {
+    let anthropic = Anthropic {
+        endpoint: "https://api.anthropic.com".to_string(),
+        model: "some-model".to_string(),
+        api_key: "key".to_string(),
+        max_tokens: 100,
+    };
+    let request = anthropic.request("Be brief.", "What is `Index`?");
+    assert_eq!(request.url, "https://api.anthropic.com/v1/messages");
+    assert!(request
+        .headers
+        .contains(&("x-api-key".to_string(), "key".to_string())));
+    assert_eq!(request.body["system"], "Be brief.");
+    assert_eq!(request.body["messages"][0]["content"], "What is `Index`?");
+    let response = json!({
+        "content": [
+            { "type": "text", "text": "It is " },
+            { "type": "text", "text": "the index." },
+        ]
+    });
+    assert_eq!(
+        anthropic.answer(&response).as_deref(),
+        Some("It is the index.")
+    );
+    assert_eq!(anthropic.answer(&json!({ "content": [] })), None);
+
+    let openai = OpenAi {
+        endpoint: "http://localhost:8080".to_string(),
+        model: "some-model".to_string(),
+        api_key: "key".to_string(),
+        max_tokens: 100,
+    };
+    let request = openai.request("Be brief.", "What is `Index`?");
+    assert_eq!(request.url, "http://localhost:8080/v1/chat/completions");
+    assert_eq!(request.body["messages"][0]["role"], "system");
+    assert_eq!(request.body["messages"][1]["content"], "What is `Index`?");
+    let response = json!({ "choices": [{ "message": { "content": "The index." } }] });
+    assert_eq!(openai.answer(&response).as_deref(), Some("The index."));
+}
+
+#[test]
+fn unknown_providers_are_rejected() {
+    let config = LlmConfig {
+        provider: "carrier-pigeon".to_string(),
+        ..LlmConfig::default()
+    };
+    let error = provider(&config).err().expect("unknown provider");
+    assert_eq!(error.code(), "CM030");
+}
@@ -0,0 +1,86 @@
+use contextmesh::config::LanguageConfig;
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use std::fs;
+use tempfile::TempDir;
+
+const SHAPES: &str = r#"import math
+
+class Shape:
+    def area(self):
+        raise NotImplementedError
+
+
+class Circle(Shape):
+    def __init__(self, r):
+        self.r = r
+
+    def area(self):
+        return math.pi * self.r ** 2
+
+
+def main():
+    print(Circle(2).area())
+"#;
+
+/// Finds definitions with the keyword regex, whether or not ctags is installed.
+fn regex_only() -> LanguageConfig {
+    LanguageConfig {
+        ctags: Some(false),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn languages_without_a_parser_get_their_definitions_indexed() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("shapes.py");
+    fs::write(&path, SHAPES).unwrap();
+    let path = path.to_string_lossy().to_string();
+
+    let mut code_parser = CodeParser::new_tags("python", &regex_only());
+    assert!(code_parser.low_fidelity_source().is_some());
+    let mut index = Index::new();
+    index.index_file(path.clone(), &mut code_parser).unwrap();
+
+    let mut names: Vec<(String, Option<String>)> = index
+        .symbols
+        .values()
+        .map(|sym| {
+            let parent = sym.parent.and_then(|id| index.symbol(id));
+            (sym.name.clone(), parent.map(|parent| parent.name.clone()))
+        })
+        .collect();
+    names.sort();
+    let expected = [
+        ("Circle", None),
+        ("Shape", None),
+        ("__init__", Some("Circle")),
+        ("area", Some("Circle")),
+        ("area", Some("Shape")),
+        ("main", None),
+    ];
+    let expected: Vec<(String, Option<String>)> = expected
+        .iter()
+        .map(|(name, parent)| (name.to_string(), parent.map(str::to_string)))
+        .collect();
+    assert_eq!(names, expected);
+    assert!(index.low_fidelity_files.contains(&path));
+}
+
+#[test]
+fn offsets_are_bytes_of_the_file_after_invalid_utf8() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("latin1.py");
+    let code = b"# caf\xe9 \xff\xfe\n\ndef after():\n    pass\n";
+    fs::write(&path, code).unwrap();
+
+    let mut code_parser = CodeParser::new_tags("python", &regex_only());
+    let parsed = code_parser.parse_file(&path.to_string_lossy()).unwrap();
+    let after = &parsed.symbols[0];
+    assert_eq!(after.name, "after");
+    assert_eq!(
+        &code[after.start_byte..after.end_byte],
+        b"def after():\n    pass"
+    );
+}
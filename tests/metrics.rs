@@ -0,0 +1,64 @@
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use contextmesh::symbol::Metrics;
+use std::fs;
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"pub struct Parser {
+    depth: usize,
+}
+
+impl Parser {
+    pub fn classify(&self, values: &[i32], strict: bool) -> usize {
+        let mut count = 0;
+        for value in values {
+            if *value > 0 && strict {
+                count += 1;
+            } else if *value < 0 {
+                match value {
+                    -1 => count += 2,
+                    -2 => count += 3,
+                    _ => {}
+                }
+            }
+        }
+        count
+    }
+}
+"#;
+
+#[test]
+fn definitions_are_measured() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("parser.rs");
+    fs::write(&path, SOURCE).unwrap();
+
+    let mut index = Index::new();
+    index
+        .index_file(
+            path.to_string_lossy().to_string(),
+            &mut CodeParser::new_rust().unwrap(),
+        )
+        .unwrap();
+    let metrics = |name: &str| {
+        index
+            .symbols
+            .values()
+            .find(|sym| sym.name == name)
+            .and_then(|sym| sym.metrics)
+            .unwrap()
+    };
+
+    // for, if, &&, else if, and two more match arms
+    assert_eq!(
+        metrics("classify"),
+        Metrics {
+            lines: 15,
+            complexity: 7,
+            nesting: 3,
+            parameters: 3,
+        }
+    );
+    // The method is measured on its own
+    assert_eq!(metrics("Parser").complexity, 1);
+}
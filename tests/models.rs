@@ -0,0 +1,28 @@
+use contextmesh::config::Config;
+use contextmesh::models::{ModelProfile, Tokenizer};
+
+#[test]
+fn profiles_turn_context_windows_into_budgets() {
+    let mut config = Config::default();
+    let sonnet = ModelProfile::named(&config, "claude-sonnet").unwrap();
+    let mini = ModelProfile::named(&config, "gpt-4o-mini").unwrap();
+    assert!(sonnet.budget() > mini.budget());
+
+    // Four bytes per token is the estimate budgets are counted in
+    let local: ModelProfile = toml::from_str("context_tokens = 32768").unwrap();
+    assert_eq!(local.tokenizer, Tokenizer::Approximate);
+    assert_eq!(local.budget(), 32768 - 4096);
+
+    // The config's profiles replace built-in ones of the same name
+    let dense = ModelProfile {
+        context_tokens: 32768,
+        reserved_output: 4096,
+        tokenizer: Tokenizer::Claude,
+    };
+    config.models.insert("claude-sonnet".to_string(), dense);
+    let sonnet = ModelProfile::named(&config, "claude-sonnet").unwrap();
+    assert!(sonnet.budget() < local.budget());
+
+    let error = ModelProfile::named(&config, "gpt-2").unwrap_err();
+    assert_eq!(error.code(), "CM030");
+}
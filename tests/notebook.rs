@@ -0,0 +1,27 @@
+use contextmesh::parser::notebook;
+
+#[test]
+fn notebooks_become_scripts_of_their_code_cells_in_execution_order() {
+    let notebook = br##"{
+ "cells": [
+  {"cell_type": "markdown", "source": ["# Analysis"]},
+  {"cell_type": "code", "execution_count": 2, "source": ["def clean(df):\n", "    return df"]},
+  {"cell_type": "code", "execution_count": 1, "source": ["%matplotlib inline\n", "!pip install pandas\n", "import pandas"]},
+  {"cell_type": "code", "execution_count": null, "source": "%%bash\nls"}
+ ],
+ "nbformat": 4
+}"##;
+    let script = notebook::script(notebook).unwrap();
+    assert_eq!(
+        script,
+        "# In[1]: cell 3\n# %matplotlib inline\n# !pip install pandas\nimport pandas\n\n\
+         # In[2]: cell 2\ndef clean(df):\n    return df\n\n\
+         # In[ ]: cell 4\n# %%bash\n# ls\n\n"
+    );
+
+    let starts: Vec<usize> = script.match_indices("# In[").map(|(idx, _)| idx).collect();
+    let cells = notebook::cells(script.as_bytes());
+    assert_eq!(cells, [(starts[0], 3), (starts[1], 2), (starts[2], 4)]);
+
+    assert!(notebook::script(b"not json").is_err());
+}
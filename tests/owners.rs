@@ -0,0 +1,85 @@
+use std::fs;
+use std::process::Command;
+
+use contextmesh::owners::CodeOwners;
+use serde_json::Value;
+
+mod common;
+use common::project;
+
+#[test]
+fn the_last_matching_rule_decides_the_owners() {
+    let owners = CodeOwners::parse(
+        "# Default\n* @team\n\n[Docs]\ndocs/ @writers # prose\n/src/net/ @net-team @ops\n*.md @writers\n",
+    );
+    assert_eq!(owners.owners_of("./src/main.rs"), ["@team"]);
+    assert_eq!(owners.owners_of("src/net/client.rs"), ["@net-team", "@ops"]);
+    // Unanchored patterns match at any depth, anchored ones only at the root
+    assert_eq!(owners.owners_of("guides/docs/setup.txt"), ["@writers"]);
+    assert_eq!(owners.owners_of("lib/src/net/client.rs"), ["@team"]);
+    assert_eq!(owners.owners_of("src/net/README.md"), ["@writers"]);
+
+    assert!(CodeOwners::parse("# nobody\n")
+        .owners_of("src/main.rs")
+        .is_empty());
+}
+
+#[test]
+fn owners_combine_codeowners_and_blame() {
+    let dir = project("multi_module");
+    fs::create_dir_all(dir.path().join(".github")).unwrap();
+    fs::write(
+        dir.path().join(".github/CODEOWNERS"),
+        "* @team\n/src/net/ @net-team\n",
+    )
+    .unwrap();
+    let run = |program: &str, args: &[&str]| {
+        let output = Command::new(program)
+            .args(args)
+            .current_dir(dir.path())
+            .env("GIT_AUTHOR_NAME", "Ada")
+            .env("GIT_AUTHOR_EMAIL", "ada@example.com")
+            .env("GIT_COMMITTER_NAME", "Ada")
+            .env("GIT_COMMITTER_EMAIL", "ada@example.com")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output.stdout
+    };
+    run("git", &["init", "-q"]);
+    run("git", &["add", "-A"]);
+    run("git", &["commit", "-qm", "Initial commit"]);
+    let contextmesh = env!("CARGO_BIN_EXE_contextmesh");
+    run(contextmesh, &["index"]);
+
+    let owners: Vec<Value> = serde_json::from_slice(&run(
+        contextmesh,
+        &["owners", "src/net", "--format", "json"],
+    ))
+    .unwrap();
+    let rows: Vec<(&str, u64, u64)> = owners
+        .iter()
+        .map(|row| {
+            (
+                row["owner"].as_str().unwrap(),
+                row["codeowners files"].as_u64().unwrap(),
+                row["blamed lines"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(rows, [("@net-team", 3, 0), ("Ada", 0, 31)]);
+
+    // A symbol is blamed for its own lines only
+    let owners: Vec<Value> = serde_json::from_slice(&run(
+        contextmesh,
+        &["owners", "Settings", "--format", "json"],
+    ))
+    .unwrap();
+    assert_eq!(owners[0]["owner"], "@team");
+    assert_eq!(owners[1]["owner"], "Ada");
+    assert_eq!(owners[1]["blamed lines"], 4);
+}
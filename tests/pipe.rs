@@ -0,0 +1,64 @@
+//! Talks to `contextmesh pipe` on a copy of a fixture project.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+mod common;
+use common::project;
+
+#[test]
+fn requests_get_answers_and_a_broken_index_fails_only_its_request() {
+    let dir = project("multi_module");
+    let indexed = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+        .arg("index")
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(indexed.status.success());
+    let index_path = dir.path().join(".contextmesh/index.bin");
+    let good_index = fs::read(&index_path).unwrap();
+
+    let mut pipe = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+        .arg("pipe")
+        .current_dir(dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = pipe.stdin.take().unwrap();
+    let mut stdout = BufReader::new(pipe.stdout.take().unwrap());
+    let mut ask = |request: Value| -> Value {
+        writeln!(stdin, "{}", request).unwrap();
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    };
+
+    let response = ask(json!({"id": 1, "op": "search", "pattern": "connect"}));
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"][0]["name"], "connect", "{}", response);
+
+    // e.g. caught in the middle of being written
+    fs::write(&index_path, b"not an index").unwrap();
+    let response = ask(json!({"id": 2, "op": "search", "pattern": "connect"}));
+    assert_eq!(response["id"], 2);
+    assert!(response["error"].is_object(), "{}", response);
+
+    fs::write(&index_path, good_index).unwrap();
+    let response = ask(json!({"id": 3, "op": "show", "symbol": "connect"}));
+    assert_eq!(response["id"], 3);
+    assert!(
+        response["result"][0]["source"]
+            .as_str()
+            .is_some_and(|source| source.contains("fn connect")),
+        "{}",
+        response
+    );
+
+    drop(stdin);
+    assert!(pipe.wait().unwrap().success());
+}
@@ -0,0 +1,69 @@
+use contextmesh::config::PruneConfig;
+use contextmesh::index::Index;
+use tempfile::TempDir;
+
+mod common;
+use common::index_sources;
+
+const SOURCES: &[(&str, &str)] = &[
+    (
+        "src/shapes.rs",
+        "/// A point.\npub struct Point {\n    x: i32,\n    y: i32,\n}\n\n/// Makes one.\npub fn origin() {}\n\npub fn unit() {}\n",
+    ),
+    ("vendor/lib.rs", "pub fn vendored() {}\n"),
+    ("src/main.rs", "fn main() {\n    vendored();\n    origin();\n}\n"),
+];
+
+/// Sorted names of the symbols of the file ending in `suffix`.
+fn names_in(index: &Index, suffix: &str) -> Vec<String> {
+    let mut names: Vec<String> = index
+        .symbols
+        .values()
+        .filter(|sym| sym.file_path.ends_with(suffix))
+        .map(|sym| sym.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn pruning_drops_docs_excluded_files_and_symbols_over_the_cap() {
+    let dir = TempDir::new().unwrap();
+    let mut index = index_sources(&dir, SOURCES);
+    assert_eq!(
+        names_in(&index, "shapes.rs"),
+        ["Point", "origin", "unit", "x", "y"]
+    );
+
+    let report = index.prune(&PruneConfig {
+        drop_docs: true,
+        exclude: vec!["**/vendor/**".to_string()],
+        max_symbols_per_file: Some(3),
+    });
+    assert_eq!(report.docs, 2);
+    assert_eq!(report.excluded, 1);
+    assert_eq!((report.capped, report.capped_files), (2, 1));
+
+    // Top-level symbols are kept before the fields
+    assert_eq!(names_in(&index, "shapes.rs"), ["Point", "origin", "unit"]);
+    assert!(names_in(&index, "lib.rs").is_empty());
+    assert!(index.symbols.values().all(|sym| sym.doc.is_none()));
+
+    // The user of a dropped symbol keeps the reference, unresolved
+    let unresolved: Vec<&str> = index
+        .unresolved_references()
+        .map(|(_, name)| name)
+        .collect();
+    assert!(unresolved.contains(&"vendored"), "{:?}", unresolved);
+    assert!(index.invariant_violations().is_empty());
+}
+
+#[test]
+fn nothing_is_pruned_without_policies() {
+    let dir = TempDir::new().unwrap();
+    let mut index = index_sources(&dir, SOURCES);
+    let config = PruneConfig::default();
+    assert!(!config.is_enabled());
+    assert!(index.prune(&config).is_empty());
+    assert_eq!(index.symbols.len(), 7);
+}
@@ -0,0 +1,91 @@
+use contextmesh::index::Index;
+use contextmesh::query::Query;
+use tempfile::TempDir;
+
+mod common;
+use common::fixture_index;
+
+const FILES: usize = 4;
+const FNS_PER_FILE: usize = 3;
+
+/// Sorted names of the symbols `expression` selects.
+fn names(index: &Index, expression: &str) -> Vec<String> {
+    let mut names: Vec<String> = Query::parse(expression)
+        .unwrap()
+        .evaluate(index)
+        .into_iter()
+        .map(|hash| index.symbols[hash].name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn predicates_combine_with_and_or_not() {
+    let dir = TempDir::new().unwrap();
+    let index = fixture_index(&dir, FILES, FNS_PER_FILE);
+
+    assert_eq!(
+        names(&index, "kind:function file:**/module_1.rs name:/fn_[01]$/"),
+        ["module_1_fn_0", "module_1_fn_1"]
+    );
+    assert_eq!(
+        names(&index, "name:Record0 or name:\"Record3\""),
+        ["Record0", "Record3"]
+    );
+    assert_eq!(
+        names(
+            &index,
+            "file:**/module_2.rs and not (kind:function or kind:field_declaration)"
+        ),
+        ["Record2"]
+    );
+    assert_eq!(names(&index, "name:module_3_*_2"), ["module_3_fn_2"]);
+}
+
+#[test]
+fn traversals_follow_edges_to_the_given_depth() {
+    let dir = TempDir::new().unwrap();
+    let index = fixture_index(&dir, FILES, FNS_PER_FILE);
+
+    // module_N_fn_0 calls module_{N-1}_fn_0
+    assert_eq!(
+        names(&index, "deps of name:module_2_fn_0"),
+        ["module_1_fn_0"]
+    );
+    assert_eq!(
+        names(&index, "deps(depth<=2) of name:module_2_fn_0"),
+        ["module_0_fn_0", "module_1_fn_0"]
+    );
+    assert_eq!(
+        names(&index, "deps(depth=2) of name:module_2_fn_0"),
+        ["module_0_fn_0"]
+    );
+    assert_eq!(
+        names(&index, "users(*) of name:module_1_fn_0"),
+        [
+            "module_1_fn_1",
+            "module_1_fn_2",
+            "module_2_fn_0",
+            "module_2_fn_1",
+            "module_2_fn_2",
+            "module_3_fn_0",
+            "module_3_fn_1",
+            "module_3_fn_2"
+        ]
+    );
+}
+
+#[test]
+fn malformed_queries_are_rejected() {
+    for expression in [
+        "",
+        "name:",
+        "colour:red",
+        "users(depth<2) of name:x",
+        "(kind:fn",
+        "file:/a/",
+    ] {
+        assert!(Query::parse(expression).is_err(), "{} parsed", expression);
+    }
+}
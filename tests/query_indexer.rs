@@ -0,0 +1,141 @@
+use contextmesh::config::LanguageConfig;
+use contextmesh::parser::query::QueryIndexer;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn import_paths_are_joined_from_their_segments() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("tags.scm"),
+        "(function_definition name: (identifier) @name) @definition.function",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("imports.scm"),
+        r#"
+        (import_from_statement
+          module_name: (dotted_name [(identifier) "."]+ @import.path)
+          name: (dotted_name) @import.name)
+        (import_from_statement
+          module_name: (dotted_name [(identifier) "."]+ @import.glob)
+          (wildcard_import))
+        "#,
+    )
+    .unwrap();
+    let config = LanguageConfig {
+        queries: Some(dir.path().to_string_lossy().to_string()),
+        grammar: Some("python".to_string()),
+        ..LanguageConfig::default()
+    };
+    let mut indexer = QueryIndexer::from_config("python", &config)
+        .unwrap()
+        .unwrap();
+
+    let code = b"from pkg.models import User\nfrom pkg.util import *\n\ndef load():\n    pass\n";
+    let parsed = indexer.parse("app.py", code).unwrap();
+    assert_eq!(parsed.imports.aliases["User"], "pkg::models::User");
+    assert_eq!(parsed.imports.globs, ["pkg::util"]);
+    assert_eq!(parsed.symbols[0].name, "load");
+}
+
+#[test]
+fn paths_stopping_at_a_separator_are_not_imported() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("tags.scm"),
+        "(function_definition name: (identifier) @name) @definition.function",
+    )
+    .unwrap();
+    // The first two patterns stop short of the whole module path, as a pattern for
+    // a package followed by selectors does
+    fs::write(
+        dir.path().join("imports.scm"),
+        r#"
+        (import_from_statement
+          module_name: (dotted_name . (identifier) @import.path))
+        (import_from_statement
+          module_name: (dotted_name . (identifier) @import.path "." @import.path))
+        (import_from_statement
+          module_name: (dotted_name [(identifier) "."]+ @import.path)
+          name: (dotted_name) @import.name)
+        "#,
+    )
+    .unwrap();
+    let config = LanguageConfig {
+        queries: Some(dir.path().to_string_lossy().to_string()),
+        grammar: Some("python".to_string()),
+        ..LanguageConfig::default()
+    };
+    let mut indexer = QueryIndexer::from_config("python", &config)
+        .unwrap()
+        .unwrap();
+
+    let parsed = indexer
+        .parse("app.py", b"from pkg.models import User\n")
+        .unwrap();
+    assert_eq!(parsed.imports.aliases.len(), 1);
+    assert_eq!(parsed.imports.aliases["User"], "pkg::models::User");
+}
+
+/// Runs the bundled Scala queries with the compiled tree-sitter-scala library named
+/// by `CONTEXTMESH_SCALA_GRAMMAR`; run it with `cargo test -- --ignored`.
+#[test]
+#[ignore = "needs a tree-sitter-scala library in CONTEXTMESH_SCALA_GRAMMAR"]
+fn scala_imports_alias_only_imported_names() {
+    let grammar = std::env::var("CONTEXTMESH_SCALA_GRAMMAR")
+        .expect("CONTEXTMESH_SCALA_GRAMMAR names a tree-sitter-scala library");
+    let config = LanguageConfig {
+        grammar: Some(grammar),
+        ..LanguageConfig::default()
+    };
+    let mut indexer = QueryIndexer::from_config("scala", &config)
+        .unwrap()
+        .unwrap();
+
+    let code = b"import a.b.C\nimport d.e.{F, G => H}\nimport i.j._\n\nobject Main\n";
+    let parsed = indexer.parse("Main.scala", code).unwrap();
+    let mut aliases: Vec<_> = parsed.imports.aliases.into_iter().collect();
+    aliases.sort();
+    assert_eq!(
+        aliases,
+        [
+            ("C".to_string(), "a::b::C".to_string()),
+            ("F".to_string(), "d::e::F".to_string()),
+            ("H".to_string(), "d::e::G".to_string()),
+        ]
+    );
+    assert_eq!(parsed.imports.globs, ["i::j"]);
+}
+
+#[test]
+fn bundled_queries_wait_for_a_grammar_that_is_not_bundled() {
+    assert!(
+        QueryIndexer::from_config("scala", &LanguageConfig::default())
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        QueryIndexer::from_config("kotlin", &LanguageConfig::default())
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn python_uses_the_bundled_grammar_and_queries_by_default() {
+    let mut indexer = QueryIndexer::from_config("python", &LanguageConfig::default())
+        .unwrap()
+        .unwrap();
+
+    let code = b"from pkg.models import *\n\ndef load():\n    return User()\n";
+    let parsed = indexer.parse("tool.py", code).unwrap();
+    let load = parsed
+        .symbols
+        .iter()
+        .find(|sym| sym.name == "load")
+        .unwrap();
+    assert_eq!(&*load.node_kind, "function");
+    assert!(load.references.contains("User"));
+    assert_eq!(parsed.imports.globs, ["pkg::models"]);
+}
@@ -0,0 +1,69 @@
+use std::collections::{HashMap, HashSet};
+
+use contextmesh::related::{name_words, rank};
+use tempfile::TempDir;
+
+mod common;
+use common::index_sources;
+
+#[test]
+fn names_split_into_lowercase_words() {
+    let words = |name: &str| {
+        let mut words: Vec<String> = name_words(name).into_iter().collect();
+        words.sort();
+        words
+    };
+    assert_eq!(words("save_index"), ["index", "save"]);
+    assert_eq!(words("IndexSaver"), ["index", "saver"]);
+    assert_eq!(words("HTTPClient2"), ["httpclient2"]);
+    assert_eq!(words("load__v2_file"), ["file", "load", "v2"]);
+}
+
+#[test]
+fn related_symbols_are_ranked_by_all_signals() {
+    let dir = TempDir::new().unwrap();
+    let index = index_sources(
+        &dir,
+        &[
+            (
+                "src/store.rs",
+                "pub fn save_index() {\n    write_file();\n}\n\npub fn load_index() {\n    read_file();\n}\n\nfn write_file() {}\n\nfn read_file() {}\n",
+            ),
+            ("src/cli.rs", "fn run() {\n    save_index();\n}\n"),
+            ("src/docs.rs", "fn render() {}\n"),
+            ("src/other.rs", "fn unrelated() {}\n"),
+        ],
+    );
+    let hash_of = |name: &str| {
+        index
+            .symbols
+            .iter()
+            .find(|(_, sym)| sym.name == name)
+            .map(|(hash, _)| hash.as_str())
+            .unwrap()
+    };
+    let seeds = HashSet::from([hash_of("save_index")]);
+    // `docs.rs` keeps changing with the seed's file
+    let docs = dir.path().join("src/docs.rs").to_string_lossy().to_string();
+    let co_changes = HashMap::from([(docs, 0.5)]);
+
+    let related = rank(&index, &seeds, &co_changes);
+    let names: Vec<&str> = related.iter().map(|r| r.symbol.name.as_str()).collect();
+    // Neighbors first, then co-change, then the name-alike
+    assert_eq!(
+        names,
+        ["run", "write_file", "render", "load_index"],
+        "{:?}",
+        related
+            .iter()
+            .map(|r| (&r.symbol.name, r.score))
+            .collect::<Vec<_>>()
+    );
+
+    let render = &related[2];
+    assert_eq!((render.proximity, render.co_change), (0.0, 0.5));
+    let load = &related[3];
+    assert_eq!((load.proximity, load.co_change), (0.0, 0.0));
+    assert!(load.name_similarity > 0.0);
+    assert!(related.iter().all(|r| r.symbol.name != "unrelated"));
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use contextmesh::index::Index;
+use contextmesh::remote::{shards, Remote, MAX_SHARD_SIZE, MIN_SHARD_SIZE};
+
+mod common;
+use common::project;
+
+#[test]
+fn remotes_are_parsed_by_scheme() {
+    let parse = |url: &str| Remote::parse(url).unwrap();
+    assert_eq!(
+        parse("file:///srv/index/"),
+        Remote::Dir("/srv/index".into())
+    );
+    assert_eq!(
+        parse("s3://bucket/x"),
+        Remote::S3("s3://bucket/x".to_string())
+    );
+    assert_eq!(
+        parse("https://example.com/x"),
+        Remote::Http("https://example.com/x".to_string())
+    );
+    let ssh = |host: &str, path: &str| Remote::Ssh {
+        host: host.to_string(),
+        path: path.to_string(),
+    };
+    assert_eq!(parse("ssh://user@host/srv/x"), ssh("user@host", "/srv/x"));
+    assert_eq!(parse("host:indexes"), ssh("host", "indexes"));
+    // A colon in a local path
+    assert_eq!(parse("./a:b"), Remote::Dir(PathBuf::from("./a:b")));
+
+    assert!(Remote::parse("").is_err());
+    assert!(Remote::parse("ssh://host").is_err());
+    // Hosts that ssh would take for options
+    assert!(Remote::parse("-oProxyCommand=touch:x").is_err());
+    assert!(Remote::parse("ssh://-oProxyCommand=touch/x").is_err());
+}
+
+/// Bytes that look random, the same in every run.
+fn noise(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn an_insertion_only_changes_the_shards_around_it() {
+    let data = noise(12 * 1024 * 1024, 7);
+    let before = shards(&data);
+    assert_eq!(before.concat(), data);
+    assert!(before.len() > 3);
+    for shard in &before[..before.len() - 1] {
+        assert!((MIN_SHARD_SIZE..=MAX_SHARD_SIZE).contains(&shard.len()));
+    }
+
+    let mut changed = data.clone();
+    changed.splice(6_000_000..6_000_000, noise(100, 11));
+    let after = shards(&changed);
+    assert_eq!(after.concat(), changed);
+    let kept = after.iter().filter(|shard| before.contains(shard)).count();
+    assert!(kept >= after.len() - 2, "{} of {} kept", kept, after.len());
+}
+
+#[test]
+fn pushed_indexes_are_pulled_back() {
+    let dir = project("multi_module");
+    let remote = tempfile::TempDir::new().unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+    let remote_path = remote.path().to_str().unwrap();
+    run(&["index"]);
+    let index_path = dir.path().join(".contextmesh/index.bin");
+    let pushed = Index::decompressed(fs::read(&index_path).unwrap()).unwrap();
+    run(&["push", "--remote", remote_path]);
+    assert!(remote.path().join("manifests/default.json").exists());
+
+    fs::remove_file(&index_path).unwrap();
+    run(&["pull", "--remote", remote_path]);
+    let pulled = Index::decompressed(fs::read(&index_path).unwrap()).unwrap();
+    assert_eq!(pulled, pushed);
+}
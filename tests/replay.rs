@@ -0,0 +1,74 @@
+use std::fs;
+use std::process::{Command, Output};
+
+mod common;
+use common::project;
+
+#[test]
+fn replays_report_changed_and_gone_symbols() {
+    let dir = project("multi_module");
+    let contextmesh = |args: &[&str]| -> Output {
+        let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .args(["--sink", "stdout"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    };
+    contextmesh(&["index"]);
+    contextmesh(&[
+        "context",
+        "--symbol",
+        "default_address",
+        "--symbol",
+        "with_retries",
+        "--symbol",
+        "Client",
+        "--manifest",
+        "manifest.json",
+    ]);
+
+    // Unchanged, the manifest gives the same bundle
+    let output = contextmesh(&["replay", "manifest.json"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("0 of 3 symbol(s) changed, 0 moved, and 0 disappeared"),
+        "{}",
+        stderr
+    );
+
+    let config = dir.path().join("src/config.rs");
+    let source = fs::read_to_string(&config).unwrap();
+    fs::write(&config, source.replace("127.0.0.1:7000", "0.0.0.0:7000")).unwrap();
+    fs::write(dir.path().join("src/net/retry.rs"), "").unwrap();
+    contextmesh(&["index"]);
+
+    let output = contextmesh(&["replay", "manifest.json"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Changed: `crate::config::default_address` (src/config.rs:"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Gone: `crate::net::retry::with_retries` (src/net/retry.rs:"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("1 of 3 symbol(s) changed, 0 moved, and 1 disappeared"),
+        "{}",
+        stderr
+    );
+    // The changed symbol goes in as it is now, the gone one is left out
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("0.0.0.0:7000"), "{}", stdout);
+    assert!(!stdout.contains("fn with_retries"), "{}", stdout);
+    assert!(stdout.contains("pub struct Client"), "{}", stdout);
+}
@@ -0,0 +1,27 @@
+use std::fs;
+
+use contextmesh::utils::root_relative;
+use tempfile::TempDir;
+
+#[test]
+fn indexed_paths_are_relative_to_the_root() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/lib.rs"), "").unwrap();
+
+    let absolute = root.join("src/lib.rs");
+    let forms = [
+        "src/lib.rs",
+        "./src/lib.rs",
+        "src/../src/lib.rs",
+        absolute.to_str().unwrap(),
+    ];
+    for path in forms {
+        assert_eq!(root_relative(path, &root), "./src/lib.rs", "{}", path);
+    }
+    assert_eq!(
+        root_relative("/elsewhere/lib.rs", &root),
+        "/elsewhere/lib.rs"
+    );
+}
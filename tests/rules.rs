@@ -0,0 +1,75 @@
+//! The `[[rules]]` of `check` and the `[architecture]` section match the same
+//! symbols for the same patterns.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::process::Command;
+
+use serde_json::Value;
+
+mod common;
+use common::project;
+
+const CONFIG: &str = r#"
+[[rules]]
+from = "src/main.rs"
+deny = ["crate::config"]
+
+[[rules]]
+from = "crate::net::client"
+deny = ["src/net/*.rs"]
+allow = ["src/net/client.rs"]
+
+[[architecture.deny]]
+from = "src/main.rs"
+to = "crate::config"
+
+[[architecture.deny]]
+from = "crate::net::client"
+to = "src/net/*.rs"
+
+[[architecture.allow]]
+from = "crate::net::client"
+to = "src/net/client.rs"
+"#;
+
+#[test]
+fn rules_and_architecture_patterns_mean_the_same() {
+    let dir = project("multi_module");
+    fs::create_dir_all(dir.path().join(".contextmesh")).unwrap();
+    fs::write(dir.path().join(".contextmesh/config.toml"), CONFIG).unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output.stdout
+    };
+    run(&["index"]);
+    let problems: Vec<Value> =
+        serde_json::from_slice(&run(&["check", "--format", "json"])).unwrap();
+
+    // (file, line, file and line depended on) of the problems of `kind`
+    let edges = |kind: &str| -> BTreeSet<String> {
+        problems
+            .iter()
+            .filter(|problem| problem["kind"] == kind)
+            .map(|problem| {
+                format!(
+                    "{}:{} -> {}",
+                    problem["file_path"], problem["line_number"], problem["related"]
+                )
+            })
+            .collect()
+    };
+    let rules = edges("dependency-rule");
+    assert!(rules.iter().any(|edge| edge.contains("config.rs")));
+    assert!(rules.iter().any(|edge| edge.contains("retry.rs")));
+    assert_eq!(edges("architecture"), rules);
+}
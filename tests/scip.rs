@@ -0,0 +1,208 @@
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use contextmesh::scip::{
+    byte_offset, from_index, Document, Occurrence, PositionEncoding, ScipIndex, DEFINITION_ROLE,
+};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn columns_are_counted_in_the_position_encoding() {
+    // `x` after two-byte `é` and a four-byte, two-unit emoji
+    let source = "fn f() {}\nlet é = \"😀\"; x\n".as_bytes();
+    let x = source.iter().rposition(|&b| b == b'x').unwrap();
+    assert_eq!(byte_offset(source, 1, 17, PositionEncoding::Utf8), Some(x));
+    assert_eq!(byte_offset(source, 1, 14, PositionEncoding::Utf16), Some(x));
+    assert_eq!(byte_offset(source, 1, 13, PositionEncoding::Utf32), Some(x));
+
+    // Inside a character, or past the end of the line
+    assert_eq!(byte_offset(source, 1, 5, PositionEncoding::Utf8), None);
+    assert_eq!(byte_offset(source, 1, 10, PositionEncoding::Utf16), None);
+    assert_eq!(byte_offset(source, 0, 10, PositionEncoding::Utf8), None);
+    assert_eq!(byte_offset(source, 3, 0, PositionEncoding::Utf8), None);
+}
+
+#[test]
+fn negative_ranges_have_no_start() {
+    let occurrence = |range: Vec<i32>| Occurrence {
+        range,
+        ..Occurrence::default()
+    };
+    assert_eq!(occurrence(vec![1, 2, 3]).start(), Some((1, 2)));
+    assert_eq!(occurrence(vec![-1, 2, 3]).start(), None);
+    assert_eq!(occurrence(vec![1, -2, 1, 3]).start(), None);
+    assert_eq!(occurrence(vec![1, 2]).start(), None);
+}
+
+#[test]
+fn utf16_occurrences_are_imported_at_the_right_symbols() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(&path, "fn a() {\n    b();\n}\n\n/* 😀 */ fn b() {}\n").unwrap();
+    let path = path.to_string_lossy().to_string();
+    let mut index = Index::new();
+    index
+        .index_file(path.clone(), &mut CodeParser::new_rust().unwrap())
+        .unwrap();
+
+    let document = Document {
+        relative_path: path,
+        language: "rust".to_string(),
+        occurrences: vec![
+            Occurrence {
+                range: vec![4, 12, 13],
+                symbol: "local b".to_string(),
+                roles: DEFINITION_ROLE,
+            },
+            Occurrence {
+                range: vec![1, 4, 5],
+                symbol: "local b".to_string(),
+                roles: 0,
+            },
+            // Out of range, and skipped
+            Occurrence {
+                range: vec![-1, 4, 5],
+                symbol: "local b".to_string(),
+                roles: 0,
+            },
+        ],
+        symbols: Vec::new(),
+        position_encoding: PositionEncoding::Utf16,
+    };
+    let scip = ScipIndex {
+        documents: vec![document],
+        ..ScipIndex::default()
+    };
+    let decoded = ScipIndex::decode(&scip.encode()).unwrap();
+    assert_eq!(decoded.documents, scip.documents);
+
+    let precise = decoded.references_in(&index);
+    assert_eq!(precise.definitions, 1);
+    let hash = |name: &str| {
+        index
+            .symbols
+            .iter()
+            .find(|(_, sym)| sym.name == name)
+            .map(|(hash, _)| hash.clone())
+            .unwrap()
+    };
+    assert_eq!(precise.references[&hash("a")], [hash("b")].into());
+    assert_eq!(precise.references.len(), 1);
+
+    // Read as bytes, the definition's column doesn't start the name
+    let mut as_bytes = decoded;
+    as_bytes.documents[0].position_encoding = PositionEncoding::Utf8;
+    assert_eq!(as_bytes.references_in(&index).definitions, 0);
+}
+
+#[test]
+fn exported_symbols_have_monikers_and_ranges() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(
+        &path,
+        "/// Runs.\npub fn run() {\n    helper();\n}\n\nfn helper() {}\n",
+    )
+    .unwrap();
+    let path = path.to_string_lossy().to_string();
+    let mut index = Index::new();
+    index
+        .index_file(path.clone(), &mut CodeParser::new_rust().unwrap())
+        .unwrap();
+
+    let scip = from_index(
+        &index,
+        "file:///project".to_string(),
+        |_| Some("demo".to_string()),
+        |_| true,
+    );
+    let decoded = ScipIndex::decode(&scip.encode()).unwrap();
+    assert_eq!(decoded, scip);
+    assert_eq!(scip.project_root, "file:///project");
+    let [document] = &scip.documents[..] else {
+        panic!("{:?}", scip.documents);
+    };
+    assert_eq!(document.language, "rust");
+
+    // Named by package, then the module path of the file (here, absolute)
+    let symbols: Vec<&str> = document.symbols.iter().map(|info| &*info.symbol).collect();
+    let [helper, run] = symbols[..] else {
+        panic!("{:?}", symbols);
+    };
+    assert!(run.starts_with("contextmesh cargo demo . "), "{}", run);
+    assert!(run.ends_with("/lib/run()."), "{}", run);
+    assert!(helper.ends_with("/lib/helper()."), "{}", helper);
+    assert_eq!(
+        document.symbols[1].documentation,
+        ["```rust\npub fn run()\n```", "Runs."]
+    );
+
+    // Definitions and the reference from `run`, as [line, start, end] on one line
+    let occurrences: Vec<(&[i32], &str, bool)> = document
+        .occurrences
+        .iter()
+        .map(|o| (&o.range[..], &*o.symbol, o.is_definition()))
+        .collect();
+    assert_eq!(
+        occurrences,
+        [
+            (&[1, 7, 10][..], run, true),
+            (&[2, 4, 10][..], helper, false),
+            (&[5, 3, 9][..], helper, true),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn rust_analyzer_runs_on_the_workspace_root_from_a_member() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::process::Command;
+
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let member = root.join("app");
+    fs::create_dir_all(member.join("src")).unwrap();
+    fs::create_dir_all(member.join(".contextmesh")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"app\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        member.join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(member.join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+    fs::write(
+        member.join(".contextmesh/config.toml"),
+        "[languages.rust]\nrust_analyzer = true\n",
+    )
+    .unwrap();
+
+    // A stand-in that fails with the directory it was asked to index
+    let bin = TempDir::new().unwrap();
+    let fake = bin.path().join("rust-analyzer");
+    fs::write(&fake, "#!/bin/sh\necho \"asked for $2\" >&2\nexit 1\n").unwrap();
+    fs::set_permissions(&fake, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = std::env::join_paths(
+        std::iter::once(bin.path().to_path_buf())
+            .chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+        .arg("index")
+        .current_dir(&member)
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("asked for {};", root.display())),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,40 @@
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn scoping_keeps_the_symbols_of_a_subtree_and_their_edges() {
+    let dir = TempDir::new().unwrap();
+    let files = [
+        ("src/parser/lexer.rs", "pub fn tokenize() { helper(); }\n"),
+        (
+            "src/util/helpers.rs",
+            "pub fn helper() {}\npub fn unused() {}\n",
+        ),
+    ];
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = Index::new();
+    for (path, source) in files {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+        index
+            .index_file(path.to_string_lossy().to_string(), &mut code_parser)
+            .unwrap();
+    }
+    index.recheck_unresolved();
+
+    let scope = format!("{}/src/parser/", dir.path().display());
+    index.restrict_to(&[scope]);
+    let names: Vec<&str> = index
+        .symbols
+        .values()
+        .map(|sym| sym.name.as_str())
+        .collect();
+    assert_eq!(names, ["tokenize"]);
+    // The edge out of the scope is kept, not turned into an unresolved reference
+    let tokenize = index.symbols.values().next().unwrap();
+    assert_eq!(tokenize.dependencies.len(), 1);
+    assert_eq!(index.unresolved_count(), 0);
+}
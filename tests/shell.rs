@@ -0,0 +1,61 @@
+use contextmesh::parser::shell::ShellIndexer;
+
+#[test]
+fn scripts_depend_on_the_functions_and_scripts_they_use() {
+    let script = br#"#!/bin/bash
+# Deploys the server.
+source "$(dirname "$0")/lib/common.sh"
+
+# Builds the release binary.
+build() {
+  log_step "build"  # not a call: retry
+  cat <<EOT
+fake() { never_called; }
+EOT
+}
+
+function release {
+  build && bash ci/publish.sh --dry-run
+}
+
+RUST_LOG=info release | tee out.log
+"#;
+    let parsed = ShellIndexer.parse("ci/deploy.sh", script);
+    let names: Vec<&str> = parsed.symbols.iter().map(|sym| sym.name.as_str()).collect();
+    assert_eq!(names, ["deploy.sh", "build", "release"]);
+    assert_eq!(parsed.parents, [(1, 0), (2, 0)]);
+
+    let references = |idx: usize| {
+        let mut references: Vec<&str> = parsed.symbols[idx]
+            .references
+            .iter()
+            .map(String::as_str)
+            .collect();
+        references.sort();
+        references
+    };
+    assert_eq!(references(0), ["common.sh", "release"]);
+    assert_eq!(references(1), ["log_step"]);
+    assert_eq!(references(2), ["build", "publish.sh"]);
+    assert_eq!(
+        parsed.symbols[0].doc.as_deref(),
+        Some("Deploys the server.")
+    );
+    assert_eq!(
+        parsed.symbols[1].doc.as_deref(),
+        Some("Builds the release binary.")
+    );
+}
+
+#[test]
+fn no_op_builtins_are_not_dependencies() {
+    let script =
+        b"noop() { :; }\nwait_up() {\n  while true; do : \"$1\"; done\n  false || noop\n}\n";
+    let parsed = ShellIndexer.parse("noop.sh", script);
+    let references: Vec<Vec<&str>> = parsed
+        .symbols
+        .iter()
+        .map(|sym| sym.references.iter().map(String::as_str).collect())
+        .collect();
+    assert_eq!(references, [vec![], vec![], vec!["noop"]]);
+}
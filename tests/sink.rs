@@ -0,0 +1,123 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use contextmesh::config::OutputConfig;
+use contextmesh::sink::{self, Sink, DEFAULT_SINKS};
+use tempfile::TempDir;
+
+mod common;
+use common::project;
+
+/// Runs `contextmesh combine --sink osc52` in `dir` on a pseudo-terminal, as
+/// the OSC 52 sink only writes to a terminal, and returns what it printed.
+#[cfg(unix)]
+fn combine_on_a_terminal(dir: &TempDir) -> String {
+    let command = format!(
+        "'{}' combine --sink osc52",
+        env!("CARGO_BIN_EXE_contextmesh")
+    );
+    let output = Command::new("script")
+        .args(["-qec", &command, "/dev/null"])
+        .current_dir(dir.path())
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn the_osc52_sink_sends_the_bundle_base64_encoded() {
+    let dir = TempDir::new().unwrap();
+    let bundle = |content: &str| {
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), content).unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(["combine", "--sink", "stdout"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Bundles of each length modulo 3, so that each padding is used
+    for content in ["fn a() {}\n", "fn ab() {}\n", "fn abc() {}\n"] {
+        let mut base64 = Command::new("base64")
+            .arg("-w0")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let plain = bundle(content);
+        base64
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(plain.as_bytes())
+            .unwrap();
+        let encoded = String::from_utf8(base64.wait_with_output().unwrap().stdout).unwrap();
+
+        let printed = combine_on_a_terminal(&dir);
+        let sequence = format!("\x1b]52;c;{}\x07", encoded);
+        assert!(printed.contains(&sequence), "{:?}", printed);
+    }
+}
+
+#[test]
+fn sinks_come_from_the_config_or_the_defaults() {
+    let (sinks, file) = sink::chosen(&OutputConfig::default());
+    assert_eq!(sinks, DEFAULT_SINKS);
+    assert_eq!(file, None);
+
+    let (sinks, file) = sink::chosen(&OutputConfig {
+        sinks: Some(vec![Sink::File, Sink::Stdout]),
+        file: Some("bundle.md".to_string()),
+    });
+    assert_eq!(sinks, [Sink::File, Sink::Stdout]);
+    assert_eq!(file, Some(PathBuf::from("bundle.md")));
+}
+
+#[test]
+fn content_goes_to_the_first_sink_that_takes_it() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bundle.md");
+    // Outside of tmux, its sink is passed over
+    std::env::remove_var("TMUX");
+
+    let used = sink::send("content", &[Sink::Tmux, Sink::File], Some(&path)).unwrap();
+    assert_eq!(used, Sink::File);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "content");
+
+    let error = sink::send("content", &[Sink::Tmux, Sink::File], None).unwrap_err();
+    let error = error.to_string();
+    assert!(error.contains("not running inside tmux"), "{}", error);
+    assert!(error.contains("no output file set"), "{}", error);
+}
+
+#[test]
+fn the_stdout_sink_gets_the_bundle_alone() {
+    let dir = project("multi_module");
+    let contextmesh = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap()
+    };
+
+    // Without an index, files are collected from the directory
+    let output = contextmesh(&["combine", "--sink", "stdout"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("# ./src/"), "{}", stdout);
+
+    assert!(contextmesh(&["index"]).status.success());
+    let output = contextmesh(&["combine", "--sink", "stdout"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("# ./src/"), "{}", stdout);
+    assert!(!stdout.contains("Combined Content"), "{}", stdout);
+}
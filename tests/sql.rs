@@ -0,0 +1,28 @@
+use contextmesh::parser::sql::SqlIndexer;
+
+#[test]
+fn unterminated_quoted_identifiers_keep_their_text() {
+    for source in [
+        "SELECT x FROM [é",
+        "CREATE TABLE \"é",
+        "CREATE TABLE `é",
+        "CREATE TABLE [",
+    ] {
+        SqlIndexer.parse("schema.sql", source.as_bytes());
+    }
+
+    let parsed = SqlIndexer.parse("schema.sql", "CREATE TABLE [é".as_bytes());
+    let names: Vec<&str> = parsed.symbols.iter().map(|sym| sym.name.as_str()).collect();
+    assert_eq!(names, ["é"]);
+}
+
+#[test]
+fn quoted_identifiers_lose_their_delimiters() {
+    let parsed = SqlIndexer.parse(
+        "schema.sql",
+        b"CREATE TABLE [users] (id INT);\nCREATE TABLE \"orders\" (id INT);",
+    );
+    let names: Vec<&str> = parsed.symbols.iter().map(|sym| sym.name.as_str()).collect();
+    assert!(names.contains(&"users"), "{:?}", names);
+    assert!(names.contains(&"orders"), "{:?}", names);
+}
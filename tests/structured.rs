@@ -0,0 +1,90 @@
+use contextmesh::parser::structured::StructuredIndexer;
+use contextmesh::parser::ParsedFile;
+
+/// (name, kind, parent name, line) of each symbol, in source order.
+fn tree(parsed: &ParsedFile) -> Vec<(&str, &str, Option<&str>, usize)> {
+    parsed
+        .symbols
+        .iter()
+        .enumerate()
+        .map(|(idx, sym)| {
+            let parent = parsed
+                .parents
+                .iter()
+                .find(|(child, _)| *child == idx)
+                .map(|(_, parent)| parsed.symbols[*parent].name.as_str());
+            (sym.name.as_str(), &*sym.node_kind, parent, sym.line_number)
+        })
+        .collect()
+}
+
+#[test]
+fn toml_tables_hold_their_keys() {
+    let code = br#"[package]
+name = "demo" # the crate
+version = "0.1.0"
+
+[features]
+default = [
+    "std",
+]
+
+[[bin]]
+name = "demo"
+"#;
+    let parsed = StructuredIndexer.parse("Cargo.toml", code);
+    assert_eq!(
+        tree(&parsed),
+        [
+            ("package", "table", None, 1),
+            ("name", "key", Some("package"), 2),
+            ("version", "key", Some("package"), 3),
+            ("features", "table", None, 5),
+            ("default", "key", Some("features"), 6),
+            ("bin", "table", None, 10),
+            ("name", "key", Some("bin"), 11),
+        ]
+    );
+    // A value spanning lines is part of its key
+    let default = &parsed.symbols[4];
+    let source = std::str::from_utf8(&code[default.start_byte..default.end_byte]).unwrap();
+    assert_eq!(source.trim_end(), "default = [\n    \"std\",\n]");
+}
+
+#[test]
+fn yaml_keys_nest_by_indentation() {
+    let code = b"name: CI\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: cargo test\n";
+    let parsed = StructuredIndexer.parse(".github/workflows/ci.yml", code);
+    assert_eq!(
+        tree(&parsed),
+        [
+            ("name", "key", None, 1),
+            ("jobs", "table", None, 2),
+            ("test", "table", Some("jobs"), 3),
+            ("runs-on", "key", Some("test"), 4),
+            ("steps", "table", Some("test"), 5),
+            ("run", "key", Some("steps"), 6),
+        ]
+    );
+}
+
+#[test]
+fn json_objects_hold_their_members() {
+    let code = br#"{
+  "name": "web",
+  "scripts": { "build": "vite build", "test": "vitest" },
+  "files": ["dist"]
+}
+"#;
+    let parsed = StructuredIndexer.parse("package.json", code);
+    assert_eq!(
+        tree(&parsed),
+        [
+            ("name", "key", None, 2),
+            ("scripts", "table", None, 3),
+            ("build", "key", Some("scripts"), 3),
+            ("test", "key", Some("scripts"), 3),
+            ("files", "key", None, 4),
+        ]
+    );
+}
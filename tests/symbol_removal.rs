@@ -0,0 +1,136 @@
+use contextmesh::fixtures::{fixture_module, generate_rust_fixture};
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+const FILES: usize = 300;
+const FNS_PER_FILE: usize = 20;
+
+fn build_index(paths: &[PathBuf], code_parser: &mut CodeParser) -> Index {
+    let mut index = Index::new();
+    for path in paths {
+        index
+            .index_file(path.to_string_lossy().to_string(), code_parser)
+            .unwrap();
+    }
+    index.recheck_unresolved();
+    index
+}
+
+/// Every edge must point at a live symbol, and `used_by` must mirror `dependencies`.
+fn assert_graph_consistent(index: &Index) {
+    for (hash, sym) in &index.symbols {
+        for dep_id in &sym.dependencies {
+            let dep = index
+                .symbol(*dep_id)
+                .unwrap_or_else(|| panic!("'{}' depends on a removed symbol", sym.name));
+            assert!(
+                dep.used_by
+                    .iter()
+                    .any(|id| index.hash_of(*id) == Some(hash)),
+                "'{}' is missing the backlink from '{}'",
+                dep.name,
+                sym.name
+            );
+        }
+        for user_id in &sym.used_by {
+            let user = index
+                .symbol(*user_id)
+                .unwrap_or_else(|| panic!("'{}' is used by a removed symbol", sym.name));
+            assert!(
+                user.dependencies
+                    .iter()
+                    .any(|id| index.hash_of(*id) == Some(hash)),
+                "'{}' has a stale backlink from '{}'",
+                sym.name,
+                user.name
+            );
+        }
+    }
+}
+
+#[test]
+fn removing_a_file_detaches_its_symbols_from_a_large_graph() {
+    let dir = TempDir::new().unwrap();
+    let paths = generate_rust_fixture(dir.path(), FILES, FNS_PER_FILE).unwrap();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = build_index(&paths, &mut code_parser);
+    assert_graph_consistent(&index);
+
+    // Empty a file in the middle: its symbols go away, and the next module's
+    // functions lose their cross-file dependencies.
+    let emptied = FILES / 2;
+    let symbols_per_file = index.symbols.len() / FILES;
+    fs::write(&paths[emptied], "").unwrap();
+    index
+        .index_file(
+            paths[emptied].to_string_lossy().to_string(),
+            &mut code_parser,
+        )
+        .unwrap();
+
+    assert_eq!(index.symbols.len(), (FILES - 1) * symbols_per_file);
+    assert_graph_consistent(&index);
+    let next_module = paths[emptied + 1].to_string_lossy().to_string();
+    for sym in index.symbols.values() {
+        if *sym.file_path == *next_module {
+            assert!(sym
+                .dependencies
+                .iter()
+                .all(|id| *index.symbol(*id).unwrap().file_path == *next_module));
+        }
+    }
+}
+
+#[test]
+fn reindexing_a_changed_file_keeps_the_graph_consistent() {
+    let dir = TempDir::new().unwrap();
+    let paths = generate_rust_fixture(dir.path(), FILES, FNS_PER_FILE).unwrap();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = build_index(&paths, &mut code_parser);
+
+    for changed in [0, FILES / 3, FILES - 1] {
+        fs::write(&paths[changed], fixture_module(changed, FNS_PER_FILE + 1)).unwrap();
+        index
+            .index_file(
+                paths[changed].to_string_lossy().to_string(),
+                &mut code_parser,
+            )
+            .unwrap();
+        assert_graph_consistent(&index);
+    }
+}
+
+#[test]
+fn restoring_a_removed_file_relinks_its_users_on_recheck() {
+    let dir = TempDir::new().unwrap();
+    let paths = generate_rust_fixture(dir.path(), FILES, FNS_PER_FILE).unwrap();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = build_index(&paths, &mut code_parser);
+
+    let emptied = FILES / 2;
+    let emptied_path = paths[emptied].to_string_lossy().to_string();
+    fs::write(&paths[emptied], "").unwrap();
+    index
+        .index_file(emptied_path.clone(), &mut code_parser)
+        .unwrap();
+
+    fs::write(&paths[emptied], fixture_module(emptied, FNS_PER_FILE)).unwrap();
+    index
+        .index_file(emptied_path.clone(), &mut code_parser)
+        .unwrap();
+    assert_eq!(index.recheck_unresolved(), FNS_PER_FILE);
+    assert_graph_consistent(&index);
+
+    let next_module = paths[emptied + 1].to_string_lossy().to_string();
+    for sym in index.symbols.values() {
+        if *sym.file_path == *next_module && &*sym.node_kind == "function_item" {
+            assert!(sym
+                .dependencies
+                .iter()
+                .any(|id| *index.symbol(*id).unwrap().file_path == *emptied_path));
+        }
+    }
+}
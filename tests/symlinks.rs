@@ -0,0 +1,61 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::symlink;
+
+use contextmesh::utils::collect_files;
+use tempfile::TempDir;
+
+#[test]
+fn linked_files_are_collected_once_and_cycles_end() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("src/nested")).unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+    fs::write(root.join("src/nested/mod.rs"), "pub fn nested() {}\n").unwrap();
+    // A cycle, a link to a file sorting before it, a hard link, and a dangling
+    // link
+    symlink(root.join("src"), root.join("src/nested/back")).unwrap();
+    symlink(root.join("src/lib.rs"), root.join("src/alias.rs")).unwrap();
+    fs::hard_link(root.join("src/lib.rs"), root.join("src/copy.rs")).unwrap();
+    symlink(root.join("missing.rs"), root.join("src/dangling.rs")).unwrap();
+    fs::create_dir(root.join("outside")).unwrap();
+    fs::write(root.join("outside/extra.rs"), "pub fn extra() {}\n").unwrap();
+    symlink(root.join("outside"), root.join("src/linked")).unwrap();
+
+    let collect = |follow_symlinks| {
+        let src = root.join("src");
+        let mut files = collect_files(src.to_str().unwrap(), &["rs"], follow_symlinks);
+        for file in &mut files {
+            *file = file.rsplit("src/").next().unwrap().to_string();
+        }
+        files.sort();
+        files
+    };
+    // lib.rs by its own path, its hard link, nested/mod.rs, and linked/extra.rs
+    let followed = collect(true);
+    assert_eq!(
+        followed,
+        ["copy.rs", "lib.rs", "linked/extra.rs", "nested/mod.rs"]
+    );
+
+    // Nothing linked
+    let unfollowed = collect(false);
+    assert_eq!(unfollowed, ["copy.rs", "lib.rs", "nested/mod.rs"]);
+}
+
+#[test]
+fn a_file_reached_through_links_only_keeps_the_same_path_every_run() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir(root.join("outside")).unwrap();
+    fs::write(root.join("outside/extra.rs"), "pub fn extra() {}\n").unwrap();
+    for name in ["b", "a", "c"] {
+        symlink(root.join("outside"), root.join("src").join(name)).unwrap();
+    }
+
+    let src = root.join("src");
+    let files = collect_files(src.to_str().unwrap(), &["rs"], true);
+    assert_eq!(files, [src.join("a/extra.rs").to_string_lossy()]);
+}
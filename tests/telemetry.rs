@@ -0,0 +1,22 @@
+use contextmesh::telemetry::{self, CAN_EXPORT};
+
+#[test]
+fn the_endpoint_variable_is_ignored_without_the_otel_feature() {
+    let endpoint = telemetry::endpoint(None, Some("http://localhost:4318")).unwrap();
+    match CAN_EXPORT {
+        true => assert_eq!(endpoint.as_deref(), Some("http://localhost:4318")),
+        false => assert_eq!(endpoint, None),
+    }
+    assert_eq!(telemetry::endpoint(None, Some("")).unwrap(), None);
+    assert_eq!(telemetry::endpoint(None, None).unwrap(), None);
+}
+
+#[test]
+fn a_given_endpoint_needs_the_otel_feature() {
+    let endpoint =
+        telemetry::endpoint(Some("http://collector:4318"), Some("http://localhost:4318"));
+    match CAN_EXPORT {
+        true => assert_eq!(endpoint.unwrap().as_deref(), Some("http://collector:4318")),
+        false => assert!(endpoint.is_err()),
+    }
+}
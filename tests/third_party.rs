@@ -0,0 +1,97 @@
+use contextmesh::config::ThirdPartyConfig;
+use contextmesh::third_party::third_party_files;
+use std::collections::HashSet;
+use std::fs;
+use tempfile::TempDir;
+
+const OWN_HEADER: &str = "// Copyright 2024 Acme Inc. All rights reserved.\n\nfn main() {}\n";
+
+#[test]
+fn vendored_paths_and_foreign_headers_are_third_party() {
+    let dir = TempDir::new().unwrap();
+    let files = [
+        ("src/main.rs", OWN_HEADER),
+        ("src/lib.rs", OWN_HEADER),
+        ("src/plain.rs", "fn plain() {}\n"),
+        (
+            "src/sha.rs",
+            "/*\n * Copyright (c) 2015-2019 The Hashes Authors\n * SPDX-License-Identifier: MIT\n */\n",
+        ),
+        ("vendor/zlib/inflate.c", "int inflate(void);\n"),
+    ];
+    let mut paths = Vec::new();
+    for (path, source) in files {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+        paths.push(path.to_string_lossy().to_string());
+    }
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+
+    let found = third_party_files(&paths, &ThirdPartyConfig::default());
+    let expected: HashSet<String> = [paths[3], paths[4]].map(str::to_string).into();
+    assert_eq!(found, expected);
+
+    // A holder of the project's own, e.g. of an acquired codebase
+    let config = ThirdPartyConfig {
+        own_copyright: vec!["The Hashes Authors".to_string()],
+        ..ThirdPartyConfig::default()
+    };
+    let found = third_party_files(&paths, &config);
+    assert_eq!(found, [paths[4].to_string()].into());
+}
+
+fn write_files(dir: &TempDir, files: &[(&str, &str)]) -> Vec<String> {
+    files
+        .iter()
+        .map(|(path, source)| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, source).unwrap();
+            path.to_string_lossy().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn headers_are_not_foreign_for_being_in_the_minority() {
+    let dir = TempDir::new().unwrap();
+    let paths = write_files(
+        &dir,
+        &[
+            ("src/main.rs", OWN_HEADER),
+            ("src/a.rs", "fn a() {}\n"),
+            ("src/b.rs", "fn b() {}\n"),
+            ("src/c.rs", "fn c() {}\n"),
+            ("external/api/client.rs", "fn client() {}\n"),
+        ],
+    );
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    assert!(third_party_files(&paths, &ThirdPartyConfig::default()).is_empty());
+}
+
+#[test]
+fn tied_headers_are_only_foreign_to_the_configured_holders() {
+    let dir = TempDir::new().unwrap();
+    let paths = write_files(
+        &dir,
+        &[
+            ("src/main.rs", OWN_HEADER),
+            (
+                "src/sha.rs",
+                "// Copyright 2019 The Hashes Authors\nfn sha() {}\n",
+            ),
+        ],
+    );
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    assert!(third_party_files(&paths, &ThirdPartyConfig::default()).is_empty());
+
+    let config = ThirdPartyConfig {
+        own_copyright: vec!["Acme Inc".to_string()],
+        ..ThirdPartyConfig::default()
+    };
+    assert_eq!(
+        third_party_files(&paths, &config),
+        [paths[1].to_string()].into()
+    );
+}
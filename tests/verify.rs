@@ -0,0 +1,96 @@
+use std::fs;
+
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+use tempfile::TempDir;
+
+#[test]
+fn drifted_edges_are_reported_and_repaired() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(&path, "fn a() { b(); }\nfn b() { c(); }\nfn c() {}\n").unwrap();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = Index::new();
+    index
+        .index_file(path.to_string_lossy().to_string(), &mut code_parser)
+        .unwrap();
+    assert!(index.invariant_violations().is_empty());
+
+    // `b` forgets its user `a`, and `c` depends on itself
+    let hash_of = |index: &Index, name: &str| {
+        let (hash, _) = index
+            .symbols
+            .iter()
+            .find(|(_, sym)| sym.name == name)
+            .unwrap();
+        hash.clone()
+    };
+    let (b, c) = (hash_of(&index, "b"), hash_of(&index, "c"));
+    index.symbols.get_mut(&b).unwrap().used_by.clear();
+    let b_dependencies = index.symbols[&b].dependencies.clone();
+    let c_id = *b_dependencies.iter().next().unwrap();
+    index.symbols.get_mut(&c).unwrap().dependencies.insert(c_id);
+
+    let violations = index.invariant_violations();
+    assert_eq!(violations.len(), 2, "{:#?}", violations);
+    assert_eq!(index.repair_invariants(), 2);
+    assert!(index.invariant_violations().is_empty());
+    assert_eq!(index.symbols[&b].used_by.len(), 1);
+    assert!(index.symbols[&c].dependencies.is_empty());
+}
+
+#[test]
+fn missing_changed_and_duplicated_files_are_reported() {
+    let dir = TempDir::new().unwrap();
+    let mut code_parser = CodeParser::new_rust().unwrap();
+    let mut index = Index::new();
+    let mut paths = Vec::new();
+    for (name, source) in [
+        ("a.rs", "fn a() { b(); }\n"),
+        ("b.rs", "fn b() {}\n"),
+        ("c.rs", "fn c() {}\n"),
+    ] {
+        let path = dir.path().join(name).to_string_lossy().into_owned();
+        fs::write(&path, source).unwrap();
+        paths.push(path);
+    }
+    index.index_files(&paths, &mut code_parser).unwrap();
+    assert!(index.check_integrity().is_empty());
+
+    fs::remove_file(&paths[2]).unwrap();
+    fs::write(&paths[1], "fn b() { a(); }\n").unwrap();
+    // The same function indexed again as another kind of node
+    let mut copy = index
+        .symbols
+        .values()
+        .find(|sym| sym.name == "a")
+        .unwrap()
+        .clone();
+    copy.node_kind = "macro_invocation".into();
+    copy.dependencies.clear();
+    copy.used_by.clear();
+    index.symbols.insert(copy.hash(), copy);
+
+    let report = index.check_integrity();
+    assert_eq!(report.missing_files, [paths[2].clone()]);
+    assert_eq!(report.stale_files, [paths[1].clone()]);
+    assert_eq!(report.duplicates, [(paths[0].clone(), 1, "a".to_string())]);
+
+    let remaining = index.repair_integrity();
+    assert!(
+        remaining.invariants.is_empty(),
+        "{:#?}",
+        remaining.invariants
+    );
+    assert!(remaining.missing_files.is_empty());
+    assert!(remaining.duplicates.is_empty());
+    // Only parsing it again fixes a changed file
+    assert_eq!(remaining.stale_files, [paths[1].clone()]);
+    let kinds: Vec<&str> = index
+        .symbols
+        .values()
+        .filter(|sym| sym.name == "a")
+        .map(|sym| &*sym.node_kind)
+        .collect();
+    assert_eq!(kinds, ["function_item"]);
+}
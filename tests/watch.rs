@@ -0,0 +1,70 @@
+use std::fs;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use contextmesh::index::Index;
+use contextmesh::parser::CodeParser;
+
+mod common;
+use common::project;
+
+/// (name, line, start byte, end byte) of every parsed symbol.
+fn parsed(code_parser: &mut CodeParser, code: &str) -> Vec<(String, usize, usize, usize)> {
+    let mut symbols: Vec<_> = code_parser
+        .parse_source("src/lib.rs", code.as_bytes().to_vec())
+        .unwrap()
+        .symbols
+        .into_iter()
+        .map(|sym| (sym.name, sym.line_number, sym.start_byte, sym.end_byte))
+        .collect();
+    symbols.sort();
+    symbols
+}
+
+#[test]
+fn reparsing_an_edited_file_from_its_cached_tree_matches_a_fresh_parse() {
+    let before = "fn load() {\n    save();\n}\n\nfn save() {}\n";
+    let after = "fn load() {\n    check();\n    save();\n}\n\nfn check() {}\n\nfn save() {}\n";
+    let mut cached = CodeParser::new_rust().unwrap();
+    cached.enable_tree_cache();
+    parsed(&mut cached, before);
+
+    let mut fresh = CodeParser::new_rust().unwrap();
+    assert_eq!(parsed(&mut cached, after), parsed(&mut fresh, after));
+    assert_eq!(parsed(&mut cached, before), parsed(&mut fresh, before));
+}
+
+#[test]
+fn watching_updates_the_index_when_a_file_changes() {
+    let dir = project("multi_module");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_contextmesh"))
+        .args(["index", "--watch"])
+        .current_dir(dir.path())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let path = dir.path().join(".contextmesh/index.bin");
+    let has_symbol = |name: &str| {
+        Index::load_index_from(&path)
+            .is_ok_and(|index| index.symbols.values().any(|sym| sym.name == name))
+    };
+    let wait_for = |name: &str| {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while !has_symbol(name) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(100));
+        }
+        has_symbol(name)
+    };
+
+    let found = wait_for("Settings") && {
+        let config = dir.path().join("src/config.rs");
+        let code = fs::read_to_string(&config).unwrap();
+        fs::write(&config, format!("{}\npub fn watched() {{}}\n", code)).unwrap();
+        wait_for("watched")
+    };
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(found);
+}
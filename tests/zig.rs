@@ -0,0 +1,78 @@
+use contextmesh::config::LanguageConfig;
+use contextmesh::parser::zig_indexer::{doc_comment_line, module_path};
+use contextmesh::parser::CodeParser;
+use contextmesh::symbol::Visibility;
+
+#[test]
+fn doc_comments_are_triple_slash_lines_only() {
+    assert_eq!(doc_comment_line("/// A point."), Some("A point."));
+    assert_eq!(doc_comment_line("///   indented  "), Some("  indented"));
+    assert_eq!(doc_comment_line("///"), Some(""));
+    assert_eq!(doc_comment_line("//// banner"), None);
+    assert_eq!(doc_comment_line("// note"), None);
+}
+
+#[test]
+fn imported_files_are_module_paths() {
+    assert_eq!(module_path("std"), "std");
+    assert_eq!(module_path("math.zig"), "math");
+    assert_eq!(module_path("net/http.zig"), "net::http");
+}
+
+const SOURCE: &str = r#"const std = @import("std");
+const math = @import("util/math.zig");
+
+/// A point on the plane.
+pub const Point = struct {
+    x: i32,
+    y: i32,
+};
+
+const limit = 10;
+
+pub fn add(a: Point, b: Point) Point {
+    const sum = math.add(a.x, b.x);
+    return Point{ .x = sum, .y = a.y + b.y };
+}
+"#;
+
+/// Parses a Zig file with the compiled tree-sitter-zig library named by
+/// `CONTEXTMESH_ZIG_GRAMMAR`; run it with `cargo test -- --ignored`.
+#[test]
+#[ignore = "needs a tree-sitter-zig library in CONTEXTMESH_ZIG_GRAMMAR"]
+fn zig_declarations_become_symbols() {
+    let grammar = std::env::var("CONTEXTMESH_ZIG_GRAMMAR")
+        .expect("CONTEXTMESH_ZIG_GRAMMAR names a tree-sitter-zig library");
+    let config = LanguageConfig {
+        grammar: Some(grammar),
+        ..LanguageConfig::default()
+    };
+    let mut parser = CodeParser::new_zig(&config).unwrap().unwrap();
+    let parsed = parser
+        .parse_source("src/point.zig", SOURCE.as_bytes().to_vec())
+        .unwrap();
+
+    let mut names: Vec<&str> = parsed.symbols.iter().map(|sym| sym.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["Point", "add", "limit"]);
+
+    let point = parsed
+        .symbols
+        .iter()
+        .find(|sym| sym.name == "Point")
+        .unwrap();
+    assert_eq!(&*point.node_kind, "struct_declaration");
+    assert_eq!(point.visibility, Visibility::Public);
+    assert_eq!(point.signature, "pub const Point = struct");
+    assert_eq!(point.doc.as_deref(), Some("A point on the plane."));
+
+    let limit = parsed
+        .symbols
+        .iter()
+        .find(|sym| sym.name == "limit")
+        .unwrap();
+    assert_eq!(limit.visibility, Visibility::Private);
+
+    assert_eq!(parsed.imports.aliases["std"], "std");
+    assert_eq!(parsed.imports.aliases["math"], "util::math");
+}